@@ -9,8 +9,12 @@ use std::{
 
 use itertools::Itertools;
 use tardis::{
-    basic::{dto::TardisContext, result::TardisResult},
-    web::poem::{http::header::FORWARDED, Request},
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    web::poem::{
+        self,
+        http::{header::FORWARDED, StatusCode},
+        Request,
+    },
 };
 
 pub const REMOTE_ADDR: &str = "remote-addr";
@@ -129,3 +133,24 @@ pub fn sort_hashmap_query(query: HashMap<String, String>) -> String {
     }
     query.iter().map(|a| format!("{}={}", a.0, a.1)).sorted_by(|a, b| Ord::cmp(&a.to_lowercase(), &b.to_lowercase())).join("&")
 }
+
+/// Convert a tardis error to a poem error, the status is taken from the leading number of the error code
+///
+/// 将tardis错误转换为poem错误，状态码取自错误码开头的数字
+pub fn tardis_err_to_poem_err(e: TardisError) -> poem::Error {
+    let status = e.code.split('-').next().and_then(|code| StatusCode::from_str(code).ok()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    poem::Error::from_string(e.message, status)
+}
+
+/// Percent-encode a query value, only the unreserved characters of RFC 3986 are kept
+///
+/// 对查询参数值进行百分号编码，仅保留RFC 3986中的非保留字符
+pub fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
@@ -1,2 +1,3 @@
 pub mod event_connect_api;
+pub mod event_dead_letter_api;
 pub mod event_register_api;
@@ -0,0 +1,63 @@
+use tardis::web::context_extractor::TardisContextExtractor;
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::Query;
+use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
+
+use crate::dto::event_dto::{EventDeadLetterDetailResp, EventDeadLetterInfoResp};
+use crate::event_constants::get_tardis_inst;
+use crate::serv::event_dead_letter_serv::EventDeadLetterServ;
+#[derive(Clone)]
+pub struct EventDeadLetterApi;
+
+/// Event Dead Letter API
+///
+/// 事件死信API
+#[poem_openapi::OpenApi(prefix_path = "/ca/dead_letter")]
+impl EventDeadLetterApi {
+    /// Find Dead Letters of a Topic
+    ///
+    /// 查询主题的死信
+    #[oai(path = "/", method = "get")]
+    async fn paginate(
+        &self,
+        topic_code: Query<String>,
+        reason: Query<Option<String>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<EventDeadLetterInfoResp>> {
+        let funs = get_tardis_inst();
+        let result = EventDeadLetterServ.paginate(&topic_code.0, reason.0.as_deref(), page_number.0, page_size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Get Dead Letter Detail
+    ///
+    /// 获取死信详情
+    #[oai(path = "/detail", method = "get")]
+    async fn get(&self, topic_code: Query<String>, message_id: Query<String>, ctx: TardisContextExtractor) -> TardisApiResult<EventDeadLetterDetailResp> {
+        let funs = get_tardis_inst();
+        let result = EventDeadLetterServ.get(&topic_code.0, &message_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Replay Dead Letter to its Original Topic
+    ///
+    /// 重新投递死信到原主题
+    #[oai(path = "/replay", method = "put")]
+    async fn replay(&self, topic_code: Query<String>, message_id: Query<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = get_tardis_inst();
+        EventDeadLetterServ.replay(&topic_code.0, &message_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Purge Dead Letters, all of the topic if message_id is not specified
+    ///
+    /// 清除死信，未指定message_id时清除主题下所有死信
+    #[oai(path = "/", method = "delete")]
+    async fn purge(&self, topic_code: Query<String>, message_id: Query<Option<String>>, ctx: TardisContextExtractor) -> TardisApiResult<u32> {
+        let funs = get_tardis_inst();
+        let count = EventDeadLetterServ.purge(&topic_code.0, message_id.0.as_deref(), &funs, &ctx.0).await?;
+        TardisResp::ok(count)
+    }
+}
@@ -1,8 +1,10 @@
+use tardis::chrono::{DateTime, Utc};
 use tardis::web::context_extractor::TardisContextExtractor;
 use tardis::web::poem_openapi;
 use tardis::web::poem_openapi::param::Query;
-use tardis::web::web_resp::{TardisApiResult, TardisResp};
+use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp};
 
+use crate::dto::event_dto::{EventMessageDetailResp, EventMessageInfoResp, EventMessagePendingCountResp};
 use crate::event_constants::get_tardis_inst;
 use crate::serv::event_message_serv::EventMessageServ;
 #[derive(Clone)]
@@ -13,13 +15,55 @@ pub struct EventMessageApi;
 /// 事件主题API
 #[poem_openapi::OpenApi(prefix_path = "/ci/message")]
 impl EventMessageApi {
-    /// Add Event Definition
+    /// Clear archived messages and expired dead letters
     ///
-    /// 添加事件主题
+    /// 清理已归档消息和过期死信
     #[oai(path = "/clear_archived", method = "delete")]
     async fn clear_archived(&self, topic_code: Query<Option<String>>, _ctx: TardisContextExtractor) -> TardisApiResult<u32> {
         let funs = get_tardis_inst();
         let count = EventMessageServ.clear_archived(topic_code.0.as_deref(), &funs).await?;
         TardisResp::ok(count)
     }
+
+    /// Find messages of topic
+    ///
+    /// 查找主题的消息
+    #[oai(path = "/paged", method = "get")]
+    #[allow(clippy::too_many_arguments)]
+    async fn paginate(
+        &self,
+        topic_code: Query<String>,
+        // pending, expired or archived
+        status: Query<Option<String>>,
+        archived: Query<Option<bool>>,
+        time_start: Query<Option<DateTime<Utc>>>,
+        time_end: Query<Option<DateTime<Utc>>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        _ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<EventMessageInfoResp>> {
+        let funs = get_tardis_inst();
+        let result = EventMessageServ.paginate(&topic_code.0, status.0.as_deref(), archived.0, time_start.0, time_end.0, page_number.0, page_size.0, &funs).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Get message with payload and delivery status
+    ///
+    /// 获取消息内容及投递状态
+    #[oai(path = "/detail", method = "get")]
+    async fn get(&self, topic_code: Query<String>, message_id: Query<String>, ctx: TardisContextExtractor) -> TardisApiResult<EventMessageDetailResp> {
+        let funs = get_tardis_inst();
+        let result = EventMessageServ.get(&topic_code.0, &message_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Count pending messages per topic
+    ///
+    /// 统计各主题待投递消息数
+    #[oai(path = "/pending_count", method = "get")]
+    async fn count_pending(&self, topic_code: Query<Option<String>>, _ctx: TardisContextExtractor) -> TardisApiResult<Vec<EventMessagePendingCountResp>> {
+        let funs = get_tardis_inst();
+        let result = EventMessageServ.count_pending(topic_code.0.as_deref(), &funs).await?;
+        TardisResp::ok(result)
+    }
 }
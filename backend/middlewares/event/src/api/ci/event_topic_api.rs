@@ -7,9 +7,14 @@ use tardis::web::poem_openapi::param::{Path, Query};
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
-use crate::dto::event_dto::{EventTopicConfig, EventTopicFilterReq, EventTopicInfoResp, SetTopicAuth};
+use crate::dto::event_dto::{
+    EventSchemaAddReq, EventSchemaCompatibilityResp, EventSchemaInfoResp, EventTopicConfig, EventTopicFilterReq, EventTopicInfoResp, EventWebhookAddReq, EventWebhookInfoResp,
+    EventWebhookModifyReq, SetTopicAuth,
+};
 use crate::event_constants::get_tardis_inst;
+use crate::serv::event_schema_serv::EventSchemaServ;
 use crate::serv::event_topic_serv::EventTopicServ;
+use crate::serv::event_webhook_serv::EventWebhookServ;
 #[derive(Clone)]
 pub struct EventTopicApi;
 
@@ -132,4 +137,112 @@ impl EventTopicApi {
         EventTopicServ::unregister_user(TopicCode::new(topic_code.0), &ctx.0.ak, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
+
+    /// Add webhook subscription to topic
+    ///
+    /// 添加主题的Webhook订阅
+    #[oai(path = "/:topic_code/webhook", method = "post")]
+    async fn add_webhook(&self, topic_code: Path<String>, add_req: Json<EventWebhookAddReq>, ctx: TardisContextExtractor) -> TardisApiResult<String> {
+        let funs = get_tardis_inst();
+        let id = EventWebhookServ.add(&topic_code.0, add_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(id)
+    }
+
+    /// Modify webhook subscription
+    ///
+    /// 修改Webhook订阅
+    #[oai(path = "/:topic_code/webhook/:id", method = "put")]
+    async fn modify_webhook(&self, topic_code: Path<String>, id: Path<String>, modify_req: Json<EventWebhookModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = get_tardis_inst();
+        EventWebhookServ.modify(&topic_code.0, &id.0, modify_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Delete webhook subscription
+    ///
+    /// 删除Webhook订阅
+    #[oai(path = "/:topic_code/webhook/:id", method = "delete")]
+    async fn delete_webhook(&self, topic_code: Path<String>, id: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = get_tardis_inst();
+        EventWebhookServ.delete(&topic_code.0, &id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Get webhook subscription with its delivery status
+    ///
+    /// 获取Webhook订阅及投递状态
+    #[oai(path = "/:topic_code/webhook/:id", method = "get")]
+    async fn get_webhook(&self, topic_code: Path<String>, id: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<EventWebhookInfoResp> {
+        let funs = get_tardis_inst();
+        let result = EventWebhookServ.get(&topic_code.0, &id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Find webhook subscriptions of topic with their delivery status
+    ///
+    /// 查找主题的Webhook订阅及投递状态
+    #[oai(path = "/:topic_code/webhook", method = "get")]
+    async fn find_webhooks(&self, topic_code: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<EventWebhookInfoResp>> {
+        let funs = get_tardis_inst();
+        let result = EventWebhookServ.find(&topic_code.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Register a new schema version of event code in topic
+    ///
+    /// 注册主题下事件的新版本Schema
+    #[oai(path = "/:topic_code/schema/:event_code", method = "post")]
+    async fn add_schema(
+        &self,
+        topic_code: Path<String>,
+        event_code: Path<String>,
+        add_req: Json<EventSchemaAddReq>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<EventSchemaInfoResp> {
+        let funs = get_tardis_inst();
+        let result = EventSchemaServ.add(&topic_code.0, &event_code.0, add_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Check schema compatibility with the latest version
+    ///
+    /// 检查Schema与最新版本的兼容性
+    #[oai(path = "/:topic_code/schema/:event_code/compatibility", method = "put")]
+    async fn check_schema_compatibility(
+        &self,
+        topic_code: Path<String>,
+        event_code: Path<String>,
+        add_req: Json<EventSchemaAddReq>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<EventSchemaCompatibilityResp> {
+        let funs = get_tardis_inst();
+        let result = EventSchemaServ.check_compatibility(&topic_code.0, &event_code.0, &add_req.0.schema, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Get schema of event code, the latest version if not specified
+    ///
+    /// 获取事件Schema，未指定版本时返回最新版本
+    #[oai(path = "/:topic_code/schema/:event_code", method = "get")]
+    async fn get_schema(
+        &self,
+        topic_code: Path<String>,
+        event_code: Path<String>,
+        version: Query<Option<i32>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<EventSchemaInfoResp> {
+        let funs = get_tardis_inst();
+        let result = EventSchemaServ.get(&topic_code.0, &event_code.0, version.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Find schemas of topic, all versions if event code is specified, otherwise the latest ones
+    ///
+    /// 查找主题的Schema，指定事件时返回所有版本，否则返回各事件最新版本
+    #[oai(path = "/:topic_code/schema", method = "get")]
+    async fn find_schemas(&self, topic_code: Path<String>, event_code: Query<Option<String>>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<EventSchemaInfoResp>> {
+        let funs = get_tardis_inst();
+        let result = EventSchemaServ.find(&topic_code.0, event_code.0.as_deref(), &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
 }
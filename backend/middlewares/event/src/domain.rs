@@ -1,3 +1,6 @@
 pub mod event_auth;
+pub mod event_dead_letter;
 pub mod event_message;
+pub mod event_schema;
 pub mod event_topic;
+pub mod event_webhook;
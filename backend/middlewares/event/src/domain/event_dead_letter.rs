@@ -0,0 +1,79 @@
+use asteroid_mq::prelude::MessageId;
+use tardis::chrono::{DateTime, Utc};
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::prelude::*;
+
+use tardis::{TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation};
+
+use super::event_message;
+
+/// The message failed to be delivered more than `max_delivery_attempts` times
+pub const REASON_MAX_DELIVERY_ATTEMPTS: &str = "max_delivery_attempts";
+/// The message expired before it was acknowledged
+pub const REASON_EXPIRED: &str = "expired";
+/// The payload didn't match the topic's schema and the schema quarantines invalid payloads
+pub const REASON_INVALID_PAYLOAD: &str = "invalid_payload";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation)]
+#[sea_orm(table_name = "mq_dead_letter")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub message_id: String,
+    #[index]
+    pub topic: String,
+    #[index]
+    pub reason: String,
+    pub delivery_failures: i32,
+    pub ack_kind: i16,
+    pub target_kind: i16,
+    #[sea_orm(column_type = "DateTime")]
+    pub expire_time: Option<DateTime<Utc>>,
+    pub max_receiver: Option<i32>,
+    pub subjects: Vec<String>,
+    pub payload: Vec<u8>,
+    pub status: Vec<u8>,
+    #[sea_orm(column_type = "DateTime")]
+    pub time: DateTime<Utc>,
+    #[index]
+    #[sea_orm(column_type = "DateTime")]
+    pub dead_time: DateTime<Utc>,
+}
+
+impl Model {
+    pub fn from_message(message: event_message::Model, reason: &str) -> Self {
+        Model {
+            message_id: message.message_id,
+            topic: message.topic,
+            reason: reason.to_string(),
+            delivery_failures: message.delivery_failures,
+            ack_kind: message.ack_kind,
+            target_kind: message.target_kind,
+            expire_time: message.expire_time,
+            max_receiver: message.max_receiver,
+            subjects: message.subjects,
+            payload: message.payload,
+            status: message.status,
+            time: message.time,
+            dead_time: Utc::now(),
+        }
+    }
+
+    /// Rebuild the message to be sent again under a new message id, the delivery status is reset and the original time to live is kept
+    pub fn into_replay_message(self) -> event_message::Model {
+        let now = Utc::now();
+        event_message::Model {
+            message_id: MessageId::new_snowflake().to_base64(),
+            topic: self.topic,
+            archived: false,
+            delivery_failures: 0,
+            ack_kind: self.ack_kind,
+            target_kind: self.target_kind,
+            expire_time: self.expire_time.map(|expire| now + (expire - self.time).max(tardis::chrono::Duration::zero())),
+            max_receiver: self.max_receiver,
+            subjects: self.subjects,
+            payload: self.payload,
+            status: vec![],
+            time: now,
+        }
+    }
+}
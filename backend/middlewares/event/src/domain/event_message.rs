@@ -18,6 +18,7 @@ pub struct Model {
     pub topic: String,
     #[index]
     pub archived: bool,
+    pub delivery_failures: i32,
     pub ack_kind: i16,
     pub target_kind: i16,
     #[sea_orm(column_type = "DateTime")]
@@ -30,11 +31,35 @@ pub struct Model {
     pub time: DateTime<Utc>,
 }
 
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_EXPIRED: &str = "expired";
+pub const STATUS_ARCHIVED: &str = "archived";
+
 const EP_ADDR_SIZE: usize = size_of::<EndpointAddr>();
 const STATUS_SIZE: usize = 1;
 const ENTRY_SIZE: usize = EP_ADDR_SIZE + STATUS_SIZE;
 impl Model {
-    pub fn status_update(&mut self, mut status: HashMap<EndpointAddr, MessageStatusKind>) {
+    /// `archived`, or `expired` when it outlived its expire time without being archived, otherwise `pending`
+    pub fn status_label(&self, now: DateTime<Utc>) -> &'static str {
+        if self.archived {
+            STATUS_ARCHIVED
+        } else if self.expire_time.is_some_and(|expire| expire < now) {
+            STATUS_EXPIRED
+        } else {
+            STATUS_PENDING
+        }
+    }
+    pub fn ack_kind_label(&self) -> String {
+        MessageAckExpectKind::try_from_u8(self.ack_kind as u8).map_or_else(|| self.ack_kind.to_string(), |kind| format!("{kind:?}"))
+    }
+    pub fn target_kind_label(&self) -> String {
+        format!("{:?}", MessageTargetKind::from(self.target_kind as u8))
+    }
+    /// Apply the status update, returns the failed delivery attempts in it,
+    /// that is the endpoints turning failed or unreachable, an endpoint already failed reported again is not counted
+    pub fn status_update(&mut self, mut status: HashMap<EndpointAddr, MessageStatusKind>) -> i32 {
+        let is_failed = |kind: u8| matches!(MessageStatusKind::try_from_u8(kind), Some(MessageStatusKind::Failed | MessageStatusKind::Unreachable));
+        let mut failed_attempts = 0;
         for entry in self.status.chunks_mut(ENTRY_SIZE) {
             if entry.len() != ENTRY_SIZE {
                 break;
@@ -45,13 +70,22 @@ impl Model {
 
             let addr = EndpointAddr::from(addr_bytes);
             if let Some(update_kind) = status.remove(&addr) {
-                kind[0] = update_kind as u8;
+                let update_kind = update_kind as u8;
+                if is_failed(update_kind) && !is_failed(kind[0]) {
+                    failed_attempts += 1;
+                }
+                kind[0] = update_kind;
             }
         }
         for (addr, kind) in status {
+            let kind = kind as u8;
+            if is_failed(kind) {
+                failed_attempts += 1;
+            }
             self.status.extend(addr.bytes);
-            self.status.push(kind as u8);
+            self.status.push(kind);
         }
+        failed_attempts
     }
     pub fn status_to_binary(status: HashMap<EndpointAddr, MessageStatusKind>) -> Vec<u8> {
         let mut status_vec = Vec::new();
@@ -91,6 +125,7 @@ impl Model {
             status: Self::status_to_binary(durable_message.status),
             time: durable_message.time,
             archived: false,
+            delivery_failures: 0,
         }
     }
     pub fn try_into_durable_message(self) -> TardisResult<DurableMessage> {
@@ -0,0 +1,46 @@
+use tardis::chrono::{DateTime, Utc};
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::prelude::*;
+
+use tardis::{TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation};
+
+use crate::dto::event_dto::EventSchemaInfoResp;
+
+/// Reject the message when its payload doesn't match the schema
+pub const ON_INVALID_REJECT: &str = "reject";
+/// Reject the message and keep it in the dead-letter store for inspection or replay
+pub const ON_INVALID_QUARANTINE: &str = "quarantine";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation)]
+#[sea_orm(table_name = "mq_schema")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    #[index]
+    pub topic: String,
+    #[index]
+    pub event_code: String,
+    pub version: i32,
+    pub schema: Json,
+    pub on_invalid: String,
+    pub ak: String,
+    #[sea_orm(column_type = "DateTime")]
+    pub create_time: DateTime<Utc>,
+}
+
+impl Model {
+    pub fn format_id(topic: &str, event_code: &str, version: i32) -> String {
+        format!("{topic}/{event_code}/{version}")
+    }
+
+    pub fn into_info_resp(self) -> EventSchemaInfoResp {
+        EventSchemaInfoResp {
+            topic: self.topic,
+            event_code: self.event_code,
+            version: self.version,
+            schema: self.schema,
+            on_invalid: self.on_invalid,
+            create_time: self.create_time,
+        }
+    }
+}
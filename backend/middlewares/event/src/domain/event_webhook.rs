@@ -0,0 +1,50 @@
+use tardis::chrono::{DateTime, Utc};
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::prelude::*;
+
+use tardis::{TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation};
+
+use crate::dto::event_dto::EventWebhookInfoResp;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation)]
+#[sea_orm(table_name = "mq_webhook")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    #[index]
+    pub topic: String,
+    pub url: String,
+    pub secret: String,
+    pub subjects: Vec<String>,
+    pub enabled: bool,
+    pub ak: String,
+    pub delivered_count: i64,
+    pub failed_count: i64,
+    pub consecutive_failures: i32,
+    #[sea_orm(column_type = "DateTime")]
+    pub last_delivery_time: Option<DateTime<Utc>>,
+    pub last_failed_message_id: Option<String>,
+    pub last_error: Option<String>,
+    #[sea_orm(column_type = "DateTime")]
+    pub create_time: DateTime<Utc>,
+}
+
+impl Model {
+    pub fn into_info_resp(self) -> EventWebhookInfoResp {
+        EventWebhookInfoResp {
+            id: self.id,
+            topic: self.topic,
+            url: self.url,
+            subjects: self.subjects,
+            enabled: self.enabled,
+            ak: self.ak,
+            delivered_count: self.delivered_count,
+            failed_count: self.failed_count,
+            consecutive_failures: self.consecutive_failures,
+            last_delivery_time: self.last_delivery_time,
+            last_failed_message_id: self.last_failed_message_id,
+            last_error: self.last_error,
+            create_time: self.create_time,
+        }
+    }
+}
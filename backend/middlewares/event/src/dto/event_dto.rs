@@ -157,3 +157,159 @@ pub struct SetTopicAuth {
     pub read: bool,
     pub write: bool,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventDeadLetterInfoResp {
+    pub message_id: String,
+    pub topic: String,
+    /// `max_delivery_attempts` or `expired`
+    pub reason: String,
+    pub delivery_failures: i32,
+    pub subjects: Vec<String>,
+    pub time: DateTime<Utc>,
+    pub dead_time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventDeadLetterDetailResp {
+    pub message_id: String,
+    pub topic: String,
+    /// `max_delivery_attempts` or `expired`
+    pub reason: String,
+    pub delivery_failures: i32,
+    pub subjects: Vec<String>,
+    pub expire_time: Option<DateTime<Utc>>,
+    pub max_receiver: Option<i32>,
+    /// Base64 encoded payload
+    pub payload: String,
+    /// Payload as text, if it is valid utf-8
+    pub payload_text: Option<String>,
+    pub time: DateTime<Utc>,
+    pub dead_time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventWebhookAddReq {
+    #[oai(validator(min_length = "1", max_length = "2000"))]
+    pub url: String,
+    /// Secret to sign the deliveries with HMAC-SHA256
+    #[oai(validator(min_length = "16", max_length = "255"))]
+    pub secret: String,
+    /// Subject interests of the subscription, e.g. an event code
+    #[oai(validator(min_items = "1"))]
+    pub subjects: Vec<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventWebhookModifyReq {
+    #[oai(validator(min_length = "1", max_length = "2000"))]
+    pub url: Option<String>,
+    #[oai(validator(min_length = "16", max_length = "255"))]
+    pub secret: Option<String>,
+    #[oai(validator(min_items = "1"))]
+    pub subjects: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventWebhookInfoResp {
+    pub id: String,
+    pub topic: String,
+    pub url: String,
+    pub subjects: Vec<String>,
+    pub enabled: bool,
+    pub ak: String,
+    pub delivered_count: i64,
+    pub failed_count: i64,
+    pub consecutive_failures: i32,
+    pub last_delivery_time: Option<DateTime<Utc>>,
+    pub last_failed_message_id: Option<String>,
+    pub last_error: Option<String>,
+    pub create_time: DateTime<Utc>,
+}
+
+/// Request body posted to the webhook url
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EventWebhookDelivery {
+    pub topic: String,
+    pub message_id: String,
+    pub subjects: Vec<String>,
+    /// Message payload, base64 encoded if it is not valid utf-8
+    pub payload: String,
+    pub payload_base64: bool,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventMessageInfoResp {
+    pub message_id: String,
+    pub topic: String,
+    /// `pending`, `expired` or `archived`
+    pub status: String,
+    pub archived: bool,
+    pub ack_kind: String,
+    pub target_kind: String,
+    pub subjects: Vec<String>,
+    pub delivery_failures: i32,
+    pub expire_time: Option<DateTime<Utc>>,
+    pub time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventMessageDetailResp {
+    pub message_id: String,
+    pub topic: String,
+    /// `pending`, `expired` or `archived`
+    pub status: String,
+    pub archived: bool,
+    pub ack_kind: String,
+    pub target_kind: String,
+    pub subjects: Vec<String>,
+    pub delivery_failures: i32,
+    pub expire_time: Option<DateTime<Utc>>,
+    pub max_receiver: Option<i32>,
+    /// Message payload, base64 encoded if it is not valid utf-8, absent if the caller has no read permission to the topic
+    pub payload: Option<String>,
+    pub payload_base64: bool,
+    pub payload_redacted: bool,
+    pub delivery_status: Vec<EventMessageDeliveryStatusResp>,
+    pub time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventMessageDeliveryStatusResp {
+    /// Hex encoded endpoint address
+    pub endpoint: String,
+    pub status: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, FromQueryResult)]
+pub struct EventMessagePendingCountResp {
+    pub topic: String,
+    pub count: i64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventSchemaAddReq {
+    /// JSON Schema of the payload
+    pub schema: tardis::serde_json::Value,
+    /// `reject` (default) or `quarantine` invalid payloads
+    pub on_invalid: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventSchemaInfoResp {
+    pub topic: String,
+    pub event_code: String,
+    pub version: i32,
+    pub schema: tardis::serde_json::Value,
+    pub on_invalid: String,
+    pub create_time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct EventSchemaCompatibilityResp {
+    pub compatible: bool,
+    /// Changes that would reject payloads accepted by the latest version
+    pub incompatibilities: Vec<String>,
+}
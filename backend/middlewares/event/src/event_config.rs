@@ -21,6 +21,23 @@ pub struct EventConfig {
     pub avatars: Vec<String>,
     pub cluster: Option<String>,
     pub invoke: InvokeConfig,
+    // failed deliveries before a durable message is moved to the dead-letter store, 0 to disable
+    pub max_delivery_attempts: u32,
+    // interval of moving expired unacknowledged messages to the dead-letter store, 0 to disable
+    pub dead_letter_scan_interval_sec: u64,
+    // dead letters older than this are purged by `clear_archived`
+    pub dead_letter_retention_sec: u64,
+    // attempts to post a message to a webhook before it is acknowledged as failed
+    pub webhook_max_attempts: u32,
+    // backoff before the second attempt, doubled for each further attempt
+    pub webhook_backoff_base_ms: u64,
+    pub webhook_backoff_max_ms: u64,
+    // timeout of each post to a webhook
+    pub webhook_timeout_sec: u64,
+    // hosts allowed as webhook targets even though they resolve to loopback, link-local or private addresses
+    pub webhook_allowed_hosts: Vec<String>,
+    // interval of applying the webhook subscriptions on the raft leader, the only node delivering them
+    pub webhook_reconcile_interval_sec: u64,
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
@@ -61,6 +78,15 @@ impl Default for EventConfig {
             cluster: Some(Self::CLUSTER_K8S.to_string()),
             raft: None,
             invoke: Default::default(),
+            max_delivery_attempts: 5,
+            dead_letter_scan_interval_sec: 60,
+            dead_letter_retention_sec: 7 * 24 * 60 * 60,
+            webhook_max_attempts: 5,
+            webhook_backoff_base_ms: 1000,
+            webhook_backoff_max_ms: 60000,
+            webhook_timeout_sec: 30,
+            webhook_allowed_hosts: Vec::new(),
+            webhook_reconcile_interval_sec: 10,
         }
     }
 }
@@ -25,14 +25,14 @@ use tardis::{
 
 use crate::{
     api::{
-        ca::{event_connect_api, event_register_api},
+        ca::{event_connect_api, event_dead_letter_api, event_register_api},
         ci::{event_message_api, event_topic_api},
     },
-    domain::{event_auth, event_message, event_topic},
+    domain::{event_auth, event_dead_letter, event_message, event_schema, event_topic, event_webhook},
     event_config::{EventConfig, EventInfo, EventInfoManager},
     event_constants::{DOMAIN_CODE, KIND_CODE},
     mq_adapter::{BiosDurableAdapter, BiosEdgeAuthAdapter},
-    serv::event_register_serv,
+    serv::{event_dead_letter_serv::EventDeadLetterServ, event_register_serv, event_schema_serv::EventSchemaServ, event_webhook_serv::EventWebhookServ},
 };
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
@@ -62,9 +62,20 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
 async fn init_db(domain_code: String, kind_code: String, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
     bios_basic::rbum::rbum_initializer::init(funs.module_code(), funs.conf::<EventConfig>().rbum.clone()).await?;
     invoke_initializer::init(funs.module_code(), funs.conf::<EventConfig>().invoke.clone())?;
+    // Tables introduced after the first release, created or upgraded on every startup
+    funs.db()
+        .init(event_dead_letter::ActiveModel::init(
+            TardisFuns::reldb().backend(),
+            None,
+            TardisFuns::reldb().compatible_type(),
+        ))
+        .await?;
+    funs.db().init(event_webhook::ActiveModel::init(TardisFuns::reldb().backend(), None, TardisFuns::reldb().compatible_type())).await?;
+    funs.db().init(event_schema::ActiveModel::init(TardisFuns::reldb().backend(), None, TardisFuns::reldb().compatible_type())).await?;
     if let Some(domain_id) = RbumDomainServ::get_rbum_domain_id_by_code(&domain_code, funs).await? {
         let kind_id = RbumKindServ::get_rbum_kind_id_by_code(&kind_code, funs).await?.expect("missing event kind");
         EventInfoManager::set(EventInfo { kind_id, domain_id })?;
+        funs.db().execute_one("ALTER TABLE mq_message ADD COLUMN IF NOT EXISTS delivery_failures INTEGER NOT NULL DEFAULT 0", vec![]).await?;
         return Ok(());
     }
     // Initialize event component RBUM item table and indexs
@@ -126,6 +137,7 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
                     register_serv: register_serv.clone(),
                 },
                 event_register_api::EventRegisterApi { register_serv },
+                event_dead_letter_api::EventDeadLetterApi,
             ),
         )
         .await;
@@ -147,9 +159,46 @@ async fn init_mq_cluster(config: &EventConfig, funs: TardisFunsInst, ctx: Tardis
             max_payload_size: 1024 * 1024,
         })
         .await;
+    EventSchemaServ::listen_changes();
+    if config.webhook_reconcile_interval_sec > 0 {
+        init_webhook_reconcile(Duration::from_secs(config.webhook_reconcile_interval_sec), funs.clone());
+    }
+    if config.durable && config.dead_letter_scan_interval_sec > 0 {
+        init_dead_letter_scan(Duration::from_secs(config.dead_letter_scan_interval_sec), funs);
+    }
     Ok(())
 }
 
+fn init_dead_letter_scan(interval: Duration, funs: Arc<TardisFunsInst>) {
+    tardis::tokio::spawn(async move {
+        let mut interval = tardis::tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            // the scan runs on the raft leader only, otherwise every node would move the same messages
+            if !is_mq_leader().await {
+                continue;
+            }
+            match EventDeadLetterServ.dead_letter_expired(&funs).await {
+                Ok(0) => {}
+                Ok(count) => tracing::debug!(count, "moved expired messages to dead-letter store"),
+                Err(error) => tracing::warn!(?error, "fail to move expired messages to dead-letter store"),
+            }
+        }
+    });
+}
+
+fn init_webhook_reconcile(interval: Duration, funs: Arc<TardisFunsInst>) {
+    tardis::tokio::spawn(async move {
+        let mut interval = tardis::tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            if let Err(error) = EventWebhookServ.reconcile(&funs).await {
+                tracing::warn!(?error, "fail to reconcile event webhook subscriptions");
+            }
+        }
+    });
+}
+
 pub async fn init_mq_node(config: &EventConfig, funs: Arc<TardisFunsInst>, ctx: &TardisContext) -> asteroid_mq::prelude::Node {
     let timeout = Duration::from_secs(config.startup_timeout);
     if let Some(node) = TardisFuns::store().get_singleton::<asteroid_mq::prelude::Node>() {
@@ -203,6 +252,13 @@ pub async fn init_mq_node(config: &EventConfig, funs: Arc<TardisFunsInst>, ctx:
 pub fn mq_node_opt() -> Option<asteroid_mq::prelude::Node> {
     TardisFuns::store().get_singleton::<asteroid_mq::prelude::Node>()
 }
+/// Whether this node is the raft leader, the only node running the cluster wide background tasks
+pub async fn is_mq_leader() -> bool {
+    match mq_node_opt() {
+        Some(node) => node.raft().await.metrics().borrow().state.is_leader(),
+        None => false,
+    }
+}
 pub fn mq_node() -> asteroid_mq::prelude::Node {
     mq_node_opt().expect("mq node not initialized")
 }
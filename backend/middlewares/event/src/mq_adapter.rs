@@ -2,18 +2,26 @@
 use std::sync::Arc;
 
 use asteroid_mq::{
-    prelude::{Durable, DurableError, NodeId},
+    prelude::{Durable, DurableError, MessageId, NodeId},
     protocol::node::edge::{
         auth::{EdgeAuth, EdgeAuthError},
         EdgeRequestEnum,
     },
 };
 use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
-use tardis::{basic::dto::TardisContext, TardisFunsInst};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::Utc,
+    TardisFunsInst,
+};
 
 use crate::{
+    domain::{event_dead_letter::REASON_INVALID_PAYLOAD, event_message},
     dto::event_dto::{EventTopicAddOrModifyReq, EventTopicFilterReq},
-    serv::{event_auth_serv::EventAuthServ, event_message_serv::EventMessageServ, event_register_serv::EventRegisterServ, event_topic_serv::EventTopicServ},
+    serv::{
+        event_auth_serv::EventAuthServ, event_dead_letter_serv::EventDeadLetterServ, event_message_serv::EventMessageServ, event_register_serv::EventRegisterServ,
+        event_schema_serv::EventSchemaServ, event_topic_serv::EventTopicServ,
+    },
 };
 
 /*
@@ -38,6 +46,17 @@ impl BiosDurableAdapter {
 
 impl Durable for BiosDurableAdapter {
     async fn save(&self, topic: asteroid_mq::prelude::TopicCode, message: asteroid_mq::prelude::DurableMessage) -> Result<(), DurableError> {
+        // durable messages of local and cluster publishers don't pass the edge auth
+        let subjects = message.message.header.subjects.iter().map(ToString::to_string).collect::<Vec<_>>();
+        check_payload(
+            &topic.to_string(),
+            &subjects,
+            &message.message.payload.0,
+            || Some(event_message::Model::from_durable_message(topic.clone(), message.clone())),
+            &self.funs,
+        )
+        .await
+        .map_err(|e| DurableError::with_source(Self::CONTEXT, e))?;
         self.message_serv.save(topic, message, &self.funs).await.map_err(|e| DurableError::with_source(Self::CONTEXT, e))
     }
     async fn archive(&self, topic: asteroid_mq::prelude::TopicCode, message_id: asteroid_mq::prelude::MessageId) -> Result<(), asteroid_mq::prelude::DurableError> {
@@ -125,19 +144,58 @@ impl EdgeAuth for BiosEdgeAuthAdapter {
             EdgeRequestEnum::EndpointInterest(endpoint_interest) => (endpoint_interest.topic_code.clone(), CheckOption::Read),
             EdgeRequestEnum::SetState(set_state) => (set_state.topic.clone(), CheckOption::Read),
         };
-        if !EventTopicServ::is_check_auth(&topic, funs, ctx).await.map_err(|e| EdgeAuthError::new("topic not found", e))? {
-            return Ok(());
+        if EventTopicServ::is_check_auth(&topic, funs, ctx).await.map_err(|e| EdgeAuthError::new("topic not found", e))? {
+            let ctx = self.register_serv.get_ctx(from).await.map_err(|e| EdgeAuthError::new("node_id not registered", e))?;
+            let auth = self.auth_serv.get_auth(topic.clone(), &ctx.ak, funs).await.map_err(|e| EdgeAuthError::new("auth not found", e))?;
+            if !match check_option {
+                CheckOption::Write => auth.write,
+                CheckOption::Read => auth.read,
+            } {
+                return Err(EdgeAuthError::new_local("no write permission"));
+            }
+        }
+        if let EdgeRequestEnum::SendMessage(edge_message) = request {
+            let subjects = edge_message.header.subjects.iter().map(ToString::to_string).collect::<Vec<_>>();
+            let quarantined = || {
+                Some(event_message::Model {
+                    message_id: MessageId::new_snowflake().to_base64(),
+                    topic: topic.to_string(),
+                    archived: false,
+                    delivery_failures: 0,
+                    ack_kind: edge_message.header.ack_kind as u8 as i16,
+                    target_kind: edge_message.header.target_kind as u8 as i16,
+                    expire_time: edge_message.header.durability.as_ref().map(|d| d.expire),
+                    max_receiver: edge_message.header.durability.as_ref().and_then(|d| d.max_receiver.map(|r| r as i32)),
+                    subjects: subjects.clone(),
+                    payload: edge_message.payload.0.to_vec(),
+                    status: vec![],
+                    time: Utc::now(),
+                })
+            };
+            check_payload(&topic.to_string(), &subjects, &edge_message.payload.0, quarantined, funs).await.map_err(|e| EdgeAuthError::new("invalid payload", e))?;
         }
-        let ctx = self.register_serv.get_ctx(from).await.map_err(|e| EdgeAuthError::new("node_id not registered", e))?;
-        let auth = self.auth_serv.get_auth(topic, &ctx.ak, funs).await.map_err(|e| EdgeAuthError::new("auth not found", e))?;
+        Ok(())
+    }
+}
 
-        if match check_option {
-            CheckOption::Write => auth.write,
-            CheckOption::Read => auth.read,
-        } {
-            Ok(())
-        } else {
-            Err(EdgeAuthError::new_local("no write permission"))
+/// Check the payload of a message published to the topic against the schemas of its subjects.
+///
+/// This is where every publisher meets: edge clients, local and cluster publishers of durable messages and dead-letter replays.
+/// The message built by `quarantined` is kept in the dead-letter store when the violated schema quarantines invalid payloads.
+pub(crate) async fn check_payload(
+    topic: &str,
+    subjects: &[String],
+    payload: &[u8],
+    quarantined: impl FnOnce() -> Option<event_message::Model>,
+    funs: &TardisFunsInst,
+) -> TardisResult<()> {
+    if let Err(violation) = EventSchemaServ.validate_payload(topic, subjects, payload, funs).await? {
+        if violation.quarantine {
+            if let Some(message) = quarantined() {
+                EventDeadLetterServ.quarantine(message, REASON_INVALID_PAYLOAD, funs).await?;
+            }
         }
+        return Err(TardisError::bad_request(&violation.message, "event-payload-invalid"));
     }
+    Ok(())
 }
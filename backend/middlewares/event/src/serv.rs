@@ -1,5 +1,8 @@
 pub mod event_auth_serv;
 pub mod event_connect_serv;
+pub mod event_dead_letter_serv;
 pub mod event_message_serv;
 pub mod event_register_serv;
+pub mod event_schema_serv;
 pub mod event_topic_serv;
+pub mod event_webhook_serv;
@@ -0,0 +1,190 @@
+use asteroid_mq::prelude::TopicCode;
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Utc},
+    db::sea_orm::{
+        sea_query::{Expr, OnConflict},
+        ColumnTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    },
+    web::web_resp::TardisPage,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    domain::{
+        event_dead_letter::{self, REASON_EXPIRED},
+        event_message,
+    },
+    dto::event_dto::{EventDeadLetterDetailResp, EventDeadLetterInfoResp},
+    event_initializer::{mq_error, mq_node_opt},
+    mq_adapter::check_payload,
+};
+
+use super::event_topic_serv::EventTopicServ;
+
+/// Expired messages moved per scan, the rest are left to the next scan
+const EXPIRED_SCAN_LIMIT: u64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventDeadLetterServ;
+
+impl EventDeadLetterServ {
+    /// Move a message to the dead-letter store and archive it, so it won't be delivered anymore
+    pub async fn move_to_dead_letter(&self, message: event_message::Model, reason: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        let message_id = message.message_id.clone();
+        let mut conn = funs.reldb().conn();
+        conn.begin().await?;
+        // several nodes may move the same message at the same time
+        conn.execute(
+            &event_dead_letter::Entity::insert(event_dead_letter::Model::from_message(message, reason).into_active_model())
+                .on_conflict(OnConflict::column(event_dead_letter::Column::MessageId).do_nothing().to_owned())
+                .into_query(),
+        )
+        .await?;
+        conn.execute(
+            &event_message::Entity::update_many().col_expr(event_message::Column::Archived, Expr::value(true)).filter(event_message::Column::MessageId.eq(message_id)).into_query(),
+        )
+        .await?;
+        conn.commit().await?;
+        Ok(())
+    }
+
+    /// Keep a message rejected on publish, it has never been persisted as a durable message
+    pub async fn quarantine(&self, message: event_message::Model, reason: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        let conn = funs.reldb().conn();
+        // a durable message rejected again on saving keeps its id
+        event_dead_letter::Entity::insert(event_dead_letter::Model::from_message(message, reason).into_active_model())
+            .on_conflict(OnConflict::column(event_dead_letter::Column::MessageId).do_nothing().to_owned())
+            .exec_without_returning(conn.raw_conn())
+            .await?;
+        Ok(())
+    }
+
+    /// Move the messages that expired before being acknowledged to the dead-letter store
+    pub async fn dead_letter_expired(&self, funs: &TardisFunsInst) -> TardisResult<u32> {
+        let select = event_message::Entity::find()
+            .filter(event_message::Column::Archived.eq(false))
+            .filter(event_message::Column::ExpireTime.lt(Utc::now()))
+            .order_by_asc(event_message::Column::Time)
+            .limit(Some(EXPIRED_SCAN_LIMIT));
+        let conn = funs.reldb().conn();
+        let models = select.all(conn.raw_conn()).await?;
+        let count = models.len() as u32;
+        for model in models {
+            self.move_to_dead_letter(model, REASON_EXPIRED, funs).await?;
+        }
+        Ok(count)
+    }
+
+    pub async fn paginate(
+        &self,
+        topic: &str,
+        reason: Option<&str>,
+        page_number: u32,
+        page_size: u32,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<TardisPage<EventDeadLetterInfoResp>> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        let page_size = page_size.clamp(1, 500);
+        let mut select = event_dead_letter::Entity::find().filter(event_dead_letter::Column::Topic.eq(topic));
+        if let Some(reason) = reason {
+            select = select.filter(event_dead_letter::Column::Reason.eq(reason));
+        }
+        let conn = funs.reldb().conn();
+        let raw_conn = conn.raw_conn();
+        let total_size = select.clone().count(raw_conn).await?;
+        let models = select
+            .order_by_desc(event_dead_letter::Column::DeadTime)
+            .limit(Some(page_size as u64))
+            .offset(Some((page_number.max(1) as u64 - 1) * page_size as u64))
+            .all(raw_conn)
+            .await?;
+        Ok(TardisPage {
+            page_size: page_size as u64,
+            page_number: page_number as u64,
+            total_size,
+            records: models
+                .into_iter()
+                .map(|model| EventDeadLetterInfoResp {
+                    message_id: model.message_id,
+                    topic: model.topic,
+                    reason: model.reason,
+                    delivery_failures: model.delivery_failures,
+                    subjects: model.subjects,
+                    time: model.time,
+                    dead_time: model.dead_time,
+                })
+                .collect(),
+        })
+    }
+
+    pub async fn get(&self, topic: &str, message_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<EventDeadLetterDetailResp> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        let model = Self::find_one(topic, message_id, funs).await?;
+        Ok(EventDeadLetterDetailResp {
+            message_id: model.message_id,
+            topic: model.topic,
+            reason: model.reason,
+            delivery_failures: model.delivery_failures,
+            subjects: model.subjects,
+            expire_time: model.expire_time,
+            max_receiver: model.max_receiver,
+            payload: TardisFuns::crypto.base64.encode(&model.payload),
+            payload_text: String::from_utf8(model.payload).ok(),
+            time: model.time,
+            dead_time: model.dead_time,
+        })
+    }
+
+    /// Send the dead letter to its original topic again as a new message,
+    /// it is removed from the store together with the archived original once the topic accepted it
+    pub async fn replay(&self, topic: &str, message_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        EventTopicServ::check_topic_auth(topic, true, funs, ctx).await?;
+        let model = Self::find_one(topic, message_id, funs).await?;
+        let node = mq_node_opt().ok_or_else(|| TardisError::conflict("event mq node is not enabled", "event-mq-disabled"))?;
+        let mq_topic = node.get_topic(&TopicCode::new(topic.to_string())).ok_or_else(|| TardisError::not_found(&format!("topic {topic} not found"), "event-topic-not-found"))?;
+        let message = model.into_replay_message();
+        // the dead letter is kept as is when the payload still doesn't match the schema
+        check_payload(topic, &message.subjects, &message.payload, || None, funs).await?;
+        let message = message.try_into_durable_message()?.message;
+        mq_topic.send_message(message).await.map_err(mq_error)?;
+        let mut conn = funs.reldb().conn();
+        conn.begin().await?;
+        conn.execute(&event_message::Entity::delete_many().filter(event_message::Column::MessageId.eq(message_id)).filter(event_message::Column::Archived.eq(true)).into_query())
+            .await?;
+        conn.execute(&event_dead_letter::Entity::delete_many().filter(event_dead_letter::Column::MessageId.eq(message_id)).into_query()).await?;
+        conn.commit().await?;
+        Ok(())
+    }
+
+    /// Remove one dead letter of the topic, or all of them when `message_id` is not given
+    pub async fn purge(&self, topic: &str, message_id: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<u32> {
+        EventTopicServ::check_topic_auth(topic, true, funs, ctx).await?;
+        let mut filter = event_dead_letter::Column::Topic.eq(topic);
+        if let Some(message_id) = message_id {
+            filter = filter.and(event_dead_letter::Column::MessageId.eq(message_id));
+        }
+        let conn = funs.reldb().conn();
+        let delete_result = event_dead_letter::Entity::delete_many().filter(filter).exec(conn.raw_conn()).await?;
+        Ok(delete_result.rows_affected as u32)
+    }
+
+    /// Remove the dead letters moved before `before`, used by the archived message housekeeping
+    pub async fn clear(&self, topic: Option<&str>, before: DateTime<Utc>, funs: &TardisFunsInst) -> TardisResult<u32> {
+        let mut filter = event_dead_letter::Column::DeadTime.lt(before);
+        if let Some(topic) = topic {
+            filter = filter.and(event_dead_letter::Column::Topic.eq(topic));
+        }
+        let conn = funs.reldb().conn();
+        let delete_result = event_dead_letter::Entity::delete_many().filter(filter).exec(conn.raw_conn()).await?;
+        Ok(delete_result.rows_affected as u32)
+    }
+
+    async fn find_one(topic: &str, message_id: &str, funs: &TardisFunsInst) -> TardisResult<event_dead_letter::Model> {
+        let select = event_dead_letter::Entity::find().filter(event_dead_letter::Column::Topic.eq(topic)).filter(event_dead_letter::Column::MessageId.eq(message_id));
+        let conn = funs.reldb().conn();
+        let model = select.one(conn.raw_conn()).await?;
+        model.ok_or_else(|| TardisError::not_found(&format!("dead letter {message_id} not found"), "event-dead-letter-not-found"))
+    }
+}
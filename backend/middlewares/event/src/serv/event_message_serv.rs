@@ -3,13 +3,23 @@ use asteroid_mq::{
     protocol::node::raft::proposal::MessageStateUpdate,
 };
 use tardis::{
-    basic::{error::TardisError, result::TardisResult},
-    chrono::Utc,
-    db::sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, Set, Unchanged},
-    TardisFunsInst,
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Duration, Utc},
+    db::sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, Unchanged},
+    web::web_resp::TardisPage,
+    TardisFuns, TardisFunsInst,
 };
 
-use crate::domain::event_message::{ActiveModel, Column, Entity, Model};
+use crate::{
+    domain::{
+        event_dead_letter::REASON_MAX_DELIVERY_ATTEMPTS,
+        event_message::{ActiveModel, Column, Entity, Model, STATUS_ARCHIVED, STATUS_EXPIRED, STATUS_PENDING},
+    },
+    dto::event_dto::{EventMessageDeliveryStatusResp, EventMessageDetailResp, EventMessageInfoResp, EventMessagePendingCountResp},
+    event_config::EventConfig,
+};
+
+use super::{event_dead_letter_serv::EventDeadLetterServ, event_topic_serv::EventTopicServ};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct EventMessageServ;
 
@@ -22,7 +32,9 @@ impl EventMessageServ {
             filter = filter.and(Column::Topic.eq(topic));
         }
         let delete_result = Entity::delete_many().filter(filter).exec(raw_conn).await?;
-        Ok(delete_result.rows_affected as u32)
+        let retention = Duration::seconds(funs.conf::<EventConfig>().dead_letter_retention_sec.min(i64::MAX as u64) as i64);
+        let dead_letters = EventDeadLetterServ.clear(topic, Utc::now() - retention, funs).await?;
+        Ok(delete_result.rows_affected as u32 + dead_letters)
     }
     pub async fn save(&self, topic: TopicCode, message: DurableMessage, funs: &TardisFunsInst) -> TardisResult<()> {
         let model: Model = Model::from_durable_message(topic, message);
@@ -81,16 +93,136 @@ impl EventMessageServ {
         let raw_conn = conn.raw_conn();
         let model = select.one(raw_conn).await?;
         if let Some(mut model) = model {
-            model.status_update(status);
+            let failed_attempts = model.status_update(status);
+            model.delivery_failures += failed_attempts;
             Entity::update(ActiveModel {
                 message_id: Unchanged(message_id.to_base64()),
-                status: Set(model.status),
+                status: Set(model.status.clone()),
+                delivery_failures: Set(model.delivery_failures),
                 ..Default::default()
             })
             .filter(Column::Topic.eq(topic.to_string()))
             .exec(raw_conn)
             .await?;
+            let max_delivery_attempts = funs.conf::<EventConfig>().max_delivery_attempts;
+            if failed_attempts > 0 && max_delivery_attempts > 0 && model.delivery_failures as u32 >= max_delivery_attempts {
+                EventDeadLetterServ.move_to_dead_letter(model, REASON_MAX_DELIVERY_ATTEMPTS, funs).await?;
+            }
         }
         Ok(())
     }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn paginate(
+        &self,
+        topic: &str,
+        status: Option<&str>,
+        archived: Option<bool>,
+        time_start: Option<DateTime<Utc>>,
+        time_end: Option<DateTime<Utc>>,
+        page_number: u32,
+        page_size: u32,
+        funs: &TardisFunsInst,
+    ) -> TardisResult<TardisPage<EventMessageInfoResp>> {
+        let page_size = page_size.clamp(1, 500);
+        let now = Utc::now();
+        let mut select = Entity::find().filter(Column::Topic.eq(topic));
+        match status {
+            Some(STATUS_PENDING) => select = select.filter(Column::Archived.eq(false)).filter(Column::ExpireTime.gte(now).or(Column::ExpireTime.is_null())),
+            Some(STATUS_EXPIRED) => select = select.filter(Column::Archived.eq(false)).filter(Column::ExpireTime.lt(now)),
+            Some(STATUS_ARCHIVED) => select = select.filter(Column::Archived.eq(true)),
+            Some(status) => return Err(TardisError::bad_request(&format!("unknown message status {status}"), "event-message-invalid-status")),
+            None => {}
+        }
+        if let Some(archived) = archived {
+            select = select.filter(Column::Archived.eq(archived));
+        }
+        if let Some(time_start) = time_start {
+            select = select.filter(Column::Time.gte(time_start));
+        }
+        if let Some(time_end) = time_end {
+            select = select.filter(Column::Time.lt(time_end));
+        }
+        let conn = funs.reldb().conn();
+        let raw_conn = conn.raw_conn();
+        let total_size = select.clone().count(raw_conn).await?;
+        let models = select.order_by_desc(Column::Time).limit(Some(page_size as u64)).offset(Some((page_number.max(1) as u64 - 1) * page_size as u64)).all(raw_conn).await?;
+        Ok(TardisPage {
+            page_size: page_size as u64,
+            page_number: page_number as u64,
+            total_size,
+            records: models
+                .into_iter()
+                .map(|model| EventMessageInfoResp {
+                    status: model.status_label(now).to_string(),
+                    ack_kind: model.ack_kind_label(),
+                    target_kind: model.target_kind_label(),
+                    message_id: model.message_id,
+                    topic: model.topic,
+                    archived: model.archived,
+                    subjects: model.subjects,
+                    delivery_failures: model.delivery_failures,
+                    expire_time: model.expire_time,
+                    time: model.time,
+                })
+                .collect(),
+        })
+    }
+
+    /// Get a message with its delivery status, the payload is redacted if the caller has no read permission to the topic
+    pub async fn get(&self, topic: &str, message_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<EventMessageDetailResp> {
+        let select = Entity::find().filter(Column::Topic.eq(topic)).filter(Column::MessageId.eq(message_id));
+        let conn = funs.reldb().conn();
+        let model = select.one(conn.raw_conn()).await?;
+        let model = model.ok_or_else(|| TardisError::not_found(&format!("event message {message_id} not found"), "event-message-not-found"))?;
+        let readable = EventTopicServ::has_topic_auth(topic, false, funs, ctx).await?;
+        let (payload, payload_base64) = if readable {
+            match String::from_utf8(model.payload.clone()) {
+                Ok(text) => (Some(text), false),
+                Err(e) => (Some(TardisFuns::crypto.base64.encode(e.as_bytes())), true),
+            }
+        } else {
+            (None, false)
+        };
+        let delivery_status = Model::status_from_binary(model.status.clone())
+            .into_iter()
+            .map(|(endpoint, status)| EventMessageDeliveryStatusResp {
+                endpoint: TardisFuns::crypto.hex.encode(endpoint.bytes),
+                status: format!("{status:?}"),
+            })
+            .collect();
+        Ok(EventMessageDetailResp {
+            status: model.status_label(Utc::now()).to_string(),
+            ack_kind: model.ack_kind_label(),
+            target_kind: model.target_kind_label(),
+            message_id: model.message_id,
+            topic: model.topic,
+            archived: model.archived,
+            subjects: model.subjects,
+            delivery_failures: model.delivery_failures,
+            expire_time: model.expire_time,
+            max_receiver: model.max_receiver,
+            payload,
+            payload_base64,
+            payload_redacted: !readable,
+            delivery_status,
+            time: model.time,
+        })
+    }
+
+    /// Count the messages waiting for delivery, grouped by topic
+    pub async fn count_pending(&self, topic: Option<&str>, funs: &TardisFunsInst) -> TardisResult<Vec<EventMessagePendingCountResp>> {
+        let mut select = Entity::find()
+            .select_only()
+            .column(Column::Topic)
+            .column_as(Expr::col(Column::MessageId).count(), "count")
+            .filter(Column::Archived.eq(false))
+            .filter(Column::ExpireTime.gte(Utc::now()).or(Column::ExpireTime.is_null()));
+        if let Some(topic) = topic {
+            select = select.filter(Column::Topic.eq(topic));
+        }
+        let conn = funs.reldb().conn();
+        let result = select.group_by(Column::Topic).order_by_asc(Column::Topic).into_model::<EventMessagePendingCountResp>().all(conn.raw_conn()).await?;
+        Ok(result)
+    }
 }
@@ -0,0 +1,420 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use jsonschema::JSONSchema;
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::Utc,
+    cluster::cluster_broadcast::ClusterBroadcastChannel,
+    db::sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder},
+    serde_json::Value,
+    tardis_static,
+    tokio::{
+        self,
+        sync::{broadcast::error::RecvError, RwLock},
+    },
+    TardisFunsInst,
+};
+
+use crate::{
+    domain::event_schema::{Column, Entity, Model, ON_INVALID_QUARANTINE, ON_INVALID_REJECT},
+    dto::event_dto::{EventSchemaAddReq, EventSchemaCompatibilityResp, EventSchemaInfoResp},
+};
+
+use super::event_topic_serv::EventTopicServ;
+
+struct CompiledSchema {
+    validator: JSONSchema,
+    quarantine: bool,
+}
+
+/// A payload that doesn't match the latest schema of its event code
+#[derive(Debug)]
+pub struct SchemaViolation {
+    pub quarantine: bool,
+    pub message: String,
+}
+
+/// Versioned JSON Schemas of topic payloads, keyed by event code (the message subject)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventSchemaServ;
+
+impl EventSchemaServ {
+    /// Register a new version of the event code's schema, it must be backward compatible with the latest version
+    pub async fn add(&self, topic: &str, event_code: &str, add_req: EventSchemaAddReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<EventSchemaInfoResp> {
+        EventTopicServ::check_topic_auth(topic, true, funs, ctx).await?;
+        let on_invalid = add_req.on_invalid.unwrap_or(ON_INVALID_REJECT.to_string());
+        if on_invalid != ON_INVALID_REJECT && on_invalid != ON_INVALID_QUARANTINE {
+            return Err(TardisError::bad_request(&format!("unknown on_invalid {on_invalid}"), "event-schema-invalid-on-invalid"));
+        }
+        Self::compile(&add_req.schema)?;
+        let latest = Self::find_latest(topic, event_code, funs).await?;
+        if let Some(latest) = &latest {
+            let incompatibilities = backward_incompatibilities(&latest.schema, &add_req.schema);
+            if !incompatibilities.is_empty() {
+                return Err(TardisError::conflict(
+                    &format!("schema is not backward compatible with version {}: {}", latest.version, incompatibilities.join("; ")),
+                    "event-schema-incompatible",
+                ));
+            }
+        }
+        let version = latest.map_or(1, |latest| latest.version + 1);
+        let model = Model {
+            id: Model::format_id(topic, event_code, version),
+            topic: topic.to_string(),
+            event_code: event_code.to_string(),
+            version,
+            schema: add_req.schema,
+            on_invalid,
+            ak: ctx.ak.clone(),
+            create_time: Utc::now(),
+        };
+        let conn = funs.reldb().conn();
+        let model = model.into_active_model().insert(conn.raw_conn()).await?;
+        let key = (topic.to_string(), event_code.to_string());
+        cache().write().await.remove(&key);
+        // the other nodes would validate against the cached previous version until it expires
+        schema_changes().send(key);
+        Ok(model.into_info_resp())
+    }
+
+    /// Check a schema against the latest version without registering it
+    pub async fn check_compatibility(
+        &self,
+        topic: &str,
+        event_code: &str,
+        schema: &Value,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<EventSchemaCompatibilityResp> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        Self::compile(schema)?;
+        let incompatibilities = match Self::find_latest(topic, event_code, funs).await? {
+            Some(latest) => backward_incompatibilities(&latest.schema, schema),
+            None => vec![],
+        };
+        Ok(EventSchemaCompatibilityResp {
+            compatible: incompatibilities.is_empty(),
+            incompatibilities,
+        })
+    }
+
+    /// Get a version of the event code's schema, the latest one if `version` is not given
+    pub async fn get(&self, topic: &str, event_code: &str, version: Option<i32>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<EventSchemaInfoResp> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        let model = match version {
+            Some(version) => {
+                let conn = funs.reldb().conn();
+                Entity::find_by_id(Model::format_id(topic, event_code, version)).one(conn.raw_conn()).await?
+            }
+            None => Self::find_latest(topic, event_code, funs).await?,
+        };
+        let model = model.ok_or_else(|| TardisError::not_found(&format!("schema of event {event_code} in topic {topic} not found"), "event-schema-not-found"))?;
+        Ok(model.into_info_resp())
+    }
+
+    /// Find the schemas of the topic, all versions are returned when `event_code` is given, otherwise only the latest ones
+    pub async fn find(&self, topic: &str, event_code: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<EventSchemaInfoResp>> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        let mut select = Entity::find().filter(Column::Topic.eq(topic));
+        if let Some(event_code) = event_code {
+            select = select.filter(Column::EventCode.eq(event_code));
+        }
+        let conn = funs.reldb().conn();
+        let models = select.order_by_asc(Column::EventCode).order_by_desc(Column::Version).all(conn.raw_conn()).await?;
+        let mut seen = HashSet::new();
+        Ok(models.into_iter().filter(|model| event_code.is_some() || seen.insert(model.event_code.clone())).map(Model::into_info_resp).collect())
+    }
+
+    /// Validate a published payload against the latest schema of each of its subjects, subjects without schema are not checked
+    pub async fn validate_payload(&self, topic: &str, subjects: &[String], payload: &[u8], funs: &TardisFunsInst) -> TardisResult<Result<(), SchemaViolation>> {
+        let mut payload_json = None;
+        for subject in subjects {
+            let Some(schema) = Self::get_compiled(topic, subject, funs).await? else {
+                continue;
+            };
+            if payload_json.is_none() {
+                match tardis::serde_json::from_slice::<Value>(payload) {
+                    Ok(json) => payload_json = Some(json),
+                    Err(e) => {
+                        return Ok(Err(SchemaViolation {
+                            quarantine: schema.quarantine,
+                            message: format!("payload of event {subject} is not valid json: {e}"),
+                        }))
+                    }
+                }
+            }
+            let Some(json) = payload_json.as_ref() else {
+                continue;
+            };
+            if let Err(errors) = schema.validator.validate(json) {
+                let errors = errors.map(|e| format!("{}: {}", e.instance_path, e)).collect::<Vec<_>>();
+                return Ok(Err(SchemaViolation {
+                    quarantine: schema.quarantine,
+                    message: format!("payload of event {subject} doesn't match its schema: {}", errors.join("; ")),
+                }));
+            }
+        }
+        Ok(Ok(()))
+    }
+
+    /// Drop the cached compiled schemas changed on any node of the cluster
+    pub fn listen_changes() {
+        let mut changes = schema_changes().subscribe();
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(key) => {
+                        cache().write().await.remove(&key);
+                    }
+                    Err(RecvError::Lagged(_)) => cache().write().await.clear(),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    async fn get_compiled(topic: &str, event_code: &str, funs: &TardisFunsInst) -> TardisResult<Option<Arc<CompiledSchema>>> {
+        const EXPIRE_DURATION: Duration = Duration::from_secs(60);
+        let key = (topic.to_string(), event_code.to_string());
+        let now = Instant::now();
+        if let Some((expire, schema)) = cache().read().await.get(&key) {
+            if *expire > now {
+                return Ok(schema.clone());
+            }
+        }
+        let schema = match Self::find_latest(topic, event_code, funs).await? {
+            Some(model) => Some(Arc::new(CompiledSchema {
+                validator: Self::compile(&model.schema)?,
+                quarantine: model.on_invalid == ON_INVALID_QUARANTINE,
+            })),
+            None => None,
+        };
+        cache().write().await.insert(key, (now + EXPIRE_DURATION, schema.clone()));
+        Ok(schema)
+    }
+
+    async fn find_latest(topic: &str, event_code: &str, funs: &TardisFunsInst) -> TardisResult<Option<Model>> {
+        let conn = funs.reldb().conn();
+        let model = Entity::find().filter(Column::Topic.eq(topic)).filter(Column::EventCode.eq(event_code)).order_by_desc(Column::Version).one(conn.raw_conn()).await?;
+        Ok(model)
+    }
+
+    fn compile(schema: &Value) -> TardisResult<JSONSchema> {
+        JSONSchema::compile(schema).map_err(|e| TardisError::bad_request(&format!("invalid json schema: {e}"), "event-schema-invalid"))
+    }
+}
+
+tardis_static! {
+    cache: RwLock<HashMap<(String, String), (Instant, Option<Arc<CompiledSchema>>)>>;
+    // (topic, event code) of the schemas added on any node
+    schema_changes: Arc<ClusterBroadcastChannel<(String, String)>> = ClusterBroadcastChannel::new(SCHEMA_CHANGE_CHANNEL_IDENT, SCHEMA_CHANGE_CHANNEL_SIZE);
+}
+
+const SCHEMA_CHANGE_CHANNEL_IDENT: &str = "bios-event-schema-change";
+const SCHEMA_CHANGE_CHANNEL_SIZE: usize = 64;
+
+/// List the changes from `old` to `new` that would reject payloads `old` accepts.
+///
+/// This is a structural check over the common keywords (`type`, `enum`, `required`, `properties`,
+/// `additionalProperties`, `items` and the numeric/length bounds), references and combinators are compared as is.
+/// A property added to an object accepting additional properties is checked against the old `additionalProperties`,
+/// so constraining a property that used to be accepted as anything is incompatible.
+pub(crate) fn backward_incompatibilities(old: &Value, new: &Value) -> Vec<String> {
+    let mut incompatibilities = vec![];
+    check_compatibility("#", old, new, &mut incompatibilities);
+    incompatibilities
+}
+
+fn check_compatibility(path: &str, old: &Value, new: &Value, incompatibilities: &mut Vec<String>) {
+    // nothing was accepted by a `false` schema
+    if accepts_any(new) || old == new || old == &Value::Bool(false) {
+        return;
+    }
+    if accepts_any(old) {
+        incompatibilities.push(format!("{path}: values are restricted"));
+        return;
+    }
+    let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+        incompatibilities.push(format!("{path}: schema changed"));
+        return;
+    };
+    for keyword in ["$ref", "allOf", "anyOf", "oneOf", "not", "const", "pattern", "format"] {
+        if new.get(keyword).is_some_and(|value| old.get(keyword) != Some(value)) {
+            incompatibilities.push(format!("{path}: `{keyword}` changed"));
+        }
+    }
+    if let Some(new_types) = types(new) {
+        match types(old) {
+            Some(old_types) => {
+                for old_type in old_types {
+                    if !new_types.contains(&old_type) && !(old_type == "integer" && new_types.contains(&"number")) {
+                        incompatibilities.push(format!("{path}: type `{old_type}` is no longer accepted"));
+                    }
+                }
+            }
+            None => incompatibilities.push(format!("{path}: type is restricted")),
+        }
+    }
+    if let Some(new_enum) = new.get("enum").and_then(Value::as_array) {
+        match old.get("enum").and_then(Value::as_array) {
+            Some(old_enum) => {
+                for value in old_enum.iter().filter(|value| !new_enum.contains(value)) {
+                    incompatibilities.push(format!("{path}: enum value {value} is removed"));
+                }
+            }
+            None => incompatibilities.push(format!("{path}: values are restricted to an enum")),
+        }
+    }
+    let old_required = strings(old.get("required"));
+    for required in strings(new.get("required")).difference(&old_required) {
+        incompatibilities.push(format!("{path}: property `{required}` became required"));
+    }
+    let empty = tardis::serde_json::Map::new();
+    let old_properties = old.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+    let new_properties = new.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+    // additional properties are allowed unless stated otherwise
+    let accept_all = Value::Bool(true);
+    let old_additional = old.get("additionalProperties").unwrap_or(&accept_all);
+    for (name, new_property) in new_properties {
+        let old_property = old_properties.get(name).unwrap_or(old_additional);
+        check_compatibility(&format!("{path}/properties/{name}"), old_property, new_property, incompatibilities);
+    }
+    if new.get("additionalProperties").is_some_and(|additional| additional == &Value::Bool(false)) {
+        if old.get("additionalProperties") != Some(&Value::Bool(false)) {
+            incompatibilities.push(format!("{path}: additional properties are no longer allowed"));
+        }
+        for name in old_properties.keys().filter(|name| !new_properties.contains_key(*name)) {
+            incompatibilities.push(format!("{path}: property `{name}` is removed"));
+        }
+    }
+    match (old.get("items"), new.get("items")) {
+        (Some(old_items), Some(new_items)) => check_compatibility(&format!("{path}/items"), old_items, new_items, incompatibilities),
+        (None, Some(new_items)) if !accepts_any(new_items) => incompatibilities.push(format!("{path}: items are restricted")),
+        _ => {}
+    }
+    for keyword in ["minimum", "exclusiveMinimum", "minLength", "minItems", "minProperties"] {
+        if let Some(new_bound) = new.get(keyword).and_then(Value::as_f64) {
+            if old.get(keyword).and_then(Value::as_f64).map_or(true, |old_bound| new_bound > old_bound) {
+                incompatibilities.push(format!("{path}: `{keyword}` is raised"));
+            }
+        }
+    }
+    for keyword in ["maximum", "exclusiveMaximum", "maxLength", "maxItems", "maxProperties"] {
+        if let Some(new_bound) = new.get(keyword).and_then(Value::as_f64) {
+            if old.get(keyword).and_then(Value::as_f64).map_or(true, |old_bound| new_bound < old_bound) {
+                incompatibilities.push(format!("{path}: `{keyword}` is lowered"));
+            }
+        }
+    }
+}
+
+fn accepts_any(schema: &Value) -> bool {
+    match schema {
+        Value::Bool(accept) => *accept,
+        Value::Object(object) => object.keys().all(|key| matches!(key.as_str(), "$schema" | "$id" | "title" | "description" | "examples" | "default")),
+        _ => false,
+    }
+}
+
+fn types(schema: &tardis::serde_json::Map<String, Value>) -> Option<Vec<&str>> {
+    match schema.get("type")? {
+        Value::String(t) => Some(vec![t.as_str()]),
+        Value::Array(ts) => Some(ts.iter().filter_map(Value::as_str).collect()),
+        _ => None,
+    }
+}
+
+fn strings(value: Option<&Value>) -> HashSet<&str> {
+    value.and_then(Value::as_array).map(|values| values.iter().filter_map(Value::as_str).collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use tardis::serde_json::json;
+
+    use super::backward_incompatibilities;
+
+    #[test]
+    fn test_backward_incompatibilities() {
+        let v1 = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer"},
+                "kind": {"enum": ["a", "b"]},
+                "tags": {"type": "array", "items": {"type": "string", "maxLength": 10}}
+            },
+            "required": ["id"]
+        });
+        // adding an unconstrained property and widening types are compatible
+        let v2 = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "number"},
+                "kind": {"enum": ["a", "b", "c"]},
+                "tags": {"type": "array", "items": {"type": "string", "maxLength": 20}},
+                "note": {"description": "free text"}
+            },
+            "required": ["id"]
+        });
+        assert!(backward_incompatibilities(&v1, &v2).is_empty());
+        assert!(backward_incompatibilities(&v1, &v1).is_empty());
+
+        // v1 accepts any additional property, `{"note": 1}` included
+        let v2_typed_note = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer"},
+                "note": {"type": "string"}
+            },
+            "required": ["id"]
+        });
+        assert_eq!(
+            backward_incompatibilities(&v1, &v2_typed_note),
+            vec!["#/properties/note: values are restricted".to_string()]
+        );
+        // but no additional property is accepted by a closed object
+        let v1_closed = json!({
+            "type": "object",
+            "properties": {"id": {"type": "integer"}},
+            "additionalProperties": false
+        });
+        let v2_closed = json!({
+            "type": "object",
+            "properties": {"id": {"type": "integer"}, "note": {"type": "string"}},
+            "additionalProperties": false
+        });
+        assert!(backward_incompatibilities(&v1_closed, &v2_closed).is_empty());
+        // and the property must match the additional properties schema of the old version
+        let v1_additional = json!({"type": "object", "additionalProperties": {"type": "string"}});
+        assert!(backward_incompatibilities(&v1_additional, &json!({"type": "object", "properties": {"note": {"type": ["string", "null"]}}})).is_empty());
+        assert_eq!(
+            backward_incompatibilities(&v1_additional, &json!({"type": "object", "properties": {"note": {"type": "integer"}}})).len(),
+            1
+        );
+
+        let v3 = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "string"},
+                "kind": {"enum": ["a"]},
+                "tags": {"type": "array", "items": {"type": "string", "maxLength": 5}}
+            },
+            "required": ["id", "kind"],
+            "additionalProperties": false
+        });
+        let incompatibilities = backward_incompatibilities(&v1, &v3);
+        assert_eq!(incompatibilities.len(), 5, "{incompatibilities:?}");
+        assert!(incompatibilities.contains(&"#/properties/id: type `integer` is no longer accepted".to_string()));
+        assert!(incompatibilities.contains(&"#/properties/kind: enum value \"b\" is removed".to_string()));
+        assert!(incompatibilities.contains(&"#: property `kind` became required".to_string()));
+        assert!(incompatibilities.contains(&"#: additional properties are no longer allowed".to_string()));
+        assert!(incompatibilities.contains(&"#/properties/tags/items: `maxLength` is lowered".to_string()));
+
+        assert!(backward_incompatibilities(&json!({}), &json!({"type": "object"})).len() == 1);
+        assert!(backward_incompatibilities(&json!({"type": "object"}), &json!(true)).is_empty());
+    }
+}
@@ -144,6 +144,25 @@ impl EventTopicServ {
         cache().write().await.insert(topic_code.clone(), (expire, resp.check_auth));
         Ok(resp.check_auth)
     }
+    /// Check the topic permission of the context's ak, the same rule as native clients when `check_auth` is on
+    pub async fn check_topic_auth(topic: &str, write: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        if !Self::has_topic_auth(topic, write, funs, ctx).await? {
+            return Err(TardisError::forbidden(&format!("no permission to topic {topic}"), "event-topic-forbidden"));
+        }
+        Ok(())
+    }
+
+    pub async fn has_topic_auth(topic: &str, write: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+        let topic_code = TopicCode::new(topic.to_string());
+        if !Self::is_check_auth(&topic_code, funs, ctx).await? {
+            return Ok(true);
+        }
+        let permitted = match EventAuthServ::new().get_auth(topic_code, &ctx.ak, funs).await {
+            Ok(auth) => (write && auth.write) || (!write && auth.read),
+            Err(_) => false,
+        };
+        Ok(permitted)
+    }
     // pub async fn init(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
     //     // let defs = Self::find_items(&EventTopicFilterReq::default(), None, None, funs, ctx).await?;
 
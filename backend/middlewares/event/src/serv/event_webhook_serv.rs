@@ -0,0 +1,388 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    sync::Mutex,
+    time::Duration,
+};
+
+use asteroid_mq::prelude::{Interest, TopicCode};
+use asteroid_mq_sdk::ClientNode;
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::Utc,
+    db::sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set},
+    tardis_static,
+    tokio::{self, task::AbortHandle},
+    tracing,
+    url::{Host, Url},
+    web::reqwest::{redirect::Policy, Client},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    domain::event_webhook::{ActiveModel, Column, Entity, Model},
+    dto::event_dto::{EventWebhookAddReq, EventWebhookDelivery, EventWebhookInfoResp, EventWebhookModifyReq},
+    event_config::EventConfig,
+    event_constants::get_tardis_inst,
+    event_initializer::{is_mq_leader, mq_node_opt},
+};
+
+use super::event_topic_serv::EventTopicServ;
+
+pub const HEADER_TOPIC: &str = "Bios-Event-Topic";
+pub const HEADER_MESSAGE_ID: &str = "Bios-Event-Message-Id";
+pub const HEADER_TIMESTAMP: &str = "Bios-Event-Timestamp";
+/// `sha256={hex(hmac_sha256("{timestamp}.{body}", secret))}`
+pub const HEADER_SIGNATURE: &str = "Bios-Event-Signature";
+
+tardis_static! {
+    // running subscriptions with the subject interests they were started with
+    webhook_tasks: Mutex<HashMap<String, (AbortHandle, Vec<String>)>>;
+}
+
+/// Webhook subscriptions of topics.
+///
+/// Enabled subscriptions run as local client node endpoints on the raft leader only, so each message is posted once
+/// in the cluster. Messages are posted to the url and acknowledged as processed once the url responds with 2xx,
+/// or as failed after the configured attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventWebhookServ;
+
+impl EventWebhookServ {
+    pub async fn add(&self, topic: &str, add_req: EventWebhookAddReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        Self::check_url(&add_req.url, funs).await?;
+        let model = Model {
+            id: TardisFuns::field.nanoid(),
+            topic: topic.to_string(),
+            url: add_req.url,
+            secret: add_req.secret,
+            subjects: add_req.subjects,
+            enabled: add_req.enabled.unwrap_or(true),
+            ak: ctx.ak.clone(),
+            delivered_count: 0,
+            failed_count: 0,
+            consecutive_failures: 0,
+            last_delivery_time: None,
+            last_failed_message_id: None,
+            last_error: None,
+            create_time: Utc::now(),
+        };
+        let conn = funs.reldb().conn();
+        let model = model.into_active_model().insert(conn.raw_conn()).await?;
+        self.reconcile(funs).await?;
+        Ok(model.id)
+    }
+
+    pub async fn modify(&self, topic: &str, id: &str, modify_req: EventWebhookModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        let model = Self::find_own(topic, id, funs, ctx).await?;
+        let mut active_model = ActiveModel {
+            id: Set(model.id),
+            ..Default::default()
+        };
+        if let Some(url) = modify_req.url {
+            Self::check_url(&url, funs).await?;
+            active_model.url = Set(url);
+        }
+        if let Some(secret) = modify_req.secret {
+            active_model.secret = Set(secret);
+        }
+        if let Some(subjects) = modify_req.subjects {
+            active_model.subjects = Set(subjects);
+        }
+        if let Some(enabled) = modify_req.enabled {
+            active_model.enabled = Set(enabled);
+        }
+        let conn = funs.reldb().conn();
+        active_model.update(conn.raw_conn()).await?;
+        // applied right away when this node is the leader, otherwise by the leader's next reconciliation
+        self.reconcile(funs).await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, topic: &str, id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        let model = Self::find_own(topic, id, funs, ctx).await?;
+        let conn = funs.reldb().conn();
+        Entity::delete_by_id(model.id).exec(conn.raw_conn()).await?;
+        self.reconcile(funs).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, topic: &str, id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<EventWebhookInfoResp> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        Ok(Self::find_one(topic, id, funs).await?.into_info_resp())
+    }
+
+    pub async fn find(&self, topic: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<EventWebhookInfoResp>> {
+        EventTopicServ::check_topic_auth(topic, false, funs, ctx).await?;
+        let conn = funs.reldb().conn();
+        let models = Entity::find().filter(Column::Topic.eq(topic)).order_by_asc(Column::CreateTime).all(conn.raw_conn()).await?;
+        Ok(models.into_iter().map(Model::into_info_resp).collect())
+    }
+
+    /// Apply the enabled subscriptions to this node, called periodically on every node once the mq node is ready.
+    ///
+    /// The raft leader runs every enabled subscription the owner still has read permission to, other nodes run none.
+    /// Subscriptions modified or removed through another node are applied here.
+    pub async fn reconcile(&self, funs: &TardisFunsInst) -> TardisResult<()> {
+        if !is_mq_leader().await {
+            Self::stop_all();
+            return Ok(());
+        }
+        let conn = funs.reldb().conn();
+        let models = Entity::find().filter(Column::Enabled.eq(true)).all(conn.raw_conn()).await?;
+        let enabled_ids = models.iter().map(|model| model.id.clone()).collect::<HashSet<_>>();
+        let running_ids = webhook_tasks().lock().map(|tasks| tasks.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+        for id in running_ids.iter().filter(|id| !enabled_ids.contains(*id)) {
+            Self::stop(id);
+        }
+        for model in models {
+            if !Self::is_owner_permitted(&model, funs).await? {
+                Self::stop(&model.id);
+                continue;
+            }
+            let running =
+                webhook_tasks().lock().ok().and_then(|tasks| tasks.get(&model.id).map(|(task, subjects)| !task.is_finished() && subjects == &model.subjects)).unwrap_or(false);
+            if !running {
+                Self::start(model);
+            }
+        }
+        Ok(())
+    }
+
+    fn start(webhook: Model) {
+        Self::stop(&webhook.id);
+        let Some(node) = mq_node_opt() else {
+            return;
+        };
+        let id = webhook.id.clone();
+        let subjects = webhook.subjects.clone();
+        let task = tokio::spawn(async move {
+            let id = webhook.id.clone();
+            if let Err(error) = Self::run(webhook, node).await {
+                tracing::warn!(?error, webhook = id, "event webhook subscription stopped");
+            }
+        });
+        if let Ok(mut tasks) = webhook_tasks().lock() {
+            tasks.insert(id, (task.abort_handle(), subjects));
+        }
+    }
+
+    fn stop(id: &str) {
+        if let Some((task, _)) = webhook_tasks().lock().ok().and_then(|mut tasks| tasks.remove(id)) {
+            task.abort();
+        }
+    }
+
+    fn stop_all() {
+        if let Ok(mut tasks) = webhook_tasks().lock() {
+            for (_, (task, _)) in tasks.drain() {
+                task.abort();
+            }
+        }
+    }
+
+    /// The local client node is not checked by the edge auth, so the subscription is checked against its owner's permission instead
+    async fn is_owner_permitted(webhook: &Model, funs: &TardisFunsInst) -> TardisResult<bool> {
+        let owner_ctx = TardisContext {
+            ak: webhook.ak.clone(),
+            ..Default::default()
+        };
+        EventTopicServ::has_topic_auth(&webhook.topic, false, funs, &owner_ctx).await
+    }
+
+    async fn run(webhook: Model, node: asteroid_mq::prelude::Node) -> TardisResult<()> {
+        let funs = get_tardis_inst();
+        let client_node = ClientNode::connect_local_without_auth(node).await.map_err(|e| TardisError::internal_error(&e.to_string(), "event-webhook-connect-failed"))?;
+        let mut endpoint = client_node
+            .create_endpoint(TopicCode::new(webhook.topic.clone()), webhook.subjects.iter().map(|subject| Interest::new(subject.clone())))
+            .await
+            .map_err(|e| TardisError::internal_error(&e.to_string(), "event-webhook-subscribe-failed"))?;
+        while let Some(message) = endpoint.next_message().await {
+            // the subscription may be modified on another node, always deliver with the latest url and secret
+            let conn = funs.reldb().conn();
+            let Some(webhook) = Entity::find_by_id(webhook.id.clone()).one(conn.raw_conn()).await?.filter(|webhook| webhook.enabled) else {
+                return Ok(());
+            };
+            if !Self::is_owner_permitted(&webhook, &funs).await? {
+                tracing::info!(webhook = webhook.id, ak = webhook.ak, "event webhook owner lost read permission, subscription stopped");
+                return Ok(());
+            }
+            let message_id = message.header.message_id.to_base64();
+            let payload = message.payload.0.to_vec();
+            let (payload, payload_base64) = match String::from_utf8(payload) {
+                Ok(text) => (text, false),
+                Err(e) => (TardisFuns::crypto.base64.encode(e.as_bytes()), true),
+            };
+            let delivery = EventWebhookDelivery {
+                topic: webhook.topic.clone(),
+                message_id: message_id.clone(),
+                subjects: message.header.subjects.iter().map(ToString::to_string).collect(),
+                payload,
+                payload_base64,
+            };
+            match Self::deliver(&webhook, &delivery, &funs).await {
+                Ok(()) => {
+                    Self::record_success(&webhook.id, &funs).await?;
+                    let _ = message.ack_processed().await;
+                }
+                Err(error) => {
+                    tracing::debug!(?error, webhook = webhook.id, message_id, "event webhook delivery failed");
+                    Self::record_failure(&webhook.id, &message_id, &error.to_string(), &funs).await?;
+                    let _ = message.ack_failed().await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn deliver(webhook: &Model, delivery: &EventWebhookDelivery, funs: &TardisFunsInst) -> TardisResult<()> {
+        let config = funs.conf::<EventConfig>();
+        let body = TardisFuns::json.obj_to_string(delivery)?;
+        let max_attempts = config.webhook_max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let result = Self::post(webhook, &delivery.message_id, &body, funs).await;
+            if result.is_ok() || attempt >= max_attempts {
+                return result;
+            }
+            tokio::time::sleep(backoff(attempt, config.webhook_backoff_base_ms, config.webhook_backoff_max_ms)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn post(webhook: &Model, message_id: &str, body: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        // the host may resolve to another address than when the subscription was saved,
+        // and the request is pinned to the checked addresses so it cannot be resolved again to an internal one
+        let pinned_addrs = Self::check_url(&webhook.url, funs).await?;
+        let mut client = Client::builder().redirect(Policy::none()).timeout(Duration::from_secs(funs.conf::<EventConfig>().webhook_timeout_sec));
+        if let Some((domain, addrs)) = pinned_addrs {
+            client = client.resolve_to_addrs(&domain, &addrs);
+        }
+        let client = client.build().map_err(|e| TardisError::internal_error(&format!("fail to build webhook client: {e}"), "event-webhook-delivery-failed"))?;
+        let timestamp = Utc::now().timestamp_millis().to_string();
+        let signature = TardisFuns::crypto.hex.encode(TardisFuns::crypto.digest.hmac_sha256(format!("{timestamp}.{body}"), &webhook.secret)?);
+        // redirects are not followed, their targets are not checked
+        let resp = client
+            .post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header(HEADER_TOPIC, &webhook.topic)
+            .header(HEADER_MESSAGE_ID, message_id)
+            .header(HEADER_TIMESTAMP, timestamp)
+            .header(HEADER_SIGNATURE, format!("sha256={signature}"))
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(|e| TardisError::internal_error(&format!("fail to post webhook: {e}"), "event-webhook-delivery-failed"))?;
+        let code = resp.status().as_u16();
+        if (200..300).contains(&code) {
+            Ok(())
+        } else {
+            Err(TardisError::custom(
+                &code.to_string(),
+                &format!("webhook responded with status {}: {}", code, resp.text().await.unwrap_or_default()),
+                "event-webhook-delivery-failed",
+            ))
+        }
+    }
+
+    async fn record_success(id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        let conn = funs.reldb().conn();
+        Entity::update_many()
+            .col_expr(Column::DeliveredCount, Expr::col(Column::DeliveredCount).add(1))
+            .col_expr(Column::ConsecutiveFailures, Expr::value(0))
+            .col_expr(Column::LastDeliveryTime, Expr::value(Utc::now()))
+            .filter(Column::Id.eq(id))
+            .exec(conn.raw_conn())
+            .await?;
+        Ok(())
+    }
+
+    async fn record_failure(id: &str, message_id: &str, error: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        let conn = funs.reldb().conn();
+        Entity::update_many()
+            .col_expr(Column::FailedCount, Expr::col(Column::FailedCount).add(1))
+            .col_expr(Column::ConsecutiveFailures, Expr::col(Column::ConsecutiveFailures).add(1))
+            .col_expr(Column::LastDeliveryTime, Expr::value(Utc::now()))
+            .col_expr(Column::LastFailedMessageId, Expr::value(message_id))
+            .col_expr(Column::LastError, Expr::value(error))
+            .filter(Column::Id.eq(id))
+            .exec(conn.raw_conn())
+            .await?;
+        Ok(())
+    }
+
+    async fn find_one(topic: &str, id: &str, funs: &TardisFunsInst) -> TardisResult<Model> {
+        let conn = funs.reldb().conn();
+        let model = Entity::find().filter(Column::Topic.eq(topic)).filter(Column::Id.eq(id)).one(conn.raw_conn()).await?;
+        model.ok_or_else(|| TardisError::not_found(&format!("webhook {id} not found"), "event-webhook-not-found"))
+    }
+
+    /// Only the ak that created the subscription may change it
+    async fn find_own(topic: &str, id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Model> {
+        let model = Self::find_one(topic, id, funs).await?;
+        if model.ak != ctx.ak {
+            return Err(TardisError::forbidden(&format!("webhook {id} belongs to another ak"), "event-webhook-forbidden"));
+        }
+        Ok(model)
+    }
+
+    /// Reject urls that are not http(s) or whose host resolves to loopback, link-local or private addresses,
+    /// unless the host is in `webhook_allowed_hosts`.
+    ///
+    /// Returns the checked addresses of a domain host, which the requests to the url must be pinned to
+    async fn check_url(url: &str, funs: &TardisFunsInst) -> TardisResult<Option<(String, Vec<SocketAddr>)>> {
+        let invalid = |msg: &str| TardisError::bad_request(&format!("invalid webhook url: {msg}"), "event-webhook-invalid-url");
+        let url = Url::parse(url).map_err(|e| invalid(&e.to_string()))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(invalid("url must be http or https"));
+        }
+        let host = url.host().ok_or_else(|| invalid("missing host"))?;
+        let host_str = host.to_string();
+        if funs.conf::<EventConfig>().webhook_allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host_str.trim_matches(|c| c == '[' || c == ']'))) {
+            return Ok(None);
+        }
+        let port = url.port_or_known_default().unwrap_or(80);
+        let (domain, addrs) = match host {
+            Host::Ipv4(ip) => (None, vec![SocketAddr::new(IpAddr::V4(ip), port)]),
+            Host::Ipv6(ip) => (None, vec![SocketAddr::new(IpAddr::V6(ip), port)]),
+            Host::Domain(domain) => (
+                Some(domain.to_string()),
+                tokio::net::lookup_host((domain, port)).await.map_err(|e| invalid(&format!("fail to resolve host {domain}: {e}")))?.collect::<Vec<_>>(),
+            ),
+        };
+        if addrs.is_empty() {
+            return Err(invalid(&format!("host {host_str} resolves to no address")));
+        }
+        if let Some(addr) = addrs.iter().find(|addr| is_internal_addr(&addr.ip())) {
+            return Err(invalid(&format!("host {host_str} resolves to internal address {}", addr.ip())));
+        }
+        Ok(domain.map(|domain| (domain, addrs)))
+    }
+}
+
+/// Loopback, link-local, private, shared, unspecified and broadcast addresses, which webhooks must not reach
+fn is_internal_addr(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast() || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ipv4) = ip.to_ipv4_mapped() {
+                return is_internal_addr(&IpAddr::V4(ipv4));
+            }
+            let first = ip.segments()[0];
+            // unique local fc00::/7 and link-local fe80::/10
+            ip.is_loopback() || ip.is_unspecified() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+/// Backoff before the attempt after `attempt`, doubled from `base_ms` and capped by `max_ms`
+fn backoff(attempt: u32, base_ms: u64, max_ms: u64) -> Duration {
+    let factor = 1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX);
+    Duration::from_millis(base_ms.saturating_mul(factor).min(max_ms))
+}
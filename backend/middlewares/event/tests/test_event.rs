@@ -1,14 +1,27 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::Duration;
 
-use asteroid_mq::prelude::{Interest, Subject, TopicCode};
+use asteroid_mq::prelude::{Interest, MessageAckExpectKind, MessageDurableConfig, Subject, TopicCode};
 use asteroid_mq_sdk::model::EdgeMessage;
 use bios_basic::rbum::rbum_config::RbumConfig;
 use bios_basic::test::init_test_container;
 use bios_basic::test::test_http_client::TestHttpClient;
+use bios_mw_event::dto::event_dto::{
+    EventDeadLetterDetailResp, EventDeadLetterInfoResp, EventMessageInfoResp, EventSchemaAddReq, EventSchemaCompatibilityResp, EventSchemaInfoResp, EventWebhookAddReq,
+    EventWebhookDelivery, EventWebhookInfoResp, EventWebhookModifyReq,
+};
 use bios_mw_event::event_constants::DOMAIN_CODE;
 use bios_mw_event::event_initializer;
 use tardis::basic::dto::TardisContext;
+use tardis::chrono::{TimeDelta, Utc};
 use tardis::log as tracing;
+use tardis::serde_json::{json, Value};
+use tardis::web::poem_openapi::param::{Header, Query};
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::poem_openapi::{self, ApiResponse};
+use tardis::web::web_resp::{TardisPage, Void};
+use tardis::web::web_server::{WebServerModule, WebServerModuleOption};
 use tardis::{tardis_static, tokio, TardisFuns};
 #[tokio::test(flavor = "multi_thread")]
 async fn test_event() -> Result<(), Box<dyn std::error::Error>> {
@@ -19,6 +32,10 @@ async fn test_event() -> Result<(), Box<dyn std::error::Error>> {
     init_data().await?;
     // tokio::io::stdin().read_buf(&mut Vec::new()).await?;
     test_event_topic_api().await?;
+    test_event_webhook_api().await?;
+    test_event_schema_api().await?;
+    test_event_dead_letter_api().await?;
+    test_event_message_api().await?;
     Ok(())
 }
 
@@ -29,6 +46,7 @@ async fn init_data() -> Result<(), Box<dyn std::error::Error>> {
     let web_server = TardisFuns::web_server();
     // Initialize Event
     event_initializer::init(web_server.as_ref()).await?;
+    web_server.add_module("webhook", WebServerModule::from(WebhookReceiverApi).options(WebServerModuleOption { uniform_error: false })).await;
     web_server.start().await?;
 
     let ctx = TardisContext {
@@ -104,3 +122,361 @@ pub async fn test_event_topic_api() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+const WEBHOOK_SECRET: &str = "test-webhook-secret";
+
+tardis_static! {
+    // message id -> attempts received
+    webhook_attempts: Mutex<HashMap<String, u32>>;
+    webhook_deliveries: Mutex<Vec<EventWebhookDelivery>>;
+}
+
+#[derive(ApiResponse)]
+enum WebhookReceiveResp {
+    #[oai(status = 200)]
+    Ok,
+    #[oai(status = 500)]
+    Failed,
+}
+
+#[derive(Clone)]
+struct WebhookReceiverApi;
+
+/// Receives the webhook deliveries, failing the first `fail` attempts of each message
+#[poem_openapi::OpenApi]
+impl WebhookReceiverApi {
+    #[oai(path = "/receive", method = "post")]
+    async fn receive(
+        &self,
+        fail: Query<u32>,
+        #[oai(name = "Bios-Event-Timestamp")] timestamp: Header<String>,
+        #[oai(name = "Bios-Event-Signature")] signature: Header<String>,
+        body: Json<Value>,
+    ) -> WebhookReceiveResp {
+        let delivery: EventWebhookDelivery = TardisFuns::json.json_to_obj(body.0).expect("invalid delivery");
+        let body = TardisFuns::json.obj_to_string(&delivery).expect("invalid delivery");
+        let expected = TardisFuns::crypto.hex.encode(TardisFuns::crypto.digest.hmac_sha256(format!("{}.{body}", timestamp.0), WEBHOOK_SECRET).expect("fail to sign"));
+        assert_eq!(signature.0, format!("sha256={expected}"));
+        let attempts = {
+            let mut attempts = webhook_attempts().lock().expect("lock poisoned");
+            let count = attempts.entry(delivery.message_id.clone()).or_insert(0);
+            *count += 1;
+            *count
+        };
+        if attempts <= fail.0 {
+            return WebhookReceiveResp::Failed;
+        }
+        webhook_deliveries().lock().expect("lock poisoned").push(delivery);
+        WebhookReceiveResp::Ok
+    }
+}
+
+fn received_payloads() -> Vec<String> {
+    webhook_deliveries().lock().expect("lock poisoned").iter().map(|delivery| delivery.payload.clone()).collect()
+}
+
+pub async fn test_event_webhook_api() -> Result<(), Box<dyn std::error::Error>> {
+    const TEST_TOPIC_NAME: &str = "test-topic";
+    const TOPIC_CODE: TopicCode = TopicCode::const_new(TEST_TOPIC_NAME);
+    let mut client = TestHttpClient::new(format!("http://127.0.0.1:8080/{}", DOMAIN_CODE));
+    client.set_auth(test_tardis_context())?;
+    let client_node = bios_sdk_invoke::clients::event_client::mq_client_node();
+
+    // internal addresses are rejected unless allowed by config
+    for url in [
+        "http://10.0.0.1/hook",
+        "http://localhost:8080/webhook/receive",
+        "http://[::1]:8080/webhook/receive",
+        "ftp://example.com/hook",
+    ] {
+        let resp = client
+            .post_resp::<EventWebhookAddReq, String>(
+                &format!("/ci/topic/{TEST_TOPIC_NAME}/webhook"),
+                &EventWebhookAddReq {
+                    url: url.to_string(),
+                    secret: WEBHOOK_SECRET.to_string(),
+                    subjects: vec!["webhook_event".to_string()],
+                    enabled: None,
+                },
+            )
+            .await;
+        assert!(resp.code.starts_with("400"), "{url} should be rejected");
+    }
+
+    // create, the first attempt of each message fails and is retried
+    let webhook_id: String = client
+        .post(
+            &format!("/ci/topic/{TEST_TOPIC_NAME}/webhook"),
+            &EventWebhookAddReq {
+                url: "http://127.0.0.1:8080/webhook/receive?fail=1".to_string(),
+                secret: WEBHOOK_SECRET.to_string(),
+                subjects: vec!["webhook_event".to_string()],
+                enabled: None,
+            },
+        )
+        .await;
+    let webhooks: Vec<EventWebhookInfoResp> = client.get(&format!("/ci/topic/{TEST_TOPIC_NAME}/webhook")).await;
+    assert_eq!(webhooks.len(), 1);
+    assert_eq!(webhooks[0].ak, "test");
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    // deliver
+    client_node.send_message(EdgeMessage::builder(TOPIC_CODE, [Subject::const_new("webhook_event")], "webhook message 1").build()).await?;
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(received_payloads(), vec!["webhook message 1".to_string()]);
+    assert!(webhook_attempts().lock().expect("lock poisoned").values().all(|attempts| *attempts == 2));
+    let webhook: EventWebhookInfoResp = client.get(&format!("/ci/topic/{TEST_TOPIC_NAME}/webhook/{webhook_id}")).await;
+    assert_eq!(webhook.delivered_count, 1);
+    assert_eq!(webhook.failed_count, 0);
+
+    // messages of other subjects are not delivered
+    client_node.send_message(EdgeMessage::builder(TOPIC_CODE, [Subject::const_new("other_event")], "other message").build()).await?;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(received_payloads().len(), 1);
+
+    // failed after the configured attempts
+    let _: Void = client
+        .put(
+            &format!("/ci/topic/{TEST_TOPIC_NAME}/webhook/{webhook_id}"),
+            &EventWebhookModifyReq {
+                url: Some("http://127.0.0.1:8080/webhook/receive?fail=100".to_string()),
+                secret: None,
+                subjects: None,
+                enabled: None,
+            },
+        )
+        .await;
+    client_node.send_message(EdgeMessage::builder(TOPIC_CODE, [Subject::const_new("webhook_event")], "webhook message 2").build()).await?;
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(received_payloads().len(), 1);
+    let webhook: EventWebhookInfoResp = client.get(&format!("/ci/topic/{TEST_TOPIC_NAME}/webhook/{webhook_id}")).await;
+    assert_eq!(webhook.delivered_count, 1);
+    assert!(webhook.failed_count >= 1);
+    assert!(webhook.last_error.is_some());
+
+    // only the owner can change the subscription
+    let mut other_client = TestHttpClient::new(format!("http://127.0.0.1:8080/{}", DOMAIN_CODE));
+    other_client.set_auth(&TardisContext {
+        own_paths: "test".to_string(),
+        ak: "other".to_string(),
+        owner: "other".to_string(),
+        ..Default::default()
+    })?;
+    assert!(other_client.delete_resp(&format!("/ci/topic/{TEST_TOPIC_NAME}/webhook/{webhook_id}")).await.code.starts_with("403"));
+
+    // delete
+    client.delete(&format!("/ci/topic/{TEST_TOPIC_NAME}/webhook/{webhook_id}")).await;
+    let webhooks: Vec<EventWebhookInfoResp> = client.get(&format!("/ci/topic/{TEST_TOPIC_NAME}/webhook")).await;
+    assert!(webhooks.is_empty());
+    let attempts = webhook_attempts().lock().expect("lock poisoned").values().sum::<u32>();
+    client_node.send_message(EdgeMessage::builder(TOPIC_CODE, [Subject::const_new("webhook_event")], "webhook message 3").build()).await?;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(webhook_attempts().lock().expect("lock poisoned").values().sum::<u32>(), attempts);
+    Ok(())
+}
+
+pub async fn test_event_schema_api() -> Result<(), Box<dyn std::error::Error>> {
+    const TEST_TOPIC_NAME: &str = "test-topic";
+    const TOPIC_CODE: TopicCode = TopicCode::const_new(TEST_TOPIC_NAME);
+    let mut client = TestHttpClient::new(format!("http://127.0.0.1:8080/{}", DOMAIN_CODE));
+    client.set_auth(test_tardis_context())?;
+    let client_node = bios_sdk_invoke::clients::event_client::mq_client_node();
+    let received = std::sync::Arc::new(Mutex::new(Vec::<String>::new()));
+    let mut ep = client_node.create_endpoint(TOPIC_CODE, [Interest::new("order_created"), Interest::new("order_audited")]).await?;
+    let received_by_ep = received.clone();
+    tokio::spawn(async move {
+        while let Some(message) = ep.next_message().await {
+            received_by_ep.lock().expect("lock poisoned").push(message.text().expect("not utf-8"));
+            let _ = message.ack_processed().await;
+        }
+    });
+
+    // publish without schema is not checked
+    client_node.send_message(EdgeMessage::builder(TOPIC_CODE, [Subject::const_new("order_created")], "not json").build()).await?;
+
+    // register, closed so that properties can be added by the next versions
+    let schema_v1 = json!({
+        "type": "object",
+        "required": ["id"],
+        "properties": { "id": { "type": "string" } },
+        "additionalProperties": false
+    });
+    let schema: EventSchemaInfoResp = client
+        .post(
+            &format!("/ci/topic/{TEST_TOPIC_NAME}/schema/order_created"),
+            &EventSchemaAddReq {
+                schema: schema_v1.clone(),
+                on_invalid: None,
+            },
+        )
+        .await;
+    assert_eq!(schema.version, 1);
+    assert_eq!(schema.on_invalid, "reject");
+    let resp = client
+        .post_resp::<EventSchemaAddReq, EventSchemaInfoResp>(
+            &format!("/ci/topic/{TEST_TOPIC_NAME}/schema/order_audited"),
+            &EventSchemaAddReq {
+                schema: schema_v1.clone(),
+                on_invalid: Some("drop".to_string()),
+            },
+        )
+        .await;
+    assert!(resp.code.starts_with("400"));
+
+    // version compatibility
+    let schema_incompatible = json!({
+        "type": "object",
+        "required": ["id", "amount"],
+        "properties": { "id": { "type": "string" }, "amount": { "type": "number" } }
+    });
+    let compatibility: EventSchemaCompatibilityResp = client
+        .put(
+            &format!("/ci/topic/{TEST_TOPIC_NAME}/schema/order_created/compatibility"),
+            &EventSchemaAddReq {
+                schema: schema_incompatible.clone(),
+                on_invalid: None,
+            },
+        )
+        .await;
+    assert!(!compatibility.compatible);
+    assert!(!compatibility.incompatibilities.is_empty());
+    let resp = client
+        .post_resp::<EventSchemaAddReq, EventSchemaInfoResp>(
+            &format!("/ci/topic/{TEST_TOPIC_NAME}/schema/order_created"),
+            &EventSchemaAddReq {
+                schema: schema_incompatible,
+                on_invalid: None,
+            },
+        )
+        .await;
+    assert!(resp.code.starts_with("409"));
+    let schema_v2 = json!({
+        "type": "object",
+        "required": ["id"],
+        "properties": { "id": { "type": "string" }, "amount": { "type": "number" } }
+    });
+    let schema: EventSchemaInfoResp = client
+        .post(
+            &format!("/ci/topic/{TEST_TOPIC_NAME}/schema/order_created"),
+            &EventSchemaAddReq {
+                schema: schema_v2,
+                on_invalid: None,
+            },
+        )
+        .await;
+    assert_eq!(schema.version, 2);
+    let schema: EventSchemaInfoResp = client.get(&format!("/ci/topic/{TEST_TOPIC_NAME}/schema/order_created?version=1")).await;
+    assert_eq!(schema.schema, schema_v1);
+    let schemas: Vec<EventSchemaInfoResp> = client.get(&format!("/ci/topic/{TEST_TOPIC_NAME}/schema?event_code=order_created")).await;
+    assert_eq!(schemas.iter().map(|schema| schema.version).collect::<Vec<_>>(), vec![2, 1]);
+
+    // publish with schema
+    client_node.send_message(EdgeMessage::builder(TOPIC_CODE, [Subject::const_new("order_created")], r#"{"id":"o1","amount":1}"#).build()).await?;
+    assert!(client_node.send_message(EdgeMessage::builder(TOPIC_CODE, [Subject::const_new("order_created")], r#"{"amount":"1"}"#).build()).await.is_err());
+    assert!(client_node.send_message(EdgeMessage::builder(TOPIC_CODE, [Subject::const_new("order_created")], "not json").build()).await.is_err());
+
+    // invalid payloads of quarantined schemas are kept as dead letters
+    let _: EventSchemaInfoResp = client
+        .post(
+            &format!("/ci/topic/{TEST_TOPIC_NAME}/schema/order_audited"),
+            &EventSchemaAddReq {
+                schema: schema_v1,
+                on_invalid: Some("quarantine".to_string()),
+            },
+        )
+        .await;
+    assert!(client_node.send_message(EdgeMessage::builder(TOPIC_CODE, [Subject::const_new("order_audited")], r#"{"id":1}"#).build()).await.is_err());
+    let dead_letters: TardisPage<EventDeadLetterInfoResp> =
+        client.get(&format!("/ca/dead_letter?topic_code={TEST_TOPIC_NAME}&reason=invalid_payload&page_number=1&page_size=10")).await;
+    assert_eq!(dead_letters.total_size, 1);
+    assert_eq!(dead_letters.records[0].subjects, vec!["order_audited".to_string()]);
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(
+        *received.lock().expect("lock poisoned"),
+        vec!["not json".to_string(), r#"{"id":"o1","amount":1}"#.to_string()]
+    );
+    Ok(())
+}
+
+pub async fn test_event_dead_letter_api() -> Result<(), Box<dyn std::error::Error>> {
+    const TEST_TOPIC_NAME: &str = "test-topic";
+    const TOPIC_CODE: TopicCode = TopicCode::const_new(TEST_TOPIC_NAME);
+    let mut client = TestHttpClient::new(format!("http://127.0.0.1:8080/{}", DOMAIN_CODE));
+    client.set_auth(test_tardis_context())?;
+    let client_node = bios_sdk_invoke::clients::event_client::mq_client_node();
+    let durable_message = |subject: &'static str, payload: &str| {
+        EdgeMessage::builder(TOPIC_CODE, [Subject::const_new(subject)], payload)
+            .mode_durable(MessageDurableConfig {
+                expire: Utc::now() + TimeDelta::seconds(2),
+                max_receiver: None,
+            })
+            .with_ack(MessageAckExpectKind::Processed)
+            .build()
+    };
+
+    // the delivery fails once, which is `max_delivery_attempts` in the test config
+    let fail = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let received = std::sync::Arc::new(Mutex::new(Vec::<String>::new()));
+    let mut ep = client_node.create_endpoint(TOPIC_CODE, [Interest::new("dl_event")]).await?;
+    let (fail_by_ep, received_by_ep) = (fail.clone(), received.clone());
+    tokio::spawn(async move {
+        while let Some(message) = ep.next_message().await {
+            if fail_by_ep.load(std::sync::atomic::Ordering::SeqCst) {
+                let _ = message.ack_failed().await;
+            } else {
+                received_by_ep.lock().expect("lock poisoned").push(message.text().expect("not utf-8"));
+                let _ = message.ack_processed().await;
+            }
+        }
+    });
+    let _ = client_node.send_message(durable_message("dl_event", "dead letter message")).await;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let dead_letters: TardisPage<EventDeadLetterInfoResp> = client
+        .get(&format!(
+            "/ca/dead_letter?topic_code={TEST_TOPIC_NAME}&reason=max_delivery_attempts&page_number=1&page_size=10"
+        ))
+        .await;
+    let dead_letter = dead_letters.records.iter().find(|dead_letter| dead_letter.subjects == vec!["dl_event".to_string()]).expect("message not moved to dead-letter store");
+    let detail: EventDeadLetterDetailResp = client.get(&format!("/ca/dead_letter/detail?topic_code={TEST_TOPIC_NAME}&message_id={}", dead_letter.message_id)).await;
+    assert_eq!(detail.payload_text.as_deref(), Some("dead letter message"));
+    assert!(detail.delivery_failures >= 1);
+
+    // redelivery by replaying the dead letter
+    fail.store(false, std::sync::atomic::Ordering::SeqCst);
+    let _: Void = client
+        .put(
+            &format!("/ca/dead_letter/replay?topic_code={TEST_TOPIC_NAME}&message_id={}", dead_letter.message_id),
+            &Void {},
+        )
+        .await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(*received.lock().expect("lock poisoned"), vec!["dead letter message".to_string()]);
+    let resp = client.get_resp::<EventDeadLetterDetailResp>(&format!("/ca/dead_letter/detail?topic_code={TEST_TOPIC_NAME}&message_id={}", dead_letter.message_id)).await;
+    assert!(resp.code.starts_with("404"));
+
+    // expiry, nobody is interested in the subject so the message is never acknowledged
+    let _ = client_node.send_message(durable_message("dl_nobody", "expired message")).await;
+    tokio::time::sleep(Duration::from_secs(4)).await;
+    let dead_letters: TardisPage<EventDeadLetterInfoResp> = client.get(&format!("/ca/dead_letter?topic_code={TEST_TOPIC_NAME}&reason=expired&page_number=1&page_size=10")).await;
+    assert!(dead_letters.records.iter().any(|dead_letter| dead_letter.subjects == vec!["dl_nobody".to_string()]));
+
+    Ok(())
+}
+
+pub async fn test_event_message_api() -> Result<(), Box<dyn std::error::Error>> {
+    const TEST_TOPIC_NAME: &str = "test-topic";
+    let mut client = TestHttpClient::new(format!("http://127.0.0.1:8080/{}", DOMAIN_CODE));
+    client.set_auth(test_tardis_context())?;
+    let messages: TardisPage<EventMessageInfoResp> = client.get(&format!("/ci/message/paged?topic_code={TEST_TOPIC_NAME}&page_number=1&page_size=2")).await;
+    assert_eq!(messages.page_size, 2);
+    assert!(messages.total_size >= 1);
+    assert!(messages.records.len() <= 2);
+    // page size is clamped
+    let messages: TardisPage<EventMessageInfoResp> = client.get(&format!("/ci/message/paged?topic_code={TEST_TOPIC_NAME}&page_number=1&page_size=1000000")).await;
+    assert_eq!(messages.page_size, 500);
+    let messages: TardisPage<EventMessageInfoResp> = client.get(&format!("/ci/message/paged?topic_code={TEST_TOPIC_NAME}&page_number=1&page_size=0")).await;
+    assert_eq!(messages.page_size, 1);
+    assert!(messages.records.len() <= 1);
+    Ok(())
+}
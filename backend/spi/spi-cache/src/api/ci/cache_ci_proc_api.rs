@@ -6,7 +6,10 @@ use tardis::web::poem_openapi;
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
-use crate::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+use crate::dto::cache_proc_dto::{
+    ExpReq, KIncrReq, KLockReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KmIncrReq, KmReq, KmsReq, KtLeaseReq, KtReq, KvReq, KvWithExReq, KzRangeReq,
+    KzScoreRangeReq, LockResp, ZMemberResp,
+};
 use crate::serv::cache_proc_serv;
 #[derive(Clone)]
 pub struct CacheCiProcApi;
@@ -209,4 +212,95 @@ impl CacheCiProcApi {
         let funs = crate::get_tardis_inst();
         TardisResp::ok(cache_proc_serv::bitcount_range_by_bit(&req.0, &funs, &ctx.0).await?)
     }
+
+    /// zadd
+    #[oai(path = "/zadd", method = "put")]
+    async fn zadd(&self, req: Json<KmsReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zadd(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zincr
+    #[oai(path = "/zincr", method = "post")]
+    async fn zincr(&self, req: Json<KmIncrReq>, ctx: TardisContextExtractor) -> TardisApiResult<f64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zincr(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrem
+    #[oai(path = "/zrem", method = "put")]
+    async fn zrem(&self, req: Json<KmReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrem(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zscore
+    #[oai(path = "/zscore", method = "put")]
+    async fn zscore(&self, req: Json<KmReq>, ctx: TardisContextExtractor) -> TardisApiResult<Option<f64>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zscore(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrank
+    #[oai(path = "/zrank", method = "put")]
+    async fn zrank(&self, req: Json<KmReq>, ctx: TardisContextExtractor) -> TardisApiResult<Option<u64>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrank(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrevrank
+    #[oai(path = "/zrevrank", method = "put")]
+    async fn zrevrank(&self, req: Json<KmReq>, ctx: TardisContextExtractor) -> TardisApiResult<Option<u64>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrevrank(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zcard
+    #[oai(path = "/zcard", method = "put")]
+    async fn zcard(&self, req: Json<KReq>, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zcard(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrange, by rank
+    #[oai(path = "/zrange", method = "put")]
+    async fn zrange(&self, req: Json<KzRangeReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<ZMemberResp>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrange(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrangebyscore
+    #[oai(path = "/zrangebyscore", method = "put")]
+    async fn zrangebyscore(&self, req: Json<KzScoreRangeReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<ZMemberResp>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrangebyscore(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// publish, key is the channel
+    #[oai(path = "/publish", method = "post")]
+    async fn publish(&self, req: Json<KvReq>, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::publish(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// lock_acquire
+    #[oai(path = "/lock/acquire", method = "post")]
+    async fn lock_acquire(&self, req: Json<KLockReq>, ctx: TardisContextExtractor) -> TardisApiResult<LockResp> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::lock_acquire(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// lock_renew
+    #[oai(path = "/lock/renew", method = "post")]
+    async fn lock_renew(&self, req: Json<KtLeaseReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::lock_renew(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// lock_release
+    #[oai(path = "/lock/release", method = "put")]
+    async fn lock_release(&self, req: Json<KtReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::lock_release(&req.0, &funs, &ctx.0).await?)
+    }
 }
@@ -1,2 +1,4 @@
 pub const DOMAIN_CODE: &str = "spi-cache";
 pub const SPI_REDIS_KIND_CODE: &str = "spi-bs-redis";
+/// Prefix of the keys holding the state of the service (e.g. the locks), refused by the key-value operations
+pub const RESERVED_KEY_PREFIX: &str = "__spi_cache__:";
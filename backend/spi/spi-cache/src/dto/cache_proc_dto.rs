@@ -84,3 +84,96 @@ pub struct KbRangeReq {
     pub start: u32,
     pub end: u32,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KmReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    #[oai(validator(min_length = "1"))]
+    pub member: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KmsReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    #[oai(validator(min_length = "1"))]
+    pub member: String,
+    pub score: f64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KmIncrReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    #[oai(validator(min_length = "1"))]
+    pub member: String,
+    pub delta: f64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KzRangeReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    /// Start rank (inclusive), negative numbers count from the end
+    pub start: i64,
+    /// Stop rank (inclusive), negative numbers count from the end
+    pub stop: i64,
+    /// Order by score descending
+    pub rev: Option<bool>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KzScoreRangeReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    /// Min score (inclusive), unbounded if absent
+    pub min: Option<f64>,
+    /// Max score (inclusive), unbounded if absent
+    pub max: Option<f64>,
+    pub offset: Option<u32>,
+    pub count: Option<u32>,
+    /// Order by score descending
+    pub rev: Option<bool>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ZMemberResp {
+    pub member: String,
+    pub score: f64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KLockReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    #[oai(validator(minimum(value = "1")))]
+    pub lease_ms: u64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KtReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    #[oai(validator(min_length = "1"))]
+    pub token: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KtLeaseReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    #[oai(validator(min_length = "1"))]
+    pub token: String,
+    #[oai(validator(minimum(value = "1")))]
+    pub lease_ms: u64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LockResp {
+    pub acquired: bool,
+    /// Holder token, required to renew or release the lock
+    pub token: Option<String>,
+    /// Monotonically increasing per lock key, pass it to the protected resource to reject stale holders
+    pub fencing_token: Option<u64>,
+}
@@ -44,5 +44,18 @@ spi_dispatch_service! {
         getbit(req: &KbReq) -> TardisResult<bool>;
         bitcount(req: &KReq) -> TardisResult<u32>;
         bitcount_range_by_bit(req: &KbRangeReq) -> TardisResult<u32>;
+        zadd(req: &KmsReq) -> TardisResult<bool>;
+        zincr(req: &KmIncrReq) -> TardisResult<f64>;
+        zrem(req: &KmReq) -> TardisResult<bool>;
+        zscore(req: &KmReq) -> TardisResult<Option<f64>>;
+        zrank(req: &KmReq) -> TardisResult<Option<u64>>;
+        zrevrank(req: &KmReq) -> TardisResult<Option<u64>>;
+        zcard(req: &KReq) -> TardisResult<u64>;
+        zrange(req: &KzRangeReq) -> TardisResult<Vec<ZMemberResp>>;
+        zrangebyscore(req: &KzScoreRangeReq) -> TardisResult<Vec<ZMemberResp>>;
+        publish(req: &KvReq) -> TardisResult<u64>;
+        lock_acquire(req: &KLockReq) -> TardisResult<LockResp>;
+        lock_renew(req: &KtLeaseReq) -> TardisResult<bool>;
+        lock_release(req: &KtReq) -> TardisResult<bool>;
     }
 }
@@ -2,14 +2,29 @@ use std::collections::HashMap;
 
 use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common};
 use tardis::{
-    basic::{dto::TardisContext, result::TardisResult},
-    cache::cache_client::TardisCacheClient,
-    TardisFunsInst,
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    cache::{cache_client::TardisCacheClient, AsyncCommands},
+    TardisFuns, TardisFunsInst,
 };
 
-use crate::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+use crate::cache_constants::RESERVED_KEY_PREFIX;
+use crate::dto::cache_proc_dto::{
+    ExpReq, KIncrReq, KLockReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KmIncrReq, KmReq, KmsReq, KtLeaseReq, KtReq, KvReq, KvWithExReq, KzRangeReq,
+    KzScoreRangeReq, LockResp, ZMemberResp,
+};
+
+/// Namespaced key of a request, keys with the [`RESERVED_KEY_PREFIX`] are refused as they hold the state of the service, e.g. the locks
+pub(crate) fn format_key(req_key: &str, ext: &HashMap<String, String>) -> TardisResult<String> {
+    if req_key.starts_with(RESERVED_KEY_PREFIX) {
+        return Err(TardisError::bad_request(
+            &format!("key [{req_key}] uses the reserved prefix [{RESERVED_KEY_PREFIX}]"),
+            "400-spi-cache-reserved-key",
+        ));
+    }
+    Ok(namespaced_key(req_key, ext))
+}
 
-pub(crate) fn format_key(req_key: &str, ext: &HashMap<String, String>) -> String {
+fn namespaced_key(req_key: &str, ext: &HashMap<String, String>) -> String {
     if let Some(key_prefix) = common::get_isolation_flag_from_ext(ext) {
         format!("{key_prefix}{req_key}")
     } else {
@@ -19,141 +34,280 @@ pub(crate) fn format_key(req_key: &str, ext: &HashMap<String, String>) -> String
 
 pub async fn set(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.set(&format_key(&req.key, bs_inst.1), &req.value).await?)
+    Ok(bs_inst.0.set(&format_key(&req.key, bs_inst.1)?, &req.value).await?)
 }
 
 pub async fn set_ex(req: &KvWithExReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.set_ex(&format_key(&req.key, bs_inst.1), &req.value, req.exp_sec).await?)
+    Ok(bs_inst.0.set_ex(&format_key(&req.key, bs_inst.1)?, &req.value, req.exp_sec).await?)
 }
 
 pub async fn set_nx(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.set_nx(&format_key(&req.key, bs_inst.1), &req.value).await?)
+    Ok(bs_inst.0.set_nx(&format_key(&req.key, bs_inst.1)?, &req.value).await?)
 }
 
 pub async fn get(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.get(&format_key(&req.key, bs_inst.1)).await?)
+    Ok(bs_inst.0.get(&format_key(&req.key, bs_inst.1)?).await?)
 }
 
 pub async fn getset(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.getset(&format_key(&req.key, bs_inst.1), &req.value).await?)
+    Ok(bs_inst.0.getset(&format_key(&req.key, bs_inst.1)?, &req.value).await?)
 }
 
 pub async fn incr(req: &KIncrReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<i64> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.incr(&format_key(&req.key, bs_inst.1), req.delta as isize).await? as i64)
+    Ok(bs_inst.0.incr(&format_key(&req.key, bs_inst.1)?, req.delta as isize).await? as i64)
 }
 
 pub async fn del(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.del(&format_key(&req.key, bs_inst.1)).await?)
+    Ok(bs_inst.0.del(&format_key(&req.key, bs_inst.1)?).await?)
 }
 
 pub async fn exists(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.exists(&format_key(&req.key, bs_inst.1)).await?)
+    Ok(bs_inst.0.exists(&format_key(&req.key, bs_inst.1)?).await?)
 }
 
 pub async fn expire(req: &ExpReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.expire(&format_key(&req.key, bs_inst.1), req.exp_sec as i64).await?)
+    Ok(bs_inst.0.expire(&format_key(&req.key, bs_inst.1)?, req.exp_sec as i64).await?)
 }
 
 pub async fn ttl(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.ttl(&format_key(&req.key, bs_inst.1)).await? as u64)
+    Ok(bs_inst.0.ttl(&format_key(&req.key, bs_inst.1)?).await? as u64)
 }
 
 // list operations
 
 pub async fn lpush(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.lpush(&format_key(&req.key, bs_inst.1), &req.value).await?)
+    Ok(bs_inst.0.lpush(&format_key(&req.key, bs_inst.1)?, &req.value).await?)
 }
 
 pub async fn lrangeall(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.lrangeall(&format_key(&req.key, bs_inst.1)).await?)
+    Ok(bs_inst.0.lrangeall(&format_key(&req.key, bs_inst.1)?).await?)
 }
 
 pub async fn llen(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.llen(&format_key(&req.key, bs_inst.1)).await? as u64)
+    Ok(bs_inst.0.llen(&format_key(&req.key, bs_inst.1)?).await? as u64)
 }
 
 // hash operations
 
 pub async fn hget(req: &KfReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hget(&format_key(&req.key, bs_inst.1), &req.field).await?)
+    Ok(bs_inst.0.hget(&format_key(&req.key, bs_inst.1)?, &req.field).await?)
 }
 
 pub async fn hset(req: &KfvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hset(&format_key(&req.key, bs_inst.1), &req.field, &req.value).await?)
+    Ok(bs_inst.0.hset(&format_key(&req.key, bs_inst.1)?, &req.field, &req.value).await?)
 }
 
 pub async fn hset_nx(req: &KfvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hset_nx(&format_key(&req.key, bs_inst.1), &req.field, &req.value).await?)
+    Ok(bs_inst.0.hset_nx(&format_key(&req.key, bs_inst.1)?, &req.field, &req.value).await?)
 }
 
 pub async fn hdel(req: &KfReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hdel(&format_key(&req.key, bs_inst.1), &req.field).await?)
+    Ok(bs_inst.0.hdel(&format_key(&req.key, bs_inst.1)?, &req.field).await?)
 }
 
 pub async fn hincr(req: &KfIncrReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<i64> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hincr(&format_key(&req.key, bs_inst.1), &req.field, req.delta as isize).await? as i64)
+    Ok(bs_inst.0.hincr(&format_key(&req.key, bs_inst.1)?, &req.field, req.delta as isize).await? as i64)
 }
 
 pub async fn hexists(req: &KfReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hexists(&format_key(&req.key, bs_inst.1), &req.field).await?)
+    Ok(bs_inst.0.hexists(&format_key(&req.key, bs_inst.1)?, &req.field).await?)
 }
 
 pub async fn hkeys(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hkeys(&format_key(&req.key, bs_inst.1)).await?)
+    Ok(bs_inst.0.hkeys(&format_key(&req.key, bs_inst.1)?).await?)
 }
 
 pub async fn hvals(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hvals(&format_key(&req.key, bs_inst.1)).await?)
+    Ok(bs_inst.0.hvals(&format_key(&req.key, bs_inst.1)?).await?)
 }
 
 pub async fn hgetall(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<HashMap<String, String>> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hgetall(&format_key(&req.key, bs_inst.1)).await?)
+    Ok(bs_inst.0.hgetall(&format_key(&req.key, bs_inst.1)?).await?)
 }
 
 pub async fn hlen(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.hlen(&format_key(&req.key, bs_inst.1)).await? as u64)
+    Ok(bs_inst.0.hlen(&format_key(&req.key, bs_inst.1)?).await? as u64)
 }
 
 // bitmap operations
 
 pub async fn setbit(req: &KbvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.setbit(&format_key(&req.key, bs_inst.1), req.offset as usize, req.value).await?)
+    Ok(bs_inst.0.setbit(&format_key(&req.key, bs_inst.1)?, req.offset as usize, req.value).await?)
 }
 
 pub async fn getbit(req: &KbReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.getbit(&format_key(&req.key, bs_inst.1), req.offset as usize).await?)
+    Ok(bs_inst.0.getbit(&format_key(&req.key, bs_inst.1)?, req.offset as usize).await?)
 }
 
 pub async fn bitcount(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u32> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.bitcount(&format_key(&req.key, bs_inst.1)).await? as u32)
+    Ok(bs_inst.0.bitcount(&format_key(&req.key, bs_inst.1)?).await? as u32)
 }
 
 pub async fn bitcount_range_by_bit(req: &KbRangeReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u32> {
     let bs_inst = inst.inst::<TardisCacheClient>();
-    Ok(bs_inst.0.bitcount_range_by_bit(&format_key(&req.key, bs_inst.1), req.start as usize, req.end as usize).await? as u32)
+    Ok(bs_inst.0.bitcount_range_by_bit(&format_key(&req.key, bs_inst.1)?, req.start as usize, req.end as usize).await? as u32)
+}
+
+// sorted set operations
+
+pub async fn zadd(req: &KmsReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let added: u64 = bs_inst.0.cmd().await?.zadd(format_key(&req.key, bs_inst.1)?, &req.member, req.score).await?;
+    Ok(added > 0)
+}
+
+pub async fn zincr(req: &KmIncrReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<f64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    Ok(bs_inst.0.cmd().await?.zincr(format_key(&req.key, bs_inst.1)?, &req.member, req.delta).await?)
+}
+
+pub async fn zrem(req: &KmReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let removed: u64 = bs_inst.0.cmd().await?.zrem(format_key(&req.key, bs_inst.1)?, &req.member).await?;
+    Ok(removed > 0)
+}
+
+pub async fn zscore(req: &KmReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<f64>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    Ok(bs_inst.0.cmd().await?.zscore(format_key(&req.key, bs_inst.1)?, &req.member).await?)
+}
+
+pub async fn zrank(req: &KmReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<u64>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    Ok(bs_inst.0.cmd().await?.zrank(format_key(&req.key, bs_inst.1)?, &req.member).await?)
+}
+
+pub async fn zrevrank(req: &KmReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<u64>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    Ok(bs_inst.0.cmd().await?.zrevrank(format_key(&req.key, bs_inst.1)?, &req.member).await?)
+}
+
+pub async fn zcard(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    Ok(bs_inst.0.cmd().await?.zcard(format_key(&req.key, bs_inst.1)?).await?)
+}
+
+pub async fn zrange(req: &KzRangeReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<ZMemberResp>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut cmd = bs_inst.0.cmd().await?;
+    let key = format_key(&req.key, bs_inst.1)?;
+    let members: Vec<(String, f64)> = if req.rev.unwrap_or(false) {
+        cmd.zrevrange_withscores(key, req.start as isize, req.stop as isize).await?
+    } else {
+        cmd.zrange_withscores(key, req.start as isize, req.stop as isize).await?
+    };
+    Ok(members.into_iter().map(|(member, score)| ZMemberResp { member, score }).collect())
+}
+
+pub async fn zrangebyscore(req: &KzScoreRangeReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<ZMemberResp>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut cmd = bs_inst.0.cmd().await?;
+    let key = format_key(&req.key, bs_inst.1)?;
+    let min = req.min.map(|min| min.to_string()).unwrap_or_else(|| "-inf".to_string());
+    let max = req.max.map(|max| max.to_string()).unwrap_or_else(|| "+inf".to_string());
+    let offset = req.offset.unwrap_or(0) as isize;
+    // a negative count returns all the members from the offset
+    let count = req.count.map(|count| count as isize).unwrap_or(-1);
+    let members: Vec<(String, f64)> = if req.rev.unwrap_or(false) {
+        cmd.zrevrangebyscore_limit_withscores(key, max, min, offset, count).await?
+    } else {
+        cmd.zrangebyscore_limit_withscores(key, min, max, offset, count).await?
+    };
+    Ok(members.into_iter().map(|(member, score)| ZMemberResp { member, score }).collect())
+}
+
+// pub/sub operations
+
+/// Publish a message to the (tenant namespaced) channel, returns the number of subscribers that received it.
+pub async fn publish(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    Ok(bs_inst.0.cmd().await?.publish(format_key(&req.key, bs_inst.1)?, &req.value).await?)
+}
+
+// lock operations
+
+/// Acquire the lock if it is free, the fencing token is bumped on every successful acquire.
+const LOCK_ACQUIRE_SCRIPT: &str = r#"
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+    return redis.call('INCR', KEYS[2])
+end
+return 0
+"#;
+
+const LOCK_RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+const LOCK_RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+fn lock_key(req_key: &str, ext: &HashMap<String, String>) -> String {
+    namespaced_key(&format!("{RESERVED_KEY_PREFIX}lock:{req_key}"), ext)
+}
+
+fn fencing_key(req_key: &str, ext: &HashMap<String, String>) -> String {
+    namespaced_key(&format!("{RESERVED_KEY_PREFIX}lock:{req_key}:fencing"), ext)
+}
+
+pub async fn lock_acquire(req: &KLockReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<LockResp> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let token = TardisFuns::field.nanoid();
+    let fencing_token: u64 =
+        bs_inst.0.script(LOCK_ACQUIRE_SCRIPT).key(lock_key(&req.key, bs_inst.1)).key(fencing_key(&req.key, bs_inst.1)).arg(&token).arg(req.lease_ms).invoke().await?;
+    if fencing_token == 0 {
+        return Ok(LockResp {
+            acquired: false,
+            token: None,
+            fencing_token: None,
+        });
+    }
+    Ok(LockResp {
+        acquired: true,
+        token: Some(token),
+        fencing_token: Some(fencing_token),
+    })
+}
+
+pub async fn lock_renew(req: &KtLeaseReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let renewed: u64 = bs_inst.0.script(LOCK_RENEW_SCRIPT).key(lock_key(&req.key, bs_inst.1)).arg(&req.token).arg(req.lease_ms).invoke().await?;
+    Ok(renewed > 0)
+}
+
+pub async fn lock_release(req: &KtReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let released: u64 = bs_inst.0.script(LOCK_RELEASE_SCRIPT).key(lock_key(&req.key, bs_inst.1)).arg(&req.token).invoke().await?;
+    Ok(released > 0)
 }
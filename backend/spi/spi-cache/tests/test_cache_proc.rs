@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_cache::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+use bios_spi_cache::dto::cache_proc_dto::{
+    ExpReq, KIncrReq, KLockReq, KReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KmIncrReq, KmReq, KmsReq, KtLeaseReq, KtReq, KvReq, KvWithExReq, KzRangeReq, KzScoreRangeReq,
+    LockResp, ZMemberResp,
+};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::log::info;
@@ -325,5 +328,182 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     let result: u32 = client.put("/ci/proc/bitcount", &KReq { key: "k_bitmap".into() }).await;
     assert_eq!(result, 2);
 
+    info!("【test_cache_sorted_set】");
+    for (member, score) in [("m1", 10.0), ("m2", 30.0), ("m3", 20.0)] {
+        let result: bool = client
+            .put(
+                "/ci/proc/zadd",
+                &KmsReq {
+                    key: "k_zset".into(),
+                    member: member.to_string(),
+                    score,
+                },
+            )
+            .await;
+        assert!(result);
+    }
+    let result: f64 = client
+        .post(
+            "/ci/proc/zincr",
+            &KmIncrReq {
+                key: "k_zset".into(),
+                member: "m1".to_string(),
+                delta: 25.0,
+            },
+        )
+        .await;
+    assert_eq!(result, 35.0);
+    let result: Option<u64> = client
+        .put(
+            "/ci/proc/zrevrank",
+            &KmReq {
+                key: "k_zset".into(),
+                member: "m1".to_string(),
+            },
+        )
+        .await;
+    assert_eq!(result, Some(0));
+    let result: Vec<ZMemberResp> = client
+        .put(
+            "/ci/proc/zrange",
+            &KzRangeReq {
+                key: "k_zset".into(),
+                start: 0,
+                stop: 1,
+                rev: Some(true),
+            },
+        )
+        .await;
+    assert_eq!(result.iter().map(|m| m.member.as_str()).collect::<Vec<_>>(), vec!["m1", "m2"]);
+    let result: Vec<ZMemberResp> = client
+        .put(
+            "/ci/proc/zrangebyscore",
+            &KzScoreRangeReq {
+                key: "k_zset".into(),
+                min: Some(20.0),
+                max: None,
+                offset: None,
+                count: Some(2),
+                rev: None,
+            },
+        )
+        .await;
+    assert_eq!(result.iter().map(|m| (m.member.as_str(), m.score)).collect::<Vec<_>>(), vec![("m3", 20.0), ("m2", 30.0)]);
+    let result: bool = client
+        .put(
+            "/ci/proc/zrem",
+            &KmReq {
+                key: "k_zset".into(),
+                member: "m3".to_string(),
+            },
+        )
+        .await;
+    assert!(result);
+    let result: u64 = client.put("/ci/proc/zcard", &KReq { key: "k_zset".into() }).await;
+    assert_eq!(result, 2);
+    let result: TardisResp<Option<f64>> = client
+        .put_resp(
+            "/ci/proc/zscore",
+            &KmReq {
+                key: "k_zset".into(),
+                member: "m3".to_string(),
+            },
+        )
+        .await;
+    assert_eq!(result.data, None);
+
+    info!("【test_cache_publish】");
+    let result: u64 = client
+        .post(
+            "/ci/proc/publish",
+            &KvReq {
+                key: "channel".into(),
+                value: "hello".to_string(),
+            },
+        )
+        .await;
+    assert_eq!(result, 0);
+
+    info!("【test_cache_lock】");
+    let lock: LockResp = client
+        .post(
+            "/ci/proc/lock/acquire",
+            &KLockReq {
+                key: "k_lock".into(),
+                lease_ms: 1000,
+            },
+        )
+        .await;
+    assert!(lock.acquired);
+    let token = lock.token.unwrap();
+    let fencing_token = lock.fencing_token.unwrap();
+    let result: LockResp = client
+        .post(
+            "/ci/proc/lock/acquire",
+            &KLockReq {
+                key: "k_lock".into(),
+                lease_ms: 1000,
+            },
+        )
+        .await;
+    assert!(!result.acquired);
+    let result: bool = client
+        .post(
+            "/ci/proc/lock/renew",
+            &KtLeaseReq {
+                key: "k_lock".into(),
+                token: "other".to_string(),
+                lease_ms: 1000,
+            },
+        )
+        .await;
+    assert!(!result);
+    let result: bool = client
+        .post(
+            "/ci/proc/lock/renew",
+            &KtLeaseReq {
+                key: "k_lock".into(),
+                token: token.clone(),
+                lease_ms: 1000,
+            },
+        )
+        .await;
+    assert!(result);
+    let result: bool = client.put("/ci/proc/lock/release", &KtReq { key: "k_lock".into(), token }).await;
+    assert!(result);
+    let result: LockResp = client
+        .post(
+            "/ci/proc/lock/acquire",
+            &KLockReq {
+                key: "k_lock".into(),
+                lease_ms: 200,
+            },
+        )
+        .await;
+    assert!(result.acquired);
+    assert!(result.fencing_token.unwrap() > fencing_token);
+    // the lease expires without release
+    tardis::tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    let result: LockResp = client
+        .post(
+            "/ci/proc/lock/acquire",
+            &KLockReq {
+                key: "k_lock".into(),
+                lease_ms: 200,
+            },
+        )
+        .await;
+    assert!(result.acquired);
+    // the lock keys are out of reach of the key-value operations
+    let result: TardisResp<Option<String>> = client
+        .put_resp(
+            "/ci/proc/get",
+            &KReq {
+                key: "__spi_cache__:lock:k_lock".into(),
+            },
+        )
+        .await;
+    assert_eq!(result.code, "400-spi-cache-reserved-key");
+
     Ok(())
 }
@@ -34,12 +34,13 @@ pub async fn init_nacos_servers(cfg: &ConfConfig) -> TardisResult<()> {
     grpc_server
         .add_grpc_route(
             WebServerGrpcModule::default()
-                .with_grpc_service(BiRequestStreamGrpcServer::new(BiRequestStreamProtoImpl))
-                .with_grpc_service(RequestGrpcServer::new(RequestProtoImpl))
+                .with_grpc_service(PeerAddrService(BiRequestStreamGrpcServer::new(BiRequestStreamProtoImpl)))
+                .with_grpc_service(PeerAddrService(RequestGrpcServer::new(RequestProtoImpl)))
                 .with_descriptor(NACOS_GRPC_SERVICE_DESCRIPTOR.to_vec()),
         )
         .await;
     http_server.add_route(nacos_module).await;
+    start_push_task();
 
     HTTP_SERVER.get_or_init(|| http_server).start().await?;
     GRPC_SERVER.get_or_init(|| grpc_server).start().await?;
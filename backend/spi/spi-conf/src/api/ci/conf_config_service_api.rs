@@ -1,18 +1,25 @@
+use bios_basic::helper::request_helper::tardis_err_to_poem_err;
 use tardis::{
     basic::error::TardisError,
+    chrono::Utc,
     db::sea_orm::prelude::Uuid,
     web::{
         context_extractor::TardisContextExtractor,
-        poem::web::RealIp,
-        poem_openapi::{self, param::Query, payload::Json},
+        poem::{self, web::RealIp},
+        poem_openapi::{
+            self,
+            param::Query,
+            payload::{Attachment, Binary, Json},
+        },
         web_resp::{TardisApiResult, TardisResp, Void},
     },
 };
 
 use crate::{conf_constants::error, serv::*};
 use crate::{
-    dto::{conf_config_dto::*, conf_namespace_dto::*},
-    serv::placeholder::render_content_for_ip,
+    dto::{conf_config_dto::*, conf_config_transfer_dto::*, conf_namespace_dto::*},
+    serv::{placeholder::render_content_for_ip, transfer},
+    utils::{parse_labels, parse_tags},
 };
 
 #[derive(Default, Clone, Copy, Debug)]
@@ -35,6 +42,8 @@ impl ConfCiConfigServiceApi {
         tag: Query<Option<String>>,
         /// 配置类型
         r#type: Query<Option<String>>,
+        /// 客户端标签，格式为 `k1=v1,k2=v2`，用于匹配灰度规则
+        labels: Query<Option<String>>,
         ctx: TardisContextExtractor,
         real_ip: RealIp,
     ) -> TardisApiResult<String> {
@@ -48,7 +57,11 @@ impl ConfCiConfigServiceApi {
             tp: r#type.0,
         };
         let funs = crate::get_tardis_inst();
-        let mut content = get_config(&mut descriptor, &funs, &ctx.0).await?;
+        let client = ConfigClient {
+            ip: real_ip.0,
+            labels: labels.0.as_deref().map(parse_labels).unwrap_or_default(),
+        };
+        let mut content = get_config_for_client(&mut descriptor, &client, &funs, &ctx.0).await?.content;
         content = render_content_for_ip(&descriptor, content, real_ip.0, &funs, &ctx.0).await?;
 
         TardisResp::ok(content)
@@ -66,6 +79,8 @@ impl ConfCiConfigServiceApi {
         tag: Query<Option<String>>,
         /// 配置类型
         r#type: Query<Option<String>>,
+        /// 客户端标签，格式为 `k1=v1,k2=v2`，用于匹配灰度规则
+        labels: Query<Option<String>>,
         ctx: TardisContextExtractor,
         real_ip: RealIp,
     ) -> TardisApiResult<ConfigItem> {
@@ -79,7 +94,11 @@ impl ConfCiConfigServiceApi {
             tp: r#type.0,
         };
         let funs = crate::get_tardis_inst();
-        let mut config_item = get_config_detail(&mut descriptor, &funs, &ctx.0).await?;
+        let client = ConfigClient {
+            ip: real_ip.0,
+            labels: labels.0.as_deref().map(parse_labels).unwrap_or_default(),
+        };
+        let mut config_item = get_config_for_client(&mut descriptor, &client, &funs, &ctx.0).await?;
         config_item.content = render_content_for_ip(&descriptor, config_item.content, real_ip.0, &funs, &ctx.0).await?;
         config_item.md5 = gen_md5(&config_item.content);
 
@@ -114,6 +133,87 @@ impl ConfCiConfigServiceApi {
         delete_config(&mut descriptor, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
+    /// Publish a beta (gray) version of config, only the clients matching the rule get it
+    ///
+    /// 发布灰度配置，仅匹配规则的客户端获取该配置
+    #[oai(path = "/config/beta", method = "post")]
+    async fn publish_beta_config(&self, mut publish_request: Json<ConfigBetaPublishRequest>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        let result = publish_beta_config(&mut publish_request.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+    #[oai(path = "/config/beta", method = "get")]
+    async fn get_beta_config(
+        &self,
+        tenant: Query<Option<NamespaceId>>,
+        namespace_id: Query<Option<NamespaceId>>,
+        /// 配置分组名
+        group: Query<String>,
+        /// 配置名
+        data_id: Query<String>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Option<ConfigBetaItem>> {
+        let namespace_id = namespace_id.0.or(tenant.0).unwrap_or("public".into());
+        let mut descriptor = ConfigDescriptor {
+            namespace_id,
+            group: group.0,
+            data_id: data_id.0,
+            ..Default::default()
+        };
+        let funs = crate::get_tardis_inst();
+        let beta = get_beta_config(&mut descriptor, &funs, &ctx.0).await?;
+        TardisResp::ok(beta)
+    }
+    /// Promote the beta version to everyone
+    ///
+    /// 全量发布灰度配置
+    #[oai(path = "/config/beta/promote", method = "put")]
+    async fn promote_beta_config(
+        &self,
+        tenant: Query<Option<NamespaceId>>,
+        namespace_id: Query<Option<NamespaceId>>,
+        /// 配置分组名
+        group: Query<String>,
+        /// 配置名
+        data_id: Query<String>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<bool> {
+        let namespace_id = namespace_id.0.or(tenant.0).unwrap_or("public".into());
+        let mut descriptor = ConfigDescriptor {
+            namespace_id,
+            group: group.0,
+            data_id: data_id.0,
+            ..Default::default()
+        };
+        let funs = crate::get_tardis_inst();
+        let result = promote_beta_config(&mut descriptor, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+    /// Abandon the beta version
+    ///
+    /// 放弃灰度配置
+    #[oai(path = "/config/beta", method = "delete")]
+    async fn abandon_beta_config(
+        &self,
+        tenant: Query<Option<NamespaceId>>,
+        namespace_id: Query<Option<NamespaceId>>,
+        /// 配置分组名
+        group: Query<String>,
+        /// 配置名
+        data_id: Query<String>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<bool> {
+        let namespace_id = namespace_id.0.or(tenant.0).unwrap_or("public".into());
+        let mut descriptor = ConfigDescriptor {
+            namespace_id,
+            group: group.0,
+            data_id: data_id.0,
+            ..Default::default()
+        };
+        let funs = crate::get_tardis_inst();
+        let result = abandon_beta_config(&mut descriptor, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
     #[oai(path = "/configs/listener", method = "get")]
     async fn listener(
         &self,
@@ -125,6 +225,8 @@ impl ConfCiConfigServiceApi {
         /// 配置名
         data_id: Query<String>,
         md5: Query<Option<String>>,
+        /// 客户端标签，格式为 `k1=v1,k2=v2`，用于匹配灰度规则
+        labels: Query<Option<String>>,
         ctx: TardisContextExtractor,
         real_ip: RealIp,
     ) -> TardisApiResult<Option<ConfigDescriptor>> {
@@ -140,7 +242,11 @@ impl ConfCiConfigServiceApi {
         };
         let md5 = md5.0.unwrap_or_default();
         let funs = crate::get_tardis_inst();
-        let config = if md5.is_empty() || md5 != get_md5(&mut descriptor, real_ip.0, &funs, &ctx.0).await? {
+        let client = ConfigClient {
+            ip: real_ip.0,
+            labels: labels.0.as_deref().map(parse_labels).unwrap_or_default(),
+        };
+        let config = if md5.is_empty() || md5 != get_md5(&mut descriptor, &client, &funs, &ctx.0).await? {
             // if md5 is empty or changed, return descriptor
             Some(descriptor)
         } else {
@@ -184,6 +290,46 @@ impl ConfCiConfigServiceApi {
         let resp = get_configs(request, mode, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
+    /// Export configs of a namespace as a nacos compatible zip, placeholders are not rendered
+    ///
+    /// 导出命名空间的配置为nacos兼容的zip文件，占位符不会被渲染
+    #[oai(path = "/configs/export", method = "get")]
+    async fn export_configs(
+        &self,
+        namespace_id: Query<Option<NamespaceId>>,
+        tenant: Query<Option<NamespaceId>>,
+        /// 配置分组名，多个以逗号分隔，为空时导出全部分组
+        groups: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> poem::Result<Attachment<Vec<u8>>> {
+        let mut namespace_id = namespace_id.0.or(tenant.0).unwrap_or("public".into());
+        if namespace_id.is_empty() {
+            namespace_id = "public".into();
+        }
+        let groups = groups.0.as_deref().map(parse_tags).unwrap_or_default();
+        let funs = crate::get_tardis_inst();
+        let zip_bytes = transfer::export_namespace(&namespace_id, &groups, &funs, &ctx.0).await.map_err(tardis_err_to_poem_err)?;
+        let file_name = format!("nacos_config_export_{namespace_id}_{}.zip", Utc::now().format("%Y%m%d%H%M%S"));
+        Ok(Attachment::new(zip_bytes).filename(file_name))
+    }
+    /// Import configs from a nacos compatible zip
+    ///
+    /// 从nacos兼容的zip文件导入配置
+    #[oai(path = "/configs/import", method = "post")]
+    async fn import_configs(
+        &self,
+        namespace_id: Query<Option<NamespaceId>>,
+        tenant: Query<Option<NamespaceId>>,
+        /// 同名配置的处理策略，默认为abort
+        policy: Query<Option<ConfigImportPolicy>>,
+        file: Binary<Vec<u8>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ConfigImportResponse> {
+        let namespace_id = namespace_id.0.or(tenant.0).unwrap_or("public".into());
+        let funs = crate::get_tardis_inst();
+        let response = transfer::import_namespace(&namespace_id, &file.0, policy.0.unwrap_or_default(), &funs, &ctx.0).await?;
+        TardisResp::ok(response)
+    }
     #[oai(path = "/history/list", method = "get")]
     async fn history_list(
         &self,
@@ -281,6 +427,76 @@ impl ConfCiConfigServiceApi {
         let config = find_previous_history(&mut descriptor, &id, &funs, &ctx.0).await?;
         TardisResp::ok(config)
     }
+    /// Rollback the config to a history version, the content and tags of the version are republished
+    ///
+    /// 回滚配置到历史版本，重新发布该版本的内容和标签
+    #[oai(path = "/history/rollback", method = "put")]
+    async fn history_rollback(
+        &self,
+        /// 命名空间
+        namespace_id: Query<Option<NamespaceId>>,
+        /// 租户
+        tenant: Query<Option<NamespaceId>>,
+        /// 配置分组名
+        group: Query<String>,
+        /// 配置名
+        data_id: Query<String>,
+        /// 历史版本id
+        id: Query<String>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<bool> {
+        let mut namespace_id = namespace_id.0.or(tenant.0).unwrap_or("public".into());
+        if namespace_id.is_empty() {
+            namespace_id = "public".into();
+        }
+        let id = Uuid::parse_str(&id.0).map_err(|e| TardisError::bad_request(&e.to_string(), error::INVALID_UUID))?;
+        let mut descriptor = ConfigDescriptor {
+            namespace_id,
+            group: group.0,
+            data_id: data_id.0,
+            ..Default::default()
+        };
+        let funs = crate::get_tardis_inst();
+        let result = rollback_config(&mut descriptor, &id, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+    /// Compare two history versions, compare with the current config if `to_id` is absent
+    ///
+    /// 比较两个历史版本，未指定`to_id`时与当前配置比较
+    #[oai(path = "/history/diff", method = "get")]
+    async fn history_diff(
+        &self,
+        /// 命名空间
+        namespace_id: Query<Option<NamespaceId>>,
+        /// 租户
+        tenant: Query<Option<NamespaceId>>,
+        /// 配置分组名
+        group: Query<String>,
+        /// 配置名
+        data_id: Query<String>,
+        /// 起始历史版本id
+        from_id: Query<String>,
+        /// 目标历史版本id
+        to_id: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ConfigHistoryDiffResponse> {
+        let mut namespace_id = namespace_id.0.or(tenant.0).unwrap_or("public".into());
+        if namespace_id.is_empty() {
+            namespace_id = "public".into();
+        }
+        let parse_id = |id: &str| Uuid::parse_str(id).map_err(|e| TardisError::bad_request(&e.to_string(), error::INVALID_UUID));
+        let from_id = parse_id(&from_id.0)?;
+        let to_id = to_id.0.as_deref().map(parse_id).transpose()?;
+        let mut descriptor = ConfigDescriptor {
+            namespace_id,
+            group: group.0,
+            data_id: data_id.0,
+            ..Default::default()
+        };
+        let funs = crate::get_tardis_inst();
+        let response = diff_history(&mut descriptor, &from_id, to_id.as_ref(), &funs, &ctx.0).await?;
+        TardisResp::ok(response)
+    }
     #[oai(path = "/history/configs", method = "get")]
     async fn configs_by_namespace(
         &self,
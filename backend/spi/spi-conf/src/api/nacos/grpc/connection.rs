@@ -0,0 +1,183 @@
+//! nacos grpc connections and server push
+//!
+//! Nacos clients send unary requests and keep a bi-stream open on the same channel,
+//! so the connection is identified by the peer address of the transport, which the unary requests share with the bi-stream.
+//! The client ip reported in the request metadata is never trusted.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::OnceLock,
+};
+
+use tardis::{
+    log,
+    tokio::{
+        self,
+        sync::{broadcast::error::RecvError, mpsc::UnboundedSender, RwLock},
+        task::JoinHandle,
+    },
+    web::{
+        poem::{self, endpoint::BoxEndpoint, web::RemoteAddr, EndpointExt, IntoEndpoint},
+        poem_grpc::{self, Status},
+    },
+};
+
+use super::{AsPayload, ConfigChangeNotifyRequest, ConfigContext, ConfigListenContext, Payload};
+use crate::{
+    dto::conf_config_dto::ConfigDescriptor,
+    serv::{subscribe_config_change, ConfigChangeEvent},
+};
+
+/// (own paths, descriptor with fixed namespace id)
+type ListenKey = (String, ConfigDescriptor);
+
+#[derive(Default)]
+struct ConnectionRegistry {
+    /// connection id -> sender of the bi-stream
+    connections: HashMap<String, UnboundedSender<Result<Payload, Status>>>,
+    /// connection id -> listened configs, the value is the config context given by client
+    listeners: HashMap<String, HashMap<ListenKey, ConfigContext>>,
+    /// connection id -> labels reported in `ConnectionSetupRequest`
+    labels: HashMap<String, HashMap<String, String>>,
+}
+
+/// Wraps a grpc service to make the peer address of the transport available in the extensions of its requests
+#[derive(Clone)]
+pub struct PeerAddrService<S>(pub S);
+
+impl<S: poem_grpc::Service> poem_grpc::Service for PeerAddrService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S: IntoEndpoint<Endpoint = BoxEndpoint<'static, poem::Response>>> IntoEndpoint for PeerAddrService<S> {
+    type Endpoint = BoxEndpoint<'static, poem::Response>;
+
+    fn into_endpoint(self) -> Self::Endpoint {
+        self.0
+            .into_endpoint()
+            .before(|mut req| async move {
+                let remote_addr = req.remote_addr().clone();
+                req.extensions_mut().insert(remote_addr);
+                Ok(req)
+            })
+            .boxed()
+    }
+}
+
+/// the peer address of the transport, the requests of a client channel share it
+pub fn peer_addr<T>(request: &poem_grpc::Request<T>) -> Option<SocketAddr> {
+    request.extensions().get::<RemoteAddr>().and_then(|remote_addr| remote_addr.as_socket_addr()).copied()
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: RwLock<ConnectionRegistry> = Default::default();
+}
+
+static PUSH_TASK: OnceLock<JoinHandle<()>> = OnceLock::new();
+
+fn listen_key(own_paths: &str, context: &ConfigListenContext) -> (ListenKey, ConfigContext) {
+    let mut descriptor = ConfigDescriptor {
+        namespace_id: context.tenant.clone(),
+        group: context.group.clone(),
+        data_id: context.data_id.clone(),
+        ..Default::default()
+    };
+    descriptor.fix_namespace_id();
+    let config_context = ConfigContext {
+        group: context.group.clone(),
+        data_id: context.data_id.clone(),
+        tenant: context.tenant.clone(),
+    };
+    ((own_paths.to_string(), descriptor), config_context)
+}
+
+/// id of the connection with the peer address
+pub fn connection_id(peer_addr: SocketAddr) -> String {
+    peer_addr.to_string()
+}
+
+/// bind a bi-stream to its connection, called when receiving `ConnectionSetupRequest`
+pub async fn register_connection(connection_id: &str, labels: HashMap<String, String>, sender: UnboundedSender<Result<Payload, Status>>) {
+    log::debug!("[spi-conf.nacos.grpc] connection {connection_id} registered, labels: {labels:?}");
+    let mut registry = REGISTRY.write().await;
+    registry.connections.insert(connection_id.to_string(), sender);
+    registry.labels.insert(connection_id.to_string(), labels);
+}
+
+/// labels reported by the connection
+pub async fn client_labels(connection_id: &str) -> HashMap<String, String> {
+    REGISTRY.read().await.labels.get(connection_id).cloned().unwrap_or_default()
+}
+
+/// remove a bi-stream with the configs listened on its connection
+pub async fn unregister_connection(connection_id: &str) {
+    let mut registry = REGISTRY.write().await;
+    registry.listeners.remove(connection_id);
+    registry.labels.remove(connection_id);
+    if registry.connections.remove(connection_id).is_some() {
+        log::debug!("[spi-conf.nacos.grpc] connection {connection_id} unregistered");
+    }
+}
+
+pub async fn add_listeners(connection_id: &str, own_paths: &str, contexts: &[ConfigListenContext]) {
+    let mut registry = REGISTRY.write().await;
+    let listeners = registry.listeners.entry(connection_id.to_string()).or_default();
+    for context in contexts {
+        let (key, config_context) = listen_key(own_paths, context);
+        listeners.insert(key, config_context);
+    }
+}
+
+pub async fn remove_listeners(connection_id: &str, own_paths: &str, contexts: &[ConfigListenContext]) {
+    let mut registry = REGISTRY.write().await;
+    if let Some(listeners) = registry.listeners.get_mut(connection_id) {
+        for context in contexts {
+            listeners.remove(&listen_key(own_paths, context).0);
+        }
+        if listeners.is_empty() {
+            registry.listeners.remove(connection_id);
+        }
+    }
+}
+
+async fn push(event: ConfigChangeEvent) {
+    let ConfigChangeEvent { own_paths, mut descriptor } = event;
+    descriptor.fix_namespace_id();
+    let key = (own_paths, descriptor);
+    let mut closed = HashSet::new();
+    {
+        let registry = REGISTRY.read().await;
+        for (connection_id, sender) in &registry.connections {
+            let Some(config_context) = registry.listeners.get(connection_id).and_then(|listeners| listeners.get(&key)) else {
+                continue;
+            };
+            let request = ConfigChangeNotifyRequest::new(config_context.clone());
+            log::trace!("[spi-conf.nacos.grpc] push {request:?} to connection {connection_id}");
+            if sender.send(Ok(request.as_payload())).is_err() {
+                closed.insert(connection_id.clone());
+            }
+        }
+    }
+    for connection_id in closed {
+        unregister_connection(&connection_id).await;
+    }
+}
+
+/// start the task which pushes config changes to nacos grpc clients, only the first call takes effect
+pub fn start_push_task() {
+    PUSH_TASK.get_or_init(|| {
+        let mut receiver = subscribe_config_change();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => push(event).await,
+                    Err(RecvError::Lagged(count)) => {
+                        log::warn!("[spi-conf.nacos.grpc] push task lagged, {count} config changes are skipped, clients will catch up by polling");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    });
+}
@@ -1,11 +1,14 @@
-use std::{collections::HashMap, net::IpAddr};
+use std::{collections::HashMap, net::SocketAddr};
 
+use bios_basic::helper::request_helper::mapped_ipv6_to_ipv4;
 use serde::{Deserialize, Serialize};
 use tardis::{
     basic::{error::TardisError, result::TardisResult},
     futures_util::StreamExt,
-    log, serde_json,
+    log, serde_json, TardisFuns,
 };
+mod connection;
+pub use connection::{start_push_task, PeerAddrService};
 #[allow(non_snake_case)]
 mod proto;
 pub use proto::{
@@ -14,8 +17,9 @@ pub use proto::{
 use tardis::web::poem_grpc::{self, Code, Request, Response, Status};
 
 use crate::{
-    dto::conf_config_dto::{ConfigDescriptor, ConfigItem},
+    dto::conf_config_dto::{self, ConfigClient, ConfigDescriptor, ConfigItem},
     serv::placeholder::render_content_for_ip,
+    utils::parse_tags,
 };
 
 #[derive(Clone, Default)]
@@ -28,14 +32,14 @@ impl RequestProto for RequestProtoImpl {
         };
         log::trace!("metadata: {metadata:?}");
         let access_token = metadata.headers.get("accessToken").map(|x| x.as_str());
-        let client_ip = metadata.client_ip.parse::<IpAddr>().ok();
+        let peer_addr = connection::peer_addr(&request);
         let Some(body) = &request.body else {
             return Err(Status::new(Code::InvalidArgument));
         };
         let body = String::from_utf8_lossy(&body.value);
         log::trace!("body: {}", body);
         let type_info = &metadata.r#type;
-        dispatch_request(type_info, &body, access_token, client_ip).await.map(Response::new).map_err(|e| {
+        dispatch_request(type_info, &body, access_token, peer_addr).await.map(Response::new).map_err(|e| {
             log::error!("[spi-conf.nacos.grpc] dispatch_request error: {}", e);
             Status::new(Code::Internal)
         })
@@ -47,28 +51,48 @@ pub struct BiRequestStreamProtoImpl;
 
 impl BiRequestStreamProto for BiRequestStreamProtoImpl {
     async fn request_bi_stream(&self, mut request_stream: Request<poem_grpc::Streaming<Payload>>) -> Result<Response<poem_grpc::Streaming<Payload>>, Status> {
-        let (mut _tx, rx) = tardis::tokio::sync::mpsc::unbounded_channel::<Result<Payload, Status>>();
+        let (tx, rx) = tardis::tokio::sync::mpsc::unbounded_channel::<Result<Payload, Status>>();
+        let connection_id = connection::peer_addr(&request_stream).map(connection::connection_id);
         tardis::tokio::spawn(async move {
             while let Some(maybe_pld) = request_stream.next().await {
-                if let Ok(payload) = maybe_pld {
-                    let Some(metadata) = &payload.metadata else {
-                        return Err(Status::new(Code::InvalidArgument));
-                    };
-                    log::trace!("bistream: metadata: {metadata:?}");
-                    let Some(body) = &payload.body else {
-                        return Err(Status::new(Code::InvalidArgument));
-                    };
-                    let body = String::from_utf8_lossy(&body.value);
-                    log::trace!("bistream: body: {}", body);
+                let payload = match maybe_pld {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        log::debug!("[spi-conf.nacos.grpc] bistream: connection {connection_id:?} error: {e}");
+                        break;
+                    }
+                };
+                let Some(metadata) = &payload.metadata else {
+                    continue;
+                };
+                log::trace!("bistream: metadata: {metadata:?}");
+                if let Some(body) = &payload.body {
+                    log::trace!("bistream: body: {}", String::from_utf8_lossy(&body.value));
                 }
+                match metadata.r#type.as_str() {
+                    "ConnectionSetupRequest" => {
+                        if let Some(connection_id) = &connection_id {
+                            let labels = payload
+                                .body
+                                .as_ref()
+                                .and_then(|body| serde_json::from_slice::<ConnectionSetupRequest>(&body.value).ok())
+                                .map(|request| request.labels)
+                                .unwrap_or_default();
+                            connection::register_connection(connection_id, labels, tx.clone()).await;
+                        } else {
+                            log::warn!("[spi-conf.nacos.grpc] bistream: unknown peer address, server push is disabled for this connection");
+                        }
+                    }
+                    // responses of server push, e.g. ConfigChangeNotifyResponse
+                    type_info => log::trace!("[spi-conf.nacos.grpc] bistream: received {type_info} from connection {connection_id:?}"),
+                }
+            }
+            if let Some(connection_id) = &connection_id {
+                connection::unregister_connection(connection_id).await;
             }
-            Ok(())
         });
         let stream = tokio_stream::wrappers::UnboundedReceiverStream::new(rx);
-        let _resp_stream = poem_grpc::Streaming::new(stream);
-        // temporary return unimplemented
-        Err(Status::new(Code::Unimplemented))
-        // Ok(Response::new(resp_stream))
+        Ok(Response::new(poem_grpc::Streaming::new(stream)))
     }
 }
 
@@ -123,6 +147,18 @@ impl NaocsGrpcResponse {
             request_id: None,
         }
     }
+    pub fn fail(error_code: i32, message: impl Into<String>) -> Self {
+        Self {
+            result_code: 500,
+            error_code: Some(error_code),
+            message: Some(message.into()),
+            request_id: None,
+        }
+    }
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
 }
 
 impl AsPayload for NaocsGrpcResponse {
@@ -169,6 +205,14 @@ impl HealthCheckResponse {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionSetupRequest {
+    /// labels of the client, used to match beta rules
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigQueryRequest {
@@ -217,7 +261,7 @@ impl From<ConfigItem> for ConfigQueryResponse {
             encrypted_data_key: item.encrypted_data_key,
             md5: item.md5,
             last_modified: item.last_modified_time.timestamp_millis() as u64,
-            is_beta: false,
+            is_beta: item.is_beta,
             tag: item.config_tags.join(","),
             response: NaocsGrpcResponse::success(),
         }
@@ -264,7 +308,81 @@ impl AsPayload for ConfigChangeBatchListenResponse {
     const TYPE_NAME: &'static str = "ConfigChangeBatchListenResponse";
 }
 
-pub async fn dispatch_request(type_info: &str, value: &str, access_token: Option<&str>, ip: Option<IpAddr>) -> TardisResult<Payload> {
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigPublishRequest {
+    pub data_id: String,
+    pub group: String,
+    pub tenant: Option<String>,
+    pub content: String,
+    /// when present, publish only if the md5 of the config on server side equals to it
+    pub cas_md5: Option<String>,
+    /// extra info, e.g. `type`, `appName`, `config_tags`, `desc`, `use`, `effect`, `schema`
+    #[serde(default)]
+    pub addition_map: HashMap<String, String>,
+    pub request_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigPublishResponse {
+    #[serde(flatten)]
+    pub response: NaocsGrpcResponse,
+}
+
+impl AsPayload for ConfigPublishResponse {
+    const TYPE_NAME: &'static str = "ConfigPublishResponse";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigRemoveRequest {
+    pub data_id: String,
+    pub group: String,
+    pub tenant: Option<String>,
+    pub tag: Option<String>,
+    pub request_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigRemoveResponse {
+    #[serde(flatten)]
+    pub response: NaocsGrpcResponse,
+}
+
+impl AsPayload for ConfigRemoveResponse {
+    const TYPE_NAME: &'static str = "ConfigRemoveResponse";
+}
+
+/// server push request, sent through the bi-stream when a listened config changed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChangeNotifyRequest {
+    pub request_id: String,
+    pub headers: HashMap<String, String>,
+    pub module: String,
+    #[serde(flatten)]
+    pub context: ConfigContext,
+}
+
+impl ConfigChangeNotifyRequest {
+    pub fn new(context: ConfigContext) -> Self {
+        Self {
+            request_id: TardisFuns::field.nanoid(),
+            headers: HashMap::new(),
+            module: "config".into(),
+            context,
+        }
+    }
+}
+
+impl AsPayload for ConfigChangeNotifyRequest {
+    const TYPE_NAME: &'static str = "ConfigChangeNotifyRequest";
+}
+
+/// dispatch a unary request, `peer_addr` is the peer address of the transport which identifies the connection
+pub async fn dispatch_request(type_info: &str, value: &str, access_token: Option<&str>, peer_addr: Option<SocketAddr>) -> TardisResult<Payload> {
     use crate::serv::*;
     let funs = crate::get_tardis_inst();
     let get_ctx = async {
@@ -273,8 +391,18 @@ pub async fn dispatch_request(type_info: &str, value: &str, access_token: Option
         };
         jwt_validate(token, &funs).await.map_err(|e| TardisError::unauthorized(&format!("invalid access token, error: {e}, token: {token}"), ""))
     };
+    let connection_id = peer_addr.map(connection::connection_id);
+    let ip = peer_addr.map(|peer_addr| mapped_ipv6_to_ipv4(peer_addr.ip()));
+    let client = ConfigClient {
+        ip,
+        labels: if let Some(connection_id) = &connection_id {
+            connection::client_labels(connection_id).await
+        } else {
+            HashMap::new()
+        },
+    };
     let response = match type_info {
-        "ServerCheckRequest" => ServerCheckResponse::success(None).as_payload(),
+        "ServerCheckRequest" => ServerCheckResponse::success(connection_id).as_payload(),
         "HealthCheckRequest" => HealthCheckResponse::success().as_payload(),
         "ConfigQueryRequest" => {
             let Ok(ctx) = get_ctx.await else {
@@ -287,7 +415,7 @@ pub async fn dispatch_request(type_info: &str, value: &str, access_token: Option
                 group,
                 ..Default::default()
             };
-            match get_config_detail(&mut descriptor, &funs, &ctx).await {
+            match get_config_for_client(&mut descriptor, &client, &funs, &ctx).await {
                 Ok(mut data) => {
                     data.content = render_content_for_ip(&descriptor, data.content, ip, &funs, &ctx).await?;
                     data.md5 = gen_md5(&data.content);
@@ -301,6 +429,13 @@ pub async fn dispatch_request(type_info: &str, value: &str, access_token: Option
             let ConfigBatchListenRequest { listen, config_listen_contexts } =
                 serde_json::from_str(value).map_err(|_e| TardisError::bad_request("expect a ConfigBatchListenRequest", ""))?;
             let mut changed_configs = Vec::with_capacity(config_listen_contexts.len());
+            if let Some(connection_id) = &connection_id {
+                if listen {
+                    connection::add_listeners(connection_id, &ctx.own_paths, &config_listen_contexts).await;
+                } else {
+                    connection::remove_listeners(connection_id, &ctx.own_paths, &config_listen_contexts).await;
+                }
+            }
             if listen {
                 for config in config_listen_contexts {
                     let mut descriptor = ConfigDescriptor {
@@ -309,7 +444,7 @@ pub async fn dispatch_request(type_info: &str, value: &str, access_token: Option
                         data_id: config.data_id,
                         ..Default::default()
                     };
-                    let server_side_md5 = get_md5(&mut descriptor, ip, &funs, &ctx).await?;
+                    let server_side_md5 = get_md5(&mut descriptor, &client, &funs, &ctx).await?;
                     if server_side_md5 != config.md5 {
                         changed_configs.push(ConfigContext {
                             group: descriptor.group,
@@ -325,6 +460,85 @@ pub async fn dispatch_request(type_info: &str, value: &str, access_token: Option
             }
             .as_payload()
         }
+        "ConfigPublishRequest" => {
+            let Ok(ctx) = get_ctx.await else {
+                return Ok(NaocsGrpcResponse::unregister().as_payload());
+            };
+            let ConfigPublishRequest {
+                data_id,
+                group,
+                tenant,
+                content,
+                cas_md5,
+                mut addition_map,
+                request_id,
+            } = serde_json::from_str(value).map_err(|_e| TardisError::bad_request("expect a ConfigPublishRequest", ""))?;
+            let mut descriptor = ConfigDescriptor {
+                namespace_id: tenant.unwrap_or("public".into()),
+                data_id,
+                group,
+                tp: addition_map.remove("type"),
+                ..Default::default()
+            };
+            descriptor.fix_namespace_id();
+            let mut publish_request = conf_config_dto::ConfigPublishRequest {
+                content,
+                descriptor,
+                app_name: addition_map.remove("appName"),
+                src_user: Some(ctx.owner.clone()),
+                config_tags: addition_map.remove("config_tags").as_deref().map(parse_tags).unwrap_or_default(),
+                desc: addition_map.remove("desc"),
+                r#use: addition_map.remove("use"),
+                effect: addition_map.remove("effect"),
+                schema: addition_map.remove("schema"),
+            };
+            let result = match cas_md5.filter(|md5| !md5.is_empty()) {
+                Some(cas_md5) => publish_config_cas(&mut publish_request, &cas_md5, &funs, &ctx).await,
+                None => publish_config(&mut publish_request, &funs, &ctx).await,
+            };
+            let response = match result {
+                Ok(_) => NaocsGrpcResponse::success(),
+                Err(e) => {
+                    log::warn!("[spi-conf.nacos.grpc] publish config error: {e}");
+                    NaocsGrpcResponse::fail(500, e.message)
+                }
+            };
+            ConfigPublishResponse {
+                response: response.with_request_id(request_id),
+            }
+            .as_payload()
+        }
+        "ConfigRemoveRequest" => {
+            let Ok(ctx) = get_ctx.await else {
+                return Ok(NaocsGrpcResponse::unregister().as_payload());
+            };
+            let ConfigRemoveRequest {
+                data_id,
+                group,
+                tenant,
+                request_id,
+                ..
+            } = serde_json::from_str(value).map_err(|_e| TardisError::bad_request("expect a ConfigRemoveRequest", ""))?;
+            let mut descriptor = ConfigDescriptor {
+                namespace_id: tenant.unwrap_or("public".into()),
+                data_id,
+                group,
+                ..Default::default()
+            };
+            descriptor.fix_namespace_id();
+            // removing a config which doesn't exist succeeds, same as the http api
+            let response = match delete_config(&mut descriptor, &funs, &ctx).await {
+                Ok(_) => NaocsGrpcResponse::success(),
+                Err(e) => {
+                    log::warn!("[spi-conf.nacos.grpc] remove config error: {e}");
+                    NaocsGrpcResponse::fail(500, e.message)
+                }
+            };
+            ConfigRemoveResponse {
+                response: response.with_request_id(request_id),
+            }
+            .as_payload()
+        }
         _ => {
             log::debug!("[Spi-Conf.Nacos.Grpc] unknown type_info: {}", type_info);
             Payload::default()
@@ -332,3 +546,33 @@ pub async fn dispatch_request(type_info: &str, value: &str, access_token: Option
     };
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_publish_request() {
+        let value = r#"{"headers":{},"requestId":"12","dataId":"app.yaml","group":"DEFAULT_GROUP","tenant":"","content":"a: 1","casMd5":null,"additionMap":{"type":"yaml","config_tags":"t1,t2"},"module":"config"}"#;
+        let request: ConfigPublishRequest = serde_json::from_str(value).unwrap();
+        assert_eq!(request.data_id, "app.yaml");
+        assert_eq!(request.cas_md5, None);
+        assert_eq!(request.addition_map.get("type").map(String::as_str), Some("yaml"));
+        assert_eq!(request.request_id.as_deref(), Some("12"));
+    }
+
+    #[test]
+    fn test_config_change_notify_request() {
+        let request = ConfigChangeNotifyRequest::new(ConfigContext {
+            group: "DEFAULT_GROUP".into(),
+            data_id: "app.yaml".into(),
+            tenant: "".into(),
+        });
+        let payload = request.as_payload();
+        assert_eq!(payload.metadata.unwrap().r#type, "ConfigChangeNotifyRequest");
+        let body: serde_json::Value = serde_json::from_slice(&payload.body.unwrap().value).unwrap();
+        assert_eq!(body["dataId"], "app.yaml");
+        assert_eq!(body["group"], "DEFAULT_GROUP");
+        assert_eq!(body["module"], "config");
+    }
+}
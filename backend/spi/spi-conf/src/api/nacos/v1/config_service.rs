@@ -1,3 +1,4 @@
+use bios_basic::helper::request_helper::tardis_err_to_poem_err;
 use poem::web::RealIp;
 use tardis::{
     basic::error::TardisError,
@@ -20,7 +21,7 @@ use crate::{
 };
 use crate::{conf_constants::error, serv::*};
 
-use super::{missing_param, tardis_err_to_poem_err};
+use super::missing_param;
 
 #[derive(Default, Clone, Copy, Debug)]
 pub struct ConfNacosV1CsApi;
@@ -51,7 +52,8 @@ impl ConfNacosV1CsApi {
         };
         let funs = crate::get_tardis_inst();
         let ctx = extract_context(request).await?;
-        let mut content = get_config(&mut descriptor, &funs, &ctx).await.map_err(tardis_err_to_poem_err)?;
+        let client = ConfigClient::from_ip(real_ip.0);
+        let mut content = get_config_for_client(&mut descriptor, &client, &funs, &ctx).await.map_err(tardis_err_to_poem_err)?.content;
         content = render_content_for_ip(&descriptor, content, real_ip.0, &funs, &ctx).await?;
         Ok(PlainText(content))
     }
@@ -152,7 +154,7 @@ impl ConfNacosV1CsApi {
             data_id: data_id.to_owned(),
             ..Default::default()
         };
-        let config = if md5.is_empty() || md5 != get_md5(&mut descriptor, real_ip.0, &funs, &ctx).await? {
+        let config = if md5.is_empty() || md5 != get_md5(&mut descriptor, &ConfigClient::from_ip(real_ip.0), &funs, &ctx).await? {
             // if md5 is empty or changed, return listening_configs
             listening_configs
         } else {
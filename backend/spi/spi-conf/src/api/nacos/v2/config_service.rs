@@ -1,10 +1,10 @@
+use bios_basic::helper::request_helper::tardis_err_to_poem_err;
 use poem::web::RealIp;
 use tardis::web::{
     poem::{self, web::Form, Request},
     poem_openapi::{self, param::Query, payload::Json},
 };
 
-use super::tardis_err_to_poem_err;
 use crate::serv::{placeholder::render_content_for_ip, *};
 use crate::{
     api::nacos::extract_context,
@@ -43,7 +43,8 @@ impl ConfNacosV2CsApi {
         };
         let funs = crate::get_tardis_inst();
         let ctx = extract_context(request).await?;
-        let mut content = get_config(&mut descriptor, &funs, &ctx).await.map_err(tardis_err_to_poem_err)?;
+        let client = ConfigClient::from_ip(real_ip.0);
+        let mut content = get_config_for_client(&mut descriptor, &client, &funs, &ctx).await.map_err(tardis_err_to_poem_err)?.content;
         content = render_content_for_ip(&descriptor, content, real_ip.0, &funs, &ctx).await?;
         Ok(Json(NacosResponse::ok(content)))
     }
@@ -23,6 +23,10 @@ pub struct ConfConfig {
     pub iam_client: IamClientConfig,
     pub data_id_env_config: String,
     pub group_env_config: String,
+    /// max number of entries in an imported zip, default as 1000
+    pub import_max_entries: usize,
+    /// max uncompressed size in byte of each entry in an imported zip, default as 10MB
+    pub import_max_entry_size: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -59,6 +63,8 @@ impl Default for ConfConfig {
             iam_client: Default::default(),
             data_id_env_config: ".env".to_string(),
             group_env_config: "DEFAULT-GROUP".to_string(),
+            import_max_entries: 1000,
+            import_max_entry_size: 10 * 1024 * 1024,
         }
     }
 }
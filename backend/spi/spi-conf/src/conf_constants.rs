@@ -15,16 +15,28 @@ pub mod error {
         "spi-conf" {
             NAMESPACE_DEFAULT_CANNOT_DELETE: 400 = "default-namespace-cannot-be-deleted";
             INVALID_UUID:               400 = "invalid-uuid";
+            INVALID_BETA_RULE:          400 = "invalid-beta-rule";
+            INVALID_ROLLBACK_TARGET:    400 = "invalid-rollback-target";
+            INVALID_IMPORT_FILE:        400 = "invalid-import-file";
             CONF_NOTFOUND:              404 = "conf-not-exist";
+            BETA_CONF_NOTFOUND:         404 = "beta-conf-not-exist";
             NAMESPACE_NOTFOUND:         404 = "namespace-not-exist";
             CONFLICT_AK:                 409 = "conflict-username";
             EXCEED_MAX_RETRY_TIMES:           409 = "exceed-max-retry-times";
+            CAS_PUBLISH_FAIL:           409 = "cas-publish-fail";
+            IMPORT_CONFLICT:            409 = "import-conflict";
             VALID_ERROR:                401 = "valid-error";
             CACHE_ERROR:                500 = "cache-error";
+            EXPORT_ERROR:               500 = "export-error";
         }
     }
 }
 
+/// capacity of the config change broadcast channel
+pub const CONFIG_CHANGE_CHANNEL_SIZE: usize = 1024;
+/// ident of the config change broadcast channel in the cluster
+pub const CONFIG_CHANGE_CHANNEL_IDENT: &str = "spi-conf/config-change";
+
 /// spi-conf cert kind
 pub const SPI_CONF_CERT_KIND: &str = "spi-conf";
 
@@ -1,4 +1,5 @@
 pub mod conf_auth_dto;
 pub mod conf_config_dto;
 pub mod conf_config_nacos_dto;
+pub mod conf_config_transfer_dto;
 pub mod conf_namespace_dto;
@@ -1,8 +1,9 @@
-use std::hash::Hash;
+use std::{collections::HashMap, hash::Hash, net::IpAddr};
 
 use super::conf_namespace_dto::NamespaceId;
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
-use tardis::{db::sea_orm::prelude::*, web::poem_openapi};
+use tardis::{crypto::crypto_digest::TardisCryptoDigest, db::sea_orm::prelude::*, web::poem_openapi};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub enum SearchMode {
@@ -139,6 +140,10 @@ pub struct ConfigItem {
     pub encrypted_data_key: Option<String>,
     /// 配置tags
     pub config_tags: Vec<String>,
+    /// 配置描述
+    pub desc: Option<String>,
+    /// 是否为灰度配置
+    pub is_beta: bool,
 }
 
 impl Default for ConfigItem {
@@ -159,6 +164,8 @@ impl Default for ConfigItem {
             last_modified_time: Default::default(),
             encrypted_data_key: None,
             config_tags: Default::default(),
+            desc: None,
+            is_beta: false,
         }
     }
 }
@@ -229,7 +236,181 @@ pub struct ConfigListResponse {
     pub page_items: Vec<ConfigItem>,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ConfigHistoryDiffResponse {
+    /// 比较的起始版本
+    pub from: ConfigItem,
+    /// 比较的目标版本
+    pub to: ConfigItem,
+    /// 按行比较的unified diff
+    pub diff: String,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
 pub struct HistoryConfigsRequest {
     namespace_id: NamespaceId,
 }
+
+/// 配置客户端信息，用于匹配灰度规则
+#[derive(Debug, Clone, Default)]
+pub struct ConfigClient {
+    /// 客户端ip
+    pub ip: Option<IpAddr>,
+    /// 客户端标签
+    pub labels: HashMap<String, String>,
+}
+
+impl ConfigClient {
+    pub fn from_ip(ip: Option<IpAddr>) -> Self {
+        Self { ip, ..Default::default() }
+    }
+}
+
+/// 灰度规则，客户端满足任一条件即可获取灰度配置
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ConfigBetaRule {
+    /// 客户端ip或网段，如 `10.0.0.1`、`10.0.1.0/24`
+    #[oai(default)]
+    pub ips: Vec<String>,
+    /// 客户端标签，需全部匹配
+    #[oai(default)]
+    pub labels: HashMap<String, String>,
+    /// 按客户端ip计算的灰度百分比，0-100
+    #[oai(validator(maximum(value = "100")))]
+    pub percentage: Option<u8>,
+}
+
+impl ConfigBetaRule {
+    /// parse ips into ip nets, a single ip is treated as a host net
+    pub fn ip_nets(&self) -> Result<Vec<IpNet>, String> {
+        self.ips
+            .iter()
+            .map(|ip| {
+                let ip = ip.trim();
+                ip.parse::<IpNet>().or_else(|_| ip.parse::<IpAddr>().map(IpNet::from)).map_err(|_| format!("invalid ip or cidr: {ip}"))
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ips.is_empty() && self.labels.is_empty() && self.percentage.unwrap_or_default() == 0
+    }
+
+    /// check if a client should get the beta config
+    pub fn matches(&self, descriptor: &ConfigDescriptor, client: &ConfigClient) -> bool {
+        if let Some(ip) = client.ip {
+            if self.ip_nets().unwrap_or_default().iter().any(|net| net.contains(&ip)) {
+                return true;
+            }
+        }
+        if !self.labels.is_empty() && self.labels.iter().all(|(k, v)| client.labels.get(k) == Some(v)) {
+            return true;
+        }
+        match (self.percentage, client.ip) {
+            (Some(percentage), Some(ip)) if percentage > 0 => Self::bucket(descriptor, ip) < percentage,
+            _ => false,
+        }
+    }
+
+    /// stable bucket in [0, 100) of a client for a config,
+    /// a client keeps being selected while the percentage grows
+    fn bucket(descriptor: &ConfigDescriptor, ip: IpAddr) -> u8 {
+        let key = format!("{}/{}/{}/{ip}", descriptor.namespace_id, descriptor.group, descriptor.data_id);
+        let digest = TardisCryptoDigest.md5(&key).expect("md5 digest shouldn't fail");
+        let head = u32::from_str_radix(&digest[..8], 16).unwrap_or_default();
+        (head % 100) as u8
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ConfigBetaPublishRequest {
+    /// 灰度配置内容
+    pub content: String,
+    #[serde(flatten)]
+    #[oai(flatten)]
+    pub descriptor: ConfigDescriptor,
+    /// 灰度规则
+    pub rule: ConfigBetaRule,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ConfigBetaItem {
+    /// 配置名
+    pub data_id: String,
+    /// 配置分组
+    pub group: String,
+    /// 租户信息（命名空间）
+    pub namespace: String,
+    /// 灰度配置内容的md5值
+    pub md5: String,
+    /// 灰度配置内容
+    pub content: String,
+    /// 灰度规则
+    pub rule: ConfigBetaRule,
+    /// 源用户
+    pub src_user: Option<String>,
+    /// 创建时间
+    pub created_time: DateTimeUtc,
+    /// 上次修改时间
+    pub last_modified_time: DateTimeUtc,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beta_rule_matches() {
+        let descriptor = ConfigDescriptor {
+            group: "DEFAULT-GROUP".into(),
+            data_id: "app.yaml".into(),
+            ..Default::default()
+        };
+        let client = |ip: &str, labels: &[(&str, &str)]| ConfigClient {
+            ip: ip.parse().ok(),
+            labels: labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        };
+        let rule = ConfigBetaRule {
+            ips: vec!["10.0.0.1".into(), "192.168.1.0/24".into()],
+            labels: HashMap::from([("env".to_string(), "canary".to_string())]),
+            percentage: None,
+        };
+        assert!(rule.ip_nets().is_ok());
+        assert!(rule.matches(&descriptor, &client("10.0.0.1", &[])));
+        assert!(rule.matches(&descriptor, &client("192.168.1.20", &[])));
+        assert!(!rule.matches(&descriptor, &client("10.0.0.2", &[])));
+        assert!(rule.matches(&descriptor, &client("10.0.0.2", &[("env", "canary"), ("zone", "a")])));
+        assert!(!rule.matches(&descriptor, &client("10.0.0.2", &[("env", "prod")])));
+
+        let all = ConfigBetaRule {
+            percentage: Some(100),
+            ..Default::default()
+        };
+        let none = ConfigBetaRule {
+            percentage: Some(0),
+            ..Default::default()
+        };
+        let half = ConfigBetaRule {
+            percentage: Some(50),
+            ..Default::default()
+        };
+        let mut selected = 0;
+        for i in 0..=255 {
+            let c = client(&format!("10.0.1.{i}"), &[]);
+            assert!(all.matches(&descriptor, &c));
+            assert!(!none.matches(&descriptor, &c));
+            if half.matches(&descriptor, &c) {
+                selected += 1;
+            }
+        }
+        assert!((64..192).contains(&selected));
+        assert!(ConfigBetaRule {
+            ips: vec!["not-an-ip".into()],
+            ..Default::default()
+        }
+        .ip_nets()
+        .is_err());
+    }
+}
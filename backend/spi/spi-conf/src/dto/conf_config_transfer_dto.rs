@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use tardis::web::poem_openapi;
+
+use super::conf_config_dto::ConfigItemDigest;
+
+/// 导入时遇到同名配置的处理策略
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigImportPolicy {
+    /// 存在冲突时终止导入，不导入任何配置
+    #[default]
+    Abort,
+    /// 跳过冲突的配置
+    Skip,
+    /// 覆盖冲突的配置
+    Overwrite,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+#[oai(rename_all = "camelCase")]
+pub struct ConfigImportResponse {
+    /// 成功导入的数量
+    pub succ_count: u32,
+    /// 跳过的数量
+    pub skip_count: u32,
+    /// 跳过的配置
+    pub skip_data: Vec<ConfigItemDigest>,
+    /// 导入失败的配置
+    pub fail_data: Vec<ConfigItemDigest>,
+}
+
+/// `.metadata.yml` in the nacos export zip
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ConfigExportMetadata {
+    #[serde(default)]
+    pub metadata: Vec<ConfigExportMetadataItem>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigExportMetadataItem {
+    pub group: String,
+    pub data_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub tp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_name: Option<String>,
+    /// not in the nacos format, ignored by nacos when importing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub config_tags: Vec<String>,
+}
@@ -21,11 +21,16 @@ use bios_basic::{
 use poem::http::StatusCode;
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    cluster::cluster_broadcast::ClusterBroadcastChannel,
     db::sea_orm::prelude::Uuid,
     log,
+    serde::{Deserialize, Serialize},
     serde_json::{self, json},
     tokio::{sync::OnceCell, time::Instant},
-    tokio::{sync::RwLock, task::JoinHandle},
+    tokio::{
+        sync::{broadcast, RwLock},
+        task::JoinHandle,
+    },
     web::poem,
     TardisFunsInst,
 };
@@ -45,6 +50,7 @@ use crate::{
 #[cfg(feature = "spi-pg")]
 mod pg;
 pub mod placeholder;
+pub mod transfer;
 
 spi_dispatch_service! {
     @mgr: true,
@@ -70,12 +76,14 @@ spi_dispatch_service! {
         // for configs
         /// publish config
         publish_config(req: &mut ConfigPublishRequest) -> TardisResult<bool>;
+        /// publish config only if the md5 of the config on server side equals to `cas_md5`
+        publish_config_cas(req: &mut ConfigPublishRequest, cas_md5: &str) -> TardisResult<bool>;
         /// get config
         get_config(descriptor: &mut ConfigDescriptor) -> TardisResult<String>;
         /// get config detail
         get_config_detail(descriptor: &mut ConfigDescriptor) -> TardisResult<ConfigItem>;
-        /// get content's md5 value by descriptor
-        get_md5(descriptor: &mut ConfigDescriptor, source_addr: Option<std::net::IpAddr>) -> TardisResult<String>;
+        /// get content's md5 value by descriptor, the beta version is used if the client matches the beta rule
+        get_md5(descriptor: &mut ConfigDescriptor, client: &ConfigClient) -> TardisResult<String>;
         get_raw_md5(descriptor: &mut ConfigDescriptor) -> TardisResult<String>;
         /// delete config
         delete_config(descriptor: &mut ConfigDescriptor) -> TardisResult<bool>;
@@ -83,6 +91,22 @@ spi_dispatch_service! {
         get_configs_by_namespace(namespace_id: &NamespaceId) -> TardisResult<Vec<ConfigItemDigest>>;
         /// get config
         get_configs(req: ConfigListRequest, mode: SearchMode) -> TardisResult<ConfigListResponse>;
+        /// get raw configs of a namespace for exporting
+        get_configs_for_export(namespace_id: &NamespaceId, groups: &[String]) -> TardisResult<Vec<ConfigPublishRequest>>;
+        /// publish imported configs in one transaction
+        import_configs(configs: &mut [ConfigPublishRequest]) -> TardisResult<()>;
+
+        // for beta (gray) release
+        /// publish a beta version of config to the clients matching the rule
+        publish_beta_config(req: &mut ConfigBetaPublishRequest) -> TardisResult<bool>;
+        /// get the beta version of config
+        get_beta_config(descriptor: &mut ConfigDescriptor) -> TardisResult<Option<ConfigBetaItem>>;
+        /// promote the beta version to everyone
+        promote_beta_config(descriptor: &mut ConfigDescriptor) -> TardisResult<bool>;
+        /// abandon the beta version
+        abandon_beta_config(descriptor: &mut ConfigDescriptor) -> TardisResult<bool>;
+        /// get config detail for a client, the beta version is returned if the client matches the beta rule
+        get_config_for_client(descriptor: &mut ConfigDescriptor, client: &ConfigClient) -> TardisResult<ConfigItem>;
 
         // for config history
         /// get config history list
@@ -91,6 +115,8 @@ spi_dispatch_service! {
         find_history(descriptor: &mut ConfigDescriptor, id: &Uuid) -> TardisResult<ConfigItem>;
         /// find previous history
         find_previous_history(descriptor: &mut ConfigDescriptor, id: &Uuid) -> TardisResult<ConfigItem>;
+        /// republish the content of a history version
+        rollback_config(descriptor: &mut ConfigDescriptor, id: &Uuid) -> TardisResult<bool>;
     }
 
 }
@@ -98,6 +124,30 @@ spi_dispatch_service! {
 lazy_static::lazy_static! {
     static ref TOKEN_CTX_MAP: Arc<RwLock<BTreeMap<String, (TardisContext, Instant)>>> = Default::default();
     static ref MAP_CLEANER_TASK: OnceCell<JoinHandle<()>> = Default::default();
+    static ref CONFIG_CHANGE_TX: Arc<ClusterBroadcastChannel<ConfigChangeEvent>> = ClusterBroadcastChannel::new(CONFIG_CHANGE_CHANNEL_IDENT, CONFIG_CHANGE_CHANNEL_SIZE);
+}
+
+/// a config was published or deleted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigChangeEvent {
+    /// own paths of the context which the config belongs to
+    pub own_paths: String,
+    pub descriptor: ConfigDescriptor,
+}
+
+/// broadcast a config change to the listeners in all nodes of the cluster, e.g. nacos grpc connections,
+/// so that clients connected to other nodes are pushed too
+pub fn notify_config_change(descriptor: &ConfigDescriptor, ctx: &TardisContext) {
+    let event = ConfigChangeEvent {
+        own_paths: ctx.own_paths.clone(),
+        descriptor: descriptor.clone(),
+    };
+    CONFIG_CHANGE_TX.send(event);
+}
+
+/// subscribe config changes happened in the cluster, including this node
+pub fn subscribe_config_change() -> broadcast::Receiver<ConfigChangeEvent> {
+    CONFIG_CHANGE_TX.subscribe()
 }
 
 pub fn gen_md5(content: &str) -> String {
@@ -105,6 +155,24 @@ pub fn gen_md5(content: &str) -> String {
     TardisCryptoDigest.md5(content).expect("md5 digest shouldn't fail")
 }
 
+/// compare two history versions, compare with the current config if `to_id` is none
+pub async fn diff_history(
+    descriptor: &mut ConfigDescriptor,
+    from_id: &Uuid,
+    to_id: Option<&Uuid>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<ConfigHistoryDiffResponse> {
+    let from = find_history(descriptor, from_id, funs, ctx).await?;
+    let to = if let Some(to_id) = to_id {
+        find_history(descriptor, to_id, funs, ctx).await?
+    } else {
+        get_config_detail(descriptor, funs, ctx).await?
+    };
+    let diff = similar::TextDiff::from_lines(&from.content, &to.content).unified_diff().context_radius(3).header(&from.id, &to.id).to_string();
+    Ok(ConfigHistoryDiffResponse { from, to, diff })
+}
+
 /// register a cert for nacos
 pub async fn register(req: RegisterRequest, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<RegisterResponse> {
     const GEN_AK_MAX_RETRY: usize = 8;
@@ -6,6 +6,8 @@ mod conf_pg_namespace_serv;
 pub use conf_pg_namespace_serv::*;
 mod conf_pg_config_history_serv;
 pub use conf_pg_config_history_serv::*;
+mod conf_pg_config_beta_serv;
+pub use conf_pg_config_beta_serv::*;
 
 use tardis::db::sea_orm::Value;
 
@@ -0,0 +1,210 @@
+use bios_basic::spi::spi_funs::SpiBsInst;
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    db::{
+        reldb_client::TardisRelDBClient,
+        sea_orm::{prelude::DateTimeUtc, Value},
+    },
+    serde_json, TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    conf_constants::error,
+    dto::conf_config_dto::*,
+    serv::{gen_md5, notify_config_change},
+};
+
+use super::{add_history, conf_pg_initializer, get_config_attributes, get_config_detail, publish_config_in_tx, HistoryInsertParams, OpType};
+
+macro_rules! get {
+    ($result:expr => {$($name:ident: $type:ty,)*}) => {
+        $(let $name = $result.try_get::<$type>("", stringify!($name))?;)*
+    };
+}
+
+pub async fn publish_beta_config(req: &mut ConfigBetaPublishRequest, _funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<bool> {
+    req.descriptor.fix_namespace_id();
+    req.rule.ip_nets().map_err(|e| TardisError::bad_request(&e, error::INVALID_BETA_RULE))?;
+    if req.rule.is_empty() {
+        return Err(TardisError::bad_request("beta rule should select at least one client", error::INVALID_BETA_RULE));
+    }
+    let data_id = &req.descriptor.data_id;
+    let group = &req.descriptor.group;
+    let namespace = &req.descriptor.namespace_id;
+    let content = &req.content;
+    let md5 = &gen_md5(content);
+    let rule = TardisFuns::json.obj_to_json(&req.rule)?;
+    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
+    let conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
+    let (mut conn, table_name) = conns.config_beta;
+    let (_, history_table_name) = conns.config_history;
+    let history = HistoryInsertParams {
+        data_id,
+        group,
+        namespace,
+        content,
+        md5,
+        ..Default::default()
+    };
+    conn.begin().await?;
+    add_history(history, OpType::BetaPublish, &conn, &history_table_name, ctx).await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+    (data_id, grp, namespace_id, content, md5, rule, src_user)
+VALUES
+    ($1, $2, $3, $4, $5, $6, $7)
+ON CONFLICT (namespace_id, grp, data_id) DO UPDATE
+SET content = EXCLUDED.content, md5 = EXCLUDED.md5, rule = EXCLUDED.rule, src_user = EXCLUDED.src_user"#,
+        ),
+        vec![
+            Value::from(data_id),
+            Value::from(group),
+            Value::from(namespace),
+            Value::from(content),
+            Value::from(md5),
+            Value::from(rule),
+            Value::from(&ctx.owner),
+        ],
+    )
+    .await?;
+    conn.commit().await?;
+    notify_config_change(&req.descriptor, ctx);
+    Ok(true)
+}
+
+pub async fn get_beta_config(descriptor: &mut ConfigDescriptor, _funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<Option<ConfigBetaItem>> {
+    descriptor.fix_namespace_id();
+    let data_id = &descriptor.data_id;
+    let group = &descriptor.group;
+    let namespace = &descriptor.namespace_id;
+    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
+    let conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
+    let (conn, table_name) = conns.config_beta;
+    let Some(qry_result) = conn
+        .query_one(
+            &format!(
+                r#"SELECT md5, content, rule, src_user, created_time, modified_time FROM {table_name} cb
+WHERE cb.namespace_id=$1 AND cb.grp=$2 AND cb.data_id=$3"#,
+            ),
+            vec![Value::from(namespace), Value::from(group), Value::from(data_id)],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    get!(qry_result => {
+        md5: String,
+        content: String,
+        rule: serde_json::Value,
+        src_user: Option<String>,
+        created_time: DateTimeUtc,
+        modified_time: DateTimeUtc,
+    });
+    Ok(Some(ConfigBetaItem {
+        data_id: data_id.clone(),
+        group: group.clone(),
+        namespace: namespace.clone(),
+        md5,
+        content,
+        rule: TardisFuns::json.json_to_obj(rule)?,
+        src_user,
+        created_time,
+        last_modified_time: modified_time,
+    }))
+}
+
+pub async fn promote_beta_config(descriptor: &mut ConfigDescriptor, funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<bool> {
+    let beta = get_beta_config(descriptor, funs, ctx, bs_inst).await?.ok_or_else(|| TardisError::not_found("beta config not found", error::BETA_CONF_NOTFOUND))?;
+    let data_id = &descriptor.data_id;
+    let group = &descriptor.group;
+    let namespace = &descriptor.namespace_id;
+    // keep the attributes of the formal config
+    let (app_name, schema) = get_config_attributes(descriptor, ctx, bs_inst).await?;
+    let config_tags = get_config_detail(&mut descriptor.clone(), funs, ctx, bs_inst).await.map(|config| config.config_tags).unwrap_or_default();
+    let mut publish_request = ConfigPublishRequest {
+        content: beta.content,
+        descriptor: descriptor.clone(),
+        app_name,
+        src_user: Some(ctx.owner.clone()),
+        config_tags,
+        schema,
+        ..Default::default()
+    };
+    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
+    let mut conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
+    // publish the formal config and remove the beta version in one transaction
+    conns.config.0.begin().await?;
+    publish_config_in_tx(&mut publish_request, Some(OpType::BetaPromote), None, &conns, ctx).await?;
+    let (conn, _) = &conns.config;
+    let beta_table_name = &conns.config_beta.1;
+    conn.execute_one(
+        &format!(
+            r#"DELETE FROM {beta_table_name} cb
+WHERE cb.namespace_id=$1 AND cb.grp=$2 AND cb.data_id=$3"#,
+        ),
+        vec![Value::from(namespace), Value::from(group), Value::from(data_id)],
+    )
+    .await?;
+    conns.config.0.commit().await?;
+    notify_config_change(descriptor, ctx);
+    Ok(true)
+}
+
+pub async fn abandon_beta_config(descriptor: &mut ConfigDescriptor, funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<bool> {
+    let beta = get_beta_config(descriptor, funs, ctx, bs_inst).await?.ok_or_else(|| TardisError::not_found("beta config not found", error::BETA_CONF_NOTFOUND))?;
+    let data_id = &descriptor.data_id;
+    let group = &descriptor.group;
+    let namespace = &descriptor.namespace_id;
+    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
+    let conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
+    let (mut conn, table_name) = conns.config_beta;
+    let (_, history_table_name) = conns.config_history;
+    let history = HistoryInsertParams {
+        data_id,
+        group,
+        namespace,
+        content: &beta.content,
+        md5: &beta.md5,
+        ..Default::default()
+    };
+    conn.begin().await?;
+    add_history(history, OpType::BetaAbandon, &conn, &history_table_name, ctx).await?;
+    conn.execute_one(
+        &format!(
+            r#"DELETE FROM {table_name} cb
+WHERE cb.namespace_id=$1 AND cb.grp=$2 AND cb.data_id=$3"#,
+        ),
+        vec![Value::from(namespace), Value::from(group), Value::from(data_id)],
+    )
+    .await?;
+    conn.commit().await?;
+    notify_config_change(descriptor, ctx);
+    Ok(true)
+}
+
+pub async fn get_config_for_client(
+    descriptor: &mut ConfigDescriptor,
+    client: &ConfigClient,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    bs_inst: &SpiBsInst,
+) -> TardisResult<ConfigItem> {
+    if let Some(beta) = get_beta_config(descriptor, funs, ctx, bs_inst).await? {
+        if beta.rule.matches(descriptor, client) {
+            return Ok(ConfigItem {
+                data_id: beta.data_id,
+                group: beta.group,
+                namespace: beta.namespace,
+                md5: beta.md5,
+                content: beta.content,
+                src_user: beta.src_user,
+                created_time: beta.created_time,
+                last_modified_time: beta.last_modified_time,
+                is_beta: true,
+                ..Default::default()
+            });
+        }
+    }
+    get_config_detail(descriptor, funs, ctx, bs_inst).await
+}
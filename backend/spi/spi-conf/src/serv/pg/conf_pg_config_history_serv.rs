@@ -2,7 +2,7 @@ use bios_basic::spi::spi_funs::SpiBsInst;
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
     db::{
-        reldb_client::TardisRelDBClient,
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
         sea_orm::{
             prelude::{DateTimeUtc, Uuid},
             Value,
@@ -13,10 +13,10 @@ use tardis::{
 
 use crate::{
     conf_constants::error,
-    dto::conf_config_dto::{ConfigDescriptor, ConfigHistoryListRequest, ConfigItem, ConfigListResponse},
+    dto::conf_config_dto::{ConfigDescriptor, ConfigHistoryListRequest, ConfigItem, ConfigListResponse, ConfigPublishRequest},
 };
 
-use super::conf_pg_initializer;
+use super::{conf_pg_initializer, get_config_attributes, publish_config_with_op};
 
 #[derive(Debug, Default)]
 pub struct HistoryInsertParams<'a> {
@@ -36,6 +36,14 @@ pub enum OpType {
     Insert,
     Update,
     Delete,
+    /// publish a beta (gray) version
+    BetaPublish,
+    /// promote the beta version to everyone
+    BetaPromote,
+    /// abandon the beta version
+    BetaAbandon,
+    /// rollback to a history version
+    Rollback,
 }
 
 impl OpType {
@@ -44,6 +52,10 @@ impl OpType {
             OpType::Insert => 'I',
             OpType::Update => 'U',
             OpType::Delete => 'D',
+            OpType::BetaPublish => 'B',
+            OpType::BetaPromote => 'P',
+            OpType::BetaAbandon => 'A',
+            OpType::Rollback => 'R',
         }
     }
 }
@@ -209,7 +221,26 @@ WHERE T.id = $4
         Err(TardisError::not_found("history config not found", error::CONF_NOTFOUND))
     }
 }
-pub async fn add_history(param: HistoryInsertParams<'_>, op_type: OpType, _funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<bool> {
+pub async fn rollback_config(descriptor: &mut ConfigDescriptor, id: &Uuid, funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<bool> {
+    let history = find_history(descriptor, id, funs, ctx, bs_inst).await?;
+    if history.op_type == OpType::Delete.as_char().to_string() {
+        return Err(TardisError::bad_request("can't rollback to a deleted version", error::INVALID_ROLLBACK_TARGET));
+    }
+    let (app_name, schema) = get_config_attributes(descriptor, ctx, bs_inst).await?;
+    let mut publish_request = ConfigPublishRequest {
+        content: history.content,
+        descriptor: descriptor.clone(),
+        app_name,
+        src_user: Some(ctx.owner.clone()),
+        config_tags: history.config_tags,
+        schema,
+        ..Default::default()
+    };
+    publish_config_with_op(&mut publish_request, Some(OpType::Rollback), funs, ctx, bs_inst).await
+}
+
+/// insert a history record with the given connection, so that it's in the same transaction as the config change
+pub async fn add_history(param: HistoryInsertParams<'_>, op_type: OpType, conn: &TardisRelDBlConnection, table_name: &str, ctx: &TardisContext) -> TardisResult<bool> {
     let HistoryInsertParams {
         data_id,
         group,
@@ -234,10 +265,6 @@ pub async fn add_history(param: HistoryInsertParams<'_>, op_type: OpType, _funs:
         ("src_user", Value::from(src_user)),
         ("config_tags", Value::from(config_tags.join(","))),
     ];
-    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
-    let conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
-    let (mut conn, table_name) = conns.config_history;
-    conn.begin().await?;
     let (fields, placeholders, values) = super::gen_insert_sql_stmt(params);
     conn.execute_one(
         &format!(
@@ -250,6 +277,5 @@ VALUES
         values,
     )
     .await?;
-    conn.commit().await?;
     Ok(true)
 }
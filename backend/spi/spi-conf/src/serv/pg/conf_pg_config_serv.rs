@@ -1,6 +1,5 @@
 use std::{
     collections::{HashMap, HashSet},
-    net::IpAddr,
     time::Duration,
 };
 
@@ -22,7 +21,7 @@ use tardis::{
 use crate::{
     conf_constants::*,
     dto::{conf_config_dto::*, conf_namespace_dto::*},
-    serv::{gen_md5, placeholder::render_content_for_ip},
+    serv::{gen_md5, notify_config_change, placeholder::render_content_for_ip},
 };
 
 // local memory cached md5
@@ -36,7 +35,7 @@ macro_rules! get {
     };
 }
 
-use super::{add_history, conf_pg_initializer, gen_select_sql_stmt, HistoryInsertParams, OpType};
+use super::{add_history, conf_pg_initializer, conf_pg_initializer::SpiConfTableAndConns, gen_select_sql_stmt, get_config_for_client, HistoryInsertParams, OpType};
 
 fn md5(content: &str) -> String {
     use tardis::crypto::crypto_digest::TardisCryptoDigest;
@@ -121,6 +120,7 @@ WHERE c.namespace_id=$1 AND c.grp=$2 AND c.data_id=$3"#,
         modified_time: DateTimeUtc,
         src_user: Option<String>,
         tags: Option<String>,
+        config_desc: Option<String>,
     });
     let config_tags = tags.map(|tags| tags.split(',').filter(|s| !s.is_empty()).map(String::from).collect()).unwrap_or_default();
     // fix md5 automatically
@@ -142,12 +142,40 @@ WHERE c.namespace_id=$1 AND c.grp=$2 AND c.data_id=$3"#,
         last_modified_time: modified_time,
         config_tags,
         src_user,
+        desc: config_desc,
         ..Default::default()
     })
 }
-pub async fn get_md5(descriptor: &mut ConfigDescriptor, source_addr: Option<IpAddr>, funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<String> {
-    let content = get_config(descriptor, funs, ctx, bs_inst).await?;
-    let content = render_content_for_ip(descriptor, content, source_addr, funs, ctx).await?;
+/// get app name and schema of the formal config, they are kept when republishing the config from a beta or history version
+pub async fn get_config_attributes(descriptor: &ConfigDescriptor, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<(Option<String>, Option<String>)> {
+    let data_id = &descriptor.data_id;
+    let group = &descriptor.group;
+    let namespace = &descriptor.namespace_id;
+    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
+    let conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
+    let (conn, table_name) = conns.config;
+    let Some(qry_result) = conn
+        .query_one(
+            &format!(
+                r#"SELECT app_name, schema FROM {table_name} cc
+WHERE cc.namespace_id=$1 AND cc.grp=$2 AND cc.data_id=$3"#,
+            ),
+            vec![Value::from(namespace), Value::from(group), Value::from(data_id)],
+        )
+        .await?
+    else {
+        return Ok((None, None));
+    };
+    get!(qry_result => {
+        app_name: Option<String>,
+        schema: Option<String>,
+    });
+    Ok((app_name, schema))
+}
+
+pub async fn get_md5(descriptor: &mut ConfigDescriptor, client: &ConfigClient, funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<String> {
+    let content = get_config_for_client(descriptor, client, funs, ctx, bs_inst).await?.content;
+    let content = render_content_for_ip(descriptor, content, client.ip, funs, ctx).await?;
     Ok(gen_md5(&content))
 }
 
@@ -188,6 +216,68 @@ WHERE cc.namespace_id='{namespace}' AND cc.grp='{group}' AND cc.data_id='{data_i
 }
 
 pub async fn publish_config(req: &mut ConfigPublishRequest, funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<bool> {
+    publish_config_with_op(req, None, funs, ctx, bs_inst).await
+}
+
+/// publish config only if it exists and its md5 equals to `cas_md5`,
+/// the md5 is checked in the update statement so that concurrent publishes can't both succeed
+pub async fn publish_config_cas(req: &mut ConfigPublishRequest, cas_md5: &str, funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<bool> {
+    do_publish_config(req, None, Some(cas_md5), funs, ctx, bs_inst).await
+}
+
+/// publish config and record history with the given op type,
+/// if it's none, the op type is insert or update according to whether the config exists
+pub async fn publish_config_with_op(
+    req: &mut ConfigPublishRequest,
+    specified_op_type: Option<OpType>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    bs_inst: &SpiBsInst,
+) -> TardisResult<bool> {
+    do_publish_config(req, specified_op_type, None, funs, ctx, bs_inst).await
+}
+
+async fn do_publish_config(
+    req: &mut ConfigPublishRequest,
+    specified_op_type: Option<OpType>,
+    cas_md5: Option<&str>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    bs_inst: &SpiBsInst,
+) -> TardisResult<bool> {
+    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
+    let mut conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
+    conns.config.0.begin().await?;
+    publish_config_in_tx(req, specified_op_type, cas_md5, &conns, ctx).await?;
+    conns.config.0.commit().await?;
+    notify_config_change(&req.descriptor, ctx);
+    Ok(true)
+}
+
+/// publish imported configs in one transaction, none of them is published if any fails
+pub async fn import_configs(configs: &mut [ConfigPublishRequest], _funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<()> {
+    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
+    let mut conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
+    conns.config.0.begin().await?;
+    for config in configs.iter_mut() {
+        publish_config_in_tx(config, None, None, &conns, ctx).await?;
+    }
+    conns.config.0.commit().await?;
+    for config in configs.iter() {
+        notify_config_change(&config.descriptor, ctx);
+    }
+    Ok(())
+}
+
+/// publish config and record history with the connection of `conns.config`,
+/// the transaction is begun and committed by the caller
+pub(crate) async fn publish_config_in_tx(
+    req: &mut ConfigPublishRequest,
+    specified_op_type: Option<OpType>,
+    cas_md5: Option<&str>,
+    conns: &SpiConfTableAndConns,
+    ctx: &TardisContext,
+) -> TardisResult<()> {
     // clear cache
     req.descriptor.fix_namespace_id();
     {
@@ -213,20 +303,23 @@ pub async fn publish_config(req: &mut ConfigPublishRequest, funs: &TardisFunsIns
         schema,
         config_tags: config_tags.iter().map(String::as_str).collect(),
     };
-    let params = vec![
+    let mut params = vec![
         ("content", Value::from(content)),
         ("md5", Value::from(md5)),
         ("app_name", Value::from(app_name)),
         ("schema", Value::from(schema)),
         ("src_user", Value::from(src_user)),
     ];
+    // keep the description if it's not given, most clients don't send it when publishing
+    if let Some(desc) = &req.desc {
+        params.push(("config_desc", Value::from(desc.as_str())));
+    }
 
     let key_params = vec![("data_id", Value::from(data_id)), ("grp", Value::from(group)), ("namespace_id", Value::from(namespace))];
-    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
-    let conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
-    let (mut conn, config_table_name) = conns.config;
-    let (_, tag_table_name) = conns.tag;
-    let (_, config_tag_rel_table_name) = conns.config_tag_rel;
+    let (conn, config_table_name) = &conns.config;
+    let tag_table_name = &conns.tag.1;
+    let config_tag_rel_table_name = &conns.config_tag_rel.1;
+    let history_table_name = &conns.config_history.1;
     // check if exists
     let qry_result = conn
         .query_one(
@@ -238,9 +331,11 @@ WHERE cc.grp=$1 AND cc.namespace_id=$2 AND cc.data_id=$3"#,
         )
         .await?
         .and_then(|r| r.try_get::<Uuid>("", "id").ok());
+    if cas_md5.is_some() && qry_result.is_none() {
+        return Err(TardisError::conflict("cas publish fail, config not exists", error::CAS_PUBLISH_FAIL));
+    }
     let op_type: OpType;
 
-    conn.begin().await?;
     // if has config tags, insert tags first
     if !config_tags.is_empty() {
         let placeholders = (1..=config_tags.len()).map(|idx| format!("(${idx})")).collect::<Vec<String>>().join(", ");
@@ -255,23 +350,31 @@ WHERE cc.grp=$1 AND cc.namespace_id=$2 AND cc.data_id=$3"#,
     // update or insert config, get config id
     let config_id = if let Some(uuid) = qry_result {
         // if exists, update
-        op_type = OpType::Update;
+        op_type = specified_op_type.unwrap_or(OpType::Update);
+        let mut key_params = key_params;
+        if let Some(cas_md5) = cas_md5 {
+            key_params.push(("md5", Value::from(cas_md5)));
+        }
         let (set_caluse, where_caluse, values) = super::gen_update_sql_stmt(params, key_params);
-        add_history(history, op_type, funs, ctx, bs_inst).await?;
-        conn.execute_one(
-            &format!(
-                r#"UPDATE {config_table_name} 
+        let result = conn
+            .execute_one(
+                &format!(
+                    r#"UPDATE {config_table_name} 
 SET {set_caluse}
 WHERE {where_caluse}"#,
-            ),
-            values,
-        )
-        .await?;
+                ),
+                values,
+            )
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(TardisError::conflict("cas publish fail, server md5 may have changed", error::CAS_PUBLISH_FAIL));
+        }
+        add_history(history, op_type, conn, history_table_name, ctx).await?;
         uuid
     } else {
         // if not exists, insert
-        op_type = OpType::Insert;
-        add_history(history, op_type, funs, ctx, bs_inst).await?;
+        op_type = specified_op_type.unwrap_or(OpType::Insert);
+        add_history(history, op_type, conn, history_table_name, ctx).await?;
         let mut fields_and_values = params;
         fields_and_values.extend(key_params);
         let (fields, placeholders, values) = super::gen_insert_sql_stmt(fields_and_values);
@@ -348,11 +451,10 @@ WHERE {where_caluse}"#,
         )
         .await?;
     }
-    conn.commit().await?;
-    Ok(true)
+    Ok(())
 }
 
-pub async fn delete_config(descriptor: &mut ConfigDescriptor, funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<bool> {
+pub async fn delete_config(descriptor: &mut ConfigDescriptor, _funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<bool> {
     descriptor.fix_namespace_id();
     // clear cache
     {
@@ -371,18 +473,30 @@ pub async fn delete_config(descriptor: &mut ConfigDescriptor, funs: &TardisFunsI
         ..Default::default()
     };
     let (mut conn, table_name) = conns.config;
+    let (_, beta_table_name) = conns.config_beta;
+    let (_, history_table_name) = conns.config_history;
     conn.begin().await?;
-    add_history(history, OpType::Delete, funs, ctx, bs_inst).await?;
+    add_history(history, OpType::Delete, &conn, &history_table_name, ctx).await?;
+    // the beta version goes with the formal one
+    conn.execute_one(
+        &format!(
+            r#"DELETE FROM {beta_table_name} cb
+WHERE cb.namespace_id=$1 AND cb.grp=$2 AND cb.data_id=$3"#,
+        ),
+        vec![Value::from(namespace), Value::from(group), Value::from(data_id)],
+    )
+    .await?;
     conn.execute_one(
         &format!(
             r#"DELETE FROM {table_name} cc
-WHERE cc.namespace_id='{namespace}' AND cc.grp='{group}' AND cc.data_id='{data_id}'
+WHERE cc.namespace_id=$1 AND cc.grp=$2 AND cc.data_id=$3
     "#,
         ),
-        vec![Value::from(data_id), Value::from(group), Value::from(namespace)],
+        vec![Value::from(namespace), Value::from(group), Value::from(data_id)],
     )
     .await?;
     conn.commit().await?;
+    notify_config_change(descriptor, ctx);
     Ok(true)
 }
 
@@ -425,6 +539,71 @@ ORDER BY created_time DESC
     Ok(list)
 }
 
+/// get raw configs of a namespace for exporting, all groups are included if `groups` is empty
+pub async fn get_configs_for_export(
+    namespace_id: &NamespaceId,
+    groups: &[String],
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    bs_inst: &SpiBsInst,
+) -> TardisResult<Vec<ConfigPublishRequest>> {
+    let namespace_id = if namespace_id.is_empty() { "public" } else { namespace_id };
+    let typed_inst = bs_inst.inst::<TardisRelDBClient>();
+    let conns = conf_pg_initializer::init_table_and_conn(typed_inst, ctx, true).await?;
+    let (conn, table_name) = conns.config;
+    let config_tag_rel_table_name = conns.config_tag_rel.1;
+    let mut values = vec![Value::from(namespace_id)];
+    let group_condition_clause = if groups.is_empty() {
+        String::new()
+    } else {
+        let placeholders = (2..=groups.len() + 1).map(|idx| format!("${idx}")).collect::<Vec<String>>().join(", ");
+        values.extend(groups.iter().map(Value::from));
+        format!("AND c.grp IN ({placeholders})")
+    };
+    let qry_result = conn
+        .query_all(
+            &format!(
+                r#"SELECT data_id, grp, content, app_name, schema, config_desc,
+    ARRAY_TO_STRING(
+        ARRAY(select tag_id from {config_tag_rel_table_name} tcr where tcr.config_id = c.id ORDER BY tag_id), ','
+    ) as tags
+FROM {table_name} c
+WHERE c.namespace_id=$1 {group_condition_clause}
+ORDER BY grp, data_id"#,
+            ),
+            values,
+        )
+        .await?;
+    qry_result
+        .iter()
+        .map(|result| {
+            get!(result => {
+                data_id: String,
+                grp: String,
+                content: String,
+                app_name: Option<String>,
+                schema: Option<String>,
+                config_desc: Option<String>,
+                tags: Option<String>,
+            });
+            Ok(ConfigPublishRequest {
+                content,
+                descriptor: ConfigDescriptor {
+                    namespace_id: namespace_id.to_string(),
+                    group: grp,
+                    data_id,
+                    ..Default::default()
+                },
+                app_name,
+                schema,
+                desc: config_desc,
+                config_tags: tags.map(|tags| tags.split(',').filter(|s| !s.is_empty()).map(String::from).collect()).unwrap_or_default(),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
 pub async fn get_configs(req: ConfigListRequest, mode: SearchMode, _funs: &TardisFunsInst, ctx: &TardisContext, bs_inst: &SpiBsInst) -> TardisResult<ConfigListResponse> {
     // query config history list by a ConfigDescriptor
     let ConfigListRequest {
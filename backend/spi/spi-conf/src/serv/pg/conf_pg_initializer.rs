@@ -1,14 +1,23 @@
+use std::{
+    collections::HashSet,
+    sync::{Mutex, OnceLock},
+};
+
 use bios_basic::spi::{spi_funs::TypedSpiBsInst, spi_initializer};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
 };
+
+/// Config tables already checked by [`upgrade_config_table`] in this process
+static UPGRADED_CONFIG_TABLES: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
 pub struct SpiConfTableAndConns {
     pub namespace: (TardisRelDBlConnection, String),
     pub config: (TardisRelDBlConnection, String),
     pub config_history: (TardisRelDBlConnection, String),
     pub tag: (TardisRelDBlConnection, String),
     pub config_tag_rel: (TardisRelDBlConnection, String),
+    pub config_beta: (TardisRelDBlConnection, String),
 }
 pub async fn init_table_and_conn_namespace(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
     let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
@@ -41,7 +50,7 @@ pub async fn init_table_and_conn_config(
     ctx: &TardisContext,
     mgr: bool,
 ) -> TardisResult<(TardisRelDBlConnection, String)> {
-    spi_initializer::common_pg::init_table_and_conn(
+    let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
         ctx,
         mgr,
@@ -60,14 +69,30 @@ src_user character varying,
 src_ip cidr,
 created_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
 modified_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
-tp character varying"#
+tp character varying,
+config_desc text"#
         ),
         None,
         vec![("data_id", "btree"), ("grp", "btree"), ("md5", "btree"), ("app_name", "btree")],
         None,
         Some("modified_time"),
     )
-    .await
+    .await?;
+    upgrade_config_table(&conn, &table_name).await?;
+    Ok((conn, table_name))
+}
+
+/// Add the columns introduced after the config table was created, checked once per table in this process
+async fn upgrade_config_table(conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<()> {
+    let upgraded_tables = UPGRADED_CONFIG_TABLES.get_or_init(Default::default);
+    if upgraded_tables.lock().map(|tables| tables.contains(table_name)).unwrap_or(false) {
+        return Ok(());
+    }
+    conn.execute_one(&format!("ALTER TABLE {table_name} ADD COLUMN IF NOT EXISTS config_desc text"), vec![]).await?;
+    if let Ok(mut tables) = upgraded_tables.lock() {
+        tables.insert(table_name.to_string());
+    }
+    Ok(())
 }
 
 pub async fn init_table_and_conn_history(
@@ -107,6 +132,39 @@ tp character varying"#
     .await
 }
 
+pub async fn init_table_and_conn_beta(
+    bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
+    namespace_table_name: &str,
+    ctx: &TardisContext,
+    mgr: bool,
+) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "conf_config_beta",
+        &format!(
+            r#"id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+data_id character varying NOT NULL,
+grp character varying NOT NULL DEFAULT 'DEFAULT-GROUP',
+namespace_id character varying NOT NULL DEFAULT 'public' REFERENCES {namespace_table_name} ON DELETE CASCADE,
+md5 character(32) NOT NULL,
+content text NOT NULL,
+rule jsonb NOT NULL,
+src_user character varying,
+created_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+modified_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+UNIQUE (namespace_id, grp, data_id)"#
+        ),
+        None,
+        vec![("data_id", "btree"), ("grp", "btree")],
+        None,
+        Some("modified_time"),
+    )
+    .await
+}
+
 pub async fn init_table_and_conn_tag(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
     spi_initializer::common_pg::init_table_and_conn(bs_inst, ctx, mgr, None, "conf_tag", r#"id character varying PRIMARY KEY"#, None, vec![], None, None).await
 }
@@ -143,11 +201,13 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
     let (config_history_conn, history_table_name) = init_table_and_conn_history(bs_inst, namespace_table_name.as_str(), ctx, mgr).await?;
     let (tag_conn, tag_table_name) = init_table_and_conn_tag(bs_inst, ctx, mgr).await?;
     let (config_tag_rel_conn, config_tag_rel_table_name) = init_table_and_conn_tag_config_rel(bs_inst, &config_table_name, &tag_table_name, ctx, mgr).await?;
+    let (config_beta_conn, config_beta_table_name) = init_table_and_conn_beta(bs_inst, namespace_table_name.as_str(), ctx, mgr).await?;
     Ok(SpiConfTableAndConns {
         namespace: (name_space_conn, namespace_table_name),
         config: (config_conn, config_table_name),
         config_history: (config_history_conn, history_table_name),
         tag: (tag_conn, tag_table_name),
         config_tag_rel: (config_tag_rel_conn, config_tag_rel_table_name),
+        config_beta: (config_beta_conn, config_beta_table_name),
     })
 }
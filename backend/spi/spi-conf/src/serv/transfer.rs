@@ -0,0 +1,257 @@
+// Namespace export/import in the nacos zip format:
+// every config is an entry named `{group}/{dataId}` holding the raw content,
+// and `.metadata.yml` holds the type and app name of the configs.
+// Placeholders such as `$CERT{}` and `$ENV{}` are exported as they are stored, without rendering.
+
+use std::{
+    collections::HashSet,
+    io::{Cursor, Read, Write},
+};
+
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    log, TardisFunsInst,
+};
+
+use crate::{
+    conf_config::ConfConfig,
+    conf_constants::error::*,
+    dto::{
+        conf_config_dto::{ConfigDescriptor, ConfigItemDigest, ConfigPublishRequest},
+        conf_config_transfer_dto::*,
+        conf_namespace_dto::{NamespaceDescriptor, NamespaceId},
+    },
+};
+
+const METADATA_FILE_NAME: &str = ".metadata.yml";
+
+fn zip_err(e: impl std::fmt::Display) -> TardisError {
+    TardisError::bad_request(&format!("invalid zip file: {e}"), INVALID_IMPORT_FILE)
+}
+
+/// pack configs into a nacos compatible zip
+pub fn pack_configs(configs: &[ConfigPublishRequest]) -> TardisResult<Vec<u8>> {
+    let internal_err = |e: String| TardisError::internal_error(&e, EXPORT_ERROR);
+    let metadata = ConfigExportMetadata {
+        metadata: configs
+            .iter()
+            .map(|config| ConfigExportMetadataItem {
+                group: config.descriptor.group.clone(),
+                data_id: config.descriptor.data_id.clone(),
+                desc: config.desc.clone(),
+                tp: config.schema.clone(),
+                app_name: config.app_name.clone(),
+                config_tags: config.config_tags.clone(),
+            })
+            .collect(),
+    };
+    let mut buf = Vec::new();
+    {
+        let mut zip_writer = zip::ZipWriter::new(Cursor::new(&mut buf));
+        let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for config in configs {
+            zip_writer.start_file(format!("{}/{}", config.descriptor.group, config.descriptor.data_id), options).map_err(|e| internal_err(e.to_string()))?;
+            zip_writer.write_all(config.content.as_bytes()).map_err(|e| internal_err(e.to_string()))?;
+        }
+        let metadata = serde_yaml::to_string(&metadata).map_err(|e| internal_err(e.to_string()))?;
+        zip_writer.start_file(METADATA_FILE_NAME, options).map_err(|e| internal_err(e.to_string()))?;
+        zip_writer.write_all(metadata.as_bytes()).map_err(|e| internal_err(e.to_string()))?;
+        zip_writer.finish().map_err(|e| internal_err(e.to_string()))?;
+    }
+    Ok(buf)
+}
+
+/// unpack a nacos compatible zip into configs of the namespace,
+/// the zip is rejected if it has more than `max_entries` entries or any entry is larger than `max_entry_size` bytes
+pub fn unpack_configs(namespace_id: &NamespaceId, bytes: &[u8], max_entries: usize, max_entry_size: u64) -> TardisResult<Vec<ConfigPublishRequest>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(zip_err)?;
+    if archive.len() > max_entries {
+        return Err(zip_err(format!("too many entries, at most {max_entries} are allowed")));
+    }
+    let mut configs = Vec::new();
+    let mut metadata = ConfigExportMetadata::default();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(zip_err)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        // the declared size can be forged, so the read is bounded as well
+        if file.size() > max_entry_size {
+            return Err(zip_err(format!("entry {name} is larger than {max_entry_size} bytes")));
+        }
+        let mut content = String::new();
+        file.by_ref().take(max_entry_size + 1).read_to_string(&mut content).map_err(|e| zip_err(format!("entry {name} is not utf-8 text, {e}")))?;
+        if content.len() as u64 > max_entry_size {
+            return Err(zip_err(format!("entry {name} is larger than {max_entry_size} bytes")));
+        }
+        if name == METADATA_FILE_NAME {
+            metadata = serde_yaml::from_str(&content).map_err(|e| zip_err(format!("invalid {METADATA_FILE_NAME}, {e}")))?;
+            continue;
+        }
+        let Some((group, data_id)) = name.split_once('/').filter(|(group, data_id)| !group.is_empty() && !data_id.is_empty() && !data_id.contains('/')) else {
+            return Err(zip_err(format!("entry {name} should be named as {{group}}/{{dataId}}")));
+        };
+        configs.push(ConfigPublishRequest {
+            content,
+            descriptor: ConfigDescriptor {
+                namespace_id: namespace_id.clone(),
+                group: group.to_string(),
+                data_id: data_id.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+    }
+    for item in metadata.metadata {
+        if let Some(config) = configs.iter_mut().find(|config| config.descriptor.group == item.group && config.descriptor.data_id == item.data_id) {
+            config.descriptor.tp.clone_from(&item.tp);
+            config.schema = item.tp;
+            config.app_name = item.app_name;
+            config.desc = item.desc;
+            config.config_tags = item.config_tags;
+        }
+    }
+    Ok(configs)
+}
+
+/// export the configs of a namespace, all groups are exported if `groups` is empty
+pub async fn export_namespace(namespace_id: &NamespaceId, groups: &[String], funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<u8>> {
+    let configs = super::get_configs_for_export(namespace_id, groups, funs, ctx).await?;
+    pack_configs(&configs)
+}
+
+/// import configs into a namespace, the configs are published in one transaction
+pub async fn import_namespace(
+    namespace_id: &NamespaceId,
+    bytes: &[u8],
+    policy: ConfigImportPolicy,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<ConfigImportResponse> {
+    let namespace_id = if namespace_id.is_empty() { "public".to_string() } else { namespace_id.clone() };
+    // make sure the namespace exists
+    super::get_namespace(
+        &mut NamespaceDescriptor {
+            namespace_id: namespace_id.clone(),
+        },
+        funs,
+        ctx,
+    )
+    .await?;
+    let cfg = funs.conf::<ConfConfig>();
+    let configs = unpack_configs(&namespace_id, bytes, cfg.import_max_entries, cfg.import_max_entry_size)?;
+    let existed = super::get_configs_by_namespace(&namespace_id, funs, ctx).await?.into_iter().map(|digest| (digest.group, digest.data_id)).collect::<HashSet<_>>();
+    let is_conflict = |config: &ConfigPublishRequest| existed.contains(&(config.descriptor.group.clone(), config.descriptor.data_id.clone()));
+    let digest = |config: &ConfigPublishRequest| ConfigItemDigest {
+        data_id: config.descriptor.data_id.clone(),
+        group: config.descriptor.group.clone(),
+        namespace: namespace_id.clone(),
+        app_name: config.app_name.clone(),
+        r#type: config.schema.clone(),
+    };
+    if policy == ConfigImportPolicy::Abort {
+        let conflicts = configs.iter().filter(|config| is_conflict(config)).map(|config| format!("{}/{}", config.descriptor.group, config.descriptor.data_id)).collect::<Vec<_>>();
+        if !conflicts.is_empty() {
+            return Err(TardisError::conflict(&format!("configs already exist: {}", conflicts.join(", ")), IMPORT_CONFLICT));
+        }
+    }
+    let mut response = ConfigImportResponse::default();
+    let mut to_publish = Vec::with_capacity(configs.len());
+    for mut config in configs {
+        if policy == ConfigImportPolicy::Skip && is_conflict(&config) {
+            response.skip_count += 1;
+            response.skip_data.push(digest(&config));
+            continue;
+        }
+        config.src_user = Some(ctx.owner.clone());
+        to_publish.push(config);
+    }
+    if let Err(e) = super::import_configs(&mut to_publish, funs, ctx).await {
+        log::warn!("[spi-conf] import configs into namespace {namespace_id} failed: {e}");
+        return Err(e);
+    }
+    response.succ_count = to_publish.len() as u32;
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_and_unpack_configs() {
+        let configs = vec![
+            ConfigPublishRequest {
+                content: "password: $CERT{DB_PASSWORD}\nhost: $ENV{DB_HOST}\n".into(),
+                descriptor: ConfigDescriptor {
+                    group: "DEFAULT_GROUP".into(),
+                    data_id: "app.yaml".into(),
+                    ..Default::default()
+                },
+                app_name: Some("app".into()),
+                schema: Some("yaml".into()),
+                desc: Some("database of app".into()),
+                config_tags: vec!["db".into(), "prod".into()],
+                ..Default::default()
+            },
+            ConfigPublishRequest {
+                content: "a=1".into(),
+                descriptor: ConfigDescriptor {
+                    group: "OTHER".into(),
+                    data_id: "app.properties".into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ];
+        let bytes = pack_configs(&configs).unwrap();
+        let unpacked = unpack_configs(&"dev".to_string(), &bytes, 10, 1024).unwrap();
+        assert_eq!(unpacked.len(), 2);
+        let app = unpacked.iter().find(|config| config.descriptor.data_id == "app.yaml").unwrap();
+        assert_eq!(app.descriptor.namespace_id, "dev");
+        assert_eq!(app.descriptor.group, "DEFAULT_GROUP");
+        assert_eq!(app.content, configs[0].content);
+        assert_eq!(app.schema.as_deref(), Some("yaml"));
+        assert_eq!(app.app_name.as_deref(), Some("app"));
+        assert_eq!(app.desc.as_deref(), Some("database of app"));
+        assert_eq!(app.config_tags, vec!["db".to_string(), "prod".to_string()]);
+        let other = unpacked.iter().find(|config| config.descriptor.data_id == "app.properties").unwrap();
+        assert_eq!(other.schema, None);
+        assert_eq!(other.desc, None);
+        assert!(other.config_tags.is_empty());
+    }
+
+    #[test]
+    fn test_unpack_invalid_entry() {
+        let mut buf = Vec::new();
+        {
+            let mut zip_writer = zip::ZipWriter::new(Cursor::new(&mut buf));
+            zip_writer.start_file("no-group", zip::write::SimpleFileOptions::default()).unwrap();
+            zip_writer.write_all(b"a=1").unwrap();
+            zip_writer.finish().unwrap();
+        }
+        assert!(unpack_configs(&"public".to_string(), &buf, 10, 1024).is_err());
+        assert!(unpack_configs(&"public".to_string(), b"not a zip", 10, 1024).is_err());
+    }
+
+    #[test]
+    fn test_unpack_limits() {
+        let configs = (0..3)
+            .map(|idx| ConfigPublishRequest {
+                content: "a".repeat(100),
+                descriptor: ConfigDescriptor {
+                    group: "DEFAULT_GROUP".into(),
+                    data_id: format!("app{idx}.properties"),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let bytes = pack_configs(&configs).unwrap();
+        // 3 configs and the metadata
+        assert_eq!(unpack_configs(&"public".to_string(), &bytes, 4, 1024).unwrap().len(), 3);
+        assert!(unpack_configs(&"public".to_string(), &bytes, 3, 100).is_err());
+        assert!(unpack_configs(&"public".to_string(), &bytes, 4, 99).is_err());
+    }
+}
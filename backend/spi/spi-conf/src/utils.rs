@@ -38,6 +38,18 @@ pub(crate) fn parse_tags(tags: &str) -> Vec<String> {
     v
 }
 
+/// parse labels in the form of `k1=v1,k2=v2`
+pub(crate) fn parse_labels(labels: &str) -> HashMap<String, String> {
+    labels
+        .split(',')
+        .filter_map(|label| {
+            let (key, value) = label.split_once('=')?;
+            let key = key.trim();
+            (!key.is_empty()).then(|| (key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
 pub(crate) fn dot_env_parser(config: &str) -> HashMap<String, String> {
     let mut map = HashMap::new();
     for line in config.lines() {
@@ -68,3 +80,12 @@ URL=http://www.baidu.com
     assert_eq!(map.get("VALUE"), Some(&"123".to_string()));
     assert_eq!(map.get("URL"), Some(&"http://www.baidu.com".to_string()));
 }
+
+#[cfg(test)]
+#[test]
+fn test_parse_labels() {
+    let map = parse_labels("env=canary, zone = a,invalid,=b");
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get("env"), Some(&"canary".to_string()));
+    assert_eq!(map.get("zone"), Some(&"a".to_string()));
+}
@@ -9,9 +9,12 @@ use bios_spi_conf::{
 };
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
+    config::config_dto::WebClientModuleConfig,
     log,
     serde_json::{json, Value},
-    tokio, TardisFuns,
+    tokio,
+    web::web_client::TardisWebClient,
+    TardisFuns,
 };
 mod spi_conf_test_common;
 use spi_conf_test_common::*;
@@ -58,6 +61,9 @@ async fn spi_conf_namespace_test() -> TardisResult<()> {
     test_register(&mut client).await?;
     test_curd(&mut client).await?;
     test_tags(&mut client).await?;
+    test_beta(&mut client).await?;
+    test_rollback(&mut client).await?;
+    test_transfer(&mut client).await?;
 
     // web_server_handle.await.unwrap()?;
     drop(container_hold);
@@ -489,6 +495,175 @@ pub async fn test_tags(client: &mut TestHttpClient) -> TardisResult<()> {
     Ok(())
 }
 
+pub async fn test_beta(client: &mut TestHttpClient) -> TardisResult<()> {
+    const DATA_ID: &str = "conf-beta-test";
+    const QUERY: &str = "namespace_id=public&group=DEFAULT-GROUP&data_id=conf-beta-test";
+    let _response = client
+        .post::<_, bool>(
+            "/ci/cs/config",
+            &json!( {
+                "content": "formal",
+                "group": "DEFAULT-GROUP",
+                "data_id": DATA_ID,
+                "config_tags": ["tag1"],
+            }),
+        )
+        .await;
+    // 1. beta by ip, the test client comes from localhost
+    let _response = client
+        .post::<_, bool>(
+            "/ci/cs/config/beta",
+            &json!( {
+                "content": "beta by ip",
+                "group": "DEFAULT-GROUP",
+                "data_id": DATA_ID,
+                "rule": { "ips": ["127.0.0.0/8", "::1"] },
+            }),
+        )
+        .await;
+    let response = client.get::<ConfigItem>(&format!("/ci/cs/config/detail?{QUERY}")).await;
+    assert!(response.is_beta);
+    assert_eq!(response.content, "beta by ip");
+    // 2. abandon
+    client.delete(&format!("/ci/cs/config/beta?{QUERY}")).await;
+    let response = client.get::<String>(&format!("/ci/cs/config?{QUERY}")).await;
+    assert_eq!(response, "formal");
+    assert!(client.get::<Option<Value>>(&format!("/ci/cs/config/beta?{QUERY}")).await.is_none());
+    // 3. beta by label
+    let _response = client
+        .post::<_, bool>(
+            "/ci/cs/config/beta",
+            &json!( {
+                "content": "beta by label",
+                "group": "DEFAULT-GROUP",
+                "data_id": DATA_ID,
+                "rule": { "labels": { "env": "canary" } },
+            }),
+        )
+        .await;
+    let response = client.get::<String>(&format!("/ci/cs/config?{QUERY}")).await;
+    assert_eq!(response, "formal");
+    let response = client.get::<String>(&format!("/ci/cs/config?{QUERY}&labels=env=canary")).await;
+    assert_eq!(response, "beta by label");
+    // 4. promote
+    let _response = client.put::<_, bool>(&format!("/ci/cs/config/beta/promote?{QUERY}"), &Value::Null).await;
+    let response = client.get::<ConfigItem>(&format!("/ci/cs/config/detail?{QUERY}")).await;
+    assert!(!response.is_beta);
+    assert_eq!(response.content, "beta by label");
+    assert!(response.config_tags.contains(&"tag1".to_string()));
+    // 5. every stage is recorded in history
+    let response = client.get::<ConfigListResponse>(&format!("/ci/cs/history/list?{QUERY}")).await;
+    let op_types = response.page_items.iter().map(|item| item.op_type.as_str()).collect::<Vec<_>>();
+    assert_eq!(op_types, vec!["P", "B", "A", "B", "I"]);
+    Ok(())
+}
+
+pub async fn test_rollback(client: &mut TestHttpClient) -> TardisResult<()> {
+    const QUERY: &str = "namespace_id=public&group=DEFAULT-GROUP&data_id=conf-rollback-test";
+    for content in ["a=1\nb=2\n", "a=1\nb=3\n"] {
+        let _response = client
+            .post::<_, bool>(
+                "/ci/cs/config",
+                &json!( {
+                    "content": content,
+                    "group": "DEFAULT-GROUP",
+                    "data_id": "conf-rollback-test",
+                }),
+            )
+            .await;
+    }
+    let history = client.get::<ConfigListResponse>(&format!("/ci/cs/history/list?{QUERY}")).await;
+    assert_eq!(history.total_count, 2);
+    let first_id = &history.page_items[1].id;
+    // 1. diff with the current config
+    let diff = client.get::<Value>(&format!("/ci/cs/history/diff?{QUERY}&from_id={first_id}")).await;
+    let diff = diff["diff"].as_str().unwrap_or_default();
+    assert!(diff.contains("-b=2"));
+    assert!(diff.contains("+b=3"));
+    // 2. rollback
+    let _response = client.put::<_, bool>(&format!("/ci/cs/history/rollback?{QUERY}&id={first_id}"), &Value::Null).await;
+    let response = client.get::<String>(&format!("/ci/cs/config?{QUERY}")).await;
+    assert_eq!(response, "a=1\nb=2\n");
+    let history = client.get::<ConfigListResponse>(&format!("/ci/cs/history/list?{QUERY}")).await;
+    assert_eq!(history.total_count, 3);
+    assert_eq!(history.page_items[0].op_type, "R");
+    // 3. diff between two history versions
+    let diff = client.get::<Value>(&format!("/ci/cs/history/diff?{QUERY}&from_id={first_id}&to_id={}", history.page_items[0].id)).await;
+    assert_eq!(diff["diff"], "");
+    Ok(())
+}
+
+pub async fn test_transfer(client: &mut TestHttpClient) -> TardisResult<()> {
+    const BASE_URL: &str = "https://127.0.0.1:8080/spi-conf";
+    const QUERY: &str = "group=TRANSFER-GROUP&data_id=conf-transfer-test";
+    let _response = client
+        .post::<_, bool>(
+            "/ci/cs/config",
+            &json!( {
+                "content": "password: $CERT{DB_PASSWORD}\n",
+                "group": "TRANSFER-GROUP",
+                "data_id": "conf-transfer-test",
+                "schema": "yaml",
+                "app_name": "app",
+                "desc": "for transfer test",
+                "config_tags": ["tag2", "tag1"],
+            }),
+        )
+        .await;
+    let _response = client
+        .post::<_, bool>(
+            "/ci/namespace",
+            &NamespaceAttribute {
+                namespace: "transfer".to_string(),
+                namespace_show_name: "导入测试".to_string(),
+                namespace_desc: None,
+            },
+        )
+        .await;
+    // the export api responds a zip file instead of json
+    let ctx_base64 = TardisFuns::crypto.base64.encode(TardisFuns::json.obj_to_string(client.context())?);
+    let context_header_name = TardisFuns::fw_config().web_server().context_conf.context_header_name.clone();
+    let web_client = TardisWebClient::init(&WebClientModuleConfig::default())?;
+    let zip_bytes = web_client
+        .raw()
+        .get(format!("{BASE_URL}/ci/cs/configs/export?namespace_id=public&groups=TRANSFER-GROUP"))
+        .header(&context_header_name, &ctx_base64)
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap()
+        .to_vec();
+    let import = |policy: &'static str| {
+        web_client
+            .raw()
+            .post(format!("{BASE_URL}/ci/cs/configs/import?namespace_id=transfer&policy={policy}"))
+            .header(&context_header_name, &ctx_base64)
+            .header("Content-Type", "application/octet-stream")
+            .body(zip_bytes.clone())
+            .send()
+    };
+    let response = import("abort").await.unwrap().json::<Value>().await.unwrap();
+    assert_eq!(response["data"]["succCount"], 1);
+    // 1. everything except the namespace is kept
+    let exported = client.get::<ConfigItem>(&format!("/ci/cs/config/detail?namespace_id=public&{QUERY}")).await;
+    let imported = client.get::<ConfigItem>(&format!("/ci/cs/config/detail?namespace_id=transfer&{QUERY}")).await;
+    assert_eq!(imported.namespace, "transfer");
+    assert_eq!(imported.content, "password: $CERT{DB_PASSWORD}\n");
+    assert_eq!(imported.md5, exported.md5);
+    assert_eq!(imported.desc.as_deref(), Some("for transfer test"));
+    let mut config_tags = imported.config_tags.clone();
+    config_tags.sort();
+    assert_eq!(config_tags, vec!["tag1", "tag2"]);
+    // 2. conflicts
+    let response = import("abort").await.unwrap().json::<Value>().await.unwrap();
+    assert!(response["code"].as_str().unwrap_or_default().starts_with("409"));
+    let response = import("skip").await.unwrap().json::<Value>().await.unwrap();
+    assert_eq!(response["data"]["skipCount"], 1);
+    Ok(())
+}
+
 pub async fn test_register(client: &mut TestHttpClient) -> TardisResult<()> {
     let RegisterResponse { username, password } = client.post("/ci/auth/register", &json!({})).await;
     log::info!("username: {username}, password: {password}");
@@ -5,7 +5,9 @@ use tardis::web::poem_openapi::param::Query;
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
-use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq};
+use crate::dto::graph_dto::{
+    GraphCyclesResp, GraphNodeVersionResp, GraphPathResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq, GraphTraverseDirection, GraphTraverseResp,
+};
 use crate::serv::graph_basic_serv;
 #[derive(Clone)]
 pub struct GraphCiRelApi;
@@ -60,4 +62,68 @@ impl GraphCiRelApi {
         let resp = graph_basic_serv::find_rels(from_key.0, from_version.0, depth.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
+
+    /// Find Shortest Path
+    ///
+    /// `tags` is comma separated, empty means all tags. Rels are walked in their own direction unless `directed` is false.
+    #[oai(path = "/path", method = "get")]
+    async fn find_path(
+        &self,
+        from_key: Query<String>,
+        from_version: Query<String>,
+        to_key: Query<String>,
+        to_version: Query<String>,
+        tags: Query<Option<String>>,
+        directed: Query<Option<bool>>,
+        depth: Query<Option<u8>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Option<GraphPathResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::find_path(
+            from_key.0,
+            from_version.0,
+            to_key.0,
+            to_version.0,
+            split_tags(tags.0),
+            directed.0.unwrap_or(true),
+            depth.0,
+            &funs,
+            &ctx.0,
+        )
+        .await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Upstream / Downstream Nodes
+    ///
+    /// `tags` is comma separated, empty means all tags.
+    #[oai(path = "/traverse", method = "get")]
+    async fn traverse(
+        &self,
+        key: Query<String>,
+        version: Query<String>,
+        direction: Query<GraphTraverseDirection>,
+        tags: Query<Option<String>>,
+        depth: Query<Option<u8>>,
+        size: Query<Option<u32>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<GraphTraverseResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::traverse(key.0, version.0, direction.0, split_tags(tags.0), depth.0, size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Cycles
+    ///
+    /// `tags` is comma separated.
+    #[oai(path = "/cycles", method = "get")]
+    async fn find_cycles(&self, tags: Query<String>, depth: Query<Option<u8>>, size: Query<Option<u32>>, ctx: TardisContextExtractor) -> TardisApiResult<GraphCyclesResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::find_cycles(split_tags(Some(tags.0)), depth.0, size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+}
+
+fn split_tags(tags: Option<String>) -> Vec<String> {
+    tags.map(|tags| tags.split(',').map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect()).unwrap_or_default()
 }
@@ -52,3 +52,56 @@ pub struct GraphRelDetailResp {
     pub form_rels: HashMap<String, Vec<GraphRelDetailResp>>,
     pub to_rels: HashMap<String, Vec<GraphRelDetailResp>>,
 }
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphTraverseDirection {
+    /// Follow rels from `from` to `to`, i.e. the nodes impacted by the given node
+    Downstream,
+    /// Follow rels from `to` to `from`, i.e. the nodes the given node depends on
+    Upstream,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GraphNodeResp {
+    pub key: String,
+    pub version: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GraphPathRelResp {
+    pub tag: String,
+    pub from_key: String,
+    pub from_version: String,
+    pub to_key: String,
+    pub to_version: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphPathResp {
+    /// Nodes in walking order, a cycle doesn't repeat its first node at the end
+    pub nodes: Vec<GraphNodeResp>,
+    /// Rels in walking order, always in their original direction
+    pub rels: Vec<GraphPathRelResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, sea_orm::FromQueryResult)]
+pub struct GraphTraverseNodeResp {
+    pub key: String,
+    pub version: String,
+    /// Min depth from the given node
+    pub depth: i32,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphTraverseResp {
+    pub nodes: Vec<GraphTraverseNodeResp>,
+    /// Whether the result exceeds the size limit and is truncated
+    pub truncated: bool,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphCyclesResp {
+    pub cycles: Vec<GraphPathResp>,
+    /// Whether the result exceeds the size limit and is truncated
+    pub truncated: bool,
+}
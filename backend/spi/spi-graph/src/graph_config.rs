@@ -2,8 +2,22 @@ use bios_basic::rbum::rbum_config::RbumConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct GraphConfig {
     pub rbum: RbumConfig,
+    /// Max depth of path / traverse / cycle queries, larger requested depths are capped to it
+    pub max_traverse_depth: u8,
+    /// Max number of nodes / cycles returned by traverse / cycle queries
+    pub max_traverse_size: u32,
+}
+
+impl Default for GraphConfig {
+    fn default() -> Self {
+        GraphConfig {
+            rbum: Default::default(),
+            max_traverse_depth: 20,
+            max_traverse_size: 1000,
+        }
+    }
 }
@@ -5,7 +5,9 @@ use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::TardisFunsInst;
 
-use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq};
+use crate::dto::graph_dto::{
+    GraphCyclesResp, GraphNodeVersionResp, GraphPathResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq, GraphTraverseDirection, GraphTraverseResp,
+};
 use crate::graph_initializer;
 
 use super::pg;
@@ -22,6 +24,9 @@ spi_dispatch_service! {
         upgrade_version(upgrade_version_req: &GraphRelUpgradeVersionReq) -> TardisResult<()>;
         find_versions(tag: String, key: String) -> TardisResult<Vec<GraphNodeVersionResp>>;
         find_rels(from_key: String, from_version: String, depth: Option<u8>) -> TardisResult<GraphRelDetailResp>;
+        find_path(from_key: String, from_version: String, to_key: String, to_version: String, tags: Vec<String>, directed: bool, depth: Option<u8>) -> TardisResult<Option<GraphPathResp>>;
+        traverse(key: String, version: String, direction: GraphTraverseDirection, tags: Vec<String>, depth: Option<u8>, size: Option<u32>) -> TardisResult<GraphTraverseResp>;
+        find_cycles(tags: Vec<String>, depth: Option<u8>, size: Option<u32>) -> TardisResult<GraphCyclesResp>;
     }
 }
 
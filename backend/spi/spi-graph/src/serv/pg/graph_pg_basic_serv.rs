@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, HashMap};
+
 use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer};
 use itertools::Itertools;
 use tardis::{
@@ -9,7 +11,13 @@ use tardis::{
     TardisFunsInst,
 };
 
-use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq};
+use crate::{
+    dto::graph_dto::{
+        GraphCyclesResp, GraphNodeResp, GraphNodeVersionResp, GraphPathRelResp, GraphPathResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq,
+        GraphTraverseDirection, GraphTraverseResp,
+    },
+    graph_config::GraphConfig,
+};
 
 use super::graph_pg_initializer;
 
@@ -247,6 +255,276 @@ pub async fn delete_rels(
     Ok(())
 }
 
+/// Cap the requested depth and size by the config, returns (depth, size)
+fn traverse_limits(depth: Option<u8>, size: Option<u32>, funs: &TardisFunsInst) -> (i32, i64) {
+    let conf = funs.conf::<GraphConfig>();
+    let depth = depth.unwrap_or(conf.max_traverse_depth).min(conf.max_traverse_depth).max(1);
+    let size = size.unwrap_or(conf.max_traverse_size).min(conf.max_traverse_size).max(1);
+    (depth as i32, size as i64)
+}
+
+/// Push the tag filter into sql values, returns the where fragment
+fn tag_fragment(tags: &[String], sql_vals: &mut Vec<Value>) -> String {
+    if tags.is_empty() {
+        return "".to_string();
+    }
+    sql_vals.push(Value::from(tags.to_vec()));
+    format!("AND g.tag = ANY(${})", sql_vals.len())
+}
+
+/// The shortest path from one node to another.
+///
+/// The search walks level by level and keeps one path per node for each level, so the result set is bounded by `nodes * depth`.
+/// Rels are walked in their own direction unless `directed` is false.
+pub async fn find_path(
+    from_key: String,
+    from_version: String,
+    to_key: String,
+    to_version: String,
+    tags: Vec<String>,
+    directed: bool,
+    depth: Option<u8>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Option<GraphPathResp>> {
+    if from_key == to_key && from_version == to_version {
+        return Ok(Some(GraphPathResp {
+            nodes: vec![GraphNodeResp {
+                key: from_key,
+                version: from_version,
+            }],
+            rels: vec![],
+        }));
+    }
+    let (depth, _) = traverse_limits(depth, None, funs);
+    let mut sql_vals: Vec<Value> = vec![
+        Value::from(from_key.as_str()),
+        Value::from(from_version.as_str()),
+        Value::from(to_key.as_str()),
+        Value::from(to_version.as_str()),
+        Value::from(depth),
+    ];
+    let mut where_fragment = tag_fragment(&tags, &mut sql_vals);
+    if directed {
+        where_fragment.push_str(" AND g.reverse = false");
+    }
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    let result = conn
+        .find_dto_by_sql::<GraphWalkRecord>(
+            &format!(
+                r#"WITH RECURSIVE walk(to_key, to_version, depth, keys, versions, tags, reverses) AS (
+    SELECT g.to_key, g.to_version, 1, ARRAY[g.from_key, g.to_key]::varchar[], ARRAY[g.from_version, g.to_version]::varchar[], ARRAY[g.tag]::varchar[], ARRAY[g.reverse]
+    FROM {table_name} AS g
+    WHERE g.from_key = $1 AND g.from_version = $2 {where_fragment}
+  UNION ALL
+    SELECT to_key, to_version, depth, keys, versions, tags, reverses FROM (
+        SELECT DISTINCT ON (g.to_key, g.to_version)
+            g.to_key, g.to_version, w.depth + 1 AS depth, w.keys || g.to_key AS keys, w.versions || g.to_version AS versions, w.tags || g.tag AS tags, w.reverses || g.reverse AS reverses
+        FROM {table_name} AS g, walk AS w
+        WHERE g.from_key = w.to_key AND g.from_version = w.to_version
+            AND w.depth < $5 AND NOT (w.to_key = $3 AND w.to_version = $4)
+            AND NOT EXISTS (SELECT 1 FROM unnest(w.keys, w.versions) AS p(k, v) WHERE p.k = g.to_key AND p.v = g.to_version)
+            {where_fragment}
+    ) t
+)
+SELECT keys, versions, tags, reverses FROM walk
+WHERE to_key = $3 AND to_version = $4
+ORDER BY depth
+LIMIT 1"#
+            ),
+            sql_vals,
+        )
+        .await?;
+    Ok(result.map(|record| record.into_path(false)))
+}
+
+/// All nodes transitively reachable from the given node in the direction, with the min depth of each node.
+///
+/// Rows of the recursion are deduplicated by `(node, depth)`, so the search terminates within `depth` levels even if the graph has cycles.
+pub async fn traverse(
+    key: String,
+    version: String,
+    direction: GraphTraverseDirection,
+    tags: Vec<String>,
+    depth: Option<u8>,
+    size: Option<u32>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<GraphTraverseResp> {
+    let (depth, size) = traverse_limits(depth, size, funs);
+    let mut sql_vals: Vec<Value> = vec![
+        Value::from(key.as_str()),
+        Value::from(version.as_str()),
+        Value::from(direction == GraphTraverseDirection::Upstream),
+        Value::from(depth),
+        // one more row to tell whether the result is truncated
+        Value::from(size + 1),
+    ];
+    let where_fragment = tag_fragment(&tags, &mut sql_vals);
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    let mut nodes = conn
+        .find_dtos_by_sql(
+            &format!(
+                r#"WITH RECURSIVE walk(key, version, depth) AS (
+    SELECT g.to_key, g.to_version, 1
+    FROM {table_name} AS g
+    WHERE g.from_key = $1 AND g.from_version = $2 AND g.reverse = $3 {where_fragment}
+  UNION
+    SELECT g.to_key, g.to_version, w.depth + 1
+    FROM {table_name} AS g, walk AS w
+    WHERE g.from_key = w.key AND g.from_version = w.version AND g.reverse = $3 AND w.depth < $4 {where_fragment}
+)
+SELECT key, version, MIN(depth) AS depth FROM walk
+WHERE NOT (key = $1 AND version = $2)
+GROUP BY key, version
+ORDER BY depth, key, version
+LIMIT $5"#
+            ),
+            sql_vals,
+        )
+        .await?;
+    let truncated = nodes.len() as i64 > size;
+    nodes.truncate(size as usize);
+    Ok(GraphTraverseResp { nodes, truncated })
+}
+
+/// Simple cycles formed by rels of the tags.
+///
+/// For each node the shortest cycle through it whose other nodes are all greater than it in `(key, version)` order is reported,
+/// so a cycle is reported once, starting from its smallest node. A rel from a node to itself is a cycle of the node.
+/// The rels of the tags are loaded once and every node is visited at most once per start node,
+/// so the search is bounded by `nodes * rels` instead of the number of paths.
+pub async fn find_cycles(tags: Vec<String>, depth: Option<u8>, size: Option<u32>, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<GraphCyclesResp> {
+    if tags.is_empty() {
+        return Err(funs.err().bad_request("spi-graph-rel", "find-cycles", "[tags] cannot be empty", "400-spi-graph-tag-require"));
+    }
+    let (depth, size) = traverse_limits(depth, size, funs);
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    let rels = conn
+        .find_dtos_by_sql::<GraphRelRecord>(
+            &format!(
+                r#"SELECT tag, from_key, from_version, to_key, to_version, reverse
+FROM {table_name}
+WHERE reverse = false AND tag = ANY($1)
+ORDER BY from_key, from_version, to_key, to_version, tag"#
+            ),
+            vec![Value::from(tags)],
+        )
+        .await?;
+    let mut graph: BTreeMap<(&str, &str), Vec<&GraphRelRecord>> = BTreeMap::new();
+    for rel in &rels {
+        graph.entry((rel.from_key.as_str(), rel.from_version.as_str())).or_default().push(rel);
+    }
+    let mut cycles = Vec::new();
+    for start in graph.keys() {
+        if let Some(record) = shortest_cycle(&graph, *start, depth as usize) {
+            cycles.push(record.into_path(true));
+            // one more cycle to tell whether the result is truncated
+            if cycles.len() as i64 > size {
+                break;
+            }
+        }
+    }
+    let truncated = cycles.len() as i64 > size;
+    cycles.truncate(size as usize);
+    Ok(GraphCyclesResp { cycles, truncated })
+}
+
+/// Breadth-first search of the shortest cycle through `start` within `depth` rels, other nodes of the cycle are greater than `start`
+fn shortest_cycle<'a>(graph: &BTreeMap<(&'a str, &'a str), Vec<&'a GraphRelRecord>>, start: (&'a str, &'a str), depth: usize) -> Option<GraphWalkRecord> {
+    // the rel by which each visited node is first reached
+    let mut reached_by: HashMap<(&str, &str), &GraphRelRecord> = HashMap::new();
+    let mut frontier = vec![start];
+    for _ in 0..depth {
+        let mut next = Vec::new();
+        for node in frontier {
+            for rel in graph.get(&node).into_iter().flatten() {
+                let to = (rel.to_key.as_str(), rel.to_version.as_str());
+                if to == start {
+                    let mut path = vec![*rel];
+                    let mut current = node;
+                    while current != start {
+                        let rel = reached_by[&current];
+                        path.push(rel);
+                        current = (rel.from_key.as_str(), rel.from_version.as_str());
+                    }
+                    path.reverse();
+                    return Some(GraphWalkRecord::from_rels(&path));
+                }
+                if to > start && !reached_by.contains_key(&to) {
+                    reached_by.insert(to, *rel);
+                    next.push(to);
+                }
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+    None
+}
+
+#[derive(sea_orm::FromQueryResult)]
+struct GraphWalkRecord {
+    pub keys: Vec<String>,
+    pub versions: Vec<String>,
+    pub tags: Vec<String>,
+    pub reverses: Vec<bool>,
+}
+
+impl GraphWalkRecord {
+    /// The walk along the connected rels, the first rel starts the walk
+    fn from_rels(rels: &[&GraphRelRecord]) -> Self {
+        let mut record = GraphWalkRecord {
+            keys: vec![],
+            versions: vec![],
+            tags: vec![],
+            reverses: vec![],
+        };
+        if let Some(first) = rels.first() {
+            record.keys.push(first.from_key.clone());
+            record.versions.push(first.from_version.clone());
+        }
+        for rel in rels {
+            record.keys.push(rel.to_key.clone());
+            record.versions.push(rel.to_version.clone());
+            record.tags.push(rel.tag.clone());
+            record.reverses.push(rel.reverse);
+        }
+        record
+    }
+
+    fn into_path(self, is_cycle: bool) -> GraphPathResp {
+        let mut nodes: Vec<GraphNodeResp> = self.keys.into_iter().zip(self.versions).map(|(key, version)| GraphNodeResp { key, version }).collect();
+        let rels = nodes
+            .iter()
+            .tuple_windows()
+            .zip(self.tags.into_iter().zip(self.reverses))
+            .map(|((from, to), (tag, reverse))| {
+                // a reverse rel is stored with swapped nodes
+                let (from, to) = if reverse { (to, from) } else { (from, to) };
+                GraphPathRelResp {
+                    tag,
+                    from_key: from.key.clone(),
+                    from_version: from.version.clone(),
+                    to_key: to.key.clone(),
+                    to_version: to.version.clone(),
+                }
+            })
+            .collect();
+        if is_cycle {
+            nodes.pop();
+        }
+        GraphPathResp { nodes, rels }
+    }
+}
+
 #[derive(sea_orm::FromQueryResult)]
 struct GraphRelRecord {
     pub tag: String,
@@ -1,5 +1,7 @@
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_graph::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeDelRelReq, GraphRelUpgradeVersionReq};
+use bios_spi_graph::dto::graph_dto::{
+    GraphCyclesResp, GraphNodeResp, GraphNodeVersionResp, GraphPathResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeDelRelReq, GraphRelUpgradeVersionReq, GraphTraverseResp,
+};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::serde_json::json;
@@ -445,5 +447,104 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
             }
         })
     );
+
+    test_traverse(client).await?;
+    Ok(())
+}
+
+async fn test_traverse(client: &mut TestHttpClient) -> TardisResult<()> {
+    let test_case_sqls = vec![
+        ("svc-dep", "svc-a", "1", "svc-b", "1"),
+        ("svc-dep", "svc-b", "1", "svc-c", "1"),
+        ("svc-dep", "svc-c", "1", "svc-a", "1"),
+        ("svc-dep", "svc-c", "1", "svc-d", "1"),
+        ("svc-call", "svc-a", "1", "svc-d", "1"),
+    ];
+    for (tag, from_key, from_version, to_key, to_version) in test_case_sqls {
+        let _: Void = client
+            .put(
+                "/ci/rel",
+                &GraphRelAddReq {
+                    tag: tag.to_string(),
+                    from_key: from_key.into(),
+                    from_version: from_version.to_string(),
+                    to_key: to_key.into(),
+                    to_version: to_version.to_string(),
+                },
+            )
+            .await;
+    }
+    let node = |key: &str| GraphNodeResp {
+        key: key.to_string(),
+        version: "1".to_string(),
+    };
+
+    // Find Path
+    let result: GraphPathResp = client.get("/ci/path?from_key=svc-a&from_version=1&to_key=svc-d&to_version=1&tags=svc-dep").await;
+    assert_eq!(result.nodes, vec![node("svc-a"), node("svc-b"), node("svc-c"), node("svc-d")]);
+    assert_eq!(result.rels.len(), 3);
+    assert_eq!(result.rels[2].from_key, "svc-c");
+    let result: GraphPathResp = client.get("/ci/path?from_key=svc-a&from_version=1&to_key=svc-d&to_version=1").await;
+    assert_eq!(result.nodes, vec![node("svc-a"), node("svc-d")]);
+    assert_eq!(result.rels[0].tag, "svc-call");
+    assert!(client.get_resp::<GraphPathResp>("/ci/path?from_key=svc-d&from_version=1&to_key=svc-a&to_version=1").await.data.is_none());
+    let result: GraphPathResp = client.get("/ci/path?from_key=svc-d&from_version=1&to_key=svc-a&to_version=1&tags=svc-call&directed=false").await;
+    assert_eq!(result.nodes, vec![node("svc-d"), node("svc-a")]);
+    assert_eq!(result.rels[0].from_key, "svc-a");
+    assert_eq!(result.rels[0].to_key, "svc-d");
+    assert!(client.get_resp::<GraphPathResp>("/ci/path?from_key=svc-a&from_version=1&to_key=svc-d&to_version=1&tags=svc-dep&depth=2").await.data.is_none());
+
+    // Traverse
+    let result: GraphTraverseResp = client.get("/ci/traverse?key=svc-b&version=1&direction=Downstream&tags=svc-dep").await;
+    assert!(!result.truncated);
+    assert_eq!(
+        result.nodes.iter().map(|node| (node.key.as_str(), node.depth)).collect::<Vec<_>>(),
+        vec![("svc-c", 1), ("svc-a", 2), ("svc-d", 2)]
+    );
+    let result: GraphTraverseResp = client.get("/ci/traverse?key=svc-b&version=1&direction=Downstream&tags=svc-dep&depth=1").await;
+    assert_eq!(result.nodes.len(), 1);
+    let result: GraphTraverseResp = client.get("/ci/traverse?key=svc-b&version=1&direction=Downstream&tags=svc-dep&size=2").await;
+    assert!(result.truncated);
+    assert_eq!(result.nodes.len(), 2);
+    let result: GraphTraverseResp = client.get("/ci/traverse?key=svc-d&version=1&direction=Upstream&tags=svc-dep").await;
+    assert_eq!(
+        result.nodes.iter().map(|node| (node.key.as_str(), node.depth)).collect::<Vec<_>>(),
+        vec![("svc-c", 1), ("svc-b", 2), ("svc-a", 3)]
+    );
+    let result: GraphTraverseResp = client.get("/ci/traverse?key=svc-d&version=1&direction=Upstream").await;
+    assert_eq!(result.nodes[0].key, "svc-a");
+    assert_eq!(result.nodes.len(), 3);
+
+    // Find Cycles
+    let result: GraphCyclesResp = client.get("/ci/cycles?tags=svc-dep,svc-call").await;
+    assert!(!result.truncated);
+    assert_eq!(result.cycles.len(), 1);
+    assert_eq!(result.cycles[0].nodes, vec![node("svc-a"), node("svc-b"), node("svc-c")]);
+    assert_eq!(result.cycles[0].rels.len(), 3);
+    let result: GraphCyclesResp = client.get("/ci/cycles?tags=svc-dep&depth=2").await;
+    assert!(result.cycles.is_empty());
+    let result: GraphCyclesResp = client.get("/ci/cycles?tags=svc-call").await;
+    assert!(result.cycles.is_empty());
+    // a rel from a node to itself
+    let _: Void = client
+        .put(
+            "/ci/rel",
+            &GraphRelAddReq {
+                tag: "svc-self".to_string(),
+                from_key: "svc-d".into(),
+                from_version: "1".to_string(),
+                to_key: "svc-d".into(),
+                to_version: "1".to_string(),
+            },
+        )
+        .await;
+    let result: GraphCyclesResp = client.get("/ci/cycles?tags=svc-self,svc-dep").await;
+    assert_eq!(result.cycles.len(), 2);
+    assert_eq!(result.cycles[1].nodes, vec![node("svc-d")]);
+    assert_eq!(result.cycles[1].rels.len(), 1);
+    assert_eq!(result.cycles[1].rels[0].to_key, "svc-d");
+    let result: GraphCyclesResp = client.get("/ci/cycles?tags=svc-self,svc-dep&size=1").await;
+    assert!(result.truncated);
+    assert_eq!(result.cycles.len(), 1);
     Ok(())
 }
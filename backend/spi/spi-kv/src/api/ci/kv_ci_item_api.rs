@@ -1,12 +1,18 @@
+use bios_basic::helper::request_helper::tardis_err_to_poem_err;
+use tardis::chrono::{DateTime, Utc};
+use tardis::futures::stream::BoxStream;
 use tardis::web::context_extractor::TardisContextExtractor;
+use tardis::web::poem;
 use tardis::web::poem_openapi;
 use tardis::web::poem_openapi::param::Query;
-use tardis::web::poem_openapi::payload::Json;
+use tardis::web::poem_openapi::payload::{EventStream, Json};
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
 use crate::dto::kv_item_dto::{
-    KvItemAddOrModifyReq, KvItemDetailResp, KvItemKeyReq, KvItemMatchReq, KvItemSummaryResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
+    KvItemAddOrModifyReq, KvItemChangeResp, KvItemChangesResp, KvItemDetailResp, KvItemHistoryResp, KvItemKeyReq, KvItemMatchReq, KvItemRestoreReq, KvItemSummaryResp,
+    KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
 };
+use crate::kv_constants::WATCH_BATCH_SIZE;
 use crate::serv::kv_item_serv;
 
 /// 将 query 中的逗号分隔 `own_paths` 解析为列表；空或仅空白则返回 `None`
@@ -44,24 +50,62 @@ impl KvCiItemApi {
 
     /// Get Item
     ///
-    /// 获取Item
+    /// 获取Item；传入 `as_of` 时返回该时间点的Item
     #[oai(path = "/item", method = "get")]
-    async fn get_item(&self, key: Query<String>, extract: Query<Option<String>>, ctx: TardisContextExtractor) -> TardisApiResult<Option<KvItemDetailResp>> {
+    async fn get_item(
+        &self,
+        key: Query<String>,
+        extract: Query<Option<String>>,
+        as_of: Query<Option<DateTime<Utc>>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Option<KvItemDetailResp>> {
         let funs = crate::get_tardis_inst();
-        let resp = kv_item_serv::get_item(key.0, extract.0, &funs, &ctx.0).await?;
+        let resp = kv_item_serv::get_item(key.0, extract.0, as_of.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
 
     /// Find Items By keys
     ///
-    /// 通过keys查找Items
+    /// 通过keys查找Items；传入 `as_of` 时返回该时间点的Items
     #[oai(path = "/items", method = "get")]
-    async fn find_items(&self, keys: Query<Vec<String>>, extract: Query<Option<String>>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<KvItemSummaryResp>> {
+    async fn find_items(
+        &self,
+        keys: Query<Vec<String>>,
+        extract: Query<Option<String>>,
+        as_of: Query<Option<DateTime<Utc>>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Vec<KvItemSummaryResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = kv_item_serv::find_items(keys.0, extract.0, as_of.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Page Item History
+    ///
+    /// 分页获取Item的历史记录，按时间倒序
+    #[oai(path = "/item/history", method = "get")]
+    async fn find_history(
+        &self,
+        key: Query<String>,
+        page_number: Query<u32>,
+        page_size: Query<u16>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<KvItemHistoryResp>> {
         let funs = crate::get_tardis_inst();
-        let resp = kv_item_serv::find_items(keys.0, extract.0, &funs, &ctx.0).await?;
+        let resp = kv_item_serv::find_history(key.0, page_number.0, page_size.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
 
+    /// Restore Item To a history record
+    ///
+    /// 将Item恢复到某条历史记录的状态
+    #[oai(path = "/item/restore", method = "put")]
+    async fn restore_item(&self, mut restore_req: Json<KvItemRestoreReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        kv_item_serv::restore_item(&mut restore_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
     /// Match Items By key prefix
     ///
     /// 通过key前缀匹配Items；`own_paths` 为逗号分隔的 path 列表，不传或空表示不过滤
@@ -156,6 +200,39 @@ impl KvCiItemApi {
         TardisResp::ok(Void {})
     }
 
+    /// Find Item Changes By key prefix
+    ///
+    /// 通过key前缀查找Item的变更；不传 `from_revision` 时仅返回当前最新的版本号
+    #[oai(path = "/item/changes", method = "get")]
+    async fn find_changes(
+        &self,
+        key_prefix: Query<String>,
+        from_revision: Query<Option<i64>>,
+        size: Query<Option<u32>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<KvItemChangesResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = kv_item_serv::find_changes(key_prefix.0, from_revision.0, size.0.unwrap_or(WATCH_BATCH_SIZE).clamp(1, WATCH_BATCH_SIZE), &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Watch Item Changes By key prefix
+    /// ps: server-sent events, resume from the revision of the last received change after reconnecting
+    ///
+    /// 通过key前缀监听Item的变更
+    /// ps: 以SSE推送，重连后从最后收到的变更的版本号继续监听
+    #[oai(path = "/item/watch", method = "get")]
+    async fn watch_changes(
+        &self,
+        key_prefix: Query<String>,
+        from_revision: Query<Option<i64>>,
+        ctx: TardisContextExtractor,
+    ) -> poem::Result<EventStream<BoxStream<'static, KvItemChangeResp>>> {
+        let funs = crate::get_tardis_inst();
+        let stream = kv_item_serv::watch_changes(key_prefix.0, from_revision.0, &funs, &ctx.0).await.map_err(tardis_err_to_poem_err)?;
+        Ok(EventStream::new(stream))
+    }
+
     /// Add Or Modify Key-Name
     ///
     /// 添加或修改Key-Name
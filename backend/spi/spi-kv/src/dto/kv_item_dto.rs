@@ -15,6 +15,11 @@ pub struct KvItemAddOrModifyReq {
     pub disable: Option<bool>,
     pub info: Option<String>,
     pub scope_level: Option<i16>,
+    /// 期望的当前版本号，不一致时写入失败并返回冲突；为 `0` 时要求记录不存在
+    #[oai(validator(minimum(value = "0")))]
+    pub expected_revision: Option<i64>,
+    /// 存活时间（秒），过期后记录不可见并会被后台清理；为 `0` 时取消过期，不传则保持原有设置
+    pub ttl_sec: Option<u32>,
 }
 impl From<bios_sdk_invoke::clients::spi_kv_client::KvItemAddOrModifyReq> for KvItemAddOrModifyReq {
     fn from(req: bios_sdk_invoke::clients::spi_kv_client::KvItemAddOrModifyReq) -> Self {
@@ -24,6 +29,8 @@ impl From<bios_sdk_invoke::clients::spi_kv_client::KvItemAddOrModifyReq> for KvI
             info: req.info,
             scope_level: req.scope_level,
             disable: None,
+            expected_revision: None,
+            ttl_sec: None,
         }
     }
 }
@@ -37,6 +44,10 @@ pub struct KvItemDetailResp {
     pub own_paths: String,
     pub disable: bool,
     pub scope_level: i16,
+    /// 版本号，每次修改递增
+    pub revision: i64,
+    /// 过期时间，为空时永不过期
+    pub expire_time: Option<DateTime<Utc>>,
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
 }
@@ -51,10 +62,101 @@ pub struct KvItemSummaryResp {
     pub own_paths: String,
     pub disable: bool,
     pub scope_level: i16,
+    /// 版本号，每次修改递增
+    pub revision: i64,
+    /// 过期时间，为空时永不过期
+    pub expire_time: Option<DateTime<Utc>>,
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
 }
 
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum KvItemChangeKind {
+    Put,
+    Delete,
+    Disable,
+    Enable,
+}
+
+impl KvItemChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KvItemChangeKind::Put => "put",
+            KvItemChangeKind::Delete => "delete",
+            KvItemChangeKind::Disable => "disable",
+            KvItemChangeKind::Enable => "enable",
+        }
+    }
+
+    pub fn parse(op: &str) -> Option<Self> {
+        match op {
+            "put" => Some(KvItemChangeKind::Put),
+            "delete" => Some(KvItemChangeKind::Delete),
+            "disable" => Some(KvItemChangeKind::Disable),
+            "enable" => Some(KvItemChangeKind::Enable),
+            _ => None,
+        }
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct KvItemChangeResp {
+    /// 变更的版本号，在同一存储内递增，可作为 `from_revision` 续读
+    pub revision: i64,
+    pub op: KvItemChangeKind,
+    pub key: String,
+    /// 变更后的值，删除时为删除前的值
+    pub value: Option<Value>,
+    pub own_paths: String,
+    pub scope_level: i16,
+    /// 变更后Item的版本号
+    pub item_revision: i64,
+    pub create_time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvItemChangesResp {
+    pub changes: Vec<KvItemChangeResp>,
+    /// 已读取到的版本号，作为下次请求的 `from_revision`
+    pub revision: i64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvItemHistoryResp {
+    /// 历史记录ID，可用于恢复到该版本
+    pub id: i64,
+    pub op: KvItemChangeKind,
+    pub key: String,
+    /// 变更后的值，删除时为空
+    pub value: Option<Value>,
+    /// 变更前的值，新增时为空
+    pub prev_value: Option<Value>,
+    pub info: String,
+    pub owner: String,
+    pub own_paths: String,
+    pub disable: bool,
+    pub scope_level: i16,
+    /// 变更后Item的版本号
+    pub item_revision: i64,
+    pub expire_time: Option<DateTime<Utc>>,
+    /// 操作人
+    pub operator: String,
+    pub create_time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvItemRestoreReq {
+    #[oai(validator(min_length = "2"))]
+    pub key: TrimString,
+    /// 要恢复到的历史记录ID
+    pub history_id: i64,
+    /// 期望的当前版本号，不一致时恢复失败并返回冲突
+    #[oai(validator(minimum(value = "0")))]
+    pub expected_revision: Option<i64>,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
 pub struct KvItemMatchReq {
     pub key_prefix: String,
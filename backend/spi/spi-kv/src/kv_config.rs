@@ -1,5 +1,6 @@
 use bios_basic::rbum::rbum_config::RbumConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -7,6 +8,16 @@ use std::fmt::Debug;
 pub struct KvConfig {
     pub rbum: RbumConfig,
     pub cache_key_async_task_status: String,
+    /// Interval of sweeping expired items and compacting the change log and the history, `0` disables the sweep
+    pub expired_item_sweep_interval_sec: u32,
+    /// How long the changes are kept for watchers to resume from
+    pub change_retention_sec: u32,
+    /// Interval of polling the change log for watchers
+    pub watch_poll_interval_ms: u64,
+    /// How long the history of items is kept, `0` keeps it forever
+    pub history_retention_sec: u32,
+    /// History retention by key prefix overriding `history_retention_sec`, the longest matching prefix wins
+    pub history_retention_by_key_prefix: HashMap<String, u32>,
 }
 
 impl Default for KvConfig {
@@ -14,6 +25,11 @@ impl Default for KvConfig {
         KvConfig {
             rbum: Default::default(),
             cache_key_async_task_status: "iam:cache:task:status".to_string(),
+            expired_item_sweep_interval_sec: 60,
+            change_retention_sec: 7 * 24 * 60 * 60,
+            watch_poll_interval_ms: 1000,
+            history_retention_sec: 90 * 24 * 60 * 60,
+            history_retention_by_key_prefix: HashMap::new(),
         }
     }
 }
@@ -1,3 +1,7 @@
 pub const DOMAIN_CODE: &str = "spi-kv";
 pub const KEY_PREFIX_BY_KEY_NAME: &str = "__k_n__:";
 pub const KEY_PREFIX_BY_TAG: &str = "__tag__:";
+/// Max number of changes fetched by a poll of the watch
+pub const WATCH_BATCH_SIZE: u32 = 100;
+/// Key of the advisory lock electing the node which sweeps expired items
+pub const SWEEPER_LOCK_KEY: &str = "spi-kv/sweeper";
@@ -10,6 +10,7 @@ use crate::{
     api::ci::{kv_ci_item_api, kv_ci_transfer_api},
     kv_config::KvConfig,
     kv_constants::DOMAIN_CODE,
+    serv::kv_item_serv,
 };
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
@@ -21,6 +22,8 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     init_db(&funs, &ctx).await?;
     funs.commit().await?;
     init_api(web_server).await?;
+    let conf = funs.conf::<KvConfig>();
+    kv_item_serv::start_sweeper(conf.expired_item_sweep_interval_sec, conf.change_retention_sec);
     info!("[BIOS.KV] Module initialized");
     Ok(())
 }
@@ -1,13 +1,29 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::chrono::{DateTime, Utc};
+use tardis::db::reldb_client::TardisRelDBlConnection;
+use tardis::db::sea_orm::Value;
+use tardis::futures::stream::{self, BoxStream, StreamExt};
+use tardis::log::{trace, warn};
+use tardis::tokio::{self, task::JoinHandle, time};
 use tardis::web::web_resp::TardisPage;
+use tardis::{TardisFuns, TardisFunsInst};
 
 use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
+use bios_basic::spi::spi_initializer;
 use bios_basic::spi_dispatch_service;
 
 use crate::dto::kv_item_dto::{
-    KvItemAddOrModifyReq, KvItemDetailResp, KvItemMatchReq, KvItemSummaryResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
+    KvItemAddOrModifyReq, KvItemChangeResp, KvItemChangesResp, KvItemDetailResp, KvItemHistoryResp, KvItemMatchReq, KvItemRestoreReq, KvItemSummaryResp, KvNameAddOrModifyReq,
+    KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
 };
+use crate::kv_config::KvConfig;
+use crate::kv_constants::{SWEEPER_LOCK_KEY, WATCH_BATCH_SIZE};
 use crate::kv_initializer;
 
 use super::pg;
@@ -23,8 +39,8 @@ spi_dispatch_service! {
         add_or_modify_item(add_or_modify_req: &mut KvItemAddOrModifyReq) -> TardisResult<()>;
         add_or_modify_key_name(add_or_modify_req: &mut KvNameAddOrModifyReq) -> TardisResult<()>;
         add_or_modify_tag(add_or_modify_req: &mut KvTagAddOrModifyReq) -> TardisResult<()>;
-        get_item(key: String, extract: Option<String>) -> TardisResult<Option<KvItemDetailResp>>;
-        find_items(keys: Vec<String>, extract: Option<String>) -> TardisResult<Vec<KvItemSummaryResp>>;
+        get_item(key: String, extract: Option<String>, as_of: Option<DateTime<Utc>>) -> TardisResult<Option<KvItemDetailResp>>;
+        find_items(keys: Vec<String>, extract: Option<String>, as_of: Option<DateTime<Utc>>) -> TardisResult<Vec<KvItemSummaryResp>>;
         find_key_names(keys: Vec<String>) -> TardisResult<Vec<KvNameFindResp>>;
         find_tags(keys: Vec<String>) -> TardisResult<Vec<KvTagFindResp>>;
         page_tags(
@@ -40,5 +56,123 @@ spi_dispatch_service! {
         delete_item(key: String) -> TardisResult<()>;
         disable_item(key: String) -> TardisResult<()>;
         enabled_item(key: String) -> TardisResult<()>;
+        sweep_expired_items() -> TardisResult<u64>;
+        compact_changes(retention_sec: u32) -> TardisResult<u64>;
+        find_changes(key_prefix: String, from_revision: Option<i64>, size: u32) -> TardisResult<KvItemChangesResp>;
+        find_history(key: String, page_number: u32, page_size: u16) -> TardisResult<TardisPage<KvItemHistoryResp>>;
+        restore_item(restore_req: &mut KvItemRestoreReq) -> TardisResult<()>;
+        compact_history(retention_sec: u32, retention_by_key_prefix: HashMap<String, u32>) -> TardisResult<u64>;
+    }
+}
+
+/// Stream the changes of items with the key prefix, starting after `from_revision` or the latest revision if not set.
+///
+/// The change log is polled, so watchers on any node get the changes committed by all nodes.
+pub async fn watch_changes(key_prefix: String, from_revision: Option<i64>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<BoxStream<'static, KvItemChangeResp>> {
+    // fail fast if the revision is compacted
+    let first = find_changes(key_prefix.clone(), from_revision, WATCH_BATCH_SIZE, funs, ctx).await?;
+    let poll_interval = Duration::from_millis(funs.conf::<KvConfig>().watch_poll_interval_ms);
+    let stream = stream::unfold((first, ctx.clone()), move |(mut resp, ctx)| {
+        let key_prefix = key_prefix.clone();
+        async move {
+            while resp.changes.is_empty() {
+                time::sleep(poll_interval).await;
+                let funs = crate::get_tardis_inst();
+                resp = match find_changes(key_prefix.clone(), Some(resp.revision), WATCH_BATCH_SIZE, &funs, &ctx).await {
+                    Ok(resp) => resp,
+                    Err(error) => {
+                        // the client should resume from the last received revision
+                        warn!("[SPI-KV] watch on {key_prefix} stopped: {error}");
+                        return None;
+                    }
+                };
+            }
+            let changes = std::mem::take(&mut resp.changes);
+            Some((stream::iter(changes), (resp, ctx)))
+        }
+    })
+    .flatten();
+    Ok(stream.boxed())
+}
+
+static SWEEP_TASK: OnceLock<JoinHandle<()>> = OnceLock::new();
+
+/// Find the contexts to access the item tables by the schemas holding them, the schema name is derived from the `ak` of the context.
+///
+/// Only the tables in the database of the module are found, tables of private backend services are not swept,
+/// expired items there are still hidden from reads.
+async fn find_sweep_contexts(conn: &TardisRelDBlConnection) -> TardisResult<Vec<TardisContext>> {
+    let isolation_flag = spi_initializer::common::get_isolation_flag_from_context(&TardisContext::default());
+    let table_name = spi_initializer::common_pg::package_table_name("kv", &TardisContext::default());
+    let table_name = table_name.rsplit('.').next().unwrap_or_default();
+    let schemas = conn
+        .query_all(
+            "SELECT table_schema FROM information_schema.tables WHERE table_name = $1 AND table_schema LIKE $2",
+            vec![Value::from(table_name), Value::from(format!("{isolation_flag}%"))],
+        )
+        .await?;
+    let mut contexts = Vec::with_capacity(schemas.len());
+    for schema in schemas {
+        let schema_name: String = schema.try_get("", "table_schema")?;
+        let Some(Ok(ak)) = schema_name.strip_prefix(&isolation_flag).and_then(|ak| TardisFuns::crypto.hex.decode(ak).ok()).map(String::from_utf8) else {
+            continue;
+        };
+        contexts.push(TardisContext { ak, ..Default::default() });
+    }
+    Ok(contexts)
+}
+
+/// Sweep all item tables if no other node is sweeping, the election lasts until the sweep ends
+async fn sweep_all(change_retention_sec: u32) -> TardisResult<()> {
+    let funs = crate::get_tardis_inst();
+    let conf = funs.conf::<KvConfig>();
+    let mut conn = funs.reldb().conn();
+    conn.begin().await?;
+    let elected = conn
+        .query_one("SELECT pg_try_advisory_xact_lock(hashtext($1)) AS elected", vec![Value::from(SWEEPER_LOCK_KEY)])
+        .await?
+        .map(|result| result.try_get::<bool>("", "elected"))
+        .transpose()?
+        .unwrap_or(false);
+    if !elected {
+        conn.commit().await?;
+        return Ok(());
+    }
+    for ctx in find_sweep_contexts(&conn).await? {
+        let ak = &ctx.ak;
+        match sweep_expired_items(&funs, &ctx).await {
+            Ok(count) => trace!("[SPI-KV] {count} expired items are swept for {ak}"),
+            Err(error) => warn!("[SPI-KV] failed to sweep expired items for {ak}: {error}"),
+        }
+        match compact_changes(change_retention_sec, &funs, &ctx).await {
+            Ok(count) => trace!("[SPI-KV] {count} changes for {ak} are compacted"),
+            Err(error) => warn!("[SPI-KV] failed to compact changes for {ak}: {error}"),
+        }
+        match compact_history(conf.history_retention_sec, conf.history_retention_by_key_prefix.clone(), &funs, &ctx).await {
+            Ok(count) => trace!("[SPI-KV] {count} history records for {ak} are compacted"),
+            Err(error) => warn!("[SPI-KV] failed to compact history for {ak}: {error}"),
+        }
+    }
+    conn.commit().await?;
+    Ok(())
+}
+
+/// Start the task which deletes expired items and compacts the change log and the history periodically, only the first call takes effect.
+///
+/// Each node starts the task, but only one of them sweeps at a time, see [`sweep_all`].
+pub fn start_sweeper(interval_sec: u32, change_retention_sec: u32) {
+    if interval_sec == 0 {
+        return;
     }
+    SWEEP_TASK.get_or_init(|| {
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(interval_sec as u64));
+            loop {
+                interval.tick().await;
+                if let Err(error) = sweep_all(change_retention_sec).await {
+                    warn!("[SPI-KV] failed to sweep: {error}");
+                }
+            }
+        })
+    });
 }
@@ -6,11 +6,12 @@ use tardis::db::sea_orm::Value;
 use tardis::TardisFunsInst;
 use tardis::{basic::dto::TardisContext, db::reldb_client::TardisRelDBClient};
 
+use crate::dto::kv_item_dto::KvItemChangeKind;
 use crate::dto::kv_transfer_dto::{KvExportAggResp, KvExportDataReq, KvExportDataResp, KvImportAggReq, KvImportDataReq};
 use crate::kv_config::KvConfig;
 use crate::kv_initializer;
 
-use super::pg::kv_pg_initializer;
+use super::pg::{kv_pg_initializer, kv_pg_item_serv};
 
 pub async fn export_data(export_req: &KvExportDataReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<KvExportDataResp> {
     let inst_arc = funs.init(None, ctx, true, kv_initializer::init_fun).await?;
@@ -21,7 +22,7 @@ pub async fn export_data(export_req: &KvExportDataReq, funs: &TardisFunsInst, ct
             &format!(
                 r#"SELECT k AS key, v AS value, info, owner, own_paths, disable, scope_level, create_time, update_time
 FROM {}
-WHERE ((create_time > $1 and create_time < $2) or (update_time > $1 and update_time <= $2)) AND k NOT LIKE 'flow:config:%'
+WHERE ((create_time > $1 and create_time < $2) or (update_time > $1 and update_time <= $2)) AND k NOT LIKE 'flow:config:%' AND (expire_time IS NULL OR expire_time > now())
 ORDER BY create_time DESC
 "#,
                 table_name
@@ -58,12 +59,14 @@ pub async fn import_data(receive_req: &KvImportDataReq, funs: &TardisFunsInst, c
 pub async fn import_kv(kv_data: Vec<KvImportAggReq>, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let log_tables = kv_pg_item_serv::init_log_tables(inst, ctx).await?;
     conn.begin().await?;
+    kv_pg_item_serv::lock_change_log(&conn, &log_tables.change_table_name).await?;
     for kv in &kv_data {
         let sql = format!(
-            r#"INSERT INTO {} (k, v, info, owner, own_paths, disable, scope_level, create_time, update_time)
+            r#"INSERT INTO {} AS t (k, v, info, owner, own_paths, disable, scope_level, create_time, update_time)
 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) 
-ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v, info = EXCLUDED.info, owner = EXCLUDED.owner, own_paths = EXCLUDED.own_paths, disable = EXCLUDED.disable, scope_level = EXCLUDED.scope_level, create_time = EXCLUDED.create_time, update_time = EXCLUDED.update_time"#,
+ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v, info = EXCLUDED.info, owner = EXCLUDED.owner, own_paths = EXCLUDED.own_paths, disable = EXCLUDED.disable, scope_level = EXCLUDED.scope_level, create_time = EXCLUDED.create_time, update_time = EXCLUDED.update_time, revision = t.revision + 1, expire_time = NULL"#,
             table_name
         );
         let params = vec![
@@ -76,8 +79,9 @@ ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v, info = EXCLUDED.info, owner = EXCL
             Value::from(kv.scope_level),
             Value::from(kv.create_time),
             Value::from(kv.update_time),
+            Value::from(ctx.owner.clone()),
         ];
-        conn.execute_one(&sql, params).await?;
+        conn.execute_one(&kv_pg_item_serv::with_change_log(&sql, &table_name, &log_tables, KvItemChangeKind::Put, 10), params).await?;
     }
     conn.commit().await?;
     Ok(true)
@@ -1,11 +1,22 @@
+use std::{
+    collections::HashSet,
+    sync::{Mutex, OnceLock},
+};
+
 use bios_basic::spi::{spi_funs::TypedSpiBsInst, spi_initializer};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
 };
 
+/// Tables already checked by [`upgrade_table`] in this process
+static UPGRADED_TABLES: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
 pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
-    spi_initializer::common_pg::init_table_and_conn(
+    let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
         ctx,
         mgr,
@@ -18,12 +29,111 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
     owner VARCHAR(255) NULL,
     scope_level SMALLINT NULL,
     disable BOOLEAN NOT NULL,
+    revision BIGINT NOT NULL DEFAULT 1,
+    expire_time timestamp with time zone NULL,
     create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
         None,
-        vec![("k", "btree"), ("v", "gin")],
+        vec![("k", "btree"), ("v", "gin"), ("expire_time", "btree")],
         None,
         Some("update_time"),
     )
+    .await?;
+    upgrade_table(&conn, &table_name).await?;
+    Ok((conn, table_name))
+}
+
+/// Change log of the items, `id` is the revision of the change feed
+pub async fn init_change_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "kv_change",
+        r#"id BIGSERIAL PRIMARY KEY,
+    k character varying NOT NULL,
+    op character varying NOT NULL,
+    v jsonb NULL,
+    own_paths VARCHAR(255) NULL,
+    scope_level SMALLINT NULL,
+    item_revision BIGINT NOT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        None,
+        vec![("k", "btree"), ("create_time", "btree")],
+        None,
+        None,
+    )
+    .await
+}
+
+/// History of the items, each row is the state of an item after a change
+pub async fn init_history_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "kv_history",
+        r#"id BIGSERIAL PRIMARY KEY,
+    k character varying NOT NULL,
+    op character varying NOT NULL,
+    v jsonb NULL,
+    prev_v jsonb NULL,
+    info character varying NOT NULL,
+    owner VARCHAR(255) NULL,
+    own_paths VARCHAR(255) NULL,
+    scope_level SMALLINT NULL,
+    disable BOOLEAN NOT NULL,
+    item_revision BIGINT NOT NULL,
+    expire_time timestamp with time zone NULL,
+    item_create_time timestamp with time zone NOT NULL,
+    operator VARCHAR(255) NOT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        None,
+        vec![("k", "btree"), ("create_time", "btree")],
+        None,
+        None,
+    )
     .await
 }
+
+/// Add the columns and indexes introduced after the table was created, checked once per table in this process
+async fn upgrade_table(conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<()> {
+    let upgraded_tables = UPGRADED_TABLES.get_or_init(Default::default);
+    if upgraded_tables.lock().map(|tables| tables.contains(table_name)).unwrap_or(false) {
+        return Ok(());
+    }
+    conn.execute_one(
+        &format!(
+            r#"ALTER TABLE {table_name}
+    ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 1,
+    ADD COLUMN IF NOT EXISTS expire_time timestamp with time zone NULL"#
+        ),
+        vec![],
+    )
+    .await?;
+    // new tables get the index on creation, see `init_table_and_conn`
+    let has_expire_time_index = conn
+        .query_one(
+            "SELECT 1 FROM pg_indexes WHERE schemaname || '.' || tablename = $1 AND indexdef LIKE '%(expire_time)%'",
+            vec![Value::from(table_name)],
+        )
+        .await?
+        .is_some();
+    if !has_expire_time_index {
+        let index_name = format!("idx_{}_expire_time", table_name.replace('.', "_"));
+        conn.execute_one(
+            &format!(
+                "CREATE INDEX IF NOT EXISTS {} ON {table_name} USING btree(expire_time)",
+                &index_name[..index_name.len().min(63)]
+            ),
+            vec![],
+        )
+        .await?;
+    }
+    if let Ok(mut tables) = upgraded_tables.lock() {
+        tables.insert(table_name.to_string());
+    }
+    Ok(())
+}
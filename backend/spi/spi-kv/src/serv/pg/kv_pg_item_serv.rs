@@ -1,24 +1,108 @@
+use std::collections::HashMap;
+
 use bios_basic::{
     rbum::{dto::rbum_filer_dto::RbumBasicFilterReq, helper::rbum_scope_helper},
     spi::spi_funs::{SpiBsInst, SpiBsInstExtractor},
 };
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    db::{reldb_client::TardisRelDBClient, sea_orm::Value},
+    chrono::{DateTime, Duration, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
     serde_json::json,
     web::web_resp::TardisPage,
     TardisFuns, TardisFunsInst,
 };
 
 use crate::{
-    dto::kv_item_dto::{KvItemAddOrModifyReq, KvItemDetailResp, KvItemMatchReq, KvItemSummaryResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp},
+    dto::kv_item_dto::{
+        KvItemAddOrModifyReq, KvItemChangeKind, KvItemChangeResp, KvItemChangesResp, KvItemDetailResp, KvItemHistoryResp, KvItemMatchReq, KvItemRestoreReq, KvItemSummaryResp,
+        KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
+    },
     kv_constants,
 };
 
 use super::kv_pg_initializer;
 
-pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
-    let params = vec![
+const NOT_EXPIRED_FRAGMENT: &str = "(expire_time IS NULL OR expire_time > now())";
+
+/// Lock the change log until the transaction ends, so that the revisions of the change log are committed in order.
+///
+/// Writers take the revisions one after another, so a change with a smaller revision is always committed before a greater one,
+/// see [`committed_max_revision`] for how a watcher relies on it to never skip a change.
+pub(crate) async fn lock_change_log(conn: &TardisRelDBlConnection, change_table_name: &str) -> TardisResult<()> {
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(change_table_name)]).await?;
+    Ok(())
+}
+
+/// Get the max revision of the change log below which all changes are committed.
+///
+/// The revisions are committed in order, see [`lock_change_log`], so all changes up to the max revision visible are committed,
+/// watchers read it without taking any lock.
+async fn committed_max_revision(conn: &TardisRelDBlConnection, change_table_name: &str) -> TardisResult<(i64, i64)> {
+    let stat = conn
+        .query_one(
+            &format!("SELECT COALESCE(MIN(id), 0) AS min_revision, COALESCE(MAX(id), 0) AS max_revision FROM {change_table_name}"),
+            vec![],
+        )
+        .await?;
+    Ok(match stat {
+        Some(stat) => (stat.try_get::<i64>("", "min_revision")?, stat.try_get::<i64>("", "max_revision")?),
+        None => (0, 0),
+    })
+}
+
+/// The `LIKE` pattern matching the keys starting with the prefix, the wildcards in the prefix are escaped
+fn like_prefix(key_prefix: &str) -> String {
+    format!("{}%", key_prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))
+}
+
+/// Tables recording the changes of the items, see [`with_change_log`]
+pub(crate) struct KvLogTables {
+    pub change_table_name: String,
+    pub history_table_name: String,
+}
+
+pub(crate) async fn init_log_tables(inst: &SpiBsInst, ctx: &TardisContext) -> TardisResult<KvLogTables> {
+    let (_, change_table_name) = kv_pg_initializer::init_change_table_and_conn(inst.inst::<TardisRelDBClient>(), ctx, true).await?;
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(inst.inst::<TardisRelDBClient>(), ctx, true).await?;
+    Ok(KvLogTables {
+        change_table_name,
+        history_table_name,
+    })
+}
+
+/// Wrap a statement on the item table to record the changed items into the change log and the history,
+/// the statement must not have a `RETURNING` clause, `operator_param` is the index of the parameter holding the operator.
+///
+/// All sub-statements see the same snapshot, so `prev` holds the values of the changed keys before the statement.
+pub(crate) fn with_change_log(sql: &str, table_name: &str, log_tables: &KvLogTables, op: KvItemChangeKind, operator_param: usize) -> String {
+    format!(
+        r#"WITH changed AS (
+{sql}
+RETURNING k, v, info, owner, own_paths, scope_level, disable, revision, expire_time, create_time
+),
+prev AS (
+    SELECT k, v FROM {table_name} WHERE k IN (SELECT k FROM changed)
+),
+history AS (
+    INSERT INTO {} (k, op, v, prev_v, info, owner, own_paths, scope_level, disable, item_revision, expire_time, item_create_time, operator)
+    SELECT changed.k, '{op}', {}, prev.v, changed.info, changed.owner, changed.own_paths, changed.scope_level, changed.disable, changed.revision, changed.expire_time, changed.create_time, ${operator_param}
+    FROM changed LEFT JOIN prev ON prev.k = changed.k
+)
+INSERT INTO {} (k, op, v, own_paths, scope_level, item_revision)
+SELECT k, '{op}', v, own_paths, scope_level, revision FROM changed"#,
+        log_tables.history_table_name,
+        if op == KvItemChangeKind::Delete { "NULL" } else { "changed.v" },
+        log_tables.change_table_name,
+        op = op.as_str(),
+    )
+}
+
+pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut params = vec![
         Value::from(add_or_modify_req.key.to_string()),
         Value::from(add_or_modify_req.value.clone()),
         Value::from(add_or_modify_req.info.as_ref().unwrap_or(&"".to_string()).as_str()),
@@ -26,6 +110,9 @@ pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, _funs:
         Value::from(ctx.own_paths.clone()),
         Value::from(add_or_modify_req.disable.unwrap_or(false)),
         Value::from(add_or_modify_req.scope_level.unwrap_or(0)),
+        Value::from(
+            add_or_modify_req.ttl_sec.filter(|ttl_sec| *ttl_sec > 0).map(|ttl_sec| Utc::now() + Duration::try_seconds(ttl_sec as i64).expect("TimeDelta::seconds out of bounds")),
+        ),
     ];
     let mut update_opt_fragments: Vec<&str> = Vec::new();
     update_opt_fragments.push("v = $2");
@@ -40,25 +127,92 @@ pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, _funs:
     if add_or_modify_req.scope_level.is_some() {
         update_opt_fragments.push("scope_level = $7");
     }
+    if add_or_modify_req.ttl_sec.is_some() {
+        update_opt_fragments.push("expire_time = $8");
+    }
+    update_opt_fragments.push("revision = t.revision + 1");
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let log_tables = init_log_tables(inst, ctx).await?;
     conn.begin().await?;
+    lock_change_log(&conn, &log_tables.change_table_name).await?;
+    // an expired item is treated as not existing
     conn.execute_one(
-        &format!(
-            r#"INSERT INTO {} 
-    (k, v, info, owner, own_paths, disable, scope_level)
+        &with_change_log(
+            &format!("DELETE FROM {table_name} WHERE k = $1 AND expire_time <= now()"),
+            &table_name,
+            &log_tables,
+            KvItemChangeKind::Delete,
+            2,
+        ),
+        vec![Value::from(add_or_modify_req.key.to_string()), Value::from(ctx.owner.clone())],
+    )
+    .await?;
+    let insert_sql = format!(
+        r#"INSERT INTO {table_name} AS t
+    (k, v, info, owner, own_paths, disable, scope_level, expire_time)
 VALUES
-    ($1, $2, $3, $4, $5, $6, $7)
+    ($1, $2, $3, $4, $5, $6, $7, $8)"#
+    );
+    let affected_rows = match add_or_modify_req.expected_revision {
+        None => {
+            conn.execute_one(
+                &with_change_log(
+                    &format!(
+                        r#"{insert_sql}
 ON CONFLICT (k)
 DO UPDATE SET
+    {}"#,
+                        update_opt_fragments.join(", ")
+                    ),
+                    &table_name,
+                    &log_tables,
+                    KvItemChangeKind::Put,
+                    4,
+                ),
+                params,
+            )
+            .await?
+        }
+        Some(0) => {
+            conn.execute_one(
+                &with_change_log(&format!("{insert_sql}\nON CONFLICT (k) DO NOTHING"), &table_name, &log_tables, KvItemChangeKind::Put, 4),
+                params,
+            )
+            .await?
+        }
+        Some(expected_revision) => {
+            params.push(Value::from(expected_revision));
+            conn.execute_one(
+                &with_change_log(
+                    &format!(
+                        r#"UPDATE {table_name} AS t
+SET
     {}
-"#,
-            table_name,
-            update_opt_fragments.join(", ")
-        ),
-        params,
-    )
-    .await?;
+WHERE
+    t.k = $1 AND t.revision = $9"#,
+                        update_opt_fragments.join(", ")
+                    ),
+                    &table_name,
+                    &log_tables,
+                    KvItemChangeKind::Put,
+                    4,
+                ),
+                params,
+            )
+            .await?
+        }
+    }
+    .rows_affected();
+    if affected_rows == 0 {
+        conn.rollback().await?;
+        return Err(funs.err().conflict(
+            "kv_item",
+            "add_or_modify",
+            &format!("item {} doesn't match the expected revision", add_or_modify_req.key),
+            "409-spi-kv-revision-conflict",
+        ));
+    }
     conn.commit().await?;
     Ok(())
 }
@@ -70,6 +224,8 @@ pub async fn add_or_modify_key_name(add_or_modify_req: &mut KvNameAddOrModifyReq
         scope_level: add_or_modify_req.scope_level,
         disable: add_or_modify_req.disable,
         info: None,
+        expected_revision: None,
+        ttl_sec: None,
     };
     self::add_or_modify_item(&req, funs, ctx, inst).await
 }
@@ -81,26 +237,56 @@ pub async fn add_or_modify_tag(add_or_modify_req: &mut KvTagAddOrModifyReq, funs
         scope_level: add_or_modify_req.scope_level,
         info: None,
         disable: add_or_modify_req.disable,
+        expected_revision: None,
+        ttl_sec: None,
     };
     self::add_or_modify_item(&req, funs, ctx, inst).await
 }
 
-pub async fn get_item(key: String, extract: Option<String>, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<KvItemDetailResp>> {
+/// Select the items as they were at the time in the `as_of_param` parameter from the history, items deleted or expired at that time are excluded
+fn as_of_sql(history_table_name: &str, extract: &str, key_fragment: &str, as_of_param: usize) -> String {
+    format!(
+        r#"SELECT key, value, info, owner, own_paths, disable, scope_level, revision, expire_time, create_time, update_time
+FROM (
+    SELECT DISTINCT ON (k) k AS key, op, v{extract} AS value, info, owner, own_paths, disable, scope_level, item_revision AS revision, expire_time, item_create_time AS create_time, create_time AS update_time
+    FROM {history_table_name}
+    WHERE
+        {key_fragment} AND create_time <= ${as_of_param}
+    ORDER BY k, id DESC
+) AS h
+WHERE
+    op <> '{}' AND (expire_time IS NULL OR expire_time > ${as_of_param})"#,
+        KvItemChangeKind::Delete.as_str()
+    )
+}
+
+pub async fn get_item(
+    key: String,
+    extract: Option<String>,
+    as_of: Option<DateTime<Utc>>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Option<KvItemDetailResp>> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
-    let result = conn
-        .get_dto_by_sql::<KvItemDetailResp>(
+    let extract = if let Some(extract) = extract { format!("->'{extract}'") } else { "".to_string() };
+    let result = if let Some(as_of) = as_of {
+        let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(inst.inst::<TardisRelDBClient>(), ctx, true).await?;
+        conn.get_dto_by_sql::<KvItemDetailResp>(&as_of_sql(&history_table_name, &extract, "k = $1", 2), vec![Value::from(key), Value::from(as_of)]).await?
+    } else {
+        conn.get_dto_by_sql::<KvItemDetailResp>(
             &format!(
-                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, create_time, update_time
+                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, revision, expire_time, create_time, update_time
 FROM {}
 WHERE 
-    k = $1"#,
-                if let Some(extract) = extract { format!("->'{extract}'") } else { "".to_string() },
-                table_name,
+    k = $1 AND {}"#,
+                extract, table_name, NOT_EXPIRED_FRAGMENT,
             ),
             vec![Value::from(key)],
         )
-        .await?;
+        .await?
+    };
     if let Some(detail) = result.as_ref() {
         if !rbum_scope_helper::check_scope(
             &detail.own_paths,
@@ -117,7 +303,14 @@ WHERE
     Ok(result)
 }
 
-pub async fn find_items(keys: Vec<String>, extract: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<Vec<KvItemSummaryResp>> {
+pub async fn find_items(
+    keys: Vec<String>,
+    extract: Option<String>,
+    as_of: Option<DateTime<Utc>>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    _inst: &SpiBsInst,
+) -> TardisResult<Vec<KvItemSummaryResp>> {
     let mut sql_vals: Vec<Value> = vec![];
     let place_holder = keys
         .iter()
@@ -130,19 +323,22 @@ pub async fn find_items(keys: Vec<String>, extract: Option<String>, funs: &Tardi
     let inst_arc = funs.bs(ctx).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
     let (conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
-    let result = conn
-        .find_dtos_by_sql::<KvItemSummaryResp>(
-            &format!(
-                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, create_time, update_time
+    let extract = if let Some(extract) = extract { format!("->'{extract}'") } else { "".to_string() };
+    let sql = if let Some(as_of) = as_of {
+        let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(inst_arc.inst::<TardisRelDBClient>(), ctx, true).await?;
+        sql_vals.push(Value::from(as_of));
+        as_of_sql(&history_table_name, &extract, &format!("k IN ({place_holder})"), sql_vals.len())
+    } else {
+        format!(
+            r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, revision, expire_time, create_time, update_time
 FROM {}
 WHERE 
-    k IN ({})"#,
-                if let Some(extract) = extract { format!("->'{extract}'") } else { "".to_string() },
-                table_name,
-                place_holder
-            ),
-            sql_vals,
+    k IN ({}) AND {}"#,
+            extract, table_name, place_holder, NOT_EXPIRED_FRAGMENT,
         )
+    };
+    let result = conn
+        .find_dtos_by_sql::<KvItemSummaryResp>(&sql, sql_vals)
         .await?
         .into_iter()
         .filter(|item| {
@@ -162,7 +358,7 @@ WHERE
 
 pub async fn find_key_names(keys: Vec<String>, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<KvNameFindResp>> {
     let keys = keys.into_iter().map(|key| format!("{}{}", kv_constants::KEY_PREFIX_BY_KEY_NAME, key)).collect();
-    self::find_items(keys, None, funs, ctx, inst).await.and_then(|items| {
+    self::find_items(keys, None, None, funs, ctx, inst).await.and_then(|items| {
         items
             .into_iter()
             .map::<TardisResult<KvNameFindResp>, _>(|item| {
@@ -180,7 +376,7 @@ pub async fn find_key_names(keys: Vec<String>, funs: &TardisFunsInst, ctx: &Tard
 
 pub async fn find_tags(keys: Vec<String>, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<KvTagFindResp>> {
     let keys = keys.iter().map(|r| format!("{}{}", kv_constants::KEY_PREFIX_BY_TAG, r)).collect::<Vec<_>>();
-    self::find_items(keys, None, funs, ctx, inst).await.and_then(|items| {
+    self::find_items(keys, None, None, funs, ctx, inst).await.and_then(|items| {
         items
             .into_iter()
             .map(|item| {
@@ -254,6 +450,7 @@ pub async fn match_items(match_req: KvItemMatchReq, _funs: &TardisFunsInst, ctx:
         sql_vals.push(Value::from(disable));
         where_fragments.push(format!("disable = ${}", sql_vals.len()));
     }
+    where_fragments.push(NOT_EXPIRED_FRAGMENT.to_string());
     if let Some(desc_sort_by_create) = match_req.desc_sort_by_create {
         order_fragments.push(format!("create_time {}", if desc_sort_by_create { "DESC" } else { "ASC" }));
     }
@@ -270,7 +467,7 @@ pub async fn match_items(match_req: KvItemMatchReq, _funs: &TardisFunsInst, ctx:
     let result = conn
         .query_all(
             &format!(
-                r#"SELECT k, v{} AS v, info, owner, own_paths, disable, scope_level, create_time, update_time, count(*) OVER() AS total
+                r#"SELECT k, v{} AS v, info, owner, own_paths, disable, scope_level, revision, expire_time, create_time, update_time, count(*) OVER() AS total
 FROM {}
 WHERE 
     {}
@@ -310,6 +507,8 @@ WHERE
                 own_paths: item.try_get("", "own_paths")?,
                 disable: item.try_get("", "disable")?,
                 scope_level: item.try_get("", "scope_level")?,
+                revision: item.try_get("", "revision")?,
+                expire_time: item.try_get("", "expire_time")?,
                 create_time: item.try_get("", "create_time")?,
                 update_time: item.try_get("", "update_time")?,
             })
@@ -338,8 +537,14 @@ WHERE
 pub async fn delete_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let log_tables = init_log_tables(inst, ctx).await?;
     conn.begin().await?;
-    conn.execute_one(&format!("DELETE FROM {table_name} WHERE k = $1"), vec![Value::from(key)]).await?;
+    lock_change_log(&conn, &log_tables.change_table_name).await?;
+    conn.execute_one(
+        &with_change_log(&format!("DELETE FROM {table_name} WHERE k = $1"), &table_name, &log_tables, KvItemChangeKind::Delete, 2),
+        vec![Value::from(key), Value::from(ctx.owner.clone())],
+    )
+    .await?;
     conn.commit().await?;
     Ok(())
 }
@@ -347,8 +552,20 @@ pub async fn delete_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContex
 pub async fn disable_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let log_tables = init_log_tables(inst, ctx).await?;
     conn.begin().await?;
-    conn.execute_one(&format!("UPDATE {table_name} SET disable = true WHERE k = $1"), vec![Value::from(key)]).await?;
+    lock_change_log(&conn, &log_tables.change_table_name).await?;
+    conn.execute_one(
+        &with_change_log(
+            &format!("UPDATE {table_name} SET disable = true, revision = revision + 1 WHERE k = $1"),
+            &table_name,
+            &log_tables,
+            KvItemChangeKind::Disable,
+            2,
+        ),
+        vec![Value::from(key), Value::from(ctx.owner.clone())],
+    )
+    .await?;
     conn.commit().await?;
     Ok(())
 }
@@ -356,8 +573,20 @@ pub async fn disable_item(key: String, _funs: &TardisFunsInst, ctx: &TardisConte
 pub async fn enabled_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let log_tables = init_log_tables(inst, ctx).await?;
     conn.begin().await?;
-    conn.execute_one(&format!("UPDATE {table_name} SET disable = false WHERE k = $1"), vec![Value::from(key)]).await?;
+    lock_change_log(&conn, &log_tables.change_table_name).await?;
+    conn.execute_one(
+        &with_change_log(
+            &format!("UPDATE {table_name} SET disable = false, revision = revision + 1 WHERE k = $1"),
+            &table_name,
+            &log_tables,
+            KvItemChangeKind::Enable,
+            2,
+        ),
+        vec![Value::from(key), Value::from(ctx.owner.clone())],
+    )
+    .await?;
     conn.commit().await?;
     Ok(())
 }
@@ -412,3 +641,340 @@ pub async fn page_tags(
         })
     })
 }
+
+pub async fn sweep_expired_items(_funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let log_tables = init_log_tables(inst, ctx).await?;
+    conn.begin().await?;
+    lock_change_log(&conn, &log_tables.change_table_name).await?;
+    let result = conn
+        .execute_one(
+            &with_change_log(
+                &format!("DELETE FROM {table_name} WHERE expire_time <= now()"),
+                &table_name,
+                &log_tables,
+                KvItemChangeKind::Delete,
+                1,
+            ),
+            // expired items are deleted by the system
+            vec![Value::from("")],
+        )
+        .await?;
+    conn.commit().await?;
+    Ok(result.rows_affected())
+}
+
+/// Delete changes older than the retention, the latest change is always kept to tell whether a revision is compacted
+pub async fn compact_changes(retention_sec: u32, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let (conn, change_table_name) = kv_pg_initializer::init_change_table_and_conn(inst.inst::<TardisRelDBClient>(), ctx, true).await?;
+    let result = conn
+        .execute_one(
+            &format!("DELETE FROM {change_table_name} WHERE create_time < $1 AND id < (SELECT MAX(id) FROM {change_table_name})"),
+            vec![Value::from(
+                Utc::now() - Duration::try_seconds(retention_sec as i64).expect("TimeDelta::seconds out of bounds"),
+            )],
+        )
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Split the history retention into rules of `(key prefix, retention, longer key prefixes with their own rules)`,
+/// the `None` key prefix is the default rule, so the retention of a key is decided by the longest matching key prefix.
+/// Rules keeping the history forever are omitted.
+fn history_retention_rules(retention_sec: u32, retention_by_key_prefix: &HashMap<String, u32>) -> Vec<(Option<String>, u32, Vec<String>)> {
+    let longer_key_prefixes = |key_prefix: &str| {
+        let mut key_prefixes = retention_by_key_prefix.keys().filter(|other| other.len() > key_prefix.len() && other.starts_with(key_prefix)).cloned().collect::<Vec<_>>();
+        key_prefixes.sort();
+        key_prefixes
+    };
+    let mut rules = vec![(None, retention_sec, longer_key_prefixes(""))];
+    rules.extend(retention_by_key_prefix.iter().map(|(key_prefix, retention_sec)| (Some(key_prefix.clone()), *retention_sec, longer_key_prefixes(key_prefix))));
+    rules.retain(|(_, retention_sec, _)| *retention_sec > 0);
+    rules.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+    rules
+}
+
+/// Delete the history older than the retention of its key, the latest history of an existing item is always kept for point-in-time reads
+pub async fn compact_history(
+    retention_sec: u32,
+    retention_by_key_prefix: HashMap<String, u32>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<u64> {
+    let (conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(inst.inst::<TardisRelDBClient>(), ctx, true).await?;
+    let mut count = 0;
+    for (key_prefix, retention_sec, excluded_key_prefixes) in history_retention_rules(retention_sec, &retention_by_key_prefix) {
+        let mut sql_vals = vec![Value::from(
+            Utc::now() - Duration::try_seconds(retention_sec as i64).expect("TimeDelta::seconds out of bounds"),
+        )];
+        let mut where_fragments = vec![
+            "h.create_time < $1".to_string(),
+            format!(
+                "(h.op = '{}' OR EXISTS (SELECT 1 FROM {history_table_name} AS newer WHERE newer.k = h.k AND newer.id > h.id))",
+                KvItemChangeKind::Delete.as_str()
+            ),
+        ];
+        if let Some(key_prefix) = key_prefix {
+            sql_vals.push(Value::from(like_prefix(&key_prefix)));
+            where_fragments.push(format!("h.k LIKE ${}", sql_vals.len()));
+        }
+        if !excluded_key_prefixes.is_empty() {
+            sql_vals.push(Value::from(
+                excluded_key_prefixes.into_iter().map(|key_prefix| like_prefix(&key_prefix)).collect::<Vec<_>>(),
+            ));
+            where_fragments.push(format!("NOT (h.k LIKE ANY(${}))", sql_vals.len()));
+        }
+        count += conn.execute_one(&format!("DELETE FROM {history_table_name} AS h WHERE {}", where_fragments.join(" AND ")), sql_vals).await?.rows_affected();
+    }
+    Ok(count)
+}
+
+pub async fn find_history(
+    key: String,
+    page_number: u32,
+    page_size: u16,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<KvItemHistoryResp>> {
+    let (conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(inst.inst::<TardisRelDBClient>(), ctx, true).await?;
+    let records = conn
+        .query_all(
+            &format!(
+                r#"SELECT id, k, op, v, prev_v, info, owner, own_paths, scope_level, disable, item_revision, expire_time, operator, create_time, count(*) OVER() AS total
+FROM {history_table_name}
+WHERE
+    k = $1
+ORDER BY id DESC
+LIMIT $2 OFFSET $3"#
+            ),
+            vec![Value::from(key), Value::from(page_size), Value::from((page_number.max(1) - 1) * page_size as u32)],
+        )
+        .await?;
+    let mut total_size: i64 = 0;
+    let mut history = Vec::with_capacity(records.len());
+    for record in records {
+        if total_size == 0 {
+            total_size = record.try_get("", "total")?;
+        }
+        let own_paths: Option<String> = record.try_get("", "own_paths")?;
+        let scope_level: Option<i16> = record.try_get("", "scope_level")?;
+        let own_paths = own_paths.unwrap_or_default();
+        let scope_level = scope_level.unwrap_or(0);
+        if !rbum_scope_helper::check_scope(
+            &own_paths,
+            Some(scope_level),
+            &RbumBasicFilterReq {
+                ignore_scope: false,
+                ..Default::default()
+            },
+            &ctx.own_paths,
+        ) {
+            continue;
+        }
+        let op: String = record.try_get("", "op")?;
+        let Some(op) = KvItemChangeKind::parse(&op) else {
+            continue;
+        };
+        let owner: Option<String> = record.try_get("", "owner")?;
+        history.push(KvItemHistoryResp {
+            id: record.try_get("", "id")?,
+            op,
+            key: record.try_get("", "k")?,
+            value: record.try_get("", "v")?,
+            prev_value: record.try_get("", "prev_v")?,
+            info: record.try_get("", "info")?,
+            owner: owner.unwrap_or_default(),
+            own_paths,
+            disable: record.try_get("", "disable")?,
+            scope_level,
+            item_revision: record.try_get("", "item_revision")?,
+            expire_time: record.try_get("", "expire_time")?,
+            operator: record.try_get("", "operator")?,
+            create_time: record.try_get("", "create_time")?,
+        });
+    }
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size: total_size as u64,
+        records: history,
+    })
+}
+
+/// Restore an item to the state recorded in its history, the restore itself is recorded as a new modification
+pub async fn restore_item(restore_req: &mut KvItemRestoreReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let (conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(inst.inst::<TardisRelDBClient>(), ctx, true).await?;
+    let history = conn
+        .query_one(
+            &format!("SELECT op, v, info, own_paths, scope_level, disable, expire_time FROM {history_table_name} WHERE id = $1 AND k = $2"),
+            vec![Value::from(restore_req.history_id), Value::from(restore_req.key.to_string())],
+        )
+        .await?;
+    let history = match history {
+        Some(history) => {
+            let own_paths: Option<String> = history.try_get("", "own_paths")?;
+            let scope_level: Option<i16> = history.try_get("", "scope_level")?;
+            if rbum_scope_helper::check_scope(
+                &own_paths.unwrap_or_default(),
+                scope_level,
+                &RbumBasicFilterReq {
+                    ignore_scope: false,
+                    ..Default::default()
+                },
+                &ctx.own_paths,
+            ) {
+                Some((history, scope_level))
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+    let Some((history, scope_level)) = history else {
+        return Err(funs.err().not_found(
+            "kv_item",
+            "restore",
+            &format!("history {} of item {} not found", restore_req.history_id, restore_req.key),
+            "404-spi-kv-history-not-exist",
+        ));
+    };
+    let op: String = history.try_get("", "op")?;
+    if op == KvItemChangeKind::Delete.as_str() {
+        return Err(funs.err().bad_request(
+            "kv_item",
+            "restore",
+            &format!("history {} of item {} is a deletion", restore_req.history_id, restore_req.key),
+            "400-spi-kv-history-deleted",
+        ));
+    }
+    let expire_time: Option<DateTime<Utc>> = history.try_get("", "expire_time")?;
+    let req = KvItemAddOrModifyReq {
+        key: restore_req.key.to_string().into(),
+        value: history.try_get("", "v")?,
+        disable: Some(history.try_get("", "disable")?),
+        info: Some(history.try_get("", "info")?),
+        scope_level,
+        expected_revision: restore_req.expected_revision,
+        // the remaining time to live is restored, an item expired since then never expires
+        ttl_sec: Some(
+            expire_time.map(|expire_time| (expire_time - Utc::now()).num_seconds()).filter(|ttl_sec| *ttl_sec > 0).map(|ttl_sec| ttl_sec.min(u32::MAX as i64) as u32).unwrap_or(0),
+        ),
+    };
+    self::add_or_modify_item(&req, funs, ctx, inst).await
+}
+
+pub async fn find_changes(
+    key_prefix: String,
+    from_revision: Option<i64>,
+    size: u32,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<KvItemChangesResp> {
+    let (conn, change_table_name) = kv_pg_initializer::init_change_table_and_conn(inst.inst::<TardisRelDBClient>(), ctx, true).await?;
+    let (min_revision, max_revision) = committed_max_revision(&conn, &change_table_name).await?;
+    // start from the latest revision
+    let Some(from_revision) = from_revision else {
+        return Ok(KvItemChangesResp {
+            changes: vec![],
+            revision: max_revision,
+        });
+    };
+    if min_revision > from_revision + 1 {
+        return Err(funs.err().conflict(
+            "kv_item",
+            "find_changes",
+            &format!("changes after revision {from_revision} are compacted, please reload the items"),
+            "409-spi-kv-revision-compacted",
+        ));
+    }
+    // changes not greater than `max_revision` are all committed, see `committed_max_revision`
+    let records = conn
+        .query_all(
+            &format!(
+                r#"SELECT id, k, op, v, own_paths, scope_level, item_revision, create_time
+FROM {change_table_name}
+WHERE
+    id > $1 AND id <= $2 AND k LIKE $3
+ORDER BY id
+LIMIT $4"#
+            ),
+            vec![
+                Value::from(from_revision),
+                Value::from(max_revision),
+                Value::from(like_prefix(&key_prefix)),
+                Value::from(size as i64),
+            ],
+        )
+        .await?;
+    let revision = if records.len() as u32 >= size {
+        records.last().map(|record| record.try_get::<i64>("", "id")).transpose()?.unwrap_or(from_revision)
+    } else {
+        max_revision.max(from_revision)
+    };
+    let mut changes = Vec::with_capacity(records.len());
+    for record in records {
+        let own_paths: Option<String> = record.try_get("", "own_paths")?;
+        let scope_level: Option<i16> = record.try_get("", "scope_level")?;
+        let own_paths = own_paths.unwrap_or_default();
+        let scope_level = scope_level.unwrap_or(0);
+        if !rbum_scope_helper::check_scope(
+            &own_paths,
+            Some(scope_level),
+            &RbumBasicFilterReq {
+                ignore_scope: false,
+                ..Default::default()
+            },
+            &ctx.own_paths,
+        ) {
+            continue;
+        }
+        let op: String = record.try_get("", "op")?;
+        let Some(op) = KvItemChangeKind::parse(&op) else {
+            continue;
+        };
+        changes.push(KvItemChangeResp {
+            revision: record.try_get("", "id")?,
+            op,
+            key: record.try_get("", "k")?,
+            value: record.try_get("", "v")?,
+            own_paths,
+            scope_level,
+            item_revision: record.try_get("", "item_revision")?,
+            create_time: record.try_get("", "create_time")?,
+        });
+    }
+    Ok(KvItemChangesResp { changes, revision })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{history_retention_rules, like_prefix};
+
+    #[test]
+    fn test_like_prefix() {
+        assert_eq!(like_prefix("a:"), "a:%");
+        assert_eq!(like_prefix("a_b%c\\"), "a\\_b\\%c\\\\%");
+    }
+
+    #[test]
+    fn test_history_retention_rules() {
+        assert!(history_retention_rules(0, &HashMap::new()).is_empty());
+        assert_eq!(history_retention_rules(60, &HashMap::new()), vec![(None, 60, vec![])]);
+        let retention_by_key_prefix = HashMap::from([("a:".to_string(), 10), ("a:b:".to_string(), 0), ("a:b:c:".to_string(), 30), ("d:".to_string(), 20)]);
+        assert_eq!(
+            history_retention_rules(60, &retention_by_key_prefix),
+            vec![
+                (None, 60, vec!["a:".to_string(), "a:b:".to_string(), "a:b:c:".to_string(), "d:".to_string()]),
+                (Some("a:".to_string()), 10, vec!["a:b:".to_string(), "a:b:c:".to_string()]),
+                (Some("a:b:c:".to_string()), 30, vec![]),
+                (Some("d:".to_string()), 20, vec![]),
+            ]
+        );
+    }
+}
@@ -1,9 +1,10 @@
 use std::time::Duration;
 
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_kv::dto::kv_item_dto::{KvItemDetailResp, KvItemSummaryResp, KvNameFindResp, KvTagFindResp};
+use bios_spi_kv::dto::kv_item_dto::{KvItemChangeKind, KvItemChangesResp, KvItemDetailResp, KvItemHistoryResp, KvItemSummaryResp, KvNameFindResp, KvTagFindResp};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::chrono::{SecondsFormat, Utc};
 use tardis::serde_json::json;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::{TardisPage, TardisResp, Void};
@@ -279,5 +280,126 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     assert_eq!(result.value, "postgres://xxxx");
     assert_eq!(result.info, "xx系统的数据库地址");
 
+    // revision
+    let _: Void = client.put("/ci/item", &json!({"key": "flow:cfg", "value": 1, "expected_revision": 0})).await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=flow:cfg").await;
+    assert_eq!(result.revision, 1);
+    assert!(result.expire_time.is_none());
+    let result: TardisResp<Void> = client.put_resp("/ci/item", &json!({"key": "flow:cfg", "value": 2, "expected_revision": 0})).await;
+    assert_eq!(result.code, "409-spi-kv-revision-conflict");
+    let _: Void = client.put("/ci/item", &json!({"key": "flow:cfg", "value": 2, "expected_revision": 1})).await;
+    let result: TardisResp<Void> = client.put_resp("/ci/item", &json!({"key": "flow:cfg", "value": 3, "expected_revision": 1})).await;
+    assert_eq!(result.code, "409-spi-kv-revision-conflict");
+    let _: Void = client.put("/ci/item", &json!({"key": "flow:cfg", "value": 3})).await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=flow:cfg").await;
+    assert_eq!(result.revision, 3);
+    assert_eq!(result.value, 3);
+    let result: TardisResp<Void> = client.put_resp("/ci/item", &json!({"key": "flow:new", "value": 1, "expected_revision": 1})).await;
+    assert_eq!(result.code, "409-spi-kv-revision-conflict");
+
+    // ttl
+    let _: Void = client.put("/ci/item", &json!({"key": "flow:lock", "value": 1, "ttl_sec": 1})).await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=flow:lock").await;
+    assert!(result.expire_time.is_some());
+    let result: TardisPage<KvItemSummaryResp> = client.get("/ci/item/match?key_prefix=flow:&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 2);
+    sleep(Duration::from_millis(1500)).await;
+    let result: TardisResp<KvItemDetailResp> = client.get_resp("/ci/item?key=flow:lock").await;
+    assert!(result.data.is_none());
+    let result: Vec<KvItemSummaryResp> = client.get("/ci/items?keys=flow:lock&keys=flow:cfg").await;
+    assert_eq!(result.len(), 1);
+    let result: TardisPage<KvItemSummaryResp> = client.get("/ci/item/match?key_prefix=flow:&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 1);
+    // an expired item can be created again
+    let _: Void = client.put("/ci/item", &json!({"key": "flow:lock", "value": 2, "expected_revision": 0, "ttl_sec": 60})).await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=flow:lock").await;
+    assert_eq!(result.value, 2);
+    assert_eq!(result.revision, 1);
+    // remove the ttl
+    let _: Void = client.put("/ci/item", &json!({"key": "flow:lock", "value": 2, "ttl_sec": 0})).await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=flow:lock").await;
+    assert!(result.expire_time.is_none());
+    assert_eq!(result.revision, 2);
+
+    // changes
+    let result: KvItemChangesResp = client.get("/ci/item/changes?key_prefix=watch:").await;
+    assert!(result.changes.is_empty());
+    let from_revision = result.revision;
+    let _: Void = client.put("/ci/item", &json!({"key": "watch:a", "value": 1})).await;
+    let _: Void = client.put("/ci/item", &json!({"key": "watch:b", "value": 2})).await;
+    let _: Void = client.put("/ci/item", &json!({"key": "other:c", "value": 3})).await;
+    client.delete("/ci/item?key=watch:a").await;
+    let result: KvItemChangesResp = client.get(&format!("/ci/item/changes?key_prefix=watch:&from_revision={from_revision}")).await;
+    assert_eq!(
+        result.changes.iter().map(|change| (change.key.as_str(), change.op, change.item_revision)).collect::<Vec<_>>(),
+        vec![
+            ("watch:a", KvItemChangeKind::Put, 1),
+            ("watch:b", KvItemChangeKind::Put, 1),
+            ("watch:a", KvItemChangeKind::Delete, 1)
+        ]
+    );
+    assert_eq!(result.changes[1].value, Some(json!(2)));
+    assert_eq!(result.revision, result.changes[2].revision);
+    let result: KvItemChangesResp = client.get(&format!("/ci/item/changes?key_prefix=watch:&from_revision={}", result.revision)).await;
+    assert!(result.changes.is_empty());
+    let result: KvItemChangesResp = client.get(&format!("/ci/item/changes?key_prefix=watch:&from_revision={from_revision}&size=1")).await;
+    assert_eq!(result.changes.len(), 1);
+    assert_eq!(result.revision, result.changes[0].revision);
+    // wildcards in the prefix are matched as they are
+    let _: Void = client.put("/ci/item", &json!({"key": "watch%x", "value": 4})).await;
+    let result: KvItemChangesResp = client.get(&format!("/ci/item/changes?key_prefix=watch%25&from_revision={from_revision}")).await;
+    assert_eq!(result.changes.len(), 1);
+    assert_eq!(result.changes[0].key, "watch%x");
+
+    // history
+    let _: Void = client.put("/ci/item", &json!({"key": "hist:a", "value": 1, "info": "v1"})).await;
+    sleep(Duration::from_millis(500)).await;
+    let as_of = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    sleep(Duration::from_millis(500)).await;
+    let _: Void = client.put("/ci/item", &json!({"key": "hist:a", "value": 2})).await;
+    let _: Void = client.put("/ci/item", &json!({"key": "hist:a", "value": 2, "disable": true})).await;
+    let result: TardisPage<KvItemHistoryResp> = client.get("/ci/item/history?key=hist:a&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 3);
+    assert_eq!(
+        result.records.iter().map(|history| (history.op, history.item_revision, history.value.clone(), history.prev_value.clone())).collect::<Vec<_>>(),
+        vec![
+            (KvItemChangeKind::Put, 3, Some(json!(2)), Some(json!(2))),
+            (KvItemChangeKind::Put, 2, Some(json!(2)), Some(json!(1))),
+            (KvItemChangeKind::Put, 1, Some(json!(1)), None)
+        ]
+    );
+    assert!(result.records[0].disable);
+    assert_eq!(result.records[0].operator, "app001");
+    let first_history_id = result.records[2].id;
+    // point-in-time read
+    let result: KvItemDetailResp = client.get(&format!("/ci/item?key=hist:a&as_of={as_of}")).await;
+    assert_eq!(result.value, 1);
+    assert_eq!(result.revision, 1);
+    assert!(!result.disable);
+    let result: Vec<KvItemSummaryResp> = client.get(&format!("/ci/items?keys=hist:a&keys=hist:b&as_of={as_of}")).await;
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].info, "v1");
+    // restore
+    let result: TardisResp<Void> = client.put_resp("/ci/item/restore", &json!({"key": "hist:a", "history_id": first_history_id, "expected_revision": 1})).await;
+    assert_eq!(result.code, "409-spi-kv-revision-conflict");
+    let _: Void = client.put("/ci/item/restore", &json!({"key": "hist:a", "history_id": first_history_id})).await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=hist:a").await;
+    assert_eq!(result.value, 1);
+    assert_eq!(result.revision, 4);
+    assert!(!result.disable);
+    let result: TardisResp<Void> = client.put_resp("/ci/item/restore", &json!({"key": "hist:b", "history_id": first_history_id})).await;
+    assert_eq!(result.code, "404-spi-kv-history-not-exist");
+    client.delete("/ci/item?key=hist:a").await;
+    let result: TardisPage<KvItemHistoryResp> = client.get("/ci/item/history?key=hist:a&page_number=1&page_size=1").await;
+    assert_eq!(result.total_size, 5);
+    assert_eq!(result.records[0].op, KvItemChangeKind::Delete);
+    assert_eq!(result.records[0].prev_value, Some(json!(1)));
+    let result: TardisResp<Void> = client.put_resp("/ci/item/restore", &json!({"key": "hist:a", "history_id": result.records[0].id})).await;
+    assert_eq!(result.code, "400-spi-kv-history-deleted");
+    let result: TardisResp<KvItemDetailResp> = client.get_resp(&format!("/ci/item?key=hist:a&as_of={}", Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true))).await;
+    assert!(result.data.is_none());
+    let result: KvItemDetailResp = client.get(&format!("/ci/item?key=hist:a&as_of={as_of}")).await;
+    assert_eq!(result.value, 1);
+
     Ok(())
 }
@@ -1 +1,3 @@
+#[cfg(feature = "spi-fs")]
+pub mod object_ci_fs_api;
 pub mod object_ci_obj_api;
@@ -0,0 +1,77 @@
+use bios_basic::helper::request_helper::tardis_err_to_poem_err;
+use tardis::web::poem;
+use tardis::web::poem_openapi::param::Query;
+use tardis::web::poem_openapi::payload::Binary;
+use tardis::web::poem_openapi::{self, ApiResponse};
+
+use crate::object_config::ObjectConfig;
+use crate::serv::fs::object_fs_obj_serv::{self, FsSignedMethod};
+
+/// Response of the object upload
+///
+/// 对象上传的响应
+#[derive(ApiResponse)]
+enum ObjectFsPutResp {
+    /// Etag of the uploaded content, used to complete multipart uploads
+    ///
+    /// 上传内容的ETag，用于完成分片上传
+    #[oai(status = 200)]
+    Ok(#[oai(header = "ETag")] String),
+}
+
+#[derive(Clone)]
+pub struct ObjectCiFsApi;
+
+/// Interface Console Object Local File System API
+///
+/// The signed urls of the local file system kind are served by this api, they are authorized by the signature instead of the context.
+/// 接口控制台对象服务本地文件系统API
+/// 本地文件系统类型的预签名地址由该API提供，通过地址中的签名而不是上下文进行授权。
+#[poem_openapi::OpenApi(prefix_path = "/ci/obj/fs", tag = "bios_basic::ApiTag::Interface")]
+impl ObjectCiFsApi {
+    /// Get object by signed url
+    ///
+    /// 通过签名地址获取对象
+    #[oai(path = "/object", method = "get")]
+    async fn get_object(&self, ak: Query<String>, path: Query<String>, expires: Query<i64>, signature: Query<String>) -> poem::Result<Binary<poem::Body>> {
+        let funs = crate::get_tardis_inst();
+        let file = object_fs_obj_serv::verify_signed_url(FsSignedMethod::Get, &ak.0, &path.0, None, expires.0, &signature.0, &funs).await.map_err(tardis_err_to_poem_err)?;
+        let content = object_fs_obj_serv::open_object(&file.path, &path.0).await.map_err(tardis_err_to_poem_err)?;
+        Ok(Binary(poem::Body::from_async_read(content)))
+    }
+
+    /// Put object by signed url
+    /// ps: the body is read as is, whatever the content type is, like the presigned urls of object storages.
+    /// The body is streamed to the file and rejected once it is larger than the size signed into the url or `fs_max_object_size`.
+    ///
+    /// 通过签名地址上传对象
+    /// ps: 与对象存储的预签名地址一致，不论内容类型均按原始内容读取。
+    /// 请求体以流的方式写入文件，超过签入地址的大小或 `fs_max_object_size` 时拒绝。
+    #[oai(path = "/object", method = "put")]
+    async fn put_object(
+        &self,
+        ak: Query<String>,
+        path: Query<String>,
+        size: Query<Option<u64>>,
+        expires: Query<i64>,
+        signature: Query<String>,
+        body: poem::Body,
+    ) -> poem::Result<ObjectFsPutResp> {
+        let funs = crate::get_tardis_inst();
+        let file = object_fs_obj_serv::verify_signed_url(FsSignedMethod::Put, &ak.0, &path.0, size.0, expires.0, &signature.0, &funs).await.map_err(tardis_err_to_poem_err)?;
+        let max_size = funs.conf::<ObjectConfig>().fs_max_object_size;
+        let max_size = size.0.map_or(max_size, |size| size.min(max_size));
+        let etag = object_fs_obj_serv::write_object(&file, &path.0, body.into_async_read(), max_size).await.map_err(tardis_err_to_poem_err)?;
+        Ok(ObjectFsPutResp::Ok(etag))
+    }
+
+    /// Delete object by signed url
+    ///
+    /// 通过签名地址删除对象
+    #[oai(path = "/object", method = "delete")]
+    async fn delete_object(&self, ak: Query<String>, path: Query<String>, expires: Query<i64>, signature: Query<String>) -> poem::Result<()> {
+        let funs = crate::get_tardis_inst();
+        let file = object_fs_obj_serv::verify_signed_url(FsSignedMethod::Delete, &ak.0, &path.0, None, expires.0, &signature.0, &funs).await.map_err(tardis_err_to_poem_err)?;
+        object_fs_obj_serv::delete_object(&file.path, &path.0).await.map_err(tardis_err_to_poem_err)
+    }
+}
@@ -14,11 +14,11 @@ use tardis::web::poem_openapi::param::Query;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
 use crate::dto::object_dto::{
-    ClientCreateReq, ObjectBatchBuildCreatePresignUrlReq, ObjectBatchDeleteReq, ObjectCompleteMultipartUploadReq, ObjectCopyReq, ObjectInitiateMultipartUploadReq,
-    ObjectObjPresignKind, ObjectPresignBatchViewReq,
+    ClientCreateReq, ObjectBatchBuildCreatePresignUrlReq, ObjectBatchDeleteReq, ObjectCompleteMultipartUploadReq, ObjectCopyReq, ObjectHeadResp, ObjectInitiateMultipartUploadReq,
+    ObjectListResp, ObjectObjPresignKind, ObjectPresignBatchViewReq, ObjectQuotaModifyReq, ObjectTagsPutReq, ObjectUsageResp,
 };
 use crate::object_constants;
-use crate::serv::object_obj_serv;
+use crate::serv::{object_obj_serv, object_quota_serv};
 #[derive(Clone)]
 pub struct ObjectCiObjApi;
 
@@ -66,9 +66,15 @@ impl ObjectCiObjApi {
         // 指定桶，当且仅当使用自定义服务ID时该参数有效。
         // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
         bucket: Query<Option<String>>,
+        // 上传的字节数，签入上传地址并用于检查存储配额，存在配额时必填。
+        // Bytes to upload, signed into the url and checked against the storage quota, required when a quota applies.
+        size: Query<Option<u64>>,
         ctx: TardisContextExtractor,
     ) -> TardisApiResult<String> {
         let funs = crate::get_tardis_inst();
+        if bs_id.0.is_none() {
+            object_quota_serv::check_upload(size.0, &funs, &ctx.0).await?;
+        }
         let url = object_obj_serv::presign_obj_url(
             ObjectObjPresignKind::Upload,
             object_path.0.trim(),
@@ -78,6 +84,7 @@ impl ObjectCiObjApi {
             private.0,
             special.0,
             obj_exp.0,
+            size.0,
             bucket.0,
             bs_id.0,
             &funs,
@@ -87,6 +94,33 @@ impl ObjectCiObjApi {
         TardisResp::ok(url)
     }
 
+    /// Confirm the upload by a presigned url, the uploaded object is accounted to the storage quota with its real size
+    /// ps: the object is removed when it exceeds the quota
+    ///
+    /// 确认通过预签名地址的上传，上传的对象按实际大小计入存储配额
+    /// ps: 超出配额时删除该对象
+    #[oai(path = "/presign/put/confirm", method = "put")]
+    async fn presign_put_confirm(
+        &self,
+        // 对象的路径
+        // path of object
+        object_path: Query<String>,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        obj_exp: Query<Option<u32>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        object_obj_serv::confirm_upload(object_path.0.trim(), private.0, special.0, obj_exp.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void)
+    }
+
     /// Fetch URL for temporary authorization of file delete
     ///
     /// 获取文件删除临时授权的 URL
@@ -128,6 +162,7 @@ impl ObjectCiObjApi {
             private.0,
             special.0,
             obj_exp.0,
+            None,
             bucket.0,
             bs_id.0,
             &funs,
@@ -178,6 +213,7 @@ impl ObjectCiObjApi {
             private.0,
             special.0,
             obj_exp.0,
+            None,
             bucket.0,
             bs_id.0,
             &funs,
@@ -214,6 +250,9 @@ impl ObjectCiObjApi {
     #[oai(path = "/multi_upload/initiate_multipart_upload", method = "post")]
     async fn initiate_multipart_upload(&self, req: Json<ObjectInitiateMultipartUploadReq>, ctx: TardisContextExtractor) -> TardisApiResult<String> {
         let funs = crate::get_tardis_inst();
+        if req.0.bs_id.is_none() {
+            object_quota_serv::check_upload(req.0.size, &funs, &ctx.0).await?;
+        }
         let upload_id = object_obj_serv::initiate_multipart_upload(req.0, &funs, &ctx.0).await?;
         TardisResp::ok(upload_id)
     }
@@ -229,8 +268,10 @@ impl ObjectCiObjApi {
     }
 
     /// Multipart Upload:Complete Multipart Upload Task
+    /// ps: the object is accounted to the storage quota with its real size, and removed when it exceeds the quota
     ///
     /// 分片上传： 完成分片上传任务
+    /// ps: 对象按实际大小计入存储配额，超出配额时删除该对象
     #[oai(path = "/multi_upload/complete_multipart_upload", method = "post")]
     async fn complete_multipart_upload(&self, req: Json<ObjectCompleteMultipartUploadReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
         let funs = crate::get_tardis_inst();
@@ -322,6 +363,170 @@ impl ObjectCiObjApi {
         TardisResp::ok(object_obj_serv::object_exist(object_path.0, private.0, special.0, obj_exp.0, bucket.0, bs_id.0, &funs, &ctx.0).await?)
     }
 
+    /// List objects under the prefix by pages
+    ///
+    /// 按前缀分页列出对象
+    #[oai(path = "/object/list", method = "get")]
+    async fn object_list(
+        &self,
+        // 对象路径的前缀
+        // prefix of the object paths
+        prefix: Query<Option<String>>,
+        // 分隔符，指定时前缀下一级的目录作为公共前缀返回，一般为 `/`
+        // Delimiter, the next level directories under the prefix are returned as common prefixes when specified, usually `/`
+        delimiter: Query<Option<String>>,
+        // 上一页返回的续读标识
+        // Continuation token returned by the previous page
+        continuation_token: Query<Option<String>>,
+        // 每页数量，默认且最大为1000
+        // Page size, 1000 by default and at most
+        page_size: Query<Option<u16>>,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // 使用obs时，传入数值不生效，仅表示使用tamp桶。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        // When using obs, passing in a value does not take effect, it only indicates the use of the tamp bucket.
+        obj_exp: Query<Option<u32>>,
+        // 服务ID，使用外部自定义服务时，传入该值。
+        // Service ID, pass this value when using an external custom service.
+        bs_id: Query<Option<String>>,
+        // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+        // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+        bucket: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ObjectListResp> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(
+            object_obj_serv::object_list(
+                prefix.0.unwrap_or_default(),
+                delimiter.0,
+                continuation_token.0,
+                page_size.0,
+                private.0,
+                special.0,
+                obj_exp.0,
+                bucket.0,
+                bs_id.0,
+                &funs,
+                &ctx.0,
+            )
+            .await?,
+        )
+    }
+
+    /// Get object information and user metadata
+    ///
+    /// 获取对象信息及用户自定义元数据
+    #[oai(path = "/object/head", method = "get")]
+    async fn object_head(
+        &self,
+        // 对象的路径
+        // path of object
+        object_path: Query<String>,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // 使用obs时，传入数值不生效，仅表示使用tamp桶。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        // When using obs, passing in a value does not take effect, it only indicates the use of the tamp bucket.
+        obj_exp: Query<Option<u32>>,
+        // 服务ID，使用外部自定义服务时，传入该值。
+        // Service ID, pass this value when using an external custom service.
+        bs_id: Query<Option<String>>,
+        // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+        // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+        bucket: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ObjectHeadResp> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(object_obj_serv::object_head(object_path.0, private.0, special.0, obj_exp.0, bucket.0, bs_id.0, &funs, &ctx.0).await?)
+    }
+
+    /// Put object tags
+    ///
+    /// 设置对象标签
+    #[oai(path = "/object/tags", method = "put")]
+    async fn object_tags_put(&self, req: Json<ObjectTagsPutReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        object_obj_serv::object_tags_put(req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void)
+    }
+
+    /// Get object tags
+    ///
+    /// 获取对象标签
+    #[oai(path = "/object/tags", method = "get")]
+    async fn object_tags_get(
+        &self,
+        // 对象的路径
+        // path of object
+        object_path: Query<String>,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // 使用obs时，传入数值不生效，仅表示使用tamp桶。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        // When using obs, passing in a value does not take effect, it only indicates the use of the tamp bucket.
+        obj_exp: Query<Option<u32>>,
+        // 服务ID，使用外部自定义服务时，传入该值。
+        // Service ID, pass this value when using an external custom service.
+        bs_id: Query<Option<String>>,
+        // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+        // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+        bucket: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<HashMap<String, String>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(object_obj_serv::object_tags_get(object_path.0, private.0, special.0, obj_exp.0, bucket.0, bs_id.0, &funs, &ctx.0).await?)
+    }
+
+    /// Set storage quota
+    /// ps: only the sys or tenant contexts can set the quotas of the own paths below them
+    ///
+    /// 设置存储配额
+    /// ps: 仅平台或租户上下文可设置其下级路径的配额
+    #[oai(path = "/quota", method = "put")]
+    async fn quota_modify(&self, req: Json<ObjectQuotaModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        object_quota_serv::modify_quota(&req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void)
+    }
+
+    /// Get storage usage report of the current own paths and its descendants
+    ///
+    /// 获取当前路径及其下级路径的存储用量报告
+    #[oai(path = "/quota/usage", method = "get")]
+    async fn usage_find(&self, ctx: TardisContextExtractor) -> TardisApiResult<Vec<ObjectUsageResp>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(object_quota_serv::find_usages(&funs, &ctx.0).await?)
+    }
+
+    /// Recount storage usage of the current own paths from the buckets
+    /// ps: uploads by presigned urls are not accounted until confirmed or recounted,
+    /// the default bucket shared by services without isolation flag is only recounted under the prefix of the current own paths
+    ///
+    /// 从存储桶重新统计当前路径的存储用量
+    /// ps: 通过预签名地址上传的对象在确认上传或重新统计前不计入用量，
+    /// 无隔离标识的服务共用默认桶，仅统计当前路径前缀下的对象
+    #[oai(path = "/quota/usage/refresh", method = "put")]
+    async fn usage_refresh(&self, ctx: TardisContextExtractor) -> TardisApiResult<ObjectUsageResp> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(object_obj_serv::refresh_usage(&funs, &ctx.0).await?)
+    }
+
     /// Check object is exist
     ///
     /// 添加自定义服务实例
@@ -0,0 +1,2 @@
+pub mod object_quota;
+pub mod object_usage_item;
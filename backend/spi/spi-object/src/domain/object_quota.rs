@@ -0,0 +1,49 @@
+use tardis::basic::dto::TardisContext;
+use tardis::chrono::{DateTime, Utc};
+use tardis::db::reldb_client::TardisActiveModel;
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::sea_query::{ColumnDef, IndexCreateStatement, Table, TableCreateStatement};
+use tardis::db::sea_orm::*;
+
+/// Quota and usage of the objects stored by an own paths (tenant or app)
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "object_quota")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub own_paths: String,
+    /// Max bytes of the own paths and its descendants, `0` means unlimited
+    pub quota_bytes: i64,
+    /// Bytes stored by the own paths itself
+    pub used_bytes: i64,
+    pub object_count: i64,
+    pub update_time: DateTime<Utc>,
+}
+
+impl TardisActiveModel for ActiveModel {
+    fn fill_ctx(&mut self, _: &TardisContext, _: bool) {}
+
+    fn create_table_statement(db: DbBackend) -> TableCreateStatement {
+        let mut builder = Table::create();
+        builder
+            .table(Entity.table_ref())
+            .if_not_exists()
+            .col(ColumnDef::new(Column::OwnPaths).not_null().string().primary_key())
+            .col(ColumnDef::new(Column::QuotaBytes).not_null().big_integer().default(0))
+            .col(ColumnDef::new(Column::UsedBytes).not_null().big_integer().default(0))
+            .col(ColumnDef::new(Column::ObjectCount).not_null().big_integer().default(0))
+            .col(ColumnDef::new(Column::UpdateTime).not_null().timestamp_with_time_zone());
+        if db == DatabaseBackend::MySql {
+            builder.engine("InnoDB").character_set("utf8mb4").collate("utf8mb4_0900_as_cs");
+        }
+        builder.to_owned()
+    }
+
+    fn create_index_statement() -> Vec<IndexCreateStatement> {
+        vec![]
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
@@ -0,0 +1,51 @@
+use tardis::basic::dto::TardisContext;
+use tardis::chrono::{DateTime, Utc};
+use tardis::db::reldb_client::TardisActiveModel;
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::sea_query::{ColumnDef, Index, IndexCreateStatement, Table, TableCreateStatement};
+use tardis::db::sea_orm::*;
+
+/// Size of an object of the built-in service accounted to the usage of an own paths
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "object_usage_item")]
+pub struct Model {
+    /// Bucket of the object, empty for the default bucket of services without isolation flag
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub bucket: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub object_path: String,
+    pub own_paths: String,
+    /// Bytes declared when the upload url was issued, or counted by the last refresh
+    pub size: i64,
+    pub update_time: DateTime<Utc>,
+}
+
+impl TardisActiveModel for ActiveModel {
+    fn fill_ctx(&mut self, _: &TardisContext, _: bool) {}
+
+    fn create_table_statement(db: DbBackend) -> TableCreateStatement {
+        let mut builder = Table::create();
+        builder
+            .table(Entity.table_ref())
+            .if_not_exists()
+            .col(ColumnDef::new(Column::Bucket).not_null().string())
+            .col(ColumnDef::new(Column::ObjectPath).not_null().string())
+            .col(ColumnDef::new(Column::OwnPaths).not_null().string())
+            .col(ColumnDef::new(Column::Size).not_null().big_integer().default(0))
+            .col(ColumnDef::new(Column::UpdateTime).not_null().timestamp_with_time_zone())
+            .primary_key(Index::create().col(Column::Bucket).col(Column::ObjectPath));
+        if db == DatabaseBackend::MySql {
+            builder.engine("InnoDB").character_set("utf8mb4").collate("utf8mb4_0900_as_cs");
+        }
+        builder.to_owned()
+    }
+
+    fn create_index_statement() -> Vec<IndexCreateStatement> {
+        vec![Index::create().name(&format!("idx-{}-own_paths", Entity.table_name())).table(Entity).col(Column::OwnPaths).to_owned()]
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
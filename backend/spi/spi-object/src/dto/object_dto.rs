@@ -1,6 +1,12 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-use tardis::{basic::field::TrimString, web::poem_openapi};
+use tardis::{
+    basic::field::TrimString,
+    chrono::{DateTime, Utc},
+    web::poem_openapi,
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum ObjectObjPresignKind {
@@ -13,6 +19,9 @@ pub enum ObjectObjPresignKind {
 pub struct ObjectInitiateMultipartUploadReq {
     pub object_path: String,
     pub content_type: Option<String>,
+    // 预计上传的字节数，用于检查存储配额，存在配额时必填，完成上传时按实际大小计入配额。
+    // Bytes expected to upload, checked against the storage quota and required when a quota applies, the real size is accounted once the upload is completed.
+    pub size: Option<u64>,
     pub private: Option<bool>,
     pub special: Option<bool>,
     // 服务ID，使用外部自定义服务时，传入该值。
@@ -103,6 +112,78 @@ pub struct ObjectPresignBatchViewReq {
     pub bucket: Option<String>,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectListResp {
+    pub objects: Vec<ObjectSummaryResp>,
+    // 指定分隔符时，前缀下一级的目录（以分隔符结尾）。
+    // The next level directories under the prefix (ending with the delimiter) when a delimiter is specified.
+    pub common_prefixes: Vec<String>,
+    // 下一页的续读标识，为空时表示没有更多对象。
+    // Continuation token of the next page, empty when there are no more objects.
+    pub next_continuation_token: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectSummaryResp {
+    pub object_path: String,
+    pub size: u64,
+    pub etag: Option<String>,
+    pub last_modified: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectHeadResp {
+    pub object_path: String,
+    pub size: Option<i64>,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    // 用户自定义元数据，键不含 `x-amz-meta-` 前缀。
+    // User defined metadata, the keys are without the `x-amz-meta-` prefix.
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectTagsPutReq {
+    pub object_path: String,
+    // 对象的全部标签，会替换已有标签，最多10个。
+    // All tags of the object, replacing the existing tags, at most 10.
+    pub tags: HashMap<String, String>,
+    pub private: Option<bool>,
+    pub special: Option<bool>,
+    pub obj_exp: Option<u32>,
+    // 服务ID，使用外部自定义服务时，传入该值。
+    // Service ID, pass this value when using an external custom service.
+    pub bs_id: Option<String>,
+    // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+    // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+    pub bucket: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectQuotaModifyReq {
+    // 配额所属的路径（租户或应用），须在当前上下文的路径之下，仅平台或租户上下文可设置配额。
+    // Own paths (tenant or app) of the quota, should be below the own paths of the context, only the sys or tenant contexts can set quotas.
+    pub own_paths: String,
+    // 该路径及其下级路径最多可存储的字节数，为0时不限制。
+    // Max bytes stored by the own paths and its descendants, unlimited when 0.
+    #[oai(validator(minimum(value = "0")))]
+    pub quota_bytes: i64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectUsageResp {
+    pub own_paths: String,
+    // 为0时不限制。
+    // Unlimited when 0.
+    pub quota_bytes: i64,
+    // 该路径自身存储的字节数，不含下级路径。
+    // Bytes stored by the own paths itself, the descendants are excluded.
+    pub used_bytes: i64,
+    pub object_count: i64,
+    pub update_time: DateTime<Utc>,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct ClientCreateReq {
     pub kind: String,
@@ -1,6 +1,7 @@
 #![warn(clippy::unwrap_used)]
 
 mod api;
+mod domain;
 mod dto;
 pub mod object_config;
 pub mod object_constants;
@@ -1,9 +1,36 @@
 use bios_basic::rbum::rbum_config::RbumConfig;
+use bios_sdk_invoke::invoke_config::InvokeConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct ObjectConfig {
     pub rbum: RbumConfig,
+    pub invoke: InvokeConfig,
+    /// Public url of this service, used to build the signed urls of the local file system kind
+    pub fs_base_url: String,
+    /// Secret signing the urls of the local file system kind, should be the same on all nodes.
+    /// Required by the local file system kind, its backend services fail to initialize if empty.
+    pub fs_sign_secret: String,
+    /// Max size in bytes of an object uploaded by a signed url of the local file system kind, default as 5GB like s3
+    pub fs_max_object_size: u64,
+    /// Multipart uploads and staging files of the local file system kind untouched for this long are removed as abandoned, default as 1 day
+    pub fs_upload_expire_sec: u64,
+    /// Fact key of spi-stats receiving the usage of each own paths when it changes, empty disables the feeding
+    pub usage_stats_fact_key: String,
+}
+
+impl Default for ObjectConfig {
+    fn default() -> Self {
+        ObjectConfig {
+            rbum: Default::default(),
+            invoke: InvokeConfig::default(),
+            fs_base_url: "http://127.0.0.1:8080/spi-object".to_string(),
+            fs_sign_secret: "".to_string(),
+            fs_max_object_size: 5 * 1024 * 1024 * 1024,
+            fs_upload_expire_sec: 24 * 3600,
+            usage_stats_fact_key: "".to_string(),
+        }
+    }
 }
@@ -1,5 +1,8 @@
 pub const DOMAIN_CODE: &str = "spi-object";
 pub const SPI_S3_KIND_CODE: &str = "spi-bs-s3";
 pub const SPI_OBS_KIND_CODE: &str = "spi-bs-obs";
+pub const SPI_FS_KIND_CODE: &str = "spi-bs-fs";
 
 pub const USE_REGION_ENDPOINT: &str = "use_region_endpoint";
+/// Suffix of the built-in bucket holding the objects with expiration, see [`crate::serv::object_obj_serv::built_in_bucket_name`]
+pub const TAMP_BUCKET_SUFFIX: &str = "tamp";
@@ -1,13 +1,18 @@
 use bios_basic::spi::{api::spi_ci_bs_api, dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use bios_sdk_invoke::invoke_initializer;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
+    db::reldb_client::TardisActiveModel,
     log::info,
     web::web_server::TardisWebServer,
     TardisFuns, TardisFunsInst,
 };
 
+#[cfg(feature = "spi-fs")]
+use crate::api::ci::object_ci_fs_api;
 use crate::{
     api::ci::object_ci_obj_api,
+    domain::{object_quota, object_usage_item},
     object_config::ObjectConfig,
     object_constants::{self, DOMAIN_CODE},
     serv,
@@ -17,6 +22,7 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     info!("[BIOS.Object] Module initializing");
     let mut funs = crate::get_tardis_inst();
     bios_basic::rbum::rbum_initializer::init(funs.module_code(), funs.conf::<ObjectConfig>().rbum.clone()).await?;
+    invoke_initializer::init(funs.module_code(), funs.conf::<ObjectConfig>().invoke.clone())?;
     funs.begin().await?;
     let ctx = spi_initializer::init(DOMAIN_CODE, &funs).await?;
     init_db(&funs, &ctx).await?;
@@ -27,11 +33,24 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
 }
 
 async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    funs.db().init(object_quota::ActiveModel::init(TardisFuns::reldb().backend(), None, TardisFuns::reldb().compatible_type())).await?;
+    funs.db()
+        .init(object_usage_item::ActiveModel::init(
+            TardisFuns::reldb().backend(),
+            None,
+            TardisFuns::reldb().compatible_type(),
+        ))
+        .await?;
     spi_initializer::add_kind(object_constants::SPI_S3_KIND_CODE, funs, ctx).await?;
+    #[cfg(feature = "spi-fs")]
+    spi_initializer::add_kind(object_constants::SPI_FS_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
 async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
+    #[cfg(feature = "spi-fs")]
+    web_server.add_module(DOMAIN_CODE, (spi_ci_bs_api::SpiCiBsApi, object_ci_obj_api::ObjectCiObjApi, object_ci_fs_api::ObjectCiFsApi)).await;
+    #[cfg(not(feature = "spi-fs"))]
     web_server.add_module(DOMAIN_CODE, (spi_ci_bs_api::SpiCiBsApi, object_ci_obj_api::ObjectCiObjApi)).await;
     Ok(())
 }
@@ -43,6 +62,8 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
         object_constants::SPI_S3_KIND_CODE => serv::s3::object_s3_initializer::init(&bs_cert, ctx, mgr).await,
         #[cfg(feature = "spi-s3")]
         object_constants::SPI_OBS_KIND_CODE => serv::obs::object_obs_initializer::init(&bs_cert, ctx, mgr).await,
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => serv::fs::object_fs_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }?;
     info!("[BIOS.Object] Fun [{}]({}) initialized", bs_cert.kind_code, bs_cert.conn_uri);
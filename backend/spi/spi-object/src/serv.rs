@@ -1,4 +1,7 @@
 pub mod custom_s3;
+#[cfg(feature = "spi-fs")]
+pub mod fs;
 pub mod object_obj_serv;
+pub mod object_quota_serv;
 pub mod obs;
 pub mod s3;
@@ -10,6 +10,8 @@ use tardis::{
 
 use tardis::serde_json::Value as JsonValue;
 
+use crate::serv::s3;
+
 /// 自定义外部obs服务初始化
 /// 外部服务由API申请资源时初始化，不需要随系统spi初始化
 pub async fn init(bs_cert: &SpiBsCertResp, _ctx: &TardisContext, _mgr: bool) -> TardisResult<SpiBsInst> {
@@ -24,8 +26,7 @@ pub async fn init(bs_cert: &SpiBsCertResp, _ctx: &TardisContext, _mgr: bool) ->
         .ok_or_else(|| TardisError::bad_request("Tardis context ext should have a `region` field with type string", "400-spi-invalid-tardis-ctx"))?;
     let tardis_os_config = OSModuleConfig::builder().kind("s3").endpoint(&bs_cert.conn_uri).ak(&bs_cert.ak).sk(&bs_cert.sk).region(region).default_bucket(default_bucket).build();
     let client = TardisOSClient::init(&tardis_os_config)?;
-    Ok(SpiBsInst {
-        client: Box::new(client),
-        ext: HashMap::new(),
-    })
+    let mut ext = HashMap::new();
+    s3::set_conn_to_ext(&bs_cert.conn_uri, &bs_cert.ak, &bs_cert.sk, region, default_bucket, &mut ext)?;
+    Ok(SpiBsInst { client: Box::new(client), ext })
 }
@@ -0,0 +1,2 @@
+pub mod object_fs_initializer;
+pub mod object_fs_obj_serv;
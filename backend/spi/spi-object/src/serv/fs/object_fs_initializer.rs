@@ -0,0 +1,84 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    log::warn,
+    tokio::{self, fs, time},
+    TardisFuns,
+};
+
+use crate::{object_config::ObjectConfig, object_constants::DOMAIN_CODE};
+
+use super::object_fs_obj_serv;
+
+/// Interval of sweeping the abandoned uploads and expired objects
+const SWEEP_INTERVAL_SEC: u64 = 3600;
+
+/// Roots swept by a task of this process
+static SWEPT_ROOTS: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+
+/// Client of the local file system, objects are stored in `{root}/{bucket}/{object_path}`
+pub struct FsClient {
+    pub(crate) root: PathBuf,
+}
+
+/// 本地文件系统引擎初始化
+/// `conn_uri` 为存储根目录（可带 `file://` 前缀），每个桶对应根目录下的一个子目录。
+/// local file system engine initialization
+/// `conn_uri` is the root directory of the storage (the `file://` prefix is optional), each bucket is a sub directory of the root.
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, _: bool) -> TardisResult<SpiBsInst> {
+    let config = TardisFuns::cs_config::<ObjectConfig>(DOMAIN_CODE);
+    if config.fs_sign_secret.is_empty() {
+        return Err(TardisError::internal_error(
+            "The secret signing the urls of the file system is required, please configure fs_sign_secret",
+            "500-spi-object-fs-sign-secret-required",
+        ));
+    }
+    let root = bs_cert.conn_uri.strip_prefix("file://").unwrap_or(&bs_cert.conn_uri);
+    if root.is_empty() {
+        return Err(TardisError::bad_request(
+            "The root directory of the file system is required",
+            "400-spi-object-fs-root-required",
+        ));
+    }
+    fs::create_dir_all(root).await.map_err(|e| TardisError::internal_error(&format!("Root directory {root} creation failed: {e}"), "500-spi-object-fs-io-error"))?;
+    // the root is resolved from the backend service when serving the signed urls, so it should not depend on the working directory
+    let root = fs::canonicalize(root).await.map_err(|e| TardisError::internal_error(&format!("Root directory {root} resolving failed: {e}"), "500-spi-object-fs-io-error"))?;
+    let mut ext = HashMap::new();
+    if !bs_cert.private {
+        let bucket_name_prefix = spi_initializer::common::get_isolation_flag_from_context(ctx);
+        for bucket_name_suffix in ["pri", "pub", "spe", "tamp"] {
+            let bucket_name = format!("{bucket_name_prefix}-{bucket_name_suffix}");
+            fs::create_dir_all(root.join(&bucket_name))
+                .await
+                .map_err(|e| TardisError::internal_error(&format!("Bucket {bucket_name} creation failed: {e}"), "500-spi-object-fs-io-error"))?;
+        }
+        spi_initializer::common::set_isolation_flag_to_ext(&bucket_name_prefix, &mut ext);
+    };
+    spawn_sweep(root.clone(), config.fs_upload_expire_sec);
+    Ok(SpiBsInst {
+        client: Box::new(FsClient { root }),
+        ext,
+    })
+}
+
+/// Sweep the root periodically, once per root in this process, see [`object_fs_obj_serv::sweep`]
+fn spawn_sweep(root: PathBuf, upload_expire_sec: u64) {
+    let swept_roots = SWEPT_ROOTS.get_or_init(Default::default);
+    if !swept_roots.lock().map(|mut roots| roots.insert(root.clone())).unwrap_or(false) {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(SWEEP_INTERVAL_SEC));
+        loop {
+            interval.tick().await;
+            if let Err(e) = object_fs_obj_serv::sweep(root.clone(), upload_expire_sec).await {
+                warn!("[BIOS.Object] Sweeping {} failed: {e:?}", root.display());
+            }
+        }
+    });
+}
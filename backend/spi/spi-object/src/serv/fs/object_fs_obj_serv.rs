@@ -0,0 +1,749 @@
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use md5::{Digest, Md5};
+
+use bios_basic::{
+    helper::request_helper::encode_query_value,
+    spi::spi_funs::{SpiBsInst, SpiBsInstExtractor},
+};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, SecondsFormat, Utc},
+    tokio::{
+        fs::{self, File},
+        io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+        task,
+    },
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::object_dto::{ObjectHeadResp, ObjectListResp, ObjectObjPresignKind, ObjectSummaryResp},
+    object_config::ObjectConfig,
+    object_constants::{self, TAMP_BUCKET_SUFFIX},
+    object_initializer,
+    serv::object_obj_serv::built_in_bucket_name,
+};
+
+use super::object_fs_initializer::FsClient;
+
+/// Path of the api serving the signed urls, see `ObjectCiFsApi`
+const OBJECT_API_PATH: &str = "/ci/obj/fs/object";
+/// Directory under the root holding the parts of multipart uploads
+const MULTIPART_DIR: &str = ".multipart";
+/// Directory under the root holding the files being written, they are moved into the buckets once complete
+const STAGING_DIR: &str = ".staging";
+/// Bucket used by private services, which have no isolation flag
+const DEFAULT_BUCKET_NAME: &str = "default";
+/// File in the upload directory recording the key of the object being uploaded
+const MULTIPART_TARGET_FILE: &str = "target";
+/// Max objects of a page, the same as s3
+const MAX_LIST_PAGE_SIZE: u16 = 1000;
+/// Size of the buffer copying the content of objects
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Http method allowed by a signed url
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsSignedMethod {
+    Get,
+    Put,
+    Delete,
+}
+
+impl FsSignedMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FsSignedMethod::Get => "GET",
+            FsSignedMethod::Put => "PUT",
+            FsSignedMethod::Delete => "DELETE",
+        }
+    }
+}
+
+/// 本地文件系统服务
+/// 预签名地址由本服务的 `/ci/obj/fs/object` 接口提供，地址中的签名与过期时间用于替代对象存储的预签名。
+/// local file system service
+/// Presigned urls are served by the `/ci/obj/fs/object` api of this service, the signature and expiration in the url stand in for the presigning of object storages.
+pub(crate) struct FsService;
+
+impl FsService {
+    pub async fn presign_obj_url(
+        presign_kind: ObjectObjPresignKind,
+        object_path: &str,
+        exp_secs: u32,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        size: Option<u64>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<String> {
+        let key = Self::object_key(object_path, private, special, obj_exp, inst)?;
+        let method = match presign_kind {
+            ObjectObjPresignKind::Upload => FsSignedMethod::Put,
+            ObjectObjPresignKind::Delete => FsSignedMethod::Delete,
+            ObjectObjPresignKind::View => FsSignedMethod::Get,
+        };
+        sign_url(method, &ctx.ak, &key, exp_secs, size.filter(|_| method == FsSignedMethod::Put), funs)
+    }
+
+    pub async fn batch_get_presign_obj_url(
+        object_paths: Vec<String>,
+        exp_secs: u32,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<HashMap<String, String>> {
+        let mut result = HashMap::with_capacity(object_paths.len());
+        for object_path in object_paths {
+            if let Ok(url) = Self::presign_obj_url(ObjectObjPresignKind::View, &object_path, exp_secs, private, special, obj_exp, None, funs, ctx, inst).await {
+                result.insert(object_path, url);
+            }
+        }
+        Ok(result)
+    }
+
+    pub async fn initiate_multipart_upload(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<String> {
+        let client = inst.inst::<FsClient>().0;
+        let key = Self::object_key(object_path, private, special, obj_exp, inst)?;
+        let upload_id = TardisFuns::field.nanoid();
+        let upload_dir = resolve_path(&client.root, &format!("{MULTIPART_DIR}/{upload_id}"))?;
+        fs::create_dir_all(&upload_dir).await.map_err(|e| io_error(object_path, e))?;
+        fs::write(upload_dir.join(MULTIPART_TARGET_FILE), key.as_bytes()).await.map_err(|e| io_error(object_path, e))?;
+        Ok(upload_id)
+    }
+
+    /// Build the signed urls uploading the parts `1..=part_number`
+    pub async fn batch_build_create_presign_url(
+        object_path: &str,
+        upload_id: &str,
+        part_number: u32,
+        expire_sec: u32,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<Vec<String>> {
+        let client = inst.inst::<FsClient>().0;
+        let key = Self::object_key(object_path, private, special, obj_exp, inst)?;
+        Self::check_upload(&client.root, upload_id, &key).await?;
+        (1..=part_number).map(|part| sign_url(FsSignedMethod::Put, &ctx.ak, &format!("{MULTIPART_DIR}/{upload_id}/{part}"), expire_sec, None, funs)).collect()
+    }
+
+    /// Assemble the uploaded parts in order, `parts` are the etags of the parts returned by their uploads
+    pub async fn complete_multipart_upload(
+        object_path: &str,
+        upload_id: &str,
+        parts: Vec<String>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        inst: &SpiBsInst,
+    ) -> TardisResult<()> {
+        let client = inst.inst::<FsClient>().0;
+        let key = Self::object_key(object_path, private, special, obj_exp, inst)?;
+        let upload_dir = Self::check_upload(&client.root, upload_id, &key).await?;
+        if parts.is_empty() {
+            return Err(TardisError::bad_request("At least one part is required", "400-spi-object-fs-parts-required"));
+        }
+        let path = resolve_path(&client.root, &key)?;
+        let tmp_path = create_parent_and_tmp_path(&client.root, &path, object_path).await?;
+        let assemble = async {
+            let mut target = File::create(&tmp_path).await.map_err(|e| io_error(object_path, e))?;
+            for (idx, etag) in parts.iter().enumerate() {
+                let part = File::open(upload_dir.join((idx + 1).to_string())).await.map_err(|e| io_error(object_path, e))?;
+                let (_, md5) = copy_with_md5(part, &mut target, u64::MAX, object_path).await?;
+                if etag.trim_matches('"') != md5 {
+                    return Err(TardisError::bad_request(
+                        &format!("The etag of part {} doesn't match the uploaded content", idx + 1),
+                        "400-spi-object-fs-invalid-part",
+                    ));
+                }
+            }
+            fs::rename(&tmp_path, &path).await.map_err(|e| io_error(object_path, e))
+        };
+        if let Err(e) = assemble.await {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(e);
+        }
+        fs::remove_dir_all(&upload_dir).await.map_err(|e| io_error(object_path, e))
+    }
+
+    pub async fn object_delete(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<()> {
+        let client = inst.inst::<FsClient>().0;
+        let key = Self::object_key(object_path, private, special, obj_exp, inst)?;
+        delete_object(&resolve_path(&client.root, &key)?, object_path).await
+    }
+
+    pub async fn batch_object_delete(object_paths: Vec<String>, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+        let mut failed_object_paths = vec![];
+        for object_path in object_paths {
+            if Self::object_delete(&object_path, private, special, obj_exp, inst).await.is_err() {
+                failed_object_paths.push(object_path);
+            }
+        }
+        Ok(failed_object_paths)
+    }
+
+    pub async fn object_copy(from: &str, to: &str, private: Option<bool>, special: Option<bool>, inst: &SpiBsInst) -> TardisResult<()> {
+        let client = inst.inst::<FsClient>().0;
+        let from_path = resolve_path(&client.root, &Self::object_key(from, private, special, None, inst)?)?;
+        let to_path = resolve_path(&client.root, &Self::object_key(to, private, special, None, inst)?)?;
+        let tmp_path = create_parent_and_tmp_path(&client.root, &to_path, to).await?;
+        if let Err(e) = fs::copy(&from_path, &tmp_path).await {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(io_error(from, e));
+        }
+        fs::rename(&tmp_path, &to_path).await.map_err(|e| io_error(to, e))
+    }
+
+    pub async fn object_exist(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<bool> {
+        let client = inst.inst::<FsClient>().0;
+        let path = resolve_path(&client.root, &Self::object_key(object_path, private, special, obj_exp, inst)?)?;
+        match fs::metadata(&path).await {
+            Ok(metadata) => Ok(metadata.is_file()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(io_error(object_path, e)),
+        }
+    }
+
+    /// List the objects under the prefix by pages like s3, the continuation token is the last path or common prefix of the previous page.
+    ///
+    /// The directories are walked in the order of the keys, so only the entries before the end of the page are read.
+    pub async fn object_list(
+        prefix: &str,
+        delimiter: Option<String>,
+        continuation_token: Option<String>,
+        page_size: Option<u16>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        inst: &SpiBsInst,
+    ) -> TardisResult<ObjectListResp> {
+        let client = inst.inst::<FsClient>().0;
+        let bucket_dir = resolve_path(&client.root, &Self::bucket_name(private, special, obj_exp, inst))?;
+        let page_size = page_size.unwrap_or(MAX_LIST_PAGE_SIZE).clamp(1, MAX_LIST_PAGE_SIZE) as usize;
+        let list_prefix = prefix.to_string();
+        let (objects, common_prefixes, next_continuation_token) = task::spawn_blocking(move || {
+            let mut pager = KeyPager::new(&list_prefix, delimiter.as_deref(), continuation_token.as_deref(), page_size);
+            let objects = walk_sorted(&bucket_dir, &mut pager)?;
+            let (_, common_prefixes, next_continuation_token) = pager.finish();
+            Ok::<_, std::io::Error>((objects, common_prefixes, next_continuation_token))
+        })
+        .await
+        .map_err(|e| TardisError::internal_error(&format!("Objects listing failed: {e}"), "500-spi-object-fs-io-error"))?
+        .map_err(|e| io_error(prefix, e))?;
+        Ok(ObjectListResp {
+            objects: objects
+                .into_iter()
+                .map(|(key, metadata)| ObjectSummaryResp {
+                    size: metadata.len(),
+                    etag: None,
+                    last_modified: modified_time(&metadata).map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true)).unwrap_or_default(),
+                    object_path: key,
+                })
+                .collect(),
+            common_prefixes,
+            next_continuation_token,
+        })
+    }
+
+    /// The local file system keeps no content type, etag or user metadata of the objects
+    pub async fn object_head(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<ObjectHeadResp> {
+        let client = inst.inst::<FsClient>().0;
+        let path = resolve_path(&client.root, &Self::object_key(object_path, private, special, obj_exp, inst)?)?;
+        let metadata = fs::metadata(&path).await.map_err(|e| io_error(object_path, e))?;
+        if !metadata.is_file() {
+            return Err(TardisError::not_found(&format!("Object {object_path} not found"), "404-spi-object-fs-not-found"));
+        }
+        Ok(ObjectHeadResp {
+            object_path: object_path.to_string(),
+            size: Some(metadata.len() as i64),
+            content_type: None,
+            etag: None,
+            last_modified: modified_time(&metadata).map(|time| time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+            metadata: HashMap::new(),
+        })
+    }
+
+    /// Key of the object relative to the root, the bucket follows the rules of the built-in buckets.
+    ///
+    /// Objects of the tamp bucket are kept under the directory of their expiration days, like the lifecycle prefixes of s3,
+    /// so that they can be removed once expired, see [`sweep`]. The directory is part of the keys listed from the bucket.
+    fn object_key(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<String> {
+        let bucket_name = Self::bucket_name(private, special, obj_exp, inst);
+        let key = match obj_exp {
+            Some(obj_exp) if bucket_name.ends_with(TAMP_BUCKET_SUFFIX) => format!("{bucket_name}/{obj_exp}/{}", object_path.trim_start_matches('/')),
+            _ => format!("{bucket_name}/{}", object_path.trim_start_matches('/')),
+        };
+        // fail fast on paths escaping the bucket
+        resolve_path(Path::new(""), &key)?;
+        Ok(key)
+    }
+
+    fn bucket_name(private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> String {
+        built_in_bucket_name(private, special, obj_exp, inst).unwrap_or_else(|| DEFAULT_BUCKET_NAME.to_string())
+    }
+
+    /// Check the upload exists and belongs to the object, returns the directory of the upload
+    async fn check_upload(root: &Path, upload_id: &str, key: &str) -> TardisResult<PathBuf> {
+        let upload_dir = resolve_path(root, &format!("{MULTIPART_DIR}/{upload_id}"))?;
+        match fs::read_to_string(upload_dir.join(MULTIPART_TARGET_FILE)).await {
+            Ok(target) if target == key => Ok(upload_dir),
+            Ok(_) => Err(TardisError::bad_request(
+                &format!("Upload {upload_id} doesn't belong to the object"),
+                "400-spi-object-fs-upload-mismatch",
+            )),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(TardisError::not_found(&format!("Upload {upload_id} not found"), "404-spi-object-fs-upload-not-found")),
+            Err(e) => Err(io_error(upload_id, e)),
+        }
+    }
+}
+
+/// File pointed by a verified signed url
+pub struct FsSignedFile {
+    /// Root of the backend service the file belongs to
+    pub root: PathBuf,
+    pub path: PathBuf,
+}
+
+/// Verify a signed url and resolve the file it points to, the root is resolved from the backend service bound to `ak` instead of taken from the url.
+/// `size` is the max size of the upload signed into the url.
+pub async fn verify_signed_url(method: FsSignedMethod, ak: &str, key: &str, size: Option<u64>, expires: i64, signature: &str, funs: &TardisFunsInst) -> TardisResult<FsSignedFile> {
+    if expires < Utc::now().timestamp() {
+        return Err(TardisError::unauthorized("The url is expired", "401-spi-object-fs-url-expired"));
+    }
+    if sign(method, ak, key, size, expires, &sign_secret(funs)?)? != signature {
+        return Err(TardisError::unauthorized("The signature of the url is invalid", "401-spi-object-fs-invalid-signature"));
+    }
+    let ctx = TardisContext {
+        ak: ak.to_string(),
+        ..Default::default()
+    };
+    let inst = funs.init(None, &ctx, true, object_initializer::init_fun).await?;
+    if inst.kind_code() != object_constants::SPI_FS_KIND_CODE {
+        return Err(TardisError::unauthorized("The signature of the url is invalid", "401-spi-object-fs-invalid-signature"));
+    }
+    let root = inst.inst::<FsClient>().0.root.clone();
+    let path = resolve_path(&root, key)?;
+    Ok(FsSignedFile { root, path })
+}
+
+/// Open the object to be streamed
+pub async fn open_object(path: &Path, key: &str) -> TardisResult<File> {
+    let file = File::open(path).await.map_err(|e| io_error(key, e))?;
+    if !file.metadata().await.map_err(|e| io_error(key, e))?.is_file() {
+        return Err(TardisError::not_found(&format!("Object {key} not found"), "404-spi-object-fs-not-found"));
+    }
+    Ok(file)
+}
+
+/// Write the object atomically from the stream, returns the etag of the content, which is its md5 like object storages.
+///
+/// The content is written to the staging directory first, and rejected once it is larger than `max_size`.
+pub async fn write_object(file: &FsSignedFile, key: &str, content: impl AsyncRead + Unpin, max_size: u64) -> TardisResult<String> {
+    let tmp_path = create_parent_and_tmp_path(&file.root, &file.path, key).await?;
+    let write = async {
+        let mut target = File::create(&tmp_path).await.map_err(|e| io_error(key, e))?;
+        let (_, md5) = copy_with_md5(content, &mut target, max_size, key).await?;
+        fs::rename(&tmp_path, &file.path).await.map_err(|e| io_error(key, e))?;
+        Ok(md5)
+    };
+    match write.await {
+        Ok(md5) => Ok(format!("\"{md5}\"")),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path).await;
+            Err(e)
+        }
+    }
+}
+
+/// Copy the content to the file and flush it, returns the size and the md5 of the content
+async fn copy_with_md5(mut content: impl AsyncRead + Unpin, target: &mut File, max_size: u64, key: &str) -> TardisResult<(u64, String)> {
+    let mut hasher = Md5::new();
+    let mut buf = vec![0; COPY_BUFFER_SIZE];
+    let mut size = 0;
+    loop {
+        let len = content.read(&mut buf).await.map_err(|e| io_error(key, e))?;
+        if len == 0 {
+            break;
+        }
+        size += len as u64;
+        if size > max_size {
+            return Err(TardisError::custom(
+                "413",
+                &format!("Object {key} is larger than {max_size} bytes"),
+                "413-spi-object-fs-too-large",
+            ));
+        }
+        hasher.update(&buf[..len]);
+        target.write_all(&buf[..len]).await.map_err(|e| io_error(key, e))?;
+    }
+    target.flush().await.map_err(|e| io_error(key, e))?;
+    Ok((size, TardisFuns::crypto.hex.encode(hasher.finalize())))
+}
+
+/// Delete the object, deleting an object not existing succeeds like object storages
+pub async fn delete_object(path: &Path, key: &str) -> TardisResult<()> {
+    match fs::remove_file(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(io_error(key, e)),
+        _ => Ok(()),
+    }
+}
+
+/// Resolve the key of an object to a file under the root, keys escaping the root are rejected
+fn resolve_path(root: &Path, key: &str) -> TardisResult<PathBuf> {
+    let mut path = root.to_path_buf();
+    let mut depth = 0;
+    for component in Path::new(key).components() {
+        match component {
+            Component::Normal(name) => {
+                path.push(name);
+                depth += 1;
+            }
+            Component::CurDir => {}
+            _ => return Err(TardisError::bad_request(&format!("Invalid object path {key}"), "400-spi-object-fs-invalid-path")),
+        }
+    }
+    if depth == 0 {
+        return Err(TardisError::bad_request(&format!("Invalid object path {key}"), "400-spi-object-fs-invalid-path"));
+    }
+    Ok(path)
+}
+
+/// Create the parent directory of the object, returns a path in the staging directory to write the object to before moving it in place.
+///
+/// The staging directory is under the same root as the buckets, so the move is a rename, and files being written never show up in the buckets.
+async fn create_parent_and_tmp_path(root: &Path, path: &Path, key: &str) -> TardisResult<PathBuf> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await.map_err(|e| io_error(key, e))?;
+    }
+    let staging_dir = root.join(STAGING_DIR);
+    fs::create_dir_all(&staging_dir).await.map_err(|e| io_error(key, e))?;
+    Ok(staging_dir.join(format!("{}.tmp", TardisFuns::field.nanoid())))
+}
+
+/// Remove the multipart uploads and staging files untouched for `upload_expire_sec`, which are abandoned,
+/// and the objects of the tamp buckets older than their expiration days
+pub async fn sweep(root: PathBuf, upload_expire_sec: u64) -> TardisResult<()> {
+    task::spawn_blocking(move || sweep_blocking(&root, upload_expire_sec, SystemTime::now()))
+        .await
+        .map_err(|e| TardisError::internal_error(&format!("Sweeping failed: {e}"), "500-spi-object-fs-io-error"))?
+        .map_err(|e| TardisError::internal_error(&format!("Sweeping failed: {e}"), "500-spi-object-fs-io-error"))
+}
+
+fn sweep_blocking(root: &Path, upload_expire_sec: u64, now: SystemTime) -> std::io::Result<()> {
+    for dir in [MULTIPART_DIR, STAGING_DIR] {
+        for (_, path, metadata) in sorted_entries(&root.join(dir))? {
+            if is_expired(&metadata, upload_expire_sec, now) {
+                remove_entry(&path, &metadata)?;
+            }
+        }
+    }
+    for (bucket_name, bucket_dir, metadata) in sorted_entries(root)? {
+        if !metadata.is_dir() || !bucket_name.ends_with(&format!("-{TAMP_BUCKET_SUFFIX}")) {
+            continue;
+        }
+        for (name, dir, metadata) in sorted_entries(&bucket_dir)? {
+            let Ok(obj_exp) = name.parse::<u64>() else {
+                continue;
+            };
+            if !metadata.is_dir() {
+                continue;
+            }
+            // remove the expired files under the directory of the expiration days
+            let mut dirs = vec![dir];
+            while let Some(dir) = dirs.pop() {
+                for (_, path, metadata) in sorted_entries(&dir)? {
+                    if metadata.is_dir() {
+                        dirs.push(path);
+                    } else if is_expired(&metadata, obj_exp * 24 * 3600, now) {
+                        remove_entry(&path, &metadata)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_expired(metadata: &Metadata, expire_sec: u64, now: SystemTime) -> bool {
+    metadata.modified().ok().and_then(|modified| now.duration_since(modified).ok()).is_some_and(|age| age.as_secs() >= expire_sec)
+}
+
+/// Remove the file or directory, which may have been removed by others
+fn remove_entry(path: &Path, metadata: &Metadata) -> std::io::Result<()> {
+    let result = if metadata.is_dir() { std::fs::remove_dir_all(path) } else { std::fs::remove_file(path) };
+    match result {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// The url carries the `ak` of the backend service instead of the root, so the layout of the server is not exposed
+fn sign_url(method: FsSignedMethod, ak: &str, key: &str, exp_secs: u32, size: Option<u64>, funs: &TardisFunsInst) -> TardisResult<String> {
+    let expires = Utc::now().timestamp() + exp_secs as i64;
+    let signature = sign(method, ak, key, size, expires, &sign_secret(funs)?)?;
+    Ok(format!(
+        "{}{OBJECT_API_PATH}?ak={}&path={}{}&expires={expires}&signature={signature}",
+        funs.conf::<ObjectConfig>().fs_base_url.trim_end_matches('/'),
+        encode_query_value(ak),
+        encode_query_value(key),
+        size.map(|size| format!("&size={size}")).unwrap_or_default(),
+    ))
+}
+
+fn sign(method: FsSignedMethod, ak: &str, key: &str, size: Option<u64>, expires: i64, secret: &str) -> TardisResult<String> {
+    let size = size.map(|size| size.to_string()).unwrap_or_default();
+    Ok(TardisFuns::crypto.hex.encode(TardisFuns::crypto.digest.hmac_sha256(format!("{}\n{expires}\n{ak}\n{key}\n{size}", method.as_str()), secret)?))
+}
+
+/// The secret is checked when the backend service is initialized, see `object_fs_initializer::init`
+fn sign_secret(funs: &TardisFunsInst) -> TardisResult<String> {
+    let secret = &funs.conf::<ObjectConfig>().fs_sign_secret;
+    if secret.is_empty() {
+        return Err(TardisError::internal_error(
+            "The secret signing the urls is not configured",
+            "500-spi-object-fs-sign-secret-required",
+        ));
+    }
+    Ok(secret.clone())
+}
+
+/// Page the sorted keys like the listing of s3
+struct KeyPager<'a> {
+    prefix: &'a str,
+    delimiter: Option<&'a str>,
+    continuation_token: Option<&'a str>,
+    page_size: usize,
+    objects: Vec<String>,
+    common_prefixes: Vec<String>,
+    last: Option<String>,
+    full: bool,
+}
+
+impl<'a> KeyPager<'a> {
+    fn new(prefix: &'a str, delimiter: Option<&'a str>, continuation_token: Option<&'a str>, page_size: usize) -> Self {
+        KeyPager {
+            prefix,
+            delimiter: delimiter.filter(|delimiter| !delimiter.is_empty()),
+            continuation_token,
+            page_size,
+            objects: vec![],
+            common_prefixes: vec![],
+            last: None,
+            full: false,
+        }
+    }
+
+    /// Whether all keys starting with `dir_prefix` can be skipped
+    fn skips(&self, dir_prefix: &str) -> bool {
+        if self.full || !(dir_prefix.starts_with(self.prefix) || self.prefix.starts_with(dir_prefix)) {
+            return true;
+        }
+        if let Some(continuation_token) = self.continuation_token {
+            // all keys under the directory are not greater than the token
+            if continuation_token > dir_prefix && !continuation_token.starts_with(dir_prefix) {
+                return true;
+            }
+            if self.delimiter.is_some_and(|delimiter| continuation_token.ends_with(delimiter)) && dir_prefix.starts_with(continuation_token) {
+                return true;
+            }
+        }
+        // all keys under the directory are rolled up into the last common prefix
+        self.common_prefix(dir_prefix).is_some_and(|common_prefix| Some(&common_prefix) == self.common_prefixes.last())
+    }
+
+    /// Push the next key in order, returns whether the key is added as an object
+    fn push(&mut self, key: &str) -> bool {
+        if self.full || !key.starts_with(self.prefix) {
+            return false;
+        }
+        if let Some(continuation_token) = self.continuation_token {
+            // the token of a common prefix skips all keys under it
+            let under_token = self.delimiter.is_some_and(|delimiter| continuation_token.ends_with(delimiter)) && key.starts_with(continuation_token);
+            if key <= continuation_token || under_token {
+                return false;
+            }
+        }
+        let common_prefix = self.common_prefix(key);
+        if common_prefix.is_some() && common_prefix.as_ref() == self.common_prefixes.last() {
+            return false;
+        }
+        if self.objects.len() + self.common_prefixes.len() == self.page_size {
+            self.full = true;
+            return false;
+        }
+        if let Some(common_prefix) = common_prefix {
+            self.last = Some(common_prefix.clone());
+            self.common_prefixes.push(common_prefix);
+            false
+        } else {
+            self.last = Some(key.to_string());
+            self.objects.push(key.to_string());
+            true
+        }
+    }
+
+    fn common_prefix(&self, key: &str) -> Option<String> {
+        let rest = key.strip_prefix(self.prefix)?;
+        self.delimiter.and_then(|delimiter| rest.find(delimiter).map(|idx| format!("{}{}", self.prefix, &rest[..idx + delimiter.len()])))
+    }
+
+    /// Returns the objects, the common prefixes and the continuation token of the next page
+    fn finish(self) -> (Vec<String>, Vec<String>, Option<String>) {
+        (self.objects, self.common_prefixes, if self.full { self.last } else { None })
+    }
+}
+
+/// Walk the files under the directory in the order of their keys until the page is full, returns the objects of the page with their metadata
+fn walk_sorted(dir: &Path, pager: &mut KeyPager) -> std::io::Result<Vec<(String, Metadata)>> {
+    let mut objects = vec![];
+    // directories being walked, with their key prefixes and the entries not walked yet in reverse order
+    let mut stack = vec![(String::new(), sorted_entries(dir)?)];
+    while let Some((dir_prefix, entries)) = stack.last_mut() {
+        if pager.skips(dir_prefix) {
+            stack.pop();
+            continue;
+        }
+        let Some((name, path, metadata)) = entries.pop() else {
+            stack.pop();
+            continue;
+        };
+        let key = format!("{dir_prefix}{name}");
+        if metadata.is_dir() {
+            let dir_prefix = format!("{key}/");
+            if !pager.skips(&dir_prefix) {
+                let entries = match sorted_entries(&path) {
+                    Ok(entries) => entries,
+                    Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e),
+                };
+                stack.push((dir_prefix, entries));
+            }
+        } else if pager.push(&key) {
+            objects.push((key, metadata));
+        }
+    }
+    Ok(objects)
+}
+
+/// Entries of the directory in the reverse order of their keys, a directory is ordered as its name followed by `/`
+fn sorted_entries(dir: &Path) -> std::io::Result<Vec<(String, PathBuf, Metadata)>> {
+    let mut entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .map(|entry| {
+                let entry = entry?;
+                Ok((entry.file_name().to_string_lossy().to_string(), entry.path(), entry.metadata()?))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?,
+        Err(e) if e.kind() == ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e),
+    };
+    entries.sort_by_cached_key(|(name, _, metadata)| std::cmp::Reverse(if metadata.is_dir() { format!("{name}/") } else { name.clone() }));
+    Ok(entries)
+}
+
+fn modified_time(metadata: &Metadata) -> Option<DateTime<Utc>> {
+    metadata.modified().ok().map(DateTime::<Utc>::from)
+}
+
+fn io_error(key: &str, e: std::io::Error) -> TardisError {
+    if e.kind() == ErrorKind::NotFound {
+        TardisError::not_found(&format!("Object {key} not found"), "404-spi-object-fs-not-found")
+    } else {
+        TardisError::internal_error(&format!("Object {key} access failed: {e}"), "500-spi-object-fs-io-error")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use std::time::{Duration, SystemTime};
+
+    use tardis::TardisFuns;
+
+    use super::{encode_query_value, resolve_path, sweep_blocking, KeyPager, MULTIPART_DIR, STAGING_DIR};
+
+    fn page_keys(keys: &[String], prefix: &str, delimiter: Option<&str>, continuation_token: Option<&str>, page_size: usize) -> (Vec<String>, Vec<String>, Option<String>) {
+        let mut pager = KeyPager::new(prefix, delimiter, continuation_token, page_size);
+        for key in keys {
+            pager.push(key);
+        }
+        pager.finish()
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(resolve_path(Path::new("/data"), "spi-pri/a/001.txt").ok(), Some(PathBuf::from("/data/spi-pri/a/001.txt")));
+        assert_eq!(resolve_path(Path::new("/data"), "spi-pri/./a.txt").ok(), Some(PathBuf::from("/data/spi-pri/a.txt")));
+        assert!(resolve_path(Path::new("/data"), "spi-pri/../../etc/passwd").is_err());
+        assert!(resolve_path(Path::new("/data"), "/etc/passwd").is_err());
+        assert!(resolve_path(Path::new("/data"), "").is_err());
+        assert!(resolve_path(Path::new("/data"), ".").is_err());
+    }
+
+    #[test]
+    fn test_encode_query_value() {
+        assert_eq!(encode_query_value("spi-pri/a b.txt"), "spi-pri%2Fa%20b.txt");
+        assert_eq!(encode_query_value("文"), "%E6%96%87");
+    }
+
+    #[test]
+    fn test_page_keys() {
+        let keys = ["a/1.txt", "a/2.txt", "a/b/3.txt", "a/b/4.txt", "a/c/5.txt", "b/6.txt"].map(str::to_string);
+        assert_eq!(
+            page_keys(&keys, "a/", None, None, 3),
+            (
+                vec!["a/1.txt".to_string(), "a/2.txt".to_string(), "a/b/3.txt".to_string()],
+                vec![],
+                Some("a/b/3.txt".to_string())
+            )
+        );
+        assert_eq!(
+            page_keys(&keys, "a/", None, Some("a/b/3.txt"), 3),
+            (vec!["a/b/4.txt".to_string(), "a/c/5.txt".to_string()], vec![], None)
+        );
+        assert_eq!(
+            page_keys(&keys, "a/", Some("/"), None, 3),
+            (vec!["a/1.txt".to_string(), "a/2.txt".to_string()], vec!["a/b/".to_string()], Some("a/b/".to_string()))
+        );
+        assert_eq!(page_keys(&keys, "a/", Some("/"), Some("a/b/"), 3), (vec![], vec!["a/c/".to_string()], None));
+        assert_eq!(page_keys(&keys, "c/", Some("/"), None, 3), (vec![], vec![], None));
+    }
+
+    #[test]
+    fn test_sweep() {
+        let root = std::env::temp_dir().join(TardisFuns::field.nanoid());
+        let upload = root.join(MULTIPART_DIR).join("upload001");
+        let staging = root.join(STAGING_DIR).join("001.tmp");
+        let tamp = root.join("spi-tamp").join("1").join("a").join("001.txt");
+        let private = root.join("spi-pri").join("1").join("001.txt");
+        for path in [&upload.join("target"), &staging, &tamp, &private] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "hello").unwrap();
+        }
+        sweep_blocking(&root, 3600, SystemTime::now()).unwrap();
+        assert!(upload.exists() && staging.exists() && tamp.exists());
+        // an hour later the uploads are abandoned
+        sweep_blocking(&root, 3600, SystemTime::now() + Duration::from_secs(3600)).unwrap();
+        assert!(!upload.exists() && !staging.exists());
+        assert!(tamp.exists());
+        // a day later the objects expiring in a day are removed
+        sweep_blocking(&root, 3600, SystemTime::now() + Duration::from_secs(24 * 3600)).unwrap();
+        assert!(!tamp.exists());
+        assert!(private.exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
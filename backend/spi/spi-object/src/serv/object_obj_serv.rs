@@ -3,18 +3,26 @@ use std::sync::Arc;
 
 use bios_basic::spi::serv::spi_bs_serv::SpiBsServ;
 use bios_basic::spi::spi_funs::{SpiBsInst, SpiBsInstExtractor};
+use bios_basic::spi::spi_initializer;
 use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
 use tardis::basic::result::TardisResult;
+use tardis::log::warn;
 use tardis::tokio::sync::RwLock;
 use tardis::TardisFunsInst;
 
-use crate::dto::object_dto::{ObjectBatchBuildCreatePresignUrlReq, ObjectCompleteMultipartUploadReq, ObjectInitiateMultipartUploadReq, ObjectObjPresignKind};
-use crate::object_constants::USE_REGION_ENDPOINT;
+use crate::dto::object_dto::{
+    ObjectBatchBuildCreatePresignUrlReq, ObjectCompleteMultipartUploadReq, ObjectHeadResp, ObjectInitiateMultipartUploadReq, ObjectListResp, ObjectObjPresignKind,
+    ObjectTagsPutReq, ObjectUsageResp,
+};
+use crate::object_constants::{TAMP_BUCKET_SUFFIX, USE_REGION_ENDPOINT};
 use crate::{object_constants, object_initializer};
 
 use super::custom_s3::object_custom_s3_obj_serv::CustomS3Service;
+#[cfg(feature = "spi-fs")]
+use super::fs;
 use super::s3::S3 as _;
-use super::{obs, s3};
+use super::{object_quota_serv, obs, s3};
 
 pub async fn presign_obj_url(
     presign_kind: ObjectObjPresignKind,
@@ -25,6 +33,7 @@ pub async fn presign_obj_url(
     private: Option<bool>,
     special: Option<bool>,
     obj_exp: Option<u32>,
+    size: Option<u64>,
     bucket: Option<String>,
     bs_id: Option<String>,
     funs: &TardisFunsInst,
@@ -43,6 +52,7 @@ pub async fn presign_obj_url(
                 private,
                 special,
                 obj_exp,
+                size,
                 bs_id.as_deref(),
                 bucket.as_deref(),
                 funs,
@@ -62,6 +72,7 @@ pub async fn presign_obj_url(
                 private,
                 special,
                 obj_exp,
+                size,
                 bs_id.as_deref(),
                 bucket.as_deref(),
                 funs,
@@ -70,6 +81,10 @@ pub async fn presign_obj_url(
             )
             .await
         }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => {
+            fs::object_fs_obj_serv::FsService::presign_obj_url(presign_kind, object_path, exp_secs, private, special, obj_exp, size, funs, ctx, &inst).await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -108,10 +123,15 @@ pub async fn batch_get_presign_obj_url(
             )
             .await
         }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => {
+            fs::object_fs_obj_serv::FsService::batch_get_presign_obj_url(object_paths, exp_secs, private, special, obj_exp, funs, ctx, &inst).await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
 
+/// The object is accounted to the usage once the upload is completed, see [`complete_multipart_upload`]
 pub async fn initiate_multipart_upload(req: ObjectInitiateMultipartUploadReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
     let inst = get_bs(req.bs_id.clone(), None, funs, ctx).await?;
     match inst.kind_code() {
@@ -147,6 +167,8 @@ pub async fn initiate_multipart_upload(req: ObjectInitiateMultipartUploadReq, fu
             )
             .await
         }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => fs::object_fs_obj_serv::FsService::initiate_multipart_upload(&req.object_path, req.private, req.special, req.obj_exp, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -190,12 +212,30 @@ pub async fn batch_build_create_presign_url(req: ObjectBatchBuildCreatePresignUr
             )
             .await
         }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => {
+            fs::object_fs_obj_serv::FsService::batch_build_create_presign_url(
+                &req.object_path,
+                &req.upload_id,
+                req.part_number,
+                req.expire_sec,
+                req.private,
+                req.special,
+                req.obj_exp,
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
 
+/// The completed object is accounted to the usage with its real size, see [`confirm_upload`]
 pub async fn complete_multipart_upload(req: ObjectCompleteMultipartUploadReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
     let inst = get_bs(req.bs_id.clone(), None, funs, ctx).await?;
+    let (object_path, private, special, obj_exp, accounted) = (req.object_path.clone(), req.private, req.special, req.obj_exp, req.bs_id.is_none());
     match inst.kind_code() {
         #[cfg(feature = "spi-s3")]
         object_constants::SPI_S3_KIND_CODE => {
@@ -231,8 +271,16 @@ pub async fn complete_multipart_upload(req: ObjectCompleteMultipartUploadReq, fu
             )
             .await
         }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => {
+            fs::object_fs_obj_serv::FsService::complete_multipart_upload(&req.object_path, &req.upload_id, req.parts, req.private, req.special, req.obj_exp, &inst).await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }?;
+    if accounted {
+        confirm_upload(&object_path, private, special, obj_exp, funs, ctx).await?;
     }
+    Ok(())
 }
 
 pub async fn object_delete(
@@ -246,7 +294,7 @@ pub async fn object_delete(
     ctx: &TardisContext,
 ) -> TardisResult<()> {
     let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
-    match inst.kind_code() {
+    let result = match inst.kind_code() {
         #[cfg(feature = "spi-s3")]
         object_constants::SPI_S3_KIND_CODE => {
             s3::object_s3_obj_serv::S3Service::object_delete(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
@@ -255,8 +303,15 @@ pub async fn object_delete(
         object_constants::SPI_OBS_KIND_CODE => {
             obs::object_obs_obj_serv::OBSService::object_delete(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
         }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => fs::object_fs_obj_serv::FsService::object_delete(&object_path, private, special, obj_exp, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
+    };
+    if result.is_ok() && bs_id.is_none() {
+        let bucket = usage_bucket(private, special, obj_exp, &inst);
+        log_accounting_error(object_quota_serv::forget_objects(&bucket, &[object_path], funs, ctx).await, ctx);
     }
+    result
 }
 
 pub async fn batch_object_delete(
@@ -269,8 +324,9 @@ pub async fn batch_object_delete(
     funs: &TardisFunsInst,
     ctx: &TardisContext,
 ) -> TardisResult<Vec<String>> {
+    let accounted_object_paths = if bs_id.is_none() { object_paths.clone() } else { vec![] };
     let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
-    match inst.kind_code() {
+    let result = match inst.kind_code() {
         #[cfg(feature = "spi-s3")]
         object_constants::SPI_S3_KIND_CODE => {
             s3::object_s3_obj_serv::S3Service::batch_object_delete(object_paths, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
@@ -279,8 +335,16 @@ pub async fn batch_object_delete(
         object_constants::SPI_OBS_KIND_CODE => {
             obs::object_obs_obj_serv::OBSService::batch_object_delete(object_paths, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
         }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => fs::object_fs_obj_serv::FsService::batch_object_delete(object_paths, private, special, obj_exp, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
+    };
+    if let Ok(failed_object_paths) = &result {
+        let deleted_object_paths = accounted_object_paths.into_iter().filter(|object_path| !failed_object_paths.contains(object_path)).collect::<Vec<_>>();
+        let bucket = usage_bucket(private, special, obj_exp, &inst);
+        log_accounting_error(object_quota_serv::forget_objects(&bucket, &deleted_object_paths, funs, ctx).await, ctx);
     }
+    result
 }
 
 pub async fn object_copy(
@@ -298,7 +362,7 @@ pub async fn object_copy(
         ..ctx.clone()
     };
     let inst = get_bs(bs_id.clone(), Some(USE_REGION_ENDPOINT.to_string()), funs, &mock_ctx).await?;
-    match inst.kind_code() {
+    let result = match inst.kind_code() {
         #[cfg(feature = "spi-s3")]
         object_constants::SPI_S3_KIND_CODE => {
             s3::object_s3_obj_serv::S3Service::object_copy(&from, &to, private, special, bs_id.as_deref(), bucket.as_deref(), funs, &mock_ctx, &inst).await
@@ -307,8 +371,19 @@ pub async fn object_copy(
         object_constants::SPI_OBS_KIND_CODE => {
             obs::object_obs_obj_serv::OBSService::object_copy(&from, &to, private, special, bs_id.as_deref(), bucket.as_deref(), funs, &mock_ctx, &inst).await
         }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => fs::object_fs_obj_serv::FsService::object_copy(&from, &to, private, special, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
+    };
+    if result.is_ok() && bs_id.is_none() {
+        let bucket = usage_bucket(private, special, None, &inst);
+        let copied = async {
+            let size = object_quota_serv::get_object_size(&bucket, &from, funs).await?.unwrap_or_default();
+            object_quota_serv::record_object(&bucket, &to, size, funs, ctx).await
+        };
+        log_accounting_error(copied.await, ctx);
     }
+    result
 }
 
 pub async fn object_exist(
@@ -331,10 +406,256 @@ pub async fn object_exist(
         object_constants::SPI_OBS_KIND_CODE => {
             obs::object_obs_obj_serv::OBSService::object_exist(&object_paths, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
         }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => fs::object_fs_obj_serv::FsService::object_exist(&object_paths, private, special, obj_exp, &inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+/// Max tags of an object allowed by s3
+const MAX_OBJECT_TAGS: usize = 10;
+
+pub async fn object_list(
+    prefix: String,
+    delimiter: Option<String>,
+    continuation_token: Option<String>,
+    page_size: Option<u16>,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<ObjectListResp> {
+    // obs only supports listing under the regional endpoint, see `OBSService::object_list`
+    let mock_ctx = TardisContext {
+        ext: Arc::new(RwLock::new(HashMap::from([(USE_REGION_ENDPOINT.to_string(), "true".to_string())]))),
+        ..ctx.clone()
+    };
+    let inst = get_bs(bs_id.clone(), Some(USE_REGION_ENDPOINT.to_string()), funs, &mock_ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::object_list(
+                &prefix,
+                delimiter,
+                continuation_token,
+                page_size,
+                private,
+                special,
+                obj_exp,
+                bs_id.as_deref(),
+                bucket.as_deref(),
+                funs,
+                &mock_ctx,
+                &inst,
+            )
+            .await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::object_list(
+                &prefix,
+                delimiter,
+                continuation_token,
+                page_size,
+                private,
+                special,
+                obj_exp,
+                bs_id.as_deref(),
+                bucket.as_deref(),
+                funs,
+                &mock_ctx,
+                &inst,
+            )
+            .await
+        }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => {
+            fs::object_fs_obj_serv::FsService::object_list(&prefix, delimiter, continuation_token, page_size, private, special, obj_exp, &inst).await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
 
+pub async fn object_head(
+    object_path: String,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<ObjectHeadResp> {
+    let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::object_head(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::object_head(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        #[cfg(feature = "spi-fs")]
+        object_constants::SPI_FS_KIND_CODE => fs::object_fs_obj_serv::FsService::object_head(&object_path, private, special, obj_exp, &inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn object_tags_put(req: ObjectTagsPutReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if req.tags.len() > MAX_OBJECT_TAGS {
+        return Err(TardisError::bad_request(
+            &format!("An object can have at most {MAX_OBJECT_TAGS} tags"),
+            "400-spi-object-too-many-tags",
+        ));
+    }
+    let inst = get_bs(req.bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::object_tags_put(
+                &req.object_path,
+                req.tags,
+                req.private,
+                req.special,
+                req.obj_exp,
+                req.bs_id.as_deref(),
+                req.bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::object_tags_put(
+                &req.object_path,
+                req.tags,
+                req.private,
+                req.special,
+                req.obj_exp,
+                req.bs_id.as_deref(),
+                req.bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn object_tags_get(
+    object_path: String,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<HashMap<String, String>> {
+    let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::object_tags_get(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::object_tags_get(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+/// Recount the usage of the own paths of the context from the built-in buckets.
+/// Uploads by presigned urls are only accounted once confirmed, so the usage is only exact after recounting.
+/// The objects are listed before the usage is replaced in one transaction.
+/// The default bucket of services without isolation flag is shared by all the own paths, only the objects under the prefix of the own paths are counted in it.
+pub async fn refresh_usage(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ObjectUsageResp> {
+    let mock_ctx = TardisContext {
+        ext: Arc::new(RwLock::new(HashMap::from([(USE_REGION_ENDPOINT.to_string(), "true".to_string())]))),
+        ..ctx.clone()
+    };
+    let inst = get_bs(None, Some(USE_REGION_ENDPOINT.to_string()), funs, &mock_ctx).await?;
+    let isolated = spi_initializer::common::get_isolation_flag_from_ext(&inst.ext).is_some();
+    // services without isolation flag have a single bucket shared by all the own paths
+    let buckets = if isolated {
+        vec![(Some(true), None, None), (Some(false), None, None), (None, Some(true), None), (None, None, Some(1))]
+    } else {
+        vec![(None, None, None)]
+    };
+    let prefix = if isolated || ctx.own_paths.is_empty() {
+        "".to_string()
+    } else {
+        format!("{}/", ctx.own_paths)
+    };
+    let mut usage_buckets = Vec::with_capacity(buckets.len());
+    for (private, special, obj_exp) in buckets {
+        let mut objects = vec![];
+        let mut continuation_token = None;
+        loop {
+            let page = object_list(prefix.clone(), None, continuation_token, None, private, special, obj_exp, None, None, funs, ctx).await?;
+            objects.extend(page.objects.into_iter().map(|object| (object.object_path, object.size as i64)));
+            continuation_token = page.next_continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        usage_buckets.push((usage_bucket(private, special, obj_exp, &inst), objects));
+    }
+    object_quota_serv::reload_usage(usage_buckets, funs, ctx).await
+}
+
+/// Account an object uploaded to the built-in service with its real size, called once the upload by a presigned url or a multipart upload is done.
+/// The object is removed when it exceeds the quota.
+pub async fn confirm_upload(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = get_bs(None, None, funs, ctx).await?;
+    let size = object_head(object_path.to_string(), private, special, obj_exp, None, None, funs, ctx).await?.size.unwrap_or_default();
+    if !object_quota_serv::record_upload(&usage_bucket(private, special, obj_exp, &inst), object_path, size, funs, ctx).await? {
+        object_delete(object_path.to_string(), private, special, obj_exp, None, None, funs, ctx).await?;
+        return Err(object_quota_serv::quota_exceeded(&ctx.own_paths));
+    }
+    Ok(())
+}
+
+/// Name of the built-in bucket of the kinds, `None` for services without isolation flag, which use their default bucket
+pub(crate) fn built_in_bucket_name(private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> Option<String> {
+    spi_initializer::common::get_isolation_flag_from_ext(&inst.ext).map(|bucket_name_prefix| {
+        format!(
+            "{}-{}",
+            bucket_name_prefix,
+            if special.unwrap_or(false) {
+                "spe"
+            } else if obj_exp.is_some() {
+                TAMP_BUCKET_SUFFIX
+            } else if private.unwrap_or(true) {
+                "pri"
+            } else {
+                "pub"
+            }
+        )
+    })
+}
+
+/// Bucket the objects are accounted in, empty for the default bucket of services without isolation flag
+fn usage_bucket(private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> String {
+    built_in_bucket_name(private, special, obj_exp, inst).unwrap_or_default()
+}
+
+/// Failures of accounting are only logged as the object has been changed, the usage is corrected by recounting
+fn log_accounting_error(result: TardisResult<()>, ctx: &TardisContext) {
+    if let Err(e) = result {
+        warn!("[BIOS.Object] Accounting the usage of {} failed: {e:?}", ctx.own_paths);
+    }
+}
+
 async fn get_bs(spi_bs_id: Option<String>, custom_cache_key: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Arc<SpiBsInst>> {
     if let Some(spi_bs_id) = spi_bs_id {
         let spi_bs = SpiBsServ::get_bs(&spi_bs_id, funs, ctx).await?;
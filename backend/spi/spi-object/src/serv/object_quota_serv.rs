@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+use bios_basic::rbum::{helper::rbum_scope_helper, rbum_enumeration::RbumScopeLevelKind};
+use bios_sdk_invoke::{clients::spi_stats_client::SpiStatsClient, dto::stats_record_dto::StatsFactRecordLoadReq};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::Utc,
+    db::{
+        reldb_client::TardisRelDBlConnection,
+        sea_orm::{
+            sea_query::{Cond, Expr, Query, Value},
+            ColumnTrait, Order, Set,
+        },
+    },
+    log::warn,
+    serde_json::json,
+    TardisFunsInst,
+};
+
+use crate::{
+    domain::{object_quota, object_usage_item},
+    dto::object_dto::{ObjectQuotaModifyReq, ObjectUsageResp},
+    object_config::ObjectConfig,
+};
+
+/// Set the quota of the own paths.
+/// Quotas are managed by the sys and tenant contexts only, and a tenant only sets the quotas of the own paths below it.
+pub async fn modify_quota(req: &ObjectQuotaModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if !matches!(rbum_scope_helper::get_scope_level_by_context(ctx)?, RbumScopeLevelKind::Root | RbumScopeLevelKind::L1) {
+        return Err(TardisError::forbidden("Only the sys and tenant contexts can set quotas", "403-spi-object-quota-not-admin"));
+    }
+    let own_paths = req.own_paths.trim();
+    if own_paths.is_empty() || own_paths == ctx.own_paths || !is_within(own_paths, &ctx.own_paths) {
+        return Err(TardisError::forbidden(
+            &format!("The quota of {own_paths} is out of the own paths of the context"),
+            "403-spi-object-quota-out-of-own-paths",
+        ));
+    }
+    let mut query = Query::update();
+    query
+        .table(object_quota::Entity)
+        .value(object_quota::Column::QuotaBytes, req.quota_bytes)
+        .value(object_quota::Column::UpdateTime, Utc::now())
+        .and_where(object_quota::Column::OwnPaths.eq(own_paths));
+    if funs.db().execute(&query).await?.rows_affected() == 0 {
+        insert_quota(own_paths, req.quota_bytes, 0, 0, funs, ctx).await?;
+    }
+    Ok(())
+}
+
+/// Refuse to upload when the usage of the own paths of the context or of its ancestors would exceed their quota.
+/// `size` is required once a quota applies.
+/// This only rejects the uploads early, the uploaded objects are checked again when they are accounted by [`record_upload`].
+pub async fn check_upload(size: Option<u64>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let mut query = Query::select();
+    query
+        .columns(object_quota_columns())
+        .from(object_quota::Entity)
+        .and_where(object_quota::Column::OwnPaths.is_in(ancestor_paths(&ctx.own_paths)))
+        .and_where(object_quota::Column::QuotaBytes.gt(0));
+    let quotas = funs.db().find_dtos::<object_quota::Model>(&query).await?;
+    if quotas.is_empty() {
+        return Ok(());
+    }
+    let Some(size) = size else {
+        return Err(TardisError::bad_request(
+            "The size of the upload is required by the storage quota",
+            "400-spi-object-quota-size-required",
+        ));
+    };
+    for quota in quotas {
+        let used_bytes = find_subtree(&quota.own_paths, funs).await?.iter().map(|usage| usage.used_bytes).sum::<i64>();
+        if used_bytes.saturating_add(size as i64) > quota.quota_bytes {
+            return Err(quota_exceeded(&quota.own_paths));
+        }
+    }
+    Ok(())
+}
+
+pub fn quota_exceeded(own_paths: &str) -> TardisError {
+    TardisError::forbidden(&format!("The storage quota of {own_paths} would be exceeded"), "403-spi-object-quota-exceeded")
+}
+
+/// Account an object to the own paths of the context, replacing the size accounted to it before.
+/// The object has been written by the service itself, e.g. copied, so it is accounted whatever the quota is.
+pub async fn record_object(bucket: &str, object_path: &str, size: i64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    account_object(bucket, object_path, size, false, funs, ctx).await.map(|_| ())
+}
+
+/// Account an uploaded object to the own paths of the context with its real size, replacing the size accounted to it before.
+/// Returns `false` without accounting the object when the quota of the own paths of the context or of its ancestors would be exceeded.
+pub async fn record_upload(bucket: &str, object_path: &str, size: i64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+    account_object(bucket, object_path, size, true, funs, ctx).await
+}
+
+/// Size accounted to the object, `None` if the object is not accounted
+pub async fn get_object_size(bucket: &str, object_path: &str, funs: &TardisFunsInst) -> TardisResult<Option<i64>> {
+    Ok(find_usage_items(bucket, &[object_path.to_string()], funs.db()).await?.pop().map(|item| item.size))
+}
+
+/// Remove the deleted objects from the usage of the own paths they are accounted to
+pub async fn forget_objects(bucket: &str, object_paths: &[String], funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if object_paths.is_empty() {
+        return Ok(());
+    }
+    let mut conn = funs.reldb().conn();
+    conn.begin().await?;
+    let own_paths = delete_usage_items(bucket, object_paths, &conn).await?;
+    conn.commit().await?;
+    for own_paths in own_paths {
+        feed_stats(&own_paths, funs, ctx).await;
+    }
+    Ok(())
+}
+
+/// Replace the objects accounted to the own paths of the context in the buckets and the usage of the own paths with the objects recounted from the storage, in one transaction
+pub async fn reload_usage(buckets: Vec<(String, Vec<(String, i64)>)>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ObjectUsageResp> {
+    let used_bytes = buckets.iter().flat_map(|(_, objects)| objects.iter().map(|(_, size)| *size)).sum::<i64>();
+    let object_count = buckets.iter().map(|(_, objects)| objects.len() as i64).sum::<i64>();
+    ensure_usage(&ctx.own_paths, funs, ctx).await?;
+    let mut conn = funs.reldb().conn();
+    conn.begin().await?;
+    let mut changed_own_paths = vec![];
+    for (bucket, objects) in buckets {
+        let mut query = Query::delete();
+        query
+            .from_table(object_usage_item::Entity)
+            .and_where(object_usage_item::Column::Bucket.eq(bucket.as_str()))
+            .and_where(object_usage_item::Column::OwnPaths.eq(ctx.own_paths.as_str()));
+        conn.execute(&query).await?;
+        if objects.is_empty() {
+            continue;
+        }
+        // objects accounted to other own paths
+        changed_own_paths.extend(delete_usage_items(&bucket, &objects.iter().map(|(object_path, _)| object_path.clone()).collect::<Vec<_>>(), &conn).await?);
+        conn.insert_many(objects.iter().map(|(object_path, size)| new_usage_item(&bucket, object_path, *size, ctx)).collect(), ctx).await?;
+    }
+    let mut query = Query::update();
+    query
+        .table(object_quota::Entity)
+        .value(object_quota::Column::UsedBytes, used_bytes)
+        .value(object_quota::Column::ObjectCount, object_count)
+        .value(object_quota::Column::UpdateTime, Utc::now())
+        .and_where(object_quota::Column::OwnPaths.eq(ctx.own_paths.as_str()));
+    conn.execute(&query).await?;
+    conn.commit().await?;
+    for own_paths in changed_own_paths.iter().filter(|own_paths| **own_paths != ctx.own_paths) {
+        feed_stats(own_paths, funs, ctx).await;
+    }
+    feed_stats(&ctx.own_paths, funs, ctx).await;
+    get_usage(&ctx.own_paths, funs).await?.ok_or_else(|| funs.err().not_found("object_quota", "reload_usage", "usage not found", "404-spi-object-usage-not-exist"))
+}
+
+/// Usage report of the own paths of the context and its descendants
+pub async fn find_usages(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<ObjectUsageResp>> {
+    Ok(find_subtree(&ctx.own_paths, funs).await?.into_iter().map(to_usage_resp).collect())
+}
+
+/// Account the object in one transaction.
+/// When `checked`, the quotas of the own paths of the context and its ancestors are locked, so the accounting of the uploads under them are serialized,
+/// and the usage is only increased by a conditional update keeping the usage of their subtrees within the quotas.
+async fn account_object(bucket: &str, object_path: &str, size: i64, checked: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+    ensure_usage(&ctx.own_paths, funs, ctx).await?;
+    let mut conn = funs.reldb().conn();
+    conn.begin().await?;
+    let quotas = if checked { lock_quotas(&ctx.own_paths, &conn).await? } else { vec![] };
+    let previous = find_usage_items(bucket, &[object_path.to_string()], &conn).await?.pop();
+    let (bytes, count) = match &previous {
+        Some(previous) if previous.own_paths == ctx.own_paths => (size - previous.size, 0),
+        _ => (size, 1),
+    };
+    if !add_usage(&ctx.own_paths, bytes, count, &quotas, &conn).await? {
+        conn.rollback().await?;
+        return Ok(false);
+    }
+    let previous_own_paths = match previous {
+        Some(previous) => {
+            if previous.own_paths != ctx.own_paths {
+                add_usage(&previous.own_paths, -previous.size, -1, &[], &conn).await?;
+            }
+            let mut query = Query::update();
+            query
+                .table(object_usage_item::Entity)
+                .value(object_usage_item::Column::OwnPaths, ctx.own_paths.as_str())
+                .value(object_usage_item::Column::Size, size)
+                .value(object_usage_item::Column::UpdateTime, Utc::now())
+                .and_where(object_usage_item::Column::Bucket.eq(bucket))
+                .and_where(object_usage_item::Column::ObjectPath.eq(object_path));
+            conn.execute(&query).await?;
+            Some(previous.own_paths).filter(|own_paths| *own_paths != ctx.own_paths)
+        }
+        None => {
+            conn.insert_one(new_usage_item(bucket, object_path, size, ctx), ctx).await?;
+            None
+        }
+    };
+    conn.commit().await?;
+    if let Some(previous_own_paths) = previous_own_paths {
+        feed_stats(&previous_own_paths, funs, ctx).await;
+    }
+    feed_stats(&ctx.own_paths, funs, ctx).await;
+    Ok(true)
+}
+
+/// Lock the quotas of the own paths and its ancestors in the transaction of `conn`, in the order of the own paths to avoid deadlocks
+async fn lock_quotas(own_paths: &str, conn: &TardisRelDBlConnection) -> TardisResult<Vec<object_quota::Model>> {
+    let mut query = Query::select();
+    query
+        .columns(object_quota_columns())
+        .from(object_quota::Entity)
+        .and_where(object_quota::Column::OwnPaths.is_in(ancestor_paths(own_paths)))
+        .and_where(object_quota::Column::QuotaBytes.gt(0))
+        .order_by(object_quota::Column::OwnPaths, Order::Asc)
+        .lock_exclusive();
+    conn.find_dtos::<object_quota::Model>(&query).await
+}
+
+/// Add the deltas to the usage of the own paths, the usage never goes below zero.
+/// An increase only applies when the usage of the subtrees of the `quotas` stays within them, returns whether the usage is updated.
+async fn add_usage(own_paths: &str, bytes: i64, count: i64, quotas: &[object_quota::Model], conn: &TardisRelDBlConnection) -> TardisResult<bool> {
+    if bytes == 0 && count == 0 {
+        return Ok(true);
+    }
+    let mut query = Query::update();
+    query
+        .table(object_quota::Entity)
+        .value(object_quota::Column::UsedBytes, Expr::cust(format!("GREATEST(used_bytes + ({bytes}), 0)")))
+        .value(object_quota::Column::ObjectCount, Expr::cust(format!("GREATEST(object_count + ({count}), 0)")))
+        .value(object_quota::Column::UpdateTime, Utc::now())
+        .and_where(object_quota::Column::OwnPaths.eq(own_paths));
+    if bytes > 0 {
+        for quota in quotas {
+            query.and_where(Expr::cust_with_values(
+                "(SELECT COALESCE(SUM(subtree.used_bytes), 0) FROM object_quota subtree WHERE subtree.own_paths = ? OR subtree.own_paths LIKE ?) + ? <= ?",
+                [
+                    Value::from(quota.own_paths.as_str()),
+                    Value::from(format!("{}/%", escape_like(&quota.own_paths))),
+                    Value::from(bytes),
+                    Value::from(quota.quota_bytes),
+                ],
+            ));
+        }
+    }
+    Ok(conn.execute(&query).await?.rows_affected() > 0)
+}
+
+/// Delete the usage items of the objects and subtract them from the usage of the own paths they are accounted to, returns these own paths
+async fn delete_usage_items(bucket: &str, object_paths: &[String], conn: &TardisRelDBlConnection) -> TardisResult<Vec<String>> {
+    let items = find_usage_items(bucket, object_paths, conn).await?;
+    if items.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut query = Query::delete();
+    query.from_table(object_usage_item::Entity).and_where(object_usage_item::Column::Bucket.eq(bucket)).and_where(object_usage_item::Column::ObjectPath.is_in(object_paths));
+    conn.execute(&query).await?;
+    let mut deltas: HashMap<String, (i64, i64)> = HashMap::new();
+    for item in items {
+        let delta = deltas.entry(item.own_paths).or_default();
+        delta.0 -= item.size;
+        delta.1 -= 1;
+    }
+    for (own_paths, (bytes, count)) in &deltas {
+        add_usage(own_paths, *bytes, *count, &[], conn).await?;
+    }
+    Ok(deltas.into_keys().collect())
+}
+
+/// Make sure the usage of the own paths exists, so it is updated in the transactions instead of inserted
+async fn ensure_usage(own_paths: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if get_usage(own_paths, funs).await?.is_none() && insert_quota(own_paths, 0, 0, 0, funs, ctx).await.is_err() {
+        // inserted concurrently
+        get_usage(own_paths, funs).await?.ok_or_else(|| funs.err().not_found("object_quota", "ensure_usage", "usage not found", "404-spi-object-usage-not-exist"))?;
+    }
+    Ok(())
+}
+
+async fn get_usage(own_paths: &str, funs: &TardisFunsInst) -> TardisResult<Option<ObjectUsageResp>> {
+    let mut query = Query::select();
+    query.columns(object_quota_columns()).from(object_quota::Entity).and_where(object_quota::Column::OwnPaths.eq(own_paths));
+    Ok(funs.db().get_dto::<object_quota::Model>(&query).await?.map(to_usage_resp))
+}
+
+async fn find_subtree(own_paths: &str, funs: &TardisFunsInst) -> TardisResult<Vec<object_quota::Model>> {
+    let mut query = Query::select();
+    query.columns(object_quota_columns()).from(object_quota::Entity).order_by(object_quota::Column::OwnPaths, Order::Asc);
+    if !own_paths.is_empty() {
+        query.cond_where(Cond::any().add(object_quota::Column::OwnPaths.eq(own_paths)).add(object_quota::Column::OwnPaths.like(format!("{own_paths}/%"))));
+    }
+    // `_` in the own paths is a wildcard of `LIKE`
+    Ok(funs.db().find_dtos::<object_quota::Model>(&query).await?.into_iter().filter(|usage| is_within(&usage.own_paths, own_paths)).collect())
+}
+
+async fn insert_quota(own_paths: &str, quota_bytes: i64, used_bytes: i64, object_count: i64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    funs.db()
+        .insert_one(
+            object_quota::ActiveModel {
+                own_paths: Set(own_paths.to_string()),
+                quota_bytes: Set(quota_bytes),
+                used_bytes: Set(used_bytes),
+                object_count: Set(object_count),
+                update_time: Set(Utc::now()),
+            },
+            ctx,
+        )
+        .await?;
+    Ok(())
+}
+
+async fn find_usage_items(bucket: &str, object_paths: &[String], conn: &TardisRelDBlConnection) -> TardisResult<Vec<object_usage_item::Model>> {
+    let mut query = Query::select();
+    query
+        .columns([
+            object_usage_item::Column::Bucket,
+            object_usage_item::Column::ObjectPath,
+            object_usage_item::Column::OwnPaths,
+            object_usage_item::Column::Size,
+            object_usage_item::Column::UpdateTime,
+        ])
+        .from(object_usage_item::Entity)
+        .and_where(object_usage_item::Column::Bucket.eq(bucket))
+        .and_where(object_usage_item::Column::ObjectPath.is_in(object_paths));
+    conn.find_dtos::<object_usage_item::Model>(&query).await
+}
+
+fn new_usage_item(bucket: &str, object_path: &str, size: i64, ctx: &TardisContext) -> object_usage_item::ActiveModel {
+    object_usage_item::ActiveModel {
+        bucket: Set(bucket.to_string()),
+        object_path: Set(object_path.to_string()),
+        own_paths: Set(ctx.own_paths.clone()),
+        size: Set(size),
+        update_time: Set(Utc::now()),
+    }
+}
+
+/// Send the usage to spi-stats, failures are only logged as the usage is fed again on the next change
+async fn feed_stats(own_paths: &str, funs: &TardisFunsInst, ctx: &TardisContext) {
+    let fact_key = &funs.conf::<ObjectConfig>().usage_stats_fact_key;
+    if fact_key.is_empty() || own_paths.is_empty() {
+        return;
+    }
+    if let Err(e) = load_stats_record(fact_key, own_paths, funs, ctx).await {
+        warn!("[BIOS.Object] Feeding the usage of {own_paths} to stats failed: {e:?}");
+    }
+}
+
+async fn load_stats_record(fact_key: &str, own_paths: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let Some(usage) = get_usage(own_paths, funs).await? else {
+        return Ok(());
+    };
+    SpiStatsClient::fact_record_load(
+        fact_key,
+        // the segments of own paths are nanoids, which contain no `.`
+        &own_paths.replace('/', "."),
+        StatsFactRecordLoadReq {
+            own_paths: own_paths.to_string(),
+            ct: usage.update_time,
+            idempotent_id: None,
+            ignore_updates: Some(false),
+            data: json!({
+                "own_paths": usage.own_paths,
+                "quota_bytes": usage.quota_bytes,
+                "used_bytes": usage.used_bytes,
+                "object_count": usage.object_count,
+            }),
+            ext: None,
+        },
+        funs,
+        ctx,
+    )
+    .await
+}
+
+fn to_usage_resp(usage: object_quota::Model) -> ObjectUsageResp {
+    ObjectUsageResp {
+        own_paths: usage.own_paths,
+        quota_bytes: usage.quota_bytes,
+        used_bytes: usage.used_bytes,
+        object_count: usage.object_count,
+        update_time: usage.update_time,
+    }
+}
+
+fn object_quota_columns() -> [object_quota::Column; 5] {
+    [
+        object_quota::Column::OwnPaths,
+        object_quota::Column::QuotaBytes,
+        object_quota::Column::UsedBytes,
+        object_quota::Column::ObjectCount,
+        object_quota::Column::UpdateTime,
+    ]
+}
+
+fn is_within(own_paths: &str, parent: &str) -> bool {
+    parent.is_empty() || own_paths == parent || own_paths.strip_prefix(parent).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Escape the wildcards of `LIKE` with the default escape character `\`
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// The own paths and its ancestors, e.g. `t1/app001` -> [`t1`, `t1/app001`]
+fn ancestor_paths(own_paths: &str) -> Vec<String> {
+    own_paths.match_indices('/').map(|(idx, _)| own_paths[..idx].to_string()).chain(std::iter::once(own_paths.to_string())).filter(|path| !path.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ancestor_paths, escape_like, is_within};
+
+    #[test]
+    fn test_is_within() {
+        assert!(is_within("t1/app001", "t1"));
+        assert!(is_within("t1", "t1"));
+        assert!(is_within("t1", ""));
+        assert!(!is_within("t10", "t1"));
+        assert!(!is_within("t1", "t1/app001"));
+    }
+
+    #[test]
+    fn test_ancestor_paths() {
+        assert_eq!(ancestor_paths("t1/app001"), vec!["t1".to_string(), "t1/app001".to_string()]);
+        assert_eq!(ancestor_paths("t1"), vec!["t1".to_string()]);
+        assert!(ancestor_paths("").is_empty());
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("t1/app_01"), "t1/app\\_01");
+        assert_eq!(escape_like("t1/100%\\"), "t1/100\\%\\\\");
+    }
+}
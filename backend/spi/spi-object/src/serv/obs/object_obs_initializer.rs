@@ -9,6 +9,7 @@ use tardis::{
 };
 
 use crate::object_constants::USE_REGION_ENDPOINT;
+use crate::serv::s3;
 
 use tardis::serde_json::Value as JsonValue;
 
@@ -62,6 +63,7 @@ async fn init_obs_spi_bs(conn_uri: &str, ak: &str, sk: &str, region: &str, defau
     let tardis_os_config = OSModuleConfig::builder().kind("s3").endpoint(conn_uri).ak(ak).sk(sk).region(region).default_bucket(default_bucket).build();
     let client = TardisOSClient::init(&tardis_os_config)?;
     let mut ext = HashMap::new();
+    s3::set_conn_to_ext(conn_uri, ak, sk, region, default_bucket, &mut ext)?;
     if !private {
         let bucket_name_prefix = spi_initializer::common::get_isolation_flag_from_context(ctx);
         spi_initializer::common::set_isolation_flag_to_ext(&bucket_name_prefix, &mut ext);
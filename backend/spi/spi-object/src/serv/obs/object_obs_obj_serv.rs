@@ -5,7 +5,8 @@ use tardis::{
     TardisFunsInst,
 };
 
-use crate::serv::s3::S3;
+use crate::dto::object_dto::ObjectListResp;
+use crate::serv::s3::{self, S3};
 
 /// OBS need manually configure lifecycle rules
 /// Most interfaces of the obs service use bucket domains to share the code logic of s3.
@@ -41,4 +42,25 @@ impl S3 for OBSService {
             )
             .await
     }
+
+    /// Listing is only supported under the Regional endpoint like copy, the bucket is passed in as a directory prefix and stripped from the returned paths.
+    /// 与copy一致，列出对象只能在区域域名下调用，桶当作目录前缀传入，并从返回的路径中去除。
+    async fn object_list(
+        prefix: &str,
+        delimiter: Option<String>,
+        continuation_token: Option<String>,
+        page_size: Option<u16>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        _funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<ObjectListResp> {
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst).unwrap_or_default();
+        let dir = if bucket_name.is_empty() { "".to_string() } else { format!("{bucket_name}/") };
+        s3::list_objects(&s3::get_raw_bucket(None, inst)?, &dir, prefix, delimiter, continuation_token, page_size).await
+    }
 }
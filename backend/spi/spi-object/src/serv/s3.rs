@@ -2,18 +2,34 @@ pub mod object_s3_initializer;
 pub mod object_s3_obj_serv;
 
 use std::collections::HashMap;
+use std::sync::OnceLock;
 
-use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, serv::spi_bs_serv::SpiBsServ, spi_funs::SpiBsInst, spi_initializer::common};
+use ::s3::{bucket::Bucket, creds::Credentials, error::S3Error, region::Region};
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, serv::spi_bs_serv::SpiBsServ, spi_funs::SpiBsInst};
 use itertools::Itertools;
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
     futures::future::join_all,
     os::os_client::TardisOSClient,
-    web::poem::http::{HeaderMap, HeaderValue},
-    TardisFunsInst,
+    web::poem::http::{header::CONTENT_LENGTH, HeaderMap, HeaderValue},
+    TardisFuns, TardisFunsInst,
 };
 
-use crate::dto::object_dto::ObjectObjPresignKind;
+use crate::dto::object_dto::{ObjectHeadResp, ObjectListResp, ObjectObjPresignKind, ObjectSummaryResp};
+use crate::object_constants;
+use crate::serv::object_obj_serv::built_in_bucket_name;
+
+/// Keys of the connection kept in the ext of the instance, used to build the buckets of the operations not covered by `TardisOSClient`
+const EXT_ENDPOINT: &str = "s3_endpoint";
+const EXT_REGION: &str = "s3_region";
+const EXT_AK: &str = "s3_ak";
+const EXT_SK: &str = "s3_sk";
+const EXT_DEFAULT_BUCKET: &str = "s3_default_bucket";
+/// Max keys of a page allowed by s3
+const MAX_LIST_PAGE_SIZE: u16 = 1000;
+
+/// Key encrypting the ak and sk kept in the ext of the instances, the instances only live in this process so a random key is enough
+static EXT_CREDENTIAL_KEY: OnceLock<String> = OnceLock::new();
 
 pub trait S3 {
     ///
@@ -28,6 +44,7 @@ pub trait S3 {
         private: Option<bool>,
         special: Option<bool>,
         obj_exp: Option<u32>,
+        size: Option<u64>,
         bs_id: Option<&str>,
         bucket: Option<&str>,
         funs: &TardisFunsInst,
@@ -40,18 +57,19 @@ pub trait S3 {
         let path = Self::rebuild_path(bucket_name.as_deref(), object_path, obj_exp, client).await?;
         match presign_kind {
             ObjectObjPresignKind::Upload => {
-                let headers = obj_exp
-                    .map(|o| -> TardisResult<HeaderMap> {
-                        let mut headers = HeaderMap::new();
-                        headers.insert(
-                            "x-obs-expires",
-                            HeaderValue::from_str(&o.to_string())
-                                .map_err(|_| TardisError::internal_error("Cannot convert expires to header value", "500-spi-object-invalid-header-value"))?,
-                        );
-                        Ok(headers)
-                    })
-                    .transpose()?;
-                client.object_create_url(&path, exp_secs, bucket_name.as_deref(), headers, None).await
+                let mut headers = HeaderMap::new();
+                if let Some(o) = obj_exp {
+                    headers.insert(
+                        "x-obs-expires",
+                        HeaderValue::from_str(&o.to_string())
+                            .map_err(|_| TardisError::internal_error("Cannot convert expires to header value", "500-spi-object-invalid-header-value"))?,
+                    );
+                }
+                // the signed content length bounds the upload to the size checked against the quota
+                if let Some(size) = size {
+                    headers.insert(CONTENT_LENGTH, HeaderValue::from(size));
+                }
+                client.object_create_url(&path, exp_secs, bucket_name.as_deref(), (!headers.is_empty()).then_some(headers), None).await
             }
             ObjectObjPresignKind::Delete => client.object_delete_url(&path, exp_secs, bucket_name.as_deref()).await,
             ObjectObjPresignKind::View => {
@@ -188,6 +206,7 @@ pub trait S3 {
                         private,
                         special,
                         obj_exp,
+                        None,
                         bs_id,
                         bucket,
                         _funs,
@@ -278,24 +297,203 @@ pub trait S3 {
         client.complete_multipart_upload(&path, upload_id, parts, bucket_name.as_deref()).await
     }
 
-    fn get_bucket_name(private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, bucket_name: Option<&str>, bs_id: Option<&str>, inst: &SpiBsInst) -> Option<String> {
+    /// 按前缀分页列出对象，前缀与其他操作一样经过 `rebuild_path` 处理，返回的路径去除了其添加的部分
+    /// List the objects under the prefix by pages, the prefix is rebuilt by `rebuild_path` like the other operations and the part it adds is stripped from the returned paths
+    async fn object_list(
+        prefix: &str,
+        delimiter: Option<String>,
+        continuation_token: Option<String>,
+        page_size: Option<u16>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        _funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<ObjectListResp> {
         let bs_inst = inst.inst::<TardisOSClient>();
-        common::get_isolation_flag_from_ext(bs_inst.1).map(|bucket_name_prefix| {
-            format!(
-                "{}-{}",
-                bucket_name_prefix,
-                if special.unwrap_or(false) {
-                    "spe"
-                } else if obj_exp.is_some() {
-                    "tamp"
-                } else if private.unwrap_or(true) {
-                    "pri"
-                } else {
-                    "pub"
-                }
-            )
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        let path = Self::rebuild_path(bucket_name.as_deref(), prefix, obj_exp, client).await?;
+        let dir = path.strip_suffix(prefix).unwrap_or_default();
+        let raw_bucket = get_raw_bucket(bucket_name.as_deref(), inst)?;
+        list_objects(&raw_bucket, dir, prefix, delimiter, continuation_token, page_size).await
+    }
+
+    async fn object_head(
+        object_path: &str,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        _funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<ObjectHeadResp> {
+        let bs_inst = inst.inst::<TardisOSClient>();
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        let path = Self::rebuild_path(bucket_name.as_deref(), object_path, obj_exp, client).await?;
+        let (head, _) = get_raw_bucket(bucket_name.as_deref(), inst)?.head_object(&path).await.map_err(|e| s3_error(object_path, e))?;
+        Ok(ObjectHeadResp {
+            object_path: object_path.to_string(),
+            size: head.content_length,
+            content_type: head.content_type,
+            etag: head.e_tag,
+            last_modified: head.last_modified,
+            metadata: head.metadata.unwrap_or_default(),
         })
     }
 
+    /// 替换对象的全部标签，标签为空时删除对象的标签
+    /// Replace all tags of the object, the tags of the object are deleted when the tags are empty
+    async fn object_tags_put(
+        object_path: &str,
+        tags: HashMap<String, String>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        _funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<()> {
+        let bs_inst = inst.inst::<TardisOSClient>();
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        let path = Self::rebuild_path(bucket_name.as_deref(), object_path, obj_exp, client).await?;
+        let raw_bucket = get_raw_bucket(bucket_name.as_deref(), inst)?;
+        if tags.is_empty() {
+            raw_bucket.delete_object_tagging(&path).await.map_err(|e| s3_error(object_path, e))?;
+        } else {
+            let tags = tags.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect_vec();
+            raw_bucket.put_object_tagging(&path, &tags).await.map_err(|e| s3_error(object_path, e))?;
+        }
+        Ok(())
+    }
+
+    async fn object_tags_get(
+        object_path: &str,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        _funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<HashMap<String, String>> {
+        let bs_inst = inst.inst::<TardisOSClient>();
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        let path = Self::rebuild_path(bucket_name.as_deref(), object_path, obj_exp, client).await?;
+        let (tags, _) = get_raw_bucket(bucket_name.as_deref(), inst)?.get_object_tagging(&path).await.map_err(|e| s3_error(object_path, e))?;
+        Ok(tags.into_iter().map(|tag| (tag.key(), tag.value())).collect())
+    }
+
+    fn get_bucket_name(private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, _bucket_name: Option<&str>, _bs_id: Option<&str>, inst: &SpiBsInst) -> Option<String> {
+        built_in_bucket_name(private, special, obj_exp, inst)
+    }
+
     async fn rebuild_path(bucket_name: Option<&str>, origin_path: &str, obj_exp: Option<u32>, client: &TardisOSClient) -> TardisResult<String>;
 }
+
+/// Keep the connection in the ext of the instance, the ak and sk are encrypted, see [`get_raw_bucket`]
+pub(crate) fn set_conn_to_ext(endpoint: &str, ak: &str, sk: &str, region: &str, default_bucket: &str, ext: &mut HashMap<String, String>) -> TardisResult<()> {
+    ext.insert(EXT_ENDPOINT.to_string(), endpoint.to_string());
+    ext.insert(EXT_REGION.to_string(), region.to_string());
+    ext.insert(EXT_AK.to_string(), encrypt_credential(ak)?);
+    ext.insert(EXT_SK.to_string(), encrypt_credential(sk)?);
+    ext.insert(EXT_DEFAULT_BUCKET.to_string(), default_bucket.to_string());
+    Ok(())
+}
+
+/// Encrypt the credential, returns `iv:encrypted`
+fn encrypt_credential(credential: &str) -> TardisResult<String> {
+    let iv = TardisFuns::crypto.key.rand_16_hex();
+    Ok(format!("{iv}:{}", TardisFuns::crypto.aes.encrypt_cbc(credential, ext_credential_key(), &iv)?))
+}
+
+fn decrypt_credential(encrypted: &str) -> TardisResult<String> {
+    let (iv, encrypted) =
+        encrypted.split_once(':').ok_or_else(|| TardisError::internal_error("Cannot decrypt the credentials of the bucket", "500-spi-object-s3-invalid-credentials"))?;
+    TardisFuns::crypto.aes.decrypt_cbc(encrypted, ext_credential_key(), iv)
+}
+
+fn ext_credential_key() -> &'static str {
+    EXT_CREDENTIAL_KEY.get_or_init(|| TardisFuns::crypto.key.rand_16_hex())
+}
+
+/// Build the bucket of the s3 client for the operations not covered by `TardisOSClient`.
+/// Like `TardisOSClient`, path style is used and the default bucket is used when the bucket name is empty.
+/// OBS only supports virtual-host style, so the bucket of OBS is addressed by its domain.
+pub(crate) fn get_raw_bucket(bucket_name: Option<&str>, inst: &SpiBsInst) -> TardisResult<Box<Bucket>> {
+    let bucket_name = bucket_name.filter(|bucket_name| !bucket_name.is_empty()).unwrap_or(ext_value(inst, EXT_DEFAULT_BUCKET));
+    if bucket_name.is_empty() {
+        return Err(TardisError::internal_error(
+            "Cannot get bucket name, it may due to the lack of isolation_flag and default bucket",
+            "500-spi-object-s3-cannot-get-bucket-name",
+        ));
+    }
+    let ak = decrypt_credential(ext_value(inst, EXT_AK))?;
+    let sk = decrypt_credential(ext_value(inst, EXT_SK))?;
+    let credentials = Credentials::new(Some(ak.as_str()), Some(sk.as_str()), None, None, None).map_err(|e| {
+        TardisError::internal_error(
+            &format!("Cannot build the credentials of bucket {bucket_name}: {e}"),
+            "500-spi-object-s3-invalid-credentials",
+        )
+    })?;
+    let region = Region::Custom {
+        region: ext_value(inst, EXT_REGION).to_string(),
+        endpoint: ext_value(inst, EXT_ENDPOINT).to_string(),
+    };
+    let raw_bucket = Bucket::new(bucket_name, region, credentials).map_err(|e| s3_error(bucket_name, e))?;
+    if inst.kind_code() == object_constants::SPI_OBS_KIND_CODE {
+        Ok(raw_bucket)
+    } else {
+        Ok(raw_bucket.with_path_style())
+    }
+}
+
+/// List a page of objects under `dir` + `prefix`, `dir` is stripped from the returned paths
+pub(crate) async fn list_objects(
+    raw_bucket: &Bucket,
+    dir: &str,
+    prefix: &str,
+    delimiter: Option<String>,
+    continuation_token: Option<String>,
+    page_size: Option<u16>,
+) -> TardisResult<ObjectListResp> {
+    let page_size = page_size.unwrap_or(MAX_LIST_PAGE_SIZE).clamp(1, MAX_LIST_PAGE_SIZE);
+    let (result, _) = raw_bucket.list_page(format!("{dir}{prefix}"), delimiter, continuation_token, None, Some(page_size as usize)).await.map_err(|e| s3_error(prefix, e))?;
+    let strip_dir = |path: String| path.strip_prefix(dir).map(str::to_string).unwrap_or(path);
+    Ok(ObjectListResp {
+        objects: result
+            .contents
+            .into_iter()
+            .map(|object| ObjectSummaryResp {
+                object_path: strip_dir(object.key),
+                size: object.size,
+                etag: object.e_tag,
+                last_modified: object.last_modified,
+            })
+            .collect(),
+        common_prefixes: result.common_prefixes.unwrap_or_default().into_iter().map(|common_prefix| strip_dir(common_prefix.prefix)).collect(),
+        next_continuation_token: if result.is_truncated { result.next_continuation_token } else { None },
+    })
+}
+
+fn ext_value<'a>(inst: &'a SpiBsInst, key: &str) -> &'a str {
+    inst.ext.get(key).map(String::as_str).unwrap_or_default()
+}
+
+fn s3_error(target: &str, e: S3Error) -> TardisError {
+    match e {
+        S3Error::HttpFailWithBody(404, _) => TardisError::not_found(&format!("Object {target} not found"), "404-spi-object-not-exist"),
+        e => TardisError::internal_error(&format!("Request of {target} failed: {e}"), "500-spi-object-s3-request-failed"),
+    }
+}
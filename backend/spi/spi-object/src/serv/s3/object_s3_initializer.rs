@@ -10,6 +10,8 @@ use tardis::{
 
 use tardis::serde_json::Value as JsonValue;
 
+use crate::serv::s3;
+
 /// s3 引擎初始化
 /// 使用 Regional endpoint 建立连接，默认桶允许置空。
 /// s3 engine initialization
@@ -30,6 +32,7 @@ pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, _: bool) -> Tard
     let tardis_os_config = OSModuleConfig::builder().kind("s3").endpoint(&bs_cert.conn_uri).ak(&bs_cert.ak).sk(&bs_cert.sk).region(region).default_bucket(default_bucket).build();
     let client = TardisOSClient::init(&tardis_os_config)?;
     let mut ext = HashMap::new();
+    s3::set_conn_to_ext(&bs_cert.conn_uri, &bs_cert.ak, &bs_cert.sk, region, default_bucket, &mut ext)?;
     if !bs_cert.private {
         let bucket_name_prefix = spi_initializer::common::get_isolation_flag_from_context(ctx);
         let resp = client.bucket_create_simple(&format!("{bucket_name_prefix}-pri"), true).await;
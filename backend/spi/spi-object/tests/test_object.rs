@@ -14,6 +14,7 @@ use tardis::test::test_container::TardisTestContainer;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::Void;
 use tardis::{testcontainers, tokio, TardisFuns};
+mod test_object_fs;
 mod test_object_obj;
 
 #[tokio::test]
@@ -75,5 +76,26 @@ async fn init_data(minio_url: &str) -> TardisResult<()> {
 
     test_object_obj::test(&mut client).await?;
 
+    client.set_auth(&ctx)?;
+    let fs_kind_id = RbumKindServ::get_rbum_kind_id_by_code(object_constants::SPI_FS_KIND_CODE, &funs).await?.unwrap();
+    let fs_bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-fs".to_string()),
+                kind_id: TrimString(fs_kind_id),
+                conn_uri: format!("file://{}", env::temp_dir().join("bios-spi-object-fs").display()),
+                ak: TrimString("fs".to_string()),
+                sk: TrimString("fs".to_string()),
+                ext: "{}".to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app002", fs_bs_id), &Void {}).await;
+
+    test_object_fs::test(&mut client).await?;
+
     Ok(())
 }
@@ -0,0 +1,181 @@
+use bios_basic::test::test_http_client::TestHttpClient;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::config::config_dto::WebClientModuleConfig;
+use tardis::serde_json::{json, Value};
+use tardis::web::web_client::TardisWebClient;
+use tardis::web::web_resp::{TardisResp, Void};
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    let app_ctx = TardisContext {
+        own_paths: "t1/app002".to_string(),
+        ak: "app002".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "".to_string(),
+        ..Default::default()
+    };
+    let tenant_ctx = TardisContext {
+        own_paths: "t1".to_string(),
+        ..app_ctx.clone()
+    };
+    client.set_auth(&app_ctx)?;
+    let web_client = TardisWebClient::init(&WebClientModuleConfig::default())?;
+
+    // upload and view by signed urls
+    let upload_url: String = client.get("/ci/obj/presign/put?object_path=a/001.txt&exp_secs=300&private=true").await;
+    let resp = web_client.put_str_to_str(&upload_url, "hello", None).await?;
+    assert_eq!(resp.code, 200);
+    assert!(client.get::<bool>("/ci/obj/object/exist?object_path=a/001.txt&private=true").await);
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=a/001.txt&private=false").await);
+    let view_url: String = client.get("/ci/obj/presign/view?object_path=a/001.txt&exp_secs=300&private=true").await;
+    let resp = web_client.get_to_str(&view_url, None).await?;
+    assert_eq!(resp.code, 200);
+    assert_eq!(resp.body, Some("hello".to_string()));
+    // the signature covers the path and the method
+    let resp = web_client.get_to_str(&view_url.replace("001.txt", "002.txt"), None).await?;
+    assert_eq!(resp.code, 401);
+    let resp = web_client.get_to_str(&upload_url, None).await?;
+    assert_eq!(resp.code, 401);
+    let resp: TardisResp<String> = client.get_resp("/ci/obj/presign/view?object_path=../001.txt&exp_secs=300").await;
+    assert_eq!(resp.code, "400-spi-object-fs-invalid-path");
+
+    // copy
+    let _: Void = client.post("/ci/obj/object/copy", &json!({"from": "a/001.txt", "to": "b/001.txt", "private": true})).await;
+    let view_url: String = client.get("/ci/obj/presign/view?object_path=b/001.txt&exp_secs=300&private=true").await;
+    assert_eq!(web_client.get_to_str(&view_url, None).await?.body, Some("hello".to_string()));
+
+    // multipart upload
+    let upload_id: String = client.post("/ci/obj/multi_upload/initiate_multipart_upload", &json!({"object_path": "c/001.txt", "private": true})).await;
+    let part_urls: Vec<String> = client
+        .post(
+            "/ci/obj/multi_upload/batch_build_create_presign_url",
+            &json!({"object_path": "c/001.txt", "upload_id": upload_id, "part_number": 2, "expire_sec": 300, "private": true}),
+        )
+        .await;
+    assert_eq!(part_urls.len(), 2);
+    assert!(!part_urls[0].contains("root="));
+    let mut part_etags = vec![];
+    for (part_url, content) in part_urls.iter().zip(["hello ", "world"]) {
+        let resp = web_client.put_str_to_str(part_url, content, None).await?;
+        assert_eq!(resp.code, 200);
+        part_etags.push(resp.headers.get("etag").cloned().unwrap_or_default());
+    }
+    // the etag is the md5 of the content
+    assert_eq!(part_etags[0], "\"f814893777bcc2295fff05f00e508da6\"");
+    let resp: TardisResp<Void> = client
+        .post_resp(
+            "/ci/obj/multi_upload/complete_multipart_upload",
+            &json!({"object_path": "d/001.txt", "upload_id": upload_id, "parts": part_etags, "private": true}),
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-object-fs-upload-mismatch");
+    let resp: TardisResp<Void> = client
+        .post_resp(
+            "/ci/obj/multi_upload/complete_multipart_upload",
+            &json!({"object_path": "c/001.txt", "upload_id": upload_id, "parts": [part_etags[1], part_etags[0]], "private": true}),
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-object-fs-invalid-part");
+    let _: Void = client
+        .post(
+            "/ci/obj/multi_upload/complete_multipart_upload",
+            &json!({"object_path": "c/001.txt", "upload_id": upload_id, "parts": part_etags, "private": true}),
+        )
+        .await;
+    let view_url: String = client.get("/ci/obj/presign/view?object_path=c/001.txt&exp_secs=300&private=true").await;
+    assert_eq!(web_client.get_to_str(&view_url, None).await?.body, Some("hello world".to_string()));
+
+    // list and head
+    let page: Value = client.get("/ci/obj/object/list?delimiter=/&private=true").await;
+    assert_eq!(page["objects"], json!([]));
+    assert_eq!(page["common_prefixes"], json!(["a/", "b/", "c/"]));
+    let page: Value = client.get("/ci/obj/object/list?prefix=c/&page_size=1&private=true").await;
+    assert_eq!(page["objects"][0]["object_path"], "c/001.txt");
+    assert_eq!(page["objects"][0]["size"], 11);
+    assert!(page["next_continuation_token"].is_null());
+    let head: Value = client.get("/ci/obj/object/head?object_path=c/001.txt&private=true").await;
+    assert_eq!(head["size"], 11);
+    let resp: TardisResp<Value> = client.get_resp("/ci/obj/object/head?object_path=c/002.txt&private=true").await;
+    assert_eq!(resp.code, "404-spi-object-fs-not-found");
+
+    // delete
+    client.delete("/ci/obj/object?object_path=a/001.txt&private=true").await;
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=a/001.txt&private=true").await);
+    let delete_url: String = client.get("/ci/obj/presign/delete?object_path=b/001.txt&exp_secs=300&private=true").await;
+    assert_eq!(web_client.delete_to_void(&delete_url, None).await?.code, 200);
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=b/001.txt&private=true").await);
+
+    // quota and usage
+    let usage: Value = client.put("/ci/obj/quota/usage/refresh", &Void {}).await;
+    assert_eq!(usage["own_paths"], "t1/app002");
+    assert_eq!(usage["used_bytes"], 11);
+    assert_eq!(usage["object_count"], 1);
+    // quotas are set by the sys or tenant contexts
+    let resp: TardisResp<Void> = client.put_resp("/ci/obj/quota", &json!({"own_paths": "t1/app002", "quota_bytes": 20})).await;
+    assert_eq!(resp.code, "403-spi-object-quota-not-admin");
+    client.set_auth(&tenant_ctx)?;
+    let resp: TardisResp<Void> = client.put_resp("/ci/obj/quota", &json!({"own_paths": "t1", "quota_bytes": 20})).await;
+    assert_eq!(resp.code, "403-spi-object-quota-out-of-own-paths");
+    let resp: TardisResp<Void> = client.put_resp("/ci/obj/quota", &json!({"own_paths": "t2/app001", "quota_bytes": 20})).await;
+    assert_eq!(resp.code, "403-spi-object-quota-out-of-own-paths");
+    let _: Void = client.put("/ci/obj/quota", &json!({"own_paths": "t1/app002", "quota_bytes": 20})).await;
+    client.set_auth(&app_ctx)?;
+    // the size is required once a quota applies and signed into the url, the upload is accounted with its real size once confirmed
+    let resp: TardisResp<String> = client.get_resp("/ci/obj/presign/put?object_path=e/001.txt&exp_secs=300&private=true").await;
+    assert_eq!(resp.code, "400-spi-object-quota-size-required");
+    let upload_url: String = client.get("/ci/obj/presign/put?object_path=e/001.txt&exp_secs=300&private=true&size=9").await;
+    assert_eq!(web_client.put_str_to_str(&upload_url, "1234567890", None).await?.code, 413);
+    assert_eq!(web_client.put_str_to_str(&upload_url, "123456789", None).await?.code, 200);
+    let usages: Value = client.get("/ci/obj/quota/usage").await;
+    assert_eq!(usages[0]["used_bytes"], 11);
+    let _: Void = client.put("/ci/obj/presign/put/confirm?object_path=e/001.txt&private=true", &Void {}).await;
+    let usages: Value = client.get("/ci/obj/quota/usage").await;
+    assert_eq!(usages[0]["quota_bytes"], 20);
+    assert_eq!(usages[0]["used_bytes"], 20);
+    assert_eq!(usages[0]["object_count"], 2);
+    let resp: TardisResp<String> = client.get_resp("/ci/obj/presign/put?object_path=f/001.txt&exp_secs=300&private=true&size=1").await;
+    assert_eq!(resp.code, "403-spi-object-quota-exceeded");
+    client.delete("/ci/obj/object?object_path=e/001.txt&private=true").await;
+    let resp: TardisResp<String> = client
+        .post_resp(
+            "/ci/obj/multi_upload/initiate_multipart_upload",
+            &json!({"object_path": "f/001.txt", "size": 10, "private": true}),
+        )
+        .await;
+    assert_eq!(resp.code, "403-spi-object-quota-exceeded");
+    // copy and delete are accounted
+    let upload_url: String = client.get("/ci/obj/presign/put?object_path=h/001.txt&exp_secs=300&private=true&size=9").await;
+    assert_eq!(web_client.put_str_to_str(&upload_url, "123456789", None).await?.code, 200);
+    let _: Void = client.post("/ci/obj/object/copy", &json!({"from": "c/001.txt", "to": "d/001.txt", "private": true})).await;
+    let usages: Value = client.get("/ci/obj/quota/usage").await;
+    assert_eq!(usages[0]["used_bytes"], 22);
+    assert_eq!(usages[0]["object_count"], 2);
+    // the upload exceeding the quota is removed once confirmed
+    let resp: TardisResp<Void> = client.put_resp("/ci/obj/presign/put/confirm?object_path=h/001.txt&private=true", &Void {}).await;
+    assert_eq!(resp.code, "403-spi-object-quota-exceeded");
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=h/001.txt&private=true").await);
+    let resp: TardisResp<String> = client.get_resp("/ci/obj/presign/put?object_path=f/001.txt&exp_secs=300&private=true&size=0").await;
+    assert_eq!(resp.code, "403-spi-object-quota-exceeded");
+    client.delete("/ci/obj/object?object_path=d/001.txt&private=true").await;
+    let usages: Value = client.get("/ci/obj/quota/usage").await;
+    assert_eq!(usages[0]["used_bytes"], 11);
+    assert_eq!(usages[0]["object_count"], 1);
+    client.set_auth(&tenant_ctx)?;
+    let _: Void = client.put("/ci/obj/quota", &json!({"own_paths": "t1/app002", "quota_bytes": 0})).await;
+    client.set_auth(&app_ctx)?;
+
+    // the size of the uploads is limited
+    let upload_url: String = client.get("/ci/obj/presign/put?object_path=g/001.txt&exp_secs=300&private=true").await;
+    assert_eq!(web_client.put_str_to_str(&upload_url, "12345678901234567", None).await?.code, 413);
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=g/001.txt&private=true").await);
+
+    // objects of the tamp bucket are kept under the directory of their expiration days
+    let upload_url: String = client.get("/ci/obj/presign/put?object_path=g/001.txt&exp_secs=300&obj_exp=1").await;
+    assert_eq!(web_client.put_str_to_str(&upload_url, "hello", None).await?.code, 200);
+    assert!(client.get::<bool>("/ci/obj/object/exist?object_path=g/001.txt&obj_exp=1").await);
+    let page: Value = client.get("/ci/obj/object/list?obj_exp=1").await;
+    assert_eq!(page["objects"][0]["object_path"], "1/g/001.txt");
+
+    Ok(())
+}
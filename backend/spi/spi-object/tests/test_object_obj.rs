@@ -1,6 +1,13 @@
+use std::collections::HashMap;
+
+use bios_basic::helper::request_helper::encode_query_value;
 use bios_basic::test::test_http_client::TestHttpClient;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::config::config_dto::WebClientModuleConfig;
+use tardis::serde_json::{json, Value};
+use tardis::web::web_client::TardisWebClient;
+use tardis::web::web_resp::{TardisResp, Void};
 
 pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     client.set_auth(&TardisContext {
@@ -30,6 +37,57 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     let tamp_delete_url: String = client.get(&format!("/ci/obj/presign/delete?object_path={}&exp_secs=300&private=true&obj_exp=1", "tamp/001.txt")).await;
     println!("tamp_delete_url: {}\r\nE.g.\r\ncurl -X DELETE \"{}\"", tamp_delete_url, tamp_delete_url);
 
+    // list, head and tags
+    let web_client = TardisWebClient::init(&WebClientModuleConfig::default())?;
+    for object_path in ["list/001.txt", "list/002.txt", "list/sub/003.txt"] {
+        let upload_url: String = client.get(&format!("/ci/obj/presign/put?object_path={object_path}&exp_secs=300&private=true")).await;
+        assert_eq!(web_client.put_str_to_str(&upload_url, "hello", None).await?.code, 200);
+    }
+    let page: Value = client.get("/ci/obj/object/list?prefix=list/&delimiter=/&page_size=2&private=true").await;
+    assert_eq!(page["objects"].as_array().map(|objects| objects.len()), Some(2));
+    assert_eq!(page["objects"][0]["object_path"], "list/001.txt");
+    assert_eq!(page["objects"][0]["size"], 5);
+    assert_eq!(page["objects"][1]["object_path"], "list/002.txt");
+    let next_continuation_token = page["next_continuation_token"].as_str().expect("next page expected");
+    let page: Value = client
+        .get(&format!(
+            "/ci/obj/object/list?prefix=list/&delimiter=/&page_size=2&private=true&continuation_token={}",
+            encode_query_value(next_continuation_token)
+        ))
+        .await;
+    assert_eq!(page["objects"], json!([]));
+    assert_eq!(page["common_prefixes"], json!(["list/sub/"]));
+    assert!(page["next_continuation_token"].is_null());
+    let page: Value = client.get("/ci/obj/object/list?prefix=list/&private=true").await;
+    assert_eq!(page["objects"].as_array().map(|objects| objects.len()), Some(3));
+    let page: Value = client.get("/ci/obj/object/list?prefix=list/&private=false").await;
+    assert_eq!(page["objects"], json!([]));
+
+    let head: Value = client.get("/ci/obj/object/head?object_path=list/001.txt&private=true").await;
+    assert_eq!(head["size"], 5);
+    assert!(head["etag"].is_string());
+    let resp: TardisResp<Value> = client.get_resp("/ci/obj/object/head?object_path=list/004.txt&private=true").await;
+    assert_eq!(resp.code, "404-spi-object-not-exist");
+
+    let _: Void = client
+        .put(
+            "/ci/obj/object/tags",
+            &json!({"object_path": "list/001.txt", "tags": {"kind": "doc", "owner": "app001"}, "private": true}),
+        )
+        .await;
+    let tags: HashMap<String, String> = client.get("/ci/obj/object/tags?object_path=list/001.txt&private=true").await;
+    assert_eq!(tags, HashMap::from([("kind".to_string(), "doc".to_string()), ("owner".to_string(), "app001".to_string())]));
+    let _: Void = client.put("/ci/obj/object/tags", &json!({"object_path": "list/001.txt", "tags": {}, "private": true})).await;
+    let tags: HashMap<String, String> = client.get("/ci/obj/object/tags?object_path=list/001.txt&private=true").await;
+    assert!(tags.is_empty());
+    let resp: TardisResp<Void> = client
+        .put_resp(
+            "/ci/obj/object/tags",
+            &json!({"object_path": "list/001.txt", "tags": (0..11).map(|i| (i.to_string(), i.to_string())).collect::<HashMap<_, _>>(), "private": true}),
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-object-too-many-tags");
+
     // tardis::tokio::time::sleep(std::time::Duration::from_secs(300)).await;
     Ok(())
 }
@@ -2,11 +2,11 @@ use tardis::serde_json::Value;
 use tardis::web::context_extractor::TardisContextExtractor;
 
 use tardis::web::poem_openapi;
-use tardis::web::poem_openapi::param::Query;
+use tardis::web::poem_openapi::param::{Header, Query};
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
-use crate::dto::reldb_exec_dto::{ReldbDdlReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlReq, ReldbTxResp};
+use crate::dto::reldb_exec_dto::{ReldbDdlReq, ReldbDeleteReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlReq, ReldbTxResp, ReldbUpsertReq};
 use crate::serv::reldb_exec_serv;
 
 #[derive(Clone)]
@@ -25,15 +25,27 @@ impl ReldbCiExecApi {
 
     /// Commit Transaction
     #[oai(path = "/tx", method = "put")]
-    async fn tx_commit(&self, tx_id: Query<String>) -> TardisApiResult<Void> {
-        reldb_exec_serv::tx_commit(tx_id.0).await?;
+    async fn tx_commit(
+        &self,
+        tx_id: Query<String>,
+        #[oai(name = "Bios-Reldb-Tx-Forwarded")] forwarded: Header<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        reldb_exec_serv::tx_commit(tx_id.0, forwarded.0.is_some(), &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
 
     /// Rollback Transaction
     #[oai(path = "/tx", method = "delete")]
-    async fn tx_rollback(&self, tx_id: Query<String>) -> TardisApiResult<Void> {
-        reldb_exec_serv::tx_rollback(tx_id.0).await?;
+    async fn tx_rollback(
+        &self,
+        tx_id: Query<String>,
+        #[oai(name = "Bios-Reldb-Tx-Forwarded")] forwarded: Header<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        reldb_exec_serv::tx_rollback(tx_id.0, forwarded.0.is_some(), &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
 
@@ -47,17 +59,57 @@ impl ReldbCiExecApi {
 
     /// DML
     #[oai(path = "/dml", method = "post")]
-    async fn dml(&self, mut dml_req: Json<ReldbDmlReq>, tx_id: Query<Option<String>>, ctx: TardisContextExtractor) -> TardisApiResult<ReldbDmlResp> {
+    async fn dml(
+        &self,
+        mut dml_req: Json<ReldbDmlReq>,
+        tx_id: Query<Option<String>>,
+        #[oai(name = "Bios-Reldb-Tx-Forwarded")] forwarded: Header<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ReldbDmlResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = reldb_exec_serv::dml(&mut dml_req.0, tx_id.0, forwarded.0.is_some(), &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Upsert records by primary key
+    #[oai(path = "/upsert", method = "post")]
+    async fn upsert(
+        &self,
+        mut upsert_req: Json<ReldbUpsertReq>,
+        tx_id: Query<Option<String>>,
+        #[oai(name = "Bios-Reldb-Tx-Forwarded")] forwarded: Header<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ReldbDmlResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = reldb_exec_serv::upsert(&mut upsert_req.0, tx_id.0, forwarded.0.is_some(), &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Delete records by primary key
+    #[oai(path = "/delete", method = "post")]
+    async fn delete(
+        &self,
+        mut delete_req: Json<ReldbDeleteReq>,
+        tx_id: Query<Option<String>>,
+        #[oai(name = "Bios-Reldb-Tx-Forwarded")] forwarded: Header<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ReldbDmlResp> {
         let funs = crate::get_tardis_inst();
-        let resp = reldb_exec_serv::dml(&mut dml_req.0, tx_id.0, &funs, &ctx.0).await?;
+        let resp = reldb_exec_serv::delete(&mut delete_req.0, tx_id.0, forwarded.0.is_some(), &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
 
     /// DQL
     #[oai(path = "/dql", method = "put")]
-    async fn dql(&self, mut dql_req: Json<ReldbDqlReq>, tx_id: Query<Option<String>>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<Value>> {
+    async fn dql(
+        &self,
+        mut dql_req: Json<ReldbDqlReq>,
+        tx_id: Query<Option<String>>,
+        #[oai(name = "Bios-Reldb-Tx-Forwarded")] forwarded: Header<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Vec<Value>> {
         let funs = crate::get_tardis_inst();
-        let resp = reldb_exec_serv::dql(&mut dql_req.0, tx_id.0, &funs, &ctx.0).await?;
+        let resp = reldb_exec_serv::dql(&mut dql_req.0, tx_id.0, forwarded.0.is_some(), &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
 }
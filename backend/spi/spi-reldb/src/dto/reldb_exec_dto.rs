@@ -33,19 +33,23 @@ pub struct ReldbDqlReq {
     pub params: Value,
 }
 
-// #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
-// pub struct ReldbUpsertReq {
-//     #[oai(validator(min_length = "2"))]
-//     pub table_name: String,
-//     #[oai(validator(min_length = "2"))]
-//     pub pk_name: String,
-//     #[oai(validator(min_length = "2"))]
-//     pub records: JsonArray,
-// }
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ReldbUpsertReq {
+    #[oai(validator(min_length = "2"))]
+    pub table_name: String,
+    #[oai(validator(min_length = "2"))]
+    pub pk_name: String,
+    /// Records to insert or update, all records must be objects with the same fields, including the primary key
+    #[oai(validator(min_items = "1"))]
+    pub records: Vec<Value>,
+}
 
-// #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
-// pub struct ReldbDeleteReq {
-//     #[oai(validator(min_length = "2"))]
-//     pub table_name: String,
-//     pub pk_ids: Vec<String>,
-// }
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ReldbDeleteReq {
+    #[oai(validator(min_length = "2"))]
+    pub table_name: String,
+    #[oai(validator(min_length = "2"))]
+    pub pk_name: String,
+    #[oai(validator(min_items = "1"))]
+    pub pk_ids: Vec<Value>,
+}
@@ -7,6 +7,26 @@ use std::fmt::Debug;
 pub struct ReldbConfig {
     pub rbum: RbumConfig,
     pub tx_clean_interval_sec: u8,
+    /// Base url of this node as reachable by the other nodes of the cluster, e.g. `https://10.0.0.1:8080/spi-reldb`.
+    /// When set, transaction ids carry the owning node and requests that land on another node are forwarded to it.
+    /// Leave it empty for single node deployments.
+    pub tx_node_url: String,
+    /// Secret shared by all nodes, used to sign the owning node into transaction ids so that only trusted nodes are forwarded to.
+    /// Required when `tx_node_url` is set, the module refuses to start without it.
+    pub tx_forward_secret: String,
+    /// Leading keywords of the statements accepted by the DDL API, empty means no restriction.
+    pub ddl_allowed_statements: Vec<String>,
+    /// Leading keywords of the statements accepted by the DML API (and the generated upsert/delete statements), empty means no restriction.
+    pub dml_allowed_statements: Vec<String>,
+    /// Leading keywords of the statements accepted by the DQL API, empty means no restriction.
+    pub dql_allowed_statements: Vec<String>,
+    /// Max rows a DQL may return, 0 (the default) means unlimited.
+    /// Queries (`SELECT`, `WITH`, `VALUES` and `TABLE`) are limited in the database, other statements are refused once they return more rows.
+    pub dql_max_rows: u32,
+    /// Statement timeout enforced by the database, 0 means no timeout.
+    /// PostgreSQL applies it to all statements. MySQL applies it to queries by the `MAX_EXECUTION_TIME` hint,
+    /// and DML statements running longer are killed by `KILL QUERY`.
+    pub statement_timeout_ms: u32,
 }
 
 impl Default for ReldbConfig {
@@ -14,6 +34,13 @@ impl Default for ReldbConfig {
         ReldbConfig {
             rbum: Default::default(),
             tx_clean_interval_sec: 5,
+            tx_node_url: "".to_string(),
+            tx_forward_secret: "".to_string(),
+            ddl_allowed_statements: ["create", "alter", "drop", "truncate", "comment"].iter().map(|s| s.to_string()).collect(),
+            dml_allowed_statements: ["insert", "update", "delete"].iter().map(|s| s.to_string()).collect(),
+            dql_allowed_statements: ["select", "with"].iter().map(|s| s.to_string()).collect(),
+            dql_max_rows: 0,
+            statement_timeout_ms: 30000,
         }
     }
 }
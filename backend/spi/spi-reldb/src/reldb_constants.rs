@@ -1,2 +1,4 @@
 pub const DOMAIN_CODE: &str = "spi-reldb";
 pub const SPI_MYSQL_KIND_CODE: &str = "spi-bs-mysql";
+/// Header marking the requests forwarded to the node owning the transaction, they are never forwarded again
+pub const TX_FORWARDED_HEADER: &str = "Bios-Reldb-Tx-Forwarded";
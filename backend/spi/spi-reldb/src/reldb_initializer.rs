@@ -26,6 +26,13 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     info!("[BIOS.Reldb] Module initializing");
     let mut funs = crate::get_tardis_inst();
     let clean_interval_sec = funs.conf::<ReldbConfig>().tx_clean_interval_sec;
+    // without the secret the owning nodes cannot be verified, and transactions would silently stay on the nodes they land on
+    if !funs.conf::<ReldbConfig>().tx_node_url.is_empty() && funs.conf::<ReldbConfig>().tx_forward_secret.is_empty() {
+        return Err(TardisError::internal_error(
+            "tx_forward_secret is required when tx_node_url is set",
+            "500-spi-reldb-tx-forward-secret-required",
+        ));
+    }
     bios_basic::rbum::rbum_initializer::init(funs.module_code(), funs.conf::<ReldbConfig>().rbum.clone()).await?;
     funs.begin().await?;
     let ctx = spi_initializer::init(DOMAIN_CODE, &funs).await?;
@@ -1,16 +1,24 @@
-use crate::dto::reldb_exec_dto::{ReldbDdlReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlReq, ReldbTxResp};
+use crate::dto::reldb_exec_dto::{ReldbDdlReq, ReldbDeleteReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlReq, ReldbTxResp, ReldbUpsertReq};
+use crate::reldb_config::ReldbConfig;
+use crate::reldb_constants::TX_FORWARDED_HEADER;
 use crate::reldb_initializer;
+use crate::serv::reldb_statement_serv;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use lazy_static::lazy_static;
+use serde::Serialize;
 use std::collections::HashMap;
+use subtle::ConstantTimeEq;
 use tardis::basic::error::TardisError;
 use tardis::basic::result::TardisResult;
 use tardis::chrono::Utc;
 use tardis::db::reldb_client::TardisRelDBlConnection;
-use tardis::db::sea_orm::{DbErr, FromQueryResult, Value};
-use tardis::log::trace;
+use tardis::db::sea_orm::{DatabaseBackend, DbErr, ExecResult, FromQueryResult, Statement, Value};
+use tardis::log::{trace, warn};
 use tardis::tokio::sync::RwLock;
 use tardis::tokio::time::{self, Duration};
+use tardis::web::poem_openapi::types::{ParseFromJSON, ToJSON};
+use tardis::web::web_client::TardisHttpResponse;
+use tardis::web::web_resp::{TardisResp, Void};
 use tardis::{basic::dto::TardisContext, db::reldb_client::TardisRelDBClient};
 use tardis::{serde_json::Value as JsonValue, TardisFuns, TardisFunsInst};
 
@@ -18,51 +26,33 @@ lazy_static! {
     static ref TX_CONTAINER: RwLock<HashMap<String, (TardisRelDBlConnection, i64, bool)>> = RwLock::new(HashMap::new());
 }
 
-fn parse_params(params: &JsonValue) -> Vec<Value> {
-    let Some(arr) = params.as_array() else {
-        // this means params is not an array, just return an empty array
-        return Vec::new();
-    };
-    arr.iter()
-        .filter_map(|item| {
-            match item {
-                JsonValue::Null => Some(Value::from(None::<u64>)),
-                JsonValue::Bool(b) => Some(Value::from(*b)),
-                JsonValue::Number(n) => {
-                    if let Some(x) = n.as_u64() {
-                        Some(Value::from(x))
-                    } else if let Some(x) = n.as_i64() {
-                        Some(Value::from(x))
-                    } else if let Some(x) = n.as_f64() {
-                        Some(Value::from(x))
-                    } else {
-                        // unreachable
-                        unreachable!("Json number should be parsed as u64, i64 or f64, so is's unreachable here")
-                    }
-                }
-                JsonValue::String(s) => Some(Value::from(s.as_str())),
-                // TODO do not supports array and object, just skip over it
-                JsonValue::Array(..) | JsonValue::Object(..) => None,
-            }
-        })
-        .collect::<Vec<Value>>()
-}
-
 pub async fn tx_begin(auto_commit: bool, exp_sec: Option<u8>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbTxResp> {
-    let tx_id = TardisFuns::crypto.hex.encode(TardisFuns::field.nanoid());
+    let conf = funs.conf::<ReldbConfig>();
+    let tx_id = if conf.tx_node_url.is_empty() {
+        TardisFuns::crypto.hex.encode(TardisFuns::field.nanoid())
+    } else {
+        pack_tx_id(&conf.tx_node_url, &conf.tx_forward_secret)?
+    };
     let exp_ts_at = Utc::now().timestamp_millis() + (exp_sec.unwrap_or(5)) as i64 * 1000;
     let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
+    let backend = bs_inst.0.backend();
     let mut conn = reldb_initializer::inst_conn(bs_inst).await?;
     if !conn.has_tx() {
         conn.begin().await?;
     }
+    limit_statement(&mut conn, backend, conf.statement_timeout_ms).await?;
     let mut tx_container = TX_CONTAINER.write().await;
     tx_container.insert(tx_id.clone(), (conn, exp_ts_at, auto_commit));
     Ok(ReldbTxResp { tx_id, exp_ts_at })
 }
 
-pub async fn tx_commit(tx_id: String) -> TardisResult<()> {
+pub async fn tx_commit(tx_id: String, forwarded: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if let Some(node_url) = tx_owner(&tx_id, forwarded, funs)? {
+        let resp = funs.web_client().put::<Void, TardisResp<Void>>(&format!("{node_url}/ci/exec/tx?tx_id={tx_id}"), &Void {}, forward_headers(ctx)?).await?;
+        forwarded_resp(resp)?;
+        return Ok(());
+    }
     let mut tx_container = TX_CONTAINER.write().await;
     match tx_container.remove(&tx_id) {
         Some((conn, _, _)) => conn.commit().await?,
@@ -71,7 +61,16 @@ pub async fn tx_commit(tx_id: String) -> TardisResult<()> {
     Ok(())
 }
 
-pub async fn tx_rollback(tx_id: String) -> TardisResult<()> {
+pub async fn tx_rollback(tx_id: String, forwarded: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if let Some(node_url) = tx_owner(&tx_id, forwarded, funs)? {
+        let resp = funs.web_client().delete::<TardisResp<Void>>(&format!("{node_url}/ci/exec/tx?tx_id={tx_id}"), forward_headers(ctx)?).await?;
+        forwarded_resp(resp)?;
+        return Ok(());
+    }
+    local_tx_rollback(tx_id).await
+}
+
+async fn local_tx_rollback(tx_id: String) -> TardisResult<()> {
     let mut tx_container = TX_CONTAINER.write().await;
     match tx_container.remove(&tx_id) {
         Some((conn, _, _)) => conn.rollback().await?,
@@ -81,28 +80,88 @@ pub async fn tx_rollback(tx_id: String) -> TardisResult<()> {
 }
 
 pub async fn ddl(ddl_req: &mut ReldbDdlReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let conf = funs.conf::<ReldbConfig>();
+    reldb_statement_serv::check_statement(&ddl_req.sql, &conf.ddl_allowed_statements)?;
     let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
-    let conn = reldb_initializer::inst_conn(bs_inst).await?;
-    let params = parse_params(&ddl_req.params);
+    let backend = bs_inst.0.backend();
+    let mut conn = reldb_initializer::inst_conn(bs_inst).await?;
+    limit_statement(&mut conn, backend, conf.statement_timeout_ms).await?;
+    let params = reldb_statement_serv::parse_params(&ddl_req.params, backend != DatabaseBackend::Postgres);
     conn.execute_one(&ddl_req.sql, params).await?;
     conn.commit().await?;
     Ok(())
 }
 
-pub async fn dml(dml_req: &mut ReldbDmlReq, tx_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbDmlResp> {
+pub async fn dml(dml_req: &mut ReldbDmlReq, tx_id: Option<String>, forwarded: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbDmlResp> {
+    reldb_statement_serv::check_statement(&dml_req.sql, &funs.conf::<ReldbConfig>().dml_allowed_statements)?;
+    if let Some(tx_id) = &tx_id {
+        if let Some(node_url) = tx_owner(tx_id, forwarded, funs)? {
+            let resp = funs.web_client().post::<ReldbDmlReq, TardisResp<ReldbDmlResp>>(&format!("{node_url}/ci/exec/dml?tx_id={tx_id}"), dml_req, forward_headers(ctx)?).await?;
+            return forwarded_resp(resp)?.ok_or_else(|| TardisError::internal_error("forwarded dml returned no data", "500-spi-reldb-tx-forward-failed"));
+        }
+    }
+    execute_dml(tx_id, funs, ctx, |backend| {
+        Ok(Statement::from_sql_and_values(
+            backend,
+            &dml_req.sql,
+            reldb_statement_serv::parse_params(&dml_req.params, backend != DatabaseBackend::Postgres),
+        ))
+    })
+    .await
+}
+
+pub async fn upsert(upsert_req: &mut ReldbUpsertReq, tx_id: Option<String>, forwarded: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbDmlResp> {
+    reldb_statement_serv::check_statement("insert", &funs.conf::<ReldbConfig>().dml_allowed_statements)?;
+    if let Some(tx_id) = &tx_id {
+        if let Some(node_url) = tx_owner(tx_id, forwarded, funs)? {
+            let resp =
+                funs.web_client().post::<ReldbUpsertReq, TardisResp<ReldbDmlResp>>(&format!("{node_url}/ci/exec/upsert?tx_id={tx_id}"), upsert_req, forward_headers(ctx)?).await?;
+            return forwarded_resp(resp)?.ok_or_else(|| TardisError::internal_error("forwarded upsert returned no data", "500-spi-reldb-tx-forward-failed"));
+        }
+    }
+    execute_dml(tx_id, funs, ctx, |backend| {
+        reldb_statement_serv::build_upsert(upsert_req, backend, backend != DatabaseBackend::Postgres)
+    })
+    .await
+}
+
+pub async fn delete(delete_req: &mut ReldbDeleteReq, tx_id: Option<String>, forwarded: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbDmlResp> {
+    reldb_statement_serv::check_statement("delete", &funs.conf::<ReldbConfig>().dml_allowed_statements)?;
+    if let Some(tx_id) = &tx_id {
+        if let Some(node_url) = tx_owner(tx_id, forwarded, funs)? {
+            let resp =
+                funs.web_client().post::<ReldbDeleteReq, TardisResp<ReldbDmlResp>>(&format!("{node_url}/ci/exec/delete?tx_id={tx_id}"), delete_req, forward_headers(ctx)?).await?;
+            return forwarded_resp(resp)?.ok_or_else(|| TardisError::internal_error("forwarded delete returned no data", "500-spi-reldb-tx-forward-failed"));
+        }
+    }
+    execute_dml(tx_id, funs, ctx, |backend| reldb_statement_serv::build_delete(delete_req, backend)).await
+}
+
+/// Execute a statement built for the backend of the current instance, inside the transaction if `tx_id` is given.
+async fn execute_dml(
+    tx_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    build_statement: impl FnOnce(DatabaseBackend) -> TardisResult<Statement>,
+) -> TardisResult<ReldbDmlResp> {
     let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
-    let params = parse_params(&dml_req.params);
+    let backend = bs_inst.0.backend();
+    let timeout_ms = funs.conf::<ReldbConfig>().statement_timeout_ms;
+    let statement = build_statement(backend)?;
+    let params = statement.values.map(|values| values.0).unwrap_or_default();
     let resp = if let Some(tx_id) = &tx_id {
         let tx_container = TX_CONTAINER.read().await;
         match tx_container.get(tx_id) {
-            Some((conn, _, _)) => conn.execute_one(&dml_req.sql, params).await,
+            Some((conn, _, _)) => execute_limited(conn, &statement.sql, params, bs_inst.0, timeout_ms).await,
             None => Err(TardisError::bad_request("tx not exist", "")),
         }
     } else {
-        let conn = reldb_initializer::inst_conn(bs_inst).await?;
-        let resp = conn.execute_one(&dml_req.sql, params).await;
+        let client = bs_inst.0;
+        let mut conn = reldb_initializer::inst_conn(bs_inst).await?;
+        limit_statement(&mut conn, backend, timeout_ms).await?;
+        let resp = execute_limited(&conn, &statement.sql, params, client, timeout_ms).await;
         conn.commit().await?;
         resp
     };
@@ -112,7 +171,7 @@ pub async fn dml(dml_req: &mut ReldbDmlReq, tx_id: Option<String>, funs: &Tardis
         }),
         Err(e) => {
             if let Some(tx_id) = tx_id {
-                tx_rollback(tx_id).await?;
+                local_tx_rollback(tx_id).await?;
             }
             trace!("[SPI-Reldb] dml error: {}", e);
             Err(e)
@@ -120,26 +179,150 @@ pub async fn dml(dml_req: &mut ReldbDmlReq, tx_id: Option<String>, funs: &Tardis
     }
 }
 
-pub async fn dql(dql_req: &mut ReldbDqlReq, tx_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<JsonValue>> {
+pub async fn dql(dql_req: &mut ReldbDqlReq, tx_id: Option<String>, forwarded: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<JsonValue>> {
+    let conf = funs.conf::<ReldbConfig>();
+    reldb_statement_serv::check_statement(&dql_req.sql, &conf.dql_allowed_statements)?;
+    if let Some(tx_id) = &tx_id {
+        if let Some(node_url) = tx_owner(tx_id, forwarded, funs)? {
+            let resp = funs.web_client().put::<ReldbDqlReq, TardisResp<Vec<JsonValue>>>(&format!("{node_url}/ci/exec/dql?tx_id={tx_id}"), dql_req, forward_headers(ctx)?).await?;
+            return Ok(forwarded_resp(resp)?.unwrap_or_default());
+        }
+    }
     let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
-    let params = parse_params(&dql_req.params);
+    let backend = bs_inst.0.backend();
+    let params = reldb_statement_serv::parse_params(&dql_req.params, backend != DatabaseBackend::Postgres);
+    let mysql_timeout_ms = if backend == DatabaseBackend::MySql { conf.statement_timeout_ms } else { 0 };
+    let sql = reldb_statement_serv::limit_query_sql(&dql_req.sql, conf.dql_max_rows, mysql_timeout_ms);
     let resp = if let Some(tx_id) = tx_id {
         let tx_container = TX_CONTAINER.read().await;
         match tx_container.get(&tx_id) {
-            Some((conn, _, _)) => conn.query_all(&dql_req.sql, params).await,
+            Some((conn, _, _)) => conn.query_all(&sql, params).await,
             None => Err(TardisError::bad_request("tx not exist", "")),
         }
     } else {
-        let conn = reldb_initializer::inst_conn(bs_inst).await?;
-        let resp = conn.query_all(&dql_req.sql, params).await;
+        let mut conn = reldb_initializer::inst_conn(bs_inst).await?;
+        limit_statement(&mut conn, backend, conf.statement_timeout_ms).await?;
+        let resp = conn.query_all(&sql, params).await;
         conn.commit().await?;
         resp
     }?;
+    if conf.dql_max_rows > 0 && resp.len() > conf.dql_max_rows as usize {
+        return Err(TardisError::bad_request(
+            &format!("query returns more than {} rows", conf.dql_max_rows),
+            "400-spi-reldb-too-many-rows",
+        ));
+    }
     let result = resp.iter().filter_map(|row| JsonValue::from_query_result_optional(row, "").transpose()).collect::<Result<Vec<JsonValue>, DbErr>>()?;
     Ok(result)
 }
 
+/// Let PostgreSQL abort statements running longer than `timeout_ms`.
+///
+/// The timeout is scoped to the transaction by `SET LOCAL`, so a transaction is started if the connection has none.
+/// MySQL has no transaction scoped timeout and its sessions are pooled, so its queries carry the timeout as an optimizer hint instead,
+/// see [`reldb_statement_serv::limit_query_sql`], and its other statements are killed by [`execute_limited`].
+/// The transaction is started for MySQL as well, which keeps the statements on one connection.
+async fn limit_statement(conn: &mut TardisRelDBlConnection, backend: DatabaseBackend, timeout_ms: u32) -> TardisResult<()> {
+    if timeout_ms == 0 {
+        return Ok(());
+    }
+    if !conn.has_tx() {
+        conn.begin().await?;
+    }
+    if backend == DatabaseBackend::Postgres {
+        conn.execute_one(&format!("SET LOCAL statement_timeout = {timeout_ms}"), vec![]).await?;
+    }
+    Ok(())
+}
+
+/// Execute a statement, MySQL statements running longer than `timeout_ms` are killed by another connection.
+///
+/// The optimizer hint of MySQL only applies to `SELECT`, so the other statements are killed by `KILL QUERY` instead.
+/// `conn` must be in a transaction, so the statement runs on the connection whose id is read.
+async fn execute_limited(conn: &TardisRelDBlConnection, sql: &str, params: Vec<Value>, client: &TardisRelDBClient, timeout_ms: u32) -> TardisResult<ExecResult> {
+    if timeout_ms == 0 || client.backend() != DatabaseBackend::MySql {
+        return conn.execute_one(sql, params).await;
+    }
+    let Some(connection_id) = conn.query_one("SELECT CONNECTION_ID() AS id", vec![]).await?.and_then(|row| row.try_get::<u64>("", "id").ok()) else {
+        return Err(TardisError::internal_error("cannot read the connection id", "500-spi-reldb-connection-id-not-found"));
+    };
+    let kill_conn = client.conn();
+    let watchdog = tardis::tokio::spawn(async move {
+        time::sleep(Duration::from_millis(timeout_ms as u64)).await;
+        if let Err(e) = kill_conn.execute_one(&format!("KILL QUERY {connection_id}"), vec![]).await {
+            warn!("[SPI-Reldb] kill the statement running over {timeout_ms}ms failed: {e}");
+        }
+    });
+    let resp = conn.execute_one(sql, params).await;
+    watchdog.abort();
+    resp
+}
+
+/// Build a transaction id that carries the url of the owning node, signed with the cluster secret.
+///
+/// Format: `{hex(node_url)}.{hex(nonce)}.{hex(hmac_sha256(node.nonce, secret))}`
+fn pack_tx_id(node_url: &str, secret: &str) -> TardisResult<String> {
+    let node = TardisFuns::crypto.hex.encode(node_url);
+    let nonce = TardisFuns::crypto.hex.encode(TardisFuns::field.nanoid());
+    let sign = TardisFuns::crypto.hex.encode(TardisFuns::crypto.digest.hmac_sha256(format!("{node}.{nonce}"), secret)?);
+    Ok(format!("{node}.{nonce}.{sign}"))
+}
+
+/// Extract the owning node url from a transaction id.
+///
+/// Returns `None` for ids without a node part, ids with an invalid signature, or when no secret is configured.
+fn unpack_tx_node(tx_id: &str, secret: &str) -> TardisResult<Option<String>> {
+    if secret.is_empty() {
+        return Ok(None);
+    }
+    let [node, nonce, sign] = tx_id.split('.').collect::<Vec<&str>>()[..] else {
+        return Ok(None);
+    };
+    let Ok(sign) = TardisFuns::crypto.hex.decode(sign) else {
+        return Ok(None);
+    };
+    // compared in constant time, so that a valid signature cannot be guessed from the response times
+    if !bool::from(TardisFuns::crypto.digest.hmac_sha256(format!("{node}.{nonce}"), secret)?.as_slice().ct_eq(sign.as_slice())) {
+        return Ok(None);
+    }
+    let Ok(node_url) = TardisFuns::crypto.hex.decode(node).map(String::from_utf8) else {
+        return Ok(None);
+    };
+    Ok(node_url.ok())
+}
+
+/// The url of the node holding the transaction, if it is not this node.
+/// Forwarded requests are handled by this node, so a node reachable by several urls doesn't forward to itself endlessly.
+fn tx_owner(tx_id: &str, forwarded: bool, funs: &TardisFunsInst) -> TardisResult<Option<String>> {
+    if forwarded {
+        return Ok(None);
+    }
+    let conf = funs.conf::<ReldbConfig>();
+    Ok(unpack_tx_node(tx_id, &conf.tx_forward_secret)?.filter(|node_url| node_url != &conf.tx_node_url))
+}
+
+fn forward_headers(ctx: &TardisContext) -> TardisResult<Vec<(String, String)>> {
+    Ok(vec![
+        ("Tardis-Context".to_string(), TardisFuns::crypto.base64.encode(TardisFuns::json.obj_to_string(ctx)?)),
+        (TX_FORWARDED_HEADER.to_string(), "true".to_string()),
+    ])
+}
+
+fn forwarded_resp<T>(resp: TardisHttpResponse<TardisResp<T>>) -> TardisResult<Option<T>>
+where
+    T: ParseFromJSON + ToJSON + Serialize + Send + Sync,
+{
+    match resp.body {
+        Some(body) if body.code.starts_with("200") => Ok(body.data),
+        Some(body) => Err(TardisError::custom(&body.code, &body.msg, "")),
+        None => Err(TardisError::internal_error(
+            &format!("forward to the transaction owner node failed with status {}", resp.code),
+            "500-spi-reldb-tx-forward-failed",
+        )),
+    }
+}
+
 pub async fn clean(clean_interval_sec: u8) {
     tardis::tokio::spawn(async move {
         let mut interval = time::interval(Duration::from_secs(clean_interval_sec as u64));
@@ -166,3 +349,22 @@ pub async fn clean(clean_interval_sec: u8) {
         TardisResult::<()>::Ok(())
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{pack_tx_id, unpack_tx_node};
+
+    #[test]
+    fn test_tx_id_node() {
+        let tx_id = pack_tx_id("https://10.0.0.1:8080/spi-reldb", "secret").unwrap();
+        assert_eq!(unpack_tx_node(&tx_id, "secret").unwrap(), Some("https://10.0.0.1:8080/spi-reldb".to_string()));
+        assert_eq!(unpack_tx_node(&tx_id, "other").unwrap(), None);
+        assert_eq!(unpack_tx_node(&tx_id, "").unwrap(), None);
+        assert_eq!(unpack_tx_node("6162636465", "secret").unwrap(), None);
+        let forged = format!("{}.{}", "68747470733a2f2f6576696c", tx_id.split_once('.').unwrap().1);
+        assert_eq!(unpack_tx_node(&forged, "secret").unwrap(), None);
+        let (signed, _) = tx_id.rsplit_once('.').unwrap();
+        assert_eq!(unpack_tx_node(&format!("{signed}.not-hex"), "secret").unwrap(), None);
+        assert_eq!(unpack_tx_node(&format!("{signed}.00"), "secret").unwrap(), None);
+    }
+}
@@ -0,0 +1,335 @@
+use crate::dto::reldb_exec_dto::{ReldbDeleteReq, ReldbUpsertReq};
+use tardis::basic::error::TardisError;
+use tardis::basic::result::TardisResult;
+use tardis::db::sea_orm::sea_query::{Alias, ArrayType, Expr, OnConflict, Query, SimpleExpr};
+use tardis::db::sea_orm::{DatabaseBackend, Statement, Value};
+use tardis::serde_json::Value as JsonValue;
+
+/// Convert json params to database values.
+///
+/// Arrays of strings, booleans or numbers are bound as database arrays, other arrays and objects are bound as json.
+/// Backends without array support (`array_as_json`) bind all arrays as json.
+pub(crate) fn parse_params(params: &JsonValue, array_as_json: bool) -> Vec<Value> {
+    let Some(arr) = params.as_array() else {
+        // this means params is not an array, just return an empty array
+        return Vec::new();
+    };
+    arr.iter().map(|item| json_to_value(item, array_as_json)).collect::<Vec<Value>>()
+}
+
+fn json_to_value(item: &JsonValue, array_as_json: bool) -> Value {
+    match item {
+        JsonValue::Null => Value::from(None::<u64>),
+        JsonValue::Bool(b) => Value::from(*b),
+        JsonValue::Number(n) => {
+            if let Some(x) = n.as_u64() {
+                Value::from(x)
+            } else if let Some(x) = n.as_i64() {
+                Value::from(x)
+            } else if let Some(x) = n.as_f64() {
+                Value::from(x)
+            } else {
+                // unreachable
+                unreachable!("Json number should be parsed as u64, i64 or f64, so is's unreachable here")
+            }
+        }
+        JsonValue::String(s) => Value::from(s.as_str()),
+        JsonValue::Array(items) if !array_as_json => json_to_array(items).unwrap_or_else(|| Value::Json(Some(Box::new(item.clone())))),
+        JsonValue::Array(..) | JsonValue::Object(..) => Value::Json(Some(Box::new(item.clone()))),
+    }
+}
+
+fn json_to_array(items: &[JsonValue]) -> Option<Value> {
+    if items.iter().all(JsonValue::is_string) {
+        Some(Value::Array(
+            ArrayType::String,
+            Some(Box::new(items.iter().filter_map(JsonValue::as_str).map(Value::from).collect())),
+        ))
+    } else if items.iter().all(JsonValue::is_boolean) {
+        Some(Value::Array(
+            ArrayType::Bool,
+            Some(Box::new(items.iter().filter_map(JsonValue::as_bool).map(Value::from).collect())),
+        ))
+    } else if items.iter().all(JsonValue::is_i64) {
+        Some(Value::Array(
+            ArrayType::BigInt,
+            Some(Box::new(items.iter().filter_map(JsonValue::as_i64).map(Value::from).collect())),
+        ))
+    } else if items.iter().all(JsonValue::is_number) {
+        Some(Value::Array(
+            ArrayType::Double,
+            Some(Box::new(items.iter().filter_map(JsonValue::as_f64).map(Value::from).collect())),
+        ))
+    } else {
+        None
+    }
+}
+
+/// Check that the sql is a single statement whose leading keyword is in the allowed list (empty list means no restriction).
+pub(crate) fn check_statement(sql: &str, allowed_statements: &[String]) -> TardisResult<()> {
+    let Some(keyword) = statement_keyword(sql) else {
+        return Err(TardisError::bad_request("sql must be exactly one statement", "400-spi-reldb-invalid-statement"));
+    };
+    if !allowed_statements.is_empty() && !allowed_statements.iter().any(|allowed| allowed.eq_ignore_ascii_case(&keyword)) {
+        return Err(TardisError::bad_request(
+            &format!("statement [{keyword}] is not allowed"),
+            "400-spi-reldb-statement-not-allowed",
+        ));
+    }
+    Ok(())
+}
+
+/// Kind (lowercase keyword) of the sql, which is checked against the allowed statements.
+///
+/// It is the leading keyword, except for `WITH` and `SELECT` statements containing a data-modifying keyword (`insert`, `update`, `delete`, `merge` or `into`),
+/// whose kind is that keyword, so that data-modifying CTEs and `SELECT ... INTO` are not taken as queries.
+/// Returns `None` if the sql is empty, does not start with a keyword, or contains more than one statement.
+fn statement_keyword(sql: &str) -> Option<String> {
+    let words = statement_words(sql)?;
+    let keyword = words.first()?;
+    if keyword != "with" && keyword != "select" {
+        return Some(keyword.clone());
+    }
+    let modifying = words.iter().enumerate().skip(1).find(|(idx, word)| match word.as_str() {
+        "insert" | "delete" | "merge" | "into" => true,
+        // row locks, `FOR UPDATE` and `FOR NO KEY UPDATE`
+        "update" => !matches!(words[idx - 1].as_str(), "for" | "key"),
+        _ => false,
+    });
+    Some(modifying.map(|(_, word)| word).unwrap_or(keyword).clone())
+}
+
+/// Bare words (lowercase) of the sql, the first one is the leading keyword.
+///
+/// Returns `None` if the sql is empty, does not start with a keyword, or contains more than one statement.
+/// Quoted strings, quoted identifiers, dollar quoted bodies and comments are skipped.
+fn statement_words(sql: &str) -> Option<Vec<String>> {
+    let chars = sql.chars().collect::<Vec<char>>();
+    let find = |from: usize, pat: &[char]| (from..chars.len()).find(|&i| chars[i..].starts_with(pat));
+    let mut words: Vec<String> = vec![];
+    let mut ended = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '-' && next == Some('-') {
+            i = find(i, &['\n']).unwrap_or(chars.len());
+            continue;
+        }
+        if c == '/' && next == Some('*') {
+            i = find(i + 2, &['*', '/']).map(|end| end + 2).unwrap_or(chars.len());
+            continue;
+        }
+        if ended {
+            // something other than whitespace or comments after the first statement
+            return None;
+        }
+        if c == ';' {
+            ended = true;
+            i += 1;
+            continue;
+        }
+        if words.is_empty() {
+            if !c.is_ascii_alphabetic() {
+                return None;
+            }
+            let end = (i..chars.len()).find(|&j| !chars[j].is_ascii_alphabetic()).unwrap_or(chars.len());
+            words.push(chars[i..end].iter().collect::<String>().to_lowercase());
+            i = end;
+            continue;
+        }
+        match c {
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let end = (i..chars.len()).find(|&j| !(chars[j].is_ascii_alphanumeric() || chars[j] == '_')).unwrap_or(chars.len());
+                words.push(chars[i..end].iter().collect::<String>().to_lowercase());
+                i = end;
+            }
+            '\'' | '"' | '`' => {
+                i = find(i + 1, &[c]).map(|end| end + 1).unwrap_or(chars.len());
+            }
+            '$' => {
+                let tag_end = (i + 1..chars.len()).find(|&j| !(chars[j].is_ascii_alphanumeric() || chars[j] == '_'));
+                match tag_end {
+                    Some(tag_end) if chars[tag_end] == '$' && !next.is_some_and(|n| n.is_ascii_digit()) => {
+                        let tag = &chars[i..=tag_end];
+                        i = find(tag_end + 1, tag).map(|end| end + tag.len()).unwrap_or(chars.len());
+                    }
+                    _ => i += 1,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    (!words.is_empty()).then_some(words)
+}
+
+/// Leading keywords of the statements returning rows, which can be wrapped as subqueries
+const QUERY_KEYWORDS: [&str; 4] = ["select", "with", "values", "table"];
+
+/// Wrap a query (`SELECT`, `WITH`, `VALUES` or `TABLE`) as a subquery to apply the guardrails of queries, other statements are returned as is.
+///
+/// With `max_rows` the query returns at most `max_rows + 1` rows, the extra row tells the caller the limit was exceeded.
+/// With `mysql_timeout_ms` the `MAX_EXECUTION_TIME` optimizer hint of MySQL is added, which only applies to this statement instead of the pooled session.
+pub(crate) fn limit_query_sql(sql: &str, max_rows: u32, mysql_timeout_ms: u32) -> String {
+    if (max_rows == 0 && mysql_timeout_ms == 0) || !statement_keyword(sql).is_some_and(|keyword| QUERY_KEYWORDS.contains(&keyword.as_str())) {
+        return sql.to_string();
+    }
+    let sql = sql.trim().trim_end_matches(';');
+    let hint = if mysql_timeout_ms > 0 {
+        format!(" /*+ MAX_EXECUTION_TIME({mysql_timeout_ms}) */")
+    } else {
+        "".to_string()
+    };
+    let limit = if max_rows > 0 { format!(" LIMIT {}", max_rows as u64 + 1) } else { "".to_string() };
+    // the line break ends a trailing line comment of the query
+    format!("SELECT{hint} * FROM ({sql}\n) AS reldb_limited{limit}")
+}
+
+fn check_identifier(name: &str) -> TardisResult<()> {
+    let mut chars = name.chars();
+    let valid = name.len() <= 63 && chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(TardisError::bad_request(&format!("[{name}] is not a valid identifier"), "400-spi-reldb-invalid-identifier"));
+    }
+    Ok(())
+}
+
+/// Build an `INSERT ... ON CONFLICT` statement for the records, updating all non primary key fields of existing rows.
+pub(crate) fn build_upsert(upsert_req: &ReldbUpsertReq, backend: DatabaseBackend, array_as_json: bool) -> TardisResult<Statement> {
+    check_identifier(&upsert_req.table_name)?;
+    check_identifier(&upsert_req.pk_name)?;
+    let Some(columns) = upsert_req.records.first().and_then(JsonValue::as_object).map(|record| record.keys().cloned().collect::<Vec<String>>()) else {
+        return Err(TardisError::bad_request("records must be objects", "400-spi-reldb-invalid-records"));
+    };
+    if !columns.contains(&upsert_req.pk_name) {
+        return Err(TardisError::bad_request("records must contain the primary key", "400-spi-reldb-invalid-records"));
+    }
+    for column in &columns {
+        check_identifier(column)?;
+    }
+    let mut query = Query::insert();
+    query.into_table(Alias::new(upsert_req.table_name.as_str())).columns(columns.iter().map(|column| Alias::new(column.as_str())));
+    for record in &upsert_req.records {
+        let Some(record) = record.as_object().filter(|record| record.len() == columns.len()) else {
+            return Err(TardisError::bad_request("all records must have the same fields", "400-spi-reldb-invalid-records"));
+        };
+        let values = columns
+            .iter()
+            .map(|column| record.get(column).map(|value| SimpleExpr::Value(json_to_value(value, array_as_json))))
+            .collect::<Option<Vec<SimpleExpr>>>()
+            .ok_or_else(|| TardisError::bad_request("all records must have the same fields", "400-spi-reldb-invalid-records"))?;
+        query.values(values).map_err(|e| TardisError::bad_request(&e.to_string(), "400-spi-reldb-invalid-records"))?;
+    }
+    let update_columns = columns.iter().filter(|column| *column != &upsert_req.pk_name).map(|column| Alias::new(column.as_str())).collect::<Vec<Alias>>();
+    let on_conflict = if update_columns.is_empty() {
+        // nothing to update, rewrite the primary key so that the statement stays valid on every backend
+        OnConflict::column(Alias::new(upsert_req.pk_name.as_str())).update_column(Alias::new(upsert_req.pk_name.as_str())).to_owned()
+    } else {
+        OnConflict::column(Alias::new(upsert_req.pk_name.as_str())).update_columns(update_columns).to_owned()
+    };
+    query.on_conflict(on_conflict);
+    Ok(backend.build(&query))
+}
+
+/// Build a `DELETE ... WHERE pk IN (...)` statement.
+pub(crate) fn build_delete(delete_req: &ReldbDeleteReq, backend: DatabaseBackend) -> TardisResult<Statement> {
+    check_identifier(&delete_req.table_name)?;
+    check_identifier(&delete_req.pk_name)?;
+    if delete_req.pk_ids.iter().any(|pk_id| pk_id.is_array() || pk_id.is_object() || pk_id.is_null()) {
+        return Err(TardisError::bad_request("primary keys must be strings, numbers or booleans", "400-spi-reldb-invalid-pk"));
+    }
+    let query = Query::delete()
+        .from_table(Alias::new(delete_req.table_name.as_str()))
+        .and_where(Expr::col(Alias::new(delete_req.pk_name.as_str())).is_in(delete_req.pk_ids.iter().map(|pk_id| json_to_value(pk_id, true))))
+        .to_owned();
+    Ok(backend.build(&query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_delete, build_upsert, limit_query_sql, statement_keyword};
+    use crate::dto::reldb_exec_dto::{ReldbDeleteReq, ReldbUpsertReq};
+    use tardis::db::sea_orm::DatabaseBackend;
+    use tardis::serde_json::json;
+
+    #[test]
+    fn test_statement_keyword() {
+        assert_eq!(statement_keyword("select * from t").as_deref(), Some("select"));
+        assert_eq!(statement_keyword("  -- comment\n /* block; */ INSERT into t values (1);  ").as_deref(), Some("insert"));
+        assert_eq!(statement_keyword("select ';drop table t' from t").as_deref(), Some("select"));
+        assert_eq!(statement_keyword("select \"a;b\" from t where c = $1").as_deref(), Some("select"));
+        assert_eq!(
+            statement_keyword("create function f() returns int as $body$ select 1; $body$ language sql").as_deref(),
+            Some("create")
+        );
+        assert_eq!(statement_keyword("select 1; drop table t"), None);
+        assert_eq!(statement_keyword("select 1; -- tail"), Some("select".to_string()));
+        assert_eq!(statement_keyword("(select 1)"), None);
+        assert_eq!(statement_keyword("  "), None);
+        // data-modifying ctes and select into are not queries
+        assert_eq!(statement_keyword("with d as (delete from t returning *) select * from d").as_deref(), Some("delete"));
+        assert_eq!(statement_keyword("WITH u AS (UPDATE t SET a = 1 RETURNING *) SELECT * FROM u").as_deref(), Some("update"));
+        assert_eq!(statement_keyword("with x as (select 1) insert into t select * from x").as_deref(), Some("insert"));
+        assert_eq!(statement_keyword("select * into t2 from t").as_deref(), Some("into"));
+        assert_eq!(statement_keyword("with x as (select 1) select * from x").as_deref(), Some("with"));
+        assert_eq!(statement_keyword("select * from t for update").as_deref(), Some("select"));
+        assert_eq!(statement_keyword("select * from t for no key update").as_deref(), Some("select"));
+        assert_eq!(statement_keyword("select \"delete\", last_update from t where a = 'update'").as_deref(), Some("select"));
+    }
+
+    #[test]
+    fn test_limit_query_sql() {
+        assert_eq!(limit_query_sql(" select * from t; ", 10, 0), "SELECT * FROM (select * from t\n) AS reldb_limited LIMIT 11");
+        assert_eq!(
+            limit_query_sql("select * from t -- tail", 0, 2000),
+            "SELECT /*+ MAX_EXECUTION_TIME(2000) */ * FROM (select * from t -- tail\n) AS reldb_limited"
+        );
+        assert_eq!(
+            limit_query_sql("with x as (select 1) select * from x", 10, 2000),
+            "SELECT /*+ MAX_EXECUTION_TIME(2000) */ * FROM (with x as (select 1) select * from x\n) AS reldb_limited LIMIT 11"
+        );
+        assert_eq!(limit_query_sql("values (1), (2)", 1, 0), "SELECT * FROM (values (1), (2)\n) AS reldb_limited LIMIT 2");
+        assert_eq!(limit_query_sql("explain select * from t", 10, 2000), "explain select * from t");
+        assert_eq!(limit_query_sql("select * from t", 0, 0), "select * from t");
+    }
+
+    #[test]
+    fn test_build_upsert_and_delete() {
+        let upsert_req = ReldbUpsertReq {
+            table_name: "t".to_string(),
+            pk_name: "id".to_string(),
+            records: vec![json!({"id": 1, "name": "a"}), json!({"id": 2, "name": "b"})],
+        };
+        let statement = build_upsert(&upsert_req, DatabaseBackend::Postgres, false).unwrap();
+        assert_eq!(
+            statement.sql,
+            r#"INSERT INTO "t" ("id", "name") VALUES ($1, $2), ($3, $4) ON CONFLICT ("id") DO UPDATE SET "name" = "excluded"."name""#
+        );
+        assert_eq!(statement.values.unwrap().0.len(), 4);
+
+        let invalid_req = ReldbUpsertReq {
+            table_name: "t; drop table t".to_string(),
+            pk_name: "id".to_string(),
+            records: vec![json!({"id": 1})],
+        };
+        assert!(build_upsert(&invalid_req, DatabaseBackend::Postgres, false).is_err());
+        let mismatch_req = ReldbUpsertReq {
+            table_name: "t".to_string(),
+            pk_name: "id".to_string(),
+            records: vec![json!({"id": 1, "name": "a"}), json!({"id": 2, "other": "b"})],
+        };
+        assert!(build_upsert(&mismatch_req, DatabaseBackend::Postgres, false).is_err());
+
+        let delete_req = ReldbDeleteReq {
+            table_name: "t".to_string(),
+            pk_name: "id".to_string(),
+            pk_ids: vec![json!(1), json!(2)],
+        };
+        let statement = build_delete(&delete_req, DatabaseBackend::Postgres).unwrap();
+        assert_eq!(statement.sql, r#"DELETE FROM "t" WHERE "id" IN ($1, $2)"#);
+    }
+}
@@ -1,13 +1,14 @@
 use std::time::Duration;
 
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_reldb::dto::reldb_exec_dto::{ReldbDdlReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlReq, ReldbTxResp};
+use bios_spi_reldb::dto::reldb_exec_dto::{ReldbDdlReq, ReldbDeleteReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlReq, ReldbTxResp, ReldbUpsertReq};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::log::info;
 use tardis::serde_json::{json, Value};
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::{TardisResp, Void};
+use tardis::TardisFuns;
 
 pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     client.set_auth(&TardisContext {
@@ -25,6 +26,9 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     test_tx_error(client).await?;
     test_tx_auto_commit(client).await?;
     test_tx_auto_rollback(client).await?;
+    test_structured(client).await?;
+    test_guardrails(client).await?;
+    test_tx_forward(client).await?;
 
     Ok(())
 }
@@ -83,6 +87,8 @@ pub async fn test_tx_normal(client: &mut TestHttpClient) -> TardisResult<()> {
         .await;
     let tx_resp: ReldbTxResp = client.get("/ci/exec/tx?auto_commit=false").await;
     let tx_id = tx_resp.tx_id;
+    // node aware transaction id: {node}.{nonce}.{sign}
+    assert_eq!(tx_id.split('.').count(), 3);
     let _: ReldbDmlResp = client
         .post(
             &format!("/ci/exec/dml?tx_id={}", tx_id),
@@ -381,3 +387,195 @@ pub async fn test_tx_auto_rollback(client: &mut TestHttpClient) -> TardisResult<
 
     Ok(())
 }
+
+pub async fn test_structured(client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_structured】");
+
+    let _: Void = client
+        .post(
+            "/ci/exec/ddl",
+            &ReldbDdlReq {
+                sql: "create table test_structured (id int primary key, name varchar, tags jsonb)".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    let upsert_resp: ReldbDmlResp = client
+        .post(
+            "/ci/exec/upsert",
+            &ReldbUpsertReq {
+                table_name: "test_structured".to_string(),
+                pk_name: "id".to_string(),
+                records: vec![json!({"id": 1, "name": "a", "tags": {"k": "v"}}), json!({"id": 2, "name": "b", "tags": ["x"]})],
+            },
+        )
+        .await;
+    assert_eq!(upsert_resp.affected_rows, 2);
+    let tx_resp: ReldbTxResp = client.get("/ci/exec/tx?auto_commit=false").await;
+    let _: ReldbDmlResp = client
+        .post(
+            &format!("/ci/exec/upsert?tx_id={}", tx_resp.tx_id),
+            &ReldbUpsertReq {
+                table_name: "test_structured".to_string(),
+                pk_name: "id".to_string(),
+                records: vec![json!({"id": 2, "name": "bb", "tags": ["y"]}), json!({"id": 3, "name": "c", "tags": {"k": "w"}})],
+            },
+        )
+        .await;
+    let delete_resp: ReldbDmlResp = client
+        .post(
+            &format!("/ci/exec/delete?tx_id={}", tx_resp.tx_id),
+            &ReldbDeleteReq {
+                table_name: "test_structured".to_string(),
+                pk_name: "id".to_string(),
+                pk_ids: vec![json!(1), json!(4)],
+            },
+        )
+        .await;
+    assert_eq!(delete_resp.affected_rows, 1);
+    let _: Void = client.put(&format!("/ci/exec/tx?tx_id={}", tx_resp.tx_id), &Void {}).await;
+
+    let dql_resp: Value = client
+        .put(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select id, name from test_structured where id = any($1) order by id".to_string(),
+                params: json!([[1, 2, 3]]),
+            },
+        )
+        .await;
+    assert_eq!(dql_resp.to_string(), r#"[{"id":2,"name":"bb"},{"id":3,"name":"c"}]"#);
+
+    let _: ReldbDmlResp = client
+        .post(
+            "/ci/exec/dml",
+            &ReldbDmlReq {
+                sql: "insert into test_structured (id,name,tags) values ($1,$2,$3)".to_string(),
+                params: json!([5, "e", {"k": "v", "n": 1}]),
+            },
+        )
+        .await;
+    let dql_resp: Value = client
+        .put(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select id from test_structured where tags @> $1".to_string(),
+                params: json!([{"k": "v"}]),
+            },
+        )
+        .await;
+    assert_eq!(dql_resp.to_string(), r#"[{"id":5}]"#);
+
+    Ok(())
+}
+
+pub async fn test_guardrails(client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_guardrails】");
+
+    let resp: TardisResp<ReldbDmlResp> = client
+        .post_resp(
+            "/ci/exec/dml",
+            &ReldbDmlReq {
+                sql: "drop table test_structured".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-reldb-statement-not-allowed");
+    let resp: TardisResp<Value> = client
+        .put_resp(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select 1; delete from test_structured".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-reldb-invalid-statement");
+    let resp: TardisResp<Value> = client
+        .put_resp(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "with deleted as (delete from test_structured returning id) select * from deleted".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-reldb-statement-not-allowed");
+    let resp: TardisResp<Void> = client
+        .post_resp(
+            "/ci/exec/ddl",
+            &ReldbDdlReq {
+                sql: "insert into test_structured (id,name) values ($1,$2)".to_string(),
+                params: json!([5, "e"]),
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-reldb-statement-not-allowed");
+    let resp: TardisResp<Value> = client
+        .put_resp(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select * from generate_series(1, 20)".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-reldb-too-many-rows");
+    let resp: TardisResp<Value> = client
+        .put_resp(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select pg_sleep(3)".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "-1");
+
+    Ok(())
+}
+
+pub async fn test_tx_forward(client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_tx_forward】");
+
+    let dml_req = ReldbDmlReq {
+        sql: "insert into test_structured (id,name) values ($1,$2)".to_string(),
+        params: json!([90, "forwarded"]),
+    };
+    // the owning node is unreachable, the request is forwarded to it instead of executed on this node
+    let tx_id = sign_tx_id("https://127.0.0.1:1/spi-reldb")?;
+    let resp: TardisResp<ReldbDmlResp> = client.post_resp(&format!("/ci/exec/dml?tx_id={tx_id}"), &dml_req).await;
+    assert_ne!(resp.code, "200");
+    assert!(!resp.msg.contains("tx not exist"));
+    // ids with an invalid signature are not forwarded
+    let (signed, _) = tx_id.rsplit_once('.').unwrap();
+    let resp: TardisResp<ReldbDmlResp> = client.post_resp(&format!("/ci/exec/dml?tx_id={signed}.00"), &dml_req).await;
+    assert!(resp.msg.contains("tx not exist"));
+    // this node is also reachable by localhost, the forwarded request is handled by it instead of forwarded again
+    let tx_id = sign_tx_id("https://localhost:8080/spi-reldb")?;
+    let resp: TardisResp<ReldbDmlResp> = client.post_resp(&format!("/ci/exec/dml?tx_id={tx_id}"), &dml_req).await;
+    assert!(resp.msg.contains("tx not exist"));
+    let resp: TardisResp<Void> = client.put_resp(&format!("/ci/exec/tx?tx_id={tx_id}"), &Void {}).await;
+    assert!(resp.msg.contains("tx not exist"));
+    let dql_resp: Value = client
+        .put(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select * from test_structured where id = $1".to_string(),
+                params: json!([90]),
+            },
+        )
+        .await;
+    assert_eq!(dql_resp, json!([]));
+
+    Ok(())
+}
+
+/// Sign the node into a transaction id with the secret of the test config, like another node of the cluster does
+fn sign_tx_id(node_url: &str) -> TardisResult<String> {
+    let node = TardisFuns::crypto.hex.encode(node_url);
+    let nonce = TardisFuns::crypto.hex.encode(TardisFuns::field.nanoid());
+    let sign = TardisFuns::crypto.hex.encode(TardisFuns::crypto.digest.hmac_sha256(format!("{node}.{nonce}"), "test-secret")?);
+    Ok(format!("{node}.{nonce}.{sign}"))
+}
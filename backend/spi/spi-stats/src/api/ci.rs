@@ -1,3 +1,4 @@
+pub mod stats_ci_alert_api;
 pub mod stats_ci_conf_api;
 pub mod stats_ci_metric_api;
 pub mod stats_ci_record_api;
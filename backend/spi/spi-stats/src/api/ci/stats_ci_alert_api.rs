@@ -0,0 +1,129 @@
+use tardis::chrono::{DateTime, Utc};
+use tardis::web::context_extractor::TardisContextExtractor;
+
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::{Path, Query};
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
+
+use crate::dto::stats_alert_dto::{StatsAlertEvalResp, StatsAlertHistoryResp, StatsConfAlertAddReq, StatsConfAlertInfoResp, StatsConfAlertModifyReq};
+use crate::serv::stats_alert_serv;
+use crate::stats_enumeration::StatsAlertStatusKind;
+
+#[derive(Clone)]
+pub struct StatsCiAlertApi;
+
+/// Interface Console Statistics Alert API
+///
+/// 接口控制台统计告警 API
+#[poem_openapi::OpenApi(prefix_path = "/ci/alert", tag = "bios_basic::ApiTag::Interface")]
+impl StatsCiAlertApi {
+    /// Add Alert Rule
+    ///
+    /// 添加告警规则
+    #[oai(path = "/", method = "put")]
+    async fn add(&self, add_req: Json<StatsConfAlertAddReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_alert_serv::add(&add_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Modify Alert Rule
+    ///
+    /// 修改告警规则
+    #[oai(path = "/:alert_key", method = "patch")]
+    async fn modify(&self, alert_key: Path<String>, modify_req: Json<StatsConfAlertModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_alert_serv::modify(&alert_key.0, &modify_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Delete Alert Rule
+    ///
+    /// 删除告警规则
+    #[oai(path = "/:alert_key", method = "delete")]
+    async fn delete(&self, alert_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_alert_serv::delete(&alert_key.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Find Alert Rules
+    ///
+    /// 查询告警规则
+    #[oai(path = "/", method = "get")]
+    async fn paginate(
+        &self,
+        keys: Query<Option<String>>,
+        show_name: Query<Option<String>>,
+        status: Query<Option<StatsAlertStatusKind>>,
+        is_enabled: Query<Option<bool>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        desc_by_create: Query<Option<bool>>,
+        desc_by_update: Query<Option<bool>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<StatsConfAlertInfoResp>> {
+        let funs = crate::get_tardis_inst();
+        let keys = keys.0.map(|key| key.split(',').map(|r| r.to_string()).collect());
+        let resp = stats_alert_serv::paginate(
+            keys,
+            show_name.0,
+            status.0,
+            is_enabled.0,
+            page_number.0,
+            page_size.0,
+            desc_by_create.0,
+            desc_by_update.0,
+            &funs,
+            &ctx.0,
+        )
+        .await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Evaluate Alert Rule
+    ///
+    /// Called by the schedule middleware according to the evaluation interval of the rule
+    ///
+    /// 评估告警规则
+    ///
+    /// 由调度中间件按规则的评估周期调用
+    #[oai(path = "/:alert_key/eval", method = "put")]
+    async fn evaluate(&self, alert_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<StatsAlertEvalResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = stats_alert_serv::evaluate(&alert_key.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Alert State History
+    ///
+    /// 查询告警状态历史
+    #[oai(path = "/:alert_key/history", method = "get")]
+    async fn paginate_history(
+        &self,
+        alert_key: Path<String>,
+        status: Query<Option<StatsAlertStatusKind>>,
+        start_time: Query<Option<DateTime<Utc>>>,
+        end_time: Query<Option<DateTime<Utc>>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        desc_by_create: Query<Option<bool>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<StatsAlertHistoryResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = stats_alert_serv::paginate_history(
+            &alert_key.0,
+            status.0,
+            start_time.0,
+            end_time.0,
+            page_number.0,
+            page_size.0,
+            desc_by_create.0,
+            &funs,
+            &ctx.0,
+        )
+        .await?;
+        TardisResp::ok(resp)
+    }
+}
@@ -1,16 +1,45 @@
-use bios_basic::process::task_processor::TaskProcessor;
+use bios_basic::{helper::request_helper::tardis_err_to_poem_err, process::task_processor::TaskProcessor};
 use tardis::web::{
     context_extractor::TardisContextExtractor,
-    poem::web::Json,
-    poem_openapi::{self, param::Path},
+    poem::{self, web::Json, Body},
+    poem_openapi::{self, param::Path, payload::Attachment, ApiResponse},
     web_resp::{TardisApiResult, TardisResp, Void},
 };
 
 use crate::{
-    dto::stats_transfer_dto::{StatsExportDataReq, StatsExportDataResp, StatsImportDataReq},
-    serv::stats_transfer_serv,
+    dto::stats_transfer_dto::{StatsExportDataReq, StatsExportDataResp, StatsExportMetricsReq, StatsExportRecordsReq, StatsImportDataReq},
+    serv::{
+        stats_export_serv::{self, StatsExportFile},
+        stats_transfer_serv,
+    },
 };
 
+/// Response of the file export: small files are returned directly, large files are redirected to the presigned url of spi-object
+///
+/// 文件导出的响应：小文件直接返回，大文件重定向到对象存储的预签名地址
+#[derive(ApiResponse)]
+enum StatsExportFileResp {
+    /// File content
+    ///
+    /// 文件内容
+    #[oai(status = 200)]
+    File(Attachment<Body>),
+    /// Presigned url of the file
+    ///
+    /// 文件的预签名地址
+    #[oai(status = 303)]
+    Redirect(#[oai(header = "Location")] String),
+}
+
+impl From<StatsExportFile> for StatsExportFileResp {
+    fn from(file: StatsExportFile) -> Self {
+        match file {
+            StatsExportFile::File { file_name, file } => StatsExportFileResp::File(Attachment::new(Body::from_async_read(file)).filename(file_name)),
+            StatsExportFile::Url(url) => StatsExportFileResp::Redirect(url),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct StatsCiTransferApi;
 
@@ -36,4 +65,24 @@ impl StatsCiTransferApi {
             TardisResp::ok(None)
         }
     }
+
+    /// Export Metrics as CSV/XLSX File
+    ///
+    /// 导出指标为 CSV/XLSX 文件
+    #[oai(path = "/export/metric", method = "put")]
+    async fn export_metrics(&self, export_req: Json<StatsExportMetricsReq>, ctx: TardisContextExtractor) -> poem::Result<StatsExportFileResp> {
+        let funs = crate::get_tardis_inst();
+        let file = stats_export_serv::export_metrics(&export_req.0, &funs, &ctx.0).await.map_err(tardis_err_to_poem_err)?;
+        Ok(file.into())
+    }
+
+    /// Export Fact Records as CSV/XLSX File
+    ///
+    /// 导出事实记录为 CSV/XLSX 文件
+    #[oai(path = "/export/record", method = "put")]
+    async fn export_records(&self, export_req: Json<StatsExportRecordsReq>, ctx: TardisContextExtractor) -> poem::Result<StatsExportFileResp> {
+        let funs = crate::get_tardis_inst();
+        let file = stats_export_serv::export_records(&export_req.0, &funs, &ctx.0).await.map_err(tardis_err_to_poem_err)?;
+        Ok(file.into())
+    }
 }
@@ -1,3 +1,4 @@
+pub mod stats_alert_dto;
 pub mod stats_conf_dto;
 pub mod stats_query_dto;
 pub mod stats_record_dto;
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use tardis::{
+    chrono::{DateTime, Utc},
+    web::poem_openapi,
+};
+
+use crate::{
+    dto::stats_query_dto::StatsQueryMetricsReq,
+    stats_enumeration::{StatsAlertConditionKind, StatsAlertStatusKind},
+};
+
+/// Add Alert Rule Request Object
+///
+/// 添加告警规则请求对象
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfAlertAddReq {
+    /// The primary key or encoding passed in from the external system
+    ///
+    /// 外部系统传入的主键或编码
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub key: String,
+    /// The name of the alert rule
+    ///
+    /// 告警规则的名称
+    #[oai(validator(min_length = "2"))]
+    pub show_name: String,
+    /// Saved metrics query.
+    /// `end_time - start_time` is used as the evaluation window, each evaluation moves the window to end at the evaluation time
+    ///
+    /// 保存的指标查询。
+    /// `end_time - start_time` 作为评估窗口，每次评估时窗口会平移至以评估时间为结束
+    pub query: StatsQueryMetricsReq,
+    /// The watched measure, format: `<measure column key>__<aggregate function>`, e.g. `act_hours__sum`
+    ///
+    /// 监控的度量，格式：`<度量字段编码>__<聚合函数>`，例如 `act_hours__sum`
+    pub measure: String,
+    /// Alert condition
+    ///
+    /// 告警条件
+    pub condition: StatsAlertConditionKind,
+    /// Threshold, when the condition is a percent change, the unit is percent
+    ///
+    /// 阈值，当条件为百分比变化时，单位为百分比
+    pub threshold: f64,
+    /// Evaluation interval, cron expression
+    ///
+    /// 评估周期，cron表达式
+    pub eval_cron: String,
+    /// Notification when the rule fires or resolves
+    ///
+    /// 规则触发或恢复时的通知
+    pub notify: Option<StatsAlertNotifyReq>,
+    /// default value is true
+    pub is_enabled: Option<bool>,
+    pub remark: Option<String>,
+}
+
+/// Modify Alert Rule Request Object
+///
+/// 修改告警规则请求对象
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct StatsConfAlertModifyReq {
+    #[oai(validator(min_length = "2"))]
+    pub show_name: Option<String>,
+    pub query: Option<StatsQueryMetricsReq>,
+    pub measure: Option<String>,
+    pub condition: Option<StatsAlertConditionKind>,
+    pub threshold: Option<f64>,
+    pub eval_cron: Option<String>,
+    pub notify: Option<StatsAlertNotifyReq>,
+    pub is_enabled: Option<bool>,
+    pub remark: Option<String>,
+}
+
+/// Alert Notification Request Object
+///
+/// The message is sent through reach, the replacement variables are
+/// `alert_key`, `alert_name`, `status`, `value`, `threshold` and `eval_time`
+///
+/// 告警通知请求对象
+///
+/// 通过触达服务发送消息，可用的替换变量为
+/// `alert_key`、`alert_name`、`status`、`value`、`threshold`、`eval_time`
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct StatsAlertNotifyReq {
+    /// Reach scene code
+    ///
+    /// 触达场景编码
+    pub scene_code: String,
+    pub receive_group_code: String,
+    pub receive_kind: String,
+    pub receive_ids: Vec<String>,
+}
+
+/// Alert Rule Response Object
+///
+/// 告警规则响应对象
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfAlertInfoResp {
+    pub key: String,
+    pub show_name: String,
+    pub query: StatsQueryMetricsReq,
+    pub measure: String,
+    pub condition: StatsAlertConditionKind,
+    pub threshold: f64,
+    pub eval_cron: String,
+    pub notify: Option<StatsAlertNotifyReq>,
+    pub is_enabled: bool,
+    pub remark: String,
+    /// Status of the last evaluation
+    ///
+    /// 最近一次评估的状态
+    pub status: StatsAlertStatusKind,
+    /// Value of the last evaluation
+    ///
+    /// 最近一次评估的值
+    pub last_value: Option<f64>,
+    pub last_eval_time: Option<DateTime<Utc>>,
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
+/// Alert Evaluation Response Object
+///
+/// 告警评估响应对象
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsAlertEvalResp {
+    pub status: StatsAlertStatusKind,
+    /// Whether the status has changed in this evaluation
+    ///
+    /// 本次评估状态是否发生变化
+    pub changed: bool,
+    /// The evaluated value, for percent change conditions it is the change percent
+    ///
+    /// 评估的值，当条件为百分比变化时为变化百分比
+    pub value: Option<f64>,
+    pub eval_time: DateTime<Utc>,
+}
+
+/// Alert State History Response Object
+///
+/// 告警状态历史响应对象
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsAlertHistoryResp {
+    pub id: String,
+    pub rel_alert_key: String,
+    /// Status after the change
+    ///
+    /// 变化后的状态
+    pub status: StatsAlertStatusKind,
+    pub value: Option<f64>,
+    pub threshold: f64,
+    pub create_time: DateTime<Utc>,
+}
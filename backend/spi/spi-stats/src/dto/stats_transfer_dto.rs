@@ -7,6 +7,11 @@ use tardis::{
     web::poem_openapi,
 };
 
+use crate::{
+    dto::stats_query_dto::{StatsQueryMetricsRecordReq, StatsQueryMetricsReq},
+    stats_enumeration::StatsExportFormatKind,
+};
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct StatsExportDataReq {
     pub start_time: DateTime<Utc>,
@@ -94,3 +99,39 @@ pub struct StatsImportDelAggReq {
     /// Create time
     pub ct: DateTime<Utc>,
 }
+
+/// Export Metrics Request
+///
+/// 导出指标请求
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsExportMetricsReq {
+    /// File format
+    ///
+    /// 文件格式
+    pub format: StatsExportFormatKind,
+    /// File name without extension, default is the fact key
+    ///
+    /// 文件名（不含扩展名），默认为事实编码
+    pub file_name: Option<String>,
+    pub query: StatsQueryMetricsReq,
+}
+
+/// Export Fact Records Request
+///
+/// The `page_number` and `page_size` of the query are ignored, all pages are exported (limited by `export_max_rows` in the config)
+///
+/// 导出事实记录请求
+///
+/// 查询中的 `page_number` 与 `page_size` 会被忽略，导出全部分页（受配置项 `export_max_rows` 限制）
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsExportRecordsReq {
+    /// File format
+    ///
+    /// 文件格式
+    pub format: StatsExportFormatKind,
+    /// File name without extension, default is the fact key
+    ///
+    /// 文件名（不含扩展名），默认为事实编码
+    pub file_name: Option<String>,
+    pub query: StatsQueryMetricsRecordReq,
+}
@@ -1,4 +1,5 @@
 pub mod pg;
+pub mod stats_alert_serv;
 pub mod stats_cert_serv;
 pub mod stats_conf_dim_group_serv;
 pub mod stats_conf_dim_col_serv;
@@ -6,6 +7,7 @@ pub mod stats_conf_dim_serv;
 pub mod stats_conf_fact_col_serv;
 pub mod stats_conf_fact_detail_serv;
 pub mod stats_conf_fact_serv;
+pub mod stats_export_serv;
 pub mod stats_metric_serv;
 pub mod stats_record_serv;
 pub mod stats_schema_serv;
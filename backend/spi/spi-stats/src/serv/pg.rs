@@ -1,3 +1,4 @@
+pub mod stats_pg_alert_serv;
 pub mod stats_pg_conf_dim_group_serv;
 pub mod stats_pg_conf_dim_col_serv;
 pub mod stats_pg_conf_dim_serv;
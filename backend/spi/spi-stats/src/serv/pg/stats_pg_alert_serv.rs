@@ -0,0 +1,618 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common_pg::package_table_name};
+use bios_sdk_invoke::clients::{
+    reach_client::{ReachClient, ReachMsgReceive, ReachMsgSendReq},
+    schedule_client::{AddOrModifySyncTaskReq, ScheduleClient},
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    log::warn,
+    serde_json,
+    web::web_resp::TardisPage,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::{
+        stats_alert_dto::{StatsAlertEvalResp, StatsAlertHistoryResp, StatsAlertNotifyReq, StatsConfAlertAddReq, StatsConfAlertInfoResp, StatsConfAlertModifyReq},
+        stats_query_dto::StatsQueryMetricsReq,
+    },
+    stats_config::StatsConfig,
+    stats_constants::EVAL_ALERT_TASK_CODE,
+    stats_enumeration::StatsAlertStatusKind,
+};
+
+use super::{stats_pg_initializer, stats_pg_metric_serv};
+
+/// The key of the root (rollup) group in the metrics query result
+///
+/// 指标查询结果中根（汇总）分组的键
+const ROLLUP_GROUP_KEY: &str = "ROLLUP";
+
+pub(crate) async fn add(add_req: &StatsConfAlertAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_alert_table_and_conn(bs_inst, ctx, true).await?;
+    check_query(&add_req.query, &add_req.measure, funs)?;
+    conn.begin().await?;
+    if conn.count_by_sql(&format!("SELECT 1 FROM {table_name} WHERE key = $1"), vec![Value::from(&add_req.key)]).await? != 0 {
+        conn.rollback().await?;
+        return Err(funs.err().conflict(
+            "alert_conf",
+            "add",
+            "The alert rule already exists, please delete it and then add it.",
+            "409-spi-stats-alert-conf-exist",
+        ));
+    }
+    let params = vec![
+        Value::from(add_req.key.to_string()),
+        Value::from(add_req.show_name.clone()),
+        Value::from(TardisFuns::json.obj_to_json(&add_req.query)?),
+        Value::from(add_req.measure.clone()),
+        Value::from(add_req.condition.to_string()),
+        Value::from(add_req.threshold),
+        Value::from(add_req.eval_cron.clone()),
+        Value::from(add_req.notify.as_ref().map(|notify| TardisFuns::json.obj_to_json(notify)).transpose()?),
+        Value::from(add_req.is_enabled.unwrap_or(true)),
+        Value::from(add_req.remark.as_ref().unwrap_or(&"".to_string()).as_str()),
+        Value::from(StatsAlertStatusKind::Ok.to_string()),
+    ];
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+(key, show_name, query, measure, condition, threshold, eval_cron, notify, is_enabled, remark, status)
+VALUES
+($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+"#,
+        ),
+        params,
+    )
+    .await?;
+    conn.commit().await?;
+    add_or_modify_eval_task(&add_req.key, add_req.is_enabled.unwrap_or(true), &add_req.eval_cron, funs, ctx).await?;
+    Ok(())
+}
+
+pub(crate) async fn modify(alert_conf_key: &str, modify_req: &StatsConfAlertModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_alert_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    let Some(alert_conf) = get(alert_conf_key, &conn, ctx).await? else {
+        conn.rollback().await?;
+        return Err(funs.err().not_found("alert_conf", "modify", "alert conf not found", "404-spi-stats-alert-conf-not-exist"));
+    };
+    if let Err(e) = check_query(
+        modify_req.query.as_ref().unwrap_or(&alert_conf.query),
+        modify_req.measure.as_ref().unwrap_or(&alert_conf.measure),
+        funs,
+    ) {
+        conn.rollback().await?;
+        return Err(e);
+    }
+    let mut sql_sets = vec![];
+    let mut params = vec![Value::from(alert_conf_key.to_string())];
+    if let Some(show_name) = &modify_req.show_name {
+        sql_sets.push(format!("show_name = ${}", params.len() + 1));
+        params.push(Value::from(show_name.to_string()));
+    }
+    if let Some(query) = &modify_req.query {
+        sql_sets.push(format!("query = ${}", params.len() + 1));
+        params.push(Value::from(TardisFuns::json.obj_to_json(query)?));
+    }
+    if let Some(measure) = &modify_req.measure {
+        sql_sets.push(format!("measure = ${}", params.len() + 1));
+        params.push(Value::from(measure.to_string()));
+    }
+    if let Some(condition) = &modify_req.condition {
+        sql_sets.push(format!("condition = ${}", params.len() + 1));
+        params.push(Value::from(condition.to_string()));
+    }
+    if let Some(threshold) = modify_req.threshold {
+        sql_sets.push(format!("threshold = ${}", params.len() + 1));
+        params.push(Value::from(threshold));
+    }
+    if let Some(eval_cron) = &modify_req.eval_cron {
+        sql_sets.push(format!("eval_cron = ${}", params.len() + 1));
+        params.push(Value::from(eval_cron.to_string()));
+    }
+    if let Some(notify) = &modify_req.notify {
+        sql_sets.push(format!("notify = ${}", params.len() + 1));
+        params.push(Value::from(TardisFuns::json.obj_to_json(notify)?));
+    }
+    if let Some(is_enabled) = modify_req.is_enabled {
+        sql_sets.push(format!("is_enabled = ${}", params.len() + 1));
+        params.push(Value::from(is_enabled));
+    }
+    if let Some(remark) = &modify_req.remark {
+        sql_sets.push(format!("remark = ${}", params.len() + 1));
+        params.push(Value::from(remark.to_string()));
+    }
+    if sql_sets.is_empty() {
+        conn.rollback().await?;
+        return Ok(());
+    }
+    conn.execute_one(
+        &format!(
+            r#"UPDATE {table_name}
+SET {}
+WHERE key = $1
+"#,
+            sql_sets.join(",")
+        ),
+        params,
+    )
+    .await?;
+    conn.commit().await?;
+    add_or_modify_eval_task(
+        alert_conf_key,
+        modify_req.is_enabled.unwrap_or(alert_conf.is_enabled),
+        modify_req.eval_cron.as_ref().unwrap_or(&alert_conf.eval_cron),
+        funs,
+        ctx,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Delete the alert rule.
+///
+/// The state history of the rule is kept for auditing.
+///
+/// 删除告警规则。
+///
+/// 规则的状态历史会保留以便审计。
+pub(crate) async fn delete(alert_conf_key: &str, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = stats_pg_initializer::init_conf_alert_table_and_conn(bs_inst, ctx, true).await?;
+    conn.execute_one(&format!("DELETE FROM {table_name} WHERE key = $1"), vec![Value::from(alert_conf_key)]).await?;
+    ScheduleClient::delete_sync_task(&format!("{}_{}", EVAL_ALERT_TASK_CODE, alert_conf_key), funs, ctx).await?;
+    Ok(())
+}
+
+pub(crate) async fn paginate(
+    alert_conf_keys: Option<Vec<String>>,
+    show_name: Option<String>,
+    status: Option<StatsAlertStatusKind>,
+    is_enabled: Option<bool>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<StatsConfAlertInfoResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = stats_pg_initializer::init_conf_alert_table_and_conn(bs_inst, ctx, true).await?;
+    do_paginate(
+        alert_conf_keys,
+        show_name,
+        status,
+        is_enabled,
+        page_number,
+        page_size,
+        desc_by_create,
+        desc_by_update,
+        &conn,
+        ctx,
+    )
+    .await
+}
+
+async fn get(alert_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<Option<StatsConfAlertInfoResp>> {
+    do_paginate(Some(vec![alert_conf_key.to_string()]), None, None, None, 1, 1, None, None, conn, ctx).await.map(|page| page.records.into_iter().next())
+}
+
+async fn do_paginate(
+    alert_conf_keys: Option<Vec<String>>,
+    show_name: Option<String>,
+    status: Option<StatsAlertStatusKind>,
+    is_enabled: Option<bool>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+) -> TardisResult<TardisPage<StatsConfAlertInfoResp>> {
+    let table_name = package_table_name("stats_conf_alert", ctx);
+    let mut sql_where = vec!["1 = 1".to_string()];
+    let mut sql_order = vec![];
+    let mut params: Vec<Value> = vec![Value::from(page_size), Value::from((page_number - 1) * page_size)];
+    if let Some(alert_conf_keys) = &alert_conf_keys {
+        sql_where.push(format!(
+            "key IN ({})",
+            (0..alert_conf_keys.len()).map(|idx| format!("${}", params.len() + idx + 1)).collect::<Vec<String>>().join(",")
+        ));
+        for alert_conf_key in alert_conf_keys {
+            params.push(Value::from(alert_conf_key.to_string()));
+        }
+    }
+    if let Some(show_name) = &show_name {
+        sql_where.push(format!("show_name LIKE ${}", params.len() + 1));
+        params.push(Value::from(format!("%{show_name}%")));
+    }
+    if let Some(status) = &status {
+        sql_where.push(format!("status = ${}", params.len() + 1));
+        params.push(Value::from(status.to_string()));
+    }
+    if let Some(is_enabled) = is_enabled {
+        sql_where.push(format!("is_enabled = ${}", params.len() + 1));
+        params.push(Value::from(is_enabled));
+    }
+    if let Some(desc_by_create) = desc_by_create {
+        sql_order.push(format!("create_time {}", if desc_by_create { "DESC" } else { "ASC" }));
+    }
+    if let Some(desc_by_update) = desc_by_update {
+        sql_order.push(format!("update_time {}", if desc_by_update { "DESC" } else { "ASC" }));
+    }
+
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT key, show_name, query, measure, condition, threshold, eval_cron, notify, is_enabled, remark, status, last_value, last_eval_time, create_time, update_time, count(*) OVER() AS total
+FROM {table_name}
+WHERE
+    {}
+    {}
+    LIMIT $1 OFFSET $2"#,
+                sql_where.join(" AND "),
+                if sql_order.is_empty() {
+                    "".to_string()
+                } else {
+                    format!("ORDER BY {}", sql_order.join(","))
+                }
+            ),
+            params,
+        )
+        .await?;
+
+    let mut total_size: i64 = 0;
+    let mut final_result = vec![];
+    for item in result {
+        if total_size == 0 {
+            total_size = item.try_get("", "total")?;
+        }
+        let notify: Option<serde_json::Value> = item.try_get("", "notify")?;
+        final_result.push(StatsConfAlertInfoResp {
+            key: item.try_get("", "key")?,
+            show_name: item.try_get("", "show_name")?,
+            query: TardisFuns::json.json_to_obj(item.try_get::<serde_json::Value>("", "query")?)?,
+            measure: item.try_get("", "measure")?,
+            condition: item.try_get("", "condition")?,
+            threshold: item.try_get("", "threshold")?,
+            eval_cron: item.try_get("", "eval_cron")?,
+            notify: notify.filter(|notify| !notify.is_null()).map(|notify| TardisFuns::json.json_to_obj(notify)).transpose()?,
+            is_enabled: item.try_get("", "is_enabled")?,
+            remark: item.try_get("", "remark")?,
+            status: item.try_get("", "status")?,
+            last_value: item.try_get("", "last_value")?,
+            last_eval_time: item.try_get("", "last_eval_time")?,
+            create_time: item.try_get("", "create_time")?,
+            update_time: item.try_get("", "update_time")?,
+        });
+    }
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size: total_size as u64,
+        records: final_result,
+    })
+}
+
+/// Evaluate the alert rule.
+///
+/// The saved query is moved to end at the evaluation time and executed, the watched measure is read from the root (rollup) group.
+/// When the status changes, the change is recorded in the history and a notification is sent through reach.
+/// If no value is returned, the status is kept unchanged.
+/// The result is only saved if the status is still the one read before the evaluation,
+/// so that of concurrent evaluations only one records and notifies the change.
+///
+/// 评估告警规则。
+///
+/// 保存的查询会平移至以评估时间为结束后执行，监控的度量从根（汇总）分组读取。
+/// 当状态发生变化时，记录到历史并通过触达服务发送通知。
+/// 如果没有返回值，则保持状态不变。
+/// 仅当状态仍为评估前读取的状态时才保存结果，
+/// 因此并发评估中只有一个会记录并通知状态变化。
+pub(crate) async fn evaluate(alert_conf_key: &str, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<StatsAlertEvalResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_alert_table_and_conn(bs_inst, ctx, true).await?;
+    let (_, history_table_name) = stats_pg_initializer::init_alert_history_table_and_conn(bs_inst, ctx, true).await?;
+    let Some(alert_conf) = get(alert_conf_key, &conn, ctx).await? else {
+        return Err(funs.err().not_found("alert_conf", "evaluate", "alert conf not found", "404-spi-stats-alert-conf-not-exist"));
+    };
+    let eval_time = Utc::now();
+    if !alert_conf.is_enabled {
+        return Ok(StatsAlertEvalResp {
+            status: alert_conf.status,
+            changed: false,
+            value: None,
+            eval_time,
+        });
+    }
+    let curr_value = query_measure_value(&alert_conf, eval_time, funs, ctx, inst).await?;
+    let value = if alert_conf.condition.is_change_percent() {
+        let prev_eval_time = eval_time - (alert_conf.query.end_time - alert_conf.query.start_time);
+        let prev_value = query_measure_value(&alert_conf, prev_eval_time, funs, ctx, inst).await?;
+        match (curr_value, prev_value) {
+            (Some(curr_value), Some(prev_value)) => change_percent(curr_value, prev_value),
+            _ => None,
+        }
+    } else {
+        curr_value
+    };
+    let status = match value {
+        Some(value) if alert_conf.condition.is_fired(value, alert_conf.threshold) => StatsAlertStatusKind::Firing,
+        Some(_) => StatsAlertStatusKind::Ok,
+        None => alert_conf.status.clone(),
+    };
+    let changed = status != alert_conf.status;
+
+    conn.begin().await?;
+    let saved = conn
+        .execute_one(
+            &format!("UPDATE {table_name} SET status = $2, last_value = $3, last_eval_time = $4 WHERE key = $1 AND status = $5"),
+            vec![
+                Value::from(alert_conf_key),
+                Value::from(status.to_string()),
+                Value::from(value),
+                Value::from(eval_time),
+                Value::from(alert_conf.status.to_string()),
+            ],
+        )
+        .await?
+        .rows_affected()
+        == 1;
+    if !saved {
+        // The status has been changed by a concurrent evaluation, which has already recorded and notified it
+        let status = get(alert_conf_key, &conn, ctx).await?.map(|alert_conf| alert_conf.status).unwrap_or(status);
+        conn.rollback().await?;
+        return Ok(StatsAlertEvalResp {
+            status,
+            changed: false,
+            value,
+            eval_time,
+        });
+    }
+    if changed {
+        conn.execute_one(
+            &format!("INSERT INTO {history_table_name} (id, rel_alert_key, status, value, threshold) VALUES ($1, $2, $3, $4, $5)"),
+            vec![
+                Value::from(TardisFuns::field.nanoid()),
+                Value::from(alert_conf_key),
+                Value::from(status.to_string()),
+                Value::from(value),
+                Value::from(alert_conf.threshold),
+            ],
+        )
+        .await?;
+    }
+    conn.commit().await?;
+
+    if changed {
+        if let Some(notify) = &alert_conf.notify {
+            // Notification failure should not roll back the evaluation result
+            if let Err(e) = send_notify(&alert_conf, notify, &status, value, eval_time, funs, ctx).await {
+                warn!("[BIOS.Stats] Fail to send alert [{}] notification: {e:?}", alert_conf.key);
+            }
+        }
+    }
+    Ok(StatsAlertEvalResp {
+        status,
+        changed,
+        value,
+        eval_time,
+    })
+}
+
+pub(crate) async fn paginate_history(
+    alert_conf_key: &str,
+    status: Option<StatsAlertStatusKind>,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<StatsAlertHistoryResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = stats_pg_initializer::init_alert_history_table_and_conn(bs_inst, ctx, true).await?;
+    let mut sql_where = vec!["rel_alert_key = $3".to_string()];
+    let mut params: Vec<Value> = vec![Value::from(page_size), Value::from((page_number - 1) * page_size), Value::from(alert_conf_key)];
+    if let Some(status) = &status {
+        sql_where.push(format!("status = ${}", params.len() + 1));
+        params.push(Value::from(status.to_string()));
+    }
+    if let Some(start_time) = start_time {
+        sql_where.push(format!("create_time >= ${}", params.len() + 1));
+        params.push(Value::from(start_time));
+    }
+    if let Some(end_time) = end_time {
+        sql_where.push(format!("create_time <= ${}", params.len() + 1));
+        params.push(Value::from(end_time));
+    }
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT id, rel_alert_key, status, value, threshold, create_time, count(*) OVER() AS total
+FROM {table_name}
+WHERE
+    {}
+    ORDER BY create_time {}
+    LIMIT $1 OFFSET $2"#,
+                sql_where.join(" AND "),
+                if desc_by_create.unwrap_or(true) { "DESC" } else { "ASC" }
+            ),
+            params,
+        )
+        .await?;
+
+    let mut total_size: i64 = 0;
+    let mut final_result = vec![];
+    for item in result {
+        if total_size == 0 {
+            total_size = item.try_get("", "total")?;
+        }
+        final_result.push(StatsAlertHistoryResp {
+            id: item.try_get("", "id")?,
+            rel_alert_key: item.try_get("", "rel_alert_key")?,
+            status: item.try_get("", "status")?,
+            value: item.try_get("", "value")?,
+            threshold: item.try_get("", "threshold")?,
+            create_time: item.try_get("", "create_time")?,
+        });
+    }
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size: total_size as u64,
+        records: final_result,
+    })
+}
+
+fn check_query(query: &StatsQueryMetricsReq, measure: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    if query.end_time <= query.start_time {
+        return Err(funs.err().bad_request("alert_conf", "check", "The end_time must be greater than start_time.", "400-spi-stats-alert-window-invalid"));
+    }
+    if !query.select.iter().any(|select| format!("{}__{}", select.code, select.fun.to_string().to_lowercase()) == measure) {
+        return Err(funs.err().bad_request(
+            "alert_conf",
+            "check",
+            &format!("The measure {measure} must exist in the select of query."),
+            "400-spi-stats-alert-measure-not-exist",
+        ));
+    }
+    Ok(())
+}
+
+async fn query_measure_value(
+    alert_conf: &StatsConfAlertInfoResp,
+    eval_time: DateTime<Utc>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Option<f64>> {
+    let mut query: StatsQueryMetricsReq = TardisFuns::json.json_to_obj(TardisFuns::json.obj_to_json(&alert_conf.query)?)?;
+    let window = query.end_time - query.start_time;
+    query.end_time = eval_time;
+    query.start_time = eval_time - window;
+    let resp = stats_pg_metric_serv::query_metrics(&query, funs, ctx, inst).await?;
+    Ok(extract_measure_value(&resp.group, &alert_conf.measure))
+}
+
+/// Read the measure value from the root group, descending through the rollup groups of the dimensions.
+///
+/// 从根分组读取度量值，逐层进入各维度的汇总分组。
+fn extract_measure_value(group: &serde_json::Value, measure: &str) -> Option<f64> {
+    let group = group.as_object()?;
+    if let Some(value) = group.get(measure) {
+        return match value {
+            serde_json::Value::Number(value) => value.as_f64(),
+            serde_json::Value::String(value) => value.parse::<f64>().ok(),
+            _ => None,
+        };
+    }
+    extract_measure_value(group.get(ROLLUP_GROUP_KEY)?, measure)
+}
+
+fn change_percent(curr_value: f64, prev_value: f64) -> Option<f64> {
+    if prev_value == 0.0 {
+        return None;
+    }
+    Some((curr_value - prev_value) / prev_value.abs() * 100.0)
+}
+
+async fn add_or_modify_eval_task(alert_conf_key: &str, enable: bool, eval_cron: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let mut callback_headers: HashMap<String, String> = HashMap::new();
+    callback_headers.insert("Tardis-Context".to_string(), TardisFuns::crypto.base64.encode(TardisFuns::json.obj_to_string(&ctx)?));
+    ScheduleClient::add_or_modify_sync_task(
+        AddOrModifySyncTaskReq {
+            code: format!("{}_{}", EVAL_ALERT_TASK_CODE, alert_conf_key),
+            enable,
+            cron: eval_cron.to_string(),
+            callback_url: format!("{}/ci/alert/{}/eval", funs.conf::<StatsConfig>().base_url, alert_conf_key),
+            callback_method: "PUT".to_string(),
+            callback_body: None,
+            callback_headers,
+        },
+        funs,
+        ctx,
+    )
+    .await
+}
+
+async fn send_notify(
+    alert_conf: &StatsConfAlertInfoResp,
+    notify: &StatsAlertNotifyReq,
+    status: &StatsAlertStatusKind,
+    value: Option<f64>,
+    eval_time: DateTime<Utc>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<()> {
+    let replace = HashMap::from([
+        ("alert_key".to_string(), alert_conf.key.clone()),
+        ("alert_name".to_string(), alert_conf.show_name.clone()),
+        ("status".to_string(), status.to_string()),
+        ("value".to_string(), value.map(|value| value.to_string()).unwrap_or_default()),
+        ("threshold".to_string(), alert_conf.threshold.to_string()),
+        ("eval_time".to_string(), eval_time.to_rfc3339()),
+    ]);
+    ReachClient::send_message(
+        &ReachMsgSendReq {
+            scene_code: notify.scene_code.clone(),
+            receives: vec![ReachMsgReceive {
+                receive_group_code: notify.receive_group_code.clone(),
+                receive_kind: notify.receive_kind.clone(),
+                receive_ids: notify.receive_ids.clone(),
+            }],
+            rel_item_id: alert_conf.key.clone(),
+            replace,
+        },
+        funs,
+        ctx,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use tardis::serde_json::json;
+
+    use super::{change_percent, extract_measure_value};
+    use crate::stats_enumeration::StatsAlertConditionKind;
+
+    #[test]
+    fn test_extract_measure_value() {
+        assert_eq!(extract_measure_value(&json!({"act_hours__sum": 180}), "act_hours__sum"), Some(180.0));
+        assert_eq!(extract_measure_value(&json!({"act_hours__avg": "12.5"}), "act_hours__avg"), Some(12.5));
+        assert_eq!(
+            extract_measure_value(
+                &json!({"ROLLUP": {"ROLLUP": {"act_hours__sum": 180}}, "2023-01-01": {"ROLLUP": {"act_hours__sum": 120}}}),
+                "act_hours__sum"
+            ),
+            Some(180.0)
+        );
+        assert_eq!(extract_measure_value(&json!({"2023-01-01": {"act_hours__sum": 120}}), "act_hours__sum"), None);
+    }
+
+    #[test]
+    fn test_condition() {
+        assert_eq!(change_percent(150.0, 100.0), Some(50.0));
+        assert_eq!(change_percent(50.0, 100.0), Some(-50.0));
+        assert_eq!(change_percent(50.0, 0.0), None);
+        assert!(StatsAlertConditionKind::Above.is_fired(11.0, 10.0));
+        assert!(!StatsAlertConditionKind::Above.is_fired(10.0, 10.0));
+        assert!(StatsAlertConditionKind::Below.is_fired(9.0, 10.0));
+        assert!(StatsAlertConditionKind::ChangePercentAbove.is_fired(50.0, 20.0));
+        assert!(StatsAlertConditionKind::ChangePercentBelow.is_fired(-50.0, -20.0));
+    }
+}
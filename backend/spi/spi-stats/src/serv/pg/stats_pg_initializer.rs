@@ -182,3 +182,55 @@ pub async fn init_conf_fact_detail_table_and_conn(
     )
     .await
 }
+
+pub async fn init_conf_alert_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "stats_conf_alert",
+        r#"key character varying NOT NULL,
+    show_name character varying NOT NULL,
+    query jsonb NOT NULL,
+    measure character varying NOT NULL,
+    condition character varying NOT NULL,
+    threshold double precision NOT NULL,
+    eval_cron character varying NOT NULL,
+    notify jsonb,
+    is_enabled boolean NOT NULL DEFAULT TRUE,
+    remark character varying NOT NULL,
+    status character varying NOT NULL,
+    last_value double precision,
+    last_eval_time timestamp with time zone,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    unique (key)"#,
+        None,
+        vec![],
+        None,
+        Some("update_time"),
+    )
+    .await
+}
+
+pub async fn init_alert_history_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "stats_alert_history",
+        r#"id character varying NOT NULL PRIMARY KEY,
+    rel_alert_key character varying NOT NULL,
+    status character varying NOT NULL,
+    value double precision,
+    threshold double precision NOT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        None,
+        vec![("rel_alert_key", "btree"), ("create_time", "btree")],
+        None,
+        None,
+    )
+    .await
+}
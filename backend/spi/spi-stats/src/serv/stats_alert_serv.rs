@@ -0,0 +1,46 @@
+use crate::dto::stats_alert_dto::{StatsAlertEvalResp, StatsAlertHistoryResp, StatsConfAlertAddReq, StatsConfAlertInfoResp, StatsConfAlertModifyReq};
+use crate::stats_enumeration::StatsAlertStatusKind;
+use crate::stats_initializer;
+
+use bios_basic::spi::spi_constants;
+use bios_basic::spi::spi_funs::SpiBsInstExtractor;
+use bios_basic::spi_dispatch_service;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::{DateTime, Utc};
+use tardis::web::web_resp::TardisPage;
+
+use super::pg;
+
+spi_dispatch_service! {
+    @mgr: true,
+    @init: stats_initializer::init_fun,
+    @dispatch: {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_alert_serv,
+    },
+    @method: {
+        add(add_req: &StatsConfAlertAddReq) -> TardisResult<()>;
+        modify(alert_conf_key: &str, modify_req: &StatsConfAlertModifyReq) -> TardisResult<()>;
+        delete(alert_conf_key: &str) -> TardisResult<()>;
+        paginate(
+            alert_conf_keys: Option<Vec<String>>,
+            show_name: Option<String>,
+            status: Option<StatsAlertStatusKind>,
+            is_enabled: Option<bool>,
+            page_number: u32,
+            page_size: u32,
+            desc_by_create: Option<bool>,
+            desc_by_update: Option<bool>
+        ) -> TardisResult<TardisPage<StatsConfAlertInfoResp>>;
+        evaluate(alert_conf_key: &str) -> TardisResult<StatsAlertEvalResp>;
+        paginate_history(
+            alert_conf_key: &str,
+            status: Option<StatsAlertStatusKind>,
+            start_time: Option<DateTime<Utc>>,
+            end_time: Option<DateTime<Utc>>,
+            page_number: u32,
+            page_size: u32,
+            desc_by_create: Option<bool>
+        ) -> TardisResult<TardisPage<StatsAlertHistoryResp>>;
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use bios_sdk_invoke::clients::spi_object_client::SpiObjectClient;
+use itertools::Itertools;
+use rust_xlsxwriter::Workbook;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
+use tardis::basic::result::TardisResult;
+use tardis::web::reqwest;
+use tardis::{serde_json, tokio, TardisFuns, TardisFunsInst};
+
+use crate::dto::stats_query_dto::{StatsQueryDimensionGroupReq, StatsQueryMetricsRecordReq, StatsQueryMetricsReq, StatsQueryMetricsResp, StatsQueryMetricsSelectReq};
+use crate::dto::stats_transfer_dto::{StatsExportMetricsReq, StatsExportRecordsReq};
+use crate::stats_config::StatsConfig;
+use crate::stats_enumeration::StatsExportFormatKind;
+
+use super::{stats_conf_fact_col_serv, stats_metric_serv};
+
+/// The alias suffix flag of dimension and measure columns, format: `<column key>__<function>`
+///
+/// 维度与度量列别名的后缀标识，格式：`<字段编码>__<函数>`
+const FUNCTION_SUFFIX_FLAG: &str = "__";
+/// The key of the rollup group in the metrics query result
+///
+/// 指标查询结果中汇总分组的键
+const ROLLUP_GROUP_KEY: &str = "ROLLUP";
+/// The key column of the fact record
+///
+/// 事实记录的主键列
+const RECORD_KEY_COLUMN: &str = "_key";
+
+/// Exported file
+///
+/// 导出的文件
+pub enum StatsExportFile {
+    /// Small export, streamed back directly
+    ///
+    /// 小文件，直接流式返回
+    File { file_name: String, file: tokio::fs::File },
+    /// Large export, written to spi-object, the value is the presigned url
+    ///
+    /// 大文件，写入对象存储，值为预签名地址
+    Url(String),
+}
+
+/// Tabular data to be exported
+///
+/// 待导出的表格数据
+struct StatsExportTable {
+    headers: Vec<String>,
+    rows: Vec<Vec<serde_json::Value>>,
+}
+
+/// Incremental writer of the exported file, rows are written to a temporary file as soon as they are read
+///
+/// 导出文件的增量写入器，读取到的行立即写入临时文件
+enum StatsExportWriter {
+    Csv(csv::Writer<BufWriter<File>>),
+    /// The worksheet is kept in constant memory mode, written rows are flushed to disk
+    ///
+    /// 工作表使用常量内存模式，已写入的行会刷到磁盘
+    Xlsx {
+        workbook: Workbook,
+        row_idx: u32,
+    },
+}
+
+/// Temporary file of an export, removed when dropped
+///
+/// 导出的临时文件，释放时删除
+struct StatsExportTempFile {
+    path: PathBuf,
+}
+
+/// Export the result of a metrics query.
+///
+/// Each leaf of the result group becomes a row, the dimension values come first, followed by the measures in the select order.
+///
+/// 导出指标查询的结果。
+///
+/// 结果分组的每个叶子节点为一行，先是维度值，然后按查询字段的顺序输出度量。
+pub async fn export_metrics(export_req: &StatsExportMetricsReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<StatsExportFile> {
+    let resp = stats_metric_serv::query_metrics(&export_req.query, funs, ctx).await?;
+    let table = metrics_to_table(&export_req.query, &resp);
+    let temp_file = StatsExportTempFile::new(&export_req.format);
+    let mut writer = StatsExportWriter::new(&export_req.format, &table.headers, &temp_file.path)?;
+    for row in &table.rows {
+        writer.write_row(row)?;
+    }
+    writer.finish(&temp_file.path)?;
+    package_file(
+        temp_file,
+        table.rows.len(),
+        &export_req.format,
+        export_req.file_name.as_deref().unwrap_or(&export_req.query.from),
+        funs,
+        ctx,
+    )
+    .await
+}
+
+/// Export the fact records, all pages are read until `export_max_rows` is reached.
+/// Each page is written to a temporary file before the next one is read, so only one page is kept in memory,
+/// the finished file is then streamed to the caller or to spi-object.
+///
+/// 导出事实记录，读取全部分页直到达到 `export_max_rows`。
+/// 每页写入临时文件后再读取下一页，内存中只保留一页记录，完成的文件再流式返回给调用方或上传到对象存储。
+pub async fn export_records(export_req: &StatsExportRecordsReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<StatsExportFile> {
+    let conf = funs.conf::<StatsConfig>();
+    let show_names = stats_conf_fact_col_serv::find_by_fact_conf_key(&export_req.query.from, funs, ctx)
+        .await?
+        .into_iter()
+        .map(|col| (col.key, col.show_name))
+        .collect::<HashMap<String, String>>();
+    let columns = record_columns(&export_req.query.group, &export_req.query.select);
+    let headers = columns
+        .iter()
+        .map(|column| {
+            let code = column.split(FUNCTION_SUFFIX_FLAG).next().unwrap_or(column);
+            show_names.get(code).cloned().unwrap_or_else(|| column.to_string())
+        })
+        .collect_vec();
+
+    let mut query: StatsQueryMetricsRecordReq = TardisFuns::json.json_to_obj(TardisFuns::json.obj_to_json(&export_req.query)?)?;
+    query.page_size = conf.export_page_size;
+    query.page_number = 1;
+    let temp_file = StatsExportTempFile::new(&export_req.format);
+    let mut writer = StatsExportWriter::new(&export_req.format, &headers, &temp_file.path)?;
+    let mut row_count = 0;
+    loop {
+        let page = stats_metric_serv::query_metrics_record_paginated(&query, funs, ctx).await?;
+        let fetched = page.records.len() as u64;
+        row_count += page.records.len();
+        if row_count > conf.export_max_rows {
+            return Err(funs.err().bad_request(
+                "export",
+                "export_records",
+                &format!("The number of exported rows exceeds the limit {}, please narrow the query.", conf.export_max_rows),
+                "400-spi-stats-export-too-many-rows",
+            ));
+        }
+        for record in &page.records {
+            writer.write_row(&columns.iter().map(|column| record.get(column).cloned().unwrap_or(serde_json::Value::Null)).collect_vec())?;
+        }
+        if fetched < query.page_size || query.page_number * query.page_size >= page.total_size {
+            break;
+        }
+        query.page_number += 1;
+    }
+    writer.finish(&temp_file.path)?;
+    package_file(
+        temp_file,
+        row_count,
+        &export_req.format,
+        export_req.file_name.as_deref().unwrap_or(&export_req.query.from),
+        funs,
+        ctx,
+    )
+    .await
+}
+
+fn dimension_alias(group: &StatsQueryDimensionGroupReq) -> String {
+    format!(
+        "{}{FUNCTION_SUFFIX_FLAG}{}",
+        group.code,
+        group.time_window.as_ref().map(|time_window| time_window.to_string().to_lowercase()).unwrap_or_default()
+    )
+}
+
+fn measure_alias(select: &StatsQueryMetricsSelectReq) -> String {
+    format!("{}{FUNCTION_SUFFIX_FLAG}{}", select.code, select.fun.to_string().to_lowercase())
+}
+
+fn record_columns(group: &[StatsQueryDimensionGroupReq], select: &[StatsQueryMetricsSelectReq]) -> Vec<String> {
+    vec![RECORD_KEY_COLUMN.to_string()].into_iter().chain(group.iter().map(dimension_alias)).chain(select.iter().map(measure_alias)).collect_vec()
+}
+
+fn metrics_to_table(query: &StatsQueryMetricsReq, resp: &StatsQueryMetricsResp) -> StatsExportTable {
+    let dimension_keys = query.group.iter().map(dimension_alias).collect_vec();
+    let measure_keys = query.select.iter().map(measure_alias).collect_vec();
+    let headers = dimension_keys.iter().chain(measure_keys.iter()).map(|key| resp.show_names.get(key).cloned().unwrap_or_else(|| key.to_string())).collect_vec();
+    let mut rows = vec![];
+    flatten_metrics_group(&resp.group, dimension_keys.len(), &mut vec![], &measure_keys, &mut rows);
+    StatsExportTable { headers, rows }
+}
+
+fn flatten_metrics_group(node: &serde_json::Value, depth: usize, dimension_values: &mut Vec<serde_json::Value>, measure_keys: &[String], rows: &mut Vec<Vec<serde_json::Value>>) {
+    if depth == 0 {
+        let mut row = dimension_values.clone();
+        row.extend(measure_keys.iter().map(|key| node.get(key).cloned().unwrap_or(serde_json::Value::Null)));
+        rows.push(row);
+        return;
+    }
+    if let Some(node) = node.as_object() {
+        // Sort the dimension values so that the output does not depend on the key order of the result,
+        // the rollup group is placed after the values it summarizes
+        for (key, sub_node) in node.iter().sorted_by(|(a, _), (b, _)| (*a == ROLLUP_GROUP_KEY).cmp(&(*b == ROLLUP_GROUP_KEY)).then_with(|| a.cmp(b))) {
+            // The rollup group is the subtotal of the dimension, its dimension value is left empty
+            dimension_values.push(if key == ROLLUP_GROUP_KEY {
+                serde_json::Value::Null
+            } else {
+                serde_json::Value::String(key.to_string())
+            });
+            flatten_metrics_group(sub_node, depth - 1, dimension_values, measure_keys, rows);
+            dimension_values.pop();
+        }
+    }
+}
+
+async fn package_file(
+    temp_file: StatsExportTempFile,
+    row_count: usize,
+    format: &StatsExportFormatKind,
+    file_name: &str,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<StatsExportFile> {
+    let file_name = format!("{}.{}", sanitize_file_name(file_name), format.extension());
+    let file = tokio::fs::File::open(&temp_file.path).await.map_err(io_err)?;
+    // The opened file stays readable after its path is removed
+    drop(temp_file);
+    if row_count <= funs.conf::<StatsConfig>().export_inline_max_rows {
+        return Ok(StatsExportFile::File { file_name, file });
+    }
+    let url = upload_to_object(&file_name, format, file, funs, ctx).await?;
+    Ok(StatsExportFile::Url(url))
+}
+
+/// Keep only the last path segment of the requested file name, so that it can not escape the export directory of spi-object
+///
+/// 只保留请求文件名的最后一段路径，避免其跳出对象存储中的导出目录
+fn sanitize_file_name(file_name: &str) -> String {
+    let file_name = file_name.rsplit(['/', '\\']).next().unwrap_or_default().chars().filter(|c| !c.is_control()).collect::<String>();
+    let file_name = file_name.trim_start_matches('.').trim();
+    if file_name.is_empty() {
+        "export".to_string()
+    } else {
+        file_name.to_string()
+    }
+}
+
+fn cell_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "".to_string(),
+        serde_json::Value::String(value) => value.to_string(),
+        value => value.to_string(),
+    }
+}
+
+fn csv_err(e: impl std::fmt::Display) -> TardisError {
+    TardisError::internal_error(&format!("Fail to write csv: {e}"), "500-spi-stats-export-csv-error")
+}
+
+fn xlsx_err(e: rust_xlsxwriter::XlsxError) -> TardisError {
+    TardisError::internal_error(&format!("Fail to write xlsx: {e}"), "500-spi-stats-export-xlsx-error")
+}
+
+fn io_err(e: std::io::Error) -> TardisError {
+    TardisError::internal_error(&format!("Fail to access the export file: {e}"), "500-spi-stats-export-file-error")
+}
+
+impl StatsExportTempFile {
+    fn new(format: &StatsExportFormatKind) -> Self {
+        StatsExportTempFile {
+            path: env::temp_dir().join(format!("bios-stats-export-{}.{}", TardisFuns::field.nanoid(), format.extension())),
+        }
+    }
+}
+
+impl Drop for StatsExportTempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl StatsExportWriter {
+    fn new(format: &StatsExportFormatKind, headers: &[String], path: &Path) -> TardisResult<Self> {
+        match format {
+            StatsExportFormatKind::Csv => {
+                let mut file = BufWriter::new(File::create(path).map_err(io_err)?);
+                // Add BOM so that Excel recognizes the UTF-8 encoding
+                file.write_all(&[0xEF, 0xBB, 0xBF]).map_err(io_err)?;
+                let mut writer = csv::Writer::from_writer(file);
+                writer.write_record(headers).map_err(csv_err)?;
+                Ok(StatsExportWriter::Csv(writer))
+            }
+            StatsExportFormatKind::Xlsx => {
+                let mut workbook = Workbook::new();
+                let worksheet = workbook.add_worksheet_with_constant_memory();
+                for (col_idx, header) in headers.iter().enumerate() {
+                    worksheet.write_string(0, col_idx as u16, header).map_err(xlsx_err)?;
+                }
+                Ok(StatsExportWriter::Xlsx { workbook, row_idx: 1 })
+            }
+        }
+    }
+
+    fn write_row(&mut self, row: &[serde_json::Value]) -> TardisResult<()> {
+        match self {
+            StatsExportWriter::Csv(writer) => writer.write_record(row.iter().map(cell_to_string)).map_err(csv_err),
+            StatsExportWriter::Xlsx { workbook, row_idx } => {
+                let worksheet = workbook.worksheet_from_index(0).map_err(xlsx_err)?;
+                for (col_idx, cell) in row.iter().enumerate() {
+                    let col_idx = col_idx as u16;
+                    match cell {
+                        serde_json::Value::Null => {}
+                        serde_json::Value::Number(number) => {
+                            worksheet.write_number(*row_idx, col_idx, number.as_f64().unwrap_or_default()).map_err(xlsx_err)?;
+                        }
+                        // `avg` measures are returned as strings
+                        serde_json::Value::String(value) if value.parse::<f64>().is_ok() => {
+                            worksheet.write_number(*row_idx, col_idx, value.parse::<f64>().unwrap_or_default()).map_err(xlsx_err)?;
+                        }
+                        serde_json::Value::Bool(value) => {
+                            worksheet.write_boolean(*row_idx, col_idx, *value).map_err(xlsx_err)?;
+                        }
+                        cell => {
+                            worksheet.write_string(*row_idx, col_idx, cell_to_string(cell)).map_err(xlsx_err)?;
+                        }
+                    }
+                }
+                *row_idx += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(self, path: &Path) -> TardisResult<()> {
+        match self {
+            StatsExportWriter::Csv(writer) => writer.into_inner().map_err(csv_err)?.flush().map_err(io_err),
+            StatsExportWriter::Xlsx { mut workbook, .. } => workbook.save(path).map_err(xlsx_err),
+        }
+    }
+}
+
+async fn upload_to_object(file_name: &str, format: &StatsExportFormatKind, file: tokio::fs::File, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+    let exp_secs = funs.conf::<StatsConfig>().export_url_exp_secs;
+    let object_path = format!("spi-stats/export/{}/{file_name}", TardisFuns::field.nanoid());
+    // Exported files are stored in the private temporary bucket
+    let put_url = SpiObjectClient::presign_put_obj_url(&object_path, exp_secs, Some(true), None, Some(1), None, None, funs, ctx)
+        .await?
+        .ok_or_else(|| funs.err().internal_error("export", "upload", "Fail to get presigned put url.", "500-spi-stats-export-upload-error"))?;
+    let size = file.metadata().await.map_err(io_err)?.len();
+    let resp = funs
+        .web_client()
+        .raw()
+        .put(put_url)
+        .header("Content-Type", format.content_type())
+        // The presigned url does not accept chunked uploads
+        .header("Content-Length", size)
+        .body(reqwest::Body::from(file))
+        .send()
+        .await
+        .map_err(|e| funs.err().internal_error("export", "upload", &format!("Fail to upload exported file: {e}"), "500-spi-stats-export-upload-error"))?;
+    if !resp.status().is_success() {
+        return Err(funs.err().internal_error(
+            "export",
+            "upload",
+            &format!("Fail to upload exported file, status: {}", resp.status()),
+            "500-spi-stats-export-upload-error",
+        ));
+    }
+    SpiObjectClient::presign_view_obj_url(&object_path, exp_secs, Some(true), None, Some(1), None, None, funs, ctx)
+        .await?
+        .ok_or_else(|| funs.err().internal_error("export", "upload", "Fail to get presigned view url.", "500-spi-stats-export-upload-error"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tardis::{
+        chrono::Utc,
+        serde_json::{self, json},
+    };
+
+    use super::{metrics_to_table, sanitize_file_name, StatsExportTempFile, StatsExportWriter};
+    use crate::dto::stats_query_dto::{StatsQueryDimensionGroupReq, StatsQueryMetricsReq, StatsQueryMetricsResp, StatsQueryMetricsSelectReq};
+    use crate::stats_enumeration::{StatsExportFormatKind, StatsQueryAggFunKind, StatsQueryTimeWindowKind};
+
+    #[test]
+    fn test_metrics_to_csv() {
+        let query = StatsQueryMetricsReq {
+            from: "req".to_string(),
+            rel_external_id: None,
+            select: vec![StatsQueryMetricsSelectReq {
+                rel_external_id: None,
+                code: "act_hours".to_string(),
+                fun: StatsQueryAggFunKind::Sum,
+            }],
+            ignore_distinct: None,
+            group: vec![
+                StatsQueryDimensionGroupReq {
+                    rel_external_id: None,
+                    code: "ct".to_string(),
+                    time_window: Some(StatsQueryTimeWindowKind::Date),
+                },
+                StatsQueryDimensionGroupReq {
+                    rel_external_id: None,
+                    code: "status".to_string(),
+                    time_window: None,
+                },
+            ],
+            own_paths: None,
+            ignore_group_rollup: None,
+            _where: None,
+            dimension_order: None,
+            metrics_order: None,
+            group_order: None,
+            group_agg: None,
+            having: None,
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+            limit: None,
+        };
+        let resp = StatsQueryMetricsResp {
+            from: "req".to_string(),
+            show_names: HashMap::from([
+                ("ct__date".to_string(), "创建时间".to_string()),
+                ("status__".to_string(), "状态".to_string()),
+                ("act_hours__sum".to_string(), "实例工时".to_string()),
+            ]),
+            group: json!({
+                "2023-01-02": {
+                    "ROLLUP": {"act_hours__sum": 10},
+                    "open": {"act_hours__sum": 10}
+                },
+                "2023-01-01": {
+                    "open": {"act_hours__sum": 80},
+                    "closed": {"act_hours__sum": 40},
+                    "ROLLUP": {"act_hours__sum": 120}
+                }
+            }),
+        };
+        let table = metrics_to_table(&query, &resp);
+        assert_eq!(table.headers, vec!["创建时间", "状态", "实例工时"]);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec![json!("2023-01-01"), json!("closed"), json!(40)],
+                vec![json!("2023-01-01"), json!("open"), json!(80)],
+                vec![json!("2023-01-01"), serde_json::Value::Null, json!(120)],
+                vec![json!("2023-01-02"), json!("open"), json!(10)],
+                vec![json!("2023-01-02"), serde_json::Value::Null, json!(10)]
+            ]
+        );
+        let temp_file = StatsExportTempFile::new(&StatsExportFormatKind::Csv);
+        let mut writer = StatsExportWriter::new(&StatsExportFormatKind::Csv, &table.headers, &temp_file.path).unwrap();
+        for row in &table.rows {
+            writer.write_row(row).unwrap();
+        }
+        writer.finish(&temp_file.path).unwrap();
+        let csv = std::fs::read_to_string(&temp_file.path).unwrap();
+        let mut reader = csv::Reader::from_reader(csv.trim_start_matches('\u{feff}').as_bytes());
+        assert_eq!(reader.headers().unwrap(), vec!["创建时间", "状态", "实例工时"]);
+        let records = reader.records().map(|record| record.unwrap().iter().map(str::to_string).collect::<Vec<_>>()).collect::<Vec<_>>();
+        assert_eq!(records[0], vec!["2023-01-01", "closed", "40"]);
+        assert_eq!(records[2], vec!["2023-01-01", "", "120"]);
+        assert_eq!(records.len(), 5);
+    }
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("需求统计"), "需求统计");
+        assert_eq!(sanitize_file_name("../../conf/secret"), "secret");
+        assert_eq!(sanitize_file_name("..\\report"), "report");
+        assert_eq!(sanitize_file_name(".."), "export");
+        assert_eq!(sanitize_file_name("a/"), "export");
+    }
+}
@@ -10,6 +10,22 @@ pub struct StatsConfig {
     pub base_url: String,
     pub invoke: InvokeConfig,
     pub cache_key_async_task_status: String,
+    /// Exports with more rows than this are written to spi-object and returned as a presigned url
+    ///
+    /// 超过该行数的导出会写入对象存储并返回预签名地址
+    pub export_inline_max_rows: usize,
+    /// Maximum number of rows of an export
+    ///
+    /// 单次导出的最大行数
+    pub export_max_rows: usize,
+    /// Page size used to read fact records when exporting
+    ///
+    /// 导出时读取事实记录的分页大小
+    pub export_page_size: u64,
+    /// Expiration of the presigned url of large exports, in seconds
+    ///
+    /// 大文件导出预签名地址的过期时间，单位秒
+    pub export_url_exp_secs: u32,
 }
 
 impl Default for StatsConfig {
@@ -19,6 +35,10 @@ impl Default for StatsConfig {
             invoke: InvokeConfig::default(),
             cache_key_async_task_status: "iam:cache:task:status".to_string(),
             base_url: "http://127.0.0.1:8080/spi-stats".to_string(),
+            export_inline_max_rows: 5000,
+            export_max_rows: 200000,
+            export_page_size: 1000,
+            export_url_exp_secs: 3600,
         }
     }
 }
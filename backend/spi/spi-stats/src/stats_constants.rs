@@ -2,3 +2,6 @@ pub const DOMAIN_CODE: &str = "spi-stats";
 
 /// sync fact task code
 pub const SYNC_FACT_TASK_CODE: &str = "sync_fact";
+
+/// evaluate alert rule task code
+pub const EVAL_ALERT_TASK_CODE: &str = "eval_alert";
@@ -568,3 +568,99 @@ impl TryGetable for StatsQueryTimeWindowKind {
         panic!("not implemented")
     }
 }
+
+#[derive(Display, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum, strum::EnumString)]
+pub enum StatsAlertConditionKind {
+    /// The value is greater than the threshold
+    ///
+    /// 值大于阈值
+    #[oai(rename = "above")]
+    Above,
+    /// The value is less than the threshold
+    ///
+    /// 值小于阈值
+    #[oai(rename = "below")]
+    Below,
+    /// The percent change versus the previous window is greater than the threshold
+    ///
+    /// 相比上一个窗口的变化百分比大于阈值
+    #[oai(rename = "change_percent_above")]
+    ChangePercentAbove,
+    /// The percent change versus the previous window is less than the threshold
+    ///
+    /// 相比上一个窗口的变化百分比小于阈值
+    #[oai(rename = "change_percent_below")]
+    ChangePercentBelow,
+}
+
+impl StatsAlertConditionKind {
+    pub fn is_change_percent(&self) -> bool {
+        self == &StatsAlertConditionKind::ChangePercentAbove || self == &StatsAlertConditionKind::ChangePercentBelow
+    }
+
+    /// Check whether the rule fires.
+    /// For percent change conditions, `value` should be the change percent.
+    ///
+    /// 检查规则是否触发。
+    /// 对于百分比变化条件，`value` 应为变化百分比。
+    pub fn is_fired(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            StatsAlertConditionKind::Above | StatsAlertConditionKind::ChangePercentAbove => value > threshold,
+            StatsAlertConditionKind::Below | StatsAlertConditionKind::ChangePercentBelow => value < threshold,
+        }
+    }
+}
+
+impl TryGetable for StatsAlertConditionKind {
+    fn try_get(res: &QueryResult, pre: &str, col: &str) -> Result<Self, TryGetError> {
+        let s = String::try_get(res, pre, col)?;
+        StatsAlertConditionKind::from_str(&s).map_err(|_| TryGetError::DbErr(DbErr::RecordNotFound(format!("{pre}:{col}"))))
+    }
+
+    fn try_get_by<I: sea_orm::ColIdx>(_res: &QueryResult, _index: I) -> Result<Self, TryGetError> {
+        panic!("not implemented")
+    }
+}
+
+#[derive(Display, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum, strum::EnumString)]
+pub enum StatsAlertStatusKind {
+    #[oai(rename = "ok")]
+    Ok,
+    #[oai(rename = "firing")]
+    Firing,
+}
+
+impl TryGetable for StatsAlertStatusKind {
+    fn try_get(res: &QueryResult, pre: &str, col: &str) -> Result<Self, TryGetError> {
+        let s = String::try_get(res, pre, col)?;
+        StatsAlertStatusKind::from_str(&s).map_err(|_| TryGetError::DbErr(DbErr::RecordNotFound(format!("{pre}:{col}"))))
+    }
+
+    fn try_get_by<I: sea_orm::ColIdx>(_res: &QueryResult, _index: I) -> Result<Self, TryGetError> {
+        panic!("not implemented")
+    }
+}
+
+#[derive(Display, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum, strum::EnumString)]
+pub enum StatsExportFormatKind {
+    #[oai(rename = "csv")]
+    Csv,
+    #[oai(rename = "xlsx")]
+    Xlsx,
+}
+
+impl StatsExportFormatKind {
+    pub fn extension(&self) -> &str {
+        match self {
+            StatsExportFormatKind::Csv => "csv",
+            StatsExportFormatKind::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn content_type(&self) -> &str {
+        match self {
+            StatsExportFormatKind::Csv => "text/csv",
+            StatsExportFormatKind::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+}
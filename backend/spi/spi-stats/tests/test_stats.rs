@@ -14,7 +14,9 @@ use tardis::basic::result::TardisResult;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::Void;
 use tardis::{tokio, TardisFuns};
+mod test_stats_alert;
 mod test_stats_conf;
+mod test_stats_export;
 mod test_stats_metric;
 mod test_stats_record;
 
@@ -33,6 +35,7 @@ async fn init_data() -> TardisResult<()> {
     let web_server = TardisFuns::web_server();
     // Initialize SPI Stats
     stats_initializer::init(&web_server).await.unwrap();
+    web_server.add_module("schedule", test_stats_alert::MockScheduleApi).await;
 
     tokio::spawn(async move {
         web_server.start().await.unwrap();
@@ -85,6 +88,8 @@ async fn init_data() -> TardisResult<()> {
     test_stats_conf::test(&mut client).await?;
     test_stats_record::test(&mut client).await?;
     test_stats_metric::test(&mut client).await?;
+    test_stats_export::test(&mut client).await?;
+    test_stats_alert::test(&mut client).await?;
 
     Ok(())
 }
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::Utc;
+use tardis::serde_json::{json, Value};
+use tardis::tokio::{self, time::sleep};
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::Path;
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
+
+/// Stands in for the schedule middleware that the evaluation tasks are registered to
+#[derive(Clone)]
+pub struct MockScheduleApi;
+
+#[poem_openapi::OpenApi(prefix_path = "/ci/schedule")]
+impl MockScheduleApi {
+    #[oai(path = "/jobs", method = "put")]
+    async fn add_or_modify(&self, _add_or_modify_req: Json<Value>) -> TardisApiResult<Void> {
+        TardisResp::ok(Void {})
+    }
+
+    #[oai(path = "/jobs/:code", method = "delete")]
+    async fn delete(&self, _code: Path<String>) -> TardisApiResult<Void> {
+        TardisResp::ok(Void {})
+    }
+}
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    let alert = |key: &str, measure: &str| {
+        json!({
+            "key": key,
+            "show_name": "工时告警",
+            "query": {
+                "from":"req",
+                "select":[{"code":"act_hours","fun":"sum"}],
+                "group":[],
+                "start_time": (Utc::now() - tardis::chrono::Duration::hours(1)).to_rfc3339(),
+                "end_time": Utc::now().to_rfc3339()
+            },
+            "measure": measure,
+            "condition": "above",
+            "threshold": 50.0,
+            "eval_cron": "0 0/5 * * * ?"
+        })
+    };
+
+    // measure not in select error
+    assert_eq!(
+        client.put_resp::<Value, Void>("/ci/alert", &alert("req_act_hours", "plan_hours__sum")).await.code,
+        "400-spi-stats-alert_conf-check"
+    );
+    let _: Void = client.put("/ci/alert", &alert("req_act_hours", "act_hours__sum")).await;
+    // key exist error
+    assert_eq!(
+        client.put_resp::<Value, Void>("/ci/alert", &alert("req_act_hours", "act_hours__sum")).await.code,
+        "409-spi-stats-alert_conf-add"
+    );
+    let _: Void = client.put("/ci/alert", &alert("req_act_hours_copy", "act_hours__sum")).await;
+    let alerts: TardisPage<Value> = client.get("/ci/alert?page_number=1&page_size=10").await;
+    assert_eq!(alerts.total_size, 2);
+    client.delete("/ci/alert/req_act_hours_copy").await;
+
+    // no records in the window, the status is kept
+    let resp: Value = client.put("/ci/alert/req_act_hours/eval", &Void {}).await;
+    assert_eq!(resp["status"], "ok");
+    assert_eq!(resp["changed"], false);
+    assert!(resp["value"].is_null());
+
+    let _: Void = client
+        .put(
+            "/ci/record/fact/req/batch/load",
+            &json!([{
+                "key":"r100",
+                "own_paths":"t1/a1",
+                "ct":Utc::now().to_rfc3339(),
+                "data": {
+                    "source":"hangzhou",
+                    "status":"open",
+                    "priority":1,
+                    "tag":["t1"],
+                    "creator":"acc001",
+                    "act_hours":60,
+                    "plan_hours":80
+                }
+            }]),
+        )
+        .await;
+    sleep(Duration::from_millis(100)).await;
+
+    let resp: Value = client.put("/ci/alert/req_act_hours/eval", &Void {}).await;
+    assert_eq!(resp["status"], "firing");
+    assert_eq!(resp["changed"], true);
+    assert_eq!(resp["value"], 60.0);
+    let resp: Value = client.put("/ci/alert/req_act_hours/eval", &Void {}).await;
+    assert_eq!(resp["status"], "firing");
+    assert_eq!(resp["changed"], false);
+
+    // of concurrent evaluations only one records the change
+    let _: Void = client.patch("/ci/alert/req_act_hours", &json!({ "threshold": 100.0 })).await;
+    let (resp1, resp2): (Value, Value) = tokio::join!(client.put("/ci/alert/req_act_hours/eval", &Void {}), client.put("/ci/alert/req_act_hours/eval", &Void {}));
+    assert_eq!(resp1["status"], "ok");
+    assert_eq!(resp2["status"], "ok");
+    assert_ne!(resp1["changed"], resp2["changed"]);
+    let history: TardisPage<Value> = client.get("/ci/alert/req_act_hours/history?page_number=1&page_size=10").await;
+    assert_eq!(history.total_size, 2);
+    assert_eq!(history.records[0]["status"], "ok");
+    assert_eq!(history.records[1]["status"], "firing");
+
+    // the history is kept after the rule is deleted
+    client.delete("/ci/alert/req_act_hours").await;
+    let alerts: TardisPage<Value> = client.get("/ci/alert?keys=req_act_hours&page_number=1&page_size=10").await;
+    assert_eq!(alerts.total_size, 0);
+    let history: TardisPage<Value> = client.get("/ci/alert/req_act_hours/history?page_number=1&page_size=10").await;
+    assert_eq!(history.total_size, 2);
+
+    Ok(())
+}
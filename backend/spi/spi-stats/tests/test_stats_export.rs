@@ -0,0 +1,69 @@
+use bios_basic::test::test_http_client::TestHttpClient;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::{json, Value};
+use tardis::TardisFuns;
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    let query = json!({
+        "from":"req",
+        "select":[{"code":"act_hours","fun":"sum"}],
+        "group":[{"code":"status"}],
+        "start_time":"2023-01-01T12:00:00.000Z",
+        "end_time":"2023-02-01T12:00:00.000Z",
+        "page_size":10,
+        "page_number":1
+    });
+
+    // the file name can not escape the export directory
+    let (status, disposition, body) = export(client, "/ci/export/record", &json!({ "format":"csv", "file_name":"../../req", "query":query })).await?;
+    assert_eq!(status, 200);
+    assert!(disposition.contains("req.csv"));
+    assert!(!disposition.contains(".."));
+    let csv = String::from_utf8(body).unwrap();
+    let rows = csv.trim_start_matches('\u{feff}').lines().collect::<Vec<_>>();
+    assert!(rows.len() > 1);
+    assert!(rows.iter().any(|row| row.starts_with("r002,")));
+
+    let (status, disposition, body) = export(client, "/ci/export/metric", &json!({ "format":"xlsx", "query":query })).await?;
+    assert_eq!(status, 200);
+    assert!(disposition.contains("req.xlsx"));
+    assert!(body.starts_with(b"PK"));
+
+    // the status of the error is kept
+    let (status, _, _) = export(
+        client,
+        "/ci/export/metric",
+        &json!({
+            "format":"csv",
+            "query": {
+                "from":"xx",
+                "select":[{"code":"act_hours","fun":"sum"}],
+                "group":[],
+                "start_time":"2023-01-01T12:00:00.000Z",
+                "end_time":"2023-02-01T12:00:00.000Z"
+            }
+        }),
+    )
+    .await?;
+    assert_eq!(status, 404);
+    Ok(())
+}
+
+async fn export(client: &TestHttpClient, url: &str, body: &Value) -> TardisResult<(u16, String, Vec<u8>)> {
+    let fw_config = TardisFuns::fw_config();
+    let resp = TardisFuns::web_client()
+        .raw()
+        .put(format!("http://127.0.0.1:8080/spi-stats{url}"))
+        .header(
+            &fw_config.web_server().context_conf.context_header_name,
+            TardisFuns::crypto.base64.encode(TardisFuns::json.obj_to_string(client.context())?),
+        )
+        .header("Content-Type", "application/json")
+        .body(TardisFuns::json.obj_to_string(body)?)
+        .send()
+        .await
+        .unwrap();
+    let status = resp.status().as_u16();
+    let disposition = resp.headers().get("Content-Disposition").and_then(|value| value.to_str().ok()).unwrap_or_default().to_string();
+    Ok((status, disposition, resp.bytes().await.unwrap().to_vec()))
+}
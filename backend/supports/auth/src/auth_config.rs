@@ -104,6 +104,8 @@ pub struct ApiConfig {
     pub logout_req_path: String,
     pub double_auth_req_method: String,
     pub double_auth_req_path: String,
+    pub double_auth_totp_req_path: String,
+    pub double_auth_webauthn_req_path: String,
 }
 impl Default for ApiConfig {
     fn default() -> Self {
@@ -117,11 +119,15 @@ impl Default for ApiConfig {
                 "/iam/cp/login/phonecode/vcode".to_string(),
                 "/iam/cp/login/phonevcode".to_string(),
                 "/iam/cp/ldap/login".to_string(),
+                "/iam/cp/login/webauthn/finish".to_string(),
+                "/iam/cp/login/oidc".to_string(),
             ],
             logout_req_method: "delete".to_string(),
             logout_req_path: "/iam/cp/logout".to_string(),
             double_auth_req_method: "put".to_string(),
             double_auth_req_path: "/iam/cp/validate/userpwd".to_string(),
+            double_auth_totp_req_path: "/iam/cp/validate/totp".to_string(),
+            double_auth_webauthn_req_path: "/iam/cp/validate/webauthn/finish".to_string(),
         }
     }
 }
@@ -307,6 +307,8 @@ pub(crate) struct ServConfig {
     pub logout_req_path: String,
     pub double_auth_req_method: String,
     pub double_auth_req_path: String,
+    pub double_auth_totp_req_path: String,
+    pub double_auth_webauthn_req_path: String,
     // list split by ','
     pub exclude_encrypt_decrypt_path: String,
 }
@@ -10,6 +10,7 @@ pub mod iam_open_dto;
 pub mod iam_platform_dto;
 pub mod iam_res_dto;
 pub mod iam_role_dto;
+pub mod iam_scim_dto;
 pub mod iam_set_dto;
 pub mod iam_sub_deploy_dto;
 pub mod iam_sub_deploy_host_dto;
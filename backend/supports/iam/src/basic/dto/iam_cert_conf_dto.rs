@@ -106,6 +106,12 @@ pub struct IamCertConfOAuth2ServiceAddOrModifyReq {
     /// 第三方接入的回调地址
     pub redirect_uris: Vec<String>,
     pub rel_rbum_item_id: Option<String>,
+    /// Public client (e.g. SPA or native app) that cannot keep a secret, must use PKCE instead
+    /// 无法保存密钥的公开客户端（如单页应用、原生应用），须使用 PKCE
+    pub public_client: Option<bool>,
+    /// First-party client trusted by the platform, codes are issued without the user's consent
+    /// 平台自有的受信任客户端，签发授权码无需用户同意
+    pub first_party: Option<bool>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -120,6 +126,8 @@ pub struct IamCertConfOAuth2ServiceResp {
     /// The callback addresses for the third-party access
     /// 第三方接入的回调地址列表
     pub redirect_uris: Vec<String>,
+    pub public_client: bool,
+    pub first_party: bool,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -128,6 +136,10 @@ pub struct IamCertConfOAuth2ServiceExt {
     pub client_secret: String,
     pub redirect_uris: Vec<String>,
     pub scope: Vec<String>,
+    #[serde(default)]
+    pub public_client: bool,
+    #[serde(default)]
+    pub first_party: bool,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -140,6 +152,65 @@ pub struct IamCertConfOAuth2Resp {
     #[oai(validator(max_length = "2000"))]
     pub base_url: Option<String>,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamCertConfOidcAddOrModifyReq {
+    /// Code of the identity provider, used to distinguish the providers of the same tenant
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub supplier: TrimString,
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub name: String,
+    /// Issuer identifier, the provider is discovered by `{issuer}/.well-known/openid-configuration`
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub issuer: String,
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub client_id: TrimString,
+    /// Not needed by public clients, which are protected by PKCE only.
+    /// The current secret is kept when modifying without it
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub client_secret: Option<TrimString>,
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub redirect_uri: String,
+    /// Default is `openid profile email`
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub scope: Option<String>,
+    pub claim_map: IamCertOidcClaimMap,
+    /// Create the account in the tenant when an unknown user logs in
+    pub auto_create_account: bool,
+}
+
+/// Claims of the id token mapped to the account
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IamCertOidcClaimMap {
+    /// Claim identifying the user at the provider, default is `sub`
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub open_id: Option<String>,
+    /// Default is `name`
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub name: Option<String>,
+    /// Username of the created account, e.g. `preferred_username`, a random one is generated when absent
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub user_name: Option<String>,
+    /// Default is `email`, only used when `email_verified` is true
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub mail: Option<String>,
+    /// Default is `phone_number`, only used when `phone_number_verified` is true
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub phone: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamCertConfOidcResp {
+    pub id: String,
+    pub supplier: String,
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: Option<String>,
+    pub claim_map: IamCertOidcClaimMap,
+    pub auto_create_account: bool,
+}
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct IamCertConfAkSkAddOrModifyReq {
     #[oai(validator(min_length = "2", max_length = "255"))]
@@ -147,6 +218,61 @@ pub struct IamCertConfAkSkAddOrModifyReq {
     #[oai(validator(minimum(value = "1", exclusive = "false")))]
     pub expire_sec: Option<i64>,
 }
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamCertConfTotpAddOrModifyReq {
+    /// Shown by the authenticator app beside the account name
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub issuer: String,
+    #[oai(validator(minimum(value = "6", exclusive = "false"), maximum(value = "8", exclusive = "false")))]
+    pub digits: u32,
+    /// Time step in seconds
+    #[oai(validator(minimum(value = "15", exclusive = "false"), maximum(value = "300", exclusive = "false")))]
+    pub period: u64,
+    /// Number of time steps accepted before and after the current one, to tolerate clock skew
+    #[oai(validator(maximum(value = "5", exclusive = "false")))]
+    pub skew: u64,
+    /// Whether the accounts must pass TOTP to login
+    pub login_required: bool,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamCertConfTotpResp {
+    pub issuer: String,
+    pub digits: u32,
+    pub period: u64,
+    pub skew: u64,
+    pub login_required: bool,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamCertConfWebAuthnAddOrModifyReq {
+    /// Relying party id, the domain the credentials are bound to
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub rp_id: String,
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub rp_name: String,
+    /// Origin of the login page, e.g. `https://bios.example.com`, its host must be the rp_id or a sub domain of it
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub rp_origin: String,
+    /// Maximum number of authenticators of an account
+    #[oai(validator(minimum(value = "1", exclusive = "false")))]
+    pub max_authenticators: i16,
+    /// Whether the passkey can be used to login without password
+    pub primary_enabled: bool,
+    /// Whether the accounts with passkeys must present one after the password
+    pub second_factor_required: bool,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamCertConfWebAuthnResp {
+    pub rp_id: String,
+    pub rp_name: String,
+    pub rp_origin: String,
+    pub max_authenticators: i16,
+    pub primary_enabled: bool,
+    pub second_factor_required: bool,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
 pub struct IamCertConfLdapAddOrModifyReq {
     /// Assign a code to the LdapCertConf,Used to distinguish different sources
@@ -5,6 +5,7 @@ use bios_basic::rbum::rbum_enumeration::RbumCertStatusKind;
 use serde::{Deserialize, Serialize};
 use tardis::basic::field::TrimString;
 use tardis::chrono::{DateTime, Utc};
+use tardis::serde_json::Value;
 use tardis::web::poem_openapi;
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -115,6 +116,66 @@ pub struct IamCertPhoneVCodeBindReq {
     pub vcode: String,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertTotpEnrollResp {
+    /// Base32 encoded secret, for the authenticator apps that can't scan the uri
+    pub secret: String,
+    /// `otpauth://totp/...` uri, usually rendered as a QR code
+    pub otpauth_uri: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertTotpValidateReq {
+    /// TOTP code or one of the recovery codes
+    #[oai(validator(min_length = "6", max_length = "255"))]
+    pub code: TrimString,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertTotpActivateResp {
+    /// One-time codes used when the authenticator is lost, only returned once
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertWebAuthnRegisterStartReq {
+    /// Name of the authenticator, helps the user to tell the passkeys apart
+    #[oai(validator(min_length = "1", max_length = "255"))]
+    pub name: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertWebAuthnChallengeResp {
+    /// Passed back when finishing the ceremony
+    pub state_id: String,
+    /// `PublicKeyCredentialCreationOptions` or `PublicKeyCredentialRequestOptions` to pass to `navigator.credentials`
+    pub options: Value,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertWebAuthnFinishReq {
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub state_id: String,
+    /// `PublicKeyCredential` returned by `navigator.credentials`
+    pub credential: Value,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertWebAuthnResp {
+    pub id: String,
+    pub name: String,
+    pub create_time: DateTime<Utc>,
+    pub last_used_time: Option<DateTime<Utc>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertOidcAuthorizeResp {
+    /// Returned by the provider to the redirect uri along with the code
+    pub state: String,
+    /// Redirect the browser to it
+    pub authorize_url: String,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct IamThirdPartyCertExtAddReq {
     #[oai(validator(min_length = "2", max_length = "255"))]
@@ -292,6 +353,11 @@ pub struct IamCertOAuth2ServiceCodeAddReq {
     pub redirect_uri: TrimString,
     #[oai(default)]
     pub response_type: OAuth2ResponseType,
+    /// Returned in the id token
+    pub nonce: Option<String>,
+    /// PKCE challenge, only `S256` is supported
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -299,8 +365,10 @@ pub struct IamCertOAuth2ServiceCodeVerifyReq {
     pub grant_type: Oauth2GrantType,
     pub code: String,
     pub client_id: String,
+    /// Can be empty for the public clients that use PKCE
     pub client_secret: String,
     pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
 }
 
 // 新增：刷新令牌请求
@@ -321,6 +389,8 @@ pub struct IamOauth2TokenResp {
     pub expires_in: i64,
     pub refresh_token: Option<String>,
     pub scope: Option<String>,
+    /// Signed JWT, returned when the scope contains `openid`
+    pub id_token: Option<String>,
 }
 
 // OAuth2 授权响应
@@ -367,6 +437,56 @@ pub struct IamOauth2IntrospectResp {
     pub provider: Option<String>,
     /// 主体标识，等于 Provider 侧账号 ID（仅 active 时返回）
     pub sub: Option<String>,
+    /// 令牌签发给的客户端（仅 active 时返回）
+    pub client_id: Option<String>,
+    /// 令牌的授权范围（仅 active 时返回）
+    pub scope: Option<String>,
+}
+
+/// OAuth2 令牌撤销请求（RFC 7009）
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamOauth2RevokeReq {
+    pub token: String,
+    /// `access_token` or `refresh_token`
+    pub token_type_hint: Option<String>,
+    /// Can also be passed by the basic authorization header
+    #[oai(default)]
+    pub client_id: String,
+    #[oai(default)]
+    pub client_secret: String,
+}
+
+/// OAuth2 设备授权请求（RFC 8628）
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamOauth2DeviceAuthorizationReq {
+    #[oai(default)]
+    pub client_id: String,
+    #[oai(default)]
+    pub client_secret: String,
+    pub scope: Option<String>,
+}
+
+/// OAuth2 设备授权响应
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamOauth2DeviceAuthorizationResp {
+    pub device_code: String,
+    /// 用户在验证页面输入的短码
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: String,
+    pub expires_in: i64,
+    /// 设备轮询令牌端点的最小间隔（秒）
+    pub interval: i64,
+}
+
+/// 账号对客户端的授权同意记录
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamOauth2ConsentResp {
+    pub client_id: String,
+    pub client_name: String,
+    /// 已同意的授权范围，以空格分隔
+    pub scope: String,
+    pub consent_time: DateTime<Utc>,
 }
 
 /// 临时脚本：为仅有 LDAP 凭证、无 UserPwd 的账号补全 UserPwd 后的单条结果
@@ -6,7 +6,10 @@ use tardis::chrono::{DateTime, Utc};
 use tardis::db::sea_orm;
 use tardis::web::poem_openapi;
 
-use super::iam_cert_conf_dto::{IamCertConfOAuth2AddOrModifyReq, IamCertConfOAuth2Resp, IamCertConfUserPwdAddOrModifyReq};
+use super::iam_cert_conf_dto::{
+    IamCertConfOAuth2AddOrModifyReq, IamCertConfOAuth2Resp, IamCertConfOidcAddOrModifyReq, IamCertConfOidcResp, IamCertConfTotpAddOrModifyReq, IamCertConfTotpResp,
+    IamCertConfUserPwdAddOrModifyReq, IamCertConfWebAuthnAddOrModifyReq, IamCertConfWebAuthnResp,
+};
 use super::iam_config_dto::{IamConfigAggOrModifyReq, IamConfigSummaryResp};
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -116,6 +119,9 @@ pub struct IamTenantConfigReq {
     pub token_default_coexist_num: Option<i16>,
     pub cert_conf_by_oauth2: Option<Vec<IamCertConfOAuth2AddOrModifyReq>>,
     pub cert_conf_by_ldap: Option<IamCertConfLdapAddOrModifyReq>,
+    pub cert_conf_by_totp: Option<IamCertConfTotpAddOrModifyReq>,
+    pub cert_conf_by_webauthn: Option<IamCertConfWebAuthnAddOrModifyReq>,
+    pub cert_conf_by_oidc: Option<Vec<IamCertConfOidcAddOrModifyReq>>,
     pub config: Option<Vec<IamConfigAggOrModifyReq>>,
 }
 
@@ -127,6 +133,9 @@ pub struct IamTenantConfigResp {
     pub token_default_coexist_num: i16,
     pub cert_conf_by_oauth2: Option<Vec<IamCertConfOAuth2Resp>>,
     pub cert_conf_by_ldap: Option<Vec<IamCertConfLdapResp>>,
+    pub cert_conf_by_totp: Option<IamCertConfTotpResp>,
+    pub cert_conf_by_webauthn: Option<IamCertConfWebAuthnResp>,
+    pub cert_conf_by_oidc: Option<Vec<IamCertConfOidcResp>>,
     pub config: Vec<IamConfigSummaryResp>,
     pub strict_security_mode: bool,
 }
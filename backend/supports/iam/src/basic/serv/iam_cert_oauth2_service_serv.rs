@@ -1,15 +1,24 @@
+use std::collections::HashSet;
+
 use bios_basic::rbum::{
     dto::{
         rbum_cert_conf_dto::{RbumCertConfAddReq, RbumCertConfModifyReq, RbumCertConfSummaryResp},
+        rbum_cert_dto::{RbumCertAddReq, RbumCertModifyReq, RbumCertSummaryResp},
         rbum_filer_dto::{RbumBasicFilterReq, RbumCertConfFilterReq, RbumCertFilterReq},
     },
-    rbum_enumeration::RbumCertConfStatusKind,
-    serv::{rbum_cert_serv::RbumCertConfServ, rbum_crud_serv::RbumCrudOperation as _, rbum_item_serv::RbumItemCrudOperation as _},
+    rbum_enumeration::{RbumCertConfStatusKind, RbumCertRelKind, RbumCertStatusKind},
+    serv::{rbum_cert_serv::RbumCertConfServ, rbum_cert_serv::RbumCertServ, rbum_crud_serv::RbumCrudOperation as _, rbum_item_serv::RbumItemCrudOperation as _},
 };
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use rsa::pkcs1::{DecodeRsaPrivateKey, EncodeRsaPrivateKey};
+use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPrivateKey;
 use serde::{Deserialize, Serialize};
 use tardis::{
-    basic::{dto::TardisContext, field::TrimString, result::TardisResult},
-    chrono::Utc,
+    basic::{dto::TardisContext, error::TardisError, field::TrimString, result::TardisResult},
+    chrono::{Duration, Utc},
+    serde_json::{json, Map, Value},
     TardisFuns, TardisFunsInst,
 };
 
@@ -18,12 +27,12 @@ use crate::{
         dto::{
             iam_cert_conf_dto::{IamCertConfOAuth2ServiceAddOrModifyReq, IamCertConfOAuth2ServiceExt, IamCertConfOAuth2ServiceResp},
             iam_cert_dto::{
-                IamCertOAuth2ServiceCodeAddReq, IamCertOAuth2ServiceCodeVerifyReq, IamCertOAuth2ServiceRefreshTokenReq, IamOauth2IntrospectResp, IamOauth2TokenResp,
-                IamOauth2UserInfoResp,
+                IamCertOAuth2ServiceCodeAddReq, IamCertOAuth2ServiceCodeVerifyReq, IamCertOAuth2ServiceRefreshTokenReq, IamOauth2ConsentResp, IamOauth2DeviceAuthorizationReq,
+                IamOauth2DeviceAuthorizationResp, IamOauth2IntrospectResp, IamOauth2RevokeReq, IamOauth2TokenResp, IamOauth2UserInfoResp,
             },
             iam_filer_dto::IamAccountFilterReq,
         },
-        serv::{iam_account_serv::IamAccountServ, iam_cert_serv::IamCertServ, iam_key_cache_serv::IamIdentCacheServ},
+        serv::{iam_account_serv::IamAccountServ, iam_cert_oidc_serv, iam_cert_serv::IamCertServ, iam_key_cache_serv::IamIdentCacheServ},
     },
     iam_config::{IamBasicConfigApi as _, IamConfig},
     iam_enumeration::{IamCertExtKind, IamCertKernelKind, IamCertTokenKind, OAuth2ResponseType, Oauth2GrantType, Oauth2TokenType},
@@ -34,6 +43,20 @@ const OAUTH2_PROVIDER: &str = "bios-iam";
 
 const REDIS_CODE_KEY: &str = "iam:oauth2:service:code:";
 const REDIS_REFRESH_TOKEN_KEY: &str = "iam:oauth2:service:refresh_token:";
+const REDIS_ACCESS_TOKEN_KEY: &str = "iam:oauth2:service:access_token:";
+/// 主体 -> 签发给各客户端的令牌，撤销同意时据此撤销令牌
+const REDIS_SUB_TOKENS_KEY: &str = "iam:oauth2:service:sub_tokens:";
+const REDIS_DEVICE_CODE_KEY: &str = "iam:oauth2:service:device_code:";
+const REDIS_USER_CODE_KEY: &str = "iam:oauth2:service:user_code:";
+
+const SCOPE_OPENID: &str = "openid";
+const PKCE_METHOD_S256: &str = "S256";
+/// 用户码字符集，去掉元音及易混淆字符（RFC 8628 6.1）
+const USER_CODE_ALPHABET: [char; 20] = ['B', 'C', 'D', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'V', 'W', 'X', 'Z'];
+const USER_CODE_LEN: usize = 8;
+const SIGNING_KEY_BITS: usize = 2048;
+/// 数据库中保存 id_token 签名密钥的凭证供应商标识
+const SIGNING_KEY_SUPPLIER: &str = "oauth2_signing_key";
 
 pub struct IamCertOAuth2ServiceServ;
 
@@ -46,6 +69,11 @@ pub struct IamCertOAuth2ServiceCode {
     pub state: Option<String>,
     pub created_at: i64,
     pub used: bool,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// S256 PKCE challenge
+    #[serde(default)]
+    pub code_challenge: Option<String>,
 }
 
 // 刷新令牌信息结构
@@ -57,6 +85,38 @@ pub struct IamOAuth2RefreshTokenInfo {
     pub expires_at: i64,
 }
 
+/// 访问令牌签发给的客户端及授权范围
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct IamOAuth2AccessTokenInfo {
+    sub: String,
+    client_id: String,
+    scope: String,
+    /// 客户端凭证模式签发的令牌，主体为客户端本身
+    #[serde(default)]
+    client_token: bool,
+}
+
+/// 设备码流程中等待用户确认的授权
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct IamOAuth2DeviceCode {
+    client_id: String,
+    scope: String,
+    user_code: String,
+    /// 用户确认后的账号上下文
+    approved_ctx: Option<TardisContext>,
+    denied: bool,
+    last_poll_at: i64,
+}
+
+/// 签发 id_token 的密钥
+struct IamOAuth2SigningKey {
+    kid: String,
+    encoding_key: EncodingKey,
+    /// RSA 公钥的模数及指数，base64url 编码
+    n: String,
+    e: String,
+}
+
 impl IamCertOAuth2ServiceServ {
     pub async fn add_cert_conf(add_req: &IamCertConfOAuth2ServiceAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
         let client_id = TardisFuns::crypto.key.generate_ak()?;
@@ -76,6 +136,8 @@ impl IamCertOAuth2ServiceServ {
                     client_secret,
                     redirect_uris: add_req.redirect_uris.clone(),
                     scope: vec![],
+                    public_client: add_req.public_client.unwrap_or(false),
+                    first_party: add_req.first_party.unwrap_or(false),
                 })?),
                 sk_need: Some(false),
                 sk_dynamic: Some(false),
@@ -125,6 +187,8 @@ impl IamCertOAuth2ServiceServ {
             client_secret: ext.client_secret,
             access_token_expire_sec: cert_conf.expire_sec,
             redirect_uris: ext.redirect_uris,
+            public_client: ext.public_client,
+            first_party: ext.first_party,
         })
     }
 
@@ -156,6 +220,8 @@ impl IamCertOAuth2ServiceServ {
                 client_secret: ext.client_secret,
                 access_token_expire_sec: cert_conf.expire_sec,
                 redirect_uris: ext.redirect_uris.clone(),
+                public_client: ext.public_client,
+                first_party: ext.first_party,
             });
         }
 
@@ -200,6 +266,18 @@ impl IamCertOAuth2ServiceServ {
         Ok(conf.remove(0))
     }
 
+    /// 校验客户端，返回客户端配置
+    ///
+    /// 仅注册为公开客户端（`public_client`）的客户端允许不带密钥，其安全性由 PKCE 等其他方式保证。
+    async fn authenticate_client(client_id: &str, client_secret: &str, funs: &TardisFunsInst) -> TardisResult<(RbumCertConfSummaryResp, IamCertConfOAuth2ServiceExt)> {
+        let conf = Self::get_cert_conf_by_client_id(client_id, funs).await?;
+        let ext = TardisFuns::json.str_to_obj::<IamCertConfOAuth2ServiceExt>(&conf.ext)?;
+        if !(client_secret.is_empty() && ext.public_client) && client_secret != ext.client_secret {
+            return Err(funs.err().unauthorized("oauth2", "authenticate_client", "invalid_client", "401-oauth2-invalid-client"));
+        }
+        Ok((conf, ext))
+    }
+
     /// 改进的生成授权码方法 - 使用配置中的有效期
     pub async fn generate_code(add_req: &IamCertOAuth2ServiceCodeAddReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
         // 1. 验证响应类型 - 目前只支持code模式
@@ -219,7 +297,22 @@ impl IamCertOAuth2ServiceServ {
             return Err(funs.err().bad_request("oauth2", "generate_code", "invalid_redirect_uri", "400-oauth2-invalid-redirect-uri"));
         }
 
-        // 4. 构建授权码信息
+        // 4. 验证 PKCE，仅支持 S256
+        let iam_config = funs.conf::<IamConfig>();
+        if let Some(code_challenge) = &add_req.code_challenge {
+            if add_req.code_challenge_method.as_deref() != Some(PKCE_METHOD_S256) || code_challenge.is_empty() {
+                return Err(funs.err().bad_request("oauth2", "generate_code", "only S256 code_challenge_method is supported", "400-oauth2-invalid-request"));
+            }
+        } else if iam_config.oauth2_require_pkce || ext.public_client {
+            return Err(funs.err().bad_request("oauth2", "generate_code", "code_challenge is required", "400-oauth2-invalid-request"));
+        }
+
+        // 5. 非平台自有客户端须由账号同意全部授权范围
+        if !ext.first_party && !Self::consent_granted(&conf.id, &add_req.scope.to_string(), funs, ctx).await? {
+            return Err(funs.err().forbidden("oauth2", "generate_code", "consent_required", "403-oauth2-consent-required"));
+        }
+
+        // 6. 构建授权码信息
         let code_info = IamCertOAuth2ServiceCode {
             ctx: ctx.clone(),
             client_id: add_req.client_id.to_string(),
@@ -228,10 +321,11 @@ impl IamCertOAuth2ServiceServ {
             state: add_req.state.clone(),
             created_at: Utc::now().timestamp(),
             used: false,
+            nonce: add_req.nonce.clone(),
+            code_challenge: add_req.code_challenge.clone(),
         };
 
-        // 5. 存储到Redis - 使用配置中的有效期
-        let iam_config = funs.conf::<IamConfig>();
+        // 7. 存储到Redis - 使用配置中的有效期
         let expire_sec = iam_config.oauth2_auth_code_expire_sec as u64;
         funs.cache().set_ex(&format!("{}{}", REDIS_CODE_KEY, code), &TardisFuns::json.obj_to_string(&code_info)?, expire_sec).await?;
 
@@ -245,23 +339,16 @@ impl IamCertOAuth2ServiceServ {
             return Err(funs.err().bad_request("oauth2", "verify_code", "unsupported_grant_type", "400-oauth2-unsupported-grant-type"));
         }
 
-        // 2. 获取客户端配置并验证client_secret
-        let conf = Self::get_cert_conf_by_client_id(&req.client_id, funs).await?;
-
-        let ext = TardisFuns::json.str_to_obj::<IamCertConfOAuth2ServiceExt>(&conf.ext)?;
-
-        // 验证客户端密钥
-        if req.client_secret != ext.client_secret {
-            return Err(funs.err().unauthorized("oauth2", "verify_code", "invalid_client", "401-oauth2-invalid-client"));
-        }
-
-        // 3. 获取并验证授权码
+        // 2. 获取并验证授权码
         let code_data = funs.cache().get(&format!("{}{}", REDIS_CODE_KEY, req.code)).await?;
         let code_info: IamCertOAuth2ServiceCode = match code_data {
             Some(data) => TardisFuns::json.str_to_obj(&data)?,
             None => return Err(funs.err().unauthorized("oauth2", "verify_code", "invalid_or_expired_code", "401-oauth2-invalid-code")),
         };
 
+        // 3. 获取客户端配置并验证client_secret，公开客户端可不带密钥（签发授权码时已要求 PKCE）
+        let (conf, _) = Self::authenticate_client(&req.client_id, &req.client_secret, funs).await?;
+
         // 4. 验证授权码状态和参数
         if code_info.used {
             return Err(funs.err().unauthorized("oauth2", "verify_code", "code_already_used", "401-oauth2-code-used"));
@@ -277,6 +364,13 @@ impl IamCertOAuth2ServiceServ {
             }
         }
 
+        if let Some(code_challenge) = &code_info.code_challenge {
+            let code_verifier = req.code_verifier.as_deref().unwrap_or_default();
+            if code_verifier.is_empty() || iam_cert_oidc_serv::pkce_challenge(code_verifier)? != *code_challenge {
+                return Err(funs.err().unauthorized("oauth2", "verify_code", "invalid_code_verifier", "401-oauth2-invalid-code-verifier"));
+            }
+        }
+
         // 5. 标记授权码为已使用
         let mut used_code_info = code_info.clone();
         used_code_info.used = true;
@@ -288,38 +382,8 @@ impl IamCertOAuth2ServiceServ {
             )
             .await?;
 
-        // 6. 生成访问令牌和刷新令牌
-        let access_token = TardisFuns::crypto.key.generate_token()?;
-        let refresh_token = TardisFuns::crypto.key.generate_token()?;
-
-        let iam_config = funs.conf::<IamConfig>();
-        let access_token_expire_sec = conf.expire_sec;
-
-        // 7. 存储访问令牌（复用现有的令牌缓存系统）
-        IamIdentCacheServ::add_token(&access_token, &IamCertTokenKind::TokenOauth2, &code_info.ctx.owner, None, access_token_expire_sec, conf.coexist_num, funs).await?;
-
-        // 8. 存储刷新令牌
-        let refresh_token_info = IamOAuth2RefreshTokenInfo {
-            user_id: code_info.ctx.owner.clone(),
-            client_id: req.client_id.clone(),
-            scope: code_info.scope.clone(),
-            expires_at: Utc::now().timestamp() + iam_config.oauth2_refresh_token_expire_sec as i64,
-        };
-        funs.cache()
-            .set_ex(
-                &format!("{}{}", REDIS_REFRESH_TOKEN_KEY, refresh_token),
-                &TardisFuns::json.obj_to_string(&refresh_token_info)?,
-                iam_config.oauth2_refresh_token_expire_sec as u64,
-            )
-            .await?;
-
-        Ok(IamOauth2TokenResp {
-            access_token,
-            token_type: Oauth2TokenType::Bearer,
-            expires_in: access_token_expire_sec as i64,
-            refresh_token: Some(refresh_token),
-            scope: Some(code_info.scope),
-        })
+        // 6. 生成访问令牌、刷新令牌及 id_token
+        Self::issue_token(&code_info.ctx.owner, &conf, &code_info.scope, code_info.nonce.as_deref(), true, funs).await
     }
 
     /// 刷新令牌方法
@@ -347,32 +411,257 @@ impl IamCertOAuth2ServiceServ {
 
         // 4. 获取凭证配置
         let conf = Self::get_cert_conf_by_client_id(&refresh_token_info.client_id, funs).await?;
+
+        // 5. 生成并存储新的访问令牌
+        let mut token_resp = Self::issue_token(&refresh_token_info.user_id, &conf, &refresh_token_info.scope, None, false, funs).await?;
+        token_resp.refresh_token = Some(req.refresh_token.clone()); // 保持相同的刷新令牌
+        Ok(token_resp)
+    }
+
+    /// 客户端凭证模式，令牌的主体为客户端本身，不签发刷新令牌及 id_token
+    pub async fn client_credentials(client_id: &str, client_secret: &str, scope: Option<&str>, funs: &TardisFunsInst) -> TardisResult<IamOauth2TokenResp> {
+        let (conf, ext) = Self::authenticate_client(client_id, client_secret, funs).await?;
+        // 公开客户端无法保证密钥安全，不能代表自身获取令牌
+        if ext.public_client {
+            return Err(funs.err().unauthorized("oauth2", "client_credentials", "unauthorized_client", "401-oauth2-unauthorized-client"));
+        }
+        let scope = scope.unwrap_or_default();
+        // 客户端配置了授权范围时，只能申请其中的范围
+        if !ext.scope.is_empty() && !scope.split_whitespace().all(|s| ext.scope.iter().any(|allowed| allowed == s)) {
+            return Err(funs.err().bad_request("oauth2", "client_credentials", "invalid_scope", "400-oauth2-invalid-scope"));
+        }
+        let scope = scope.split_whitespace().filter(|s| *s != SCOPE_OPENID).collect::<Vec<_>>().join(" ");
+        Self::issue_client_token(&conf, &scope, funs).await
+    }
+
+    /// 设备授权（RFC 8628），返回设备码及用户在验证页面输入的用户码
+    pub async fn device_authorization(req: &IamOauth2DeviceAuthorizationReq, funs: &TardisFunsInst) -> TardisResult<IamOauth2DeviceAuthorizationResp> {
+        Self::authenticate_client(&req.client_id, &req.client_secret, funs).await?;
+        let iam_config = funs.conf::<IamConfig>();
+        let device_code = TardisFuns::crypto.key.generate_token()?;
+        let user_code = nanoid::nanoid!(USER_CODE_LEN, &USER_CODE_ALPHABET);
+        let expire_sec = iam_config.oauth2_device_code_expire_sec as u64;
+        let device_code_info = IamOAuth2DeviceCode {
+            client_id: req.client_id.clone(),
+            scope: req.scope.clone().unwrap_or_default(),
+            user_code: user_code.clone(),
+            approved_ctx: None,
+            denied: false,
+            last_poll_at: 0,
+        };
+        funs.cache()
+            .set_ex(
+                &format!("{REDIS_DEVICE_CODE_KEY}{device_code}"),
+                &TardisFuns::json.obj_to_string(&device_code_info)?,
+                expire_sec,
+            )
+            .await?;
+        funs.cache().set_ex(&format!("{REDIS_USER_CODE_KEY}{user_code}"), &device_code, expire_sec).await?;
+        let verification_uri = if iam_config.oauth2_device_verification_uri.is_empty() {
+            format!("{}/device", iam_config.iam_base_url.trim_end_matches('/'))
+        } else {
+            iam_config.oauth2_device_verification_uri.clone()
+        };
+        Ok(IamOauth2DeviceAuthorizationResp {
+            device_code,
+            verification_uri_complete: format!("{verification_uri}{}user_code={user_code}", if verification_uri.contains('?') { '&' } else { '?' }),
+            user_code: format_user_code(&user_code),
+            verification_uri,
+            expires_in: expire_sec as i64,
+            interval: iam_config.oauth2_device_code_interval_sec as i64,
+        })
+    }
+
+    /// 用户在验证页面确认或拒绝设备授权，确认时记录同意
+    pub async fn approve_device(user_code: &str, approve: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let user_code = normalize_user_code(user_code);
+        let device_code = funs
+            .cache()
+            .get(&format!("{REDIS_USER_CODE_KEY}{user_code}"))
+            .await?
+            .ok_or_else(|| funs.err().not_found("oauth2", "approve_device", "invalid or expired user_code", "404-oauth2-invalid-user-code"))?;
+        let device_code_key = format!("{REDIS_DEVICE_CODE_KEY}{device_code}");
+        let mut device_code_info: IamOAuth2DeviceCode = match funs.cache().get(&device_code_key).await? {
+            Some(data) => TardisFuns::json.str_to_obj(&data)?,
+            None => return Err(funs.err().not_found("oauth2", "approve_device", "invalid or expired user_code", "404-oauth2-invalid-user-code")),
+        };
+        if approve {
+            Self::add_consent(&device_code_info.client_id, &device_code_info.scope, funs, ctx).await?;
+            device_code_info.approved_ctx = Some(ctx.clone());
+        } else {
+            device_code_info.denied = true;
+        }
+        // 只能确认一次
+        funs.cache().del(&format!("{REDIS_USER_CODE_KEY}{user_code}")).await?;
+        funs.cache()
+            .set_ex(
+                &device_code_key,
+                &TardisFuns::json.obj_to_string(&device_code_info)?,
+                funs.conf::<IamConfig>().oauth2_device_code_expire_sec as u64,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// 设备轮询令牌端点
+    pub async fn device_code_token(device_code: &str, client_id: &str, client_secret: &str, funs: &TardisFunsInst) -> TardisResult<IamOauth2TokenResp> {
+        let (conf, _) = Self::authenticate_client(client_id, client_secret, funs).await?;
+        let device_code_key = format!("{REDIS_DEVICE_CODE_KEY}{device_code}");
+        let mut device_code_info: IamOAuth2DeviceCode = match funs.cache().get(&device_code_key).await? {
+            Some(data) => TardisFuns::json.str_to_obj(&data)?,
+            None => return Err(funs.err().bad_request("oauth2", "device_code_token", "expired_token", "400-oauth2-expired-token")),
+        };
+        if device_code_info.client_id != client_id {
+            return Err(funs.err().unauthorized("oauth2", "device_code_token", "invalid_client", "401-oauth2-invalid-client"));
+        }
+        if device_code_info.denied {
+            funs.cache().del(&device_code_key).await?;
+            return Err(funs.err().bad_request("oauth2", "device_code_token", "access_denied", "400-oauth2-access-denied"));
+        }
+        let Some(approved_ctx) = device_code_info.approved_ctx.take() else {
+            let iam_config = funs.conf::<IamConfig>();
+            let now = Utc::now().timestamp();
+            let slow_down = now - device_code_info.last_poll_at < iam_config.oauth2_device_code_interval_sec as i64;
+            device_code_info.last_poll_at = now;
+            funs.cache()
+                .set_ex(
+                    &device_code_key,
+                    &TardisFuns::json.obj_to_string(&device_code_info)?,
+                    iam_config.oauth2_device_code_expire_sec as u64,
+                )
+                .await?;
+            return if slow_down {
+                Err(funs.err().bad_request("oauth2", "device_code_token", "slow_down", "400-oauth2-slow-down"))
+            } else {
+                Err(funs.err().bad_request("oauth2", "device_code_token", "authorization_pending", "400-oauth2-authorization-pending"))
+            };
+        };
+        funs.cache().del(&device_code_key).await?;
+        Self::issue_token(&approved_ctx.owner, &conf, &device_code_info.scope, None, true, funs).await
+    }
+
+    /// 生成并存储访问令牌，按需生成刷新令牌，授权范围包含 openid 时签发 id_token
+    async fn issue_token(
+        sub: &str,
+        conf: &RbumCertConfSummaryResp,
+        scope: &str,
+        nonce: Option<&str>,
+        with_refresh_token: bool,
+        funs: &TardisFunsInst,
+    ) -> TardisResult<IamOauth2TokenResp> {
+        let iam_config = funs.conf::<IamConfig>();
+        let access_token = TardisFuns::crypto.key.generate_token()?;
         let access_token_expire_sec = conf.expire_sec;
 
-        // 5. 生成新的访问令牌
-        let new_access_token = TardisFuns::crypto.key.generate_token()?;
+        // 存储访问令牌（复用现有的令牌缓存系统）
+        IamIdentCacheServ::add_token(&access_token, &IamCertTokenKind::TokenOauth2, sub, None, access_token_expire_sec, conf.coexist_num, funs).await?;
+        let access_token_info = IamOAuth2AccessTokenInfo {
+            sub: sub.to_string(),
+            client_id: conf.supplier.clone(),
+            scope: scope.to_string(),
+            client_token: false,
+        };
+        Self::store_access_token_info(&access_token, &access_token_info, access_token_expire_sec, funs).await?;
+        funs.cache().hset(&format!("{REDIS_SUB_TOKENS_KEY}{sub}"), &access_token, &conf.supplier).await?;
 
-        // 6. 存储新的访问令牌
-        IamIdentCacheServ::add_token(
-            &new_access_token,
-            &IamCertTokenKind::TokenOauth2,
-            &refresh_token_info.user_id,
-            None,
-            access_token_expire_sec,
-            conf.coexist_num,
-            funs,
-        )
-        .await?;
+        // 存储刷新令牌
+        let refresh_token = if with_refresh_token {
+            let refresh_token = TardisFuns::crypto.key.generate_token()?;
+            let refresh_token_info = IamOAuth2RefreshTokenInfo {
+                user_id: sub.to_string(),
+                client_id: conf.supplier.clone(),
+                scope: scope.to_string(),
+                expires_at: Utc::now().timestamp() + iam_config.oauth2_refresh_token_expire_sec as i64,
+            };
+            funs.cache()
+                .set_ex(
+                    &format!("{}{}", REDIS_REFRESH_TOKEN_KEY, refresh_token),
+                    &TardisFuns::json.obj_to_string(&refresh_token_info)?,
+                    iam_config.oauth2_refresh_token_expire_sec as u64,
+                )
+                .await?;
+            funs.cache().hset(&format!("{REDIS_SUB_TOKENS_KEY}{sub}"), &refresh_token, &conf.supplier).await?;
+            Some(refresh_token)
+        } else {
+            None
+        };
+
+        let id_token = if has_scope(scope, SCOPE_OPENID) {
+            Some(Self::sign_id_token(sub, &conf.supplier, scope, nonce, funs).await?)
+        } else {
+            None
+        };
 
         Ok(IamOauth2TokenResp {
-            access_token: new_access_token,
+            access_token,
             token_type: Oauth2TokenType::Bearer,
-            expires_in: access_token_expire_sec as i64,
-            refresh_token: Some(req.refresh_token.clone()), // 保持相同的刷新令牌
-            scope: Some(refresh_token_info.scope),
+            expires_in: access_token_expire_sec,
+            refresh_token,
+            scope: Some(scope.to_string()),
+            id_token,
         })
     }
 
+    /// 签发客户端凭证模式的访问令牌
+    ///
+    /// 令牌的主体为客户端本身，不写入账号的令牌缓存，因此不能作为账号的登录令牌，也不能获取用户信息。
+    async fn issue_client_token(conf: &RbumCertConfSummaryResp, scope: &str, funs: &TardisFunsInst) -> TardisResult<IamOauth2TokenResp> {
+        let access_token = TardisFuns::crypto.key.generate_token()?;
+        let access_token_info = IamOAuth2AccessTokenInfo {
+            sub: conf.supplier.clone(),
+            client_id: conf.supplier.clone(),
+            scope: scope.to_string(),
+            client_token: true,
+        };
+        Self::store_access_token_info(&access_token, &access_token_info, conf.expire_sec, funs).await?;
+        Ok(IamOauth2TokenResp {
+            access_token,
+            token_type: Oauth2TokenType::Bearer,
+            expires_in: conf.expire_sec,
+            refresh_token: None,
+            scope: Some(scope.to_string()),
+            id_token: None,
+        })
+    }
+
+    async fn store_access_token_info(access_token: &str, access_token_info: &IamOAuth2AccessTokenInfo, expire_sec: i64, funs: &TardisFunsInst) -> TardisResult<()> {
+        let access_token_key = format!("{REDIS_ACCESS_TOKEN_KEY}{access_token}");
+        if expire_sec > 0 {
+            funs.cache().set_ex(&access_token_key, &TardisFuns::json.obj_to_string(access_token_info)?, expire_sec as u64).await?;
+        } else {
+            funs.cache().set(&access_token_key, &TardisFuns::json.obj_to_string(access_token_info)?).await?;
+        }
+        Ok(())
+    }
+
+    /// 撤销令牌（RFC 7009），令牌不存在或已失效时同样视为成功
+    pub async fn revoke(req: &IamOauth2RevokeReq, funs: &TardisFunsInst) -> TardisResult<()> {
+        Self::authenticate_client(&req.client_id, &req.client_secret, funs).await?;
+        if let Some(refresh_token_info) = funs.cache().get(&format!("{REDIS_REFRESH_TOKEN_KEY}{}", req.token)).await? {
+            let refresh_token_info = TardisFuns::json.str_to_obj::<IamOAuth2RefreshTokenInfo>(&refresh_token_info)?;
+            if refresh_token_info.client_id == req.client_id {
+                Self::remove_token(&req.token, &refresh_token_info.user_id, funs).await?;
+            }
+        } else if let Some(access_token_info) = funs.cache().get(&format!("{REDIS_ACCESS_TOKEN_KEY}{}", req.token)).await? {
+            let access_token_info = TardisFuns::json.str_to_obj::<IamOAuth2AccessTokenInfo>(&access_token_info)?;
+            if access_token_info.client_id == req.client_id {
+                Self::remove_token(&req.token, &access_token_info.sub, funs).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 删除访问令牌或刷新令牌
+    async fn remove_token(token: &str, sub: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        let iam_config = funs.conf::<IamConfig>();
+        funs.cache().del(&format!("{REDIS_REFRESH_TOKEN_KEY}{token}")).await?;
+        funs.cache().del(&format!("{REDIS_ACCESS_TOKEN_KEY}{token}")).await?;
+        funs.cache().del(&format!("{}{}", iam_config.cache_key_token_info_, token)).await?;
+        funs.cache().hdel(&format!("{}{}", iam_config.cache_key_account_rel_, sub), token).await?;
+        funs.cache().hdel(&format!("{REDIS_SUB_TOKENS_KEY}{sub}"), token).await?;
+        Ok(())
+    }
+
     /// 保持向后兼容的简化方法
     pub async fn verify_code(add_req: &IamCertOAuth2ServiceCodeVerifyReq, funs: &TardisFunsInst) -> TardisResult<String> {
         let token_resp = Self::verify_code_and_generate_token(add_req, funs).await?;
@@ -403,6 +692,20 @@ impl IamCertOAuth2ServiceServ {
         Self::build_userinfo_by_account_id(&account_id, None, funs).await
     }
 
+    /// 根据访问令牌返回 OpenID Connect 标准声明，按令牌的授权范围返回
+    pub async fn get_userinfo_claims(access_token: &str, funs: &TardisFunsInst) -> TardisResult<Value> {
+        let account_id = Self::resolve_account_id_by_access_token(access_token, funs).await?;
+        let scope = match funs.cache().get(&format!("{REDIS_ACCESS_TOKEN_KEY}{access_token}")).await? {
+            Some(access_token_info) => TardisFuns::json.str_to_obj::<IamOAuth2AccessTokenInfo>(&access_token_info)?.scope,
+            None => return Err(funs.err().unauthorized("oauth2", "userinfo", "invalid_or_expired_token", "401-oauth2-invalid-token")),
+        };
+        let userinfo = Self::build_userinfo_by_account_id(&account_id, None, funs).await?;
+        let mut claims = Map::new();
+        claims.insert("sub".to_string(), json!(userinfo.sub));
+        fill_userinfo_claims(&mut claims, userinfo, &scope);
+        Ok(Value::Object(claims))
+    }
+
     /// 根据账号 ID 构建用户信息（供基于登录上下文 `TardisContext` 的 userinfo 端点复用）
     ///
     /// `tenant_id_override` 用于指定返回的 `tenant_id`：基于登录上下文时传入 `ctx.own_paths`，
@@ -465,17 +768,448 @@ impl IamCertOAuth2ServiceServ {
 
     /// 令牌内省（Provider 侧 introspect 端点的服务实现）
     pub async fn introspect(token: &str, funs: &TardisFunsInst) -> TardisResult<IamOauth2IntrospectResp> {
+        let access_token_info = match funs.cache().get(&format!("{REDIS_ACCESS_TOKEN_KEY}{token}")).await? {
+            Some(access_token_info) => Some(TardisFuns::json.str_to_obj::<IamOAuth2AccessTokenInfo>(&access_token_info)?),
+            None => None,
+        };
+        if let Some(access_token_info) = access_token_info.as_ref().filter(|info| info.client_token) {
+            return Ok(IamOauth2IntrospectResp {
+                active: true,
+                provider: Some(OAUTH2_PROVIDER.to_string()),
+                sub: Some(access_token_info.sub.clone()),
+                client_id: Some(access_token_info.client_id.clone()),
+                scope: Some(access_token_info.scope.clone()),
+            });
+        }
         match Self::resolve_account_id_by_access_token(token, funs).await {
             Ok(account_id) => Ok(IamOauth2IntrospectResp {
                 active: true,
                 provider: Some(OAUTH2_PROVIDER.to_string()),
                 sub: Some(account_id),
+                client_id: access_token_info.as_ref().map(|info| info.client_id.clone()),
+                scope: access_token_info.map(|info| info.scope),
             }),
             Err(_) => Ok(IamOauth2IntrospectResp {
                 active: false,
                 provider: None,
                 sub: None,
+                client_id: None,
+                scope: None,
             }),
         }
     }
+
+    /// 签发授权码前是否需要账号同意，平台自有客户端或账号已同意全部授权范围时无需同意
+    pub async fn need_consent(client_id: &str, scope: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+        let conf = Self::get_cert_conf_by_client_id(client_id, funs).await?;
+        let ext = TardisFuns::json.str_to_obj::<IamCertConfOAuth2ServiceExt>(&conf.ext)?;
+        Ok(!ext.first_party && !Self::consent_granted(&conf.id, scope, funs, ctx).await?)
+    }
+
+    /// 账号是否已同意客户端申请的全部授权范围
+    async fn consent_granted(cert_conf_id: &str, scope: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+        Ok(Self::find_consent_cert(cert_conf_id, &ctx.owner, funs).await?.map(|cert| scope.split_whitespace().all(|s| has_scope(&cert.ext, s))).unwrap_or(false))
+    }
+
+    /// 记录账号对客户端的同意，已有记录时合并授权范围
+    ///
+    /// 同意记录为关联在客户端配置下的凭证，ak 为账号 ID，ext 为已同意的授权范围。
+    pub async fn add_consent(client_id: &str, scope: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let conf = Self::get_cert_conf_by_client_id(client_id, funs).await?;
+        // 客户端通常由平台创建，以客户端的所属路径写入
+        let mock_ctx = TardisContext {
+            own_paths: conf.own_paths.clone(),
+            owner: ctx.owner.clone(),
+            ..Default::default()
+        };
+        if let Some(cert) = Self::find_consent_cert(&conf.id, &ctx.owner, funs).await? {
+            let scope = merge_scope(&cert.ext, scope);
+            if scope != cert.ext {
+                RbumCertServ::modify_rbum(
+                    &cert.id,
+                    &mut RbumCertModifyReq {
+                        ak: None,
+                        sk: None,
+                        sk_invisible: None,
+                        ignore_check_sk: false,
+                        ext: Some(scope),
+                        start_time: Some(Utc::now()),
+                        end_time: None,
+                        conn_uri: None,
+                        status: None,
+                    },
+                    funs,
+                    &mock_ctx,
+                )
+                .await?;
+            }
+            return Ok(());
+        }
+        RbumCertServ::add_rbum(
+            &mut RbumCertAddReq {
+                ak: TrimString(ctx.owner.clone()),
+                sk: None,
+                sk_invisible: None,
+                kind: Some(IamCertExtKind::OAuth2Service.to_string()),
+                supplier: Some(client_id.to_string()),
+                vcode: None,
+                ext: Some(merge_scope("", scope)),
+                start_time: None,
+                // 同意在用户撤销前一直有效，不随客户端令牌的有效期过期
+                end_time: Some(Utc::now() + Duration::try_days(365 * 100).expect("TimeDelta::days out of bounds")),
+                conn_uri: None,
+                status: RbumCertStatusKind::Enabled,
+                rel_rbum_cert_conf_id: Some(conf.id),
+                rel_rbum_kind: RbumCertRelKind::Item,
+                rel_rbum_id: ctx.owner.clone(),
+                // 账号可能属于客户端所在路径的下级租户
+                is_outside: true,
+                ignore_check_sk: false,
+            },
+            funs,
+            &mock_ctx,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// 列出当前账号的同意记录
+    pub async fn find_consents(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<IamOauth2ConsentResp>> {
+        let certs = RbumCertServ::find_rbums(
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    own_paths: Some("".to_string()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                kind: Some(IamCertExtKind::OAuth2Service.to_string()),
+                rel_rbum_id: Some(ctx.owner.clone()),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            &TardisContext::default(),
+        )
+        .await?;
+        let mut consents = Vec::new();
+        for cert in certs {
+            // 客户端已删除的记录不再返回
+            let Ok(conf) = Self::get_cert_conf_by_client_id(&cert.supplier, funs).await else {
+                continue;
+            };
+            consents.push(IamOauth2ConsentResp {
+                client_id: cert.supplier,
+                client_name: conf.name,
+                scope: cert.ext,
+                consent_time: cert.start_time,
+            });
+        }
+        Ok(consents)
+    }
+
+    /// 撤销当前账号对客户端的同意，并撤销已签发给该客户端的令牌
+    pub async fn delete_consent(client_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let conf = Self::get_cert_conf_by_client_id(client_id, funs).await?;
+        let cert = Self::find_consent_cert(&conf.id, &ctx.owner, funs)
+            .await?
+            .ok_or_else(|| funs.err().not_found("oauth2", "delete_consent", "consent not found", "404-oauth2-consent-not-exist"))?;
+        let mock_ctx = TardisContext {
+            own_paths: conf.own_paths.clone(),
+            owner: ctx.owner.clone(),
+            ..Default::default()
+        };
+        RbumCertServ::delete_rbum(&cert.id, funs, &mock_ctx).await?;
+        let tokens = funs.cache().hgetall(&format!("{REDIS_SUB_TOKENS_KEY}{}", ctx.owner)).await?;
+        for (token, token_client_id) in tokens {
+            if token_client_id == client_id {
+                Self::remove_token(&token, &ctx.owner, funs).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn find_consent_cert(cert_conf_id: &str, account_id: &str, funs: &TardisFunsInst) -> TardisResult<Option<RbumCertSummaryResp>> {
+        let certs = RbumCertServ::find_rbums(
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    own_paths: Some("".to_string()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ak: Some(account_id.to_string()),
+                rel_rbum_id: Some(account_id.to_string()),
+                rel_rbum_cert_conf_ids: Some(vec![cert_conf_id.to_string()]),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            &TardisContext::default(),
+        )
+        .await?;
+        Ok(certs.into_iter().next())
+    }
+
+    /// 签发 id_token，声明取自账号信息，按授权范围返回
+    async fn sign_id_token(account_id: &str, client_id: &str, scope: &str, nonce: Option<&str>, funs: &TardisFunsInst) -> TardisResult<String> {
+        let iam_config = funs.conf::<IamConfig>();
+        let userinfo = Self::build_userinfo_by_account_id(account_id, None, funs).await?;
+        let now = Utc::now().timestamp();
+        let mut claims = Map::new();
+        claims.insert("iss".to_string(), json!(iam_config.iam_base_url));
+        claims.insert("sub".to_string(), json!(userinfo.sub));
+        claims.insert("aud".to_string(), json!(client_id));
+        claims.insert("iat".to_string(), json!(now));
+        claims.insert("exp".to_string(), json!(now + iam_config.oauth2_id_token_expire_sec as i64));
+        if let Some(nonce) = nonce {
+            claims.insert("nonce".to_string(), json!(nonce));
+        }
+        fill_userinfo_claims(&mut claims, userinfo, scope);
+        let signing_key = Self::get_signing_key(funs).await?;
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(signing_key.kid);
+        jsonwebtoken::encode(&header, &claims, &signing_key.encoding_key)
+            .map_err(|e| funs.err().internal_error("oauth2", "sign_id_token", &format!("failed to sign id_token: {e}"), "500-oauth2-sign-id-token-error"))
+    }
+
+    /// 获取签发 id_token 的密钥，未配置时使用数据库中加密保存的各节点共用密钥
+    async fn get_signing_key(funs: &TardisFunsInst) -> TardisResult<IamOAuth2SigningKey> {
+        let iam_config = funs.conf::<IamConfig>();
+        let pem = if !iam_config.oauth2_signing_private_key.is_empty() {
+            iam_config.oauth2_signing_private_key.clone()
+        } else {
+            Self::load_signing_key(funs).await?
+        };
+        parse_signing_key(&pem).map_err(|e| funs.err().internal_error("oauth2", "get_signing_key", &format!("invalid signing key: {e}"), "500-oauth2-signing-key-error"))
+    }
+
+    /// 读取数据库中的签名密钥，不存在则生成
+    ///
+    /// 密钥保存为平台级凭证，sk 为 `iv:密文`，以 `oauth2_signing_key_secret` 经 SM4 加密。
+    /// 其他节点可能同时生成了密钥，以最早写入的为准。
+    async fn load_signing_key(funs: &TardisFunsInst) -> TardisResult<String> {
+        let secret = &funs.conf::<IamConfig>().oauth2_signing_key_secret;
+        if secret.len() != 16 {
+            return Err(funs.err().internal_error(
+                "oauth2",
+                "get_signing_key",
+                "oauth2_signing_private_key or a 16-character oauth2_signing_key_secret is required",
+                "500-oauth2-signing-key-error",
+            ));
+        }
+        let global_ctx = TardisContext::default();
+        let filter = RbumCertFilterReq {
+            basic: RbumBasicFilterReq {
+                own_paths: Some("".to_string()),
+                ..Default::default()
+            },
+            kind: Some(IamCertExtKind::OAuth2Service.to_string()),
+            suppliers: Some(vec![SIGNING_KEY_SUPPLIER.to_string()]),
+            ..Default::default()
+        };
+        let cert_id = match RbumCertServ::find_id_rbums(&filter, Some(false), None, funs, &global_ctx).await?.into_iter().next() {
+            Some(cert_id) => cert_id,
+            None => {
+                let pem = generate_signing_key_pem()
+                    .map_err(|e| funs.err().internal_error("oauth2", "get_signing_key", &format!("failed to generate signing key: {e}"), "500-oauth2-signing-key-error"))?;
+                RbumCertServ::add_rbum(
+                    &mut RbumCertAddReq {
+                        ak: TrimString(SIGNING_KEY_SUPPLIER.to_string()),
+                        sk: Some(TrimString(encrypt_signing_key_pem(&pem, secret)?)),
+                        sk_invisible: Some(true),
+                        kind: Some(IamCertExtKind::OAuth2Service.to_string()),
+                        supplier: Some(SIGNING_KEY_SUPPLIER.to_string()),
+                        vcode: None,
+                        ext: None,
+                        start_time: None,
+                        end_time: None,
+                        conn_uri: None,
+                        status: RbumCertStatusKind::Enabled,
+                        rel_rbum_cert_conf_id: None,
+                        rel_rbum_kind: RbumCertRelKind::Item,
+                        rel_rbum_id: "".to_string(),
+                        is_outside: true,
+                        ignore_check_sk: true,
+                    },
+                    funs,
+                    &global_ctx,
+                )
+                .await?;
+                RbumCertServ::find_id_rbums(&filter, Some(false), None, funs, &global_ctx)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| funs.err().internal_error("oauth2", "get_signing_key", "signing key not found", "500-oauth2-signing-key-error"))?
+            }
+        };
+        let sk = RbumCertServ::show_sk(&cert_id, &filter, funs, &global_ctx).await?;
+        decrypt_signing_key_pem(&sk, secret)
+    }
+
+    /// JWKS 端点返回的公钥集合
+    pub async fn get_jwks(funs: &TardisFunsInst) -> TardisResult<Value> {
+        let signing_key = Self::get_signing_key(funs).await?;
+        Ok(json!({
+            "keys": [{
+                "kty": "RSA",
+                "use": "sig",
+                "alg": "RS256",
+                "kid": signing_key.kid,
+                "n": signing_key.n,
+                "e": signing_key.e,
+            }]
+        }))
+    }
+
+    /// OpenID Connect 发现文档，端点地址基于 `iam_base_url`
+    pub fn get_discovery(funs: &TardisFunsInst) -> Value {
+        let issuer = funs.conf::<IamConfig>().iam_base_url.trim_end_matches('/').to_string();
+        json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/cp/oauth2/authorize"),
+            "token_endpoint": format!("{issuer}/cp/oidc/token"),
+            "userinfo_endpoint": format!("{issuer}/cp/oidc/userinfo"),
+            "jwks_uri": format!("{issuer}/cp/oidc/jwks"),
+            "revocation_endpoint": format!("{issuer}/cp/oidc/revoke"),
+            "introspection_endpoint": format!("{issuer}/cp/oauth2/introspect"),
+            "device_authorization_endpoint": format!("{issuer}/cp/oidc/device_authorization"),
+            "response_types_supported": ["code"],
+            "grant_types_supported": [
+                Oauth2GrantType::AuthorizationCode.to_string(),
+                Oauth2GrantType::RefreshToken.to_string(),
+                Oauth2GrantType::ClientCredentials.to_string(),
+                Oauth2GrantType::DeviceCode.to_string(),
+            ],
+            "subject_types_supported": ["public"],
+            "id_token_signing_alg_values_supported": ["RS256"],
+            "scopes_supported": [SCOPE_OPENID, "profile", "email", "phone"],
+            "claims_supported": ["sub", "iss", "aud", "exp", "iat", "nonce", "name", "tenant_id", "email", "email_verified", "phone_number", "phone_number_verified"],
+            "token_endpoint_auth_methods_supported": ["client_secret_basic", "client_secret_post", "none"],
+            "code_challenge_methods_supported": [PKCE_METHOD_S256],
+        })
+    }
+}
+
+/// 按授权范围填充标准用户声明，`sub` 由调用方填充
+fn fill_userinfo_claims(claims: &mut Map<String, Value>, userinfo: IamOauth2UserInfoResp, scope: &str) {
+    if has_scope(scope, "profile") {
+        claims.insert("name".to_string(), json!(userinfo.name));
+        claims.insert("tenant_id".to_string(), json!(userinfo.tenant_id));
+    }
+    if let Some(mail) = userinfo.mail.filter(|_| has_scope(scope, "email")) {
+        claims.insert("email".to_string(), json!(mail));
+        claims.insert("email_verified".to_string(), json!(true));
+    }
+    if let Some(phone) = userinfo.phone.filter(|_| has_scope(scope, "phone")) {
+        claims.insert("phone_number".to_string(), json!(phone));
+        claims.insert("phone_number_verified".to_string(), json!(true));
+    }
+}
+
+fn has_scope(scope: &str, expected: &str) -> bool {
+    scope.split_whitespace().any(|s| s == expected)
+}
+
+/// 合并授权范围，去重并保持顺序
+fn merge_scope(scope: &str, added: &str) -> String {
+    let mut seen = HashSet::new();
+    scope.split_whitespace().chain(added.split_whitespace()).filter(|s| seen.insert(*s)).collect::<Vec<_>>().join(" ")
+}
+
+/// 用户码以 `XXXX-XXXX` 的形式展示
+fn format_user_code(user_code: &str) -> String {
+    let (head, tail) = user_code.split_at(user_code.len() / 2);
+    format!("{head}-{tail}")
+}
+
+/// 忽略用户输入的分隔符、空格及大小写
+fn normalize_user_code(user_code: &str) -> String {
+    user_code.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_uppercase()).collect()
+}
+
+fn generate_signing_key_pem() -> Result<String, String> {
+    let key = RsaPrivateKey::new(&mut rand::thread_rng(), SIGNING_KEY_BITS).map_err(|e| e.to_string())?;
+    Ok(key.to_pkcs8_pem(LineEnding::LF).map_err(|e| e.to_string())?.to_string())
+}
+
+/// 加密签名私钥，返回 `iv:密文`
+fn encrypt_signing_key_pem(pem: &str, secret: &str) -> TardisResult<String> {
+    let iv = TardisFuns::crypto.key.rand_16_hex();
+    Ok(format!("{iv}:{}", TardisFuns::crypto.sm4.encrypt_cbc(pem, secret, &iv)?))
+}
+
+fn decrypt_signing_key_pem(encrypted: &str, secret: &str) -> TardisResult<String> {
+    let (iv, encrypted_pem) = encrypted.split_once(':').ok_or_else(|| TardisError::internal_error("invalid signing key", "500-oauth2-signing-key-error"))?;
+    TardisFuns::crypto.sm4.decrypt_cbc(encrypted_pem, secret, iv)
+}
+
+/// 支持 PKCS#8 及 PKCS#1 格式的 PEM
+fn parse_signing_key(pem: &str) -> Result<IamOAuth2SigningKey, String> {
+    let key = RsaPrivateKey::from_pkcs8_pem(pem).or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem)).map_err(|e| e.to_string())?;
+    let encoding_key = EncodingKey::from_rsa_der(key.to_pkcs1_der().map_err(|e| e.to_string())?.as_bytes());
+    let n = iam_cert_oidc_serv::base64_url_encode(key.n().to_bytes_be());
+    let e = iam_cert_oidc_serv::base64_url_encode(key.e().to_bytes_be());
+    // 以公钥摘要作为 kid，密钥轮换后客户端可据此重新获取
+    let kid = TardisFuns::crypto.digest.sha256(&format!("{n}.{e}")).map_err(|e| e.to_string())?.chars().take(16).collect();
+    Ok(IamOAuth2SigningKey { kid, encoding_key, n, e })
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::jwk::JwkSet;
+    use jsonwebtoken::{Algorithm, DecodingKey, Header, Validation};
+    use tardis::serde_json::{json, Map, Value};
+    use tardis::TardisFuns;
+
+    use super::{
+        decrypt_signing_key_pem, encrypt_signing_key_pem, format_user_code, generate_signing_key_pem, has_scope, merge_scope, normalize_user_code, parse_signing_key,
+        USER_CODE_ALPHABET, USER_CODE_LEN,
+    };
+
+    #[test]
+    fn test_scope() {
+        assert!(has_scope("openid profile", "openid"));
+        assert!(!has_scope("openid profile", "email"));
+        assert!(!has_scope("openid_extra", "openid"));
+        assert_eq!(merge_scope("openid profile", "profile email"), "openid profile email");
+        assert_eq!(merge_scope("", " openid  openid "), "openid");
+    }
+
+    #[test]
+    fn test_user_code() {
+        let user_code = nanoid::nanoid!(USER_CODE_LEN, &USER_CODE_ALPHABET);
+        let formatted = format_user_code(&user_code);
+        assert_eq!(formatted.len(), USER_CODE_LEN + 1);
+        assert_eq!(normalize_user_code(&formatted), user_code);
+        assert_eq!(normalize_user_code(" bcdf-ghjk "), "BCDFGHJK");
+    }
+
+    #[test]
+    fn test_signing_key() {
+        let signing_key = parse_signing_key(&generate_signing_key_pem().unwrap()).unwrap();
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(signing_key.kid.clone());
+        let claims = json!({ "iss": "http://127.0.0.1:8080/iam", "sub": "account1", "aud": "client1", "exp": tardis::chrono::Utc::now().timestamp() + 60 });
+        let id_token = jsonwebtoken::encode(&header, &claims, &signing_key.encoding_key).unwrap();
+
+        // verify by the published key, as the clients do
+        let jwks: JwkSet = TardisFuns::json
+            .json_to_obj(json!({ "keys": [{ "kty": "RSA", "use": "sig", "alg": "RS256", "kid": signing_key.kid, "n": signing_key.n, "e": signing_key.e }] }))
+            .unwrap();
+        let jwk = jwks.find(&signing_key.kid).unwrap();
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&["client1"]);
+        let verified = jsonwebtoken::decode::<Map<String, Value>>(&id_token, &DecodingKey::from_jwk(jwk).unwrap(), &validation).unwrap();
+        assert_eq!(verified.claims.get("sub"), Some(&json!("account1")));
+    }
+
+    #[test]
+    fn test_encrypt_signing_key() {
+        let pem = generate_signing_key_pem().unwrap();
+        let encrypted = encrypt_signing_key_pem(&pem, "0123456789abcdef").unwrap();
+        assert!(!encrypted.contains("PRIVATE KEY"));
+        assert_eq!(decrypt_signing_key_pem(&encrypted, "0123456789abcdef").unwrap(), pem);
+        assert!(decrypt_signing_key_pem(&encrypted, "fedcba9876543210").map(|decrypted| decrypted != pem).unwrap_or(true));
+    }
 }
@@ -0,0 +1,720 @@
+use bios_basic::rbum::dto::rbum_cert_conf_dto::{RbumCertConfAddReq, RbumCertConfDetailResp, RbumCertConfModifyReq};
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertConfFilterReq};
+use bios_basic::rbum::rbum_enumeration::{RbumCertConfStatusKind, RbumCertStatusKind, RbumScopeLevelKind};
+use bios_basic::rbum::serv::rbum_cert_serv::RbumCertConfServ;
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::log::trace;
+use tardis::serde_json::{Map, Value};
+use tardis::{TardisFuns, TardisFunsInst};
+
+use crate::basic::dto::iam_account_dto::IamAccountAggAddReq;
+use crate::basic::dto::iam_cert_conf_dto::{IamCertConfOidcAddOrModifyReq, IamCertConfOidcResp, IamCertOidcClaimMap};
+use crate::basic::dto::iam_cert_dto::{IamCertMailVCodeAddReq, IamCertOAuth2AddOrModifyReq, IamCertOidcAuthorizeResp, IamCertPhoneVCodeAddReq};
+use crate::basic::serv::clients::iam_search_client::IamSearchClient;
+use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
+use crate::basic::serv::iam_cert_oauth2_serv::IamCertOAuth2Serv;
+use crate::basic::serv::iam_cert_phone_vcode_serv::IamCertPhoneVCodeServ;
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::uri_encode;
+use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use crate::iam_config::{IamBasicConfigApi, IamConfig};
+use crate::iam_enumeration::{IamCertExtKind, IamCertKernelKind};
+
+const DEFAULT_SCOPE: &str = "openid profile email";
+/// RFC 7636 unreserved characters, without `.` and `~` to keep 64 of them
+const CODE_VERIFIER_ALPHABET: [char; 64] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i',
+    'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '-', '_',
+];
+const CODE_VERIFIER_LEN: usize = 64;
+
+/// The fields of the discovery document used here
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct OidcProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+/// Cached by the issuer, the keys are fetched again when an unknown key id shows up
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct OidcProvider {
+    metadata: OidcProviderMetadata,
+    jwks: JwkSet,
+}
+
+/// Kept in the cache between the authorization request and the callback
+#[derive(Serialize, Deserialize, Debug)]
+struct OidcState {
+    cert_conf_id: String,
+    tenant_id: String,
+    nonce: String,
+    code_verifier: String,
+    /// Set when the identity is bound to a logged-in account instead of logging in
+    bind_account_id: Option<String>,
+}
+
+/// The account fields mapped from the claims of the id token
+#[derive(Debug, PartialEq, Eq)]
+struct OidcAccountInfo {
+    open_id: String,
+    name: Option<String>,
+    user_name: Option<String>,
+    mail: Option<String>,
+    phone: Option<String>,
+}
+
+pub struct IamCertOidcServ;
+
+impl IamCertOidcServ {
+    pub async fn add_cert_conf(add_req: &IamCertConfOidcAddOrModifyReq, rel_iam_item_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        RbumCertConfServ::add_rbum(
+            &mut RbumCertConfAddReq {
+                kind: TrimString(IamCertExtKind::Oidc.to_string()),
+                supplier: Some(add_req.supplier.clone()),
+                name: TrimString(add_req.name.clone()),
+                note: None,
+                ak_note: None,
+                ak_rule: None,
+                sk_note: None,
+                sk_rule: None,
+                ext: Some(TardisFuns::json.obj_to_string(add_req)?),
+                sk_need: Some(false),
+                sk_dynamic: Some(false),
+                sk_encrypted: Some(false),
+                repeatable: None,
+                is_basic: Some(false),
+                rest_by_kinds: None,
+                expire_sec: None,
+                sk_lock_cycle_sec: None,
+                sk_lock_err_times: None,
+                sk_lock_duration_sec: None,
+                coexist_num: Some(1),
+                conn_uri: Some(add_req.issuer.clone()),
+                status: RbumCertConfStatusKind::Enabled,
+                rel_rbum_domain_id: funs.iam_basic_domain_iam_id(),
+                rel_rbum_item_id: rel_iam_item_id,
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    /// Enable the disabled conf of the provider in the scope, or add a new one
+    pub async fn add_or_enable_cert_conf(add_req: &IamCertConfOidcAddOrModifyReq, rel_iam_item_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let cert_conf = RbumCertConfServ::do_find_one_rbum(
+            &RbumCertConfFilterReq {
+                kind: Some(TrimString(IamCertExtKind::Oidc.to_string())),
+                supplier: Some(add_req.supplier.to_string()),
+                rel_rbum_item_id: Some(rel_iam_item_id.to_string()),
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        if let Some(cert_conf) = cert_conf {
+            IamCertServ::enabled_cert_conf(&cert_conf.id, funs, ctx).await?;
+            Self::modify_cert_conf(&cert_conf.id, add_req, funs, ctx).await?;
+            Ok(cert_conf.id)
+        } else {
+            Self::add_cert_conf(add_req, Some(rel_iam_item_id.to_string()), funs, ctx).await
+        }
+    }
+
+    pub async fn modify_cert_conf(id: &str, modify_req: &IamCertConfOidcAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let mut ext = modify_req.clone();
+        if ext.client_secret.is_none() {
+            ext.client_secret = Self::get_cert_conf_ext(id, funs, ctx).await?.client_secret;
+        }
+        RbumCertConfServ::modify_rbum(
+            id,
+            &mut RbumCertConfModifyReq {
+                name: Some(TrimString(modify_req.name.clone())),
+                note: None,
+                ak_note: None,
+                ak_rule: None,
+                sk_note: None,
+                sk_rule: None,
+                ext: Some(TardisFuns::json.obj_to_string(&ext)?),
+                sk_need: None,
+                sk_encrypted: None,
+                repeatable: None,
+                is_basic: None,
+                rest_by_kinds: None,
+                expire_sec: None,
+                sk_lock_cycle_sec: None,
+                sk_lock_err_times: None,
+                sk_lock_duration_sec: None,
+                coexist_num: None,
+                conn_uri: Some(modify_req.issuer.clone()),
+                status: None,
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    /// The client secret is not returned
+    pub async fn get_cert_conf(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertConfOidcResp> {
+        let ext = Self::get_cert_conf_ext(id, funs, ctx).await?;
+        Ok(IamCertConfOidcResp {
+            id: id.to_string(),
+            supplier: ext.supplier.to_string(),
+            name: ext.name,
+            issuer: ext.issuer,
+            client_id: ext.client_id.to_string(),
+            redirect_uri: ext.redirect_uri,
+            scope: ext.scope,
+            claim_map: ext.claim_map,
+            auto_create_account: ext.auto_create_account,
+        })
+    }
+
+    /// Find the conf of the provider in the tenant, then in the platform
+    pub async fn get_cert_conf_id(supplier: &str, tenant_id: &str, funs: &TardisFunsInst) -> TardisResult<String> {
+        let kind = IamCertExtKind::Oidc.to_string();
+        if !tenant_id.is_empty() {
+            if let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind_supplier(&kind, supplier, Some(tenant_id.to_string()), funs).await? {
+                return Ok(cert_conf.id);
+            }
+        }
+        IamCertServ::get_cert_conf_id_by_kind_supplier(&kind, supplier, None, funs).await
+    }
+
+    /// Start the authorization code flow, the browser is redirected to the returned url
+    ///
+    /// When `bind_account_id` is given, the identity is bound to that account by [`Self::bind`] instead of logging in.
+    pub async fn authorize(supplier: &str, tenant_id: &str, bind_account_id: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamCertOidcAuthorizeResp> {
+        let cert_conf_id = Self::get_cert_conf_id(supplier, tenant_id, funs).await?;
+        let cert_conf = Self::get_cert_conf_ext(&cert_conf_id, funs, &TardisContext::default()).await?;
+        let provider = Self::get_provider(&cert_conf.issuer, false, funs).await?;
+        let state = OidcState {
+            cert_conf_id,
+            tenant_id: tenant_id.to_string(),
+            nonce: TardisFuns::field.nanoid(),
+            code_verifier: nanoid::nanoid!(CODE_VERIFIER_LEN, &CODE_VERIFIER_ALPHABET),
+            bind_account_id,
+        };
+        let state_id = TardisFuns::field.nanoid();
+        let authorize_url = authorize_url(
+            &provider.metadata.authorization_endpoint,
+            &cert_conf,
+            &state_id,
+            &state.nonce,
+            &pkce_challenge(&state.code_verifier)?,
+        );
+        funs.cache()
+            .set_ex(
+                &format!("{}{}", funs.conf::<IamConfig>().cache_key_oidc_state_, state_id),
+                &TardisFuns::json.obj_to_string(&state)?,
+                funs.conf::<IamConfig>().oidc_state_expire_sec,
+            )
+            .await?;
+        Ok(IamCertOidcAuthorizeResp { state: state_id, authorize_url })
+    }
+
+    /// Login by the code returned to the redirect uri, return the account id and the tenant id
+    ///
+    /// An unknown user gets a new account in the tenant when the provider allows it.
+    pub async fn login(code: &str, state_id: &str, funs: &TardisFunsInst) -> TardisResult<(String, String)> {
+        let state = Self::take_state(state_id, funs).await?;
+        if state.bind_account_id.is_some() {
+            return Err(Self::state_err("login", funs));
+        }
+        let cert_conf = Self::get_cert_conf_ext(&state.cert_conf_id, funs, &TardisContext::default()).await?;
+        let account_info = Self::exchange_code(code, &state, &cert_conf, funs).await?;
+        let mock_ctx = TardisContext {
+            own_paths: state.tenant_id.clone(),
+            ..Default::default()
+        };
+        if let Some(account_id) = IamCertOAuth2Serv::get_cert_rel_account_by_open_id(&account_info.open_id, &state.cert_conf_id, funs, &mock_ctx).await? {
+            return Ok((account_id, state.tenant_id));
+        }
+        if !cert_conf.auto_create_account {
+            return Err(funs.err().unauthorized(
+                "iam_cert_oidc",
+                "login",
+                &format!("oidc user {} is not bound to any account", account_info.open_id),
+                "401-iam-cert-oidc-account-not-bound",
+            ));
+        }
+        // the tenant is chosen by the caller, a provider may only create accounts in the tenant it belongs to
+        if Self::get_cert_conf_rel_tenant_id(&state.cert_conf_id, funs).await? != state.tenant_id {
+            return Err(funs.err().unauthorized(
+                "iam_cert_oidc",
+                "login",
+                &format!("oidc user {} is not bound to any account of the tenant", account_info.open_id),
+                "401-iam-cert-oidc-account-not-bound",
+            ));
+        }
+        let account_id = Self::add_account(&account_info, &state.cert_conf_id, &state.tenant_id, funs).await?;
+        Ok((account_id, state.tenant_id))
+    }
+
+    /// Bind the identity to the current account, return the open id
+    pub async fn bind(code: &str, state_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let state = Self::take_state(state_id, funs).await?;
+        if state.bind_account_id.as_deref() != Some(ctx.owner.as_str()) {
+            return Err(Self::state_err("bind", funs));
+        }
+        let cert_conf = Self::get_cert_conf_ext(&state.cert_conf_id, funs, ctx).await?;
+        let account_info = Self::exchange_code(code, &state, &cert_conf, funs).await?;
+        if let Some(bound_account_id) = IamCertOAuth2Serv::get_cert_rel_account_by_open_id(&account_info.open_id, &state.cert_conf_id, funs, ctx).await? {
+            if bound_account_id != ctx.owner {
+                return Err(funs.err().conflict(
+                    "iam_cert_oidc",
+                    "bind",
+                    &format!("oidc user {} has already been bound to another account", account_info.open_id),
+                    "409-iam-cert-oidc-already-bound",
+                ));
+            }
+            return Ok(account_info.open_id);
+        }
+        IamCertOAuth2Serv::add_or_modify_cert(
+            &IamCertOAuth2AddOrModifyReq {
+                open_id: TrimString(account_info.open_id.clone()),
+            },
+            &ctx.owner,
+            &state.cert_conf_id,
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(account_info.open_id)
+    }
+
+    /// Exchange the code with the PKCE verifier, then verify the id token and map its claims
+    async fn exchange_code(code: &str, state: &OidcState, cert_conf: &IamCertConfOidcAddOrModifyReq, funs: &TardisFunsInst) -> TardisResult<OidcAccountInfo> {
+        let provider = Self::get_provider(&cert_conf.issuer, false, funs).await?;
+        let client_id = cert_conf.client_id.to_string();
+        let mut form = vec![
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code.to_string()),
+            ("redirect_uri", cert_conf.redirect_uri.clone()),
+            ("client_id", client_id.clone()),
+            ("code_verifier", state.code_verifier.clone()),
+        ];
+        if let Some(client_secret) = &cert_conf.client_secret {
+            form.push(("client_secret", client_secret.to_string()));
+        }
+        let body = form.iter().map(|(key, value)| format!("{key}={}", uri_encode(value))).collect::<Vec<_>>().join("&");
+        let headers = vec![
+            ("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()),
+            ("Accept".to_string(), "application/json".to_string()),
+        ];
+        let resp = funs.web_client().post_str_to_str(&provider.metadata.token_endpoint, &body, headers).await?;
+        trace!("iam oidc [{}] token response: {}", cert_conf.supplier, resp.code);
+        if resp.code != 200 {
+            return Err(funs.err().unauthorized(
+                "iam_cert_oidc",
+                "exchange_code",
+                &format!("oidc token request failed: {}", resp.body.unwrap_or_default()),
+                "401-iam-cert-oidc-token-error",
+            ));
+        }
+        let token = TardisFuns::json.str_to_obj::<Value>(&resp.body.unwrap_or_default())?;
+        let id_token = token
+            .get("id_token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| funs.err().unauthorized("iam_cert_oidc", "exchange_code", "oidc token response has no id_token", "401-iam-cert-oidc-token-error"))?;
+        // the provider may have rotated its keys since they were cached
+        let provider = if has_unknown_key(id_token, &provider.jwks) && Self::try_start_jwks_refresh(&cert_conf.issuer, funs).await? {
+            Self::get_provider(&cert_conf.issuer, true, funs).await?
+        } else {
+            provider
+        };
+        let claims = verify_id_token(id_token, &provider.jwks, &provider.metadata.issuer, &client_id, &state.nonce)
+            .map_err(|e| funs.err().unauthorized("iam_cert_oidc", "exchange_code", &format!("invalid id_token: {e}"), "401-iam-cert-oidc-id-token-invalid"))?;
+        map_claims(&claims, &cert_conf.claim_map)
+            .ok_or_else(|| funs.err().unauthorized("iam_cert_oidc", "exchange_code", "id_token has no open id claim", "401-iam-cert-oidc-id-token-invalid"))
+    }
+
+    async fn add_account(account_info: &OidcAccountInfo, cert_conf_id: &str, tenant_id: &str, funs: &TardisFunsInst) -> TardisResult<String> {
+        let mock_ctx = TardisContext {
+            own_paths: tenant_id.to_string(),
+            owner: TardisFuns::field.nanoid(),
+            ..Default::default()
+        };
+        let user_name = account_info.user_name.clone().unwrap_or_else(|| TardisFuns::field.nanoid_len(8).to_lowercase());
+        let account_id = IamAccountServ::add_account_agg(
+            &IamAccountAggAddReq {
+                id: Some(TrimString(mock_ctx.owner.clone())),
+                name: TrimString(account_info.name.clone().or_else(|| account_info.user_name.clone()).unwrap_or_else(|| account_info.open_id.clone())),
+                cert_user_name: IamCertUserPwdServ::rename_ak_if_duplicate(&user_name, funs, &mock_ctx).await?,
+                // a random password is generated, the user logs in by the provider
+                cert_password: None,
+                // added below, so that no password is sent to them
+                cert_phone: None,
+                cert_mail: None,
+                role_ids: None,
+                org_node_ids: None,
+                scope_level: Some(RbumScopeLevelKind::Root),
+                disabled: None,
+                icon: None,
+                exts: None,
+                status: Some(RbumCertStatusKind::Pending),
+                temporary: None,
+                lock_status: None,
+                logout_type: None,
+                labor_type: None,
+                id_card_no: None,
+                employee_code: None,
+                others_id: None,
+            },
+            false,
+            funs,
+            &mock_ctx,
+        )
+        .await?;
+        if let Some(mail) = &account_info.mail {
+            if let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&IamCertKernelKind::MailVCode.to_string(), Some(tenant_id.to_string()), funs).await? {
+                IamCertMailVCodeServ::add_cert_skip_activate(&IamCertMailVCodeAddReq { mail: mail.clone() }, &account_id, &cert_conf.id, funs, &mock_ctx).await?;
+            }
+        }
+        if let Some(phone) = &account_info.phone {
+            if let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&IamCertKernelKind::PhoneVCode.to_string(), Some(tenant_id.to_string()), funs).await? {
+                IamCertPhoneVCodeServ::add_cert_skip_vcode(&IamCertPhoneVCodeAddReq { phone: TrimString(phone.clone()) }, &account_id, &cert_conf.id, funs, &mock_ctx).await?;
+            }
+        }
+        IamCertOAuth2Serv::add_or_modify_cert(
+            &IamCertOAuth2AddOrModifyReq {
+                open_id: TrimString(account_info.open_id.clone()),
+            },
+            &account_id,
+            cert_conf_id,
+            funs,
+            &mock_ctx,
+        )
+        .await?;
+        IamSearchClient::async_add_or_modify_account_search(&account_id, Box::new(false), "", funs, &mock_ctx).await?;
+        mock_ctx.execute_task().await?;
+        Ok(account_id)
+    }
+
+    async fn get_cert_conf_ext(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertConfOidcAddOrModifyReq> {
+        TardisFuns::json.str_to_obj(&Self::get_cert_conf_detail(id, funs, ctx).await?.ext)
+    }
+
+    /// The tenant of the conf, empty for the platform conf
+    async fn get_cert_conf_rel_tenant_id(id: &str, funs: &TardisFunsInst) -> TardisResult<String> {
+        Ok(Self::get_cert_conf_detail(id, funs, &TardisContext::default()).await?.rel_rbum_item_id)
+    }
+
+    async fn get_cert_conf_detail(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<RbumCertConfDetailResp> {
+        RbumCertConfServ::get_rbum(
+            id,
+            &RbumCertConfFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    own_paths: Some("".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    /// Get the discovery document and the keys of the provider, `refresh` ignores the cached ones
+    async fn get_provider(issuer: &str, refresh: bool, funs: &TardisFunsInst) -> TardisResult<OidcProvider> {
+        let cache_key = format!("{}{}", funs.conf::<IamConfig>().cache_key_oidc_provider_, issuer);
+        if !refresh {
+            if let Some(provider) = funs.cache().get(&cache_key).await? {
+                return TardisFuns::json.str_to_obj(&provider);
+            }
+        }
+        let metadata = TardisFuns::json.str_to_obj::<OidcProviderMetadata>(
+            &Self::get_json(&format!("{}/.well-known/openid-configuration", issuer.trim_end_matches('/')), "get_provider_metadata", funs).await?,
+        )?;
+        if metadata.issuer.trim_end_matches('/') != issuer.trim_end_matches('/') {
+            return Err(funs.err().conflict(
+                "iam_cert_oidc",
+                "get_provider",
+                &format!("the discovered issuer {} doesn't match {issuer}", metadata.issuer),
+                "409-iam-cert-oidc-issuer-mismatch",
+            ));
+        }
+        let jwks = TardisFuns::json.str_to_obj::<JwkSet>(&Self::get_json(&metadata.jwks_uri, "get_provider_jwks", funs).await?)?;
+        let provider = OidcProvider { metadata, jwks };
+        funs.cache().set_ex(&cache_key, &TardisFuns::json.obj_to_string(&provider)?, funs.conf::<IamConfig>().oidc_provider_cache_sec).await?;
+        Ok(provider)
+    }
+
+    /// Unknown key ids are sent by anyone who can reach the callback, so the keys are fetched again at most once per cooldown
+    async fn try_start_jwks_refresh(issuer: &str, funs: &TardisFunsInst) -> TardisResult<bool> {
+        let iam_config = funs.conf::<IamConfig>();
+        let key = format!("{}{}", iam_config.cache_key_oidc_jwks_refreshed_, issuer);
+        if !funs.cache().set_nx(&key, "").await? {
+            return Ok(false);
+        }
+        funs.cache().expire(&key, iam_config.oidc_jwks_refresh_cooldown_sec as i64).await?;
+        Ok(true)
+    }
+
+    async fn get_json(url: &str, op: &str, funs: &TardisFunsInst) -> TardisResult<String> {
+        let headers = vec![("Accept".to_string(), "application/json".to_string())];
+        let resp = funs.web_client().get_to_str(url, headers).await?;
+        if resp.code != 200 {
+            return Err(funs.err().conflict(
+                "iam_cert_oidc",
+                op,
+                &format!("oidc provider request {url} failed: {}", resp.code),
+                "409-iam-cert-oidc-provider-error",
+            ));
+        }
+        Ok(resp.body.unwrap_or_default())
+    }
+
+    /// A state can only be used once
+    async fn take_state(state_id: &str, funs: &TardisFunsInst) -> TardisResult<OidcState> {
+        let key = format!("{}{}", funs.conf::<IamConfig>().cache_key_oidc_state_, state_id);
+        let state = funs.cache().get(&key).await?.ok_or_else(|| Self::state_err("take_state", funs))?;
+        funs.cache().del(&key).await?;
+        TardisFuns::json.str_to_obj(&state)
+    }
+
+    fn state_err(op: &str, funs: &TardisFunsInst) -> tardis::basic::error::TardisError {
+        funs.err().unauthorized("iam_cert_oidc", op, "oidc state is expired or invalid, please try again", "401-iam-cert-oidc-state-invalid")
+    }
+}
+
+fn authorize_url(authorization_endpoint: &str, cert_conf: &IamCertConfOidcAddOrModifyReq, state: &str, nonce: &str, code_challenge: &str) -> String {
+    let params = [
+        ("response_type", "code"),
+        ("client_id", cert_conf.client_id.as_ref()),
+        ("redirect_uri", cert_conf.redirect_uri.as_str()),
+        ("scope", cert_conf.scope.as_deref().unwrap_or(DEFAULT_SCOPE)),
+        ("state", state),
+        ("nonce", nonce),
+        ("code_challenge", code_challenge),
+        ("code_challenge_method", "S256"),
+    ];
+    let query = params.iter().map(|(key, value)| format!("{key}={}", uri_encode(value))).collect::<Vec<_>>().join("&");
+    let separator = if authorization_endpoint.contains('?') { '&' } else { '?' };
+    format!("{authorization_endpoint}{separator}{query}")
+}
+
+/// `BASE64URL(SHA256(code_verifier))` of RFC 7636
+pub(crate) fn pkce_challenge(code_verifier: &str) -> TardisResult<String> {
+    let digest = TardisFuns::crypto.digest.sha256(code_verifier)?;
+    let digest = (0..digest.len()).step_by(2).filter_map(|i| u8::from_str_radix(&digest[i..i + 2], 16).ok()).collect::<Vec<_>>();
+    Ok(base64_url_encode(digest))
+}
+
+/// Base64 url encoding without padding, as used by PKCE and JWK
+pub(crate) fn base64_url_encode(value: impl AsRef<[u8]>) -> String {
+    TardisFuns::crypto.base64.encode(value).trim_end_matches('=').replace('+', "-").replace('/', "_")
+}
+
+fn has_unknown_key(id_token: &str, jwks: &JwkSet) -> bool {
+    jsonwebtoken::decode_header(id_token).ok().and_then(|header| header.kid).map(|kid| jwks.find(&kid).is_none()).unwrap_or(false)
+}
+
+/// Verify the signature by the provider keys, then the issuer, the audience, the expiration and the nonce
+fn verify_id_token(id_token: &str, jwks: &JwkSet, issuer: &str, client_id: &str, nonce: &str) -> Result<Map<String, Value>, String> {
+    let header = jsonwebtoken::decode_header(id_token).map_err(|e| e.to_string())?;
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .ok_or_else(|| "signing key not found".to_string())?;
+    // the algorithm of the header is chosen by the signer, it must be one the key is meant for
+    if !allowed_algorithms(jwk).contains(&header.alg) {
+        return Err(format!("algorithm {:?} is not allowed for the signing key", header.alg));
+    }
+    let key = DecodingKey::from_jwk(jwk).map_err(|e| e.to_string())?;
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[issuer]);
+    validation.set_audience(&[client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    let claims = jsonwebtoken::decode::<Map<String, Value>>(id_token, &key, &validation).map_err(|e| e.to_string())?.claims;
+    if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+        return Err("nonce mismatch".to_string());
+    }
+    Ok(claims)
+}
+
+/// The `alg` of the key when it has one, otherwise the signature algorithms of its key type
+fn allowed_algorithms(jwk: &Jwk) -> Vec<Algorithm> {
+    if let Some(key_algorithm) = jwk.common.key_algorithm {
+        return format!("{key_algorithm:?}").parse::<Algorithm>().into_iter().collect();
+    }
+    match &jwk.algorithm {
+        AlgorithmParameters::RSA(_) => vec![Algorithm::RS256, Algorithm::RS384, Algorithm::RS512, Algorithm::PS256, Algorithm::PS384, Algorithm::PS512],
+        AlgorithmParameters::EllipticCurve(params) => match params.curve {
+            EllipticCurve::P256 => vec![Algorithm::ES256],
+            EllipticCurve::P384 => vec![Algorithm::ES384],
+            _ => vec![],
+        },
+        AlgorithmParameters::OctetKeyPair(_) => vec![Algorithm::EdDSA],
+        AlgorithmParameters::OctetKey(_) => vec![Algorithm::HS256, Algorithm::HS384, Algorithm::HS512],
+    }
+}
+
+fn map_claims(claims: &Map<String, Value>, claim_map: &IamCertOidcClaimMap) -> Option<OidcAccountInfo> {
+    let claim = |name: Option<&String>, default: &str| match claims.get(name.map(String::as_str).unwrap_or(default)) {
+        Some(Value::String(value)) if !value.is_empty() => Some(value.clone()),
+        Some(Value::Number(value)) => Some(value.to_string()),
+        _ => None,
+    };
+    let verified = |name: &str| claims.get(name).and_then(Value::as_bool).unwrap_or(false);
+    Some(OidcAccountInfo {
+        open_id: claim(claim_map.open_id.as_ref(), "sub")?,
+        name: claim(claim_map.name.as_ref(), "name"),
+        user_name: claim_map.user_name.as_ref().and_then(|user_name| claim(Some(user_name), "")),
+        mail: claim(claim_map.mail.as_ref(), "email").filter(|_| verified("email_verified")),
+        phone: claim(claim_map.phone.as_ref(), "phone_number").filter(|_| verified("phone_number_verified")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::jwk::JwkSet;
+    use jsonwebtoken::{Algorithm, EncodingKey, Header};
+    use tardis::basic::field::TrimString;
+    use tardis::serde_json::{json, Map, Value};
+    use tardis::TardisFuns;
+
+    use super::{authorize_url, base64_url_encode, has_unknown_key, map_claims, pkce_challenge, verify_id_token, OidcAccountInfo};
+    use crate::basic::dto::iam_cert_conf_dto::{IamCertConfOidcAddOrModifyReq, IamCertOidcClaimMap};
+
+    const SECRET: &[u8] = b"oidc-test-secret-of-at-least-32-bytes";
+
+    fn jwks() -> JwkSet {
+        let k = base64_url_encode(SECRET);
+        TardisFuns::json.json_to_obj(json!({ "keys": [{ "kty": "oct", "kid": "key1", "alg": "HS256", "k": k }] })).unwrap()
+    }
+
+    fn sign(claims: Value, kid: &str) -> String {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(kid.to_string());
+        jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+    }
+
+    fn claims() -> Value {
+        json!({
+            "iss": "https://idp.example.com",
+            "aud": "bios",
+            "sub": "user1",
+            "exp": tardis::chrono::Utc::now().timestamp() + 300,
+            "nonce": "nonce1",
+        })
+    }
+
+    #[test]
+    fn test_pkce_challenge() {
+        // RFC 7636 Appendix B
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk").unwrap(),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URLbu7GhJxcuSU"
+        );
+    }
+
+    #[test]
+    fn test_authorize_url() {
+        let cert_conf = IamCertConfOidcAddOrModifyReq {
+            supplier: TrimString("idp".to_string()),
+            name: "IdP".to_string(),
+            issuer: "https://idp.example.com".to_string(),
+            client_id: TrimString("bios".to_string()),
+            client_secret: None,
+            redirect_uri: "https://bios.example.com/oidc/callback".to_string(),
+            scope: None,
+            claim_map: IamCertOidcClaimMap::default(),
+            auto_create_account: false,
+        };
+        assert_eq!(
+            authorize_url("https://idp.example.com/auth", &cert_conf, "state1", "nonce1", "challenge1"),
+            "https://idp.example.com/auth?response_type=code&client_id=bios&redirect_uri=https%3A%2F%2Fbios.example.com%2Foidc%2Fcallback&scope=openid%20profile%20email&state=state1&nonce=nonce1&code_challenge=challenge1&code_challenge_method=S256"
+        );
+        assert!(authorize_url("https://idp.example.com/auth?tenant=t1", &cert_conf, "state1", "nonce1", "challenge1")
+            .starts_with("https://idp.example.com/auth?tenant=t1&response_type=code"));
+    }
+
+    #[test]
+    fn test_verify_id_token() {
+        let jwks = jwks();
+        let id_token = sign(claims(), "key1");
+        assert!(!has_unknown_key(&id_token, &jwks));
+        assert_eq!(
+            verify_id_token(&id_token, &jwks, "https://idp.example.com", "bios", "nonce1").unwrap().get("sub"),
+            Some(&json!("user1"))
+        );
+        assert!(verify_id_token(&id_token, &jwks, "https://idp.example.com", "bios", "nonce2").is_err());
+        assert!(verify_id_token(&id_token, &jwks, "https://other.example.com", "bios", "nonce1").is_err());
+        assert!(verify_id_token(&id_token, &jwks, "https://idp.example.com", "other", "nonce1").is_err());
+
+        let mut expired = claims();
+        expired["exp"] = json!(tardis::chrono::Utc::now().timestamp() - 3600);
+        assert!(verify_id_token(&sign(expired, "key1"), &jwks, "https://idp.example.com", "bios", "nonce1").is_err());
+
+        let rotated = sign(claims(), "key2");
+        assert!(has_unknown_key(&rotated, &jwks));
+        assert!(verify_id_token(&rotated, &jwks, "https://idp.example.com", "bios", "nonce1").is_err());
+
+        // the algorithm is pinned by the key
+        let mut header = Header::new(Algorithm::HS384);
+        header.kid = Some("key1".to_string());
+        let other_alg = jsonwebtoken::encode(&header, &claims(), &EncodingKey::from_secret(SECRET)).unwrap();
+        assert!(verify_id_token(&other_alg, &jwks, "https://idp.example.com", "bios", "nonce1").unwrap_err().contains("not allowed"));
+        // a token signed by HMAC with the public key of an RSA key must be rejected
+        let rsa_jwks: JwkSet = TardisFuns::json.json_to_obj(json!({ "keys": [{ "kty": "RSA", "kid": "rsa1", "n": base64_url_encode(SECRET), "e": "AQAB" }] })).unwrap();
+        assert!(verify_id_token(&sign(claims(), "rsa1"), &rsa_jwks, "https://idp.example.com", "bios", "nonce1").unwrap_err().contains("not allowed"));
+
+        let mut tampered = id_token.split('.').map(str::to_string).collect::<Vec<_>>();
+        tampered[1] = sign(json!({"sub": "user2"}), "key1").split('.').nth(1).unwrap().to_string();
+        assert!(verify_id_token(&tampered.join("."), &jwks, "https://idp.example.com", "bios", "nonce1").is_err());
+    }
+
+    #[test]
+    fn test_map_claims() {
+        let claims: Map<String, Value> = TardisFuns::json
+            .json_to_obj(json!({
+                "sub": "user1",
+                "oid": 42,
+                "name": "User One",
+                "preferred_username": "user1",
+                "email": "user1@example.com",
+                "email_verified": true,
+                "phone_number": "+8613800000000",
+            }))
+            .unwrap();
+        assert_eq!(
+            map_claims(&claims, &IamCertOidcClaimMap::default()),
+            Some(OidcAccountInfo {
+                open_id: "user1".to_string(),
+                name: Some("User One".to_string()),
+                user_name: None,
+                mail: Some("user1@example.com".to_string()),
+                phone: None,
+            })
+        );
+        let claim_map = IamCertOidcClaimMap {
+            open_id: Some("oid".to_string()),
+            user_name: Some("preferred_username".to_string()),
+            ..Default::default()
+        };
+        let account_info = map_claims(&claims, &claim_map).unwrap();
+        assert_eq!(account_info.open_id, "42");
+        assert_eq!(account_info.user_name, Some("user1".to_string()));
+        let claim_map = IamCertOidcClaimMap {
+            open_id: Some("upn".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(map_claims(&claims, &claim_map), None);
+    }
+}
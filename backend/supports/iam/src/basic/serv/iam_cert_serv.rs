@@ -1161,13 +1161,24 @@ impl IamCertServ {
         rel_iam_item_id: Option<String>,
         funs: &TardisFunsInst,
     ) -> TardisResult<Option<RbumCertConfIdAndExtResp>> {
-        if kind != "Ldap" {
+        // only these kinds can have several confs in the same scope, told apart by the supplier
+        if kind != "Ldap" && kind != "Oidc" {
             supplier = "";
         }
         RbumCertConfServ::get_rbum_cert_conf_id_and_ext_by_kind_supplier(kind, supplier, false, &funs.iam_basic_domain_iam_id(), rel_iam_item_id.unwrap_or_default().as_str(), funs)
             .await
     }
 
+    /// Context of the account before it logged in, e.g. to check its second factor
+    pub async fn get_account_ctx(account_id: &str, tenant_id: Option<String>, funs: &TardisFunsInst) -> TardisResult<TardisContext> {
+        let ctx = TardisContext {
+            own_paths: tenant_id.unwrap_or_default(),
+            owner: account_id.to_string(),
+            ..Default::default()
+        };
+        IamAccountServ::is_global_account_context(account_id, funs, &ctx).await
+    }
+
     pub async fn package_tardis_context_and_resp(
         tenant_id: Option<String>,
         account_id: &str,
@@ -0,0 +1,507 @@
+use bios_basic::rbum::domain::rbum_cert;
+use bios_basic::rbum::dto::rbum_cert_conf_dto::{RbumCertConfAddReq, RbumCertConfModifyReq};
+use bios_basic::rbum::dto::rbum_cert_dto::{RbumCertAddReq, RbumCertModifyReq, RbumCertSummaryResp};
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertFilterReq};
+use bios_basic::rbum::helper::rbum_scope_helper::get_max_level_id_by_context;
+use bios_basic::rbum::rbum_enumeration::{RbumCertConfStatusKind, RbumCertRelKind, RbumCertStatusKind};
+use bios_basic::rbum::serv::rbum_cert_serv::{RbumCertConfServ, RbumCertServ};
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use tardis::basic::field::TrimString;
+use tardis::chrono::Utc;
+use tardis::db::sea_orm::sea_query::{Expr, Query};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::basic::dto::iam_cert_conf_dto::{IamCertConfTotpAddOrModifyReq, IamCertConfTotpResp};
+use crate::basic::dto::iam_cert_dto::{IamCertTotpActivateResp, IamCertTotpEnrollResp};
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
+use crate::iam_config::{IamBasicConfigApi, IamConfig};
+use crate::iam_enumeration::IamCertKernelKind;
+
+/// RFC 4648 base32 alphabet, the encoding expected by the authenticator apps
+const BASE32_ALPHABET: [char; 32] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '2', '3', '4', '5', '6', '7',
+];
+/// 32 base32 chars are 160 bits, the secret length recommended by RFC 4226
+const SECRET_LEN: usize = 32;
+const RECOVERY_CODE_ALPHABET: [char; 32] = [
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'j', 'k', 'm', 'n', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '2', '3', '4', '5', '6', '7', '8', '9', '0',
+];
+const RECOVERY_CODE_LEN: usize = 10;
+const RECOVERY_CODE_NUM: usize = 10;
+
+/// Stored in the ext of the TOTP cert
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct TotpCertExt {
+    /// Digests of the recovery codes not used yet
+    recovery_codes: Vec<String>,
+    /// Time step of the last accepted code, a code can't be used twice
+    last_counter: u64,
+}
+
+pub struct IamCertTotpServ;
+
+impl IamCertTotpServ {
+    pub async fn add_cert_conf(add_req: &IamCertConfTotpAddOrModifyReq, rel_iam_item_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let id = RbumCertConfServ::add_rbum(
+            &mut RbumCertConfAddReq {
+                kind: TrimString(IamCertKernelKind::Totp.to_string()),
+                supplier: None,
+                name: TrimString(IamCertKernelKind::Totp.to_string()),
+                note: None,
+                ak_note: None,
+                ak_rule: None,
+                sk_note: None,
+                sk_rule: None,
+                ext: Some(TardisFuns::json.obj_to_string(add_req)?),
+                sk_need: Some(true),
+                sk_dynamic: None,
+                sk_encrypted: Some(false),
+                repeatable: None,
+                is_basic: Some(false),
+                rest_by_kinds: None,
+                expire_sec: None,
+                sk_lock_cycle_sec: None,
+                sk_lock_err_times: None,
+                sk_lock_duration_sec: None,
+                coexist_num: Some(1),
+                conn_uri: None,
+                status: RbumCertConfStatusKind::Enabled,
+                rel_rbum_domain_id: funs.iam_basic_domain_iam_id(),
+                rel_rbum_item_id: rel_iam_item_id,
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(id)
+    }
+
+    pub async fn modify_cert_conf(id: &str, modify_req: &IamCertConfTotpAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        RbumCertConfServ::modify_rbum(
+            id,
+            &mut RbumCertConfModifyReq {
+                name: None,
+                note: None,
+                ak_note: None,
+                ak_rule: None,
+                sk_note: None,
+                sk_rule: None,
+                ext: Some(TardisFuns::json.obj_to_string(modify_req)?),
+                sk_need: None,
+                sk_encrypted: None,
+                repeatable: None,
+                is_basic: None,
+                rest_by_kinds: None,
+                expire_sec: None,
+                sk_lock_cycle_sec: None,
+                sk_lock_err_times: None,
+                sk_lock_duration_sec: None,
+                coexist_num: None,
+                conn_uri: None,
+                status: None,
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    /// Get the enabled TOTP cert conf of the tenant, or of the platform when `rel_iam_item_id` is empty
+    pub async fn get_cert_conf(rel_iam_item_id: Option<String>, funs: &TardisFunsInst) -> TardisResult<Option<(String, IamCertConfTotpResp)>> {
+        let cert_conf = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind_supplier(&IamCertKernelKind::Totp.to_string(), "", rel_iam_item_id, funs).await?;
+        if let Some(cert_conf) = cert_conf {
+            Ok(Some((cert_conf.id, TardisFuns::json.str_to_obj(&cert_conf.ext)?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Generate a new secret for the account, the cert stays pending until [`Self::activate`] is called with a valid code
+    pub async fn enroll(account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertTotpEnrollResp> {
+        let (cert_conf_id, cert_conf) = Self::get_cert_conf_by_ctx(funs, ctx).await?;
+        if let Some(cert) = Self::find_cert(account_id, &cert_conf_id, funs, ctx).await? {
+            if cert.status == RbumCertStatusKind::Enabled {
+                return Err(funs.err().conflict("iam_cert_totp", "enroll", "TOTP is already enabled", "409-iam-cert-totp-enabled"));
+            }
+            // enrolling again replaces the secret that was never activated
+            RbumCertServ::delete_rbum(&cert.id, funs, ctx).await?;
+        }
+        let secret = nanoid::nanoid!(SECRET_LEN, &BASE32_ALPHABET);
+        RbumCertServ::add_rbum(
+            &mut RbumCertAddReq {
+                ak: TrimString(account_id.to_string()),
+                sk: Some(TrimString(secret.clone())),
+                sk_invisible: Some(true),
+                kind: None,
+                supplier: None,
+                vcode: None,
+                ext: Some(TardisFuns::json.obj_to_string(&TotpCertExt::default())?),
+                start_time: None,
+                end_time: None,
+                conn_uri: None,
+                status: RbumCertStatusKind::Pending,
+                rel_rbum_cert_conf_id: Some(cert_conf_id),
+                rel_rbum_kind: RbumCertRelKind::Item,
+                rel_rbum_id: account_id.to_string(),
+                is_outside: false,
+                ignore_check_sk: false,
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        let account_name =
+            IamCertServ::get_cert_detail_by_id_and_kind(account_id, &IamCertKernelKind::UserPwd, funs, ctx).await.map(|cert| cert.ak).unwrap_or_else(|_| account_id.to_string());
+        Ok(IamCertTotpEnrollResp {
+            otpauth_uri: otpauth_uri(&cert_conf, &account_name, &secret),
+            secret,
+        })
+    }
+
+    /// Enable the pending TOTP cert of the account and generate its recovery codes
+    pub async fn activate(account_id: &str, code: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertTotpActivateResp> {
+        let (cert_conf_id, cert_conf) = Self::get_cert_conf_by_ctx(funs, ctx).await?;
+        let cert = Self::find_cert(account_id, &cert_conf_id, funs, ctx)
+            .await?
+            .ok_or_else(|| funs.err().not_found("iam_cert_totp", "activate", "TOTP is not enrolled", "404-iam-cert-totp-not-exist"))?;
+        if cert.status != RbumCertStatusKind::Pending {
+            return Err(funs.err().conflict("iam_cert_totp", "activate", "TOTP is already enabled", "409-iam-cert-totp-enabled"));
+        }
+        Self::check_locked(account_id, funs).await?;
+        let secret = Self::get_secret(&cert.id, funs, ctx).await?;
+        let Some(counter) = verify_code(&secret, code, Utc::now().timestamp() as u64, &cert_conf) else {
+            return Err(Self::after_verify_fail(account_id, "activate", funs).await);
+        };
+        Self::after_verify_success(account_id, funs).await?;
+        let recovery_codes = (0..RECOVERY_CODE_NUM).map(|_| nanoid::nanoid!(RECOVERY_CODE_LEN, &RECOVERY_CODE_ALPHABET)).collect::<Vec<_>>();
+        let ext = TotpCertExt {
+            recovery_codes: recovery_codes.iter().map(|code| recovery_code_digest(code, &cert.id)).collect::<TardisResult<Vec<_>>>()?,
+            last_counter: counter,
+        };
+        Self::modify_cert(&cert.id, &ext, Some(RbumCertStatusKind::Enabled), funs, ctx).await?;
+        Ok(IamCertTotpActivateResp { recovery_codes })
+    }
+
+    /// Verify a TOTP code or a recovery code of the account, a recovery code can only be used once
+    pub async fn verify(account_id: &str, code: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let (cert_conf_id, cert_conf) = Self::get_cert_conf_by_ctx(funs, ctx).await?;
+        let cert = Self::find_cert(account_id, &cert_conf_id, funs, ctx).await?.filter(|cert| cert.status == RbumCertStatusKind::Enabled);
+        let cert = cert.ok_or_else(|| funs.err().not_found("iam_cert_totp", "verify", "TOTP is not enabled", "404-iam-cert-totp-not-exist"))?;
+        Self::verify_cert(account_id, &cert, &cert_conf, code, funs, ctx).await
+    }
+
+    /// Double authentication by TOTP, accepted by the auth service like the password one
+    pub async fn double_auth(code: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        Self::verify(&ctx.owner, code, funs, ctx).await?;
+        IamIdentCacheServ::add_double_auth(&ctx.owner, funs).await?;
+        Ok(())
+    }
+
+    /// Remove TOTP from the account, a valid code is required
+    pub async fn delete_cert(account_id: &str, code: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let (cert_conf_id, cert_conf) = Self::get_cert_conf_by_ctx(funs, ctx).await?;
+        let cert = Self::find_cert(account_id, &cert_conf_id, funs, ctx)
+            .await?
+            .ok_or_else(|| funs.err().not_found("iam_cert_totp", "delete", "TOTP is not enrolled", "404-iam-cert-totp-not-exist"))?;
+        if cert.status == RbumCertStatusKind::Enabled {
+            Self::verify_cert(account_id, &cert, &cert_conf, code, funs, ctx).await?;
+        }
+        RbumCertServ::delete_rbum(&cert.id, funs, ctx).await?;
+        Ok(())
+    }
+
+    /// Second factor of the login, return whether a TOTP code was verified
+    ///
+    /// The code is required when the account enabled TOTP,
+    /// accounts without TOTP are rejected when the tenant requires it.
+    pub async fn check_login(account_id: &str, code: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+        let Some((cert_conf_id, cert_conf)) = Self::get_cert_conf(get_max_level_id_by_context(ctx), funs).await? else {
+            return Ok(false);
+        };
+        match Self::find_cert(account_id, &cert_conf_id, funs, ctx).await? {
+            Some(cert) if cert.status == RbumCertStatusKind::Enabled => {
+                let code = code.ok_or_else(|| funs.err().unauthorized("iam_cert_totp", "check_login", "TOTP code is required", "401-iam-cert-totp-required"))?;
+                Self::verify_cert(account_id, &cert, &cert_conf, code, funs, ctx).await?;
+                Ok(true)
+            }
+            _ if cert_conf.login_required => Err(funs.err().unauthorized(
+                "iam_cert_totp",
+                "check_login",
+                "TOTP is required by the tenant, please enroll it first",
+                "401-iam-cert-totp-enroll-required",
+            )),
+            _ => Ok(false),
+        }
+    }
+
+    /// Second factor of the logins that can't carry a TOTP code, e.g. verification code, LDAP, OAuth2 and OIDC logins.
+    ///
+    /// The accounts that enabled TOTP, or have to enroll it, can only login by password with the code.
+    pub async fn check_login_without_code(account_id: &str, tenant_id: Option<String>, funs: &TardisFunsInst) -> TardisResult<()> {
+        let account_ctx = IamCertServ::get_account_ctx(account_id, tenant_id, funs).await?;
+        Self::check_login(account_id, None, funs, &account_ctx).await?;
+        Ok(())
+    }
+
+    async fn verify_cert(
+        account_id: &str,
+        cert: &RbumCertSummaryResp,
+        cert_conf: &IamCertConfTotpResp,
+        code: &str,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<()> {
+        Self::check_locked(account_id, funs).await?;
+        let mut ext = if cert.ext.is_empty() {
+            TotpCertExt::default()
+        } else {
+            TardisFuns::json.str_to_obj::<TotpCertExt>(&cert.ext)?
+        };
+        let secret = Self::get_secret(&cert.id, funs, ctx).await?;
+        if let Some(counter) = verify_code(&secret, code, Utc::now().timestamp() as u64, cert_conf).filter(|counter| *counter > ext.last_counter) {
+            ext.last_counter = counter;
+        } else {
+            let digest = recovery_code_digest(code, &cert.id)?;
+            let Some(idx) = ext.recovery_codes.iter().position(|recovery_code| *recovery_code == digest) else {
+                return Err(Self::after_verify_fail(account_id, "verify", funs).await);
+            };
+            ext.recovery_codes.remove(idx);
+        }
+        // concurrent requests with the same code read the same ext, only the first one may replace it
+        if !Self::compare_and_set_ext(&cert.id, &cert.ext, &ext, funs).await? {
+            return Err(Self::after_verify_fail(account_id, "verify", funs).await);
+        }
+        Self::after_verify_success(account_id, funs).await
+    }
+
+    async fn get_cert_conf_by_ctx(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<(String, IamCertConfTotpResp)> {
+        Self::get_cert_conf(get_max_level_id_by_context(ctx), funs)
+            .await?
+            .ok_or_else(|| funs.err().not_found("iam_cert_totp", "get_cert_conf", "TOTP is not enabled by the tenant", "404-iam-cert-totp-conf-not-exist"))
+    }
+
+    async fn find_cert(account_id: &str, cert_conf_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<RbumCertSummaryResp>> {
+        RbumCertServ::find_one_rbum(
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    own_paths: Some("".to_string()),
+                    ..Default::default()
+                },
+                rel_rbum_id: Some(account_id.to_string()),
+                rel_rbum_cert_conf_ids: Some(vec![cert_conf_id.to_string()]),
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    async fn get_secret(cert_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<u8>> {
+        let secret = RbumCertServ::show_sk(
+            cert_id,
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    own_paths: Some("".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        base32_decode(&secret).ok_or_else(|| funs.err().internal_error("iam_cert_totp", "get_secret", "TOTP secret is not base32 encoded", "500-iam-cert-totp-secret-invalid"))
+    }
+
+    async fn modify_cert(cert_id: &str, ext: &TotpCertExt, status: Option<RbumCertStatusKind>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        RbumCertServ::modify_rbum(
+            cert_id,
+            &mut RbumCertModifyReq {
+                ak: None,
+                sk: None,
+                sk_invisible: None,
+                ignore_check_sk: false,
+                ext: Some(TardisFuns::json.obj_to_string(ext)?),
+                start_time: None,
+                end_time: None,
+                conn_uri: None,
+                status,
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    /// Replace the ext of the cert only if it is still `expected`, return whether it was replaced
+    async fn compare_and_set_ext(cert_id: &str, expected: &str, ext: &TotpCertExt, funs: &TardisFunsInst) -> TardisResult<bool> {
+        let mut update_statement = Query::update();
+        update_statement.table(rbum_cert::Entity);
+        update_statement.value(rbum_cert::Column::Ext, TardisFuns::json.obj_to_string(ext)?);
+        update_statement.and_where(Expr::col((rbum_cert::Entity, rbum_cert::Column::Id)).eq(cert_id));
+        update_statement.and_where(Expr::col((rbum_cert::Entity, rbum_cert::Column::Ext)).eq(expected));
+        Ok(funs.db().execute(&update_statement).await?.rows_affected() > 0)
+    }
+
+    async fn check_locked(account_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        let err_times = funs.cache().get(&format!("{}{}", funs.conf::<IamConfig>().cache_key_totp_err_times_, account_id)).await?;
+        if err_times.and_then(|err_times| err_times.parse::<u32>().ok()).unwrap_or_default() >= funs.conf::<IamConfig>().totp_lock_err_times {
+            return Err(funs.err().unauthorized("iam_cert_totp", "verify", "too many invalid TOTP codes, please try again later", "401-iam-cert-totp-locked"));
+        }
+        Ok(())
+    }
+
+    async fn after_verify_success(account_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        funs.cache().del(&format!("{}{}", funs.conf::<IamConfig>().cache_key_totp_err_times_, account_id)).await?;
+        Ok(())
+    }
+
+    /// Count the failure and return the error to report
+    async fn after_verify_fail(account_id: &str, op: &str, funs: &TardisFunsInst) -> TardisError {
+        let key = format!("{}{}", funs.conf::<IamConfig>().cache_key_totp_err_times_, account_id);
+        match funs.cache().incr(&key, 1).await {
+            Ok(1) => {
+                if let Err(e) = funs.cache().expire(&key, funs.conf::<IamConfig>().totp_lock_sec as i64).await {
+                    return e;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => return e,
+        }
+        funs.err().unauthorized("iam_cert_totp", op, "invalid TOTP code", "401-iam-cert-totp-invalid")
+    }
+}
+
+/// HOTP value of the counter (RFC 4226), HMAC-SHA1 as expected by the common authenticator apps
+fn hotp(secret: &[u8], counter: u64, digits: u32) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+    format!("{:0width$}", binary % 10u32.pow(digits), width = digits as usize)
+}
+
+/// Find the time step (RFC 6238) matching the code within the tolerated skew
+fn verify_code(secret: &[u8], code: &str, now_sec: u64, cert_conf: &IamCertConfTotpResp) -> Option<u64> {
+    if code.len() != cert_conf.digits as usize {
+        return None;
+    }
+    let current = now_sec / cert_conf.period.max(1);
+    (current.saturating_sub(cert_conf.skew)..=current + cert_conf.skew).find(|counter| {
+        // compare every byte so the time taken doesn't leak the matched prefix
+        hotp(secret, *counter, cert_conf.digits).bytes().zip(code.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    })
+}
+
+/// The cert id salts the digest, the same code of two accounts doesn't give the same digest
+fn recovery_code_digest(code: &str, cert_id: &str) -> TardisResult<String> {
+    TardisFuns::crypto.digest.sha256(format!("{code}-{cert_id}").as_str())
+}
+
+fn base32_decode(input: &str) -> Option<Vec<u8>> {
+    let mut result = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in input.trim_end_matches('=').chars() {
+        let value = BASE32_ALPHABET.iter().position(|a| *a == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(result)
+}
+
+fn otpauth_uri(cert_conf: &IamCertConfTotpResp, account_name: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        uri_encode(&cert_conf.issuer),
+        uri_encode(account_name),
+        secret,
+        uri_encode(&cert_conf.issuer),
+        cert_conf.digits,
+        cert_conf.period
+    )
+}
+
+pub(crate) fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conf(digits: u32, skew: u64) -> IamCertConfTotpResp {
+        IamCertConfTotpResp {
+            issuer: "BIOS".to_string(),
+            digits,
+            period: 30,
+            skew,
+            login_required: false,
+        }
+    }
+
+    #[test]
+    fn test_base32_decode() {
+        assert_eq!(base32_decode("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap(), b"12345678901234567890");
+        assert_eq!(base32_decode("mzxw6===").unwrap(), b"foo");
+        assert!(base32_decode("GEZ1").is_none());
+    }
+
+    #[test]
+    fn test_hotp() {
+        // RFC 4226 Appendix D
+        let expected = ["755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871", "520489"];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp(b"12345678901234567890", counter as u64, 6), *code);
+        }
+    }
+
+    #[test]
+    fn test_verify_code() {
+        // RFC 6238 Appendix B, SHA1
+        let secret = b"12345678901234567890";
+        for (time, code) in [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+        ] {
+            assert_eq!(verify_code(secret, code, time, &conf(8, 0)), Some(time / 30));
+        }
+        // the previous time step is accepted within the skew
+        assert_eq!(verify_code(secret, "07081804", 1111111109 + 30, &conf(8, 1)), Some(1111111109 / 30));
+        assert_eq!(verify_code(secret, "07081804", 1111111109 + 30, &conf(8, 0)), None);
+        assert_eq!(verify_code(secret, "0708180", 1111111109, &conf(8, 0)), None);
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        assert_eq!(
+            otpauth_uri(&conf(6, 1), "bios admin", "JBSWY3DPEHPK3PXP"),
+            "otpauth://totp/BIOS:bios%20admin?secret=JBSWY3DPEHPK3PXP&issuer=BIOS&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
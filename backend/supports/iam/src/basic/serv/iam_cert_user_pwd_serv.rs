@@ -506,7 +506,7 @@ impl IamCertUserPwdServ {
     }
 
     // 不在rbum_cert_serve在做检查是因为其他凭证sk不需要去检查是否包含ak, 但是在这里做检查是因为用户密码凭证需要检查
-    fn check_sk_contains_ak(ak: &str, sk: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    pub fn check_sk_contains_ak(ak: &str, sk: &str, funs: &TardisFunsInst) -> TardisResult<()> {
         if sk.to_lowercase().contains(&ak.to_lowercase()) {
             return Err(funs.err().bad_request("iam_cert", "check ak sk", "sk can not contains ak", "400-iam-cert-sk-contains-ak"));
         }
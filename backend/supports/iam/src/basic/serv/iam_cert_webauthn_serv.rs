@@ -0,0 +1,556 @@
+use bios_basic::rbum::dto::rbum_cert_conf_dto::{RbumCertConfAddReq, RbumCertConfModifyReq};
+use bios_basic::rbum::dto::rbum_cert_dto::{RbumCertAddReq, RbumCertModifyReq, RbumCertSummaryResp};
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertFilterReq};
+use bios_basic::rbum::helper::rbum_scope_helper::get_max_level_id_by_context;
+use bios_basic::rbum::rbum_enumeration::{RbumCertConfStatusKind, RbumCertRelKind, RbumCertStatusKind};
+use bios_basic::rbum::serv::rbum_cert_serv::{RbumCertConfServ, RbumCertServ};
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use serde::{Deserialize, Serialize};
+use tardis::basic::field::TrimString;
+use tardis::cache::AsyncCommands;
+use tardis::chrono::{DateTime, Utc};
+use tardis::serde_json::Value;
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    TardisFuns, TardisFunsInst,
+};
+use webauthn_rs::prelude::{
+    CredentialID, Passkey, PasskeyAuthentication, PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential, Url, Uuid, Webauthn, WebauthnBuilder,
+};
+
+use crate::basic::dto::iam_cert_conf_dto::{IamCertConfWebAuthnAddOrModifyReq, IamCertConfWebAuthnResp};
+use crate::basic::dto::iam_cert_dto::{IamCertWebAuthnChallengeResp, IamCertWebAuthnFinishReq, IamCertWebAuthnResp};
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
+use crate::iam_config::{IamBasicConfigApi, IamConfig};
+use crate::iam_enumeration::IamCertKernelKind;
+
+/// Stored in the ext of the WebAuthn cert, the ak of the cert is the hex encoded credential id
+#[derive(Serialize, Deserialize)]
+struct WebAuthnCertExt {
+    name: String,
+    passkey: Passkey,
+    last_used_time: Option<DateTime<Utc>>,
+}
+
+/// Kept in the cache between the start and the finish of a ceremony
+#[derive(Serialize, Deserialize)]
+struct WebAuthnState {
+    account_id: String,
+    rel_iam_item_id: Option<String>,
+    ceremony: WebAuthnCeremony,
+}
+
+#[derive(Serialize, Deserialize)]
+enum WebAuthnCeremony {
+    Registration { name: String, state: PasskeyRegistration },
+    Login { tenant_id: Option<String>, state: PasskeyAuthentication },
+    Validate { state: PasskeyAuthentication },
+}
+
+pub struct IamCertWebAuthnServ;
+
+impl IamCertWebAuthnServ {
+    pub async fn add_cert_conf(add_req: &IamCertConfWebAuthnAddOrModifyReq, rel_iam_item_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        Self::check_cert_conf(add_req, funs)?;
+        let id = RbumCertConfServ::add_rbum(
+            &mut RbumCertConfAddReq {
+                kind: TrimString(IamCertKernelKind::WebAuthn.to_string()),
+                supplier: None,
+                name: TrimString(IamCertKernelKind::WebAuthn.to_string()),
+                note: None,
+                ak_note: None,
+                ak_rule: None,
+                sk_note: None,
+                sk_rule: None,
+                ext: Some(TardisFuns::json.obj_to_string(add_req)?),
+                sk_need: Some(false),
+                sk_dynamic: None,
+                sk_encrypted: Some(false),
+                repeatable: None,
+                is_basic: Some(false),
+                rest_by_kinds: None,
+                expire_sec: None,
+                sk_lock_cycle_sec: None,
+                sk_lock_err_times: None,
+                sk_lock_duration_sec: None,
+                coexist_num: Some(add_req.max_authenticators),
+                conn_uri: None,
+                status: RbumCertConfStatusKind::Enabled,
+                rel_rbum_domain_id: funs.iam_basic_domain_iam_id(),
+                rel_rbum_item_id: rel_iam_item_id,
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(id)
+    }
+
+    pub async fn modify_cert_conf(id: &str, modify_req: &IamCertConfWebAuthnAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        Self::check_cert_conf(modify_req, funs)?;
+        RbumCertConfServ::modify_rbum(
+            id,
+            &mut RbumCertConfModifyReq {
+                name: None,
+                note: None,
+                ak_note: None,
+                ak_rule: None,
+                sk_note: None,
+                sk_rule: None,
+                ext: Some(TardisFuns::json.obj_to_string(modify_req)?),
+                sk_need: None,
+                sk_encrypted: None,
+                repeatable: None,
+                is_basic: None,
+                rest_by_kinds: None,
+                expire_sec: None,
+                sk_lock_cycle_sec: None,
+                sk_lock_err_times: None,
+                sk_lock_duration_sec: None,
+                coexist_num: Some(modify_req.max_authenticators),
+                conn_uri: None,
+                status: None,
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    /// Get the enabled WebAuthn cert conf of the tenant, or of the platform when `rel_iam_item_id` is empty
+    pub async fn get_cert_conf(rel_iam_item_id: Option<String>, funs: &TardisFunsInst) -> TardisResult<Option<(String, IamCertConfWebAuthnResp)>> {
+        let cert_conf = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind_supplier(&IamCertKernelKind::WebAuthn.to_string(), "", rel_iam_item_id, funs).await?;
+        if let Some(cert_conf) = cert_conf {
+            Ok(Some((cert_conf.id, TardisFuns::json.str_to_obj(&cert_conf.ext)?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Start to register a new authenticator of the current account
+    pub async fn start_register(name: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertWebAuthnChallengeResp> {
+        let rel_iam_item_id = get_max_level_id_by_context(ctx);
+        let (cert_conf_id, cert_conf) = Self::get_cert_conf_or_err(rel_iam_item_id.clone(), funs).await?;
+        let certs = Self::find_certs_with_ext(&ctx.owner, &cert_conf_id, funs, ctx).await?;
+        if certs.len() >= cert_conf.max_authenticators as usize {
+            return Err(funs.err().conflict(
+                "iam_cert_webauthn",
+                "start_register",
+                &format!("at most {} authenticators can be registered", cert_conf.max_authenticators),
+                "409-iam-cert-webauthn-num-exceed",
+            ));
+        }
+        let user_name =
+            IamCertServ::get_cert_detail_by_id_and_kind(&ctx.owner, &IamCertKernelKind::UserPwd, funs, ctx).await.map(|cert| cert.ak).unwrap_or_else(|_| ctx.owner.clone());
+        let exclude_credentials = certs.into_iter().map(|(_, ext)| ext.passkey.cred_id().clone()).collect::<Vec<_>>();
+        let (options, state) = build_webauthn(&cert_conf)
+            .and_then(|webauthn| webauthn.start_passkey_registration(user_unique_id(&ctx.owner)?, &user_name, &user_name, Some(exclude_credentials)).map_err(|e| e.to_string()))
+            .map_err(|e| funs.err().internal_error("iam_cert_webauthn", "start_register", &e, "500-iam-cert-webauthn-error"))?;
+        let state_id = Self::add_state(
+            &WebAuthnState {
+                account_id: ctx.owner.clone(),
+                rel_iam_item_id,
+                ceremony: WebAuthnCeremony::Registration { name: name.to_string(), state },
+            },
+            funs,
+        )
+        .await?;
+        Ok(IamCertWebAuthnChallengeResp {
+            state_id,
+            options: TardisFuns::json.obj_to_json(&options)?,
+        })
+    }
+
+    /// Verify the new credential created by the authenticator and save it, return the id of the cert
+    pub async fn finish_register(finish_req: &IamCertWebAuthnFinishReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let state = Self::take_state(&finish_req.state_id, funs).await?;
+        let WebAuthnCeremony::Registration { name, state: registration } = state.ceremony else {
+            return Err(Self::state_err("finish_register", funs));
+        };
+        if state.account_id != ctx.owner {
+            return Err(Self::state_err("finish_register", funs));
+        }
+        let (cert_conf_id, cert_conf) = Self::get_cert_conf_or_err(state.rel_iam_item_id, funs).await?;
+        let credential = TardisFuns::json.json_to_obj::<RegisterPublicKeyCredential>(finish_req.credential.clone())?;
+        let passkey = build_webauthn(&cert_conf)
+            .and_then(|webauthn| webauthn.finish_passkey_registration(&credential, &registration).map_err(|e| e.to_string()))
+            .map_err(|e| funs.err().unauthorized("iam_cert_webauthn", "finish_register", &format!("invalid credential: {e}"), "401-iam-cert-webauthn-invalid"))?;
+        let id = RbumCertServ::add_rbum(
+            &mut RbumCertAddReq {
+                ak: TrimString(cred_id_to_ak(passkey.cred_id())),
+                sk: None,
+                sk_invisible: None,
+                kind: None,
+                supplier: None,
+                vcode: None,
+                ext: Some(TardisFuns::json.obj_to_string(&WebAuthnCertExt {
+                    name,
+                    passkey,
+                    last_used_time: None,
+                })?),
+                start_time: None,
+                end_time: None,
+                conn_uri: None,
+                status: RbumCertStatusKind::Enabled,
+                rel_rbum_cert_conf_id: Some(cert_conf_id),
+                rel_rbum_kind: RbumCertRelKind::Item,
+                rel_rbum_id: ctx.owner.clone(),
+                is_outside: false,
+                ignore_check_sk: true,
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(id)
+    }
+
+    /// Authenticators registered by the account
+    pub async fn find_certs(account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<IamCertWebAuthnResp>> {
+        let Some((cert_conf_id, _)) = Self::get_cert_conf(get_max_level_id_by_context(ctx), funs).await? else {
+            return Ok(vec![]);
+        };
+        let certs = Self::find_certs_with_ext(account_id, &cert_conf_id, funs, ctx).await?;
+        Ok(certs
+            .into_iter()
+            .map(|(cert, ext)| IamCertWebAuthnResp {
+                id: cert.id,
+                name: ext.name,
+                create_time: cert.create_time,
+                last_used_time: ext.last_used_time,
+            })
+            .collect())
+    }
+
+    pub async fn delete_cert(id: &str, account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let (cert_conf_id, _) = Self::get_cert_conf_or_err(get_max_level_id_by_context(ctx), funs).await?;
+        if !Self::find_certs_with_ext(account_id, &cert_conf_id, funs, ctx).await?.iter().any(|(cert, _)| cert.id == id) {
+            return Err(funs.err().not_found("iam_cert_webauthn", "delete", "authenticator not found", "404-iam-cert-webauthn-not-exist"));
+        }
+        RbumCertServ::delete_rbum(id, funs, ctx).await?;
+        Ok(())
+    }
+
+    /// Start to login by an authenticator of the account, `ctx` is the context of the account
+    pub async fn start_login(account_id: &str, tenant_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertWebAuthnChallengeResp> {
+        Self::start_authentication(account_id, |state| WebAuthnCeremony::Login { tenant_id, state }, funs, ctx).await
+    }
+
+    /// Verify the assertion started by [`Self::start_login`], return the account id and the tenant id to login
+    pub async fn finish_login(state_id: &str, credential: &Value, funs: &TardisFunsInst) -> TardisResult<(String, Option<String>)> {
+        let state = Self::take_state(state_id, funs).await?;
+        let WebAuthnCeremony::Login { tenant_id, state: authentication } = state.ceremony else {
+            return Err(Self::state_err("finish_login", funs));
+        };
+        let ctx = IamCertServ::get_account_ctx(&state.account_id, tenant_id.clone(), funs).await?;
+        Self::finish_authentication(&state.account_id, state.rel_iam_item_id, &authentication, credential, funs, &ctx).await?;
+        Ok((state.account_id, tenant_id))
+    }
+
+    /// Start the double authentication of the current account
+    pub async fn start_double_auth(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertWebAuthnChallengeResp> {
+        Self::start_authentication(&ctx.owner, |state| WebAuthnCeremony::Validate { state }, funs, ctx).await
+    }
+
+    /// Double authentication by an authenticator, accepted by the auth service like the password one
+    pub async fn finish_double_auth(finish_req: &IamCertWebAuthnFinishReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let state = Self::take_state(&finish_req.state_id, funs).await?;
+        let WebAuthnCeremony::Validate { state: authentication } = state.ceremony else {
+            return Err(Self::state_err("finish_double_auth", funs));
+        };
+        if state.account_id != ctx.owner {
+            return Err(Self::state_err("finish_double_auth", funs));
+        }
+        Self::finish_authentication(&ctx.owner, state.rel_iam_item_id, &authentication, &finish_req.credential, funs, ctx).await?;
+        IamIdentCacheServ::add_double_auth(&ctx.owner, funs).await?;
+        Ok(())
+    }
+
+    /// Second factor of the password login
+    ///
+    /// When the tenant requires it, the accounts with authenticators have to login by [`Self::start_login`] after the password.
+    pub async fn check_login(account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let Some((cert_conf_id, cert_conf)) = Self::get_cert_conf(get_max_level_id_by_context(ctx), funs).await? else {
+            return Ok(());
+        };
+        if cert_conf.second_factor_required && !Self::find_certs_with_ext(account_id, &cert_conf_id, funs, ctx).await?.is_empty() {
+            return Err(funs.err().unauthorized(
+                "iam_cert_webauthn",
+                "check_login",
+                "passkey is required after the password",
+                "401-iam-cert-webauthn-required",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Second factor of the logins that can't carry an assertion, e.g. verification code, LDAP, OAuth2 and OIDC logins.
+    ///
+    /// The accounts with authenticators can only login by password and passkey when the tenant requires it.
+    pub async fn check_login_without_code(account_id: &str, tenant_id: Option<String>, funs: &TardisFunsInst) -> TardisResult<()> {
+        let account_ctx = IamCertServ::get_account_ctx(account_id, tenant_id, funs).await?;
+        Self::check_login(account_id, funs, &account_ctx).await
+    }
+
+    async fn start_authentication(
+        account_id: &str,
+        ceremony: impl FnOnce(PasskeyAuthentication) -> WebAuthnCeremony,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<IamCertWebAuthnChallengeResp> {
+        let rel_iam_item_id = get_max_level_id_by_context(ctx);
+        let (cert_conf_id, cert_conf) = Self::get_cert_conf_or_err(rel_iam_item_id.clone(), funs).await?;
+        let passkeys = Self::find_certs_with_ext(account_id, &cert_conf_id, funs, ctx).await?.into_iter().map(|(_, ext)| ext.passkey).collect::<Vec<_>>();
+        if passkeys.is_empty() {
+            return Err(funs.err().not_found(
+                "iam_cert_webauthn",
+                "start_authentication",
+                "no authenticator is registered",
+                "404-iam-cert-webauthn-not-exist",
+            ));
+        }
+        let (options, state) = build_webauthn(&cert_conf)
+            .and_then(|webauthn| webauthn.start_passkey_authentication(&passkeys).map_err(|e| e.to_string()))
+            .map_err(|e| funs.err().internal_error("iam_cert_webauthn", "start_authentication", &e, "500-iam-cert-webauthn-error"))?;
+        let state_id = Self::add_state(
+            &WebAuthnState {
+                account_id: account_id.to_string(),
+                rel_iam_item_id,
+                ceremony: ceremony(state),
+            },
+            funs,
+        )
+        .await?;
+        Ok(IamCertWebAuthnChallengeResp {
+            state_id,
+            options: TardisFuns::json.obj_to_json(&options)?,
+        })
+    }
+
+    async fn finish_authentication(
+        account_id: &str,
+        rel_iam_item_id: Option<String>,
+        authentication: &PasskeyAuthentication,
+        credential: &Value,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<()> {
+        let (cert_conf_id, cert_conf) = Self::get_cert_conf_or_err(rel_iam_item_id, funs).await?;
+        let credential = TardisFuns::json.json_to_obj::<PublicKeyCredential>(credential.clone())?;
+        let result =
+            build_webauthn(&cert_conf).and_then(|webauthn| webauthn.finish_passkey_authentication(&credential, authentication).map_err(|e| e.to_string())).map_err(|e| {
+                funs.err().unauthorized(
+                    "iam_cert_webauthn",
+                    "finish_authentication",
+                    &format!("invalid assertion: {e}"),
+                    "401-iam-cert-webauthn-invalid",
+                )
+            })?;
+        let ak = cred_id_to_ak(result.cred_id());
+        let (cert, mut ext) = Self::find_certs_with_ext(account_id, &cert_conf_id, funs, ctx)
+            .await?
+            .into_iter()
+            .find(|(cert, _)| cert.ak == ak)
+            .ok_or_else(|| funs.err().not_found("iam_cert_webauthn", "finish_authentication", "authenticator not found", "404-iam-cert-webauthn-not-exist"))?;
+        // keep the signature counter, a cloned authenticator is detected by a counter going backwards
+        ext.passkey.update_credential(&result);
+        ext.last_used_time = Some(Utc::now());
+        RbumCertServ::modify_rbum(
+            &cert.id,
+            &mut RbumCertModifyReq {
+                ak: None,
+                sk: None,
+                sk_invisible: None,
+                ignore_check_sk: true,
+                ext: Some(TardisFuns::json.obj_to_string(&ext)?),
+                start_time: None,
+                end_time: None,
+                conn_uri: None,
+                status: None,
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    fn check_cert_conf(req: &IamCertConfWebAuthnAddOrModifyReq, funs: &TardisFunsInst) -> TardisResult<()> {
+        let cert_conf = IamCertConfWebAuthnResp {
+            rp_id: req.rp_id.clone(),
+            rp_name: req.rp_name.clone(),
+            rp_origin: req.rp_origin.clone(),
+            max_authenticators: req.max_authenticators,
+            primary_enabled: req.primary_enabled,
+            second_factor_required: req.second_factor_required,
+        };
+        build_webauthn(&cert_conf).map_err(|e| funs.err().bad_request("iam_cert_webauthn", "check_cert_conf", &e, "400-iam-cert-webauthn-conf-invalid"))?;
+        Ok(())
+    }
+
+    async fn get_cert_conf_or_err(rel_iam_item_id: Option<String>, funs: &TardisFunsInst) -> TardisResult<(String, IamCertConfWebAuthnResp)> {
+        Self::get_cert_conf(rel_iam_item_id, funs).await?.ok_or_else(|| {
+            funs.err().not_found(
+                "iam_cert_webauthn",
+                "get_cert_conf",
+                "WebAuthn is not enabled by the tenant",
+                "404-iam-cert-webauthn-conf-not-exist",
+            )
+        })
+    }
+
+    async fn find_certs_with_ext(account_id: &str, cert_conf_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<(RbumCertSummaryResp, WebAuthnCertExt)>> {
+        let certs = RbumCertServ::find_rbums(
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    own_paths: Some("".to_string()),
+                    ..Default::default()
+                },
+                rel_rbum_id: Some(account_id.to_string()),
+                rel_rbum_cert_conf_ids: Some(vec![cert_conf_id.to_string()]),
+                status: Some(RbumCertStatusKind::Enabled),
+                ..Default::default()
+            },
+            Some(false),
+            None,
+            funs,
+            ctx,
+        )
+        .await?;
+        certs
+            .into_iter()
+            .map(|cert| {
+                let ext = TardisFuns::json.str_to_obj::<WebAuthnCertExt>(&cert.ext)?;
+                Ok((cert, ext))
+            })
+            .collect()
+    }
+
+    async fn add_state(state: &WebAuthnState, funs: &TardisFunsInst) -> TardisResult<String> {
+        let state_id = TardisFuns::field.nanoid();
+        funs.cache()
+            .set_ex(
+                &format!("{}{}", funs.conf::<IamConfig>().cache_key_webauthn_state_, state_id),
+                &TardisFuns::json.obj_to_string(state)?,
+                funs.conf::<IamConfig>().webauthn_state_expire_sec,
+            )
+            .await?;
+        Ok(state_id)
+    }
+
+    /// A ceremony state can only be used once, it is read and removed by one `GETDEL` so concurrent requests can't both get it
+    async fn take_state(state_id: &str, funs: &TardisFunsInst) -> TardisResult<WebAuthnState> {
+        let key = format!("{}{}", funs.conf::<IamConfig>().cache_key_webauthn_state_, state_id);
+        let state: Option<String> = funs.cache().cmd().await?.get_del(&key).await?;
+        let state = state.ok_or_else(|| Self::state_err("take_state", funs))?;
+        TardisFuns::json.str_to_obj(&state)
+    }
+
+    fn state_err(op: &str, funs: &TardisFunsInst) -> TardisError {
+        funs.err().unauthorized(
+            "iam_cert_webauthn",
+            op,
+            "WebAuthn ceremony is expired or invalid, please try again",
+            "401-iam-cert-webauthn-state-invalid",
+        )
+    }
+}
+
+fn build_webauthn(cert_conf: &IamCertConfWebAuthnResp) -> Result<Webauthn, String> {
+    let rp_origin = Url::parse(&cert_conf.rp_origin).map_err(|e| format!("invalid rp_origin: {e}"))?;
+    WebauthnBuilder::new(&cert_conf.rp_id, &rp_origin).and_then(|builder| builder.rp_name(&cert_conf.rp_name).build()).map_err(|e| format!("invalid relying party: {e}"))
+}
+
+/// The user handle given to the authenticator, derived from the account id so it is stable across registrations
+fn user_unique_id(account_id: &str) -> Result<Uuid, String> {
+    let digest = TardisFuns::crypto.digest.sha256(account_id).map_err(|e| e.to_string())?;
+    Uuid::parse_str(&digest[..32]).map_err(|e| e.to_string())
+}
+
+fn cred_id_to_ak(cred_id: &CredentialID) -> String {
+    cred_id.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tardis::serde_json::Value;
+    use tardis::TardisFuns;
+    use webauthn_authenticator_rs::softpasskey::SoftPasskey;
+    use webauthn_authenticator_rs::WebauthnAuthenticator;
+    use webauthn_rs::prelude::{PublicKeyCredential, RegisterPublicKeyCredential, Url};
+
+    use super::{build_webauthn, cred_id_to_ak, user_unique_id, WebAuthnCeremony, WebAuthnState};
+    use crate::basic::dto::iam_cert_conf_dto::IamCertConfWebAuthnResp;
+
+    fn cert_conf() -> IamCertConfWebAuthnResp {
+        IamCertConfWebAuthnResp {
+            rp_id: "example.com".to_string(),
+            rp_name: "BIOS".to_string(),
+            rp_origin: "https://bios.example.com".to_string(),
+            max_authenticators: 5,
+            primary_enabled: true,
+            second_factor_required: false,
+        }
+    }
+
+    /// The states and the credentials go through the cache and the http body as json, like the real ceremonies
+    fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(obj: &T) -> T {
+        let json: Value = TardisFuns::json.obj_to_json(obj).unwrap();
+        TardisFuns::json.json_to_obj(json).unwrap()
+    }
+
+    #[test]
+    fn test_build_webauthn() {
+        assert!(build_webauthn(&cert_conf()).is_ok());
+        let mut conf = cert_conf();
+        conf.rp_origin = "https://bios.example.org".to_string();
+        assert!(build_webauthn(&conf).is_err());
+        conf.rp_origin = "not a url".to_string();
+        assert!(build_webauthn(&conf).is_err());
+    }
+
+    #[test]
+    fn test_user_unique_id() {
+        assert_eq!(user_unique_id("account1").unwrap(), user_unique_id("account1").unwrap());
+        assert_ne!(user_unique_id("account1").unwrap(), user_unique_id("account2").unwrap());
+    }
+
+    #[test]
+    fn test_ceremonies_by_soft_authenticator() {
+        let webauthn = build_webauthn(&cert_conf()).unwrap();
+        let origin = Url::parse("https://bios.example.com").unwrap();
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+        let (options, registration) = webauthn.start_passkey_registration(user_unique_id("account1").unwrap(), "user1", "user1", None).unwrap();
+        let WebAuthnState {
+            ceremony: WebAuthnCeremony::Registration { state: registration, .. },
+            ..
+        } = round_trip(&WebAuthnState {
+            account_id: "account1".to_string(),
+            rel_iam_item_id: None,
+            ceremony: WebAuthnCeremony::Registration {
+                name: "soft".to_string(),
+                state: registration,
+            },
+        })
+        else {
+            panic!("unexpected ceremony");
+        };
+        let credential: RegisterPublicKeyCredential = round_trip(&authenticator.do_registration(origin.clone(), options).unwrap());
+        let passkey = webauthn.finish_passkey_registration(&credential, &registration).unwrap();
+        assert_eq!(cred_id_to_ak(passkey.cred_id()).len(), passkey.cred_id().len() * 2);
+
+        let mut passkey = round_trip(&passkey);
+        let (options, authentication) = webauthn.start_passkey_authentication(&[passkey.clone()]).unwrap();
+        let authentication = round_trip(&authentication);
+        let assertion: PublicKeyCredential = round_trip(&authenticator.do_authentication(origin.clone(), options).unwrap());
+        let result = webauthn.finish_passkey_authentication(&assertion, &authentication).unwrap();
+        assert_eq!(cred_id_to_ak(result.cred_id()), cred_id_to_ak(passkey.cred_id()));
+        passkey.update_credential(&result);
+
+        // an assertion can't be replayed to another challenge
+        let (_, authentication) = webauthn.start_passkey_authentication(&[passkey]).unwrap();
+        assert!(webauthn.finish_passkey_authentication(&assertion, &authentication).is_err());
+    }
+}
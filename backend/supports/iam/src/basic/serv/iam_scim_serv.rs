@@ -0,0 +1,1478 @@
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::rbum::dto::rbum_cert_dto::RbumCertAddReq;
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertFilterReq, RbumSetCateFilterReq, RbumSetItemFilterReq};
+use bios_basic::rbum::dto::rbum_set_cate_dto::RbumSetCateSummaryResp;
+use bios_basic::rbum::dto::rbum_set_item_dto::RbumSetItemDetailResp;
+use bios_basic::rbum::helper::rbum_scope_helper::get_max_level_id_by_context;
+use bios_basic::rbum::rbum_enumeration::{RbumCertRelKind, RbumCertStatusKind, RbumScopeLevelKind};
+use bios_basic::rbum::serv::rbum_cert_serv::RbumCertServ;
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use bios_basic::rbum::serv::rbum_set_serv::RbumSetItemServ;
+use serde::de::DeserializeOwned;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::{Duration, Utc};
+use tardis::serde_json::{self, json, Map, Value};
+use tardis::{TardisFuns, TardisFunsInst};
+
+use crate::basic::dto::iam_account_dto::{IamAccountAggAddReq, IamAccountAggModifyReq, IamAccountSummaryResp};
+use crate::basic::dto::iam_cert_dto::{IamCertMailVCodeAddReq, IamCertPhoneVCodeAddReq};
+use crate::basic::dto::iam_filer_dto::{IamAccountFilterReq, IamRoleFilterReq};
+use crate::basic::dto::iam_scim_dto::*;
+use crate::basic::dto::iam_set_dto::{IamSetCateAddReq, IamSetCateModifyReq, IamSetItemAddReq};
+use crate::basic::serv::clients::iam_search_client::IamSearchClient;
+use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
+use crate::basic::serv::iam_cert_phone_vcode_serv::IamCertPhoneVCodeServ;
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use crate::basic::serv::iam_role_serv::IamRoleServ;
+use crate::basic::serv::iam_set_serv::IamSetServ;
+use crate::basic::serv::iam_tenant_serv::IamTenantServ;
+use crate::iam_config::{IamBasicConfigApi, IamConfig};
+use crate::iam_enumeration::{IamAccountLogoutTypeKind, IamAccountStatusKind, IamCertExtKind, IamCertKernelKind, IamRoleKind, IamSetKind};
+
+/// SCIM 接口相对于 `iam_base_url` 的路径
+pub const SCIM_BASE_PATH: &str = "/ci/scim/v2";
+/// 单次查询返回的最大资源数
+pub const SCIM_MAX_RESULTS: u64 = 1000;
+/// 单次批量请求的最大操作数
+pub const SCIM_MAX_BULK_OPERATIONS: usize = 1000;
+pub const SCIM_MAX_BULK_PAYLOAD_SIZE: usize = 1024 * 1024;
+
+const SCIM_ERROR_TYPES: [&str; 10] = [
+    "invalidFilter",
+    "tooMany",
+    "uniqueness",
+    "mutability",
+    "invalidSyntax",
+    "invalidPath",
+    "noTarget",
+    "invalidValue",
+    "invalidVers",
+    "sensitive",
+];
+
+pub struct IamScimServ;
+
+/// 租户下账号凭证配置的 ID，用于区分账号的各类凭证
+struct ScimCertConfIds {
+    user_pwd: Option<String>,
+    mail: Option<String>,
+    phone: Option<String>,
+}
+
+impl IamScimServ {
+    /// 为当前租户签发 SCIM 令牌，令牌明文只在此时返回
+    ///
+    /// 令牌以不关联凭证配置的凭证保存，ak 为令牌的摘要，ext 为令牌名称。
+    pub async fn add_token(add_req: &IamScimTokenAddReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimTokenAddResp> {
+        let tenant_id = Self::get_tenant_id(funs, ctx)?;
+        let token = TardisFuns::crypto.key.generate_token()?;
+        let end_time = match add_req.expire_sec {
+            Some(expire_sec) => Some(
+                Utc::now()
+                    + Duration::try_seconds(expire_sec)
+                        .ok_or_else(|| funs.err().bad_request("iam_scim", "add_token", "expire_sec is invalid", "400-iam-scim-token-expire-invalid"))?,
+            ),
+            None => None,
+        };
+        let id = RbumCertServ::add_rbum(
+            &mut RbumCertAddReq {
+                ak: TrimString(Self::digest_token(&token)?),
+                sk: None,
+                sk_invisible: None,
+                kind: Some(IamCertExtKind::Scim.to_string()),
+                supplier: None,
+                vcode: None,
+                ext: Some(add_req.name.to_string()),
+                start_time: None,
+                end_time,
+                conn_uri: None,
+                status: RbumCertStatusKind::Enabled,
+                rel_rbum_cert_conf_id: None,
+                rel_rbum_kind: RbumCertRelKind::Item,
+                rel_rbum_id: tenant_id.clone(),
+                is_outside: true,
+                ignore_check_sk: false,
+            },
+            funs,
+            &TardisContext {
+                own_paths: tenant_id,
+                ..ctx.clone()
+            },
+        )
+        .await?;
+        Ok(IamScimTokenAddResp { id, token })
+    }
+
+    pub async fn find_tokens(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<IamScimTokenResp>> {
+        let tenant_id = Self::get_tenant_id(funs, ctx)?;
+        let certs = RbumCertServ::find_rbums(
+            &RbumCertFilterReq {
+                kind: Some(IamCertExtKind::Scim.to_string()),
+                rel_rbum_kind: Some(RbumCertRelKind::Item),
+                rel_rbum_id: Some(tenant_id),
+                ..Default::default()
+            },
+            Some(true),
+            None,
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(certs
+            .into_iter()
+            .map(|cert| IamScimTokenResp {
+                id: cert.id,
+                name: cert.ext,
+                create_time: cert.create_time,
+                end_time: cert.end_time,
+            })
+            .collect())
+    }
+
+    pub async fn delete_token(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let tenant_id = Self::get_tenant_id(funs, ctx)?;
+        let cert = RbumCertServ::find_one_rbum(
+            &RbumCertFilterReq {
+                id: Some(id.to_string()),
+                kind: Some(IamCertExtKind::Scim.to_string()),
+                rel_rbum_id: Some(tenant_id),
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?
+        .ok_or_else(|| funs.err().not_found("iam_scim", "delete_token", "token not found", "404-iam-scim-token-not-exist"))?;
+        RbumCertServ::delete_rbum(&cert.id, funs, ctx).await?;
+        Ok(())
+    }
+
+    /// 校验 `Authorization: Bearer <token>`，返回令牌所属租户的上下文
+    pub async fn auth(authorization: Option<&str>, funs: &TardisFunsInst) -> TardisResult<TardisContext> {
+        let Some(token) = authorization.and_then(|authorization| authorization.strip_prefix("Bearer ")).map(str::trim).filter(|token| !token.is_empty()) else {
+            return Err(funs.err().unauthorized("iam_scim", "auth", "bearer token is required", "401-iam-scim-token-invalid"));
+        };
+        let cert = RbumCertServ::find_one_rbum(
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    own_paths: Some("".to_string()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ak: Some(Self::digest_token(token)?),
+                kind: Some(IamCertExtKind::Scim.to_string()),
+                ..Default::default()
+            },
+            funs,
+            &TardisContext::default(),
+        )
+        .await?
+        .filter(|cert| cert.status == RbumCertStatusKind::Enabled && cert.end_time > Utc::now())
+        .ok_or_else(|| funs.err().unauthorized("iam_scim", "auth", "token is invalid or expired", "401-iam-scim-token-invalid"))?;
+        if IamTenantServ::is_disabled(&cert.rel_rbum_id, funs).await? {
+            return Err(funs.err().unauthorized("iam_scim", "auth", "tenant is disabled", "401-iam-scim-token-invalid"));
+        }
+        Ok(TardisContext {
+            own_paths: cert.own_paths,
+            owner: cert.owner,
+            ..Default::default()
+        })
+    }
+
+    pub async fn get_user(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimUser> {
+        let account = Self::get_account(id, funs, ctx).await?;
+        let conf_ids = Self::get_cert_conf_ids(funs, ctx).await?;
+        Self::package_user(account, &conf_ids, funs, ctx).await
+    }
+
+    /// 查询用户，`start_index` 按 `count` 对齐到所在的分页
+    pub async fn find_users(
+        filter: Option<&str>,
+        start_index: Option<u64>,
+        count: Option<u64>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<IamScimListResp<IamScimUser>> {
+        let conf_ids = Self::get_cert_conf_ids(funs, ctx).await?;
+        let count = count.unwrap_or(SCIM_MAX_RESULTS).min(SCIM_MAX_RESULTS);
+        let Some(account_filter) = Self::package_account_filter(filter, &conf_ids, funs, ctx).await? else {
+            return Ok(list_resp(0, 1, vec![]));
+        };
+        if count == 0 {
+            let total = IamAccountServ::count_items(&account_filter, funs, ctx).await?;
+            return Ok(list_resp(total, start_index.unwrap_or(1).max(1), vec![]));
+        }
+        let page_number = (start_index.unwrap_or(1).max(1) - 1) / count + 1;
+        let page = IamAccountServ::paginate_items(&account_filter, page_number as u32, count as u32, Some(false), None, funs, ctx).await?;
+        let mut users = Vec::with_capacity(page.records.len());
+        for account in page.records {
+            users.push(Self::package_user(account, &conf_ids, funs, ctx).await?);
+        }
+        Ok(list_resp(page.total_size, (page_number - 1) * count + 1, users))
+    }
+
+    pub async fn add_user(user: &IamScimUser, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimUser> {
+        let user_name = user
+            .user_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|user_name| !user_name.is_empty())
+            .ok_or_else(|| funs.err().bad_request("iam_scim", "add_user", "invalidValue: userName is required", "400-iam-scim-user-name-required"))?;
+        let conf_ids = Self::get_cert_conf_ids(funs, ctx).await?;
+        let role_ids = match &user.roles {
+            Some(roles) => Some(Self::package_role_ids(roles, &[], funs, ctx).await?),
+            None => None,
+        };
+        let mock_ctx = TardisContext {
+            own_paths: ctx.own_paths.clone(),
+            owner: TardisFuns::field.nanoid(),
+            ..Default::default()
+        };
+        let account_id = IamAccountServ::add_account_agg(
+            &IamAccountAggAddReq {
+                id: Some(TrimString(mock_ctx.owner.clone())),
+                name: TrimString(scim_user_display_name(user).unwrap_or_else(|| user_name.to_string())),
+                cert_user_name: TrimString(user_name.to_string()),
+                // 未提供密码时生成随机密码，用户通过身份提供方登录
+                cert_password: user.password.clone().map(TrimString),
+                // 在下面添加，避免向用户发送密码
+                cert_phone: None,
+                cert_mail: None,
+                role_ids,
+                org_node_ids: None,
+                scope_level: Some(RbumScopeLevelKind::Root),
+                disabled: user.active.map(|active| !active),
+                icon: None,
+                exts: None,
+                status: Some(if user.password.is_some() {
+                    RbumCertStatusKind::Enabled
+                } else {
+                    RbumCertStatusKind::Pending
+                }),
+                temporary: None,
+                lock_status: None,
+                logout_type: None,
+                labor_type: None,
+                id_card_no: None,
+                employee_code: None,
+                others_id: user.external_id.clone(),
+            },
+            false,
+            funs,
+            &mock_ctx,
+        )
+        .await?;
+        if let (Some(mail), Some(conf_id)) = (primary_value(&user.emails), &conf_ids.mail) {
+            IamCertMailVCodeServ::add_cert_skip_activate(&IamCertMailVCodeAddReq { mail }, &account_id, conf_id, funs, &mock_ctx).await?;
+        }
+        if let (Some(phone), Some(conf_id)) = (primary_value(&user.phone_numbers), &conf_ids.phone) {
+            IamCertPhoneVCodeServ::add_cert_skip_vcode(&IamCertPhoneVCodeAddReq { phone: TrimString(phone) }, &account_id, conf_id, funs, &mock_ctx).await?;
+        }
+        IamSearchClient::async_add_or_modify_account_search(&account_id, Box::new(false), "", funs, &mock_ctx).await?;
+        mock_ctx.execute_task().await?;
+        Self::get_user(&account_id, funs, ctx).await
+    }
+
+    /// 以请求中的用户替换已有用户
+    ///
+    /// 未提供的 `emails`、`phoneNumbers`、`roles` 保持不变，以免覆盖在 bios 中维护的数据。
+    /// `roles` 只替换可通过 SCIM 分配的角色，账号的其他角色保持不变。
+    pub async fn modify_user(id: &str, user: &IamScimUser, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimUser> {
+        let current = Self::get_user(id, funs, ctx).await?;
+        if user.user_name.as_deref().is_some_and(|user_name| Some(user_name.trim()) != current.user_name.as_deref()) {
+            return Err(funs.err().bad_request("iam_scim", "modify_user", "mutability: userName can not be changed", "400-iam-scim-user-name-immutable"));
+        }
+        let role_ids = match &user.roles {
+            Some(roles) => {
+                let current_role_ids = current.roles.iter().flatten().map(|role| role.value.clone()).collect::<Vec<_>>();
+                Some(Self::package_role_ids(roles, &current_role_ids, funs, ctx).await?)
+            }
+            None => None,
+        };
+        let mut modify_req = IamAccountAggModifyReq {
+            name: scim_user_display_name(user).filter(|name| Some(name) != current.display_name.as_ref()).map(TrimString),
+            cert_mail: primary_value(&user.emails).filter(|mail| Some(mail) != primary_value(&current.emails).as_ref()).map(TrimString),
+            cert_phone: primary_value(&user.phone_numbers).filter(|phone| Some(phone) != primary_value(&current.phone_numbers).as_ref()).map(TrimString),
+            role_ids,
+            ..Default::default()
+        };
+        if let Some(active) = user.active.filter(|active| Some(*active) != current.active) {
+            Self::fill_active(&mut modify_req, active);
+        }
+        IamAccountServ::modify_account_agg(id, &modify_req, funs, ctx).await?;
+        if let Some(external_id) = user.external_id.as_ref().filter(|external_id| Some(*external_id) != current.external_id.as_ref()) {
+            IamAccountServ::init_others_id_by_id(id, external_id, funs, ctx).await?;
+        }
+        if let Some(password) = &user.password {
+            if let Some(conf_id) = Self::get_cert_conf_ids(funs, ctx).await?.user_pwd {
+                Self::reset_password(id, password, &conf_id, funs, ctx).await?;
+            }
+        }
+        IamSearchClient::async_add_or_modify_account_search(id, Box::new(true), "", funs, ctx).await?;
+        Self::get_user(id, funs, ctx).await
+    }
+
+    pub async fn patch_user(id: &str, patch_req: &IamScimPatchReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimUser> {
+        let mut resource = TardisFuns::json.obj_to_json(&Self::get_user(id, funs, ctx).await?)?;
+        apply_patch(&mut resource, &patch_req.operations)?;
+        // 部分身份提供方以字符串传递布尔值
+        if let Some(active) = resource.get("active").and_then(Value::as_str).map(|active| active.eq_ignore_ascii_case("true")) {
+            resource["active"] = json!(active);
+        }
+        let mut user = parse_resource::<IamScimUser>(resource, funs)?;
+        // 删除全部角色后 roles 为空
+        user.roles = Some(user.roles.unwrap_or_default());
+        Self::modify_user(id, &user, funs, ctx).await
+    }
+
+    /// 停用用户，账号不会被删除
+    pub async fn delete_user(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        Self::get_account(id, funs, ctx).await?;
+        let mut modify_req = IamAccountAggModifyReq::default();
+        Self::fill_active(&mut modify_req, false);
+        IamAccountServ::modify_account_agg(id, &modify_req, funs, ctx).await?;
+        IamSearchClient::async_add_or_modify_account_search(id, Box::new(true), "", funs, ctx).await?;
+        Ok(())
+    }
+
+    pub async fn get_group(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimGroup> {
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let cate = Self::get_set_cate(&set_id, id, funs, ctx).await?;
+        Self::package_group(&set_id, cate, true, funs, ctx).await
+    }
+
+    /// 查询用户组，过滤与分页在内存中完成
+    pub async fn find_groups(
+        filter: Option<&str>,
+        start_index: Option<u64>,
+        count: Option<u64>,
+        exclude_members: bool,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<IamScimListResp<IamScimGroup>> {
+        let filters = filter.map(parse_filter).transpose()?.unwrap_or_default();
+        let with_members = !exclude_members || filters.iter().any(|filter| filter.attr.starts_with("members"));
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let cates = IamSetServ::find_set_cate(
+            &RbumSetCateFilterReq {
+                rel_rbum_set_id: Some(set_id.clone()),
+                ..Default::default()
+            },
+            Some(false),
+            None,
+            funs,
+            ctx,
+        )
+        .await?;
+        let mut groups = Vec::new();
+        for cate in cates {
+            let mut group = Self::package_group(&set_id, cate, with_members, funs, ctx).await?;
+            let resource = TardisFuns::json.obj_to_json(&group)?;
+            if filters.iter().all(|filter| filter.matches(&resource)) {
+                if exclude_members {
+                    group.members = None;
+                }
+                groups.push(group);
+            }
+        }
+        let start_index = start_index.unwrap_or(1).max(1);
+        let count = count.unwrap_or(SCIM_MAX_RESULTS).min(SCIM_MAX_RESULTS);
+        let total = groups.len() as u64;
+        let groups = groups.into_iter().skip(start_index as usize - 1).take(count as usize).collect();
+        Ok(list_resp(total, start_index, groups))
+    }
+
+    /// 新建用户组，对应组织架构下的一个分类，`externalId` 保存在分类的业务编码中
+    pub async fn add_group(group: &IamScimGroup, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimGroup> {
+        if group.display_name.trim().is_empty() {
+            return Err(funs.err().bad_request("iam_scim", "add_group", "invalidValue: displayName is required", "400-iam-scim-group-name-required"));
+        }
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let cate_id = IamSetServ::add_set_cate(
+            &set_id,
+            &IamSetCateAddReq {
+                name: TrimString(group.display_name.clone()),
+                scope_level: None,
+                bus_code: group.external_id.clone().map(TrimString),
+                icon: None,
+                sort: None,
+                ext: None,
+                rbum_parent_cate_id: None,
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        if let Some(members) = &group.members {
+            for member in members {
+                Self::add_member(&set_id, &cate_id, &member.value, funs, ctx).await?;
+            }
+        }
+        Self::get_group(&cate_id, funs, ctx).await
+    }
+
+    /// 以请求中的用户组替换已有用户组，未提供 `members` 时成员保持不变
+    pub async fn modify_group(id: &str, group: &IamScimGroup, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimGroup> {
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let cate = Self::get_set_cate(&set_id, id, funs, ctx).await?;
+        let name = Some(group.display_name.trim()).filter(|name| !name.is_empty() && *name != cate.name);
+        let bus_code = group.external_id.as_deref().filter(|external_id| *external_id != cate.bus_code);
+        if name.is_some() || bus_code.is_some() {
+            IamSetServ::modify_set_cate(
+                id,
+                &IamSetCateModifyReq {
+                    name: name.map(|name| TrimString(name.to_string())),
+                    scope_level: None,
+                    bus_code: bus_code.map(|bus_code| TrimString(bus_code.to_string())),
+                    icon: None,
+                    sort: None,
+                    ext: None,
+                },
+                funs,
+                ctx,
+            )
+            .await?;
+        }
+        if let Some(members) = &group.members {
+            let stored_items = Self::find_member_items(&set_id, Some(id), None, funs, ctx).await?;
+            for member in members {
+                if !stored_items.iter().any(|item| item.rel_rbum_item_id == member.value) {
+                    Self::add_member(&set_id, id, &member.value, funs, ctx).await?;
+                }
+            }
+            for item in stored_items {
+                if !members.iter().any(|member| member.value == item.rel_rbum_item_id) {
+                    IamSetServ::delete_set_item(&item.id, funs, ctx).await?;
+                }
+            }
+        }
+        Self::get_group(id, funs, ctx).await
+    }
+
+    pub async fn patch_group(id: &str, patch_req: &IamScimPatchReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimGroup> {
+        let mut resource = TardisFuns::json.obj_to_json(&Self::get_group(id, funs, ctx).await?)?;
+        apply_patch(&mut resource, &patch_req.operations)?;
+        let mut group = parse_resource::<IamScimGroup>(resource, funs)?;
+        // 删除全部成员后 members 为空
+        group.members = Some(group.members.unwrap_or_default());
+        Self::modify_group(id, &group, funs, ctx).await
+    }
+
+    /// 删除用户组，组内账号不受影响
+    pub async fn delete_group(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        Self::get_set_cate(&set_id, id, funs, ctx).await?;
+        for item in Self::find_member_items(&set_id, Some(id), None, funs, ctx).await? {
+            IamSetServ::delete_set_item(&item.id, funs, ctx).await?;
+        }
+        IamSetServ::delete_set_cate(id, funs, ctx).await?;
+        Ok(())
+    }
+
+    /// 按顺序执行批量操作，每个操作在独立的事务中执行
+    pub async fn bulk(bulk_req: &IamScimBulkReq, funs: &mut TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimBulkResp> {
+        if bulk_req.operations.len() > SCIM_MAX_BULK_OPERATIONS {
+            return Err(TardisError::custom(
+                "413",
+                &format!("the number of operations exceeds {SCIM_MAX_BULK_OPERATIONS}"),
+                "413-iam-scim-bulk-too-many",
+            ));
+        }
+        let base_url = Self::get_base_url(funs);
+        let mut bulk_ids = HashMap::new();
+        let mut errors = 0;
+        let mut operations = Vec::with_capacity(bulk_req.operations.len());
+        for operation in &bulk_req.operations {
+            if bulk_req.fail_on_errors.is_some_and(|fail_on_errors| fail_on_errors > 0 && errors >= fail_on_errors) {
+                break;
+            }
+            let path = replace_bulk_ids(&operation.path, &bulk_ids);
+            let data = operation.data.as_ref().map(|data| replace_bulk_ids(&data.to_string(), &bulk_ids));
+            funs.begin().await?;
+            let result = Self::do_bulk_operation(&operation.method, &path, data.as_deref(), funs, ctx).await;
+            let (status, location, response) = match result {
+                Ok((resource_path, status)) => {
+                    funs.commit().await?;
+                    if let (Some(bulk_id), Some(resource_path)) = (&operation.bulk_id, &resource_path) {
+                        bulk_ids.insert(bulk_id.clone(), resource_path.rsplit('/').next().unwrap_or_default().to_string());
+                    }
+                    (status.to_string(), resource_path.map(|resource_path| format!("{base_url}{resource_path}")), None)
+                }
+                Err(e) => {
+                    funs.rollback().await?;
+                    errors += 1;
+                    let (status, response) = scim_error(&e);
+                    (status.to_string(), None, Some(response))
+                }
+            };
+            operations.push(IamScimBulkOperationResp {
+                method: operation.method.clone(),
+                bulk_id: operation.bulk_id.clone(),
+                location,
+                status,
+                response,
+            });
+        }
+        Ok(IamScimBulkResp {
+            schemas: vec![SCIM_SCHEMA_BULK_RESPONSE.to_string()],
+            operations,
+        })
+    }
+
+    /// 执行单个批量操作，返回资源的路径及状态码
+    async fn do_bulk_operation(method: &str, path: &str, data: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<(Option<String>, u16)> {
+        if path.contains("bulkId:") || data.is_some_and(|data| data.contains("bulkId:")) {
+            return Err(funs.err().conflict("iam_scim", "bulk", "invalidValue: bulkId can not be resolved", "409-iam-scim-bulk-id-unresolved"));
+        }
+        let parse_data = || -> TardisResult<Value> {
+            let data = data.ok_or_else(|| funs.err().bad_request("iam_scim", "bulk", "invalidSyntax: data is required", "400-iam-scim-invalid-syntax"))?;
+            serde_json::from_str(data).map_err(|e| funs.err().bad_request("iam_scim", "bulk", &format!("invalidSyntax: {e}"), "400-iam-scim-invalid-syntax"))
+        };
+        let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+        match (method.to_uppercase().as_str(), segments.as_slice()) {
+            ("POST", ["Users"]) => {
+                let user = Self::add_user(&parse_resource(parse_data()?, funs)?, funs, ctx).await?;
+                Ok((Some(format!("/Users/{}", user.id.unwrap_or_default())), 201))
+            }
+            ("PUT", ["Users", id]) => {
+                Self::modify_user(id, &parse_resource(parse_data()?, funs)?, funs, ctx).await?;
+                Ok((Some(format!("/Users/{id}")), 200))
+            }
+            ("PATCH", ["Users", id]) => {
+                Self::patch_user(id, &parse_resource(parse_data()?, funs)?, funs, ctx).await?;
+                Ok((Some(format!("/Users/{id}")), 200))
+            }
+            ("DELETE", ["Users", id]) => {
+                Self::delete_user(id, funs, ctx).await?;
+                Ok((Some(format!("/Users/{id}")), 204))
+            }
+            ("POST", ["Groups"]) => {
+                let group = Self::add_group(&parse_resource(parse_data()?, funs)?, funs, ctx).await?;
+                Ok((Some(format!("/Groups/{}", group.id.unwrap_or_default())), 201))
+            }
+            ("PUT", ["Groups", id]) => {
+                Self::modify_group(id, &parse_resource(parse_data()?, funs)?, funs, ctx).await?;
+                Ok((Some(format!("/Groups/{id}")), 200))
+            }
+            ("PATCH", ["Groups", id]) => {
+                Self::patch_group(id, &parse_resource(parse_data()?, funs)?, funs, ctx).await?;
+                Ok((Some(format!("/Groups/{id}")), 200))
+            }
+            ("DELETE", ["Groups", id]) => {
+                Self::delete_group(id, funs, ctx).await?;
+                Ok((Some(format!("/Groups/{id}")), 204))
+            }
+            _ => Err(funs.err().bad_request("iam_scim", "bulk", &format!("invalidPath: {method} {path} is not supported"), "400-iam-scim-invalid-path")),
+        }
+    }
+
+    pub fn get_base_url(funs: &TardisFunsInst) -> String {
+        format!("{}{SCIM_BASE_PATH}", funs.conf::<IamConfig>().iam_base_url)
+    }
+
+    /// 将过滤条件转换为账号查询条件，条件不可能满足时返回 `None`
+    async fn package_account_filter(filter: Option<&str>, conf_ids: &ScimCertConfIds, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<IamAccountFilterReq>> {
+        let mut account_filter = IamAccountFilterReq::default();
+        let mut ids: Option<Vec<String>> = None;
+        for filter in filter.map(parse_filter).transpose()?.unwrap_or_default() {
+            let value = filter.value.as_ref().and_then(Value::as_str).map(str::to_string);
+            match (filter.attr.as_str(), &filter.op, value) {
+                ("id", ScimFilterOp::Eq, Some(value)) => ids = Some(intersect_ids(ids, vec![value])),
+                ("username", ScimFilterOp::Eq, Some(value)) => ids = Some(intersect_ids(ids, Self::find_account_ids_by_ak(&value, &conf_ids.user_pwd, funs, ctx).await?)),
+                ("emails" | "emails.value", ScimFilterOp::Eq, Some(value)) => {
+                    ids = Some(intersect_ids(ids, Self::find_account_ids_by_ak(&value, &conf_ids.mail, funs, ctx).await?))
+                }
+                ("externalid", ScimFilterOp::Eq, Some(value)) => account_filter.others_id = Some(value),
+                ("displayname" | "name.formatted", ScimFilterOp::Eq, Some(value)) => account_filter.basic.names = Some(vec![value]),
+                ("displayname" | "name.formatted", ScimFilterOp::Co, Some(value)) => account_filter.basic.name = Some(value),
+                ("active", ScimFilterOp::Eq, _) if filter.value.as_ref().is_some_and(Value::is_boolean) => {
+                    account_filter.basic.enabled = filter.value.as_ref().and_then(Value::as_bool)
+                }
+                _ => {
+                    return Err(funs.err().bad_request(
+                        "iam_scim",
+                        "find_users",
+                        &format!("invalidFilter: filter on {} is not supported", filter.attr),
+                        "400-iam-scim-invalid-filter",
+                    ))
+                }
+            }
+        }
+        if ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+            return Ok(None);
+        }
+        account_filter.basic.ids = ids;
+        Ok(Some(account_filter))
+    }
+
+    async fn find_account_ids_by_ak(ak: &str, conf_id: &Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<String>> {
+        let Some(conf_id) = conf_id else {
+            return Ok(vec![]);
+        };
+        let certs = IamCertServ::find_certs(
+            &RbumCertFilterReq {
+                ak: Some(ak.to_string()),
+                rel_rbum_kind: Some(RbumCertRelKind::Item),
+                rel_rbum_cert_conf_ids: Some(vec![conf_id.clone()]),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(certs.into_iter().map(|cert| cert.rel_rbum_id).collect())
+    }
+
+    async fn get_account(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamAccountSummaryResp> {
+        IamAccountServ::find_one_item(
+            &IamAccountFilterReq {
+                basic: RbumBasicFilterReq {
+                    ids: Some(vec![id.to_string()]),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?
+        .ok_or_else(|| funs.err().not_found("iam_scim", "get_user", &format!("user {id} not found"), "404-iam-scim-user-not-exist"))
+    }
+
+    async fn package_user(account: IamAccountSummaryResp, conf_ids: &ScimCertConfIds, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimUser> {
+        let certs = IamCertServ::find_certs(
+            &RbumCertFilterReq {
+                rel_rbum_kind: Some(RbumCertRelKind::Item),
+                rel_rbum_id: Some(account.id.clone()),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await?;
+        let find_ak = |conf_id: &Option<String>| certs.iter().find(|cert| conf_id.is_some() && cert.rel_rbum_cert_conf_id == *conf_id).map(|cert| cert.ak.clone());
+        let base_url = Self::get_base_url(funs);
+        let roles = IamAccountServ::find_simple_rel_roles(&account.id, true, None, None, funs, ctx).await?;
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let groups = Self::find_member_items(&set_id, None, Some(&account.id), funs, ctx).await?;
+        Ok(IamScimUser {
+            schemas: vec![SCIM_SCHEMA_USER.to_string()],
+            external_id: Some(account.others_id).filter(|others_id| !others_id.is_empty()),
+            user_name: find_ak(&conf_ids.user_pwd),
+            name: Some(IamScimName {
+                formatted: Some(account.name.clone()),
+                ..Default::default()
+            }),
+            display_name: Some(account.name),
+            active: Some(!account.disabled),
+            password: None,
+            emails: Some(find_ak(&conf_ids.mail).map(|mail| multi_valued(mail, Some("work"))).into_iter().collect()),
+            phone_numbers: Some(find_ak(&conf_ids.phone).map(|phone| multi_valued(phone, Some("mobile"))).into_iter().collect()),
+            roles: Some(
+                roles
+                    .into_iter()
+                    .map(|role| IamScimMultiValued {
+                        value: role.rel_id,
+                        display: Some(role.rel_name),
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+            groups: Some(
+                groups
+                    .into_iter()
+                    .filter_map(|item| {
+                        let cate_id = item.rel_rbum_set_cate_id?;
+                        Some(IamScimMultiValued {
+                            reference: Some(format!("{base_url}/Groups/{cate_id}")),
+                            value: cate_id,
+                            display: item.rel_rbum_set_cate_name,
+                            ..Default::default()
+                        })
+                    })
+                    .collect(),
+            ),
+            meta: Some(IamScimMeta {
+                resource_type: "User".to_string(),
+                created: Some(account.create_time),
+                last_modified: Some(account.update_time),
+                location: format!("{base_url}/Users/{}", account.id),
+            }),
+            id: Some(account.id),
+        })
+    }
+
+    /// 校验请求中的角色，返回账号应关联的全部角色
+    ///
+    /// 只能分配租户下未停用的租户级角色，不能分配管理员角色。
+    /// 账号已有的其他角色（如管理员角色）不由 SCIM 维护，原样保留，请求中重复提交时也不视为错误。
+    async fn package_role_ids(roles: &[IamScimMultiValued], current_role_ids: &[String], funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<String>> {
+        let assignable_role_ids = Self::find_assignable_role_ids(funs, ctx).await?;
+        let mut role_ids = current_role_ids.iter().filter(|role_id| !assignable_role_ids.contains(*role_id)).cloned().collect::<Vec<_>>();
+        for role in roles {
+            if role_ids.contains(&role.value) {
+                continue;
+            }
+            if !assignable_role_ids.contains(&role.value) {
+                return Err(funs.err().bad_request(
+                    "iam_scim",
+                    "package_role_ids",
+                    &format!("invalidValue: role {} can not be assigned", role.value),
+                    "400-iam-scim-role-invalid",
+                ));
+            }
+            role_ids.push(role.value.clone());
+        }
+        Ok(role_ids)
+    }
+
+    async fn find_assignable_role_ids(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<HashSet<String>> {
+        let admin_role_ids = [
+            funs.iam_basic_role_sys_admin_id(),
+            funs.iam_basic_role_tenant_admin_id(),
+            funs.iam_basic_role_app_admin_id(),
+        ];
+        let roles = IamRoleServ::find_items(
+            &IamRoleFilterReq {
+                kind: Some(IamRoleKind::Tenant),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(roles
+            .into_iter()
+            // 租户下的内置角色继承自平台的角色，继承管理员角色的同样视为管理员角色
+            .filter(|role| !role.disabled && !admin_role_ids.contains(&role.id) && !admin_role_ids.contains(&role.extend_role_id))
+            .map(|role| role.id)
+            .collect())
+    }
+
+    async fn package_group(set_id: &str, cate: RbumSetCateSummaryResp, with_members: bool, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimGroup> {
+        let base_url = Self::get_base_url(funs);
+        let members = if with_members {
+            let items = Self::find_member_items(set_id, Some(&cate.id), None, funs, ctx).await?;
+            Some(
+                items
+                    .into_iter()
+                    .map(|item| IamScimMultiValued {
+                        reference: Some(format!("{base_url}/Users/{}", item.rel_rbum_item_id)),
+                        value: item.rel_rbum_item_id,
+                        display: Some(item.rel_rbum_item_name),
+                        kind: Some("User".to_string()),
+                        ..Default::default()
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        };
+        Ok(IamScimGroup {
+            schemas: vec![SCIM_SCHEMA_GROUP.to_string()],
+            external_id: Some(cate.bus_code).filter(|bus_code| !bus_code.is_empty()),
+            display_name: cate.name,
+            members,
+            meta: Some(IamScimMeta {
+                resource_type: "Group".to_string(),
+                created: Some(cate.create_time),
+                last_modified: Some(cate.update_time),
+                location: format!("{base_url}/Groups/{}", cate.id),
+            }),
+            id: Some(cate.id),
+        })
+    }
+
+    async fn get_set_cate(set_id: &str, id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<RbumSetCateSummaryResp> {
+        IamSetServ::find_set_cate(
+            &RbumSetCateFilterReq {
+                basic: RbumBasicFilterReq {
+                    ids: Some(vec![id.to_string()]),
+                    ..Default::default()
+                },
+                rel_rbum_set_id: Some(set_id.to_string()),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await?
+        .pop()
+        .ok_or_else(|| funs.err().not_found("iam_scim", "get_group", &format!("group {id} not found"), "404-iam-scim-group-not-exist"))
+    }
+
+    /// 查询组织架构下的成员关系，包含已停用的账号
+    async fn find_member_items(
+        set_id: &str,
+        cate_id: Option<&str>,
+        account_id: Option<&str>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<Vec<RbumSetItemDetailResp>> {
+        RbumSetItemServ::find_detail_rbums(
+            &RbumSetItemFilterReq {
+                rel_rbum_set_id: Some(set_id.to_string()),
+                rel_rbum_set_cate_ids: cate_id.map(|cate_id| vec![cate_id.to_string()]),
+                rel_rbum_item_ids: account_id.map(|account_id| vec![account_id.to_string()]),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    async fn add_member(set_id: &str, cate_id: &str, account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        Self::get_account(account_id, funs, ctx).await.map_err(|_| {
+            funs.err().bad_request(
+                "iam_scim",
+                "add_member",
+                &format!("invalidValue: member {account_id} not found"),
+                "400-iam-scim-member-not-exist",
+            )
+        })?;
+        IamSetServ::add_set_item(
+            &IamSetItemAddReq {
+                set_id: set_id.to_string(),
+                set_cate_id: cate_id.to_string(),
+                sort: 0,
+                rel_rbum_item_id: account_id.to_string(),
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn reset_password(account_id: &str, password: &str, conf_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let cert = RbumCertServ::find_one_rbum(
+            &RbumCertFilterReq {
+                rel_rbum_kind: Some(RbumCertRelKind::Item),
+                rel_rbum_id: Some(account_id.to_string()),
+                rel_rbum_cert_conf_ids: Some(vec![conf_id.to_string()]),
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?
+        .ok_or_else(|| funs.err().not_found("iam_scim", "reset_password", "user password not found", "404-iam-scim-user-pwd-not-exist"))?;
+        IamCertUserPwdServ::check_sk_contains_ak(&cert.ak, password, funs)?;
+        RbumCertServ::reset_sk(&cert.id, password, false, &RbumCertFilterReq::default(), funs, ctx).await
+    }
+
+    /// 停用时与 LDAP 同步的停用方式一致，同时注销账号
+    fn fill_active(modify_req: &mut IamAccountAggModifyReq, active: bool) {
+        modify_req.disabled = Some(!active);
+        if active {
+            modify_req.status = Some(IamAccountStatusKind::Active);
+            modify_req.logout_type = Some(IamAccountLogoutTypeKind::NotLogout);
+        } else {
+            modify_req.status = Some(IamAccountStatusKind::Logout);
+            modify_req.logout_type = Some(IamAccountLogoutTypeKind::AutomaticLogout);
+        }
+    }
+
+    async fn get_cert_conf_ids(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ScimCertConfIds> {
+        let tenant_id = Self::get_tenant_id(funs, ctx)?;
+        let get_conf_id = |kind: IamCertKernelKind| {
+            let tenant_id = tenant_id.clone();
+            async move { IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&kind.to_string(), Some(tenant_id), funs).await.map(|conf| conf.map(|conf| conf.id)) }
+        };
+        Ok(ScimCertConfIds {
+            user_pwd: get_conf_id(IamCertKernelKind::UserPwd).await?,
+            mail: get_conf_id(IamCertKernelKind::MailVCode).await?,
+            phone: get_conf_id(IamCertKernelKind::PhoneVCode).await?,
+        })
+    }
+
+    fn get_tenant_id(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        get_max_level_id_by_context(ctx)
+            .filter(|tenant_id| !tenant_id.is_empty())
+            .ok_or_else(|| funs.err().unauthorized("iam_scim", "get_tenant_id", "tenant context is required", "401-iam-scim-tenant-required"))
+    }
+
+    fn digest_token(token: &str) -> TardisResult<String> {
+        TardisFuns::crypto.digest.sha256(token)
+    }
+}
+
+/// 将服务层错误转换为 SCIM Error，返回 HTTP 状态码及响应体
+///
+/// 错误信息以 `scimType:` 开头时作为 SCIM 错误类型返回。
+pub fn scim_error(e: &TardisError) -> (u16, Value) {
+    let status = e.code.split('-').next().and_then(|status| status.parse::<u16>().ok()).filter(|status| (400..600).contains(status)).unwrap_or(500);
+    let scim_type = match e.message.split_once(": ") {
+        Some((scim_type, _)) if SCIM_ERROR_TYPES.contains(&scim_type) => Some(scim_type.to_string()),
+        _ if status == 409 => Some("uniqueness".to_string()),
+        _ => None,
+    };
+    let mut error = json!({
+        "schemas": [SCIM_SCHEMA_ERROR],
+        "status": status.to_string(),
+        "detail": e.message,
+    });
+    if let Some(scim_type) = scim_type {
+        error["scimType"] = json!(scim_type);
+    }
+    (status, error)
+}
+
+pub fn get_service_provider_config(base_url: &str) -> Value {
+    json!({
+        "schemas": [SCIM_SCHEMA_SERVICE_PROVIDER_CONFIG],
+        "patch": { "supported": true },
+        "bulk": { "supported": true, "maxOperations": SCIM_MAX_BULK_OPERATIONS, "maxPayloadSize": SCIM_MAX_BULK_PAYLOAD_SIZE },
+        "filter": { "supported": true, "maxResults": SCIM_MAX_RESULTS },
+        "changePassword": { "supported": true },
+        "sort": { "supported": false },
+        "etag": { "supported": false },
+        "authenticationSchemes": [{
+            "type": "oauthbearertoken",
+            "name": "OAuth Bearer Token",
+            "description": "Tenant scoped token issued by the tenant console",
+            "primary": true
+        }],
+        "meta": { "resourceType": "ServiceProviderConfig", "location": format!("{base_url}/ServiceProviderConfig") },
+    })
+}
+
+pub fn get_resource_types(base_url: &str) -> Value {
+    let resource_types = [("User", "/Users", SCIM_SCHEMA_USER), ("Group", "/Groups", SCIM_SCHEMA_GROUP)]
+        .into_iter()
+        .map(|(name, endpoint, schema)| {
+            json!({
+                "schemas": [SCIM_SCHEMA_RESOURCE_TYPE],
+                "id": name,
+                "name": name,
+                "endpoint": endpoint,
+                "schema": schema,
+                "meta": { "resourceType": "ResourceType", "location": format!("{base_url}/ResourceTypes/{name}") },
+            })
+        })
+        .collect::<Vec<_>>();
+    TardisFuns::json.obj_to_json(&list_resp(resource_types.len() as u64, 1, resource_types)).unwrap_or_default()
+}
+
+pub fn get_schemas(base_url: &str) -> Value {
+    let attribute = |name: &str, kind: &str, multi_valued: bool, required: bool, mutability: &str, uniqueness: &str| {
+        let returned = if mutability == "writeOnly" { "never" } else { "default" };
+        json!({
+            "name": name,
+            "type": kind,
+            "multiValued": multi_valued,
+            "required": required,
+            "caseExact": false,
+            "mutability": mutability,
+            "returned": returned,
+            "uniqueness": uniqueness,
+        })
+    };
+    let schemas = vec![
+        json!({
+            "schemas": [SCIM_SCHEMA_SCHEMA],
+            "id": SCIM_SCHEMA_USER,
+            "name": "User",
+            "attributes": [
+                attribute("userName", "string", false, true, "immutable", "server"),
+                attribute("externalId", "string", false, false, "readWrite", "none"),
+                attribute("name", "complex", false, false, "readWrite", "none"),
+                attribute("displayName", "string", false, false, "readWrite", "none"),
+                attribute("active", "boolean", false, false, "readWrite", "none"),
+                attribute("password", "string", false, false, "writeOnly", "none"),
+                attribute("emails", "complex", true, false, "readWrite", "none"),
+                attribute("phoneNumbers", "complex", true, false, "readWrite", "none"),
+                attribute("roles", "complex", true, false, "readWrite", "none"),
+                attribute("groups", "complex", true, false, "readOnly", "none"),
+            ],
+            "meta": { "resourceType": "Schema", "location": format!("{base_url}/Schemas/{SCIM_SCHEMA_USER}") },
+        }),
+        json!({
+            "schemas": [SCIM_SCHEMA_SCHEMA],
+            "id": SCIM_SCHEMA_GROUP,
+            "name": "Group",
+            "attributes": [
+                attribute("displayName", "string", false, true, "readWrite", "none"),
+                attribute("externalId", "string", false, false, "readWrite", "none"),
+                attribute("members", "complex", true, false, "readWrite", "none"),
+            ],
+            "meta": { "resourceType": "Schema", "location": format!("{base_url}/Schemas/{SCIM_SCHEMA_GROUP}") },
+        }),
+    ];
+    TardisFuns::json.obj_to_json(&list_resp(schemas.len() as u64, 1, schemas)).unwrap_or_default()
+}
+
+fn list_resp<T>(total_results: u64, start_index: u64, resources: Vec<T>) -> IamScimListResp<T> {
+    IamScimListResp {
+        schemas: vec![SCIM_SCHEMA_LIST_RESPONSE.to_string()],
+        total_results,
+        start_index,
+        items_per_page: resources.len() as u64,
+        resources,
+    }
+}
+
+pub fn parse_resource<T: DeserializeOwned>(resource: Value, funs: &TardisFunsInst) -> TardisResult<T> {
+    serde_json::from_value(resource).map_err(|e| funs.err().bad_request("iam_scim", "parse_resource", &format!("invalidSyntax: {e}"), "400-iam-scim-invalid-syntax"))
+}
+
+fn multi_valued(value: String, kind: Option<&str>) -> IamScimMultiValued {
+    IamScimMultiValued {
+        value,
+        kind: kind.map(str::to_string),
+        primary: Some(true),
+        ..Default::default()
+    }
+}
+
+/// 多值属性中的主值，没有标记主值时取第一个
+fn primary_value(values: &Option<Vec<IamScimMultiValued>>) -> Option<String> {
+    let values = values.as_ref()?;
+    values.iter().find(|value| value.primary == Some(true)).or_else(|| values.first()).map(|value| value.value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+fn scim_user_display_name(user: &IamScimUser) -> Option<String> {
+    let name = user.name.as_ref();
+    user.display_name
+        .clone()
+        .or_else(|| name.and_then(|name| name.formatted.clone()))
+        .or_else(|| {
+            let parts = name.map(|name| [&name.given_name, &name.family_name].into_iter().flatten().map(String::as_str).collect::<Vec<_>>()).unwrap_or_default();
+            Some(parts.join(" ")).filter(|name| !name.is_empty())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+fn intersect_ids(ids: Option<Vec<String>>, new_ids: Vec<String>) -> Vec<String> {
+    match ids {
+        Some(ids) => ids.into_iter().filter(|id| new_ids.contains(id)).collect(),
+        None => new_ids,
+    }
+}
+
+fn replace_bulk_ids(value: &str, bulk_ids: &HashMap<String, String>) -> String {
+    bulk_ids.iter().fold(value.to_string(), |value, (bulk_id, id)| value.replace(&format!("bulkId:{bulk_id}"), id))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScimFilterOp {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+    Pr,
+}
+
+/// 形如 `attr op value` 的过滤条件，属性名已转为小写并去掉 schema 前缀
+#[derive(Debug, Clone)]
+struct ScimFilter {
+    attr: String,
+    op: ScimFilterOp,
+    value: Option<Value>,
+}
+
+impl ScimFilter {
+    fn matches(&self, resource: &Value) -> bool {
+        let values = find_attr_values(resource, &self.attr);
+        let expected = self.value.as_ref();
+        let eq = |value: &&Value| match (value, expected) {
+            (Value::String(value), Some(Value::String(expected))) => value.to_lowercase() == expected.to_lowercase(),
+            (value, Some(expected)) => *value == expected,
+            _ => false,
+        };
+        let str_match = |f: fn(&str, &str) -> bool| {
+            values.iter().any(|value| match (value.as_str(), expected.and_then(Value::as_str)) {
+                (Some(value), Some(expected)) => f(&value.to_lowercase(), &expected.to_lowercase()),
+                _ => false,
+            })
+        };
+        match self.op {
+            ScimFilterOp::Pr => values.iter().any(|value| !value.is_null() && value.as_str() != Some("") && value.as_array().map_or(true, |array| !array.is_empty())),
+            ScimFilterOp::Eq => values.iter().any(eq),
+            ScimFilterOp::Ne => !values.iter().any(eq),
+            ScimFilterOp::Co => str_match(|value, expected| value.contains(expected)),
+            ScimFilterOp::Sw => str_match(|value, expected| value.starts_with(expected)),
+            ScimFilterOp::Ew => str_match(|value, expected| value.ends_with(expected)),
+        }
+    }
+}
+
+fn invalid_filter(msg: &str) -> TardisError {
+    TardisError::bad_request(&format!("invalidFilter: {msg}"), "400-iam-scim-invalid-filter")
+}
+
+/// 去掉属性名中的 schema 前缀并转为小写
+fn normalize_attr(attr: &str) -> String {
+    let attr = if attr.to_lowercase().starts_with("urn:") {
+        attr.rsplit(':').next().unwrap_or_default()
+    } else {
+        attr
+    };
+    attr.to_lowercase()
+}
+
+/// 解析过滤表达式，支持 `and` 连接的 `eq`、`ne`、`co`、`sw`、`ew`、`pr` 条件
+fn parse_filter(filter: &str) -> TardisResult<Vec<ScimFilter>> {
+    let mut tokens = Vec::new();
+    let mut chars = filter.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => continue,
+            '(' | ')' | '[' | ']' => return Err(invalid_filter("grouping is not supported")),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => value.push(chars.next().ok_or_else(|| invalid_filter("unterminated string"))?),
+                        Some(c) => value.push(c),
+                        None => return Err(invalid_filter("unterminated string")),
+                    }
+                }
+                tokens.push((Value::String(value), true));
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.peek().filter(|c| !c.is_whitespace() && !matches!(**c, '(' | ')' | '[' | ']' | '"')) {
+                    word.push(*c);
+                    chars.next();
+                }
+                tokens.push((Value::String(word), false));
+            }
+        }
+    }
+    let mut tokens = tokens.into_iter();
+    let mut filters = Vec::new();
+    loop {
+        let Some((Value::String(attr), false)) = tokens.next() else {
+            return Err(invalid_filter("attribute is expected"));
+        };
+        let op = match tokens.next() {
+            Some((Value::String(op), false)) => match op.to_lowercase().as_str() {
+                "eq" => ScimFilterOp::Eq,
+                "ne" => ScimFilterOp::Ne,
+                "co" => ScimFilterOp::Co,
+                "sw" => ScimFilterOp::Sw,
+                "ew" => ScimFilterOp::Ew,
+                "pr" => ScimFilterOp::Pr,
+                op => return Err(invalid_filter(&format!("operator {op} is not supported"))),
+            },
+            _ => return Err(invalid_filter("operator is expected")),
+        };
+        let value = if op == ScimFilterOp::Pr {
+            None
+        } else {
+            match tokens.next() {
+                Some((value, true)) => Some(value),
+                Some((Value::String(value), false)) => Some(serde_json::from_str(&value).map_err(|_| invalid_filter(&format!("value {value} is invalid")))?),
+                _ => return Err(invalid_filter("value is expected")),
+            }
+        };
+        filters.push(ScimFilter {
+            attr: normalize_attr(&attr),
+            op,
+            value,
+        });
+        match tokens.next() {
+            None => return Ok(filters),
+            Some((Value::String(logic), false)) if logic.eq_ignore_ascii_case("and") => continue,
+            Some((Value::String(logic), false)) if logic.eq_ignore_ascii_case("or") || logic.eq_ignore_ascii_case("not") => {
+                return Err(invalid_filter(&format!("logical operator {logic} is not supported")))
+            }
+            _ => return Err(invalid_filter("logical operator is expected")),
+        }
+    }
+}
+
+/// 按属性路径取值，多值属性会被展开
+fn find_attr_values<'a>(resource: &'a Value, attr: &str) -> Vec<&'a Value> {
+    attr.split('.').fold(vec![resource], |values, name| {
+        values
+            .into_iter()
+            .flat_map(|value| match value {
+                Value::Array(array) => array.iter().collect(),
+                value => vec![value],
+            })
+            .filter_map(|value| value.as_object().and_then(|object| find_key(object, name)).and_then(|key| value.get(key)))
+            .flat_map(|value| match value {
+                Value::Array(array) => array.iter().collect(),
+                value => vec![value],
+            })
+            .collect()
+    })
+}
+
+/// 不区分大小写查找属性名
+fn find_key<'a>(object: &'a Map<String, Value>, name: &str) -> Option<&'a str> {
+    object.keys().find(|key| key.eq_ignore_ascii_case(name)).map(String::as_str)
+}
+
+fn invalid_patch(scim_type: &str, msg: &str) -> TardisError {
+    TardisError::bad_request(&format!("{scim_type}: {msg}"), "400-iam-scim-invalid-patch")
+}
+
+/// 解析 PATCH 路径 `attr[.sub]` 或 `attr[filter][.sub]`
+fn parse_patch_path(path: &str) -> TardisResult<(String, Option<Vec<ScimFilter>>, Option<String>)> {
+    let path = if path.to_lowercase().starts_with("urn:") {
+        let end = path.find('[').unwrap_or(path.len());
+        let start = path[..end].rfind(':').map(|i| i + 1).unwrap_or_default();
+        &path[start..]
+    } else {
+        path
+    };
+    let (attr, value_filter, sub) = match path.split_once('[') {
+        Some((attr, rest)) => {
+            let (value_filter, sub) = rest.split_once(']').ok_or_else(|| invalid_patch("invalidPath", path))?;
+            let sub = if sub.is_empty() {
+                None
+            } else {
+                Some(sub.strip_prefix('.').ok_or_else(|| invalid_patch("invalidPath", path))?.to_string())
+            };
+            (attr.to_string(), Some(parse_filter(value_filter)?), sub)
+        }
+        None => match path.split_once('.') {
+            Some((attr, sub)) => (attr.to_string(), None, Some(sub.to_string())),
+            None => (path.to_string(), None, None),
+        },
+    };
+    if attr.is_empty() {
+        return Err(invalid_patch("invalidPath", path));
+    }
+    Ok((attr, value_filter, sub))
+}
+
+/// 在资源的 JSON 表示上执行 PATCH 操作
+fn apply_patch(resource: &mut Value, operations: &[IamScimPatchOperation]) -> TardisResult<()> {
+    for operation in operations {
+        let op = operation.op.to_lowercase();
+        match (&operation.path, &operation.value) {
+            (None, Some(Value::Object(values))) if op == "add" || op == "replace" => {
+                for (path, value) in values {
+                    apply_patch_path(resource, &op, path, Some(value))?;
+                }
+            }
+            (None, _) => return Err(invalid_patch("noTarget", "path is required")),
+            (Some(path), value) => apply_patch_path(resource, &op, path, value.as_ref())?,
+        }
+    }
+    Ok(())
+}
+
+fn apply_patch_path(resource: &mut Value, op: &str, path: &str, value: Option<&Value>) -> TardisResult<()> {
+    let (attr, value_filter, sub) = parse_patch_path(path)?;
+    let object = resource.as_object_mut().ok_or_else(|| invalid_patch("invalidValue", "resource is not an object"))?;
+    let key = find_key(object, &attr).map(str::to_string).unwrap_or(attr);
+    if op != "add" && op != "replace" && op != "remove" {
+        return Err(invalid_patch("invalidSyntax", &format!("op {op} is not supported")));
+    }
+    if op != "remove" && value.is_none() {
+        return Err(invalid_patch("invalidValue", "value is required"));
+    }
+    if let Some(value_filter) = value_filter {
+        let Some(Value::Array(elements)) = object.get_mut(&key) else {
+            return Err(invalid_patch("noTarget", path));
+        };
+        let matched = |element: &Value| value_filter.iter().all(|filter| filter.matches(element));
+        if !elements.iter().any(matched) {
+            return if op == "remove" { Ok(()) } else { Err(invalid_patch("noTarget", path)) };
+        }
+        match (op, &sub) {
+            ("remove", None) => elements.retain(|element| !matched(element)),
+            (_, sub) => {
+                for element in elements.iter_mut().filter(|element| matched(&**element)) {
+                    match sub {
+                        Some(sub) => set_sub_attr(element, sub, if op == "remove" { None } else { value.cloned() }),
+                        None => *element = value.cloned().unwrap_or_default(),
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+    if let Some(sub) = sub {
+        let target = object.entry(key).or_insert_with(|| json!({}));
+        set_sub_attr(target, &sub, if op == "remove" { None } else { value.cloned() });
+        return Ok(());
+    }
+    match op {
+        "remove" => match (object.get_mut(&key), value) {
+            // 部分身份提供方以 value 指定要移除的成员
+            (Some(Value::Array(elements)), Some(value)) => {
+                let removed = value.as_array().cloned().unwrap_or_else(|| vec![value.clone()]);
+                elements.retain(|element| !removed.iter().any(|removed| element.get("value").is_some_and(|value| Some(value) == removed.get("value"))));
+            }
+            _ => {
+                object.remove(&key);
+            }
+        },
+        "add" => match (object.get_mut(&key), value) {
+            (Some(Value::Array(elements)), Some(value)) => {
+                for value in value.as_array().cloned().unwrap_or_else(|| vec![value.clone()]) {
+                    if !elements.iter().any(|element| *element == value || (element.get("value").is_some() && element.get("value") == value.get("value"))) {
+                        elements.push(value);
+                    }
+                }
+            }
+            (_, value) => {
+                object.insert(key, value.cloned().unwrap_or_default());
+            }
+        },
+        _ => {
+            object.insert(key, value.cloned().unwrap_or_default());
+        }
+    }
+    Ok(())
+}
+
+fn set_sub_attr(target: &mut Value, sub: &str, value: Option<Value>) {
+    if !target.is_object() {
+        *target = json!({});
+    }
+    let Some(object) = target.as_object_mut() else {
+        return;
+    };
+    let key = find_key(object, sub).map(str::to_string).unwrap_or_else(|| sub.to_string());
+    match value {
+        Some(value) => {
+            object.insert(key, value);
+        }
+        None => {
+            object.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patch(op: &str, path: Option<&str>, value: Option<Value>) -> IamScimPatchOperation {
+        IamScimPatchOperation {
+            op: op.to_string(),
+            path: path.map(str::to_string),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let filters = parse_filter(r#"userName eq "bJensen" and urn:ietf:params:scim:schemas:core:2.0:User:active eq true and title pr"#).unwrap();
+        assert_eq!(filters.len(), 3);
+        assert_eq!(filters[0].attr, "username");
+        assert_eq!(filters[0].op, ScimFilterOp::Eq);
+        assert_eq!(filters[0].value, Some(json!("bJensen")));
+        assert_eq!(filters[1].attr, "active");
+        assert_eq!(filters[1].value, Some(json!(true)));
+        assert_eq!(filters[2].op, ScimFilterOp::Pr);
+        assert_eq!(filters[2].value, None);
+
+        let filters = parse_filter(r#"displayName co "say \"hi\"""#).unwrap();
+        assert_eq!(filters[0].value, Some(json!(r#"say "hi""#)));
+
+        for filter in [
+            r#"userName eq "a" or userName eq "b""#,
+            r#"(userName eq "a")"#,
+            r#"userName gt "a""#,
+            r#"userName eq"#,
+            r#"userName eq "a"#,
+            r#"userName eq "a" and"#,
+        ] {
+            let e = parse_filter(filter).unwrap_err();
+            assert!(e.message.starts_with("invalidFilter: "), "{filter}");
+        }
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let group = json!({"displayName": "Sales", "members": [{"value": "u1"}, {"value": "u2"}]});
+        assert!(parse_filter(r#"displayname eq "sales""#).unwrap().iter().all(|filter| filter.matches(&group)));
+        assert!(parse_filter(r#"members.value eq "u2""#).unwrap().iter().all(|filter| filter.matches(&group)));
+        assert!(parse_filter(r#"displayName sw "Sa" and displayName ew "es""#).unwrap().iter().all(|filter| filter.matches(&group)));
+        assert!(!parse_filter(r#"members.value eq "u3""#).unwrap().iter().all(|filter| filter.matches(&group)));
+        assert!(parse_filter(r#"members.value ne "u3""#).unwrap().iter().all(|filter| filter.matches(&group)));
+        assert!(!parse_filter("externalId pr").unwrap().iter().all(|filter| filter.matches(&group)));
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        let mut user = json!({"userName": "bjensen", "active": true, "name": {"formatted": "Barbara"}, "emails": [{"value": "b@example.com", "type": "work"}]});
+        apply_patch(
+            &mut user,
+            &[
+                patch("Replace", None, Some(json!({"active": false, "displayName": "Babs"}))),
+                patch("replace", Some("name.givenName"), Some(json!("Barbara"))),
+                patch("replace", Some(r#"emails[type eq "work"].value"#), Some(json!("babs@example.com"))),
+                patch("add", Some("emails"), Some(json!([{"value": "home@example.com", "type": "home"}]))),
+            ],
+        )
+        .unwrap();
+        assert_eq!(user["active"], json!(false));
+        assert_eq!(user["displayName"], json!("Babs"));
+        assert_eq!(user["name"], json!({"formatted": "Barbara", "givenName": "Barbara"}));
+        assert_eq!(user["emails"][0]["value"], json!("babs@example.com"));
+        assert_eq!(user["emails"].as_array().unwrap().len(), 2);
+
+        apply_patch(&mut user, &[patch("remove", Some(r#"emails[type eq "home"]"#), None), patch("remove", Some("name"), None)]).unwrap();
+        assert_eq!(user["emails"].as_array().unwrap().len(), 1);
+        assert!(user.get("name").is_none());
+
+        assert!(apply_patch(&mut user, &[patch("replace", Some(r#"emails[type eq "home"].value"#), Some(json!("x")))]).unwrap_err().message.starts_with("noTarget: "));
+        assert!(apply_patch(&mut user, &[patch("remove", None, None)]).unwrap_err().message.starts_with("noTarget: "));
+    }
+
+    #[test]
+    fn test_apply_patch_members() {
+        let mut group = json!({"displayName": "Sales", "members": [{"value": "u1"}]});
+        apply_patch(
+            &mut group,
+            &[patch("Add", Some("members"), Some(json!([{"value": "u1"}, {"value": "u2"}, {"value": "u3"}])))],
+        )
+        .unwrap();
+        assert_eq!(group["members"], json!([{"value": "u1"}, {"value": "u2"}, {"value": "u3"}]));
+        apply_patch(
+            &mut group,
+            &[
+                patch("Remove", Some(r#"members[value eq "u1"]"#), None),
+                patch("Remove", Some("members"), Some(json!([{"value": "u2"}]))),
+            ],
+        )
+        .unwrap();
+        assert_eq!(group["members"], json!([{"value": "u3"}]));
+        apply_patch(&mut group, &[patch("replace", Some("members"), Some(json!([])))]).unwrap();
+        assert_eq!(group["members"], json!([]));
+    }
+
+    #[test]
+    fn test_scim_error() {
+        let (status, error) = scim_error(&TardisError::bad_request("invalidFilter: operator gt is not supported", "400-iam-scim-invalid-filter"));
+        assert_eq!(status, 400);
+        assert_eq!(error["scimType"], json!("invalidFilter"));
+        let (status, error) = scim_error(&TardisError::conflict("ak is used", "409-rbum-cert-ak-duplicate"));
+        assert_eq!(status, 409);
+        assert_eq!(error["scimType"], json!("uniqueness"));
+        let (status, error) = scim_error(&TardisError::not_found("user u1 not found", "404-iam-scim-user-not-exist"));
+        assert_eq!(status, 404);
+        assert_eq!(error["status"], json!("404"));
+        assert!(error.get("scimType").is_none());
+    }
+}
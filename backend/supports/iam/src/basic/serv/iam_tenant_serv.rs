@@ -46,7 +46,10 @@ use super::clients::iam_kv_client::IamKvClient;
 use super::clients::iam_log_client::{IamLogClient, LogParamTag};
 use super::clients::iam_search_client::IamSearchClient;
 use super::iam_cert_oauth2_serv::IamCertOAuth2Serv;
+use super::iam_cert_oidc_serv::IamCertOidcServ;
 use super::iam_cert_token_serv::IamCertTokenServ;
+use super::iam_cert_totp_serv::IamCertTotpServ;
+use super::iam_cert_webauthn_serv::IamCertWebAuthnServ;
 use super::iam_config_serv::IamConfigServ;
 use super::iam_platform_serv::IamPlatformServ;
 use super::iam_role_serv::IamRoleServ;
@@ -397,6 +400,9 @@ impl IamTenantServ {
             && modify_req.cert_conf_by_mail_vcode.is_none()
             && modify_req.cert_conf_by_oauth2.is_none()
             && modify_req.cert_conf_by_ldap.is_none()
+            && modify_req.cert_conf_by_totp.is_none()
+            && modify_req.cert_conf_by_webauthn.is_none()
+            && modify_req.cert_conf_by_oidc.is_none()
             && modify_req.token_default_coexist_num.is_none()
             && modify_req.config.is_none()
         {
@@ -410,6 +416,15 @@ impl IamTenantServ {
         if modify_req.cert_conf_by_mail_vcode.is_some() {
             log_tasks.push(("修改认证方式为邮箱".to_string(), "ModifyCertifiedWay".to_string()));
         }
+        if modify_req.cert_conf_by_totp.is_some() {
+            log_tasks.push(("修改认证方式为TOTP".to_string(), "ModifyCertifiedWay".to_string()));
+        }
+        if modify_req.cert_conf_by_webauthn.is_some() {
+            log_tasks.push(("修改认证方式为通行密钥".to_string(), "ModifyCertifiedWay".to_string()));
+        }
+        if modify_req.cert_conf_by_oidc.is_some() {
+            log_tasks.push(("修改认证方式为OpenID Connect".to_string(), "ModifyCertifiedWay".to_string()));
+        }
         for (op_describe, op_kind) in log_tasks {
             let _ = IamLogClient::add_ctx_task(LogParamTag::SecurityAlarm, None, op_describe, Some(op_kind), ctx).await;
         }
@@ -503,6 +518,37 @@ impl IamTenantServ {
             }
         }
 
+        if let Some(cert_conf_by_totp) = &modify_req.cert_conf_by_totp {
+            if let Some(cert_conf_id_by_totp) = cert_confs.iter().find(|r| r.kind == IamCertKernelKind::Totp.to_string()).map(|r| r.id.clone()) {
+                IamCertTotpServ::modify_cert_conf(&cert_conf_id_by_totp, cert_conf_by_totp, funs, ctx).await?;
+            } else {
+                IamCertTotpServ::add_cert_conf(cert_conf_by_totp, Some(id.to_string()), funs, ctx).await?;
+            }
+        }
+
+        if let Some(cert_conf_by_webauthn) = &modify_req.cert_conf_by_webauthn {
+            if let Some(cert_conf_id_by_webauthn) = cert_confs.iter().find(|r| r.kind == IamCertKernelKind::WebAuthn.to_string()).map(|r| r.id.clone()) {
+                IamCertWebAuthnServ::modify_cert_conf(&cert_conf_id_by_webauthn, cert_conf_by_webauthn, funs, ctx).await?;
+            } else {
+                IamCertWebAuthnServ::add_cert_conf(cert_conf_by_webauthn, Some(id.to_string()), funs, ctx).await?;
+            }
+        }
+
+        //oidc providers are told apart by the supplier, the ones not in the request are disabled
+        if let Some(cert_conf_by_oidc) = &modify_req.cert_conf_by_oidc {
+            let old_cert_conf_by_oidc = cert_confs.iter().filter(|r| r.kind == IamCertExtKind::Oidc.to_string()).collect::<Vec<_>>();
+            for cert_conf in &old_cert_conf_by_oidc {
+                if let Some(modify) = cert_conf_by_oidc.iter().find(|r| r.supplier.to_string() == cert_conf.supplier) {
+                    IamCertOidcServ::modify_cert_conf(&cert_conf.id, modify, funs, ctx).await?;
+                } else {
+                    IamCertServ::disable_cert_conf(&cert_conf.id, funs, ctx).await?;
+                }
+            }
+            for add in cert_conf_by_oidc.iter().filter(|r| !old_cert_conf_by_oidc.iter().any(|cert_conf| cert_conf.supplier == r.supplier.to_string())) {
+                IamCertOidcServ::add_or_enable_cert_conf(add, id, funs, ctx).await?;
+            }
+        }
+
         // modify config
         if let Some(config) = &modify_req.config {
             IamConfigServ::add_or_modify_batch(id, config.to_vec(), funs, ctx).await?;
@@ -533,6 +579,10 @@ impl IamTenantServ {
         } else {
             None
         };
+        let mut cert_conf_by_oidc = Vec::new();
+        for cert_conf in cert_confs.iter().filter(|r| r.kind == IamCertExtKind::Oidc.to_string()) {
+            cert_conf_by_oidc.push(IamCertOidcServ::get_cert_conf(&cert_conf.id, funs, ctx).await?);
+        }
         let mut vec1: Vec<IamCertConfLdapResp> = Vec::new();
         for ldap_conf in cert_confs.iter().filter(|r| r.kind == IamCertExtKind::Ldap.to_string()) {
             let conf = IamCertLdapServ::get_cert_conf(&ldap_conf.id, funs, ctx).await?;
@@ -561,6 +611,9 @@ impl IamTenantServ {
                 config,
                 cert_conf_by_oauth2,
                 cert_conf_by_ldap,
+                cert_conf_by_totp: cert_confs.iter().find(|r| r.kind == IamCertKernelKind::Totp.to_string()).map(|r| TardisFuns::json.str_to_obj(&r.ext)).transpose()?,
+                cert_conf_by_webauthn: cert_confs.iter().find(|r| r.kind == IamCertKernelKind::WebAuthn.to_string()).map(|r| TardisFuns::json.str_to_obj(&r.ext)).transpose()?,
+                cert_conf_by_oidc: if cert_conf_by_oidc.is_empty() { None } else { Some(cert_conf_by_oidc) },
                 strict_security_mode: funs.conf::<IamConfig>().strict_security_mode,
                 token_default_coexist_num: cert_confs.iter().find(|r| r.kind == IamCertTokenKind::TokenDefault.to_string()).map(|r| r.coexist_num).unwrap_or(1),
             };
@@ -0,0 +1,357 @@
+use serde::Serialize;
+use tardis::basic::error::TardisError;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::Value;
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::{Header, Path, Query};
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::poem_openapi::ApiResponse;
+use tardis::TardisFuns;
+
+use crate::basic::dto::iam_scim_dto::{IamScimBulkReq, IamScimGroup, IamScimPatchReq, IamScimUser};
+use crate::basic::serv::iam_scim_serv::{self, IamScimServ, SCIM_MAX_BULK_PAYLOAD_SIZE};
+use crate::iam_constants;
+
+/// SCIM 端点的响应，直接返回 SCIM 资源而非 `TardisResp` 包装
+#[derive(ApiResponse)]
+enum IamScimResp {
+    #[oai(status = 200)]
+    Ok(Json<Value>),
+    #[oai(status = 201)]
+    Created(Json<Value>, #[oai(header = "Location")] String),
+    #[oai(status = 204)]
+    NoContent,
+    /// SCIM Error，`scimType` 为错误类型
+    #[oai(status = 400)]
+    BadRequest(Json<Value>),
+    #[oai(status = 401)]
+    Unauthorized(Json<Value>),
+    #[oai(status = 404)]
+    NotFound(Json<Value>),
+    #[oai(status = 409)]
+    Conflict(Json<Value>),
+    #[oai(status = 413)]
+    PayloadTooLarge(Json<Value>),
+    #[oai(status = 500)]
+    InternalError(Json<Value>),
+}
+
+impl IamScimResp {
+    fn ok<T: Serialize>(result: TardisResult<T>) -> Self {
+        match result.and_then(|resource| TardisFuns::json.obj_to_json(&resource)) {
+            Ok(resource) => IamScimResp::Ok(Json(resource)),
+            Err(e) => Self::from_error(e),
+        }
+    }
+
+    fn created<T: Serialize>(result: TardisResult<T>) -> Self {
+        match result.and_then(|resource| TardisFuns::json.obj_to_json(&resource)) {
+            Ok(resource) => {
+                let location = resource["meta"]["location"].as_str().unwrap_or_default().to_string();
+                IamScimResp::Created(Json(resource), location)
+            }
+            Err(e) => Self::from_error(e),
+        }
+    }
+
+    fn no_content(result: TardisResult<()>) -> Self {
+        match result {
+            Ok(_) => IamScimResp::NoContent,
+            Err(e) => Self::from_error(e),
+        }
+    }
+
+    fn from_error(e: TardisError) -> Self {
+        let (status, error) = iam_scim_serv::scim_error(&e);
+        match status {
+            400 => IamScimResp::BadRequest(Json(error)),
+            401 | 403 => IamScimResp::Unauthorized(Json(error)),
+            404 => IamScimResp::NotFound(Json(error)),
+            409 => IamScimResp::Conflict(Json(error)),
+            413 => IamScimResp::PayloadTooLarge(Json(error)),
+            _ => IamScimResp::InternalError(Json(error)),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct IamCiScimApi;
+
+/// Interface Console SCIM 2.0 API
+/// 接口控制台 SCIM 2.0 供应API
+///
+/// Lets the identity provider of a tenant push users and groups into IAM (RFC 7643 / RFC 7644).
+/// Requests are authenticated by `Authorization: Bearer <token>` with a token issued by the tenant console.
+/// Users map to accounts, groups map to the categories of the tenant org tree.
+/// Deleting a user disables the account rather than deleting it.
+#[poem_openapi::OpenApi(prefix_path = "/ci/scim/v2", tag = "bios_basic::ApiTag::Interface")]
+impl IamCiScimApi {
+    /// Get Service Provider Config
+    /// 获取服务提供方配置
+    #[oai(path = "/ServiceProviderConfig", method = "get")]
+    async fn get_service_provider_config(&self) -> IamScimResp {
+        let funs = iam_constants::get_tardis_inst();
+        IamScimResp::Ok(Json(iam_scim_serv::get_service_provider_config(&IamScimServ::get_base_url(&funs))))
+    }
+
+    /// Find Resource Types
+    /// 获取资源类型
+    #[oai(path = "/ResourceTypes", method = "get")]
+    async fn find_resource_types(&self) -> IamScimResp {
+        let funs = iam_constants::get_tardis_inst();
+        IamScimResp::Ok(Json(iam_scim_serv::get_resource_types(&IamScimServ::get_base_url(&funs))))
+    }
+
+    /// Find Schemas
+    /// 获取资源模式
+    #[oai(path = "/Schemas", method = "get")]
+    async fn find_schemas(&self) -> IamScimResp {
+        let funs = iam_constants::get_tardis_inst();
+        IamScimResp::Ok(Json(iam_scim_serv::get_schemas(&IamScimServ::get_base_url(&funs))))
+    }
+
+    /// Find Users
+    /// 查询用户
+    ///
+    /// ``filter`` supports ``and`` of ``id``, ``userName``, ``externalId``, ``emails.value``, ``active`` ``eq`` and ``displayName`` ``eq``/``co``.
+    #[oai(path = "/Users", method = "get")]
+    async fn find_users(
+        &self,
+        filter: Query<Option<String>>,
+        #[oai(name = "startIndex")] start_index: Query<Option<u64>>,
+        count: Query<Option<u64>>,
+        #[oai(name = "Authorization")] authorization: Header<Option<String>>,
+    ) -> IamScimResp {
+        let funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            IamScimServ::find_users(filter.0.as_deref(), start_index.0, count.0, &funs, &ctx).await
+        }
+        .await;
+        IamScimResp::ok(result)
+    }
+
+    /// Get User
+    /// 获取用户
+    #[oai(path = "/Users/:id", method = "get")]
+    async fn get_user(&self, id: Path<String>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            IamScimServ::get_user(&id.0, &funs, &ctx).await
+        }
+        .await;
+        IamScimResp::ok(result)
+    }
+
+    /// Add User
+    /// 添加用户
+    #[oai(path = "/Users", method = "post")]
+    async fn add_user(&self, user: Json<Value>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            let user = iam_scim_serv::parse_resource::<IamScimUser>(user.0, &funs)?;
+            funs.begin().await?;
+            let user = IamScimServ::add_user(&user, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(user)
+        }
+        .await;
+        IamScimResp::created(result)
+    }
+
+    /// Replace User
+    /// 替换用户
+    #[oai(path = "/Users/:id", method = "put")]
+    async fn modify_user(&self, id: Path<String>, user: Json<Value>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            let user = iam_scim_serv::parse_resource::<IamScimUser>(user.0, &funs)?;
+            funs.begin().await?;
+            let user = IamScimServ::modify_user(&id.0, &user, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(user)
+        }
+        .await;
+        IamScimResp::ok(result)
+    }
+
+    /// Patch User
+    /// 修改用户
+    #[oai(path = "/Users/:id", method = "patch")]
+    async fn patch_user(&self, id: Path<String>, patch_req: Json<Value>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            let patch_req = iam_scim_serv::parse_resource::<IamScimPatchReq>(patch_req.0, &funs)?;
+            funs.begin().await?;
+            let user = IamScimServ::patch_user(&id.0, &patch_req, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(user)
+        }
+        .await;
+        IamScimResp::ok(result)
+    }
+
+    /// Delete User
+    /// 删除用户，账号被停用而不是删除
+    #[oai(path = "/Users/:id", method = "delete")]
+    async fn delete_user(&self, id: Path<String>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            funs.begin().await?;
+            IamScimServ::delete_user(&id.0, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await
+        }
+        .await;
+        IamScimResp::no_content(result)
+    }
+
+    /// Find Groups
+    /// 查询用户组
+    ///
+    /// ``filter`` supports ``and`` of ``eq``/``ne``/``co``/``sw``/``ew``/``pr`` on any attribute, e.g. ``displayName eq "Sales"``.
+    #[oai(path = "/Groups", method = "get")]
+    async fn find_groups(
+        &self,
+        filter: Query<Option<String>>,
+        #[oai(name = "startIndex")] start_index: Query<Option<u64>>,
+        count: Query<Option<u64>>,
+        #[oai(name = "excludedAttributes")] excluded_attributes: Query<Option<String>>,
+        #[oai(name = "Authorization")] authorization: Header<Option<String>>,
+    ) -> IamScimResp {
+        let funs = iam_constants::get_tardis_inst();
+        let exclude_members = excluded_attributes.0.as_deref().is_some_and(|attrs| attrs.split(',').any(|attr| attr.trim().eq_ignore_ascii_case("members")));
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            IamScimServ::find_groups(filter.0.as_deref(), start_index.0, count.0, exclude_members, &funs, &ctx).await
+        }
+        .await;
+        IamScimResp::ok(result)
+    }
+
+    /// Get Group
+    /// 获取用户组
+    #[oai(path = "/Groups/:id", method = "get")]
+    async fn get_group(
+        &self,
+        id: Path<String>,
+        #[oai(name = "excludedAttributes")] excluded_attributes: Query<Option<String>>,
+        #[oai(name = "Authorization")] authorization: Header<Option<String>>,
+    ) -> IamScimResp {
+        let funs = iam_constants::get_tardis_inst();
+        let exclude_members = excluded_attributes.0.as_deref().is_some_and(|attrs| attrs.split(',').any(|attr| attr.trim().eq_ignore_ascii_case("members")));
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            let mut group = IamScimServ::get_group(&id.0, &funs, &ctx).await?;
+            if exclude_members {
+                group.members = None;
+            }
+            Ok::<_, TardisError>(group)
+        }
+        .await;
+        IamScimResp::ok(result)
+    }
+
+    /// Add Group
+    /// 添加用户组
+    #[oai(path = "/Groups", method = "post")]
+    async fn add_group(&self, group: Json<Value>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            let group = iam_scim_serv::parse_resource::<IamScimGroup>(group.0, &funs)?;
+            funs.begin().await?;
+            let group = IamScimServ::add_group(&group, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(group)
+        }
+        .await;
+        IamScimResp::created(result)
+    }
+
+    /// Replace Group
+    /// 替换用户组
+    #[oai(path = "/Groups/:id", method = "put")]
+    async fn modify_group(&self, id: Path<String>, group: Json<Value>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            let group = iam_scim_serv::parse_resource::<IamScimGroup>(group.0, &funs)?;
+            funs.begin().await?;
+            let group = IamScimServ::modify_group(&id.0, &group, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(group)
+        }
+        .await;
+        IamScimResp::ok(result)
+    }
+
+    /// Patch Group
+    /// 修改用户组
+    #[oai(path = "/Groups/:id", method = "patch")]
+    async fn patch_group(&self, id: Path<String>, patch_req: Json<Value>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            let patch_req = iam_scim_serv::parse_resource::<IamScimPatchReq>(patch_req.0, &funs)?;
+            funs.begin().await?;
+            let group = IamScimServ::patch_group(&id.0, &patch_req, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(group)
+        }
+        .await;
+        IamScimResp::ok(result)
+    }
+
+    /// Delete Group
+    /// 删除用户组
+    #[oai(path = "/Groups/:id", method = "delete")]
+    async fn delete_group(&self, id: Path<String>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            funs.begin().await?;
+            IamScimServ::delete_group(&id.0, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await
+        }
+        .await;
+        IamScimResp::no_content(result)
+    }
+
+    /// Bulk
+    /// 批量操作
+    ///
+    /// Operations are executed in order, each in its own transaction.
+    #[oai(path = "/Bulk", method = "post")]
+    async fn bulk(&self, bulk_req: Json<Value>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamScimResp {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = IamScimServ::auth(authorization.0.as_deref(), &funs).await?;
+            if bulk_req.0.to_string().len() > SCIM_MAX_BULK_PAYLOAD_SIZE {
+                return Err(TardisError::custom(
+                    "413",
+                    &format!("the payload exceeds {SCIM_MAX_BULK_PAYLOAD_SIZE} bytes"),
+                    "413-iam-scim-bulk-too-large",
+                ));
+            }
+            let bulk_req = iam_scim_serv::parse_resource::<IamScimBulkReq>(bulk_req.0, &funs)?;
+            let resp = IamScimServ::bulk(&bulk_req, &mut funs, &ctx).await?;
+            ctx.execute_task().await?;
+            Ok(resp)
+        }
+        .await;
+        IamScimResp::ok(result)
+    }
+}
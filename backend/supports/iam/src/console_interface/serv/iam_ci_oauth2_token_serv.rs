@@ -33,6 +33,7 @@ impl IamCiOauth2AkSkServ {
             Oauth2GrantType::Password => {}
             Oauth2GrantType::ClientCredentials => {}
             Oauth2GrantType::RefreshToken => {}
+            Oauth2GrantType::DeviceCode => {}
         }
 
         let access_token = TardisFuns::crypto.key.generate_token()?;
@@ -13,8 +13,9 @@ use tardis::TardisFuns;
 
 use crate::basic::dto::iam_account_dto::{IamAccountInfoResp, IamAccountInfoWithUserPwdAkResp, IamCpUserPwdBindResp};
 use crate::basic::dto::iam_cert_dto::{
-    IamCertGenericValidateSkReq, IamCertMailVCodeActivateReq, IamCertMailVCodeAddReq, IamCertPhoneVCodeAddReq, IamCertPhoneVCodeBindReq, IamCertPwdNewReq, IamCertUserNameNewReq,
-    IamCertUserPwdModifyReq, IamCertUserPwdRestReq, IamContextFetchReq,
+    IamCertGenericValidateSkReq, IamCertMailVCodeActivateReq, IamCertMailVCodeAddReq, IamCertOidcAuthorizeResp, IamCertPhoneVCodeAddReq, IamCertPhoneVCodeBindReq,
+    IamCertPwdNewReq, IamCertTotpActivateResp, IamCertTotpEnrollResp, IamCertTotpValidateReq, IamCertUserNameNewReq, IamCertUserPwdModifyReq, IamCertUserPwdRestReq,
+    IamCertWebAuthnChallengeResp, IamCertWebAuthnFinishReq, IamCertWebAuthnRegisterStartReq, IamCertWebAuthnResp, IamContextFetchReq,
 };
 use crate::basic::serv::clients::iam_log_client::{IamLogClient, LogParamTag};
 use crate::basic::serv::iam_account_serv::IamAccountServ;
@@ -22,20 +23,24 @@ use crate::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
 use crate::basic::serv::iam_cert_phone_vcode_serv::IamCertPhoneVCodeServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
 use crate::basic::serv::iam_cert_token_serv::IamCertTokenServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
 use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
 use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
 use crate::basic::serv::iam_tenant_serv::IamTenantServ;
 use crate::console_passport::dto::iam_cp_cert_dto::{
-    IamCpExistMailVCodeReq, IamCpExistPhoneVCodeReq, IamCpLdapLoginReq, IamCpMailVCodeLoginGenVCodeReq, IamCpMailVCodeLoginReq, IamCpOAuth2BindCheckReq,
-    IamCpOAuth2LoginReq, IamCpPhoneVCodeLoginGenVCodeReq, IamCpPhoneVCodeLoginSendVCodeReq, IamCpTokenSwitchReq, IamCpUserPwdBindWithLdapReq, IamCpUserPwdCheckReq,
-    IamCpUserPwdLoginReq,
+    IamCpExistMailVCodeReq, IamCpExistPhoneVCodeReq, IamCpLdapLoginReq, IamCpMailVCodeLoginGenVCodeReq, IamCpMailVCodeLoginReq, IamCpOAuth2BindCheckReq, IamCpOAuth2LoginReq,
+    IamCpOidcLoginReq, IamCpPhoneVCodeLoginGenVCodeReq, IamCpPhoneVCodeLoginSendVCodeReq, IamCpTokenSwitchReq, IamCpUserPwdBindWithLdapReq, IamCpUserPwdCheckReq,
+    IamCpUserPwdLoginReq, IamCpWebAuthnLoginFinishReq, IamCpWebAuthnLoginStartReq,
 };
 #[cfg(feature = "ldap_client")]
 use crate::console_passport::serv::iam_cp_cert_ldap_serv::IamCpCertLdapServ;
 use crate::console_passport::serv::iam_cp_cert_mail_vcode_serv::IamCpCertMailVCodeServ;
 use crate::console_passport::serv::iam_cp_cert_oauth2_serv::IamCpCertOAuth2Serv;
+use crate::console_passport::serv::iam_cp_cert_oidc_serv::IamCpCertOidcServ;
 use crate::console_passport::serv::iam_cp_cert_phone_vcode_serv::IamCpCertPhoneVCodeServ;
 use crate::console_passport::serv::iam_cp_cert_user_pwd_serv::IamCpCertUserPwdServ;
+use crate::console_passport::serv::iam_cp_cert_webauthn_serv::IamCpCertWebAuthnServ;
 use crate::iam_constants;
 use crate::iam_enumeration::{IamCertKernelKind, IamCertOAuth2Supplier};
 use bios_basic::helper::request_helper::try_set_real_ip_from_req_to_ctx;
@@ -109,6 +114,66 @@ impl IamCpCertApi {
         TardisResp::ok(resp?)
     }
 
+    /// Enroll TOTP by Username and Password
+    /// 通过用户名密码绑定TOTP
+    ///
+    /// For the accounts that can't login until TOTP is enabled
+    /// 用于启用TOTP前无法登录的账号
+    #[oai(path = "/login/userpwd/totp/enroll", method = "put")]
+    async fn enroll_totp_by_user_pwd(&self, login_req: Json<IamCpUserPwdLoginReq>, request: &Request) -> TardisApiResult<IamCertTotpEnrollResp> {
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let resp = IamCpCertUserPwdServ::enroll_totp_by_user_pwd(&login_req.0, try_get_real_ip_from_req(request).await?, &funs).await?;
+        funs.commit().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Activate TOTP by Username and Password
+    /// 通过用户名密码激活TOTP
+    #[oai(path = "/login/userpwd/totp/activate", method = "put")]
+    async fn activate_totp_by_user_pwd(&self, login_req: Json<IamCpUserPwdLoginReq>, request: &Request) -> TardisApiResult<IamCertTotpActivateResp> {
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let resp = IamCpCertUserPwdServ::activate_totp_by_user_pwd(&login_req.0, try_get_real_ip_from_req(request).await?, &funs).await?;
+        funs.commit().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Start Login by Passkey
+    /// 开始通行密钥登录
+    ///
+    /// Pass the returned options to `navigator.credentials.get`, then finish by `/login/webauthn/finish`
+    /// 将返回的参数传给 `navigator.credentials.get`，再通过 `/login/webauthn/finish` 完成登录
+    #[oai(path = "/login/webauthn/start", method = "put")]
+    async fn start_login_by_webauthn(&self, start_req: Json<IamCpWebAuthnLoginStartReq>) -> TardisApiResult<IamCertWebAuthnChallengeResp> {
+        let funs = iam_constants::get_tardis_inst();
+        let resp = IamCpCertWebAuthnServ::start_login(&start_req.0, &funs).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Start Login by Username, Password and Passkey
+    /// 开始用户名密码及通行密钥登录
+    ///
+    /// For the accounts that require passkey as the second factor
+    /// 用于需通行密钥作为第二因素的账号
+    #[oai(path = "/login/userpwd/webauthn/start", method = "put")]
+    async fn start_login_by_user_pwd_and_webauthn(&self, login_req: Json<IamCpUserPwdLoginReq>, request: &Request) -> TardisApiResult<IamCertWebAuthnChallengeResp> {
+        let funs = iam_constants::get_tardis_inst();
+        let resp = IamCpCertWebAuthnServ::start_login_by_user_pwd(&login_req.0, try_get_real_ip_from_req(request).await?, &funs).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Finish Login by Passkey
+    /// 完成通行密钥登录
+    #[oai(path = "/login/webauthn/finish", method = "put")]
+    async fn finish_login_by_webauthn(&self, finish_req: Json<IamCpWebAuthnLoginFinishReq>, request: &Request) -> TardisApiResult<IamAccountInfoResp> {
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let resp = IamCpCertWebAuthnServ::finish_login(&finish_req.0, try_get_real_ip_from_req(request).await?, &funs).await?;
+        funs.commit().await?;
+        TardisResp::ok(resp)
+    }
+
     /// Logout By Token
     /// 通过Token登出
     #[oai(path = "/logout/:token", method = "delete")]
@@ -260,6 +325,58 @@ impl IamCpCertApi {
         TardisResp::ok(open_id)
     }
 
+    /// Start Login by OpenID Connect
+    /// 开始 OpenID Connect 登录
+    ///
+    /// Redirect the browser to the returned `authorize_url`, then login by `/login/oidc` with the code and state returned to the redirect uri
+    /// 将浏览器重定向到返回的 `authorize_url`，再用回调地址收到的 code 及 state 通过 `/login/oidc` 完成登录
+    #[oai(path = "/login/oidc/:supplier/authorize", method = "get")]
+    async fn authorize_login_by_oidc(&self, supplier: Path<String>, tenant_id: Query<Option<String>>) -> TardisApiResult<IamCertOidcAuthorizeResp> {
+        let funs = iam_constants::get_tardis_inst();
+        let resp = IamCpCertOidcServ::authorize_login(&supplier.0, tenant_id.0, &funs).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Login by OpenID Connect
+    /// 通过 OpenID Connect 登录
+    ///
+    /// 身份未绑定账号时，若提供方允许，将在租户下自动创建账号。
+    #[oai(path = "/login/oidc", method = "put")]
+    async fn login_by_oidc(&self, login_req: Json<IamCpOidcLoginReq>, request: &Request) -> TardisApiResult<IamAccountInfoResp> {
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let resp = IamCpCertOidcServ::login(&login_req.0, try_get_real_ip_from_req(request).await?, &funs).await?;
+        funs.commit().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Start to Bind OpenID Connect Identity to Current Account
+    /// 开始将 OpenID Connect 身份绑定到当前账号
+    #[oai(path = "/cert/oidc/:supplier/authorize", method = "get")]
+    async fn authorize_bind_oidc(&self, supplier: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<IamCertOidcAuthorizeResp> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        let resp = IamCpCertOidcServ::authorize_bind(&supplier.0, &funs, &ctx).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Bind OpenID Connect Identity to Current Account
+    /// 将 OpenID Connect 身份绑定到当前账号
+    ///
+    /// 返回绑定的 open_id。
+    #[oai(path = "/cert/oidc/bind", method = "put")]
+    async fn bind_oidc(&self, bind_req: Json<IamCpOidcLoginReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<String> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        funs.begin().await?;
+        let open_id = IamCpCertOidcServ::bind(&bind_req.0, &funs, &ctx).await?;
+        funs.commit().await?;
+        ctx.execute_task().await?;
+        TardisResp::ok(open_id)
+    }
+
     /// Check whether the oauth2 identity is bound to a local account
     /// 判断 oauth2 身份是否已绑定本地账号
     ///
@@ -343,6 +460,154 @@ impl IamCpCertApi {
         TardisResp::ok(Void {})
     }
 
+    /// Validate TOTP By Current Account
+    /// 通过当前账号验证TOTP
+    ///
+    /// Accepted as the double authentication like `/validate/userpwd`
+    /// 与 `/validate/userpwd` 一样可作为二次认证
+    #[oai(path = "/validate/totp", method = "put")]
+    async fn validate_by_totp(&self, req: Json<IamCertTotpValidateReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        funs.begin().await?;
+        IamCertTotpServ::double_auth(&req.0.code, &funs, &ctx).await?;
+        funs.commit().await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Enroll TOTP By Current Account
+    /// 通过当前账号绑定TOTP
+    ///
+    /// The returned secret is activated by `/cert/totp/activate`
+    /// 返回的密钥需通过 `/cert/totp/activate` 激活
+    #[oai(path = "/cert/totp/enroll", method = "put")]
+    async fn enroll_totp(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<IamCertTotpEnrollResp> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        funs.begin().await?;
+        let resp = IamCertTotpServ::enroll(&ctx.owner, &funs, &ctx).await?;
+        funs.commit().await?;
+        ctx.execute_task().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Activate TOTP By Current Account
+    /// 通过当前账号激活TOTP
+    #[oai(path = "/cert/totp/activate", method = "put")]
+    async fn activate_totp(&self, req: Json<IamCertTotpValidateReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<IamCertTotpActivateResp> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        funs.begin().await?;
+        let resp = IamCertTotpServ::activate(&ctx.owner, &req.0.code, &funs, &ctx).await?;
+        funs.commit().await?;
+        ctx.execute_task().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Delete TOTP By Current Account
+    /// 通过当前账号删除TOTP
+    ///
+    /// code: TOTP code or recovery code
+    #[oai(path = "/cert/totp", method = "delete")]
+    async fn delete_totp(&self, code: Query<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        funs.begin().await?;
+        IamCertTotpServ::delete_cert(&ctx.owner, &code.0, &funs, &ctx).await?;
+        funs.commit().await?;
+        ctx.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Start to Validate Passkey By Current Account
+    /// 开始通过当前账号验证通行密钥
+    #[oai(path = "/validate/webauthn/start", method = "put")]
+    async fn start_validate_by_webauthn(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<IamCertWebAuthnChallengeResp> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        let resp = IamCertWebAuthnServ::start_double_auth(&funs, &ctx).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Finish to Validate Passkey By Current Account
+    /// 完成通过当前账号验证通行密钥
+    ///
+    /// Accepted as the double authentication like `/validate/userpwd`
+    /// 与 `/validate/userpwd` 一样可作为二次认证
+    #[oai(path = "/validate/webauthn/finish", method = "put")]
+    async fn finish_validate_by_webauthn(&self, req: Json<IamCertWebAuthnFinishReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        funs.begin().await?;
+        IamCertWebAuthnServ::finish_double_auth(&req.0, &funs, &ctx).await?;
+        funs.commit().await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Start to Register Passkey By Current Account
+    /// 开始通过当前账号注册通行密钥
+    ///
+    /// Pass the returned options to `navigator.credentials.create`, then finish by `/cert/webauthn/register/finish`
+    /// 将返回的参数传给 `navigator.credentials.create`，再通过 `/cert/webauthn/register/finish` 完成注册
+    #[oai(path = "/cert/webauthn/register/start", method = "put")]
+    async fn start_register_webauthn(
+        &self,
+        req: Json<IamCertWebAuthnRegisterStartReq>,
+        ctx: TardisContextExtractor,
+        request: &Request,
+    ) -> TardisApiResult<IamCertWebAuthnChallengeResp> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        let resp = IamCertWebAuthnServ::start_register(&req.0.name, &funs, &ctx).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Finish to Register Passkey By Current Account
+    /// 完成通过当前账号注册通行密钥
+    #[oai(path = "/cert/webauthn/register/finish", method = "put")]
+    async fn finish_register_webauthn(&self, req: Json<IamCertWebAuthnFinishReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<String> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        funs.begin().await?;
+        let id = IamCertWebAuthnServ::finish_register(&req.0, &funs, &ctx).await?;
+        funs.commit().await?;
+        ctx.execute_task().await?;
+        TardisResp::ok(id)
+    }
+
+    /// Find Passkeys By Current Account
+    /// 获取当前账号的通行密钥
+    #[oai(path = "/cert/webauthn", method = "get")]
+    async fn find_webauthn_certs(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Vec<IamCertWebAuthnResp>> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        let resp = IamCertWebAuthnServ::find_certs(&ctx.owner, &funs, &ctx).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Delete Passkey By Current Account
+    /// 删除当前账号的通行密钥
+    #[oai(path = "/cert/webauthn/:id", method = "delete")]
+    async fn delete_webauthn_cert(&self, id: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        let ctx = IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?;
+        funs.begin().await?;
+        IamCertWebAuthnServ::delete_cert(&id.0, &ctx.owner, &funs, &ctx).await?;
+        funs.commit().await?;
+        ctx.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+
     // /// Add Mail-VCode Cert
     // /// Send Activation Mail
     // #[oai(path = "/cert/mailvcode/send", method = "put")]
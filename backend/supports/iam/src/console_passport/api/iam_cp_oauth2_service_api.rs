@@ -1,15 +1,22 @@
 use bios_basic::helper::request_helper::try_set_real_ip_from_req_to_ctx;
+use tardis::basic::error::TardisError;
 use tardis::basic::field::TrimString;
+use tardis::serde_json::{json, Value};
 use tardis::web::context_extractor::TardisContextExtractor;
 use tardis::web::poem::Request;
 use tardis::web::poem_openapi;
-use tardis::web::poem_openapi::param::Query;
+use tardis::web::poem_openapi::param::{Header, Path, Query};
 use tardis::web::poem_openapi::payload::{Form, Json};
 use tardis::web::poem_openapi::ApiResponse;
-use tardis::web::web_resp::{TardisApiResult, TardisResp};
+use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
+use tardis::TardisFuns;
 
-use crate::basic::dto::iam_cert_dto::{IamOauth2IntrospectReq, IamOauth2IntrospectResp, IamOauth2TokenResp, IamOauth2UserInfoResp};
-use crate::console_passport::dto::iam_cp_cert_dto::{IamCpOAuth2ServiceAuthorizeReq, IamCpOAuth2ServiceAuthorizeResp, IamCpOAuth2ServiceTokenReq};
+use crate::basic::dto::iam_cert_dto::{
+    IamOauth2ConsentResp, IamOauth2DeviceAuthorizationReq, IamOauth2IntrospectReq, IamOauth2IntrospectResp, IamOauth2RevokeReq, IamOauth2TokenResp, IamOauth2UserInfoResp,
+};
+use crate::console_passport::dto::iam_cp_cert_dto::{
+    IamCpOAuth2ConsentAddReq, IamCpOAuth2DeviceApproveReq, IamCpOAuth2ServiceAuthorizeReq, IamCpOAuth2ServiceAuthorizeResp, IamCpOAuth2ServiceTokenReq,
+};
 use crate::console_passport::serv::iam_cp_cert_oauth2_service_serv::IamCpCertOAuth2ServiceServ;
 use crate::iam_constants;
 use crate::iam_enumeration::OAuth2ResponseType;
@@ -17,7 +24,7 @@ use crate::iam_enumeration::OAuth2ResponseType;
 /// GET /authorize 的响应：已登录时 302 跳转回接入方，参数错误时返回 400
 #[derive(ApiResponse)]
 enum IamOAuth2AuthorizeRedirectResp {
-    /// 已登录，携带授权码重定向回接入方
+    /// 已登录，携带授权码重定向回接入方，或重定向到同意页面
     #[oai(status = 302)]
     Found(#[oai(header = "Location")] String),
     /// 参数或客户端校验错误
@@ -37,9 +44,11 @@ impl IamCpOAuth2ServiceApi {
     ///
     /// This endpoint generates an authorization code that can be exchanged for an access token.
     /// It follows the OAuth2 authorization code flow.
+    /// Unless the client is first-party, the account must have consented to the scope via `POST /consent`.
     ///
     /// 此端点生成可以交换访问令牌的授权码。
     /// 它遵循OAuth2授权码流程。
+    /// 非平台自有客户端须已通过 `POST /consent` 由账号同意所申请的授权范围。
     #[oai(path = "/authorize", method = "post")]
     async fn authorize(
         &self,
@@ -63,13 +72,18 @@ impl IamCpOAuth2ServiceApi {
     ///
     /// 面向浏览器跳转：当用户已登录（携带有效 `Tardis-Context`）时，生成授权码并以 302
     /// 重定向到接入方 `redirect_uri`，携带 `code` 与 `state`。
+    /// 配置了同意页面且账号尚未同意所申请的授权范围时，携带原始参数重定向到同意页面。
     #[oai(path = "/authorize", method = "get")]
+    #[allow(clippy::too_many_arguments)]
     async fn authorize_get(
         &self,
         client_id: Query<String>,
         redirect_uri: Query<String>,
         scope: Query<String>,
         state: Query<Option<String>>,
+        nonce: Query<Option<String>>,
+        code_challenge: Query<Option<String>>,
+        code_challenge_method: Query<Option<String>>,
         ctx: TardisContextExtractor,
         request: &Request,
     ) -> IamOAuth2AuthorizeRedirectResp {
@@ -83,8 +97,11 @@ impl IamCpOAuth2ServiceApi {
             scope: TrimString::from(scope.0),
             redirect_uri: TrimString::from(redirect_uri.0),
             response_type: OAuth2ResponseType::Code,
+            nonce: nonce.0,
+            code_challenge: code_challenge.0,
+            code_challenge_method: code_challenge_method.0,
         };
-        match IamCpCertOAuth2ServiceServ::generate_authorization_code(&authorize_req, &funs, &ctx.0).await {
+        match IamCpCertOAuth2ServiceServ::authorize_with_consent(&authorize_req, request.uri().query().unwrap_or_default(), &funs, &ctx.0).await {
             Ok(resp) => {
                 let _ = ctx.0.execute_task().await;
                 IamOAuth2AuthorizeRedirectResp::Found(resp.redirect_url)
@@ -96,11 +113,11 @@ impl IamCpOAuth2ServiceApi {
     /// OAuth2 Token Endpoint
     /// OAuth2令牌端点
     ///
-    /// This endpoint exchanges authorization codes or refresh tokens for access tokens.
-    /// Supports both authorization_code and refresh_token grant types.
+    /// This endpoint exchanges authorization codes, refresh tokens, client credentials or device codes for access tokens.
+    /// Standard OAuth2 clients should use `/cp/oidc/token` instead.
     ///
-    /// 此端点将授权码或刷新令牌交换为访问令牌。
-    /// 支持authorization_code和refresh_token两种授权类型。
+    /// 此端点将授权码、刷新令牌、客户端凭证或设备码交换为访问令牌。
+    /// 标准 OAuth2 客户端应使用 `/cp/oidc/token`。
     #[oai(path = "/token", method = "post")]
     async fn token(&self, token_req: Json<IamCpOAuth2ServiceTokenReq>, request: &Request) -> TardisApiResult<IamOauth2TokenResp> {
         let funs = iam_constants::get_tardis_inst();
@@ -138,4 +155,185 @@ impl IamCpOAuth2ServiceApi {
         let resp = IamCpCertOAuth2ServiceServ::introspect(&introspect_req.0.token, &funs).await?;
         TardisResp::ok(resp)
     }
+
+    /// Approve Or Deny Device Authorization
+    /// 确认或拒绝设备授权
+    #[oai(path = "/device", method = "put")]
+    async fn approve_device(&self, approve_req: Json<IamCpOAuth2DeviceApproveReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        IamCpCertOAuth2ServiceServ::approve_device(&approve_req.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Consent To The Client
+    /// 同意客户端申请的授权范围
+    #[oai(path = "/consent", method = "post")]
+    async fn add_consent(&self, consent_req: Json<IamCpOAuth2ConsentAddReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        IamCpCertOAuth2ServiceServ::add_consent(&consent_req.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Find Consents By Current Account
+    /// 获取当前账号的授权同意记录
+    #[oai(path = "/consent", method = "get")]
+    async fn find_consents(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Vec<IamOauth2ConsentResp>> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let resp = IamCpCertOAuth2ServiceServ::find_consents(&funs, &ctx.0).await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Withdraw Consent And Revoke Issued Tokens
+    /// 撤销对客户端的授权同意及已签发的令牌
+    #[oai(path = "/consent/:client_id", method = "delete")]
+    async fn delete_consent(&self, client_id: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        IamCpCertOAuth2ServiceServ::delete_consent(&client_id.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+}
+
+/// 标准 OpenID Connect 端点的响应，直接返回 JSON 而非 `TardisResp` 包装
+#[derive(ApiResponse)]
+enum IamOidcResp {
+    #[oai(status = 200)]
+    Ok(Json<Value>),
+    /// `{"error": .., "error_description": ..}`
+    #[oai(status = 400)]
+    BadRequest(Json<Value>),
+    #[oai(status = 401)]
+    Unauthorized(Json<Value>),
+}
+
+impl IamOidcResp {
+    fn from_result(result: Result<Value, TardisError>) -> Self {
+        match result {
+            Ok(value) => IamOidcResp::Ok(Json(value)),
+            Err(e) => Self::from_error(e),
+        }
+    }
+
+    /// 将服务层错误映射为 RFC 6749 5.2 的错误码
+    fn from_error(e: TardisError) -> Self {
+        let error = match e.message.as_str() {
+            "invalid_client" => return IamOidcResp::Unauthorized(Json(json!({ "error": "invalid_client", "error_description": e.message }))),
+            "invalid_scope" | "unsupported_grant_type" | "authorization_pending" | "slow_down" | "access_denied" | "expired_token" => e.message.clone(),
+            _ if e.code.starts_with("401") => "invalid_grant".to_string(),
+            _ => "invalid_request".to_string(),
+        };
+        IamOidcResp::BadRequest(Json(json!({ "error": error, "error_description": e.message })))
+    }
+}
+
+/// 解析 `client_secret_basic` 方式传递的客户端凭证
+fn parse_basic_authorization(authorization: Option<&str>) -> Option<(String, String)> {
+    let credentials = authorization?.strip_prefix("Basic ")?;
+    let credentials = TardisFuns::crypto.base64.decode_to_string(credentials.trim()).ok()?;
+    let (client_id, client_secret) = credentials.split_once(':')?;
+    Some((client_id.to_string(), client_secret.to_string()))
+}
+
+#[derive(Clone, Default)]
+pub struct IamCpOidcProviderApi;
+
+/// Passport Console OpenID Connect Provider API
+/// 通行证控制台 OpenID Connect Provider API
+///
+/// Endpoints follow the OAuth2 / OpenID Connect specifications so that standard client libraries can be used.
+#[poem_openapi::OpenApi(prefix_path = "/cp/oidc", tag = "bios_basic::ApiTag::Passport")]
+impl IamCpOidcProviderApi {
+    /// OpenID Connect Token Endpoint
+    /// OpenID Connect 令牌端点
+    ///
+    /// Client credentials can be passed by `client_secret_basic` or `client_secret_post`.
+    #[oai(path = "/token", method = "post")]
+    async fn token(&self, token_req: Form<IamCpOAuth2ServiceTokenReq>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamOidcResp {
+        let funs = iam_constants::get_tardis_inst();
+        let mut token_req = token_req.0;
+        if let Some((client_id, client_secret)) = parse_basic_authorization(authorization.0.as_deref()) {
+            token_req.client_id = client_id;
+            token_req.client_secret = client_secret;
+        }
+        let result = IamCpCertOAuth2ServiceServ::exchange_token(&token_req, &funs).await.and_then(|resp| TardisFuns::json.obj_to_json(&resp));
+        IamOidcResp::from_result(result)
+    }
+
+    /// OpenID Connect UserInfo Endpoint
+    /// OpenID Connect 用户信息端点
+    #[oai(path = "/userinfo", method = "get")]
+    async fn userinfo(&self, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamOidcResp {
+        let funs = iam_constants::get_tardis_inst();
+        let Some(access_token) = authorization.0.as_deref().and_then(|authorization| authorization.strip_prefix("Bearer ")) else {
+            return IamOidcResp::Unauthorized(Json(json!({ "error": "invalid_token", "error_description": "bearer token is required" })));
+        };
+        match IamCpCertOAuth2ServiceServ::get_userinfo_claims(access_token.trim(), &funs).await {
+            Ok(claims) => IamOidcResp::Ok(Json(claims)),
+            Err(e) => IamOidcResp::Unauthorized(Json(json!({ "error": "invalid_token", "error_description": e.message }))),
+        }
+    }
+
+    /// JSON Web Key Set Endpoint
+    /// id_token 签名公钥端点
+    #[oai(path = "/jwks", method = "get")]
+    async fn jwks(&self) -> IamOidcResp {
+        let funs = iam_constants::get_tardis_inst();
+        IamOidcResp::from_result(IamCpCertOAuth2ServiceServ::get_jwks(&funs).await)
+    }
+
+    /// Token Revocation Endpoint
+    /// 令牌撤销端点
+    #[oai(path = "/revoke", method = "post")]
+    async fn revoke(&self, revoke_req: Form<IamOauth2RevokeReq>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamOidcResp {
+        let funs = iam_constants::get_tardis_inst();
+        let mut revoke_req = revoke_req.0;
+        if let Some((client_id, client_secret)) = parse_basic_authorization(authorization.0.as_deref()) {
+            revoke_req.client_id = client_id;
+            revoke_req.client_secret = client_secret;
+        }
+        IamOidcResp::from_result(IamCpCertOAuth2ServiceServ::revoke(&revoke_req, &funs).await.map(|_| json!({})))
+    }
+
+    /// Device Authorization Endpoint
+    /// 设备授权端点
+    #[oai(path = "/device_authorization", method = "post")]
+    async fn device_authorization(&self, device_req: Form<IamOauth2DeviceAuthorizationReq>, #[oai(name = "Authorization")] authorization: Header<Option<String>>) -> IamOidcResp {
+        let funs = iam_constants::get_tardis_inst();
+        let mut device_req = device_req.0;
+        if let Some((client_id, client_secret)) = parse_basic_authorization(authorization.0.as_deref()) {
+            device_req.client_id = client_id;
+            device_req.client_secret = client_secret;
+        }
+        let result = IamCpCertOAuth2ServiceServ::device_authorization(&device_req, &funs).await.and_then(|resp| TardisFuns::json.obj_to_json(&resp));
+        IamOidcResp::from_result(result)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct IamCpOidcDiscoveryApi;
+
+/// OpenID Connect Discovery API
+/// OpenID Connect 发现文档 API
+#[poem_openapi::OpenApi(prefix_path = "/.well-known", tag = "bios_basic::ApiTag::Passport")]
+impl IamCpOidcDiscoveryApi {
+    /// OpenID Connect Discovery Document
+    /// OpenID Connect 发现文档
+    #[oai(path = "/openid-configuration", method = "get")]
+    async fn openid_configuration(&self) -> Json<Value> {
+        let funs = iam_constants::get_tardis_inst();
+        Json(IamCpCertOAuth2ServiceServ::get_discovery(&funs))
+    }
 }
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use tardis::basic::field::TrimString;
+use tardis::serde_json::Value;
 use tardis::web::poem_openapi;
 
 use crate::iam_enumeration::{OAuth2ResponseType, Oauth2GrantType};
@@ -14,6 +15,31 @@ pub struct IamCpUserPwdLoginReq {
     pub tenant_id: Option<String>,
     #[oai(validator(min_length = "2", max_length = "255"))]
     pub flag: Option<String>,
+    /// TOTP code or recovery code, required when the account enabled TOTP
+    #[oai(validator(min_length = "6", max_length = "255"))]
+    pub totp_code: Option<TrimString>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCpWebAuthnLoginStartReq {
+    /// Username of the account
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub ak: TrimString,
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub tenant_id: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCpWebAuthnLoginFinishReq {
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub state_id: String,
+    /// `PublicKeyCredential` returned by `navigator.credentials.get`
+    pub credential: Value,
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub flag: Option<String>,
+    /// TOTP code or recovery code, required when the account enabled TOTP
+    #[oai(validator(min_length = "6", max_length = "255"))]
+    pub totp_code: Option<TrimString>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -80,6 +106,18 @@ pub struct IamCpOAuth2LoginReq {
     pub tenant_id: Option<String>,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCpOidcLoginReq {
+    /// Authorization code returned to the redirect uri
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub code: TrimString,
+    /// State returned to the redirect uri
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub state: String,
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub flag: Option<String>,
+}
+
 /// 判断 OAuth2 身份是否已绑定的请求
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct IamCpOAuth2BindCheckReq {
@@ -154,17 +192,43 @@ pub struct IamCpOAuth2ServiceAuthorizeReq {
     pub redirect_uri: TrimString,
     #[oai(default)]
     pub response_type: OAuth2ResponseType,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct IamCpOAuth2ServiceTokenReq {
     pub grant_type: Oauth2GrantType,
     pub code: Option<String>,
+    /// Can also be passed by the basic authorization header
+    #[oai(default)]
     pub client_id: String,
+    #[oai(default)]
     pub client_secret: String,
     pub redirect_uri: Option<String>,
     pub refresh_token: Option<String>,
     pub scope: Option<String>,
+    pub code_verifier: Option<String>,
+    pub device_code: Option<String>,
+}
+
+/// 用户在同意页面同意客户端授权的请求
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCpOAuth2ConsentAddReq {
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub client_id: TrimString,
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub scope: TrimString,
+}
+
+/// 用户在验证页面确认设备授权的请求
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCpOAuth2DeviceApproveReq {
+    #[oai(validator(min_length = "2", max_length = "255"))]
+    pub user_code: TrimString,
+    /// false 表示拒绝授权
+    pub approve: bool,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -4,5 +4,7 @@ pub mod iam_cp_cert_ldap_serv;
 pub mod iam_cp_cert_mail_vcode_serv;
 pub mod iam_cp_cert_oauth2_serv;
 pub mod iam_cp_cert_oauth2_service_serv;
+pub mod iam_cp_cert_oidc_serv;
 pub mod iam_cp_cert_phone_vcode_serv;
 pub mod iam_cp_cert_user_pwd_serv;
+pub mod iam_cp_cert_webauthn_serv;
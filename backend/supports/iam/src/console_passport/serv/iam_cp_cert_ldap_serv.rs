@@ -1,6 +1,8 @@
 use crate::basic::dto::iam_account_dto::{IamAccountInfoResp, IamAccountInfoWithUserPwdAkResp, IamCpUserPwdBindResp};
 use crate::basic::serv::iam_cert_ldap_serv::IamCertLdapServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
 use crate::console_passport::dto::iam_cp_cert_dto::{IamCpLdapLoginReq, IamCpUserPwdBindWithLdapReq, IamCpUserPwdCheckReq};
 use crate::iam_enumeration::{IamCertKernelKind, IamCertTokenKind};
 use bios_basic::rbum::serv::rbum_cert_serv::RbumCertServ;
@@ -26,6 +28,8 @@ impl IamCpCertLdapServ {
             if RbumCertServ::cert_is_locked(&account_id, funs).await? {
                 return Err(funs.err().unauthorized("iam_cp_cert_ldap", "login_or_register", "cert is locked", "400-rbum-cert-lock"));
             }
+            IamCertTotpServ::check_login_without_code(&account_id, login_req.tenant_id.clone(), funs).await?;
+            IamCertWebAuthnServ::check_login_without_code(&account_id, login_req.tenant_id.clone(), funs).await?;
             let (ak, status) = Self::get_pwd_cert_name(&account_id, funs, &mock_ctx).await?;
             let iam_account_info_resp = IamCertServ::package_tardis_context_and_resp(
                 login_req.tenant_id.clone(),
@@ -72,6 +76,8 @@ impl IamCpCertLdapServ {
         funs: &TardisFunsInst,
     ) -> TardisResult<IamAccountInfoWithUserPwdAkResp> {
         let (account_id, access_token) = IamCertLdapServ::bind_or_create_user_pwd_by_ldap(login_req, funs).await?;
+        IamCertTotpServ::check_login_without_code(&account_id, login_req.tenant_id.clone(), funs).await?;
+        IamCertWebAuthnServ::check_login_without_code(&account_id, login_req.tenant_id.clone(), funs).await?;
 
         let iam_account_info_resp = IamCertServ::package_tardis_context_and_resp(
             login_req.tenant_id.clone(),
@@ -8,6 +8,8 @@ use crate::basic::dto::iam_account_dto::IamAccountInfoResp;
 use crate::basic::dto::iam_cert_dto::IamCertMailVCodeAddReq;
 use crate::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
 use crate::console_passport::dto::iam_cp_cert_dto::IamCpMailVCodeLoginReq;
 use crate::iam_enumeration::IamCertKernelKind;
 
@@ -55,6 +57,8 @@ impl IamCpCertMailVCodeServ {
             } else {
                 result?
             };
+            IamCertTotpServ::check_login_without_code(&rbum_item_id, Some(tenant_id.to_string()), funs).await?;
+            IamCertWebAuthnServ::check_login_without_code(&rbum_item_id, Some(tenant_id.to_string()), funs).await?;
             let resp = IamCertServ::package_tardis_context_and_resp(Some(tenant_id.to_string()), &rbum_item_id, login_req.flag.clone(), None, ip.clone(), funs).await?;
             Ok(resp)
         } else {
@@ -70,6 +74,8 @@ impl IamCpCertMailVCodeServ {
                 funs,
             )
             .await?;
+            IamCertTotpServ::check_login_without_code(&rbum_item_id, None, funs).await?;
+            IamCertWebAuthnServ::check_login_without_code(&rbum_item_id, None, funs).await?;
             let resp = IamCertServ::package_tardis_context_and_resp(None, &rbum_item_id, login_req.flag.clone(), None, ip, funs).await?;
             Ok(resp)
         }
@@ -1,6 +1,8 @@
 use crate::basic::dto::iam_account_dto::IamAccountInfoResp;
 use crate::basic::serv::iam_cert_oauth2_serv::IamCertOAuth2Serv;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
 use crate::console_passport::dto::iam_cp_cert_dto::IamCpOAuth2LoginReq;
 use crate::iam_enumeration::{IamCertExtKind, IamCertOAuth2Supplier, IamCertTokenKind};
 use tardis::basic::dto::TardisContext;
@@ -27,6 +29,8 @@ impl IamCpCertOAuth2Serv {
         funs: &TardisFunsInst,
     ) -> TardisResult<IamAccountInfoResp> {
         let oauth_info = IamCertOAuth2Serv::get_or_add_account(cert_supplier, login_req.code.as_ref(), &login_req.tenant_id.clone().unwrap_or_default(), funs).await?;
+        IamCertTotpServ::check_login_without_code(&oauth_info.0, login_req.tenant_id.clone(), funs).await?;
+        IamCertWebAuthnServ::check_login_without_code(&oauth_info.0, login_req.tenant_id.clone(), funs).await?;
         IamCertServ::package_tardis_context_and_resp(
             login_req.tenant_id.clone(),
             &oauth_info.0,
@@ -1,39 +1,80 @@
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::serde_json::Value;
 use tardis::TardisFunsInst;
 
 use crate::basic::dto::iam_cert_dto::{
-    IamCertOAuth2ServiceCodeAddReq, IamCertOAuth2ServiceCodeVerifyReq, IamCertOAuth2ServiceRefreshTokenReq, IamOauth2IntrospectResp, IamOauth2TokenResp, IamOauth2UserInfoResp,
+    IamCertOAuth2ServiceCodeAddReq, IamCertOAuth2ServiceCodeVerifyReq, IamCertOAuth2ServiceRefreshTokenReq, IamOauth2ConsentResp, IamOauth2DeviceAuthorizationReq,
+    IamOauth2DeviceAuthorizationResp, IamOauth2IntrospectResp, IamOauth2RevokeReq, IamOauth2TokenResp, IamOauth2UserInfoResp,
 };
 use crate::basic::serv::iam_cert_oauth2_service_serv::IamCertOAuth2ServiceServ;
-use crate::console_passport::dto::iam_cp_cert_dto::{IamCpOAuth2ServiceAuthorizeReq, IamCpOAuth2ServiceAuthorizeResp, IamCpOAuth2ServiceTokenReq};
+use crate::basic::serv::iam_cert_totp_serv::uri_encode;
+use crate::console_passport::dto::iam_cp_cert_dto::{
+    IamCpOAuth2ConsentAddReq, IamCpOAuth2DeviceApproveReq, IamCpOAuth2ServiceAuthorizeReq, IamCpOAuth2ServiceAuthorizeResp, IamCpOAuth2ServiceTokenReq,
+};
+use crate::iam_config::IamConfig;
 use crate::iam_enumeration::Oauth2GrantType;
 
 pub struct IamCpCertOAuth2ServiceServ;
 
 impl IamCpCertOAuth2ServiceServ {
-    /// Generate OAuth2 authorization code
-    /// 生成OAuth2授权码
+    /// Generate OAuth2 authorization code, requires the account's consent unless the client is first-party
+    /// 生成OAuth2授权码，非平台自有客户端须已由账号同意授权
     pub async fn generate_authorization_code(req: &IamCpOAuth2ServiceAuthorizeReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCpOAuth2ServiceAuthorizeResp> {
+        let redirect_url = Self::do_generate_authorization_code(req, funs, ctx).await?;
+        Ok(IamCpOAuth2ServiceAuthorizeResp { redirect_url })
+    }
+
+    /// Record the account's consent to the client, submitted by the consent page
+    /// 记录账号对客户端的同意，由同意页面提交
+    pub async fn add_consent(req: &IamCpOAuth2ConsentAddReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        IamCertOAuth2ServiceServ::add_consent(&req.client_id, &req.scope, funs, ctx).await
+    }
+
+    /// Browser authorization, redirect to the consent page when it is configured and the account has not consented yet
+    /// 浏览器授权，配置了同意页面且账号尚未同意时跳转到同意页面，未配置时拒绝授权
+    pub async fn authorize_with_consent(
+        req: &IamCpOAuth2ServiceAuthorizeReq,
+        query: &str,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<IamCpOAuth2ServiceAuthorizeResp> {
+        let consent_uri = &funs.conf::<IamConfig>().oauth2_consent_uri;
+        if consent_uri.is_empty() || !IamCertOAuth2ServiceServ::need_consent(&req.client_id, &req.scope, funs, ctx).await? {
+            return Self::generate_authorization_code(req, funs, ctx).await;
+        }
+        let redirect_url = if query.is_empty() {
+            consent_uri.clone()
+        } else {
+            format!("{consent_uri}{}{query}", if consent_uri.contains('?') { '&' } else { '?' })
+        };
+        Ok(IamCpOAuth2ServiceAuthorizeResp { redirect_url })
+    }
+
+    async fn do_generate_authorization_code(req: &IamCpOAuth2ServiceAuthorizeReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
         let code_req = IamCertOAuth2ServiceCodeAddReq {
             client_id: req.client_id.clone(),
             state: req.state.clone(),
             scope: req.scope.clone(),
             redirect_uri: req.redirect_uri.clone(),
             response_type: req.response_type.clone(),
+            nonce: req.nonce.clone(),
+            code_challenge: req.code_challenge.clone(),
+            code_challenge_method: req.code_challenge_method.clone(),
         };
 
         let code = IamCertOAuth2ServiceServ::generate_code(&code_req, funs, ctx).await?;
 
         // Build redirect URL with authorization code
         let mut redirect_url = req.redirect_uri.to_string();
-        redirect_url.push_str(&format!("?code={}", code));
+        redirect_url.push(if redirect_url.contains('?') { '&' } else { '?' });
+        redirect_url.push_str(&format!("code={}", uri_encode(&code)));
 
         if let Some(state) = &req.state {
-            redirect_url.push_str(&format!("&state={}", state));
+            redirect_url.push_str(&format!("&state={}", uri_encode(state)));
         }
 
-        Ok(IamCpOAuth2ServiceAuthorizeResp { redirect_url })
+        Ok(redirect_url)
     }
 
     /// Exchange authorization code or refresh token for access token
@@ -52,6 +93,7 @@ impl IamCpCertOAuth2ServiceServ {
                     client_id: req.client_id.clone(),
                     client_secret: req.client_secret.clone(),
                     redirect_uri: req.redirect_uri.clone(),
+                    code_verifier: req.code_verifier.clone(),
                 };
 
                 IamCertOAuth2ServiceServ::verify_code_and_generate_token(&verify_req, funs).await
@@ -76,10 +118,52 @@ impl IamCpCertOAuth2ServiceServ {
 
                 IamCertOAuth2ServiceServ::refresh_token(&refresh_req, funs).await
             }
+            Oauth2GrantType::ClientCredentials => IamCertOAuth2ServiceServ::client_credentials(&req.client_id, &req.client_secret, req.scope.as_deref(), funs).await,
+            Oauth2GrantType::DeviceCode => {
+                let device_code = req.device_code.as_ref().ok_or_else(|| {
+                    funs.err().bad_request(
+                        "oauth2",
+                        "exchange_token",
+                        "device_code is required for device_code grant",
+                        "400-oauth2-device-code-required",
+                    )
+                })?;
+                IamCertOAuth2ServiceServ::device_code_token(device_code, &req.client_id, &req.client_secret, funs).await
+            }
             _ => Err(funs.err().bad_request("oauth2", "exchange_token", "unsupported grant type", "400-oauth2-unsupported-grant-type")),
         }
     }
 
+    /// Start the device authorization
+    /// 发起设备授权
+    pub async fn device_authorization(req: &IamOauth2DeviceAuthorizationReq, funs: &TardisFunsInst) -> TardisResult<IamOauth2DeviceAuthorizationResp> {
+        IamCertOAuth2ServiceServ::device_authorization(req, funs).await
+    }
+
+    /// Approve or deny the device authorization by the user code
+    /// 根据用户码确认或拒绝设备授权
+    pub async fn approve_device(req: &IamCpOAuth2DeviceApproveReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        IamCertOAuth2ServiceServ::approve_device(&req.user_code, req.approve, funs, ctx).await
+    }
+
+    /// Revoke an access token or refresh token
+    /// 撤销访问令牌或刷新令牌
+    pub async fn revoke(req: &IamOauth2RevokeReq, funs: &TardisFunsInst) -> TardisResult<()> {
+        IamCertOAuth2ServiceServ::revoke(req, funs).await
+    }
+
+    /// Find the consents of the current account
+    /// 获取当前账号的同意记录
+    pub async fn find_consents(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<IamOauth2ConsentResp>> {
+        IamCertOAuth2ServiceServ::find_consents(funs, ctx).await
+    }
+
+    /// Withdraw the consent to the client
+    /// 撤销对客户端的同意
+    pub async fn delete_consent(client_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        IamCertOAuth2ServiceServ::delete_consent(client_id, funs, ctx).await
+    }
+
     /// Return the user info bound to an OAuth2 access token
     /// 根据 OAuth2 访问令牌返回用户信息
     pub async fn get_userinfo(access_token: &str, funs: &TardisFunsInst) -> TardisResult<IamOauth2UserInfoResp> {
@@ -92,6 +176,24 @@ impl IamCpCertOAuth2ServiceServ {
         IamCertOAuth2ServiceServ::build_userinfo_by_account_id(&ctx.owner, Some(&ctx.own_paths), funs).await
     }
 
+    /// Return the OpenID Connect standard claims bound to an access token
+    /// 根据访问令牌返回 OpenID Connect 标准声明
+    pub async fn get_userinfo_claims(access_token: &str, funs: &TardisFunsInst) -> TardisResult<Value> {
+        IamCertOAuth2ServiceServ::get_userinfo_claims(access_token, funs).await
+    }
+
+    /// Return the public keys to verify the id_token
+    /// 返回校验 id_token 的公钥
+    pub async fn get_jwks(funs: &TardisFunsInst) -> TardisResult<Value> {
+        IamCertOAuth2ServiceServ::get_jwks(funs).await
+    }
+
+    /// Return the OpenID Connect discovery document
+    /// 返回 OpenID Connect 发现文档
+    pub fn get_discovery(funs: &TardisFunsInst) -> Value {
+        IamCertOAuth2ServiceServ::get_discovery(funs)
+    }
+
     /// Introspect an OAuth2 token
     /// OAuth2 令牌内省
     pub async fn introspect(token: &str, funs: &TardisFunsInst) -> TardisResult<IamOauth2IntrospectResp> {
@@ -0,0 +1,39 @@
+use bios_basic::rbum::helper::rbum_scope_helper::get_max_level_id_by_context;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::TardisFunsInst;
+
+use crate::basic::dto::iam_account_dto::IamAccountInfoResp;
+use crate::basic::dto::iam_cert_dto::IamCertOidcAuthorizeResp;
+use crate::basic::serv::iam_cert_oidc_serv::IamCertOidcServ;
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
+use crate::console_passport::dto::iam_cp_cert_dto::IamCpOidcLoginReq;
+use crate::console_passport::serv::iam_cp_cert_user_pwd_serv::IamCpCertUserPwdServ;
+
+pub struct IamCpCertOidcServ;
+
+impl IamCpCertOidcServ {
+    pub async fn authorize_login(supplier: &str, tenant_id: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamCertOidcAuthorizeResp> {
+        let tenant_id = IamCpCertUserPwdServ::get_tenant_id(tenant_id, funs).await?;
+        IamCertOidcServ::authorize(supplier, &tenant_id, None, funs).await
+    }
+
+    pub async fn login(login_req: &IamCpOidcLoginReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamAccountInfoResp> {
+        let (account_id, tenant_id) = IamCertOidcServ::login(&login_req.code, &login_req.state, funs).await?;
+        IamCertTotpServ::check_login_without_code(&account_id, Some(tenant_id.clone()), funs).await?;
+        IamCertWebAuthnServ::check_login_without_code(&account_id, Some(tenant_id.clone()), funs).await?;
+        IamCertServ::package_tardis_context_and_resp(Some(tenant_id), &account_id, login_req.flag.clone(), None, ip, funs).await
+    }
+
+    /// Start to bind the identity of the provider to the current account
+    pub async fn authorize_bind(supplier: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertOidcAuthorizeResp> {
+        let tenant_id = get_max_level_id_by_context(ctx).unwrap_or_default();
+        IamCertOidcServ::authorize(supplier, &tenant_id, Some(ctx.owner.clone()), funs).await
+    }
+
+    pub async fn bind(bind_req: &IamCpOidcLoginReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        IamCertOidcServ::bind(&bind_req.code, &bind_req.state, funs, ctx).await
+    }
+}
@@ -9,6 +9,8 @@ use crate::basic::dto::iam_cert_dto::IamCertPhoneVCodeAddReq;
 
 use crate::basic::serv::iam_cert_phone_vcode_serv::IamCertPhoneVCodeServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
 use crate::console_passport::dto::iam_cp_cert_dto::IamCpPhoneVCodeLoginSendVCodeReq;
 use crate::iam_enumeration::IamCertKernelKind;
 
@@ -57,6 +59,8 @@ impl IamCpCertPhoneVCodeServ {
             } else {
                 result?
             };
+            IamCertTotpServ::check_login_without_code(&rbum_item_id, Some(tenant_id.to_string()), funs).await?;
+            IamCertWebAuthnServ::check_login_without_code(&rbum_item_id, Some(tenant_id.to_string()), funs).await?;
             let resp = IamCertServ::package_tardis_context_and_resp(Some(tenant_id.to_string()), &rbum_item_id, login_req.flag.clone(), None, ip.clone(), funs).await?;
             Ok(resp)
         } else {
@@ -72,6 +76,8 @@ impl IamCpCertPhoneVCodeServ {
                 funs,
             )
             .await?;
+            IamCertTotpServ::check_login_without_code(&rbum_item_id, None, funs).await?;
+            IamCertWebAuthnServ::check_login_without_code(&rbum_item_id, None, funs).await?;
             let resp = IamCertServ::package_tardis_context_and_resp(None, &rbum_item_id, login_req.flag.clone(), None, ip, funs).await?;
             Ok(resp)
         }
@@ -11,12 +11,14 @@ use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
 use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
 
 use crate::basic::dto::iam_account_dto::{IamAccountInfoResp, IamAccountModifyReq};
-use crate::basic::dto::iam_cert_dto::{IamCertPwdNewReq, IamCertUserNameNewReq, IamCertUserPwdModifyReq};
+use crate::basic::dto::iam_cert_dto::{IamCertPwdNewReq, IamCertTotpActivateResp, IamCertTotpEnrollResp, IamCertUserNameNewReq, IamCertUserPwdModifyReq};
 use crate::basic::serv::clients::iam_log_client::{IamLogClient, LogParamTag};
 use crate::basic::serv::iam_account_serv::IamAccountServ;
 use crate::basic::serv::iam_cert_ldap_serv::IamCertLdapServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
 use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
 use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
 use crate::basic::serv::iam_tenant_serv::IamTenantServ;
 use crate::console_passport::dto::iam_cp_cert_dto::IamCpUserPwdLoginReq;
@@ -180,6 +182,33 @@ impl IamCpCertUserPwdServ {
     }
 
     pub async fn login_by_user_pwd(login_req: &IamCpUserPwdLoginReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamAccountInfoResp> {
+        let rbum_item_id = Self::validate_login_user_pwd(login_req, ip.clone(), funs).await?;
+        let account_ctx = IamCertServ::get_account_ctx(&rbum_item_id, login_req.tenant_id.clone(), funs).await?;
+        let totp_verified = IamCertTotpServ::check_login(&rbum_item_id, login_req.totp_code.as_ref().map(|code| code.to_string()).as_deref(), funs, &account_ctx).await?;
+        if !totp_verified {
+            IamCertWebAuthnServ::check_login(&rbum_item_id, funs, &account_ctx).await?;
+        }
+        let resp = IamCertServ::package_tardis_context_and_resp(login_req.tenant_id.clone(), &rbum_item_id, login_req.flag.clone(), None, ip, funs).await?;
+        Ok(resp)
+    }
+
+    /// Enroll TOTP before login, for the accounts that can't login until they enabled TOTP
+    pub async fn enroll_totp_by_user_pwd(login_req: &IamCpUserPwdLoginReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamCertTotpEnrollResp> {
+        let account_id = Self::validate_login_user_pwd(login_req, ip, funs).await?;
+        let account_ctx = IamCertServ::get_account_ctx(&account_id, login_req.tenant_id.clone(), funs).await?;
+        IamCertTotpServ::enroll(&account_id, funs, &account_ctx).await
+    }
+
+    /// Activate TOTP enrolled by [`Self::enroll_totp_by_user_pwd`]
+    pub async fn activate_totp_by_user_pwd(login_req: &IamCpUserPwdLoginReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamCertTotpActivateResp> {
+        let code = login_req.totp_code.as_ref().ok_or_else(|| funs.err().bad_request("iam_cert_totp", "activate", "TOTP code is required", "400-iam-cert-totp-code-require"))?;
+        let account_id = Self::validate_login_user_pwd(login_req, ip, funs).await?;
+        let account_ctx = IamCertServ::get_account_ctx(&account_id, login_req.tenant_id.clone(), funs).await?;
+        IamCertTotpServ::activate(&account_id, code, funs, &account_ctx).await
+    }
+
+    /// Validate the username and password of the login request, return the account id
+    pub async fn validate_login_user_pwd(login_req: &IamCpUserPwdLoginReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<String> {
         let tenant_id = Self::get_tenant_id(login_req.tenant_id.clone(), funs).await?;
         let validate_resp = IamCertServ::validate_by_ak_and_sk(
             &login_req.ak,
@@ -223,8 +252,7 @@ impl IamCpCertUserPwdServ {
             )
             .await?
         };
-        let resp = IamCertServ::package_tardis_context_and_resp(login_req.tenant_id.clone(), &rbum_item_id, login_req.flag.clone(), None, ip, funs).await?;
-        Ok(resp)
+        Ok(rbum_item_id)
     }
 
     pub async fn get_tenant_id(tenant_id: Option<String>, funs: &TardisFunsInst) -> TardisResult<String> {
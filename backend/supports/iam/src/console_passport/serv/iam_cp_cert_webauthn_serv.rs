@@ -0,0 +1,96 @@
+use bios_basic::rbum::dto::rbum_filer_dto::RbumBasicFilterReq;
+use bios_basic::rbum::helper::rbum_scope_helper::get_max_level_id_by_context;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::TardisFunsInst;
+
+use crate::basic::dto::iam_account_dto::IamAccountInfoResp;
+use crate::basic::dto::iam_cert_dto::IamCertWebAuthnChallengeResp;
+use crate::basic::dto::iam_filer_dto::IamAccountFilterReq;
+use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
+use crate::console_passport::dto::iam_cp_cert_dto::{IamCpUserPwdLoginReq, IamCpWebAuthnLoginFinishReq, IamCpWebAuthnLoginStartReq};
+use crate::console_passport::serv::iam_cp_cert_user_pwd_serv::IamCpCertUserPwdServ;
+use crate::iam_enumeration::{IamAccountLockStateKind, IamCertKernelKind};
+
+pub struct IamCpCertWebAuthnServ;
+
+impl IamCpCertWebAuthnServ {
+    /// Start to login by passkey without password, the tenant has to enable passkey as the primary factor
+    ///
+    /// Unknown, disabled and unenrolled accounts get the same error, so that the usernames can't be probed by this api.
+    pub async fn start_login(start_req: &IamCpWebAuthnLoginStartReq, funs: &TardisFunsInst) -> TardisResult<IamCertWebAuthnChallengeResp> {
+        let unavailable = || {
+            funs.err().unauthorized(
+                "iam_cert_webauthn",
+                "start_login",
+                "passkey login is not available, please login by password",
+                "401-iam-cert-webauthn-login-unavailable",
+            )
+        };
+        let tenant_id = IamCpCertUserPwdServ::get_tenant_id(start_req.tenant_id.clone(), funs).await?;
+        let Some(account_id) = Self::get_account_id_by_user_name(&start_req.ak, &tenant_id, funs).await? else {
+            return Err(unavailable());
+        };
+        let account_ctx = IamCertServ::get_account_ctx(&account_id, start_req.tenant_id.clone(), funs).await?;
+        let account = IamAccountServ::peek_item(
+            &account_id,
+            &IamAccountFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            &account_ctx,
+        )
+        .await?;
+        if account.disabled || account.lock_status != IamAccountLockStateKind::Unlocked {
+            return Err(unavailable());
+        }
+        if !IamCertWebAuthnServ::get_cert_conf(get_max_level_id_by_context(&account_ctx), funs).await?.map(|(_, cert_conf)| cert_conf.primary_enabled).unwrap_or(false) {
+            return Err(unavailable());
+        }
+        // no authenticator is registered
+        IamCertWebAuthnServ::start_login(&account_id, start_req.tenant_id.clone(), funs, &account_ctx)
+            .await
+            .map_err(|e| if e.code.starts_with("404") { unavailable() } else { e })
+    }
+
+    /// Start to login by passkey as the second factor after the password
+    pub async fn start_login_by_user_pwd(login_req: &IamCpUserPwdLoginReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamCertWebAuthnChallengeResp> {
+        let account_id = IamCpCertUserPwdServ::validate_login_user_pwd(login_req, ip, funs).await?;
+        let account_ctx = IamCertServ::get_account_ctx(&account_id, login_req.tenant_id.clone(), funs).await?;
+        IamCertWebAuthnServ::start_login(&account_id, login_req.tenant_id.clone(), funs, &account_ctx).await
+    }
+
+    pub async fn finish_login(finish_req: &IamCpWebAuthnLoginFinishReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamAccountInfoResp> {
+        let (account_id, tenant_id) = IamCertWebAuthnServ::finish_login(&finish_req.state_id, &finish_req.credential, funs).await?;
+        // passkey doesn't replace TOTP, the accounts that enabled it give the code as well
+        let account_ctx = IamCertServ::get_account_ctx(&account_id, tenant_id.clone(), funs).await?;
+        IamCertTotpServ::check_login(&account_id, finish_req.totp_code.as_ref().map(|code| code.to_string()).as_deref(), funs, &account_ctx).await?;
+        IamCertServ::package_tardis_context_and_resp(tenant_id, &account_id, finish_req.flag.clone(), None, ip, funs).await
+    }
+
+    /// Find the account of the username in the tenant, then in the platform for the global accounts
+    async fn get_account_id_by_user_name(user_name: &str, tenant_id: &str, funs: &TardisFunsInst) -> TardisResult<Option<String>> {
+        let rel_iam_item_ids = if tenant_id.is_empty() { vec![None] } else { vec![Some(tenant_id.to_string()), None] };
+        for rel_iam_item_id in rel_iam_item_ids {
+            let Ok(rbum_cert_conf_id) = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::UserPwd.to_string(), rel_iam_item_id.clone(), funs).await else {
+                continue;
+            };
+            let mock_ctx = TardisContext {
+                own_paths: rel_iam_item_id.unwrap_or_default(),
+                ..Default::default()
+            };
+            if let Some(account_id) = IamCpCertUserPwdServ::get_cert_rel_account_by_user_name(user_name, &rbum_cert_conf_id, funs, &mock_ctx).await? {
+                return Ok(Some(account_id));
+            }
+        }
+        Ok(None)
+    }
+}
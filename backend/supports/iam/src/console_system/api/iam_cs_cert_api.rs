@@ -1,5 +1,6 @@
 use bios_basic::process::task_processor::TaskProcessor;
 use bios_basic::rbum::helper::rbum_event_helper;
+use tardis::basic::field::TrimString;
 use tardis::web::context_extractor::TardisContextExtractor;
 use tardis::web::poem_openapi;
 use tardis::web::poem_openapi::param::Path;
@@ -8,7 +9,7 @@ use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
 use crate::basic::dto::iam_cert_conf_dto::{
     IamCertConfLdapAddOrModifyReq, IamCertConfLdapResp, IamCertConfOAuth2AddOrModifyReq, IamCertConfOAuth2Resp, IamCertConfOAuth2ServiceAddOrModifyReq,
-    IamCertConfOAuth2ServiceResp,
+    IamCertConfOAuth2ServiceResp, IamCertConfOidcAddOrModifyReq, IamCertConfOidcResp,
 };
 use crate::basic::serv::iam_account_serv::IamAccountServ;
 use bios_basic::rbum::dto::rbum_cert_dto::{RbumCertSummaryResp, RbumCertSummaryWithSkResp};
@@ -19,6 +20,7 @@ use crate::basic::dto::iam_cert_dto::{IamCertUserPwdRestReq, IamThirdIntegration
 use crate::basic::serv::iam_cert_ldap_serv::IamCertLdapServ;
 use crate::basic::serv::iam_cert_oauth2_serv::IamCertOAuth2Serv;
 use crate::basic::serv::iam_cert_oauth2_service_serv::IamCertOAuth2ServiceServ;
+use crate::basic::serv::iam_cert_oidc_serv::IamCertOidcServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
 use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
 use crate::iam_constants;
@@ -382,3 +384,78 @@ impl IamCsCertConfigOAuth2Api {
         TardisResp::ok(Void {})
     }
 }
+
+#[derive(Clone, Default)]
+pub struct IamCsCertConfigOidcApi;
+
+/// System Console Cert Config OpenID Connect API
+/// 系统控制台证书配置 OpenID Connect API
+///
+/// 通过 issuer 地址接入任意 OpenID Connect 身份提供方（如 Keycloak、Azure AD、Okta），supplier 为提供方编码。
+#[poem_openapi::OpenApi(prefix_path = "/cs/oidc", tag = "bios_basic::ApiTag::System")]
+impl IamCsCertConfigOidcApi {
+    /// Add or Enable OpenID Connect Provider Cert Conf
+    /// 添加或启用 OpenID Connect 身份提供方配置
+    #[oai(path = "/:supplier", method = "post")]
+    async fn add_oidc_cert(&self, supplier: Path<String>, add_req: Json<IamCertConfOidcAddOrModifyReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<String> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let mut add_req = add_req.0;
+        add_req.supplier = TrimString(supplier.0);
+        let resp = IamCertOidcServ::add_or_enable_cert_conf(&add_req, &ctx.0.own_paths, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Get OpenID Connect Provider Cert Conf
+    /// 获取 OpenID Connect 身份提供方配置
+    #[oai(path = "/:supplier", method = "get")]
+    async fn get_oidc_cert(&self, supplier: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<IamCertConfOidcResp> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let cert_conf_id = IamCertServ::get_cert_conf_id_by_kind_supplier(&IamCertExtKind::Oidc.to_string(), &supplier.0, Some(ctx.0.own_paths.clone()), &funs).await?;
+        let resp = IamCertOidcServ::get_cert_conf(&cert_conf_id, &funs, &ctx.0).await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Modify OpenID Connect Provider Cert Conf
+    /// 修改 OpenID Connect 身份提供方配置
+    ///
+    /// 未传 client_secret 时保留原值。
+    #[oai(path = "/:supplier", method = "put")]
+    async fn modify_oidc_cert(
+        &self,
+        supplier: Path<String>,
+        modify_req: Json<IamCertConfOidcAddOrModifyReq>,
+        ctx: TardisContextExtractor,
+        request: &Request,
+    ) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let cert_conf_id = IamCertServ::get_cert_conf_id_by_kind_supplier(&IamCertExtKind::Oidc.to_string(), &supplier.0, Some(ctx.0.own_paths.clone()), &funs).await?;
+        let mut modify_req = modify_req.0;
+        modify_req.supplier = TrimString(supplier.0);
+        IamCertOidcServ::modify_cert_conf(&cert_conf_id, &modify_req, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Disable OpenID Connect Provider Cert Conf
+    /// 禁用 OpenID Connect 身份提供方配置
+    #[oai(path = "/:supplier", method = "delete")]
+    async fn disable_oidc_cert(&self, supplier: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let cert_conf_id = IamCertServ::get_cert_conf_id_by_kind_supplier(&IamCertExtKind::Oidc.to_string(), &supplier.0, Some(ctx.0.own_paths.clone()), &funs).await?;
+        IamCertServ::disable_cert_conf(&cert_conf_id, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+}
@@ -0,0 +1,57 @@
+use bios_basic::helper::request_helper::try_set_real_ip_from_req_to_ctx;
+use tardis::web::context_extractor::TardisContextExtractor;
+use tardis::web::poem::Request;
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::{param::Path, payload::Json};
+use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
+
+use crate::basic::dto::iam_scim_dto::{IamScimTokenAddReq, IamScimTokenAddResp, IamScimTokenResp};
+use crate::basic::serv::iam_scim_serv::IamScimServ;
+use crate::iam_constants;
+
+#[derive(Clone, Default)]
+pub struct IamCtScimApi;
+
+/// Tenant Console SCIM API
+/// 租户控制台 SCIM 令牌管理API
+///
+/// The tokens authenticate the identity provider when it calls the SCIM 2.0 endpoint of the tenant.
+#[poem_openapi::OpenApi(prefix_path = "/ct/scim", tag = "bios_basic::ApiTag::Tenant")]
+impl IamCtScimApi {
+    /// Add SCIM Token
+    /// 添加 SCIM 令牌，令牌明文只返回一次
+    #[oai(path = "/token", method = "post")]
+    async fn add_token(&self, add_req: Json<IamScimTokenAddReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<IamScimTokenAddResp> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let result = IamScimServ::add_token(&add_req.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
+    /// Find SCIM Tokens
+    /// 获取 SCIM 令牌列表
+    #[oai(path = "/token", method = "get")]
+    async fn find_tokens(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Vec<IamScimTokenResp>> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let result = IamScimServ::find_tokens(&funs, &ctx.0).await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
+    /// Delete SCIM Token
+    /// 删除 SCIM 令牌
+    #[oai(path = "/token/:id", method = "delete")]
+    async fn delete_token(&self, id: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        IamScimServ::delete_token(&id.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+}
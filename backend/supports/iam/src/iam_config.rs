@@ -50,6 +50,24 @@ pub struct IamConfig {
     pub cache_key_role_info_: String,
     pub cache_key_double_auth_info: String,
     pub cache_key_double_auth_expire_sec: usize,
+    // account_id -> totp failed times
+    pub cache_key_totp_err_times_: String,
+    /// TOTP is locked for `totp_lock_sec` after so many failed codes
+    pub totp_lock_err_times: u32,
+    pub totp_lock_sec: u64,
+    // state_id -> webauthn ceremony state
+    pub cache_key_webauthn_state_: String,
+    pub webauthn_state_expire_sec: u64,
+    // state -> oidc authorization request
+    pub cache_key_oidc_state_: String,
+    pub oidc_state_expire_sec: u64,
+    // issuer -> oidc provider metadata and keys
+    pub cache_key_oidc_provider_: String,
+    pub oidc_provider_cache_sec: u64,
+    // issuer -> the keys were fetched again because of an unknown key id
+    pub cache_key_oidc_jwks_refreshed_: String,
+    /// The keys of a provider are fetched again for an unknown key id at most once in so many seconds
+    pub oidc_jwks_refresh_cooldown_sec: u64,
     //  -> [res_uri##action, {st,et,accounts,roles,groups,apps,tenants}]
     pub cache_key_res_info: String,
     // time_stamp -> res_uri##action
@@ -117,6 +135,18 @@ pub struct IamConfig {
     /// 必须不小于本地登录 token 的有效期，否则会出现「登录 token 未失效但 Provider token 缓存已过期」的问题；
     /// 实际生效值会与登录 token 默认时长（`RBUM_CERT_CONF_TOKEN_EXPIRE_SEC`）取较大者。
     pub oauth2_provider_token_cache_expire_sec: u32,
+    /// 签发 id_token 的 RSA 私钥（PEM），为空时自动生成并加密保存在数据库中供各节点共用
+    pub oauth2_signing_private_key: String,
+    /// 加密保存在数据库中的签名私钥所用的 SM4 密钥（16 个字符），未配置 `oauth2_signing_private_key` 时必须配置
+    pub oauth2_signing_key_secret: String,
+    pub oauth2_id_token_expire_sec: u32,
+    pub oauth2_device_code_expire_sec: u32,
+    /// 设备轮询令牌端点的最小间隔（秒）
+    pub oauth2_device_code_interval_sec: u32,
+    /// 设备码流程中用户输入 user_code 的页面，为空时使用 `{iam_base_url}/device`
+    pub oauth2_device_verification_uri: String,
+    /// 同意授权页面，浏览器访问授权端点而账号尚未同意时跳转至此，为空时拒绝尚未同意的授权请求
+    pub oauth2_consent_uri: String,
 
     // open-api 插件配置
     pub openapi_plugin_time_range: String,
@@ -240,6 +270,17 @@ impl Default for IamConfig {
             // ..:<account_id>
             cache_key_double_auth_info: "iam:cache:double_auth:info:".to_string(),
             cache_key_double_auth_expire_sec: 300,
+            cache_key_totp_err_times_: "iam:cache:totp:err_times:".to_string(),
+            totp_lock_err_times: 5,
+            totp_lock_sec: 300,
+            cache_key_webauthn_state_: "iam:cache:webauthn:state:".to_string(),
+            webauthn_state_expire_sec: 300,
+            cache_key_oidc_state_: "iam:cache:oidc:state:".to_string(),
+            oidc_state_expire_sec: 600,
+            cache_key_oidc_provider_: "iam:cache:oidc:provider:".to_string(),
+            oidc_provider_cache_sec: 3600,
+            cache_key_oidc_jwks_refreshed_: "iam:cache:oidc:jwks_refreshed:".to_string(),
+            oidc_jwks_refresh_cooldown_sec: 60,
             cache_key_res_info: "iam:res:info".to_string(),
             cache_key_res_changed_info_: "iam:res:changed:info:".to_string(),
             cache_key_res_changed_expire_sec: 300,
@@ -286,6 +327,13 @@ impl Default for IamConfig {
             oauth2_require_pkce: false,
             oauth2_allow_implicit_flow: false,
             oauth2_provider_token_cache_expire_sec: 30 * 24 * 3600, // 30天
+            oauth2_signing_private_key: "".to_string(),
+            oauth2_signing_key_secret: "".to_string(),
+            oauth2_id_token_expire_sec: 3600,
+            oauth2_device_code_expire_sec: 600,
+            oauth2_device_code_interval_sec: 5,
+            oauth2_device_verification_uri: "".to_string(),
+            oauth2_consent_uri: "".to_string(),
 
             // open-api 插件配置
             openapi_plugin_time_range: "redis-time-range:opres-time-range".to_string(),
@@ -50,6 +50,8 @@ pub enum IamCertKernelKind {
     MailVCode,
     PhoneVCode,
     AkSk,
+    Totp,
+    WebAuthn,
 }
 
 #[derive(Display, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum, strum::EnumString)]
@@ -59,6 +61,10 @@ pub enum IamCertExtKind {
     OAuth2,
     /// 和OAuth2的区别是，这是OAuth2服务提供商的类型
     OAuth2Service,
+    /// 接入通用 OpenID Connect 身份提供方的类型，supplier 为提供方编码
+    Oidc,
+    /// SCIM 2.0 供应接口的租户令牌，ak 为令牌的摘要
+    Scim,
     /// No configuration exists,can't login in ,\
     /// supplier can be "gitlab/cmbd-pwd/cmbd-ssh"
     ThirdParty,
@@ -213,6 +219,9 @@ pub enum Oauth2GrantType {
     #[strum(serialize = "refresh_token")]
     #[oai(rename = "refresh_token")]
     RefreshToken,
+    #[strum(serialize = "urn:ietf:params:oauth:grant-type:device_code")]
+    #[oai(rename = "urn:ietf:params:oauth:grant-type:device_code")]
+    DeviceCode,
 }
 
 impl Oauth2GrantType {
@@ -36,8 +36,8 @@ use crate::console_common::api::{
     iam_cc_role_api, iam_cc_sub_deploy_api, iam_cc_system_api, iam_cc_tenant_api, iam_cc_publish_system_api, iam_cc_third_party_app_api,
 };
 use crate::console_interface::api::{
-    iam_ci_account_api, iam_ci_app_api, iam_ci_app_set_api, iam_ci_cert_api, iam_ci_open_api, iam_ci_org_api, iam_ci_res_api, iam_ci_role_api, iam_ci_sub_deploy_api,
-    iam_ci_system_api,
+    iam_ci_account_api, iam_ci_app_api, iam_ci_app_set_api, iam_ci_cert_api, iam_ci_open_api, iam_ci_org_api, iam_ci_res_api, iam_ci_role_api, iam_ci_scim_api,
+    iam_ci_sub_deploy_api, iam_ci_system_api,
 };
 use crate::console_passport::api::{iam_cp_account_api, iam_cp_app_api, iam_cp_cert_api, iam_cp_oauth2_service_api, iam_cp_tenant_api};
 use crate::console_system::api::{
@@ -46,7 +46,7 @@ use crate::console_system::api::{
 };
 use crate::console_tenant::api::{
     iam_ct_account_api, iam_ct_account_attr_api, iam_ct_app_api, iam_ct_app_set_api, iam_ct_cert_api, iam_ct_cert_manage_api, iam_ct_org_api, iam_ct_res_api, iam_ct_role_api,
-    iam_ct_sub_deploy_api, iam_ct_tenant_api, iam_ct_third_party_app_api,
+    iam_ct_scim_api, iam_ct_sub_deploy_api, iam_ct_tenant_api, iam_ct_third_party_app_api,
 };
 use crate::iam_config::{BasicInfo, IamBasicInfoManager, IamConfig};
 use crate::iam_constants::RBUM_SCOPE_LEVEL_GLOBAL;
@@ -90,6 +90,8 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
                     #[cfg(feature = "ldap_client")]
                     iam_cp_cert_api::IamCpCertLdapApi,
                     iam_cp_oauth2_service_api::IamCpOAuth2ServiceApi,
+                    iam_cp_oauth2_service_api::IamCpOidcProviderApi,
+                    iam_cp_oauth2_service_api::IamCpOidcDiscoveryApi,
                     iam_cp_tenant_api::IamCpTenantApi,
                 ),
                 (
@@ -101,6 +103,7 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
                         iam_cs_cert_api::IamCsCertConfigLdapApi,
                         iam_cs_cert_api::IamCsCertConfigOAuth2Api,
                         iam_cs_cert_api::IamCsCertConfigOAuth2ServiceApi,
+                        iam_cs_cert_api::IamCsCertConfigOidcApi,
                     ),
                     iam_cs_platform_api::IamCsPlatformApi,
                     iam_cs_app_set_api::IamCsAppSetApi,
@@ -128,6 +131,7 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
                     iam_ct_sub_deploy_api::IamCtSubDeployHostApi,
                     iam_ct_sub_deploy_api::IamCtSubDeployLicenseApi,
                     iam_ct_third_party_app_api::IamCtThirdPartyAppApi,
+                    iam_ct_scim_api::IamCtScimApi,
                 ),
                 (
                     iam_ca_account_api::IamCaAccountApi,
@@ -149,6 +153,7 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
                     iam_ci_system_api::IamCiSystemApi,
                     iam_ci_open_api::IamCiOpenApi,
                     iam_ci_org_api::IamCiOrgApi,
+                    iam_ci_scim_api::IamCiScimApi,
                 ),
             )), // .middlewares(EncryptMW),
         )
@@ -68,6 +68,7 @@ impl BIOSWebTestClient {
                     sk: TrimString(password.to_string()),
                     tenant_id,
                     flag,
+                    totp_code: None,
                 },
             )
             .await;
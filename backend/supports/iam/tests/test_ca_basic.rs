@@ -75,6 +75,7 @@ pub async fn test(context: &TardisContext) -> TardisResult<(TardisContext, Tardi
             sk: TrimString(tenant_admin_pwd),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -193,6 +194,7 @@ pub async fn test(context: &TardisContext) -> TardisResult<(TardisContext, Tardi
             sk: TrimString(pwd.clone()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -213,6 +215,7 @@ pub async fn test(context: &TardisContext) -> TardisResult<(TardisContext, Tardi
             sk: TrimString(pwd),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -67,7 +67,8 @@ async fn test_single_level(context: &TardisContext, ak: &str, another_context: &
             ak: TrimString(ak.to_string()),
             sk: TrimString("sssssssssss".to_string()),
             tenant_id: rbum_scope_helper::get_path_item(RBUM_SCOPE_LEVEL_TENANT.to_int(), &context.own_paths),
-            flag: None
+            flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -100,7 +101,8 @@ async fn test_single_level(context: &TardisContext, ak: &str, another_context: &
             ak: TrimString(ak.to_string()),
             sk: TrimString("sssssssssss".to_string()),
             tenant_id: rbum_scope_helper::get_path_item(RBUM_SCOPE_LEVEL_TENANT.to_int(), &another_context.own_paths),
-            flag: None
+            flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -113,6 +115,7 @@ async fn test_single_level(context: &TardisContext, ak: &str, another_context: &
             sk: TrimString("sssssssssss".to_string()),
             tenant_id: rbum_scope_helper::get_path_item(RBUM_SCOPE_LEVEL_TENANT.to_int(), &context.own_paths),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -160,6 +163,7 @@ async fn test_single_level(context: &TardisContext, ak: &str, another_context: &
             sk: TrimString("123456789".to_string()),
             tenant_id: rbum_scope_helper::get_path_item(RBUM_SCOPE_LEVEL_TENANT.to_int(), &context.own_paths),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -0,0 +1,303 @@
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use bios_iam::basic::dto::iam_role_dto::IamRoleAddReq;
+use bios_iam::basic::dto::iam_scim_dto::{
+    IamScimBulkOperation, IamScimBulkReq, IamScimGroup, IamScimMultiValued, IamScimPatchOperation, IamScimPatchReq, IamScimTokenAddReq, IamScimUser,
+};
+use bios_iam::basic::serv::iam_role_serv::IamRoleServ;
+use bios_iam::basic::serv::iam_scim_serv::IamScimServ;
+use bios_iam::iam_config::IamBasicConfigApi;
+use bios_iam::iam_constants;
+use bios_iam::iam_enumeration::IamRoleKind;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::serde_json::json;
+
+fn role_value(role_id: &str) -> IamScimMultiValued {
+    IamScimMultiValued {
+        value: role_id.to_string(),
+        ..Default::default()
+    }
+}
+
+fn role_ids(user: &IamScimUser) -> Vec<String> {
+    user.roles.iter().flatten().map(|role| role.value.clone()).collect()
+}
+
+pub async fn test(tenant1_admin_context: &TardisContext, tenant2_admin_context: &TardisContext) -> TardisResult<()> {
+    // 批量操作在各自的事务中执行，这里不开启外层事务
+    let mut funs = iam_constants::get_tardis_inst();
+
+    info!("【test_ci_scim】 : Add Token");
+    let token1 = IamScimServ::add_token(
+        &IamScimTokenAddReq {
+            name: TrimString("idp1".to_string()),
+            expire_sec: None,
+        },
+        &funs,
+        tenant1_admin_context,
+    )
+    .await?;
+    let token2 = IamScimServ::add_token(
+        &IamScimTokenAddReq {
+            name: TrimString("idp2".to_string()),
+            expire_sec: None,
+        },
+        &funs,
+        tenant2_admin_context,
+    )
+    .await?;
+    assert_eq!(IamScimServ::find_tokens(&funs, tenant1_admin_context).await?.len(), 1);
+
+    info!("【test_ci_scim】 : Token Scoping");
+    assert!(IamScimServ::auth(None, &funs).await.is_err());
+    assert!(IamScimServ::auth(Some("Bearer xxx"), &funs).await.is_err());
+    assert!(IamScimServ::auth(Some(&token1.token), &funs).await.is_err());
+    let ctx1 = IamScimServ::auth(Some(&format!("Bearer {}", token1.token)), &funs).await?;
+    let ctx2 = IamScimServ::auth(Some(&format!("Bearer {}", token2.token)), &funs).await?;
+    assert_eq!(ctx1.own_paths, tenant1_admin_context.own_paths);
+    assert_eq!(ctx2.own_paths, tenant2_admin_context.own_paths);
+
+    let role_id = IamRoleServ::add_item(
+        &mut IamRoleAddReq {
+            id: None,
+            code: Some(TrimString("scim_role1".to_string())),
+            name: TrimString("SCIM角色1".to_string()),
+            icon: None,
+            scope_level: None,
+            disabled: None,
+            sort: None,
+            kind: Some(IamRoleKind::Tenant),
+            in_embed: None,
+            extend_role_id: None,
+            in_base: None,
+            deletable: None,
+        },
+        &funs,
+        tenant1_admin_context,
+    )
+    .await?;
+    let tenant2_role_id = IamRoleServ::add_item(
+        &mut IamRoleAddReq {
+            id: None,
+            code: Some(TrimString("scim_role2".to_string())),
+            name: TrimString("SCIM角色2".to_string()),
+            icon: None,
+            scope_level: None,
+            disabled: None,
+            sort: None,
+            kind: Some(IamRoleKind::Tenant),
+            in_embed: None,
+            extend_role_id: None,
+            in_base: None,
+            deletable: None,
+        },
+        &funs,
+        tenant2_admin_context,
+    )
+    .await?;
+
+    info!("【test_ci_scim】 : Add User");
+    let mut user = IamScimUser {
+        user_name: Some("scim_user1".to_string()),
+        display_name: Some("SCIM用户1".to_string()),
+        external_id: Some("ext_user1".to_string()),
+        active: Some(true),
+        ..Default::default()
+    };
+    // 不能分配管理员角色及其他租户的角色
+    user.roles = Some(vec![role_value(&funs.iam_basic_role_tenant_admin_id())]);
+    assert!(IamScimServ::add_user(&user, &funs, &ctx1).await.is_err());
+    user.roles = Some(vec![role_value(&tenant2_role_id)]);
+    assert!(IamScimServ::add_user(&user, &funs, &ctx1).await.is_err());
+    user.roles = Some(vec![role_value(&role_id)]);
+    let user = IamScimServ::add_user(&user, &funs, &ctx1).await?;
+    let user_id = user.id.clone().unwrap();
+    assert_eq!(user.user_name, Some("scim_user1".to_string()));
+    assert_eq!(user.external_id, Some("ext_user1".to_string()));
+    assert_eq!(user.active, Some(true));
+    assert_eq!(role_ids(&user), vec![role_id.clone()]);
+
+    info!("【test_ci_scim】 : Get User");
+    let user = IamScimServ::get_user(&user_id, &funs, &ctx1).await?;
+    assert_eq!(user.display_name, Some("SCIM用户1".to_string()));
+    assert!(IamScimServ::get_user(&user_id, &funs, &ctx2).await.is_err());
+    let users = IamScimServ::find_users(Some(r#"userName eq "scim_user1""#), None, None, &funs, &ctx1).await?;
+    assert_eq!(users.total_results, 1);
+    let users = IamScimServ::find_users(Some(r#"userName eq "scim_user1""#), None, None, &funs, &ctx2).await?;
+    assert_eq!(users.total_results, 0);
+
+    info!("【test_ci_scim】 : Modify User Keeps Roles Not Managed By SCIM");
+    IamRoleServ::add_rel_account(&funs.iam_basic_role_tenant_admin_id(), &user_id, None, &funs, tenant1_admin_context).await?;
+    let admin_role_ids = role_ids(&IamScimServ::get_user(&user_id, &funs, &ctx1).await?).into_iter().filter(|id| *id != role_id).collect::<Vec<_>>();
+    assert_eq!(admin_role_ids.len(), 1);
+    let user = IamScimServ::modify_user(
+        &user_id,
+        &IamScimUser {
+            display_name: Some("SCIM用户1修改".to_string()),
+            roles: Some(vec![]),
+            ..Default::default()
+        },
+        &funs,
+        &ctx1,
+    )
+    .await?;
+    assert_eq!(user.display_name, Some("SCIM用户1修改".to_string()));
+    assert_eq!(role_ids(&user), admin_role_ids);
+
+    info!("【test_ci_scim】 : Patch User");
+    let user = IamScimServ::patch_user(
+        &user_id,
+        &IamScimPatchReq {
+            operations: vec![IamScimPatchOperation {
+                op: "add".to_string(),
+                path: Some("roles".to_string()),
+                value: Some(json!([{ "value": role_id }])),
+            }],
+            ..Default::default()
+        },
+        &funs,
+        &ctx1,
+    )
+    .await?;
+    assert_eq!(role_ids(&user).len(), 2);
+    let user = IamScimServ::patch_user(
+        &user_id,
+        &IamScimPatchReq {
+            operations: vec![
+                IamScimPatchOperation {
+                    op: "remove".to_string(),
+                    path: Some(format!(r#"roles[value eq "{role_id}"]"#)),
+                    value: None,
+                },
+                IamScimPatchOperation {
+                    op: "replace".to_string(),
+                    path: Some("displayName".to_string()),
+                    value: Some(json!("SCIM用户1补丁")),
+                },
+            ],
+            ..Default::default()
+        },
+        &funs,
+        &ctx1,
+    )
+    .await?;
+    assert_eq!(user.display_name, Some("SCIM用户1补丁".to_string()));
+    assert_eq!(role_ids(&user), admin_role_ids);
+
+    info!("【test_ci_scim】 : Deactivate User");
+    assert!(IamScimServ::delete_user(&user_id, &funs, &ctx2).await.is_err());
+    IamScimServ::delete_user(&user_id, &funs, &ctx1).await?;
+    let user = IamScimServ::get_user(&user_id, &funs, &ctx1).await?;
+    assert_eq!(user.active, Some(false));
+    let users = IamScimServ::find_users(Some("active eq false"), None, None, &funs, &ctx1).await?;
+    assert!(users.resources.iter().any(|user| user.id.as_deref() == Some(user_id.as_str())));
+
+    info!("【test_ci_scim】 : Add Group");
+    let group = IamScimServ::add_group(
+        &IamScimGroup {
+            display_name: "SCIM组1".to_string(),
+            external_id: Some("ext_group1".to_string()),
+            members: Some(vec![IamScimMultiValued {
+                value: user_id.clone(),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        },
+        &funs,
+        &ctx1,
+    )
+    .await?;
+    let group_id = group.id.clone().unwrap();
+    assert_eq!(group.members.as_ref().unwrap().len(), 1);
+    assert!(IamScimServ::get_group(&group_id, &funs, &ctx2).await.is_err());
+    let user = IamScimServ::get_user(&user_id, &funs, &ctx1).await?;
+    assert_eq!(user.groups.unwrap()[0].value, group_id);
+
+    info!("【test_ci_scim】 : Patch Group");
+    let group = IamScimServ::patch_group(
+        &group_id,
+        &IamScimPatchReq {
+            operations: vec![
+                IamScimPatchOperation {
+                    op: "replace".to_string(),
+                    path: Some("displayName".to_string()),
+                    value: Some(json!("SCIM组1修改")),
+                },
+                IamScimPatchOperation {
+                    op: "remove".to_string(),
+                    path: Some(format!(r#"members[value eq "{user_id}"]"#)),
+                    value: None,
+                },
+            ],
+            ..Default::default()
+        },
+        &funs,
+        &ctx1,
+    )
+    .await?;
+    assert_eq!(group.display_name, "SCIM组1修改");
+    assert!(group.members.unwrap().is_empty());
+    let groups = IamScimServ::find_groups(Some(r#"displayName eq "SCIM组1修改""#), None, None, false, &funs, &ctx1).await?;
+    assert_eq!(groups.total_results, 1);
+    let groups = IamScimServ::find_groups(Some(r#"displayName eq "SCIM组1修改""#), None, None, false, &funs, &ctx2).await?;
+    assert_eq!(groups.total_results, 0);
+
+    info!("【test_ci_scim】 : Delete Group");
+    IamScimServ::delete_group(&group_id, &funs, &ctx1).await?;
+    assert!(IamScimServ::get_group(&group_id, &funs, &ctx1).await.is_err());
+
+    info!("【test_ci_scim】 : Bulk");
+    let bulk_resp = IamScimServ::bulk(
+        &IamScimBulkReq {
+            operations: vec![
+                IamScimBulkOperation {
+                    method: "POST".to_string(),
+                    bulk_id: Some("u2".to_string()),
+                    path: "/Users".to_string(),
+                    data: Some(json!({ "userName": "scim_user2", "roles": [{ "value": role_id }] })),
+                },
+                IamScimBulkOperation {
+                    method: "POST".to_string(),
+                    bulk_id: Some("g2".to_string()),
+                    path: "/Groups".to_string(),
+                    data: Some(json!({ "displayName": "SCIM组2", "members": [{ "value": "bulkId:u2" }] })),
+                },
+                IamScimBulkOperation {
+                    method: "POST".to_string(),
+                    bulk_id: Some("u3".to_string()),
+                    path: "/Users".to_string(),
+                    data: Some(json!({ "userName": "scim_user3", "roles": [{ "value": funs.iam_basic_role_tenant_admin_id() }] })),
+                },
+                IamScimBulkOperation {
+                    method: "DELETE".to_string(),
+                    bulk_id: None,
+                    path: "/Groups/bulkId:g2".to_string(),
+                    data: None,
+                },
+            ],
+            ..Default::default()
+        },
+        &mut funs,
+        &ctx1,
+    )
+    .await?;
+    let statuses = bulk_resp.operations.iter().map(|operation| operation.status.as_str()).collect::<Vec<_>>();
+    assert_eq!(statuses, vec!["201", "201", "400", "204"]);
+    let user2_id = bulk_resp.operations[0].location.as_ref().unwrap().rsplit('/').next().unwrap().to_string();
+    let user2 = IamScimServ::get_user(&user2_id, &funs, &ctx1).await?;
+    assert_eq!(role_ids(&user2), vec![role_id.clone()]);
+    assert!(user2.groups.unwrap().is_empty());
+    assert_eq!(
+        IamScimServ::find_users(Some(r#"userName eq "scim_user3""#), None, None, &funs, &ctx1).await?.total_results,
+        0
+    );
+
+    info!("【test_ci_scim】 : Delete Token");
+    IamScimServ::delete_token(&token2.id, &funs, tenant2_admin_context).await?;
+    assert!(IamScimServ::auth(Some(&format!("Bearer {}", token2.token)), &funs).await.is_err());
+    assert!(IamScimServ::auth(Some(&format!("Bearer {}", token1.token)), &funs).await.is_ok());
+
+    Ok(())
+}
@@ -9,13 +9,14 @@ use tardis::tokio::time::sleep;
 use bios_basic::rbum::serv::rbum_cert_serv::RbumCertServ;
 use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
 use bios_iam::basic::dto::iam_account_dto::IamAccountSelfModifyReq;
-use bios_iam::basic::dto::iam_cert_conf_dto::IamCertConfUserPwdAddOrModifyReq;
+use bios_iam::basic::dto::iam_cert_conf_dto::{IamCertConfTotpAddOrModifyReq, IamCertConfUserPwdAddOrModifyReq};
 use bios_iam::basic::dto::iam_cert_dto::{IamCertMailVCodeAddReq, IamCertUserNameNewReq, IamCertUserPwdModifyReq, IamContextFetchReq};
 use bios_iam::basic::dto::iam_filer_dto::IamAccountFilterReq;
 use bios_iam::basic::dto::iam_tenant_dto::{IamTenantAggAddReq, IamTenantConfigReq};
 use bios_iam::basic::serv::iam_account_serv::IamAccountServ;
 use bios_iam::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
 use bios_iam::basic::serv::iam_cert_serv::IamCertServ;
+use bios_iam::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
 use bios_iam::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
 use bios_iam::basic::serv::iam_tenant_serv::IamTenantServ;
 use bios_iam::console_passport::dto::iam_cp_cert_dto::{IamCpMailVCodeLoginReq, IamCpUserPwdLoginReq};
@@ -98,6 +99,9 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             cert_conf_by_mail_vcode: Some(true),
             cert_conf_by_oauth2: None,
             cert_conf_by_ldap: None,
+            cert_conf_by_totp: None,
+            cert_conf_by_webauthn: None,
+            cert_conf_by_oidc: None,
             config: None,
             token_default_coexist_num: None,
         },
@@ -112,7 +116,8 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             ak: TrimString("bios".to_string()),
             sk: TrimString("123456".to_string()),
             tenant_id: None,
-            flag: None
+            flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -126,7 +131,8 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             ak: TrimString("bios".to_string()),
             sk: TrimString(sysadmin_info.1.to_string()),
             tenant_id: Some(tenant_id.clone()),
-            flag: None
+            flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -140,7 +146,8 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             ak: TrimString("bios".to_string()),
             sk: TrimString(tenant_admin_pwd.to_string()),
             tenant_id: None,
-            flag: None
+            flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -155,6 +162,7 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             sk: TrimString(tenant_admin_pwd.to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -180,7 +188,8 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             ak: TrimString("bios".to_string()),
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
-            flag: None
+            flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -192,7 +201,8 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             ak: TrimString("bios".to_string()),
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
-            flag: None
+            flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -206,6 +216,7 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             sk: TrimString(tenant_admin_pwd.to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -221,6 +232,7 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             sk: TrimString(tenant_admin_pwd.to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -238,6 +250,7 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             sk: TrimString(sysadmin_info.1.to_string()),
             tenant_id: None,
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -289,7 +302,8 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             ak: TrimString("bios".to_string()),
             sk: TrimString(sysadmin_info.1.to_string()),
             tenant_id: None,
-            flag: None
+            flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -303,6 +317,7 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             sk: TrimString("123456".to_string()),
             tenant_id: None,
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -333,6 +348,7 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             sk: TrimString(tenant_admin_pwd.clone()),
             tenant_id: Some(tenant_admin_context.own_paths.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -357,6 +373,7 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             sk: TrimString("123456".to_string()),
             tenant_id: None,
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -413,6 +430,7 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             sk: TrimString(tenant_admin_pwd.to_string()),
             tenant_id: Some(tenant_admin_context.own_paths.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -457,6 +475,35 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
         &funs,
     )
     .await?;
+
+    info!("【test_cp_all】 : Login by Mail And Vcode is rejected when the tenant requires TOTP");
+    let mut totp_cert_conf = IamCertConfTotpAddOrModifyReq {
+        issuer: "bios".to_string(),
+        digits: 6,
+        period: 30,
+        skew: 1,
+        login_required: true,
+    };
+    let totp_cert_conf_id = IamCertTotpServ::add_cert_conf(&totp_cert_conf, Some(tenant_admin_context.own_paths.clone()), &funs, &tenant_admin_context).await?;
+    IamCertMailVCodeServ::send_login_mail("i@sunisle.org", &tenant_admin_context.own_paths, None, &funs).await?;
+    let vcode = RbumCertServ::get_vcode_in_cache("i@sunisle.org", &tenant_admin_context.own_paths, &funs).await?;
+    assert!(vcode.is_some());
+    sleep(Duration::from_secs(1)).await;
+    let login_result = IamCpCertMailVCodeServ::login_by_mail_vocde(
+        &IamCpMailVCodeLoginReq {
+            mail: "i@sunisle.org".to_string(),
+            vcode: TrimString(vcode.unwrap()),
+            tenant_id: Some(tenant_admin_context.own_paths.clone()),
+            flag: None,
+        },
+        None,
+        &funs,
+    )
+    .await;
+    assert_eq!(login_result.err().map(|e| e.code), Some("401-iam-cert-totp-enroll-required".to_string()));
+    totp_cert_conf.login_required = false;
+    IamCertTotpServ::modify_cert_conf(&totp_cert_conf_id, &totp_cert_conf, &funs, &tenant_admin_context).await?;
+
     info!("【test_cp_all】 : Delete Mail-VCode Cert");
     IamCertServ::delete_cert(&mail_vcode_cert_id, &funs, &tenant_admin_context).await?;
     // ------------------ Mail-VCode Cert Test End ------------------
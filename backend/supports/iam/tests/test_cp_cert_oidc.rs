@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bios_basic::rbum::helper::rbum_scope_helper;
+use bios_iam::basic::dto::iam_cert_conf_dto::{IamCertConfOidcAddOrModifyReq, IamCertOidcClaimMap};
+use bios_iam::basic::dto::iam_cert_dto::IamCertOidcAuthorizeResp;
+use bios_iam::basic::serv::iam_cert_oidc_serv::IamCertOidcServ;
+use bios_iam::console_passport::dto::iam_cp_cert_dto::IamCpOidcLoginReq;
+use bios_iam::console_passport::serv::iam_cp_cert_oidc_serv::IamCpCertOidcServ;
+use bios_iam::iam_constants;
+use bios_iam::iam_constants::RBUM_SCOPE_LEVEL_TENANT;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::serde_json::{json, Value};
+use tardis::tokio::time::sleep;
+use tardis::web::poem::endpoint::make;
+use tardis::web::poem::http::StatusCode;
+use tardis::web::poem::listener::TcpListener;
+use tardis::web::poem::web::Json;
+use tardis::web::poem::{IntoResponse, Request, Route, Server};
+use tardis::web::reqwest::Url;
+use tardis::{tokio, TardisFuns};
+
+const ISSUER: &str = "http://127.0.0.1:18090";
+const CLIENT_ID: &str = "bios";
+const SECRET: &[u8] = b"oidc-test-secret-of-at-least-32-bytes";
+
+/// The codes issued by the mock provider, with the PKCE challenge of the authorization request and the claims of the id token
+type IssuedCodes = Arc<Mutex<HashMap<String, (String, Value)>>>;
+
+pub async fn test(context: &TardisContext) -> TardisResult<()> {
+    let codes = start_mock_provider().await;
+    let mut funs = iam_constants::get_tardis_inst();
+    funs.begin().await?;
+    let tenant_id = rbum_scope_helper::get_path_item(RBUM_SCOPE_LEVEL_TENANT.to_int(), &context.own_paths);
+
+    info!("【test_cp_cert_oidc】 : Add OIDC Cert Conf");
+    let mut cert_conf = IamCertConfOidcAddOrModifyReq {
+        supplier: TrimString("idp".to_string()),
+        name: "IdP".to_string(),
+        issuer: ISSUER.to_string(),
+        client_id: TrimString(CLIENT_ID.to_string()),
+        client_secret: Some(TrimString("secret".to_string())),
+        redirect_uri: "https://bios.example.com/oidc/callback".to_string(),
+        scope: None,
+        claim_map: IamCertOidcClaimMap {
+            user_name: Some("preferred_username".to_string()),
+            ..Default::default()
+        },
+        auto_create_account: false,
+    };
+    let cert_conf_id = IamCertOidcServ::add_cert_conf(&cert_conf, tenant_id.clone(), &funs, context).await?;
+    assert_eq!(IamCertOidcServ::get_cert_conf(&cert_conf_id, &funs, context).await?.issuer, ISSUER);
+
+    info!("【test_cp_cert_oidc】 : Login by an unbound identity");
+    let authorize_resp = IamCpCertOidcServ::authorize_login("idp", tenant_id.clone(), &funs).await?;
+    let login_req = issue_code(&codes, &authorize_resp, "user1", None);
+    let login_result = IamCpCertOidcServ::login(&login_req, None, &funs).await;
+    assert_eq!(login_result.err().map(|e| e.code), Some("401-iam-cert-oidc-account-not-bound".to_string()));
+    // a state can only be used once
+    let login_result = IamCpCertOidcServ::login(&login_req, None, &funs).await;
+    assert_eq!(login_result.err().map(|e| e.code), Some("401-iam-cert-oidc-state-invalid".to_string()));
+
+    info!("【test_cp_cert_oidc】 : Bind the identity to the current account");
+    let authorize_resp = IamCpCertOidcServ::authorize_login("idp", tenant_id.clone(), &funs).await?;
+    let bind_result = IamCpCertOidcServ::bind(&issue_code(&codes, &authorize_resp, "user1", None), &funs, context).await;
+    assert_eq!(bind_result.err().map(|e| e.code), Some("401-iam-cert-oidc-state-invalid".to_string()));
+    let authorize_resp = IamCpCertOidcServ::authorize_bind("idp", &funs, context).await?;
+    assert_eq!(IamCpCertOidcServ::bind(&issue_code(&codes, &authorize_resp, "user1", None), &funs, context).await?, "user1");
+
+    info!("【test_cp_cert_oidc】 : Login by the bound identity");
+    let authorize_resp = IamCpCertOidcServ::authorize_login("idp", tenant_id.clone(), &funs).await?;
+    let account_info = IamCpCertOidcServ::login(&issue_code(&codes, &authorize_resp, "user1", None), None, &funs).await?;
+    assert_eq!(account_info.account_id, context.owner);
+    assert!(!account_info.token.is_empty());
+
+    info!("【test_cp_cert_oidc】 : The code is bound to the PKCE verifier and the id token to the nonce");
+    let authorize_resp = IamCpCertOidcServ::authorize_login("idp", tenant_id.clone(), &funs).await?;
+    let other_authorize_resp = IamCpCertOidcServ::authorize_login("idp", tenant_id.clone(), &funs).await?;
+    let mut login_req = issue_code(&codes, &other_authorize_resp, "user1", None);
+    login_req.state = authorize_resp.state;
+    let login_result = IamCpCertOidcServ::login(&login_req, None, &funs).await;
+    assert_eq!(login_result.err().map(|e| e.code), Some("401-iam-cert-oidc-token-error".to_string()));
+    let authorize_resp = IamCpCertOidcServ::authorize_login("idp", tenant_id.clone(), &funs).await?;
+    let login_req = issue_code(&codes, &authorize_resp, "user1", Some("other_nonce"));
+    let login_result = IamCpCertOidcServ::login(&login_req, None, &funs).await;
+    assert_eq!(login_result.err().map(|e| e.code), Some("401-iam-cert-oidc-id-token-invalid".to_string()));
+
+    info!("【test_cp_cert_oidc】 : Login by an unknown identity creates the account");
+    cert_conf.auto_create_account = true;
+    cert_conf.client_secret = None;
+    IamCertOidcServ::modify_cert_conf(&cert_conf_id, &cert_conf, &funs, context).await?;
+    let authorize_resp = IamCpCertOidcServ::authorize_login("idp", tenant_id.clone(), &funs).await?;
+    let account_info = IamCpCertOidcServ::login(&issue_code(&codes, &authorize_resp, "user2", None), None, &funs).await?;
+    assert_ne!(account_info.account_id, context.owner);
+    assert_eq!(account_info.account_name, "User user2");
+    let authorize_resp = IamCpCertOidcServ::authorize_login("idp", tenant_id.clone(), &funs).await?;
+    assert_eq!(
+        IamCpCertOidcServ::login(&issue_code(&codes, &authorize_resp, "user2", None), None, &funs).await?.account_id,
+        account_info.account_id
+    );
+    // an identity can only be bound to one account
+    let authorize_resp = IamCpCertOidcServ::authorize_bind("idp", &funs, context).await?;
+    let bind_result = IamCpCertOidcServ::bind(&issue_code(&codes, &authorize_resp, "user2", None), &funs, context).await;
+    assert_eq!(bind_result.err().map(|e| e.code), Some("409-iam-cert-oidc-already-bound".to_string()));
+
+    funs.rollback().await?;
+    Ok(())
+}
+
+/// Act as the browser: follow the authorization url, let the provider issue a code for the user and return to the redirect uri
+fn issue_code(codes: &IssuedCodes, authorize_resp: &IamCertOidcAuthorizeResp, sub: &str, nonce: Option<&str>) -> IamCpOidcLoginReq {
+    let authorize_url = Url::parse(&authorize_resp.authorize_url).unwrap();
+    let params = authorize_url.query_pairs().into_owned().collect::<HashMap<_, _>>();
+    assert_eq!(authorize_url.path(), "/authorize");
+    assert_eq!(params.get("client_id").map(String::as_str), Some(CLIENT_ID));
+    assert_eq!(params.get("state"), Some(&authorize_resp.state));
+    assert_eq!(params.get("code_challenge_method").map(String::as_str), Some("S256"));
+    let claims = json!({
+        "iss": ISSUER,
+        "aud": CLIENT_ID,
+        "sub": sub,
+        "exp": tardis::chrono::Utc::now().timestamp() + 300,
+        "nonce": nonce.unwrap_or(params["nonce"].as_str()),
+        "name": format!("User {sub}"),
+        "preferred_username": sub,
+    });
+    let code = TardisFuns::field.nanoid();
+    codes.lock().unwrap().insert(code.clone(), (params["code_challenge"].clone(), claims));
+    IamCpOidcLoginReq {
+        code: TrimString(code),
+        state: authorize_resp.state.clone(),
+        flag: None,
+    }
+}
+
+/// A provider with the discovery document, the keys and the token endpoint, the codes are issued by [`issue_code`]
+async fn start_mock_provider() -> IssuedCodes {
+    let codes = IssuedCodes::default();
+    let token_codes = codes.clone();
+    let route = Route::new()
+        .at(
+            "/.well-known/openid-configuration",
+            make(|_: Request| async {
+                Json(json!({
+                    "issuer": ISSUER,
+                    "authorization_endpoint": format!("{ISSUER}/authorize"),
+                    "token_endpoint": format!("{ISSUER}/token"),
+                    "jwks_uri": format!("{ISSUER}/jwks"),
+                }))
+            }),
+        )
+        .at(
+            "/jwks",
+            make(|_: Request| async { Json(json!({ "keys": [{ "kty": "oct", "kid": "key1", "alg": "HS256", "k": base64_url_encode(SECRET) }] })) }),
+        )
+        .at(
+            "/token",
+            make(move |req: Request| {
+                let codes = token_codes.clone();
+                async move {
+                    let body = req.into_body().into_string().await.unwrap_or_default();
+                    let form = body.split('&').filter_map(|kv| kv.split_once('=')).collect::<HashMap<_, _>>();
+                    let issued = form.get("code").and_then(|code| codes.lock().unwrap().remove(*code));
+                    match (issued, form.get("code_verifier")) {
+                        (Some((code_challenge, claims)), Some(code_verifier)) if pkce_challenge(code_verifier) == code_challenge => {
+                            let mut header = Header::new(Algorithm::HS256);
+                            header.kid = Some("key1".to_string());
+                            let id_token = jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap();
+                            Json(json!({ "access_token": "access_token", "token_type": "Bearer", "id_token": id_token })).into_response()
+                        }
+                        _ => Json(json!({ "error": "invalid_grant" })).with_status(StatusCode::BAD_REQUEST).into_response(),
+                    }
+                }
+            }),
+        );
+    tokio::spawn(async move {
+        Server::new(TcpListener::bind("127.0.0.1:18090")).run(route).await.unwrap();
+    });
+    sleep(Duration::from_millis(500)).await;
+    codes
+}
+
+/// `BASE64URL(SHA256(code_verifier))` of RFC 7636
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    let digest = TardisFuns::crypto.digest.sha256(code_verifier).unwrap();
+    base64_url_encode((0..digest.len()).step_by(2).map(|i| u8::from_str_radix(&digest[i..i + 2], 16).unwrap()).collect::<Vec<_>>())
+}
+
+fn base64_url_encode(value: impl AsRef<[u8]>) -> String {
+    TardisFuns::crypto.base64.encode(value).trim_end_matches('=').replace('+', "-").replace('/', "_")
+}
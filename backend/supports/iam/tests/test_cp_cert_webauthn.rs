@@ -0,0 +1,161 @@
+use bios_basic::rbum::helper::rbum_scope_helper;
+use bios_iam::basic::dto::iam_cert_conf_dto::{IamCertConfTotpAddOrModifyReq, IamCertConfWebAuthnAddOrModifyReq};
+use bios_iam::basic::dto::iam_cert_dto::{IamCertUserPwdRestReq, IamCertWebAuthnChallengeResp, IamCertWebAuthnFinishReq};
+use bios_iam::basic::serv::iam_cert_serv::IamCertServ;
+use bios_iam::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use bios_iam::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use bios_iam::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
+use bios_iam::console_passport::dto::iam_cp_cert_dto::{IamCpUserPwdLoginReq, IamCpWebAuthnLoginFinishReq, IamCpWebAuthnLoginStartReq};
+use bios_iam::console_passport::serv::iam_cp_cert_user_pwd_serv::IamCpCertUserPwdServ;
+use bios_iam::console_passport::serv::iam_cp_cert_webauthn_serv::IamCpCertWebAuthnServ;
+use bios_iam::iam_constants;
+use bios_iam::iam_constants::{RBUM_ITEM_NAME_SYS_ADMIN_ACCOUNT, RBUM_SCOPE_LEVEL_TENANT};
+use bios_iam::iam_enumeration::IamCertKernelKind;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::TardisFuns;
+use webauthn_authenticator_rs::softpasskey::SoftPasskey;
+use webauthn_authenticator_rs::WebauthnAuthenticator;
+use webauthn_rs::prelude::{CreationChallengeResponse, RequestChallengeResponse, Url};
+
+const RP_ORIGIN: &str = "https://bios.example.com";
+
+pub async fn test(context: &TardisContext) -> TardisResult<()> {
+    let ak = RBUM_ITEM_NAME_SYS_ADMIN_ACCOUNT;
+    let mut funs = iam_constants::get_tardis_inst();
+    funs.begin().await?;
+    let tenant_id = rbum_scope_helper::get_path_item(RBUM_SCOPE_LEVEL_TENANT.to_int(), &context.own_paths);
+    let origin = Url::parse(RP_ORIGIN).unwrap();
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+    let login_req = IamCpUserPwdLoginReq {
+        ak: TrimString(ak.to_string()),
+        sk: TrimString("sssssssssss".to_string()),
+        tenant_id: tenant_id.clone(),
+        flag: None,
+        totp_code: None,
+    };
+    let rbum_cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(IamCertKernelKind::UserPwd.to_string().as_str(), tenant_id.clone(), &funs).await?;
+    IamCertUserPwdServ::reset_sk(
+        &IamCertUserPwdRestReq {
+            new_sk: Some(login_req.sk.clone()),
+        },
+        &context.owner,
+        &rbum_cert_conf_id,
+        &funs,
+        context,
+    )
+    .await?;
+
+    info!("【test_cp_cert_webauthn】 : Add WebAuthn Cert Conf");
+    IamCertWebAuthnServ::add_cert_conf(
+        &IamCertConfWebAuthnAddOrModifyReq {
+            rp_id: "example.com".to_string(),
+            rp_name: "BIOS".to_string(),
+            rp_origin: RP_ORIGIN.to_string(),
+            max_authenticators: 2,
+            primary_enabled: true,
+            second_factor_required: true,
+        },
+        tenant_id.clone(),
+        &funs,
+        context,
+    )
+    .await?;
+
+    info!("【test_cp_cert_webauthn】 : Start Login without authenticator is the same as by an unknown user");
+    let start_req = IamCpWebAuthnLoginStartReq {
+        ak: TrimString(ak.to_string()),
+        tenant_id: tenant_id.clone(),
+    };
+    let unenrolled = IamCpCertWebAuthnServ::start_login(&start_req, &funs).await;
+    assert_eq!(unenrolled.err().map(|e| e.code), Some("401-iam-cert-webauthn-login-unavailable".to_string()));
+    let unknown = IamCpCertWebAuthnServ::start_login(
+        &IamCpWebAuthnLoginStartReq {
+            ak: TrimString("nobody_webauthn".to_string()),
+            tenant_id: tenant_id.clone(),
+        },
+        &funs,
+    )
+    .await;
+    assert_eq!(unknown.err().map(|e| e.code), Some("401-iam-cert-webauthn-login-unavailable".to_string()));
+    // no authenticator yet, the password is enough
+    IamCpCertUserPwdServ::login_by_user_pwd(&login_req, None, &funs).await?;
+
+    info!("【test_cp_cert_webauthn】 : Register Authenticator");
+    let challenge = IamCertWebAuthnServ::start_register("soft", &funs, context).await?;
+    let options = TardisFuns::json.json_to_obj::<CreationChallengeResponse>(challenge.options)?;
+    let credential = authenticator.do_registration(origin.clone(), options).unwrap();
+    IamCertWebAuthnServ::finish_register(
+        &IamCertWebAuthnFinishReq {
+            state_id: challenge.state_id,
+            credential: TardisFuns::json.obj_to_json(&credential)?,
+        },
+        &funs,
+        context,
+    )
+    .await?;
+    let certs = IamCertWebAuthnServ::find_certs(&context.owner, &funs, context).await?;
+    assert_eq!(certs.len(), 1);
+    assert_eq!(certs[0].name, "soft");
+    assert!(certs[0].last_used_time.is_none());
+
+    info!("【test_cp_cert_webauthn】 : Passkey is required after the password");
+    let login_result = IamCpCertUserPwdServ::login_by_user_pwd(&login_req, None, &funs).await;
+    assert_eq!(login_result.err().map(|e| e.code), Some("401-iam-cert-webauthn-required".to_string()));
+    // the logins without password can't carry the assertion either, e.g. verification code, LDAP, OAuth2 and OIDC logins
+    let login_result = IamCertWebAuthnServ::check_login_without_code(&context.owner, tenant_id.clone(), &funs).await;
+    assert_eq!(login_result.err().map(|e| e.code), Some("401-iam-cert-webauthn-required".to_string()));
+
+    info!("【test_cp_cert_webauthn】 : Login by Password and Passkey");
+    let challenge = IamCpCertWebAuthnServ::start_login_by_user_pwd(&login_req, None, &funs).await?;
+    let finish_req = sign_challenge(&mut authenticator, &origin, challenge)?;
+    let account_info = IamCpCertWebAuthnServ::finish_login(&finish_req, None, &funs).await?;
+    assert_eq!(account_info.account_id, context.owner);
+    assert!(!account_info.token.is_empty());
+    assert!(IamCertWebAuthnServ::find_certs(&context.owner, &funs, context).await?[0].last_used_time.is_some());
+    // a ceremony can't be finished twice
+    let login_result = IamCpCertWebAuthnServ::finish_login(&finish_req, None, &funs).await;
+    assert_eq!(login_result.err().map(|e| e.code), Some("401-iam-cert-webauthn-state-invalid".to_string()));
+
+    info!("【test_cp_cert_webauthn】 : Login by Passkey without Password");
+    let challenge = IamCpCertWebAuthnServ::start_login(&start_req, &funs).await?;
+    let finish_req = sign_challenge(&mut authenticator, &origin, challenge)?;
+    let account_info = IamCpCertWebAuthnServ::finish_login(&finish_req, None, &funs).await?;
+    assert_eq!(account_info.account_id, context.owner);
+
+    info!("【test_cp_cert_webauthn】 : Passkey doesn't replace TOTP required by the tenant");
+    IamCertTotpServ::add_cert_conf(
+        &IamCertConfTotpAddOrModifyReq {
+            issuer: "bios".to_string(),
+            digits: 6,
+            period: 30,
+            skew: 1,
+            login_required: true,
+        },
+        tenant_id.clone(),
+        &funs,
+        context,
+    )
+    .await?;
+    let challenge = IamCpCertWebAuthnServ::start_login(&start_req, &funs).await?;
+    let finish_req = sign_challenge(&mut authenticator, &origin, challenge)?;
+    let login_result = IamCpCertWebAuthnServ::finish_login(&finish_req, None, &funs).await;
+    assert_eq!(login_result.err().map(|e| e.code), Some("401-iam-cert-totp-enroll-required".to_string()));
+
+    funs.rollback().await?;
+    Ok(())
+}
+
+/// Sign the challenge by the soft authenticator, like `navigator.credentials.get`
+fn sign_challenge(authenticator: &mut WebauthnAuthenticator<SoftPasskey>, origin: &Url, challenge: IamCertWebAuthnChallengeResp) -> TardisResult<IamCpWebAuthnLoginFinishReq> {
+    let options = TardisFuns::json.json_to_obj::<RequestChallengeResponse>(challenge.options)?;
+    let credential = authenticator.do_authentication(origin.clone(), options).unwrap();
+    Ok(IamCpWebAuthnLoginFinishReq {
+        state_id: challenge.state_id,
+        credential: TardisFuns::json.obj_to_json(&credential)?,
+        flag: None,
+        totp_code: None,
+    })
+}
@@ -69,6 +69,7 @@ pub async fn test(context: &TardisContext) -> TardisResult<(TardisContext, Tardi
             sk: TrimString(tenant_admin_pwd),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -134,6 +135,7 @@ pub async fn test(context: &TardisContext) -> TardisResult<(TardisContext, Tardi
             sk: TrimString(tenant_admin_pwd),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
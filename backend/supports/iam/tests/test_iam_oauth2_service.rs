@@ -0,0 +1,216 @@
+use bios_iam::basic::dto::iam_cert_conf_dto::IamCertConfOAuth2ServiceAddOrModifyReq;
+use bios_iam::basic::dto::iam_cert_dto::{
+    IamCertOAuth2ServiceCodeAddReq, IamCertOAuth2ServiceCodeVerifyReq, IamCertOAuth2ServiceRefreshTokenReq, IamOauth2DeviceAuthorizationReq, IamOauth2RevokeReq,
+};
+use bios_iam::basic::serv::iam_cert_oauth2_service_serv::IamCertOAuth2ServiceServ;
+use bios_iam::iam_config::IamConfig;
+use bios_iam::iam_constants;
+use bios_iam::iam_enumeration::{OAuth2ResponseType, Oauth2GrantType};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::serde_json::{json, Map, Value};
+use tardis::{TardisFuns, TardisFunsInst};
+
+use crate::test_cp_cert_oidc::pkce_challenge;
+
+const REDIRECT_URI: &str = "https://app.example.com/callback";
+const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
+pub async fn test(context: &TardisContext) -> TardisResult<()> {
+    let mut funs = iam_constants::get_tardis_inst();
+    funs.begin().await?;
+
+    info!("【test_iam_oauth2_service】 : Add Clients");
+    let conf_id = IamCertOAuth2ServiceServ::add_cert_conf(
+        &IamCertConfOAuth2ServiceAddOrModifyReq {
+            name: TrimString("app".to_string()),
+            access_token_expire_sec: None,
+            redirect_uris: vec![REDIRECT_URI.to_string()],
+            rel_rbum_item_id: None,
+            public_client: None,
+            first_party: None,
+        },
+        &funs,
+        context,
+    )
+    .await?;
+    let client = IamCertOAuth2ServiceServ::get_cert_conf(&conf_id, &funs, context).await?;
+    let public_conf_id = IamCertOAuth2ServiceServ::add_cert_conf(
+        &IamCertConfOAuth2ServiceAddOrModifyReq {
+            name: TrimString("spa".to_string()),
+            access_token_expire_sec: None,
+            redirect_uris: vec![REDIRECT_URI.to_string()],
+            rel_rbum_item_id: None,
+            public_client: Some(true),
+            first_party: Some(true),
+        },
+        &funs,
+        context,
+    )
+    .await?;
+    let public_client = IamCertOAuth2ServiceServ::get_cert_conf(&public_conf_id, &funs, context).await?;
+
+    info!("【test_iam_oauth2_service】 : Authorization Code with PKCE");
+    let code_req = |client_id: &str, code_challenge: Option<String>| IamCertOAuth2ServiceCodeAddReq {
+        client_id: TrimString(client_id.to_string()),
+        state: Some("state1".to_string()),
+        scope: TrimString("openid profile".to_string()),
+        redirect_uri: TrimString(REDIRECT_URI.to_string()),
+        response_type: OAuth2ResponseType::Code,
+        nonce: Some("nonce1".to_string()),
+        code_challenge_method: code_challenge.as_ref().map(|_| "S256".to_string()),
+        code_challenge,
+    };
+    let verify_req = |client_id: &str, client_secret: &str, code: &str, code_verifier: &str| IamCertOAuth2ServiceCodeVerifyReq {
+        grant_type: Oauth2GrantType::AuthorizationCode,
+        code: code.to_string(),
+        client_id: client_id.to_string(),
+        client_secret: client_secret.to_string(),
+        redirect_uri: Some(REDIRECT_URI.to_string()),
+        code_verifier: Some(code_verifier.to_string()),
+    };
+    // a third-party client needs the consent of the account
+    assert!(IamCertOAuth2ServiceServ::need_consent(&client.client_id, "openid profile", &funs, context).await?);
+    let code_result = IamCertOAuth2ServiceServ::generate_code(&code_req(&client.client_id, Some(pkce_challenge(CODE_VERIFIER))), &funs, context).await;
+    assert_eq!(code_result.err().map(|e| e.code), Some("403-oauth2-consent-required".to_string()));
+    IamCertOAuth2ServiceServ::add_consent(&client.client_id, "openid profile", &funs, context).await?;
+    assert!(!IamCertOAuth2ServiceServ::need_consent(&client.client_id, "openid profile", &funs, context).await?);
+    let mut plain_req = code_req(&client.client_id, Some(CODE_VERIFIER.to_string()));
+    plain_req.code_challenge_method = Some("plain".to_string());
+    let code_result = IamCertOAuth2ServiceServ::generate_code(&plain_req, &funs, context).await;
+    assert_eq!(code_result.err().map(|e| e.code), Some("400-oauth2-invalid-request".to_string()));
+    let mut other_redirect_req = code_req(&client.client_id, Some(pkce_challenge(CODE_VERIFIER)));
+    other_redirect_req.redirect_uri = TrimString("https://evil.example.com/callback".to_string());
+    let code_result = IamCertOAuth2ServiceServ::generate_code(&other_redirect_req, &funs, context).await;
+    assert_eq!(code_result.err().map(|e| e.code), Some("400-oauth2-invalid-redirect-uri".to_string()));
+
+    let code = IamCertOAuth2ServiceServ::generate_code(&code_req(&client.client_id, Some(pkce_challenge(CODE_VERIFIER))), &funs, context).await?;
+    let token_result = IamCertOAuth2ServiceServ::verify_code_and_generate_token(&verify_req(&client.client_id, "wrong_secret", &code, CODE_VERIFIER), &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("401-oauth2-invalid-client".to_string()));
+    let token_result = IamCertOAuth2ServiceServ::verify_code_and_generate_token(&verify_req(&client.client_id, &client.client_secret, &code, "wrong_verifier"), &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("401-oauth2-invalid-code-verifier".to_string()));
+    let token = IamCertOAuth2ServiceServ::verify_code_and_generate_token(&verify_req(&client.client_id, &client.client_secret, &code, CODE_VERIFIER), &funs).await?;
+    assert_eq!(token.scope, Some("openid profile".to_string()));
+    let refresh_token = token.refresh_token.clone().unwrap();
+    // a code can only be exchanged once
+    let token_result = IamCertOAuth2ServiceServ::verify_code_and_generate_token(&verify_req(&client.client_id, &client.client_secret, &code, CODE_VERIFIER), &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("401-oauth2-code-used".to_string()));
+
+    info!("【test_iam_oauth2_service】 : Id Token and Userinfo");
+    let claims = decode_id_token(token.id_token.as_deref().unwrap(), &client.client_id, &funs).await?;
+    assert_eq!(claims.get("sub"), Some(&json!(context.owner)));
+    assert_eq!(claims.get("nonce"), Some(&json!("nonce1")));
+    assert!(claims.contains_key("name"));
+    assert!(decode_id_token(token.id_token.as_deref().unwrap(), &public_client.client_id, &funs).await.is_err());
+    let userinfo = IamCertOAuth2ServiceServ::get_userinfo_claims(&token.access_token, &funs).await?;
+    assert_eq!(userinfo.get("sub"), Some(&json!(context.owner)));
+    assert_eq!(userinfo.get("name"), claims.get("name"));
+    let introspect_resp = IamCertOAuth2ServiceServ::introspect(&token.access_token, &funs).await?;
+    assert!(introspect_resp.active);
+    assert_eq!(introspect_resp.sub, Some(context.owner.clone()));
+    assert_eq!(introspect_resp.client_id, Some(client.client_id.clone()));
+
+    info!("【test_iam_oauth2_service】 : Refresh and Revoke Token");
+    let refresh_req = IamCertOAuth2ServiceRefreshTokenReq {
+        grant_type: Oauth2GrantType::RefreshToken,
+        refresh_token: refresh_token.clone(),
+        client_id: client.client_id.clone(),
+        client_secret: Some(client.client_secret.clone()),
+        scope: None,
+    };
+    let refreshed_token = IamCertOAuth2ServiceServ::refresh_token(&refresh_req, &funs).await?;
+    assert_ne!(refreshed_token.access_token, token.access_token);
+    assert_eq!(refreshed_token.refresh_token, Some(refresh_token.clone()));
+    IamCertOAuth2ServiceServ::revoke(
+        &IamOauth2RevokeReq {
+            token: refresh_token,
+            token_type_hint: None,
+            client_id: client.client_id.clone(),
+            client_secret: client.client_secret.clone(),
+        },
+        &funs,
+    )
+    .await?;
+    let refresh_result = IamCertOAuth2ServiceServ::refresh_token(&refresh_req, &funs).await;
+    assert_eq!(refresh_result.err().map(|e| e.code), Some("401-oauth2-invalid-refresh-token".to_string()));
+    // withdrawing the consent revokes the tokens of the client
+    IamCertOAuth2ServiceServ::delete_consent(&client.client_id, &funs, context).await?;
+    assert!(!IamCertOAuth2ServiceServ::introspect(&token.access_token, &funs).await?.active);
+    assert!(!IamCertOAuth2ServiceServ::introspect(&refreshed_token.access_token, &funs).await?.active);
+
+    info!("【test_iam_oauth2_service】 : Public Client");
+    let code_result = IamCertOAuth2ServiceServ::generate_code(&code_req(&public_client.client_id, None), &funs, context).await;
+    assert_eq!(code_result.err().map(|e| e.code), Some("400-oauth2-invalid-request".to_string()));
+    // a first-party client doesn't need the consent
+    let code = IamCertOAuth2ServiceServ::generate_code(&code_req(&public_client.client_id, Some(pkce_challenge(CODE_VERIFIER))), &funs, context).await?;
+    let token = IamCertOAuth2ServiceServ::verify_code_and_generate_token(&verify_req(&public_client.client_id, "", &code, CODE_VERIFIER), &funs).await?;
+    assert!(token.id_token.is_some());
+    let code = IamCertOAuth2ServiceServ::generate_code(&code_req(&public_client.client_id, Some(pkce_challenge(CODE_VERIFIER))), &funs, context).await?;
+    let token_result = IamCertOAuth2ServiceServ::verify_code_and_generate_token(&verify_req(&public_client.client_id, "", &code, ""), &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("401-oauth2-invalid-code-verifier".to_string()));
+
+    info!("【test_iam_oauth2_service】 : Client Credentials");
+    let token_result = IamCertOAuth2ServiceServ::client_credentials(&client.client_id, "wrong_secret", None, &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("401-oauth2-invalid-client".to_string()));
+    let token_result = IamCertOAuth2ServiceServ::client_credentials(&public_client.client_id, "", None, &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("401-oauth2-unauthorized-client".to_string()));
+    let token = IamCertOAuth2ServiceServ::client_credentials(&client.client_id, &client.client_secret, Some("openid api"), &funs).await?;
+    assert_eq!(token.scope, Some("api".to_string()));
+    assert!(token.refresh_token.is_none());
+    assert!(token.id_token.is_none());
+    let introspect_resp = IamCertOAuth2ServiceServ::introspect(&token.access_token, &funs).await?;
+    assert!(introspect_resp.active);
+    assert_eq!(introspect_resp.sub, Some(client.client_id.clone()));
+    // the token of a client is not the token of an account
+    let userinfo_result = IamCertOAuth2ServiceServ::get_userinfo(&token.access_token, &funs).await;
+    assert_eq!(userinfo_result.err().map(|e| e.code), Some("401-oauth2-invalid-token".to_string()));
+
+    info!("【test_iam_oauth2_service】 : Device Code");
+    let device_req = IamOauth2DeviceAuthorizationReq {
+        client_id: client.client_id.clone(),
+        client_secret: client.client_secret.clone(),
+        scope: Some("openid profile".to_string()),
+    };
+    let device_resp = IamCertOAuth2ServiceServ::device_authorization(&device_req, &funs).await?;
+    assert!(device_resp.verification_uri_complete.ends_with(&device_resp.user_code.replace('-', "")));
+    let token_result = IamCertOAuth2ServiceServ::device_code_token(&device_resp.device_code, &client.client_id, &client.client_secret, &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("400-oauth2-authorization-pending".to_string()));
+    let token_result = IamCertOAuth2ServiceServ::device_code_token(&device_resp.device_code, &client.client_id, &client.client_secret, &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("400-oauth2-slow-down".to_string()));
+    let approve_result = IamCertOAuth2ServiceServ::approve_device("BCDF-GHJK", true, &funs, context).await;
+    assert_eq!(approve_result.err().map(|e| e.code), Some("404-oauth2-invalid-user-code".to_string()));
+    // the user code is typed in by the user, the case and the separator are ignored
+    IamCertOAuth2ServiceServ::approve_device(&device_resp.user_code.to_lowercase(), true, &funs, context).await?;
+    let token = IamCertOAuth2ServiceServ::device_code_token(&device_resp.device_code, &client.client_id, &client.client_secret, &funs).await?;
+    assert!(token.refresh_token.is_some());
+    let claims = decode_id_token(token.id_token.as_deref().unwrap(), &client.client_id, &funs).await?;
+    assert_eq!(claims.get("sub"), Some(&json!(context.owner)));
+    let token_result = IamCertOAuth2ServiceServ::device_code_token(&device_resp.device_code, &client.client_id, &client.client_secret, &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("400-oauth2-expired-token".to_string()));
+    let device_resp = IamCertOAuth2ServiceServ::device_authorization(&device_req, &funs).await?;
+    IamCertOAuth2ServiceServ::approve_device(&device_resp.user_code, false, &funs, context).await?;
+    let token_result = IamCertOAuth2ServiceServ::device_code_token(&device_resp.device_code, &client.client_id, &client.client_secret, &funs).await;
+    assert_eq!(token_result.err().map(|e| e.code), Some("400-oauth2-access-denied".to_string()));
+
+    funs.rollback().await?;
+    Ok(())
+}
+
+/// Verify the id token by the published keys, as a relying party does
+async fn decode_id_token(id_token: &str, client_id: &str, funs: &TardisFunsInst) -> TardisResult<Map<String, Value>> {
+    let jwks = TardisFuns::json.json_to_obj::<JwkSet>(IamCertOAuth2ServiceServ::get_jwks(funs).await?)?;
+    let discovery = IamCertOAuth2ServiceServ::get_discovery(funs);
+    assert_eq!(discovery["issuer"], json!(funs.conf::<IamConfig>().iam_base_url.trim_end_matches('/')));
+    let kid = jsonwebtoken::decode_header(id_token).unwrap().kid.unwrap();
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_issuer(&[funs.conf::<IamConfig>().iam_base_url.as_str()]);
+    validation.set_audience(&[client_id]);
+    let key = DecodingKey::from_jwk(jwks.find(&kid).unwrap()).unwrap();
+    jsonwebtoken::decode::<Map<String, Value>>(id_token, &key, &validation)
+        .map(|token| token.claims)
+        .map_err(|e| tardis::basic::error::TardisError::unauthorized(&e.to_string(), "401-oauth2-id-token-invalid"))
+}
@@ -1182,6 +1182,7 @@ pub async fn do_test_ldap(
                 sk: TrimString(rest_user1_pwd.to_string()),
                 tenant_id: tenant_id.clone(),
                 flag: None,
+                totp_code: None,
             },
         )
         .await;
@@ -19,13 +19,17 @@ mod test_cc_res;
 mod test_cc_role;
 mod test_cc_set;
 mod test_ci_open;
+mod test_ci_scim;
 mod test_cp_all;
+mod test_cp_cert_oidc;
+mod test_cp_cert_webauthn;
 mod test_cs_tenant;
 mod test_ct_app;
 mod test_ct_basic;
 mod test_ct_tenant;
 mod test_iam_cert_sync;
 mod test_iam_oauth2;
+mod test_iam_oauth2_service;
 mod test_key_cache;
 
 #[tokio::test]
@@ -122,7 +126,11 @@ async fn test_iam_serv() -> TardisResult<()> {
         &app2_admin_context,
     )
     .await?;
+    test_cp_cert_webauthn::test(&tenant1_admin_context).await?;
+    test_cp_cert_oidc::test(&tenant1_admin_context).await?;
+    test_iam_oauth2_service::test(&tenant1_admin_context).await?;
     test_ci_open::test(&tenant1_admin_context).await?;
+    test_ci_scim::test(&tenant1_admin_context, &tenant2_admin_context).await?;
     test_key_cache::test(&system_admin_context).await?;
     // test_iam_oauth2::test(&tenant1_admin_context).await?;
     let conf_ldap_add_or_modify_req = test_basic::gen_test_ldap_conf();
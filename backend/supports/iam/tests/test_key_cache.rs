@@ -96,6 +96,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString(tenant_admin_pwd.to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -160,6 +161,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -224,6 +226,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("45678".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -334,6 +337,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -365,6 +369,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -396,6 +401,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -488,6 +494,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -545,6 +552,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -667,6 +675,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -730,6 +739,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -801,6 +811,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -831,6 +842,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -1138,6 +1150,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,
@@ -1198,6 +1211,7 @@ pub async fn test(system_admin_context: &TardisContext) -> TardisResult<()> {
             sk: TrimString("123456".to_string()),
             tenant_id: Some(tenant_id.clone()),
             flag: None,
+            totp_code: None,
         },
         None,
         &funs,